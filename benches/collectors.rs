@@ -0,0 +1,105 @@
+//! How long each listener collector takes on this machine, and how fast its
+//! output parses.
+//!
+//! ```sh
+//! cargo bench --bench collectors
+//! ```
+//!
+//! The `collect` group runs the real commands, so it only measures the
+//! collectors installed here (on macOS, compare `lsof` with `netstat -anv`).
+//! The `parse` group feeds each parser a synthetic listing of many sockets.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use quay_core::port::listeners::{self, Collector, Target};
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::Duration;
+
+const SOCKETS: u16 = 2000;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime")
+}
+
+fn collect(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("collect");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+    for collector in [
+        Collector::Lsof,
+        Collector::NetstatMacos,
+        Collector::Ss,
+        Collector::Netstat,
+        Collector::ProcNet,
+    ] {
+        let chain = [collector];
+        let run = || rt.block_on(listeners::collect(Target::Host(None), &chain));
+        // Skip collectors that aren't installed or don't run here
+        if run().is_err() {
+            continue;
+        }
+        group.bench_function(BenchmarkId::from_parameter(collector), |b| {
+            b.iter(|| black_box(run()));
+        });
+    }
+    group.finish();
+}
+
+/// A listing of `SOCKETS` listeners in the format of `collector`.
+fn listing(collector: Collector) -> String {
+    let mut out = String::new();
+    for port in 1..=SOCKETS {
+        let pid = 1000 + u32::from(port);
+        let _ = match collector {
+            Collector::Ss => writeln!(
+                out,
+                "LISTEN 0 511 127.0.0.1:{port} 0.0.0.0:* users:((\"node\",pid={pid},fd=20))"
+            ),
+            Collector::Netstat => {
+                writeln!(out, "tcp 0 0 127.0.0.1:{port} 0.0.0.0:* LISTEN {pid}/node")
+            }
+            Collector::NetstatMacos => writeln!(
+                out,
+                "tcp4 0 0 127.0.0.1.{port} *.* LISTEN 131072 131072 {pid} 0 0x0100"
+            ),
+            _ => writeln!(
+                out,
+                "{port}: 0100007F:{port:04X} 00000000:0000 0A 00000000:00000000 00:00000000"
+            ),
+        };
+    }
+    match collector {
+        Collector::NetstatMacos => format!(
+            "Proto Recv-Q Send-Q Local Address Foreign Address (state) rhiwat shiwat pid epid\n{out}"
+        ),
+        _ => out,
+    }
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for collector in [
+        Collector::NetstatMacos,
+        Collector::Ss,
+        Collector::Netstat,
+        Collector::ProcNet,
+    ] {
+        let output = listing(collector);
+        assert_eq!(
+            listeners::parse(collector, &output).len(),
+            usize::from(SOCKETS)
+        );
+        group.bench_function(BenchmarkId::from_parameter(collector), |b| {
+            b.iter(|| listeners::parse(collector, black_box(&output)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collect, parse);
+criterion_main!(benches);
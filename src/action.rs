@@ -0,0 +1,251 @@
+//! Actions from the context menu that run outside the TUI: custom actions
+//! (`[[actions]]` in config.toml), starting expected services, the
+//! clipboard, and the browser.
+//!
+//! `{name}` placeholders in a custom command are filled from the entry,
+//! shell-quoted. Braces that don't name a variable are left alone, so
+//! `awk '{print $1}'` and `${HOME}` keep working.
+
+use crate::config::CustomAction;
+use crate::port::PortEntry;
+use std::borrow::Cow;
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use tokio::process::Child;
+
+/// Placeholders a command may use.
+const VARIABLES: [&str; 11] = [
+    "port",
+    "remote_host",
+    "remote_port",
+    "pid",
+    "process",
+    "container_id",
+    "container_name",
+    "ssh_host",
+    "user",
+    "connection",
+    "host",
+];
+
+/// Value of variable `name` for `entry`; `host` is the active connection's remote host.
+fn variable(name: &str, entry: &PortEntry, host: Option<&str>) -> Option<String> {
+    match name {
+        "port" => Some(entry.local_port.to_string()),
+        "remote_host" => entry.remote_host.clone(),
+        "remote_port" => entry.remote_port.map(|p| p.to_string()),
+        "pid" => entry.pid.map(|p| p.to_string()),
+        "process" => Some(entry.process_name.clone()),
+        "container_id" => entry.container_id.clone(),
+        "container_name" => entry.container_name.clone(),
+        "ssh_host" => entry.ssh_host.clone(),
+        "user" => entry.user.clone(),
+        "connection" => entry.connection.clone(),
+        "host" => host.map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Fill the placeholders of `command` from `entry`.
+///
+/// Fails naming the first variable the entry has no value for.
+pub fn render(command: &str, entry: &PortEntry, host: Option<&str>) -> Result<String, String> {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = after
+            .find('}')
+            .map(|end| &after[..end])
+            .filter(|name| VARIABLES.contains(name));
+        match name.map(|n| (n, variable(n, entry, host))) {
+            Some((name, Some(value))) => {
+                out.push_str(&shell_escape::escape(Cow::Owned(value)));
+                rest = &after[name.len() + 1..];
+            }
+            Some((name, None)) => return Err(format!("no {{{name}}} for this entry")),
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Whether `action` is offered for `entry`: its source matches and every
+/// placeholder has a value.
+pub fn applies(action: &CustomAction, entry: &PortEntry, host: Option<&str>) -> bool {
+    (action.sources.is_empty() || action.sources.contains(&entry.source))
+        && render(&action.command, entry, host).is_ok()
+}
+
+/// `words` as one shell command line, each word quoted as needed.
+pub fn quote_command(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|w| shell_escape::escape(Cow::Borrowed(*w)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell(command: &str) -> tokio::process::Command {
+    let (program, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// Run `command` on the terminal and wait for Enter before returning, so its
+/// output can be read.
+pub async fn run_interactive(command: &str) -> std::io::Result<ExitStatus> {
+    println!("$ {command}");
+    let mut child = shell(command).spawn()?;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status?,
+            // Ctrl-C is meant for the command (e.g. `docker logs -f`); keep quay running
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    };
+    println!("\nquay: {status}; press Enter to return");
+    tokio::task::spawn_blocking(|| std::io::stdin().read_line(&mut String::new()))
+        .await
+        .map_err(std::io::Error::other)??;
+    Ok(status)
+}
+
+/// Start `command` detached from the terminal.
+pub fn spawn_background(command: &str) -> std::io::Result<()> {
+    shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Start the command of an expected service in `dir`, detached and in its
+/// own process group so it outlives quay, with `PORT` set to its port.
+pub fn start_service(command: &str, dir: &Path, port: u16) -> std::io::Result<Child> {
+    let mut cmd = shell(command);
+    cmd.current_dir(dir)
+        .env("PORT", port.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    cmd.process_group(0);
+    cmd.spawn()
+}
+
+/// Copy `text` with an OSC 52 escape sequence, which the terminal (also over
+/// ssh, and in tmux with `set-clipboard on`) puts on the system clipboard.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::clipboard::CopyToClipboard::to_clipboard_from(text)
+    )
+}
+
+/// Open `url` with the desktop's default handler.
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        tokio::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        tokio::process::Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::PortSource;
+
+    fn container() -> PortEntry {
+        PortEntry {
+            source: PortSource::Docker,
+            local_port: 5432,
+            remote_port: Some(5432),
+            process_name: "postgres".to_string(),
+            container_id: Some("abc123".to_string()),
+            container_name: Some("my db".to_string()),
+            is_open: true,
+            ..PortEntry::default()
+        }
+    }
+
+    fn action(command: &str, sources: Vec<PortSource>) -> CustomAction {
+        CustomAction {
+            name: "test".to_string(),
+            command: command.to_string(),
+            sources,
+            key: None,
+            background: false,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let entry = container();
+        assert_eq!(
+            render("docker logs -f {container_id}", &entry, None).unwrap(),
+            "docker logs -f abc123"
+        );
+        assert_eq!(
+            render("echo {container_name}:{port}", &entry, Some("lab")).unwrap(),
+            "echo 'my db':5432"
+        );
+        assert_eq!(
+            render(
+                "ssh {host} \"awk '{print $1}' ${HOME}\"",
+                &entry,
+                Some("lab")
+            )
+            .unwrap(),
+            "ssh lab \"awk '{print $1}' ${HOME}\""
+        );
+        assert_eq!(
+            render("kill {pid}", &entry, None).unwrap_err(),
+            "no {pid} for this entry"
+        );
+        assert_eq!(render("echo {", &entry, None).unwrap(), "echo {");
+    }
+
+    #[test]
+    fn test_applies() {
+        let entry = container();
+        assert!(applies(
+            &action("docker logs {container_id}", vec![]),
+            &entry,
+            None
+        ));
+        assert!(applies(
+            &action("echo {port}", vec![PortSource::Docker]),
+            &entry,
+            None
+        ));
+        assert!(!applies(
+            &action("echo {port}", vec![PortSource::Local]),
+            &entry,
+            None
+        ));
+        assert!(!applies(&action("kill {pid}", vec![]), &entry, None));
+    }
+}
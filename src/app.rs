@@ -1,15 +1,49 @@
-use crate::connection::Connection;
-use crate::port::{PortEntry, PortSource};
+use crate::action;
+use crate::compose::ComposeFile;
+use crate::config::{ConfigDiagnostic, CustomAction, TmuxConfig, TunnelsConfig, UiLayout};
+use crate::connection::{Connection, ConnectionDefaults, Health};
+use crate::env_ports::EnvPorts;
+use crate::event::Action;
+use crate::expected::{Expected, ExpectedService};
+use crate::history;
+use crate::hostname::HostNames;
+use crate::insights::{self, Insight};
+use crate::output::Format;
+use crate::pin::Pins;
+use crate::port::docker::VmBackend;
+use crate::port::established::Established;
+use crate::port::inspect::RawSection;
+use crate::port::usage::Usage;
+use crate::port::{self, Collection, CommandError, PortEntry, PortSource, SourceStatus};
 use crate::preset::Preset;
-use std::collections::HashMap;
+use crate::query::Query;
+use crate::registry::{self, PortRegistry};
+use crate::service::Services;
+use crate::snapshot::{self, Snapshot, Snapshots};
+use crate::tmux;
+use crate::undo;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 const STATUS_MESSAGE_TICKS: u32 = 12;
+/// Status messages kept for the Messages popup.
+const STATUS_HISTORY_LEN: usize = 100;
 const DEFAULT_REFRESH_TICKS: u32 = 20;
+/// Refresh interval of the activity view (2s), unless the configured one is shorter.
+const ACTIVITY_REFRESH_TICKS: u32 = 8;
+/// A collection phase this slow is shown as a warning in the header, with a
+/// one-time hint on speeding it up.
+pub const SLOW_PHASE: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
     Normal,
     Search,
+    /// Waiting for a preset hotkey after the leader key.
+    PresetLeader,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +54,22 @@ pub enum Popup {
     Forward,
     Presets,
     Connections,
+    LaunchResults,
+    Auth,
+    Error,
+    SudoKill,
+    Messages,
+    ContextMenu,
+    Snapshots,
+    Export,
+    Scan,
+    Established,
+    Rerun,
+    /// Confirm running the start command of a closed expected port.
+    StartService,
+    Insights,
+    History,
+    Output,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -27,6 +77,7 @@ pub enum ConnectionPopupMode {
     #[default]
     List,
     AddNew,
+    Edit,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -68,6 +119,15 @@ impl ConnectionInput {
         Self::default()
     }
 
+    pub fn from_connection(conn: &Connection) -> Self {
+        Self {
+            name: conn.name.clone(),
+            remote_host: conn.remote_host.clone().unwrap_or_default(),
+            docker_target: conn.docker_target.clone().unwrap_or_default(),
+            active_field: ConnectionField::Name,
+        }
+    }
+
     pub fn active_value(&mut self) -> &mut String {
         match self.active_field {
             ConnectionField::Name => &mut self.name,
@@ -100,6 +160,8 @@ impl ConnectionInput {
             } else {
                 Some(self.docker_target.trim().to_string())
             },
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
         })
     }
 }
@@ -111,6 +173,10 @@ pub enum ForwardField {
     RemoteHost,
     RemotePort,
     SshHost,
+    /// Checkbox: listen on every interface instead of loopback.
+    BindAll,
+    /// Advanced: extra ssh arguments.
+    SshOptions,
 }
 
 impl ForwardField {
@@ -119,16 +185,20 @@ impl ForwardField {
             ForwardField::LocalPort => ForwardField::RemoteHost,
             ForwardField::RemoteHost => ForwardField::RemotePort,
             ForwardField::RemotePort => ForwardField::SshHost,
-            ForwardField::SshHost => ForwardField::LocalPort,
+            ForwardField::SshHost => ForwardField::BindAll,
+            ForwardField::BindAll => ForwardField::SshOptions,
+            ForwardField::SshOptions => ForwardField::LocalPort,
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
-            ForwardField::LocalPort => ForwardField::SshHost,
+            ForwardField::LocalPort => ForwardField::SshOptions,
             ForwardField::RemoteHost => ForwardField::LocalPort,
             ForwardField::RemotePort => ForwardField::RemoteHost,
             ForwardField::SshHost => ForwardField::RemotePort,
+            ForwardField::BindAll => ForwardField::SshHost,
+            ForwardField::SshOptions => ForwardField::BindAll,
         }
     }
 }
@@ -139,6 +209,10 @@ pub struct ForwardInput {
     pub remote_host: String,
     pub remote_port: String,
     pub ssh_host: String,
+    /// Listen on every interface, so other machines can use the forward.
+    pub bind_all: bool,
+    /// Extra ssh arguments, separated by spaces (`-C -o ServerAliveInterval=30`).
+    pub ssh_options: String,
     pub active_field: ForwardField,
 }
 
@@ -147,12 +221,15 @@ impl ForwardInput {
         Self::default()
     }
 
-    pub fn active_value(&mut self) -> &mut String {
+    /// Text of the active field; `None` on the checkbox.
+    pub fn active_value(&mut self) -> Option<&mut String> {
         match self.active_field {
-            ForwardField::LocalPort => &mut self.local_port,
-            ForwardField::RemoteHost => &mut self.remote_host,
-            ForwardField::RemotePort => &mut self.remote_port,
-            ForwardField::SshHost => &mut self.ssh_host,
+            ForwardField::LocalPort => Some(&mut self.local_port),
+            ForwardField::RemoteHost => Some(&mut self.remote_host),
+            ForwardField::RemotePort => Some(&mut self.remote_port),
+            ForwardField::SshHost => Some(&mut self.ssh_host),
+            ForwardField::SshOptions => Some(&mut self.ssh_options),
+            ForwardField::BindAll => None,
         }
     }
 
@@ -203,6 +280,8 @@ impl ForwardInput {
             remote_host: "localhost".to_string(),
             remote_port: entry.local_port.to_string(),
             ssh_host: entry.ssh_host.clone().unwrap_or_default(),
+            bind_all: false,
+            ssh_options: String::new(),
             active_field: if has_ssh_host {
                 ForwardField::LocalPort
             } else {
@@ -217,6 +296,8 @@ impl ForwardInput {
             remote_host: "localhost".to_string(),
             remote_port: entry.local_port.to_string(),
             ssh_host: remote_host.to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
             active_field: ForwardField::LocalPort,
         }
     }
@@ -227,9 +308,401 @@ impl ForwardInput {
         }
         let local_port: u16 = self.local_port.parse().ok()?;
         let remote_port: u16 = self.remote_port.parse().ok()?;
-        let spec = format!("{}:{}:{}", local_port, self.remote_host, remote_port);
+        let mut spec = format!("{}:{}:{}", local_port, self.remote_host, remote_port);
+        if self.bind_all {
+            spec = port::ssh::bind_all(&spec);
+        }
         Some((spec, self.ssh_host.clone()))
     }
+
+    /// The extra ssh arguments typed in the Advanced field.
+    pub fn options(&self) -> Vec<String> {
+        port::ssh::split_options(&self.ssh_options)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresetPopupMode {
+    #[default]
+    List,
+    Form,
+    /// Asking whether to delete the selected preset.
+    ConfirmDelete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresetField {
+    #[default]
+    Name,
+    Key,
+    Group,
+    LocalPort,
+    RemoteHost,
+    RemotePort,
+    SshHost,
+}
+
+impl PresetField {
+    pub fn next(self) -> Self {
+        match self {
+            PresetField::Name => PresetField::Key,
+            PresetField::Key => PresetField::Group,
+            PresetField::Group => PresetField::LocalPort,
+            PresetField::LocalPort => PresetField::RemoteHost,
+            PresetField::RemoteHost => PresetField::RemotePort,
+            PresetField::RemotePort => PresetField::SshHost,
+            PresetField::SshHost => PresetField::Name,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            PresetField::Name => PresetField::SshHost,
+            PresetField::Key => PresetField::Name,
+            PresetField::Group => PresetField::Key,
+            PresetField::LocalPort => PresetField::Group,
+            PresetField::RemoteHost => PresetField::LocalPort,
+            PresetField::RemotePort => PresetField::RemoteHost,
+            PresetField::SshHost => PresetField::RemotePort,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PresetInput {
+    pub name: String,
+    pub key: String,
+    pub group: String,
+    pub local_port: String,
+    pub remote_host: String,
+    pub remote_port: String,
+    pub ssh_host: String,
+    /// Options from presets.toml, kept when the preset is edited here.
+    pub ssh_options: Vec<String>,
+    pub active_field: PresetField,
+}
+
+impl PresetInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_preset(preset: &Preset) -> Self {
+        Self {
+            name: preset.name.clone(),
+            key: preset.key.clone().unwrap_or_default(),
+            group: preset.group.clone().unwrap_or_default(),
+            local_port: preset.local_port.to_string(),
+            remote_host: preset.remote_host.clone(),
+            remote_port: preset.remote_port.to_string(),
+            ssh_host: preset.ssh_host.clone(),
+            ssh_options: preset.ssh_options.clone(),
+            active_field: PresetField::Name,
+        }
+    }
+
+    /// Prefill a new preset from a table entry. `remote_host` is the SSH host
+    /// of the active connection, used when the entry has none of its own.
+    pub fn from_entry(entry: &PortEntry, remote_host: Option<&str>) -> Self {
+        let ssh_host = entry
+            .ssh_host
+            .as_deref()
+            .filter(|h| !h.is_empty())
+            .or(remote_host)
+            .unwrap_or_default();
+        let remote_port = entry.remote_port.unwrap_or(entry.local_port);
+        Self {
+            name: String::new(),
+            key: String::new(),
+            group: String::new(),
+            local_port: entry.local_port.to_string(),
+            remote_host: entry
+                .remote_host
+                .clone()
+                .unwrap_or_else(|| "localhost".to_string()),
+            remote_port: remote_port.to_string(),
+            ssh_host: ssh_host.to_string(),
+            ssh_options: Vec::new(),
+            active_field: PresetField::Name,
+        }
+    }
+
+    pub fn active_value(&mut self) -> &mut String {
+        match self.active_field {
+            PresetField::Name => &mut self.name,
+            PresetField::Key => &mut self.key,
+            PresetField::Group => &mut self.group,
+            PresetField::LocalPort => &mut self.local_port,
+            PresetField::RemoteHost => &mut self.remote_host,
+            PresetField::RemotePort => &mut self.remote_port,
+            PresetField::SshHost => &mut self.ssh_host,
+        }
+    }
+
+    pub fn is_field_valid(&self, field: PresetField) -> bool {
+        match field {
+            PresetField::Name => !self.name.trim().is_empty(),
+            PresetField::Key => self.key.trim().chars().count() <= 1,
+            PresetField::Group => true,
+            PresetField::LocalPort => self.local_port.parse::<u16>().is_ok_and(|p| p > 0),
+            PresetField::RemoteHost => !self.remote_host.trim().is_empty(),
+            PresetField::RemotePort => self.remote_port.parse::<u16>().is_ok_and(|p| p > 0),
+            PresetField::SshHost => !self.ssh_host.trim().is_empty(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.invalid_field_names().is_empty()
+    }
+
+    pub fn invalid_field_names(&self) -> Vec<&'static str> {
+        [
+            (PresetField::Name, "Name"),
+            (PresetField::Key, "Key"),
+            (PresetField::LocalPort, "Local Port"),
+            (PresetField::RemoteHost, "Remote Host"),
+            (PresetField::RemotePort, "Remote Port"),
+            (PresetField::SshHost, "SSH Host"),
+        ]
+        .into_iter()
+        .filter(|&(field, _)| !self.is_field_valid(field))
+        .map(|(_, name)| name)
+        .collect()
+    }
+
+    pub fn to_preset(&self) -> Option<Preset> {
+        if !self.is_valid() {
+            return None;
+        }
+        let key = self.key.trim();
+        let group = self.group.trim();
+        Some(Preset {
+            name: self.name.trim().to_string(),
+            key: if key.is_empty() {
+                None
+            } else {
+                Some(key.to_string())
+            },
+            local_port: self.local_port.parse().ok()?,
+            remote_host: self.remote_host.trim().to_string(),
+            remote_port: self.remote_port.parse().ok()?,
+            ssh_host: self.ssh_host.trim().to_string(),
+            group: if group.is_empty() {
+                None
+            } else {
+                Some(group.to_string())
+            },
+            ssh_options: self.ssh_options.clone(),
+        })
+    }
+}
+
+/// Outcome of starting one forward of a preset group or snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchResult {
+    pub name: String,
+    /// Success detail (e.g. PID) or failure reason.
+    pub outcome: Result<String, String>,
+}
+
+/// What several entries have in common to be shown under one group row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GroupKey {
+    Process {
+        connection: Option<String>,
+        pid: u32,
+    },
+    Container {
+        connection: Option<String>,
+        id: String,
+    },
+}
+
+impl GroupKey {
+    /// The process or container `entry` belongs to, when known.
+    pub fn of(entry: &PortEntry) -> Option<Self> {
+        let connection = entry.connection.clone();
+        match (&entry.container_id, entry.pid) {
+            (Some(id), _) => Some(GroupKey::Container {
+                connection,
+                id: id.clone(),
+            }),
+            (None, Some(pid)) => Some(GroupKey::Process { connection, pid }),
+            (None, None) => None,
+        }
+    }
+}
+
+/// What identifies an entry from one refresh to the next, so the cursor can
+/// follow it when rows above it come and go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EntryKey {
+    connection: Option<String>,
+    source: PortSource,
+    port: u16,
+    pid: Option<u32>,
+}
+
+impl EntryKey {
+    fn of(entry: &PortEntry) -> Self {
+        Self {
+            connection: entry.connection.clone(),
+            source: entry.source.clone(),
+            port: entry.local_port,
+            pid: entry.pid,
+        }
+    }
+}
+
+/// The row the cursor is on, by identity rather than position.
+enum SelectionAnchor {
+    Group(GroupKey),
+    Entry(EntryKey),
+}
+
+/// One line of the ports table; indices point into `App::filtered_entries`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableRow {
+    /// An entry that shares its process with no other.
+    Entry(usize),
+    /// Collapsible row for the `count` entries of one process or container.
+    Group {
+        key: GroupKey,
+        first: usize,
+        count: usize,
+        expanded: bool,
+    },
+    /// An entry shown under its expanded group.
+    Member(usize),
+}
+
+/// A kill refused for lack of permission, offered for a retry through sudo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SudoKill {
+    pub port: u16,
+    pub pid: u32,
+    pub process: String,
+    /// Host the process runs on; `None` for this machine.
+    pub host: Option<String>,
+    /// Owner known before any attempt; set when offering sudo up front.
+    pub owner: Option<String>,
+}
+
+/// A service quay started, until its port opens or its command gives up.
+#[derive(Debug, Clone)]
+pub struct StartedService {
+    pub name: String,
+    pub pid: u32,
+    /// Cleared by the task watching the command when it stops waiting.
+    pub pending: Arc<AtomicBool>,
+}
+
+/// Counts for the statistics line under the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Entries of each source that has any, in Local, Docker, Ssh, Scan order.
+    pub per_source: Vec<(PortSource, usize)>,
+    pub open: usize,
+    pub closed: usize,
+    /// SSH forwards started since quay started.
+    pub forwards_started: usize,
+}
+
+/// A line of the context menu: what it is called and the action it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+    pub label: String,
+    pub action: Action,
+    /// Command of a custom action, filled in for the entry.
+    pub command: Option<String>,
+}
+
+/// A custom action with its command filled in for the selected entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingAction {
+    pub name: String,
+    pub command: String,
+    pub background: bool,
+}
+
+/// A status bar message, kept for the Messages popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    pub at: chrono::DateTime<chrono::Local>,
+    pub message: String,
+    pub is_error: bool,
+}
+
+/// Raw command output for the entry in the Details popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawDetails {
+    /// Port of the entry it was collected for, to drop late results.
+    pub port: u16,
+    pub sections: Vec<RawSection>,
+}
+
+/// Recent output of the process behind a port, for the Output popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputView {
+    /// Port it was read for, to drop late results.
+    pub port: u16,
+    /// Where it was read from (`docker logs db`, a file, ...), once found.
+    pub source: Option<String>,
+    /// The lines, oldest first, or why there are none.
+    pub lines: Result<Vec<String>, String>,
+}
+
+/// `docker stats` of a connection's containers, kept across refreshes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerStats {
+    /// Host they were read on, to drop results for another connection.
+    pub remote_host: Option<String>,
+    /// Usage by container ID and by name.
+    pub usage: HashMap<String, Usage>,
+}
+
+/// Established connections to the port in the Established popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EstablishedList {
+    /// Port they were collected for, to drop late results.
+    pub port: u16,
+    /// The connections, or why they couldn't be listed.
+    pub connections: Result<Vec<Established>, String>,
+}
+
+/// A collection failure kept whole for the Error popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    /// What failed, e.g. "Refresh failed" or the connection name.
+    pub title: String,
+    /// The full error chain on one line.
+    pub summary: String,
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl ErrorReport {
+    pub fn new(title: &str, err: &anyhow::Error) -> Self {
+        let command = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<CommandError>());
+        Self {
+            title: title.to_string(),
+            summary: format!("{err:#}"),
+            command: command.map(|c| c.command.clone()),
+            exit_code: command.and_then(|c| c.exit_code),
+            stderr: command.map(|c| c.stderr.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Rows the popup draws for this report: title, summary, command,
+    /// exit code, stderr label, the stderr lines (at least one), and a gap.
+    pub fn line_count(&self) -> usize {
+        let stderr_lines = self.stderr.lines().count().max(1);
+        let command_lines = if self.command.is_some() { 2 } else { 0 };
+        2 + command_lines + 1 + stderr_lines + 1
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -240,10 +713,88 @@ pub enum Filter {
     Docker,
 }
 
+impl Filter {
+    /// Parse a config value (`all`, `local`, `ssh`, `docker`); unknown names mean `All`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "local" => Filter::Local,
+            "ssh" => Filter::Ssh,
+            "docker" => Filter::Docker,
+            _ => Filter::All,
+        }
+    }
+}
+
+/// Order of the table, cycled with `o`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Open ports first, then by port number, as collected.
+    #[default]
+    Port,
+    /// Busiest process first.
+    Cpu,
+    /// Largest process first.
+    Memory,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Port => SortKey::Cpu,
+            SortKey::Cpu => SortKey::Memory,
+            SortKey::Memory => SortKey::Port,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Port => "port",
+            SortKey::Cpu => "CPU",
+            SortKey::Memory => "memory",
+        }
+    }
+}
+
+/// Convert a refresh interval in seconds to 250ms ticks.
+pub fn refresh_ticks_for(interval_secs: u32) -> u32 {
+    interval_secs.saturating_mul(4).max(1)
+}
+
+/// Global view settings from config.toml, used when a connection does not override them.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewDefaults {
+    pub filter: Filter,
+    pub auto_refresh: bool,
+    pub refresh_ticks: u32,
+    /// Refresh interval of connections that scan over SSH.
+    pub remote_refresh_ticks: u32,
+}
+
+impl Default for ViewDefaults {
+    fn default() -> Self {
+        Self {
+            filter: Filter::All,
+            auto_refresh: false,
+            refresh_ticks: DEFAULT_REFRESH_TICKS,
+            remote_refresh_ticks: DEFAULT_REFRESH_TICKS,
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
 pub struct App {
     pub entries: Vec<PortEntry>,
     pub filtered_entries: Vec<PortEntry>,
+    /// Table lines built from `filtered_entries`; `selected` indexes these.
+    pub rows: Vec<TableRow>,
+    /// Groups the user expanded; all others are collapsed.
+    pub expanded_groups: HashSet<GroupKey>,
     pub selected: usize,
+    /// First visible table row as last drawn; a refresh moves it along with
+    /// the selection so the rows on screen don't jump.
+    pub table_offset: usize,
+    /// Furthest the open popup scrolls, as last drawn.
+    pub popup_max_scroll: usize,
     pub filter: Filter,
     pub search_query: String,
     pub input_mode: InputMode,
@@ -254,12 +805,42 @@ pub struct App {
     pub tick_count: u32,
     pub refresh_ticks: u32,
     pub status_message: Option<(String, u32)>, // (message, ticks_remaining)
+    /// The status message is an error, kept until dismissed with Esc.
+    pub status_error: bool,
+    /// Recent status messages, newest last.
+    pub status_history: VecDeque<StatusEntry>,
+    pub messages_scroll: usize,
+    pub insight_selected: usize,
+    /// Records of history.jsonl shown in the History popup, oldest first.
+    pub history: Vec<history::Record>,
+    pub history_scroll: usize,
+    pub help_scroll: usize,
     pub presets: Vec<Preset>,
     pub preset_selected: usize,
+    pub preset_input: PresetInput,
+    pub preset_popup_mode: PresetPopupMode,
+    /// Index of the preset being edited; `None` when the form creates a new one.
+    pub preset_editing: Option<usize>,
+    /// Saved snapshots (snapshots.toml).
+    pub snapshots: Snapshots,
+    pub snapshot_selected: usize,
+    /// Name being typed for a new snapshot; `Some` while the prompt is shown.
+    pub snapshot_name: Option<String>,
+    /// Port range typed in the Scan popup; kept for the next scan.
+    pub scan_range: String,
+    /// Open ports found by the last scan of this connection, merged into the table.
+    pub scan_entries: Vec<PortEntry>,
+    /// A scan is running.
+    pub scanning: bool,
+    /// Title of the launch results popup, e.g. `Group: staging`.
+    pub launch_title: String,
+    pub launch_results: Vec<LaunchResult>,
     pub remote_host: Option<String>,
     pub docker_target: Option<String>,
     pub container_ip: Option<String>,
     pub docker_port_mappings: HashMap<u16, u16>, // container_port -> host_port
+    /// VM the Docker daemon runs in (Docker Desktop, Colima, ...), if any.
+    pub docker_vm: Option<VmBackend>,
     pub connections: Vec<Connection>,
     pub active_connection: usize,
     pub connection_selected: usize,
@@ -271,14 +852,124 @@ pub struct App {
     // making them invisible to ps aux-based detection.
     pub ssh_forwards: HashMap<usize, HashMap<u16, u16>>,
     pub loading: bool,
+    /// How long the last collection took; `None` before the first.
+    pub last_refresh: Option<Duration>,
+    /// How long each phase of the last collection took.
+    pub refresh_timings: Vec<(String, Duration)>,
+    /// Phases whose slowness was already explained in the status bar.
+    pub slow_phases_hinted: HashSet<String>,
+    /// Showing mock data: nothing is collected, killed, or saved.
+    pub mock_mode: bool,
+    /// Mock entries change on every refresh (`quay dev mock --churn`).
+    pub mock_churn: bool,
+    pub view_defaults: ViewDefaults,
+    pub registry: PortRegistry,
+    /// Compose file whose published ports are expected (`--compose`).
+    pub compose: Option<ComposeFile>,
+    /// Services of the project's Procfile and `.quay.toml`.
+    pub expected: Expected,
+    /// Port variables of the project's `.env` files and the environment.
+    pub env_ports: EnvPorts,
+    /// Service names for the SERVICE column and search.
+    pub services: Services,
+    /// Names for the hosts in the REMOTE column.
+    pub host_names: HostNames,
+    /// How each source fared in the last collection, for the header badges.
+    pub sources: Vec<(PortSource, SourceStatus)>,
+    /// Config files that failed to load; shown as a warning banner.
+    pub config_diagnostics: Vec<ConfigDiagnostic>,
+    /// Last probe result per SSH host (connections sharing a host share the result).
+    pub connection_health: HashMap<String, Health>,
+    /// Host offered interactive authentication in the Auth popup.
+    pub auth_host: Option<String>,
+    /// Hosts whose authentication prompt was dismissed; not offered again.
+    pub auth_declined: HashSet<String>,
+    /// Index of the synthetic "All connections" entry, when there is one.
+    pub aggregate_connection: Option<usize>,
+    /// Failures from the last collection, shown in the Error popup.
+    pub errors: Vec<ErrorReport>,
+    pub error_scroll: usize,
+    /// Raw output for the Details popup; `None` while it is being collected.
+    pub details_raw: Option<RawDetails>,
+    pub details_scroll: usize,
+    /// Connections for the Established popup; `None` while they are being collected.
+    pub established: Option<EstablishedList>,
+    pub established_scroll: usize,
+    /// Output for the Output popup; `None` while it is being read.
+    pub output: Option<OutputView>,
+    pub output_scroll: usize,
+    /// Last `docker stats` of the active connection, for Docker entries' usage.
+    pub container_stats: Option<ContainerStats>,
+    /// A `docker stats` is running; the next one waits for it.
+    pub container_stats_loading: bool,
+    /// When each connection (local port, peer) was first listed, and whether
+    /// it was already there the first time its port was looked at.
+    pub established_seen: HashMap<(u16, String), (Instant, bool)>,
+    /// Whether the log pane is shown below the table.
+    pub show_logs: bool,
+    /// Borderless chrome so more rows fit; also used on short terminals.
+    pub compact: bool,
+    /// `ui.layout`: full-width table, or table plus a details pane.
+    pub layout: UiLayout,
+    /// `ui.latency_column`: show probe latencies in the table.
+    pub latency_column: bool,
+    /// `ui.usage_columns`: show CPU and memory of processes in the table.
+    pub usage_columns: bool,
+    /// Order of the table.
+    pub sort: SortKey,
+    /// Activity view (`t`): busiest ports first, refreshed every few seconds.
+    pub activity: bool,
+    /// `general.allow_sudo_kill`: offer sudo when a kill is refused.
+    pub allow_sudo_kill: bool,
+    /// Kill offered in the `SudoKill` popup.
+    pub sudo_kill: Option<SudoKill>,
+    /// Kills and forwards `u` can take back.
+    pub undo: undo::History,
+    /// Killed process offered in the `Rerun` popup.
+    pub rerun: Option<undo::Rerun>,
+    /// Service offered in the `StartService` popup.
+    pub start_prompt: Option<ExpectedService>,
+    /// Services started from quay, by port.
+    pub started: HashMap<u16, StartedService>,
+    /// Login name quay runs as locally, to flag ports owned by other users.
+    pub local_user: Option<String>,
+    /// Ports kept at the top of the table, per connection (pins.toml).
+    pub pins: Pins,
+    /// `general.ignore_ports` plus ports hidden with `x` this session.
+    pub ignore_ports: HashSet<u16>,
+    /// `general.ignore_processes`, matched case-insensitively.
+    pub ignore_processes: Vec<String>,
+    /// Show ignored entries (grayed out) instead of dropping them.
+    pub show_hidden: bool,
+    /// Entries dropped by the ignore list in the last `apply_filter`.
+    pub hidden_count: usize,
+    /// `[[actions]]` from config.toml.
+    pub custom_actions: Vec<CustomAction>,
+    /// Items of the context menu for the selected entry.
+    pub context_menu: Vec<MenuItem>,
+    pub context_selected: usize,
+    /// `[tmux]` from config.toml.
+    pub tmux: TmuxConfig,
+    /// Whether quay runs inside tmux, so shells and forwards can open in panes.
+    pub in_tmux: bool,
+    /// `[tunnels]` from config.toml.
+    pub tunnels: TunnelsConfig,
+    /// When each SSH forward (ssh PID and local port) last had a connection,
+    /// or was first seen without one.
+    pub tunnel_activity: HashMap<(Option<u32>, u16), Instant>,
 }
 
 impl App {
+    #[allow(clippy::too_many_lines)]
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
             filtered_entries: Vec::new(),
+            rows: Vec::new(),
+            expanded_groups: HashSet::new(),
             selected: 0,
+            table_offset: 0,
+            popup_max_scroll: 0,
             filter: Filter::All,
             search_query: String::new(),
             input_mode: InputMode::Normal,
@@ -289,12 +980,31 @@ impl App {
             tick_count: 0,
             refresh_ticks: DEFAULT_REFRESH_TICKS,
             status_message: None,
+            status_error: false,
+            status_history: VecDeque::new(),
+            messages_scroll: 0,
+            insight_selected: 0,
+            history: Vec::new(),
+            history_scroll: 0,
+            help_scroll: 0,
             presets: Vec::new(),
             preset_selected: 0,
+            preset_input: PresetInput::new(),
+            preset_popup_mode: PresetPopupMode::List,
+            preset_editing: None,
+            snapshots: Snapshots::default(),
+            snapshot_selected: 0,
+            snapshot_name: None,
+            scan_range: String::new(),
+            scan_entries: Vec::new(),
+            scanning: false,
+            launch_title: String::new(),
+            launch_results: Vec::new(),
             remote_host: None,
             docker_target: None,
             container_ip: None,
             docker_port_mappings: HashMap::new(),
+            docker_vm: None,
             connections: vec![Connection::local()],
             active_connection: 0,
             connection_selected: 0,
@@ -302,6 +1012,61 @@ impl App {
             connection_popup_mode: ConnectionPopupMode::List,
             ssh_forwards: HashMap::new(),
             loading: true,
+            last_refresh: None,
+            refresh_timings: Vec::new(),
+            slow_phases_hinted: HashSet::new(),
+            mock_mode: false,
+            mock_churn: false,
+            view_defaults: ViewDefaults::default(),
+            registry: PortRegistry::default(),
+            compose: None,
+            expected: Expected::default(),
+            env_ports: EnvPorts::default(),
+            services: Services::default(),
+            host_names: HostNames::default(),
+            sources: Vec::new(),
+            config_diagnostics: Vec::new(),
+            connection_health: HashMap::new(),
+            auth_host: None,
+            auth_declined: HashSet::new(),
+            aggregate_connection: None,
+            errors: Vec::new(),
+            error_scroll: 0,
+            details_raw: None,
+            details_scroll: 0,
+            established: None,
+            established_scroll: 0,
+            output: None,
+            output_scroll: 0,
+            container_stats: None,
+            container_stats_loading: false,
+            established_seen: HashMap::new(),
+            show_logs: false,
+            compact: false,
+            layout: UiLayout::Table,
+            latency_column: false,
+            usage_columns: false,
+            sort: SortKey::Port,
+            activity: false,
+            allow_sudo_kill: false,
+            sudo_kill: None,
+            undo: undo::History::default(),
+            rerun: None,
+            start_prompt: None,
+            started: HashMap::new(),
+            local_user: None,
+            pins: Pins::default(),
+            ignore_ports: HashSet::new(),
+            ignore_processes: Vec::new(),
+            show_hidden: false,
+            hidden_count: 0,
+            custom_actions: Vec::new(),
+            context_menu: Vec::new(),
+            context_selected: 0,
+            tmux: TmuxConfig::default(),
+            in_tmux: false,
+            tunnels: TunnelsConfig::default(),
+            tunnel_activity: HashMap::new(),
         }
     }
 
@@ -313,569 +1078,2965 @@ impl App {
         self.docker_target.is_some()
     }
 
-    pub fn preset_next(&mut self) {
-        if !self.presets.is_empty() {
-            self.preset_selected = (self.preset_selected + 1) % self.presets.len();
+    /// Whether the active connection offers the preset at `index`.
+    pub fn is_preset_visible(&self, index: usize) -> bool {
+        let Some(preset) = self.presets.get(index) else {
+            return false;
+        };
+        match self
+            .active_connection()
+            .and_then(|c| c.defaults.presets.as_ref())
+        {
+            Some(names) => names.iter().any(|n| n == &preset.name),
+            None => true,
         }
     }
 
-    pub fn preset_previous(&mut self) {
-        if !self.presets.is_empty() {
-            self.preset_selected = self
-                .preset_selected
-                .checked_sub(1)
-                .unwrap_or(self.presets.len() - 1);
-        }
+    pub fn visible_preset_indices(&self) -> Vec<usize> {
+        (0..self.presets.len())
+            .filter(|&i| self.is_preset_visible(i))
+            .collect()
     }
 
-    pub fn selected_preset(&self) -> Option<&Preset> {
-        self.presets.get(self.preset_selected)
+    /// Index of the visible preset bound to hotkey `key`.
+    pub fn preset_for_key(&self, key: char) -> Option<usize> {
+        self.visible_preset_indices()
+            .into_iter()
+            .find(|&i| self.presets[i].hotkey() == Some(key))
     }
 
-    pub fn set_status(&mut self, message: &str) {
-        self.status_message = Some((message.to_string(), STATUS_MESSAGE_TICKS));
+    /// Move the preset cursor to the first visible preset.
+    pub fn preset_first(&mut self) {
+        self.preset_selected = self.visible_preset_indices().first().copied().unwrap_or(0);
     }
 
-    pub fn tick(&mut self) {
-        self.tick_count = self.tick_count.wrapping_add(1);
-        // Decrement status message timer
-        if let Some((_, ref mut ticks)) = self.status_message {
-            if *ticks > 0 {
-                *ticks -= 1;
-            } else {
-                self.status_message = None;
-            }
+    pub fn preset_next(&mut self) {
+        let visible = self.visible_preset_indices();
+        if !visible.is_empty() {
+            let pos = visible.iter().position(|&i| i == self.preset_selected);
+            self.preset_selected = visible[pos.map_or(0, |p| (p + 1) % visible.len())];
         }
     }
 
-    pub fn should_refresh(&self) -> bool {
-        self.auto_refresh && self.tick_count > 0 && self.tick_count % self.refresh_ticks == 0
+    pub fn preset_previous(&mut self) {
+        let visible = self.visible_preset_indices();
+        if !visible.is_empty() {
+            let pos = visible.iter().position(|&i| i == self.preset_selected);
+            self.preset_selected =
+                visible[pos.map_or(0, |p| p.checked_sub(1).unwrap_or(visible.len() - 1))];
+        }
     }
 
-    pub fn reset_forward_input(&mut self) {
-        self.forward_input = ForwardInput::new();
+    pub fn selected_preset(&self) -> Option<&Preset> {
+        if self.is_preset_visible(self.preset_selected) {
+            self.presets.get(self.preset_selected)
+        } else {
+            None
+        }
     }
 
-    /// Returns the known forwards for the active connection.
-    pub fn known_forwards(&self) -> &HashMap<u16, u16> {
-        static EMPTY: std::sync::LazyLock<HashMap<u16, u16>> =
-            std::sync::LazyLock::new(HashMap::new);
-        self.ssh_forwards
-            .get(&self.active_connection)
-            .unwrap_or(&EMPTY)
+    pub fn selected_snapshot(&self) -> Option<(&String, &Snapshot)> {
+        self.snapshots.snapshots.iter().nth(self.snapshot_selected)
     }
 
-    /// Returns true if `ssh_forwards` was updated (caller should persist).
-    pub fn set_entries(&mut self, entries: Vec<PortEntry>) -> bool {
-        let mut forwards_changed = false;
-
-        if self.docker_target.is_some() && self.remote_host.is_some() {
-            // Persist newly detected mappings from collect_all() (lsof+probe)
-            for entry in &entries {
-                if let Some(fwd) = entry.forwarded_port {
-                    let map = self.ssh_forwards.entry(self.active_connection).or_default();
-                    if map.get(&entry.local_port) != Some(&fwd) {
-                        map.insert(entry.local_port, fwd);
-                        forwards_changed = true;
-                    }
-                }
-            }
+    pub fn snapshot_next(&mut self) {
+        if self.snapshot_selected + 1 < self.snapshots.snapshots.len() {
+            self.snapshot_selected += 1;
         }
-
-        self.entries = entries;
-        self.apply_filter();
-        forwards_changed
     }
 
-    pub fn apply_filter(&mut self) {
-        self.filtered_entries = self
-            .entries
-            .iter()
-            .filter(|e| {
-                let source_match = match self.filter {
-                    Filter::All => true,
-                    Filter::Local => e.source == PortSource::Local,
-                    Filter::Ssh => e.source == PortSource::Ssh,
-                    Filter::Docker => e.source == PortSource::Docker,
-                };
+    pub fn snapshot_previous(&mut self) {
+        self.snapshot_selected = self.snapshot_selected.saturating_sub(1);
+    }
 
-                let search_match = if self.search_query.is_empty() {
-                    true
-                } else {
-                    let query = self.search_query.to_lowercase();
-                    e.process_name.to_lowercase().contains(&query)
-                        || e.local_port.to_string().contains(&query)
-                        || e.remote_host
-                            .as_ref()
-                            .is_some_and(|h| h.to_lowercase().contains(&query))
-                };
+    /// Save the SSH tunnels in the table as snapshot `name`, replacing one of
+    /// the same name. Returns how many forwards it holds.
+    pub fn save_snapshot(&mut self, name: &str) -> Result<usize, String> {
+        let forwards = snapshot::forwards_of(&self.entries);
+        if forwards.is_empty() {
+            return Err("No SSH forwards running; nothing to save".to_string());
+        }
+        let count = forwards.len();
+        self.snapshots
+            .snapshots
+            .insert(name.to_string(), Snapshot::now(forwards));
+        self.snapshot_selected = self
+            .snapshots
+            .snapshots
+            .keys()
+            .position(|k| k == name)
+            .unwrap_or_default();
+        Ok(count)
+    }
 
-                source_match && search_match
-            })
-            .cloned()
-            .collect();
+    pub fn reset_preset_input(&mut self) {
+        self.preset_input = PresetInput::new();
+        self.preset_editing = None;
+    }
 
-        if self.selected >= self.filtered_entries.len() {
-            self.selected = self.filtered_entries.len().saturating_sub(1);
-        }
+    pub fn set_status(&mut self, message: &str) {
+        self.push_status(message, false);
     }
 
-    pub fn set_filter(&mut self, filter: Filter) {
-        self.filter = filter;
-        self.apply_filter();
+    /// Show an error in the status bar until it is dismissed.
+    pub fn set_error(&mut self, message: &str) {
+        self.push_status(message, true);
     }
 
-    pub fn next(&mut self) {
-        if !self.filtered_entries.is_empty() {
-            self.selected = (self.selected + 1) % self.filtered_entries.len();
+    fn push_status(&mut self, message: &str, is_error: bool) {
+        self.status_message = Some((message.to_string(), STATUS_MESSAGE_TICKS));
+        self.status_error = is_error;
+        if self.status_history.len() == STATUS_HISTORY_LEN {
+            self.status_history.pop_front();
         }
+        self.status_history.push_back(StatusEntry {
+            at: chrono::Local::now(),
+            message: message.to_string(),
+            is_error,
+        });
     }
 
-    pub fn previous(&mut self) {
-        if !self.filtered_entries.is_empty() {
-            self.selected = self
-                .selected
-                .checked_sub(1)
-                .unwrap_or(self.filtered_entries.len() - 1);
+    /// Clear a sticky error; returns whether there was one.
+    pub fn dismiss_error(&mut self) -> bool {
+        if self.status_error {
+            self.status_message = None;
+            self.status_error = false;
+            return true;
         }
+        false
     }
 
-    pub fn first(&mut self) {
-        self.selected = 0;
+    pub fn scroll_messages(&mut self, delta: isize) {
+        self.messages_scroll = self
+            .messages_scroll
+            .saturating_add_signed(delta)
+            .min(self.status_history.len().saturating_sub(1));
     }
 
-    pub fn last(&mut self) {
-        if !self.filtered_entries.is_empty() {
-            self.selected = self.filtered_entries.len() - 1;
+    pub fn move_insight(&mut self, delta: isize) {
+        self.insight_selected = self
+            .insight_selected
+            .saturating_add_signed(delta)
+            .min(self.insights().len().saturating_sub(1));
+    }
+
+    /// Close the Insights popup on the entry the selected insight is about,
+    /// clearing the search and source filter when they hide it.
+    pub fn jump_to_insight(&mut self) {
+        let Some(insight) = self.insights().into_iter().nth(self.insight_selected) else {
+            return;
+        };
+        self.popup = Popup::None;
+        let port = insight.port;
+        if !self.filtered_entries.iter().any(|e| e.local_port == port) {
+            self.search_query.clear();
+            self.filter = Filter::All;
+            self.apply_filter();
+        }
+        let Some(entry) = self.filtered_entries.iter().find(|e| e.local_port == port) else {
+            self.set_status(&format!("Nothing listens on :{port}"));
+            return;
+        };
+        if let Some(key) = GroupKey::of(entry) {
+            self.expanded_groups.insert(key);
+            self.build_rows();
+        }
+        if let Some(index) = self.rows.iter().position(|row| match row {
+            TableRow::Entry(i) | TableRow::Member(i) => {
+                self.filtered_entries[*i].local_port == port
+            }
+            TableRow::Group { .. } => false,
+        }) {
+            self.move_selection(index);
         }
     }
 
-    pub fn selected_entry(&self) -> Option<&PortEntry> {
-        self.filtered_entries.get(self.selected)
+    pub fn scroll_history(&mut self, delta: isize) {
+        self.history_scroll = self
+            .history_scroll
+            .saturating_add_signed(delta)
+            .min(self.history.len().saturating_sub(1));
+    }
+
+    /// Replace the collection errors and summarise the first one in the status bar.
+    pub fn report_errors(&mut self, errors: Vec<ErrorReport>) {
+        if let Some(first) = errors.first() {
+            let more = match errors.len() {
+                1 => String::new(),
+                n => format!(" (+{} more)", n - 1),
+            };
+            self.set_error(&format!(
+                "{}: {}{more} - E for details",
+                first.title, first.summary
+            ));
+        }
+        self.errors = errors;
+        self.error_scroll = self.error_scroll.min(self.max_error_scroll());
+    }
+
+    fn max_error_scroll(&self) -> usize {
+        self.errors
+            .iter()
+            .map(ErrorReport::line_count)
+            .sum::<usize>()
+            .saturating_sub(1)
+    }
+
+    pub fn scroll_errors(&mut self, delta: isize) {
+        self.error_scroll = self
+            .error_scroll
+            .saturating_add_signed(delta)
+            .min(self.max_error_scroll());
+    }
+
+    /// Show the Details popup for the selected entry, scrolled to the top.
+    pub fn open_details(&mut self) {
+        self.popup = Popup::Details;
+        self.details_raw = None;
+        self.details_scroll = 0;
+    }
+
+    /// Open the context menu with every action that applies to the selected
+    /// entry. Returns `false` when nothing is selected.
+    pub fn open_context_menu(&mut self) -> bool {
+        let Some(entry) = self.selected_entry() else {
+            return false;
+        };
+        let item = |label: &str, action| MenuItem {
+            label: label.to_string(),
+            action,
+            command: None,
+        };
+        // Enter starts a closed expected service instead of showing details
+        let details = if self.startable_service().is_some() {
+            Action::ShowDetails
+        } else {
+            Action::Select
+        };
+        let mut items = vec![item("Show details", details)];
+        if !self.is_docker_target() {
+            items.push(item("Who is connected", Action::ShowEstablished));
+        }
+        items.push(item("Show output", Action::ShowOutput));
+        // The merged view is read-only; see `aggregate_read_only`
+        if !self.is_aggregate() {
+            items.push(item("Kill", Action::Kill));
+            if entry.source == PortSource::Local
+                && self.remote_host.is_none()
+                && !self.is_docker_target()
+            {
+                items.push(item("Restart process", Action::RestartProcess));
+            }
+            if let Some(service) = self.startable_service() {
+                let command = service.command.as_deref().unwrap_or_default();
+                items.push(item(
+                    &format!("Start {} ({command})", service.name),
+                    Action::StartService,
+                ));
+            }
+            items.push(item("New SSH forward", Action::StartForward));
+            if self.remote_host.is_some() && entry.source != PortSource::Ssh {
+                items.push(item("Quick forward", Action::QuickForward));
+            }
+            if entry.source == PortSource::Docker && !self.is_docker_target() {
+                items.push(item("Container logs", Action::ShowLogs));
+                items.push(item("Restart container", Action::RestartContainer));
+            }
+            if self.idle_for(entry, Instant::now()).is_some() {
+                items.push(item("Close idle tunnels", Action::CloseIdleTunnels));
+            }
+        }
+        let pinned = self.pins.contains(self.pin_scope(entry), entry.local_port);
+        items.push(item(
+            if pinned { "Unpin" } else { "Pin" },
+            Action::TogglePin,
+        ));
+        items.push(item(
+            &format!("Copy {}", self.entry_address(entry)),
+            Action::CopyAddress,
+        ));
+        items.push(item("Open in browser", Action::OpenBrowser));
+        if self.in_tmux {
+            if let Ok(command) = self.tmux_shell_command() {
+                items.push(MenuItem {
+                    label: "Shell in tmux".to_string(),
+                    action: Action::TmuxShell,
+                    command: Some(command),
+                });
+            }
+            if !self.is_aggregate() && self.remote_host.is_some() && entry.source != PortSource::Ssh
+            {
+                items.push(item("Forward in tmux pane", Action::TmuxForward));
+            }
+        }
+
+        let host = self.remote_host.as_deref();
+        for (i, custom) in self.custom_actions.iter().enumerate() {
+            if action::applies(custom, entry, host) {
+                items.push(MenuItem {
+                    label: custom.name.clone(),
+                    action: Action::RunCustom(i),
+                    command: action::render(&custom.command, entry, host).ok(),
+                });
+            }
+        }
+        self.context_menu = items;
+        self.context_selected = 0;
+        self.popup = Popup::ContextMenu;
+        true
+    }
+
+    pub fn context_menu_next(&mut self) {
+        if self.context_selected + 1 < self.context_menu.len() {
+            self.context_selected += 1;
+        }
+    }
+
+    pub fn context_menu_previous(&mut self) {
+        self.context_selected = self.context_selected.saturating_sub(1);
+    }
+
+    /// Where `entry` can be reached from this machine, e.g. `localhost:3000`.
+    ///
+    /// Forwarded ports are reached through the tunnel; other remote ports on
+    /// the remote host itself.
+    pub fn entry_address(&self, entry: &PortEntry) -> String {
+        if let Some(port) = entry.forwarded_port {
+            return format!("localhost:{port}");
+        }
+        match self.remote_host.as_deref() {
+            Some(host) if entry.source != PortSource::Ssh => {
+                let host = host.rsplit_once('@').map_or(host, |(_, h)| h);
+                format!("{host}:{}", entry.local_port)
+            }
+            _ => format!("localhost:{}", entry.local_port),
+        }
+    }
+
+    /// Custom action `index` with its command filled in for the selected entry.
+    pub fn prepare_action(&self, index: usize) -> Result<PendingAction, String> {
+        let action = self
+            .custom_actions
+            .get(index)
+            .ok_or_else(|| "No such action".to_string())?;
+        let entry = self
+            .selected_entry()
+            .ok_or_else(|| "No entry selected".to_string())?;
+        if !action.sources.is_empty() && !action.sources.contains(&entry.source) {
+            return Err(format!("{}: not for {} entries", action.name, entry.source));
+        }
+        let command = action::render(&action.command, entry, self.remote_host.as_deref())
+            .map_err(|e| format!("{}: {e}", action.name))?;
+        Ok(PendingAction {
+            name: action.name.clone(),
+            command,
+            background: action.background,
+        })
+    }
+
+    /// Command "Shell in tmux" runs for the selected entry: `[tmux]
+    /// shell_command` if set, else a shell on its host or in its container.
+    pub fn tmux_shell_command(&self) -> Result<String, String> {
+        let entry = self
+            .selected_entry()
+            .ok_or_else(|| "No entry selected".to_string())?;
+        let host = self.remote_host.as_deref();
+        match &self.tmux.shell_command {
+            Some(command) => action::render(command, entry, host),
+            None => tmux::shell_command(entry, host, self.docker_target.as_deref())
+                .ok_or_else(|| "No remote host or container to open a shell in".to_string()),
+        }
+    }
+
+    /// Keep raw output if it is for the entry still shown in the Details popup.
+    pub fn apply_raw_details(&mut self, raw: RawDetails) {
+        let current = self.selected_entry().map(|e| e.local_port);
+        if self.popup == Popup::Details && current == Some(raw.port) {
+            self.details_raw = Some(raw);
+        }
+    }
+
+    /// Show the Established popup for the selected entry, scrolled to the top.
+    pub fn open_established(&mut self) {
+        self.popup = Popup::Established;
+        self.established = None;
+        self.established_scroll = 0;
+    }
+
+    /// Show the Output popup for the selected entry.
+    pub fn open_output(&mut self) {
+        self.popup = Popup::Output;
+        self.output = None;
+    }
+
+    /// Keep output read for the entry still shown in the Output popup,
+    /// scrolled to its end.
+    pub fn apply_output(&mut self, view: OutputView) {
+        let current = self.selected_entry().map(|e| e.local_port);
+        if self.popup != Popup::Output || current != Some(view.port) {
+            return;
+        }
+        self.output = Some(view);
+        self.output_scroll = usize::MAX;
+    }
+
+    /// Keep connections if they are for the entry still shown in the
+    /// Established popup, noting when each was first seen.
+    pub fn apply_established(&mut self, list: EstablishedList, now: Instant) {
+        let current = self.selected_entry().map(|e| e.local_port);
+        if self.popup != Popup::Established || current != Some(list.port) {
+            return;
+        }
+        if let Ok(connections) = &list.connections {
+            let port = list.port;
+            let first_look = !self.established_seen.keys().any(|(p, _)| *p == port);
+            let peers: HashSet<&str> = connections.iter().map(|c| c.peer.as_str()).collect();
+            // Closed connections are forgotten, so a new one from the same peer starts over
+            self.established_seen
+                .retain(|(p, peer), _| *p != port || peers.contains(peer.as_str()));
+            for peer in peers {
+                self.established_seen
+                    .entry((port, peer.to_string()))
+                    .or_insert((now, first_look));
+            }
+        }
+        self.established = Some(list);
+    }
+
+    /// How long `peer` has been connected to `port` as far as quay knows, and
+    /// whether it was connected before that (already there at the first look).
+    pub fn established_age(&self, port: u16, peer: &str, now: Instant) -> Option<(Duration, bool)> {
+        let (since, before) = self.established_seen.get(&(port, peer.to_string()))?;
+        Some((now.saturating_duration_since(*since), *before))
+    }
+
+    /// Scroll the Details popup by `delta` lines, up to `max`.
+    pub fn scroll_details(&mut self, delta: isize, max: usize) {
+        self.details_scroll = self.details_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn tick(&mut self) {
+        self.tick_count = self.tick_count.wrapping_add(1);
+        // Decrement status message timer; errors stay until dismissed
+        if let (Some((_, ticks)), false) = (&mut self.status_message, self.status_error) {
+            if *ticks > 0 {
+                *ticks -= 1;
+            } else {
+                self.status_message = None;
+            }
+        }
+    }
+
+    pub fn should_refresh(&self) -> bool {
+        (self.auto_refresh || self.activity)
+            && !self.refresh_paused()
+            && self.tick_count > 0
+            && self.tick_count % self.current_refresh_ticks() == 0
+    }
+
+    /// Ticks between auto-refreshes: shorter in the activity view.
+    pub fn current_refresh_ticks(&self) -> u32 {
+        if self.activity {
+            self.refresh_ticks.min(ACTIVITY_REFRESH_TICKS)
+        } else {
+            self.refresh_ticks
+        }
+    }
+
+    /// Auto-refresh waits while a popup is open or a search is being typed,
+    /// so rows don't move under the user.
+    pub fn refresh_paused(&self) -> bool {
+        self.popup != Popup::None || self.input_mode == InputMode::Search
+    }
+
+    pub fn reset_forward_input(&mut self) {
+        self.forward_input = ForwardInput::new();
+    }
+
+    /// Returns the known forwards for the active connection.
+    pub fn known_forwards(&self) -> &HashMap<u16, u16> {
+        static EMPTY: std::sync::LazyLock<HashMap<u16, u16>> =
+            std::sync::LazyLock::new(HashMap::new);
+        self.ssh_forwards
+            .get(&self.active_connection)
+            .unwrap_or(&EMPTY)
+    }
+
+    /// `set_entries` for a collection, keeping its per-source status.
+    pub fn set_collection(&mut self, collection: Collection) -> bool {
+        self.sources = collection.sources;
+        self.refresh_timings = collection.timings;
+        self.hint_slow_phase();
+        self.set_entries(collection.entries)
+    }
+
+    /// Phase of the last collection that took longest.
+    pub fn slowest_phase(&self) -> Option<(&str, Duration)> {
+        self.refresh_timings
+            .iter()
+            .max_by_key(|(_, elapsed)| *elapsed)
+            .map(|(name, elapsed)| (name.as_str(), *elapsed))
+    }
+
+    /// Explain, once per phase, how to speed up one slower than
+    /// [`SLOW_PHASE`]: remote phases pay for an SSH connection each.
+    fn hint_slow_phase(&mut self) {
+        let Some((name, elapsed)) = self.slowest_phase() else {
+            return;
+        };
+        if elapsed < SLOW_PHASE || self.slow_phases_hinted.contains(name) {
+            return;
+        }
+        let name = name.to_string();
+        let secs = elapsed.as_secs_f32();
+        let remote = self.is_remote() || (self.is_aggregate() && !name.starts_with("Local "));
+        let hint = if remote {
+            format!(
+                "{name} took {secs:.1}s: ControlMaster auto with ControlPersist in ~/.ssh/config reuses one SSH connection across refreshes"
+            )
+        } else if name.ends_with("docker") {
+            format!("{name} took {secs:.1}s: the Docker daemon is slow to answer `docker ps`")
+        } else {
+            format!(
+                "{name} took {secs:.1}s: a longer refresh_interval in config.toml refreshes less often"
+            )
+        };
+        self.set_status(&hint);
+        self.slow_phases_hinted.insert(name);
+    }
+
+    /// Returns true if `ssh_forwards` was updated (caller should persist).
+    pub fn set_entries(&mut self, entries: Vec<PortEntry>) -> bool {
+        let mut forwards_changed = false;
+
+        if self.docker_target.is_some() && self.remote_host.is_some() {
+            // Persist newly detected mappings from collect_all() (lsof+probe)
+            for entry in &entries {
+                if let Some(fwd) = entry.forwarded_port {
+                    let map = self.ssh_forwards.entry(self.active_connection).or_default();
+                    if map.get(&entry.local_port) != Some(&fwd) {
+                        map.insert(entry.local_port, fwd);
+                        forwards_changed = true;
+                    }
+                }
+            }
+        }
+
+        self.entries = entries;
+        self.started
+            .retain(|_, s| s.pending.load(Ordering::Relaxed));
+        self.track_tunnel_activity(Instant::now());
+        if self.uses_registry() {
+            self.add_registry_placeholders();
+        }
+        self.add_scan_entries();
+        self.fill_container_usage();
+        let anchor = self.selection_anchor();
+        self.apply_filter();
+        self.restore_selection(anchor);
+        forwards_changed
+    }
+
+    /// Keep `docker stats` read on the active connection's host and show it
+    /// on its containers' entries.
+    pub fn apply_container_stats(&mut self, stats: ContainerStats) {
+        self.container_stats_loading = false;
+        if stats.remote_host != self.remote_host || self.is_aggregate() {
+            return;
+        }
+        self.container_stats = Some(stats);
+        self.fill_container_usage();
+        let anchor = self.selection_anchor();
+        self.apply_filter();
+        self.restore_selection(anchor);
+    }
+
+    /// Set `usage` of Docker entries from the last `docker stats`.
+    fn fill_container_usage(&mut self) {
+        let Some(stats) = &self.container_stats else {
+            return;
+        };
+        for entry in &mut self.entries {
+            if entry.source != PortSource::Docker {
+                continue;
+            }
+            entry.usage = [&entry.container_id, &entry.container_name]
+                .into_iter()
+                .flatten()
+                .find_map(|key| stats.usage.get(key))
+                .copied();
+        }
+    }
+
+    /// Swap `old` for `fresh`, its recheck result, keeping the cursor on it.
+    /// Returns false when `old` is gone (a refresh came first).
+    pub fn replace_entry(&mut self, old: &PortEntry, fresh: PortEntry) -> bool {
+        let key = EntryKey::of(old);
+        let Some(slot) = self
+            .entries
+            .iter_mut()
+            .find(|e| EntryKey::of(e) == key && e.bind == old.bind)
+        else {
+            return false;
+        };
+        *slot = fresh;
+        let anchor = self.selection_anchor();
+        self.apply_filter();
+        self.restore_selection(anchor);
+        true
+    }
+
+    /// Keep the open `ports` of a finished scan in the table until the
+    /// connection changes or the scan is cleared (an empty `ports`).
+    pub fn set_scan_results(&mut self, ports: &[u16]) {
+        self.scan_entries = ports
+            .iter()
+            .map(|&port| port::scan::scan_entry(None, port))
+            .collect();
+        self.entries.retain(|e| e.source != PortSource::Scan);
+        self.add_scan_entries();
+        self.apply_filter();
+    }
+
+    /// Scan results for ports nothing else lists.
+    fn add_scan_entries(&mut self) {
+        let present: HashSet<u16> = self.entries.iter().map(|e| e.local_port).collect();
+        let before = self.entries.len();
+        self.entries.extend(
+            self.scan_entries
+                .iter()
+                .filter(|e| !present.contains(&e.local_port))
+                .cloned(),
+        );
+        if self.entries.len() > before {
+            self.entries.sort_by_key(|e| (!e.is_open, e.local_port));
+        }
+    }
+
+    /// The port registry describes this machine, so it only applies to local scans.
+    fn uses_registry(&self) -> bool {
+        !self.is_remote() && !self.is_docker_target() && !self.is_aggregate()
+    }
+
+    pub fn port_label(&self, port: u16) -> Option<&str> {
+        if self.uses_registry() {
+            self.registry.label(port)
+        } else {
+            None
+        }
+    }
+
+    /// Compose service publishing `port`, on local scans like the registry.
+    pub fn compose_service(&self, port: u16) -> Option<&str> {
+        if self.uses_registry() {
+            self.compose.as_ref()?.service_of(port)
+        } else {
+            None
+        }
+    }
+
+    /// Procfile or `.quay.toml` service expected on `port`, on local scans.
+    pub fn expected_service(&self, port: u16) -> Option<&ExpectedService> {
+        if self.uses_registry() {
+            self.expected.get(port)
+        } else {
+            None
+        }
+    }
+
+    /// What starts the selected row's service while nothing listens on its
+    /// port: the command of its expected service, or the `start` of its
+    /// `ports.toml` entry, run in the current directory. `None` while quay
+    /// is already starting it.
+    pub fn startable_service(&self) -> Option<ExpectedService> {
+        let entry = self.selected_entry().filter(|e| !e.is_open)?;
+        let port = entry.local_port;
+        if self.starting(port).is_some() {
+            return None;
+        }
+        if let Some(service) = self.expected_service(port).filter(|s| s.command.is_some()) {
+            return Some(service.clone());
+        }
+        let registered = self.registry.get(port).filter(|_| self.uses_registry())?;
+        Some(ExpectedService {
+            name: registered.label.clone(),
+            port,
+            command: Some(registered.start.clone()?),
+            dir: PathBuf::from("."),
+            origin: "ports.toml",
+        })
+    }
+
+    /// The service quay started on `port` while it waits for the port to open.
+    pub fn starting(&self, port: u16) -> Option<&StartedService> {
+        self.started
+            .get(&port)
+            .filter(|s| s.pending.load(Ordering::Relaxed))
+    }
+
+    /// Compose services with an open port, out of those publishing any.
+    pub fn compose_summary(&self) -> Option<(usize, usize)> {
+        let compose = self.compose.as_ref().filter(|_| self.uses_registry())?;
+        let up = compose
+            .services
+            .iter()
+            .filter(|s| {
+                self.entries
+                    .iter()
+                    .any(|e| e.is_open && s.ports.contains(&e.local_port))
+            })
+            .count();
+        Some((up, compose.services.len()))
+    }
+
+    /// Entries per source, open and closed, and forwards started.
+    pub fn session_stats(&self) -> SessionStats {
+        let sources = [
+            PortSource::Local,
+            PortSource::Docker,
+            PortSource::Ssh,
+            PortSource::Scan,
+        ];
+        let per_source = sources
+            .into_iter()
+            .map(|source| {
+                let count = self.entries.iter().filter(|e| e.source == source).count();
+                (source, count)
+            })
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        let open = self.entries.iter().filter(|e| e.is_open).count();
+        SessionStats {
+            per_source,
+            open,
+            closed: self.entries.len() - open,
+            forwards_started: history::forwards_started(),
+        }
+    }
+
+    /// Anomalies of the current entries; the project's `.env` only
+    /// describes this machine.
+    pub fn insights(&self) -> Vec<Insight> {
+        let empty = EnvPorts::default();
+        let env = if self.uses_registry() {
+            &self.env_ports
+        } else {
+            &empty
+        };
+        insights::collect(&self.entries, env)
+    }
+
+    /// Expected process name when a registered port is held by something else.
+    pub fn unexpected_process(&self, entry: &PortEntry) -> Option<&str> {
+        if self.uses_registry() {
+            self.registry.unexpected_process(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Host `entry` runs on (`None` for this machine), also in the
+    /// "All connections" view.
+    pub fn host_of(&self, entry: &PortEntry) -> Option<&str> {
+        // SSH tunnels are local processes even when scanning a remote host
+        if entry.source == PortSource::Ssh {
+            None
+        } else if let Some(name) = &entry.connection {
+            self.connections
+                .iter()
+                .find(|c| &c.name == name)
+                .and_then(|c| c.remote_host.as_deref())
+        } else {
+            self.remote_host.as_deref()
+        }
+    }
+
+    /// Owner of `entry` when it is not the user quay acts as on that machine.
+    ///
+    /// Remote processes compare against the login in `user@host`; without one
+    /// the remote user is unknown and nothing is flagged.
+    pub fn foreign_owner<'a>(&self, entry: &'a PortEntry) -> Option<&'a str> {
+        let acting = match self.host_of(entry) {
+            Some(host) => host.split_once('@').map(|(user, _)| user),
+            None => self.local_user.as_deref(),
+        }?;
+        if entry.is_owned_by_other(acting) {
+            entry.user.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Note which forwards carry connections at `now`; forwards whose
+    /// connections couldn't be counted start over.
+    fn track_tunnel_activity(&mut self, now: Instant) {
+        let mut seen = HashSet::new();
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| port::is_local_forward(e) && e.established.is_some())
+        {
+            let key = (entry.pid, entry.local_port);
+            seen.insert(key);
+            if entry.established > Some(0) {
+                self.tunnel_activity.insert(key, now);
+            } else {
+                self.tunnel_activity.entry(key).or_insert(now);
+            }
+        }
+        self.tunnel_activity.retain(|key, _| seen.contains(key));
+    }
+
+    /// How long `entry`'s forward has gone without a connection, once that
+    /// exceeds `[tunnels] idle_minutes`.
+    pub fn idle_for(&self, entry: &PortEntry, now: Instant) -> Option<Duration> {
+        let since = self.tunnel_activity.get(&(entry.pid, entry.local_port))?;
+        let idle = now.saturating_duration_since(*since);
+        (idle >= self.tunnels.idle_after()?).then_some(idle)
+    }
+
+    /// Idle forwards that can be closed: this user's, and only when every
+    /// forward of their ssh process is idle, since stopping it ends them all.
+    pub fn idle_tunnels(&self, now: Instant) -> Vec<PortEntry> {
+        let tunnels: Vec<&PortEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.source == PortSource::Ssh)
+            .collect();
+        let shares_busy_process = |entry: &PortEntry| {
+            // Embedded forwards run in quay itself, have no PID, and are
+            // stopped one by one
+            entry.pid.is_some()
+                && tunnels
+                    .iter()
+                    .any(|t| t.pid == entry.pid && self.idle_for(t, now).is_none())
+        };
+        tunnels
+            .iter()
+            .filter(|e| {
+                (e.pid.is_some() || e.process_name == port::ssh::EMBEDDED_PROCESS)
+                    && self.idle_for(e, now).is_some()
+            })
+            .filter(|e| self.foreign_owner(e).is_none() && !shares_busy_process(e))
+            .map(|e| (*e).clone())
+            .collect()
+    }
+
+    /// Connection name the pins of `entry` are stored under.
+    fn pin_scope<'a>(&'a self, entry: &'a PortEntry) -> &'a str {
+        entry
+            .connection
+            .as_deref()
+            .or_else(|| self.active_connection().map(|c| c.name.as_str()))
+            .unwrap_or_default()
+    }
+
+    /// File the table is exported to in the current directory, e.g.
+    /// `quay-staging-20261016-093000.md`.
+    pub fn export_path(&self, format: Format, at: chrono::DateTime<chrono::Local>) -> PathBuf {
+        let connection: String = self
+            .active_connection()
+            .map_or("local", |c| c.name.as_str())
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        PathBuf::from(format!(
+            "quay-{connection}-{}.{}",
+            at.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        ))
+    }
+
+    pub fn is_pinned(&self, entry: &PortEntry) -> bool {
+        self.pins.contains(self.pin_scope(entry), entry.local_port)
+    }
+
+    /// Whether the ignore list hides `entry`; pinned ports are never hidden.
+    pub fn is_hidden(&self, entry: &PortEntry) -> bool {
+        if self.is_pinned(entry) {
+            return false;
+        }
+        self.ignore_ports.contains(&entry.local_port) || self.ignored_process(entry).is_some()
+    }
+
+    /// The `ignore_processes` name matching the entry's process or container.
+    fn ignored_process(&self, entry: &PortEntry) -> Option<&str> {
+        let names = [
+            Some(entry.process_name.as_str()),
+            entry.container_name.as_deref(),
+        ];
+        self.ignore_processes
+            .iter()
+            .find(|ignored| {
+                names
+                    .iter()
+                    .flatten()
+                    .any(|name| name.eq_ignore_ascii_case(ignored))
+            })
+            .map(String::as_str)
+    }
+
+    /// Hide the selected port for this session, or show it again if hidden.
+    /// Returns a status message.
+    pub fn toggle_hidden(&mut self) -> Option<String> {
+        let entry = self.selected_entry()?;
+        let port = entry.local_port;
+        if let Some(name) = self.ignored_process(entry) {
+            return Some(format!(
+                ":{port} is hidden by general.ignore_processes ({name})"
+            ));
+        }
+        let message = if self.ignore_ports.remove(&port) {
+            format!("Unhid :{port} for this session")
+        } else {
+            self.ignore_ports.insert(port);
+            format!(
+                "Hid :{port} for this session (add it to general.ignore_ports to keep it hidden)"
+            )
+        };
+        self.apply_filter();
+        Some(message)
+    }
+
+    /// Pin or unpin the selected port, keeping it selected.
+    /// Returns the port and whether it is pinned now.
+    pub fn toggle_pin(&mut self) -> Option<(u16, bool)> {
+        let entry = self.selected_entry()?;
+        let port = entry.local_port;
+        let scope = self.pin_scope(entry).to_string();
+        let pinned = self.pins.toggle(&scope, port);
+        self.apply_filter();
+        if let Some(index) = self.rows.iter().position(|row| match row {
+            TableRow::Entry(i) | TableRow::Member(i) => {
+                let e = &self.filtered_entries[*i];
+                e.local_port == port && self.pin_scope(e) == scope
+            }
+            TableRow::Group { .. } => false,
+        }) {
+            self.selected = index;
+        }
+        Some((port, pinned))
+    }
+
+    /// Closed rows for pinned ports that nothing is listening on, so a
+    /// stopped dev server stays visible.
+    fn pin_placeholders(&self) -> Vec<PortEntry> {
+        let scopes: Vec<(&str, Option<&str>)> = if self.is_aggregate() {
+            self.aggregate_sources()
+                .map(|(_, c)| (c.name.as_str(), Some(c.name.as_str())))
+                .collect()
+        } else {
+            self.active_connection()
+                .map(|c| (c.name.as_str(), None))
+                .into_iter()
+                .collect()
+        };
+        let mut placeholders = Vec::new();
+        for (scope, connection) in scopes {
+            for port in self.pins.ports(scope) {
+                let present = self
+                    .entries
+                    .iter()
+                    .any(|e| e.local_port == port && e.connection.as_deref() == connection);
+                if !present {
+                    let mut entry = registry::placeholder_entry(port);
+                    entry.connection = connection.map(str::to_string);
+                    placeholders.push(entry);
+                }
+            }
+        }
+        placeholders
+    }
+
+    /// Show registered, compose, and expected ports that nothing is
+    /// listening on as closed rows.
+    fn add_registry_placeholders(&mut self) {
+        let mut present: HashSet<u16> = self.entries.iter().map(|e| e.local_port).collect();
+        let compose = self.compose.iter().flat_map(ComposeFile::ports);
+        let missing: Vec<u16> = self
+            .registry
+            .ports
+            .keys()
+            .copied()
+            .chain(compose)
+            .chain(self.expected.ports())
+            .filter(|port| present.insert(*port))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        self.entries
+            .extend(missing.into_iter().map(registry::placeholder_entry));
+        self.entries.sort_by_key(|e| (!e.is_open, e.local_port));
+    }
+
+    pub fn apply_filter(&mut self) {
+        let query = Query::parse(&self.search_query);
+        let placeholders = self.pin_placeholders();
+        let mut filtered: Vec<PortEntry> = self
+            .entries
+            .iter()
+            .chain(&placeholders)
+            .filter(|e| {
+                let source_match = match self.filter {
+                    Filter::All => true,
+                    Filter::Local => e.source == PortSource::Local,
+                    Filter::Ssh => e.source == PortSource::Ssh,
+                    Filter::Docker => e.source == PortSource::Docker,
+                };
+
+                source_match
+                    && query.matches(
+                        e,
+                        self.port_label(e.local_port)
+                            .or_else(|| self.compose_service(e.local_port))
+                            .or_else(|| {
+                                self.expected_service(e.local_port).map(|s| s.name.as_str())
+                            }),
+                        self.services.of(e),
+                    )
+            })
+            .cloned()
+            .collect();
+        let before = filtered.len();
+        if !self.show_hidden {
+            filtered.retain(|e| !self.is_hidden(e));
+        }
+        self.hidden_count = before - filtered.len();
+        // Unknown usage sorts last; the sorts are stable, so ties keep their order
+        let cpu = |e: &PortEntry| e.usage.map_or(-1.0, |u| u.cpu);
+        match self.sort {
+            _ if self.activity => filtered.sort_by(|a, b| {
+                let connections = |e: &PortEntry| e.established.unwrap_or(0);
+                connections(b)
+                    .cmp(&connections(a))
+                    .then(cpu(b).total_cmp(&cpu(a)))
+            }),
+            SortKey::Port => {}
+            SortKey::Cpu => filtered.sort_by(|a, b| cpu(b).total_cmp(&cpu(a))),
+            SortKey::Memory => {
+                filtered.sort_by_key(|e| std::cmp::Reverse(e.usage.map(|u| u.memory)));
+            }
+        }
+        // Pinned ports go first; the sort is stable, so everything else keeps its order
+        filtered.sort_by_key(|e| !self.is_pinned(e));
+        self.filtered_entries = filtered;
+        self.build_rows();
+
+        if self.selected >= self.rows.len() {
+            self.selected = self.rows.len().saturating_sub(1);
+        }
+    }
+
+    /// Sort by the next key and go to the top, where the busiest entries are.
+    pub fn cycle_sort(&mut self) {
+        // In the activity view, `o` goes back to the sort it replaced
+        if self.activity {
+            self.toggle_activity();
+            return;
+        }
+        self.sort = self.sort.next();
+        self.apply_filter();
+        self.selected = 0;
+        self.table_offset = 0;
+        self.set_status(&format!("Sorted by {}", self.sort.label()));
+    }
+
+    /// Whether the CPU and MEM columns are shown: asked for in the config,
+    /// or the table is sorted by them.
+    pub fn shows_usage(&self) -> bool {
+        self.usage_columns || self.activity || self.sort != SortKey::Port
+    }
+
+    /// Switch the activity view on or off: sorted by established connections,
+    /// then CPU, and refreshed every couple of seconds even without auto-refresh.
+    pub fn toggle_activity(&mut self) {
+        self.activity = !self.activity;
+        self.apply_filter();
+        self.selected = 0;
+        self.table_offset = 0;
+        if self.activity {
+            let secs = self.current_refresh_ticks().div_ceil(4);
+            self.set_status(&format!(
+                "Activity view: busiest first, every {secs}s ([t] to leave)"
+            ));
+        } else {
+            self.set_status(&format!("Sorted by {}", self.sort.label()));
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        self.apply_filter();
+    }
+
+    /// Group entries of the same process or container under one row, placed
+    /// where the first of them would be.
+    fn build_rows(&mut self) {
+        let keys: Vec<Option<GroupKey>> = self.filtered_entries.iter().map(GroupKey::of).collect();
+        let mut counts: HashMap<&GroupKey, usize> = HashMap::new();
+        for key in keys.iter().flatten() {
+            *counts.entry(key).or_default() += 1;
+        }
+
+        let mut rows = Vec::with_capacity(keys.len());
+        let mut placed: HashSet<&GroupKey> = HashSet::new();
+        for (index, key) in keys.iter().enumerate() {
+            let Some(key) = key.as_ref().filter(|k| counts[k] > 1) else {
+                rows.push(TableRow::Entry(index));
+                continue;
+            };
+            if !placed.insert(key) {
+                continue;
+            }
+            let expanded = self.expanded_groups.contains(key);
+            rows.push(TableRow::Group {
+                key: key.clone(),
+                first: index,
+                count: counts[key],
+                expanded,
+            });
+            if expanded {
+                let members = keys.iter().enumerate().skip(index);
+                rows.extend(
+                    members
+                        .filter(|(_, k)| k.as_ref() == Some(key))
+                        .map(|(i, _)| TableRow::Member(i)),
+                );
+            }
+        }
+        self.rows = rows;
+    }
+
+    /// Expand (`Some(true)`), collapse (`Some(false)`), or toggle (`None`) the
+    /// group of the selected row. Returns false when it is not in a group.
+    pub fn set_group_expanded(&mut self, expand: Option<bool>) -> bool {
+        let key = match self.rows.get(self.selected) {
+            Some(TableRow::Group { key, .. }) => key.clone(),
+            Some(TableRow::Member(index)) => match GroupKey::of(&self.filtered_entries[*index]) {
+                Some(key) => key,
+                None => return false,
+            },
+            _ => return false,
+        };
+        let expanded = expand.unwrap_or(!self.expanded_groups.contains(&key));
+        if expanded {
+            self.expanded_groups.insert(key.clone());
+        } else {
+            self.expanded_groups.remove(&key);
+        }
+        self.build_rows();
+        // Keep the cursor on the group row, even when collapsing from a member
+        if let Some(index) = self
+            .rows
+            .iter()
+            .position(|row| matches!(row, TableRow::Group { key: k, .. } if *k == key))
+        {
+            self.selected = index;
+        }
+        true
+    }
+
+    fn selection_anchor(&self) -> Option<SelectionAnchor> {
+        Some(match self.rows.get(self.selected)? {
+            TableRow::Group { key, .. } => SelectionAnchor::Group(key.clone()),
+            TableRow::Entry(index) | TableRow::Member(index) => {
+                SelectionAnchor::Entry(EntryKey::of(&self.filtered_entries[*index]))
+            }
+        })
+    }
+
+    /// Put the cursor back on the row `anchor` names after `rows` were
+    /// rebuilt: the same entry, or the group it has since been folded into.
+    /// Without a match the cursor keeps its position.
+    fn restore_selection(&mut self, anchor: Option<SelectionAnchor>) {
+        let group = match anchor {
+            Some(SelectionAnchor::Group(key)) => Some(key),
+            Some(SelectionAnchor::Entry(key)) => {
+                let found = self.rows.iter().position(|row| match row {
+                    TableRow::Entry(i) | TableRow::Member(i) => {
+                        EntryKey::of(&self.filtered_entries[*i]) == key
+                    }
+                    TableRow::Group { .. } => false,
+                });
+                if let Some(index) = found {
+                    self.move_selection(index);
+                    return;
+                }
+                self.filtered_entries
+                    .iter()
+                    .find(|e| EntryKey::of(e) == key)
+                    .and_then(GroupKey::of)
+            }
+            None => None,
+        };
+        let Some(group) = group else {
+            return;
+        };
+        // A group that shrank to one entry is drawn as that entry
+        let found = self.rows.iter().position(|row| match row {
+            TableRow::Group { key, .. } => *key == group,
+            TableRow::Entry(i) => GroupKey::of(&self.filtered_entries[*i]).as_ref() == Some(&group),
+            TableRow::Member(_) => false,
+        });
+        if let Some(index) = found {
+            self.move_selection(index);
+        }
+    }
+
+    /// Select row `index`, scrolling by as much as the selection moved.
+    fn move_selection(&mut self, index: usize) {
+        if index >= self.selected {
+            self.table_offset += index - self.selected;
+        } else {
+            self.table_offset = self.table_offset.saturating_sub(self.selected - index);
+        }
+        self.selected = index;
+    }
+
+    /// Whether the selected row is a group heading.
+    pub fn is_group_selected(&self) -> bool {
+        matches!(self.rows.get(self.selected), Some(TableRow::Group { .. }))
+    }
+
+    pub fn next(&mut self) {
+        if !self.rows.is_empty() {
+            self.selected = (self.selected + 1) % self.rows.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.rows.is_empty() {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.rows.len() - 1);
+        }
+    }
+
+    pub fn first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn last(&mut self) {
+        if !self.rows.is_empty() {
+            self.selected = self.rows.len() - 1;
+        }
+    }
+
+    /// Entry of the selected row; for a group, its first entry (same process).
+    pub fn selected_entry(&self) -> Option<&PortEntry> {
+        let index = match self.rows.get(self.selected)? {
+            TableRow::Entry(index) | TableRow::Member(index) => *index,
+            TableRow::Group { first, .. } => *first,
+        };
+        self.filtered_entries.get(index)
     }
 
     pub fn has_multiple_connections(&self) -> bool {
         self.connections.len() > 1
     }
 
-    pub fn active_connection(&self) -> Option<&Connection> {
-        self.connections.get(self.active_connection)
+    pub fn active_connection(&self) -> Option<&Connection> {
+        self.connections.get(self.active_connection)
+    }
+
+    /// Replace the connection list, appending the "All connections" view when
+    /// there is more than one connection to merge.
+    pub fn set_connections(&mut self, mut connections: Vec<Connection>) {
+        let was_aggregate = self.is_aggregate();
+        let selected_aggregate = self.is_aggregate_connection(self.connection_selected);
+        self.aggregate_connection = if connections.len() > 1 {
+            connections.push(Connection::aggregate());
+            Some(connections.len() - 1)
+        } else {
+            None
+        };
+        self.connections = connections;
+        if let Some(index) = self.aggregate_connection {
+            if was_aggregate {
+                self.active_connection = index;
+            }
+            if selected_aggregate {
+                self.connection_selected = index;
+            }
+        }
+    }
+
+    pub fn is_aggregate_connection(&self, index: usize) -> bool {
+        self.aggregate_connection == Some(index)
+    }
+
+    /// True while the merged "All connections" view is active.
+    pub fn is_aggregate(&self) -> bool {
+        self.is_aggregate_connection(self.active_connection)
+    }
+
+    /// Connections merged by the aggregate view, with their indices.
+    pub fn aggregate_sources(&self) -> impl Iterator<Item = (usize, &Connection)> {
+        self.connections
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.is_aggregate_connection(*i))
+    }
+
+    /// Probe result for a connection's remote host; `None` for local-only connections.
+    pub fn health_of(&self, conn: &Connection) -> Option<&Health> {
+        self.connection_health.get(conn.remote_host.as_deref()?)
+    }
+
+    /// Distinct remote hosts across all connections, in connection order.
+    pub fn remote_hosts(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = Vec::new();
+        for host in self
+            .connections
+            .iter()
+            .filter_map(|c| c.remote_host.as_ref())
+        {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+        }
+        hosts
+    }
+
+    pub fn next_connection(&mut self) {
+        if !self.connections.is_empty() {
+            self.active_connection = (self.active_connection + 1) % self.connections.len();
+        }
+    }
+
+    pub fn prev_connection(&mut self) {
+        if !self.connections.is_empty() {
+            self.active_connection = self
+                .active_connection
+                .checked_sub(1)
+                .unwrap_or(self.connections.len() - 1);
+        }
+    }
+
+    pub fn apply_connection(&mut self) {
+        if let Some(conn) = self.connections.get(self.active_connection) {
+            self.remote_host = conn.remote_host.clone();
+            self.docker_target = conn.docker_target.clone();
+            port::docker::set_connection_context(conn.docker_context.as_deref());
+            self.container_ip = None;
+            self.docker_port_mappings.clear();
+            self.docker_vm = None;
+        }
+        self.scan_entries.clear();
+        self.container_stats = None;
+        self.apply_connection_defaults();
+    }
+
+    /// Apply the active connection's filter, refresh, and preset settings,
+    /// falling back to `view_defaults` for anything it leaves unset.
+    pub fn apply_connection_defaults(&mut self) {
+        let defaults = self
+            .active_connection()
+            .map(|c| c.defaults.clone())
+            .unwrap_or_default();
+        self.filter = defaults
+            .default_filter
+            .as_deref()
+            .map_or(self.view_defaults.filter, Filter::from_name);
+        self.auto_refresh = defaults
+            .auto_refresh
+            .unwrap_or(self.view_defaults.auto_refresh);
+        let base_ticks = if self.is_remote() || self.is_aggregate() {
+            self.view_defaults.remote_refresh_ticks
+        } else {
+            self.view_defaults.refresh_ticks
+        };
+        self.refresh_ticks = defaults
+            .refresh_interval
+            .map_or(base_ticks, refresh_ticks_for);
+        self.preset_first();
+        self.apply_filter();
+    }
+
+    pub fn connection_next(&mut self) {
+        if !self.connections.is_empty() {
+            self.connection_selected = (self.connection_selected + 1) % self.connections.len();
+        }
+    }
+
+    pub fn connection_previous(&mut self) {
+        if !self.connections.is_empty() {
+            self.connection_selected = self
+                .connection_selected
+                .checked_sub(1)
+                .unwrap_or(self.connections.len() - 1);
+        }
+    }
+
+    /// Swap two connections, keeping the active connection, the cursor, and
+    /// tracked forwards attached to the connections they belong to.
+    pub fn swap_connections(&mut self, a: usize, b: usize) {
+        if a >= self.connections.len() || b >= self.connections.len() || a == b {
+            return;
+        }
+        self.connections.swap(a, b);
+        let fwd_a = self.ssh_forwards.remove(&a);
+        let fwd_b = self.ssh_forwards.remove(&b);
+        if let Some(map) = fwd_a {
+            self.ssh_forwards.insert(b, map);
+        }
+        if let Some(map) = fwd_b {
+            self.ssh_forwards.insert(a, map);
+        }
+        let swap_index = |i: usize| {
+            if i == a {
+                b
+            } else if i == b {
+                a
+            } else {
+                i
+            }
+        };
+        self.active_connection = swap_index(self.active_connection);
+        self.connection_selected = swap_index(self.connection_selected);
+    }
+
+    pub fn reset_connection_input(&mut self) {
+        self.connection_input = ConnectionInput::new();
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_refresh_ticks_default() {
+        let app = App::new();
+        assert_eq!(app.refresh_ticks, DEFAULT_REFRESH_TICKS);
+    }
+
+    #[test]
+    fn test_should_refresh_uses_refresh_ticks() {
+        let mut app = App::new();
+        app.auto_refresh = true;
+        app.refresh_ticks = 10;
+
+        // tick_count 0 should not refresh (guard)
+        app.tick_count = 0;
+        assert!(!app.should_refresh());
+
+        // tick_count 5 should not refresh
+        app.tick_count = 5;
+        assert!(!app.should_refresh());
+
+        // tick_count 10 should refresh
+        app.tick_count = 10;
+        assert!(app.should_refresh());
+
+        // tick_count 20 should refresh
+        app.tick_count = 20;
+        assert!(app.should_refresh());
+
+        // Paused while a popup is open or a search is typed
+        app.popup = Popup::Details;
+        assert!(!app.should_refresh());
+        app.popup = Popup::None;
+        app.input_mode = InputMode::Search;
+        assert!(!app.should_refresh());
+        app.input_mode = InputMode::Normal;
+
+        // auto_refresh off should not refresh
+        app.auto_refresh = false;
+        app.tick_count = 10;
+        assert!(!app.should_refresh());
+    }
+
+    #[test]
+    fn test_forward_input_empty_is_invalid() {
+        let input = ForwardInput::new();
+        assert!(!input.is_valid());
+        assert!(!input.is_local_port_valid());
+        assert!(!input.is_remote_host_valid());
+        assert!(!input.is_remote_port_valid());
+        assert!(!input.is_ssh_host_valid());
+    }
+
+    #[test]
+    fn test_forward_input_valid() {
+        let input = ForwardInput {
+            local_port: "8080".to_string(),
+            remote_host: "localhost".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "myserver".to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
+            active_field: ForwardField::LocalPort,
+        };
+        assert!(input.is_valid());
+        assert!(input.is_local_port_valid());
+        assert!(input.is_remote_host_valid());
+        assert!(input.is_remote_port_valid());
+        assert!(input.is_ssh_host_valid());
+    }
+
+    #[test]
+    fn test_forward_input_bad_port() {
+        let input = ForwardInput {
+            local_port: "99999".to_string(),
+            remote_host: "localhost".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "myserver".to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
+            active_field: ForwardField::LocalPort,
+        };
+        assert!(!input.is_local_port_valid());
+        assert!(!input.is_valid());
+    }
+
+    #[test]
+    fn test_forward_input_non_numeric_port() {
+        let input = ForwardInput {
+            local_port: "abc".to_string(),
+            remote_host: "localhost".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "myserver".to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
+            active_field: ForwardField::LocalPort,
+        };
+        assert!(!input.is_local_port_valid());
+        assert!(!input.is_valid());
+    }
+
+    #[test]
+    fn test_forward_input_whitespace_host() {
+        let input = ForwardInput {
+            local_port: "8080".to_string(),
+            remote_host: "   ".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "myserver".to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
+            active_field: ForwardField::LocalPort,
+        };
+        assert!(!input.is_remote_host_valid());
+        assert!(!input.is_valid());
+    }
+
+    #[test]
+    fn test_forward_input_from_entry() {
+        let entry = PortEntry {
+            source: PortSource::Local,
+            local_port: 3000,
+            process_name: "node".to_string(),
+            pid: Some(1234),
+            is_open: true,
+            ..PortEntry::default()
+        };
+        let input = ForwardInput::from_entry(&entry);
+        assert_eq!(input.local_port, "3000");
+        assert_eq!(input.remote_host, "localhost");
+        assert_eq!(input.remote_port, "3000");
+        assert_eq!(input.ssh_host, "");
+        assert_eq!(input.active_field, ForwardField::SshHost);
+    }
+
+    #[test]
+    fn test_forward_input_from_entry_with_ssh_host() {
+        let entry = PortEntry {
+            source: PortSource::Ssh,
+            local_port: 9000,
+            remote_host: Some("localhost".to_string()),
+            remote_port: Some(80),
+            process_name: "ssh".to_string(),
+            pid: Some(4567),
+            ssh_host: Some("myserver".to_string()),
+            is_open: true,
+            ..PortEntry::default()
+        };
+        let input = ForwardInput::from_entry(&entry);
+        assert_eq!(input.local_port, "9000");
+        assert_eq!(input.remote_host, "localhost");
+        assert_eq!(input.remote_port, "9000");
+        assert_eq!(input.ssh_host, "myserver");
+        assert_eq!(input.active_field, ForwardField::LocalPort);
+    }
+
+    #[test]
+    fn test_forward_input_to_spec() {
+        let input = ForwardInput {
+            local_port: "8080".to_string(),
+            remote_host: "localhost".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "myserver".to_string(),
+            bind_all: false,
+            ssh_options: String::new(),
+            active_field: ForwardField::LocalPort,
+        };
+        let (spec, host) = input.to_spec().unwrap();
+        assert_eq!(spec, "8080:localhost:80");
+        assert_eq!(host, "myserver");
+        let input = ForwardInput {
+            bind_all: true,
+            ..input
+        };
+        assert_eq!(input.to_spec().unwrap().0, "0.0.0.0:8080:localhost:80");
+        assert!(input.options().is_empty());
+        let input = ForwardInput {
+            ssh_options: "-C  -o ServerAliveInterval=30 ".to_string(),
+            ..input
+        };
+        assert_eq!(input.options(), ["-C", "-o", "ServerAliveInterval=30"]);
+    }
+
+    #[test]
+    fn test_forward_input_to_spec_invalid() {
+        let input = ForwardInput::new();
+        assert!(input.to_spec().is_none());
+    }
+
+    #[test]
+    fn test_connection_input_valid() {
+        let input = ConnectionInput {
+            name: "Test".to_string(),
+            remote_host: String::new(),
+            docker_target: String::new(),
+            active_field: ConnectionField::Name,
+        };
+        assert!(input.is_valid());
+        assert!(input.is_name_valid());
+    }
+
+    #[test]
+    fn test_connection_input_empty_name_invalid() {
+        let input = ConnectionInput::new();
+        assert!(!input.is_valid());
+        assert!(!input.is_name_valid());
+    }
+
+    #[test]
+    fn test_connection_input_whitespace_name_invalid() {
+        let input = ConnectionInput {
+            name: "   ".to_string(),
+            remote_host: String::new(),
+            docker_target: String::new(),
+            active_field: ConnectionField::Name,
+        };
+        assert!(!input.is_valid());
+    }
+
+    #[test]
+    fn test_connection_input_to_connection() {
+        let input = ConnectionInput {
+            name: "My Server".to_string(),
+            remote_host: "user@server".to_string(),
+            docker_target: String::new(),
+            active_field: ConnectionField::Name,
+        };
+        let conn = input.to_connection().unwrap();
+        assert_eq!(conn.name, "My Server");
+        assert_eq!(conn.remote_host, Some("user@server".to_string()));
+        assert!(conn.docker_target.is_none());
+    }
+
+    #[test]
+    fn test_connection_input_to_connection_with_docker() {
+        let input = ConnectionInput {
+            name: "Docker".to_string(),
+            remote_host: "ailab".to_string(),
+            docker_target: "syntopic-dev".to_string(),
+            active_field: ConnectionField::Name,
+        };
+        let conn = input.to_connection().unwrap();
+        assert_eq!(conn.name, "Docker");
+        assert_eq!(conn.remote_host, Some("ailab".to_string()));
+        assert_eq!(conn.docker_target, Some("syntopic-dev".to_string()));
+    }
+
+    #[test]
+    fn test_connection_input_to_connection_invalid() {
+        let input = ConnectionInput::new();
+        assert!(input.to_connection().is_none());
+    }
+
+    #[test]
+    fn test_connection_field_next() {
+        assert_eq!(ConnectionField::Name.next(), ConnectionField::RemoteHost);
+        assert_eq!(
+            ConnectionField::RemoteHost.next(),
+            ConnectionField::DockerTarget
+        );
+        assert_eq!(ConnectionField::DockerTarget.next(), ConnectionField::Name);
+    }
+
+    #[test]
+    fn test_connection_field_prev() {
+        assert_eq!(ConnectionField::Name.prev(), ConnectionField::DockerTarget);
+        assert_eq!(ConnectionField::RemoteHost.prev(), ConnectionField::Name);
+        assert_eq!(
+            ConnectionField::DockerTarget.prev(),
+            ConnectionField::RemoteHost
+        );
+    }
+
+    #[test]
+    fn test_has_multiple_connections() {
+        let mut app = App::new();
+        assert!(!app.has_multiple_connections());
+        app.connections.push(Connection {
+            name: "Test".to_string(),
+            remote_host: None,
+            docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        });
+        assert!(app.has_multiple_connections());
+    }
+
+    #[test]
+    fn test_next_prev_connection() {
+        let mut app = App::new();
+        app.connections.push(Connection {
+            name: "A".to_string(),
+            remote_host: None,
+            docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        });
+        app.connections.push(Connection {
+            name: "B".to_string(),
+            remote_host: None,
+            docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        });
+
+        assert_eq!(app.active_connection, 0);
+        app.next_connection();
+        assert_eq!(app.active_connection, 1);
+        app.next_connection();
+        assert_eq!(app.active_connection, 2);
+        app.next_connection();
+        assert_eq!(app.active_connection, 0); // wraps
+
+        app.prev_connection();
+        assert_eq!(app.active_connection, 2); // wraps
+        app.prev_connection();
+        assert_eq!(app.active_connection, 1);
+    }
+
+    #[test]
+    fn test_apply_connection() {
+        let mut app = App::new();
+        app.connections.push(Connection {
+            name: "Remote".to_string(),
+            remote_host: Some("user@server".to_string()),
+            docker_target: Some("container".to_string()),
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        });
+        app.active_connection = 1;
+        app.apply_connection();
+        assert_eq!(app.remote_host, Some("user@server".to_string()));
+        assert_eq!(app.docker_target, Some("container".to_string()));
+        assert!(app.container_ip.is_none());
+    }
+
+    #[test]
+    fn test_is_remote() {
+        let mut app = App::new();
+        assert!(!app.is_remote());
+        app.remote_host = Some("user@server".to_string());
+        assert!(app.is_remote());
     }
 
-    pub fn next_connection(&mut self) {
-        if !self.connections.is_empty() {
-            self.active_connection = (self.active_connection + 1) % self.connections.len();
-        }
+    #[test]
+    fn test_is_docker_target() {
+        let mut app = App::new();
+        assert!(!app.is_docker_target());
+        app.docker_target = Some("my-container".to_string());
+        assert!(app.is_docker_target());
     }
 
-    pub fn prev_connection(&mut self) {
-        if !self.connections.is_empty() {
-            self.active_connection = self
-                .active_connection
-                .checked_sub(1)
-                .unwrap_or(self.connections.len() - 1);
-        }
+    #[test]
+    fn test_forward_input_for_remote_entry() {
+        let entry = PortEntry {
+            source: PortSource::Local,
+            local_port: 18080,
+            process_name: "python".to_string(),
+            pid: Some(5555),
+            is_open: true,
+            ..PortEntry::default()
+        };
+        let input = ForwardInput::for_remote_entry(&entry, "user@server");
+        assert_eq!(input.local_port, "18080");
+        assert_eq!(input.remote_host, "localhost");
+        assert_eq!(input.remote_port, "18080");
+        assert_eq!(input.ssh_host, "user@server");
+        assert_eq!(input.active_field, ForwardField::LocalPort);
     }
 
-    pub fn apply_connection(&mut self) {
-        if let Some(conn) = self.connections.get(self.active_connection) {
-            self.remote_host = conn.remote_host.clone();
-            self.docker_target = conn.docker_target.clone();
-            self.container_ip = None;
-            self.docker_port_mappings.clear();
-        }
+    #[test]
+    fn test_foreign_owner() {
+        let entry = |source: PortSource, user: &str| PortEntry {
+            source,
+            local_port: 80,
+            process_name: "nginx".to_string(),
+            pid: Some(1),
+            is_open: true,
+            user: Some(user.to_string()),
+            ..PortEntry::default()
+        };
+        let mut app = App::new();
+        assert_eq!(app.foreign_owner(&entry(PortSource::Local, "root")), None);
+        app.local_user = Some("alice".to_string());
+        assert_eq!(
+            app.foreign_owner(&entry(PortSource::Local, "root")),
+            Some("root")
+        );
+        assert_eq!(app.foreign_owner(&entry(PortSource::Local, "alice")), None);
+
+        // Remote rows compare against the ssh login; tunnels stay local
+        app.remote_host = Some("deploy@server".to_string());
+        assert_eq!(app.foreign_owner(&entry(PortSource::Local, "deploy")), None);
+        assert_eq!(
+            app.foreign_owner(&entry(PortSource::Ssh, "deploy")),
+            Some("deploy")
+        );
+        app.remote_host = Some("server".to_string());
+        assert_eq!(app.foreign_owner(&entry(PortSource::Local, "root")), None);
     }
 
-    pub fn connection_next(&mut self) {
-        if !self.connections.is_empty() {
-            self.connection_selected = (self.connection_selected + 1) % self.connections.len();
-        }
+    #[test]
+    fn test_idle_tunnels() {
+        let tunnel = |pid: u32, local_port: u16, established: usize| PortEntry {
+            source: PortSource::Ssh,
+            local_port,
+            remote_host: Some("localhost".to_string()),
+            remote_port: Some(local_port),
+            process_name: "ssh".to_string(),
+            pid: Some(pid),
+            ssh_host: Some("bastion".to_string()),
+            is_open: true,
+            established: Some(established),
+            ..PortEntry::default()
+        };
+        let mut app = App::new();
+        let start = Instant::now();
+        app.entries = vec![
+            tunnel(10, 5432, 0),
+            tunnel(10, 6379, 0),
+            tunnel(20, 8080, 0),
+            tunnel(20, 9090, 1),
+        ];
+        app.track_tunnel_activity(start);
+
+        let minute = Duration::from_secs(60);
+        let later = start + 61 * minute;
+        assert_eq!(app.idle_for(&app.entries[0], start + 59 * minute), None);
+        assert_eq!(app.idle_for(&app.entries[0], later), Some(61 * minute));
+        app.track_tunnel_activity(later);
+        // :9090 is in use, and stopping PID 20 would end it too
+        let ports: Vec<u16> = app
+            .idle_tunnels(later)
+            .iter()
+            .map(|e| e.local_port)
+            .collect();
+        assert_eq!(ports, vec![5432, 6379]);
+
+        // A connection resets the clock; a forward that goes away is forgotten,
+        // and an unused local service is no tunnel
+        app.entries = vec![
+            tunnel(10, 5432, 2),
+            tunnel(20, 8080, 0),
+            PortEntry {
+                source: PortSource::Local,
+                remote_host: None,
+                ..tunnel(30, 3000, 0)
+            },
+        ];
+        app.track_tunnel_activity(start + 30 * minute);
+        assert_eq!(app.idle_for(&app.entries[0], later), None);
+        assert_eq!(app.tunnel_activity.len(), 2);
+
+        app.tunnels.idle_minutes = 0;
+        assert!(app.idle_tunnels(later).is_empty());
     }
 
-    pub fn connection_previous(&mut self) {
-        if !self.connections.is_empty() {
-            self.connection_selected = self
-                .connection_selected
-                .checked_sub(1)
-                .unwrap_or(self.connections.len() - 1);
-        }
+    #[test]
+    fn test_set_entries_adds_registry_placeholders() {
+        let mut app = App::new();
+        app.registry = PortRegistry::parse(
+            r#"
+3000 = "storefront"
+4000 = "admin"
+"#,
+        )
+        .unwrap();
+        let mut open = testing::row(PortSource::Local, 3000);
+        open.is_open = true;
+        open.process_name = "node".to_string();
+        app.set_entries(vec![open]);
+
+        assert_eq!(app.entries.len(), 2);
+        assert_eq!(app.entries[0].local_port, 3000);
+        assert_eq!(app.entries[0].process_name, "node");
+        assert_eq!(app.entries[1].local_port, 4000);
+        assert!(!app.entries[1].is_open);
+
+        app.search_query = "admin".to_string();
+        app.apply_filter();
+        assert_eq!(app.filtered_entries.len(), 1);
+        assert_eq!(app.filtered_entries[0].local_port, 4000);
     }
 
-    pub fn reset_connection_input(&mut self) {
-        self.connection_input = ConnectionInput::new();
+    #[test]
+    fn test_set_entries_adds_compose_placeholders() {
+        let mut app = App::new();
+        let compose =
+            "services:\n  web:\n    ports: [\"3000:3000\"]\n  db:\n    ports: [\"5432:5432\"]\n";
+        app.compose = Some(ComposeFile {
+            path: "compose.yaml".into(),
+            services: crate::compose::parse(compose, &|_| None),
+        });
+        let mut web = testing::row(PortSource::Local, 3000);
+        web.is_open = true;
+        app.set_entries(vec![web]);
+
+        assert_eq!(app.entries.len(), 2);
+        assert_eq!(app.entries[1].local_port, 5432);
+        assert!(!app.entries[1].is_open);
+        assert_eq!(app.compose_service(5432), Some("db"));
+        assert_eq!(app.compose_summary(), Some((1, 2)));
+
+        app.search_query = "db".to_string();
+        app.apply_filter();
+        assert_eq!(app.filtered_entries.len(), 1);
+
+        app.remote_host = Some("user@server".to_string());
+        assert_eq!(app.compose_service(5432), None);
+        assert_eq!(app.compose_summary(), None);
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_insights_of_env_ports() {
+        let mut app = App::new();
+        app.env_ports.ports = vec![crate::env_ports::EnvPort {
+            name: "PORT".to_string(),
+            port: 4000,
+            origin: ".env".to_string(),
+        }];
+        let mut web = testing::row(PortSource::Local, 3000);
+        web.is_open = true;
+        app.set_entries(vec![web]);
+        let insights = app.insights();
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].port, 4000);
+        assert_eq!(
+            insights[0].message,
+            "PORT=4000 in .env but nothing listens on :4000"
+        );
+
+        app.move_insight(5);
+        assert_eq!(app.insight_selected, 0);
+
+        app.remote_host = Some("user@server".to_string());
+        assert!(app.insights().is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_session_stats() {
+        let mut app = App::new();
+        let mut web = testing::row(PortSource::Local, 3000);
+        web.is_open = true;
+        let mut tunnel = testing::row(PortSource::Ssh, 15432);
+        tunnel.is_open = true;
+        app.set_entries(vec![web, tunnel, testing::row(PortSource::Local, 8080)]);
+        let stats = app.session_stats();
+        assert_eq!(
+            stats.per_source,
+            [(PortSource::Local, 2), (PortSource::Ssh, 1)]
+        );
+        assert_eq!((stats.open, stats.closed), (2, 1));
+    }
 
     #[test]
-    fn test_refresh_ticks_default() {
-        let app = App::new();
-        assert_eq!(app.refresh_ticks, DEFAULT_REFRESH_TICKS);
+    fn test_slow_phase_hint() {
+        let mut app = App::new();
+        let collection = |ssh_ms| Collection {
+            entries: Vec::new(),
+            sources: Vec::new(),
+            timings: vec![
+                ("local".to_string(), Duration::from_millis(300)),
+                ("ssh".to_string(), Duration::from_millis(ssh_ms)),
+            ],
+        };
+        app.set_collection(collection(100));
+        assert_eq!(
+            app.slowest_phase(),
+            Some(("local", Duration::from_millis(300)))
+        );
+        assert!(app.status_message.is_none());
+
+        app.remote_host = Some("devbox".to_string());
+        app.set_collection(collection(2900));
+        assert_eq!(
+            app.slowest_phase(),
+            Some(("ssh", Duration::from_millis(2900)))
+        );
+        let (message, _) = app.status_message.take().unwrap();
+        assert!(message.starts_with("ssh took 2.9s: ControlMaster auto"));
+
+        // Only once per phase
+        app.set_collection(collection(3000));
+        assert!(app.status_message.is_none());
     }
 
     #[test]
-    fn test_should_refresh_uses_refresh_ticks() {
+    fn test_jump_to_insight() {
         let mut app = App::new();
-        app.auto_refresh = true;
-        app.refresh_ticks = 10;
+        let mut web = testing::row(PortSource::Local, 3000);
+        web.is_open = true;
+        let mut db = testing::row(PortSource::Local, 5432);
+        db.is_open = true;
+        db.source = PortSource::Docker;
+        db.container_name = Some("db".to_string());
+        db.health = Some("unhealthy".to_string());
+        app.set_entries(vec![web, db]);
+        app.search_query = "3000".to_string();
+        app.apply_filter();
+        assert_eq!(app.filtered_entries.len(), 1);
+
+        app.update(Action::ShowInsights);
+        assert_eq!(app.popup, Popup::Insights);
+        app.jump_to_insight();
+        assert_eq!(app.popup, Popup::None);
+        assert!(app.search_query.is_empty());
+        assert_eq!(app.selected_entry().map(|e| e.local_port), Some(5432));
+    }
 
-        // tick_count 0 should not refresh (guard)
-        app.tick_count = 0;
-        assert!(!app.should_refresh());
+    #[test]
+    fn test_expected_services_start_when_down() {
+        let mut app = App::new();
+        app.expected.merge(crate::expected::parse_procfile(
+            "web: npm run dev -- --port 3000\napi: PORT=4000 node api.js\n",
+            std::path::Path::new("/srv/app"),
+        ));
+        let mut web = testing::row(PortSource::Local, 3000);
+        web.is_open = true;
+        app.set_entries(vec![web]);
+        assert_eq!(app.entries.len(), 2);
+
+        // Running services can't be started again
+        assert!(app.startable_service().is_none());
+        assert_eq!(
+            app.update(Action::RestartProcess),
+            [crate::update::Command::RestartProcess]
+        );
 
-        // tick_count 5 should not refresh
-        app.tick_count = 5;
-        assert!(!app.should_refresh());
+        app.update(Action::Down);
+        assert_eq!(app.startable_service().unwrap().name, "api");
+        assert_eq!(
+            app.update(Action::RestartProcess),
+            [crate::update::Command::StartService]
+        );
+        assert!(app.open_context_menu());
+        assert!(
+            app.context_menu
+                .iter()
+                .any(|item| item.label == "Start api (PORT=4000 node api.js)")
+        );
+    }
 
-        // tick_count 10 should refresh
-        app.tick_count = 10;
-        assert!(app.should_refresh());
+    #[test]
+    fn test_pins_sort_first_and_show_when_closed() {
+        let mut app = App::new();
+        let open = |port: u16| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.is_open = true;
+            entry
+        };
+        app.set_entries(vec![open(3000), open(5173), open(8080)]);
+        app.selected = 2;
+        assert_eq!(app.toggle_pin(), Some((8080, true)));
+        let ports: Vec<u16> = app.filtered_entries.iter().map(|e| e.local_port).collect();
+        assert_eq!(ports, vec![8080, 3000, 5173]);
+        assert_eq!(app.selected, 0);
 
-        // tick_count 20 should refresh
-        app.tick_count = 20;
-        assert!(app.should_refresh());
+        // A pinned port that stops listening stays on top, closed
+        app.set_entries(vec![open(3000), open(5173)]);
+        assert_eq!(app.filtered_entries[0].local_port, 8080);
+        assert!(!app.filtered_entries[0].is_open);
+        assert!(app.pins.contains("Local", 8080));
 
-        // auto_refresh off should not refresh
-        app.auto_refresh = false;
-        app.tick_count = 10;
+        app.selected = 0;
+        assert_eq!(app.toggle_pin(), Some((8080, false)));
+        assert_eq!(app.filtered_entries.len(), 2);
+    }
+
+    #[test]
+    fn test_cycle_sort_by_usage() {
+        let mut app = App::new();
+        let entry = |port: u16, usage: Option<(f32, u64)>| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.usage = usage.map(|(cpu, memory)| Usage { cpu, memory });
+            entry
+        };
+        app.set_entries(vec![
+            entry(3000, Some((0.5, 900))),
+            entry(5173, None),
+            entry(8080, Some((42.0, 100))),
+        ]);
+        let ports =
+            |app: &App| -> Vec<u16> { app.filtered_entries.iter().map(|e| e.local_port).collect() };
+        assert!(!app.shows_usage());
+
+        app.cycle_sort();
+        assert_eq!(app.sort, SortKey::Cpu);
+        assert_eq!(ports(&app), vec![8080, 3000, 5173]);
+        assert!(app.shows_usage());
+
+        app.cycle_sort();
+        assert_eq!(ports(&app), vec![3000, 8080, 5173]);
+
+        app.cycle_sort();
+        assert_eq!(app.sort, SortKey::Port);
+        assert_eq!(ports(&app), vec![3000, 5173, 8080]);
+    }
+
+    #[test]
+    fn test_activity_view() {
+        let mut app = App::new();
+        let entry = |port: u16, established: Option<usize>, cpu: f32| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.established = established;
+            entry.usage = Some(Usage { cpu, memory: 0 });
+            entry
+        };
+        app.set_entries(vec![
+            entry(3000, Some(1), 0.5),
+            entry(5173, None, 90.0),
+            entry(8080, Some(4), 0.0),
+            entry(9000, Some(1), 7.0),
+        ]);
+        app.refresh_ticks = 40;
+        app.tick_count = 8;
         assert!(!app.should_refresh());
+
+        app.toggle_activity();
+        let ports: Vec<u16> = app.filtered_entries.iter().map(|e| e.local_port).collect();
+        assert_eq!(ports, vec![8080, 9000, 3000, 5173]);
+        assert!(app.shows_usage());
+        // Live even with auto-refresh off
+        assert!(!app.auto_refresh);
+        assert!(app.should_refresh());
+
+        // `o` leaves it for the plain sort
+        app.cycle_sort();
+        assert!(!app.activity);
+        assert_eq!(app.sort, SortKey::Port);
+        assert_eq!(app.filtered_entries[0].local_port, 3000);
     }
 
     #[test]
-    fn test_forward_input_empty_is_invalid() {
-        let input = ForwardInput::new();
-        assert!(!input.is_valid());
-        assert!(!input.is_local_port_valid());
-        assert!(!input.is_remote_host_valid());
-        assert!(!input.is_remote_port_valid());
-        assert!(!input.is_ssh_host_valid());
+    fn test_container_stats_fill_docker_entries() {
+        let mut app = App::new();
+        let container = |port: u16, name: &str| {
+            let mut entry = testing::row(PortSource::Docker, port);
+            entry.container_id = Some(format!("{name}0123456789"));
+            entry.container_name = Some(name.to_string());
+            entry
+        };
+        app.set_entries(vec![container(5432, "db"), container(6379, "cache")]);
+        let db = Usage {
+            cpu: 12.5,
+            memory: 1 << 20,
+        };
+        let stats = |host: Option<&str>| ContainerStats {
+            remote_host: host.map(str::to_string),
+            usage: [("db".to_string(), db)].into_iter().collect(),
+        };
+
+        // Read on another connection's host
+        app.apply_container_stats(stats(Some("devbox")));
+        assert!(app.entries[0].usage.is_none());
+
+        app.apply_container_stats(stats(None));
+        assert_eq!(app.entries[0].usage, Some(db));
+        assert_eq!(app.filtered_entries[0].usage, Some(db));
+        assert!(app.entries[1].usage.is_none());
+
+        // A refresh keeps showing the last stats
+        app.set_entries(vec![container(5432, "db")]);
+        assert_eq!(app.entries[0].usage, Some(db));
+        app.apply_connection();
+        assert!(app.container_stats.is_none());
     }
 
     #[test]
-    fn test_forward_input_valid() {
-        let input = ForwardInput {
-            local_port: "8080".to_string(),
-            remote_host: "localhost".to_string(),
-            remote_port: "80".to_string(),
-            ssh_host: "myserver".to_string(),
-            active_field: ForwardField::LocalPort,
+    fn test_ignore_list_hides_entries() {
+        let mut app = App::new();
+        let entry = |port: u16, process: &str| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.process_name = process.to_string();
+            entry
         };
-        assert!(input.is_valid());
-        assert!(input.is_local_port_valid());
-        assert!(input.is_remote_host_valid());
-        assert!(input.is_remote_port_valid());
-        assert!(input.is_ssh_host_valid());
+        app.ignore_ports = [631].into_iter().collect();
+        app.ignore_processes = vec!["rapportd".to_string()];
+        app.set_entries(vec![
+            entry(631, "cupsd"),
+            entry(3000, "node"),
+            entry(49152, "Rapportd"),
+        ]);
+        assert_eq!(app.filtered_entries.len(), 1);
+        assert_eq!(app.hidden_count, 2);
+
+        app.show_hidden = true;
+        app.apply_filter();
+        assert_eq!(app.filtered_entries.len(), 3);
+        assert_eq!(app.hidden_count, 0);
+
+        // Hiding by process name can't be undone from the table
+        app.selected = 2;
+        assert!(app.toggle_hidden().unwrap().contains("ignore_processes"));
+        app.selected = 1;
+        app.toggle_hidden();
+        app.show_hidden = false;
+        app.apply_filter();
+        assert!(app.filtered_entries.is_empty());
+
+        // Pins win over the ignore list
+        app.pins.toggle("Local", 631);
+        app.apply_filter();
+        assert_eq!(app.filtered_entries[0].local_port, 631);
     }
 
     #[test]
-    fn test_forward_input_bad_port() {
-        let input = ForwardInput {
-            local_port: "99999".to_string(),
-            remote_host: "localhost".to_string(),
-            remote_port: "80".to_string(),
-            ssh_host: "myserver".to_string(),
-            active_field: ForwardField::LocalPort,
+    fn test_group_rows_collapse_and_expand() {
+        let mut app = App::new();
+        let entry = |port: u16, pid: u32| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.pid = Some(pid);
+            entry.is_open = true;
+            entry
         };
-        assert!(!input.is_local_port_valid());
-        assert!(!input.is_valid());
+        app.set_entries(vec![
+            entry(3000, 10),
+            entry(3001, 10),
+            entry(5432, 20),
+            entry(3002, 10),
+        ]);
+        let key = GroupKey::Process {
+            connection: None,
+            pid: 10,
+        };
+        assert_eq!(
+            app.rows,
+            vec![
+                TableRow::Group {
+                    key: key.clone(),
+                    first: 0,
+                    count: 3,
+                    expanded: false,
+                },
+                TableRow::Entry(2),
+            ]
+        );
+        assert_eq!(app.selected_entry().unwrap().local_port, 3000);
+
+        app.selected = 0;
+        assert!(app.set_group_expanded(None));
+        assert_eq!(app.rows.len(), 5);
+        assert_eq!(app.rows[3], TableRow::Member(3));
+
+        // Collapsing from a member moves the cursor back to its group
+        app.selected = 3;
+        assert_eq!(app.selected_entry().unwrap().local_port, 3002);
+        assert!(app.set_group_expanded(Some(false)));
+        assert_eq!(app.selected, 0);
+        assert_eq!(app.rows.len(), 2);
+
+        app.selected = 1;
+        assert!(!app.set_group_expanded(Some(true)));
     }
 
     #[test]
-    fn test_forward_input_non_numeric_port() {
-        let input = ForwardInput {
-            local_port: "abc".to_string(),
-            remote_host: "localhost".to_string(),
-            remote_port: "80".to_string(),
-            ssh_host: "myserver".to_string(),
-            active_field: ForwardField::LocalPort,
+    fn test_refresh_keeps_the_selected_entry() {
+        let mut app = App::new();
+        let entry = |port: u16, pid: u32| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.pid = Some(pid);
+            entry.is_open = true;
+            entry
         };
-        assert!(!input.is_local_port_valid());
-        assert!(!input.is_valid());
+        app.set_entries(vec![entry(3000, 10), entry(5432, 20), entry(8080, 30)]);
+        app.selected = 1;
+        app.table_offset = 1;
+
+        // A new port above the cursor moves the row down, and the view with it
+        app.set_entries(vec![
+            entry(2000, 40),
+            entry(3000, 10),
+            entry(5432, 20),
+            entry(8080, 30),
+        ]);
+        assert_eq!(app.selected, 2);
+        assert_eq!(app.table_offset, 2);
+        assert_eq!(app.selected_entry().unwrap().local_port, 5432);
+
+        // The same port under a new process is a different entry
+        app.set_entries(vec![entry(3000, 10), entry(5432, 21), entry(8080, 30)]);
+        assert_eq!(app.selected, 2);
+        assert_eq!(app.selected_entry().unwrap().local_port, 8080);
+
+        // An entry that joins a collapsed group selects the group
+        app.selected = 1;
+        app.set_entries(vec![entry(3000, 10), entry(5432, 21), entry(8080, 21)]);
+        assert_eq!(app.selected, 1);
+        assert!(app.is_group_selected());
+        app.set_entries(vec![entry(1000, 50), entry(5432, 21), entry(8080, 21)]);
+        assert_eq!(app.selected, 1);
+        assert!(app.is_group_selected());
     }
 
     #[test]
-    fn test_forward_input_whitespace_host() {
+    fn test_replace_entry() {
+        let mut app = App::new();
+        let mut node = testing::row(PortSource::Local, 3000);
+        node.pid = Some(10);
+        node.is_open = true;
+        app.set_entries(vec![testing::row(PortSource::Local, 2000), node.clone()]);
+        app.selected = 1;
+
+        let mut closed = node.clone();
+        closed.is_open = false;
+        closed.pid = None;
+        assert!(app.replace_entry(&node, closed));
+        assert_eq!(app.selected, 1);
+        assert!(!app.selected_entry().unwrap().is_open);
+        // Already replaced
+        assert!(!app.replace_entry(&node, node.clone()));
+    }
+
+    #[test]
+    fn test_set_entries_skips_registry_placeholders_in_remote_mode() {
+        let mut app = App::new();
+        app.remote_host = Some("user@server".to_string());
+        app.registry = PortRegistry::parse(r#"3000 = "storefront""#).unwrap();
+        app.set_entries(Vec::new());
+        assert!(app.entries.is_empty());
+    }
+
+    #[test]
+    fn test_forward_input_invalid_field_names() {
+        let input = ForwardInput::new();
+        let names = input.invalid_field_names();
+        assert_eq!(names.len(), 4);
+
         let input = ForwardInput {
             local_port: "8080".to_string(),
-            remote_host: "   ".to_string(),
+            remote_host: "localhost".to_string(),
             remote_port: "80".to_string(),
-            ssh_host: "myserver".to_string(),
+            ssh_host: String::new(),
+            bind_all: false,
+            ssh_options: String::new(),
             active_field: ForwardField::LocalPort,
         };
-        assert!(!input.is_remote_host_valid());
-        assert!(!input.is_valid());
+        let names = input.invalid_field_names();
+        assert_eq!(names, vec!["SSH Host"]);
     }
 
-    #[test]
-    fn test_forward_input_from_entry() {
-        let entry = PortEntry {
-            source: PortSource::Local,
-            local_port: 3000,
-            remote_host: None,
-            remote_port: None,
-            process_name: "node".to_string(),
-            pid: Some(1234),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
-            is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+    #[test]
+    fn test_preset_input_roundtrip() {
+        let preset = Preset {
+            name: "Prod DB".to_string(),
+            key: Some("1".to_string()),
+            local_port: 5432,
+            remote_host: "localhost".to_string(),
+            remote_port: 5432,
+            ssh_host: "bastion".to_string(),
+            group: Some("prod".to_string()),
+            ssh_options: vec!["-C".to_string()],
         };
-        let input = ForwardInput::from_entry(&entry);
-        assert_eq!(input.local_port, "3000");
-        assert_eq!(input.remote_host, "localhost");
-        assert_eq!(input.remote_port, "3000");
-        assert_eq!(input.ssh_host, "");
-        assert_eq!(input.active_field, ForwardField::SshHost);
+        let input = PresetInput::from_preset(&preset);
+        assert!(input.is_valid());
+        let out = input.to_preset().unwrap();
+        assert_eq!(out.name, "Prod DB");
+        assert_eq!(out.key.as_deref(), Some("1"));
+        assert_eq!(out.local_port, 5432);
+        assert_eq!(out.ssh_host, "bastion");
+        assert_eq!(out.group.as_deref(), Some("prod"));
+        assert_eq!(out.ssh_options, ["-C"]);
     }
 
     #[test]
-    fn test_forward_input_from_entry_with_ssh_host() {
+    fn test_preset_input_validation() {
+        let mut input = PresetInput::new();
+        assert_eq!(
+            input.invalid_field_names(),
+            vec![
+                "Name",
+                "Local Port",
+                "Remote Host",
+                "Remote Port",
+                "SSH Host"
+            ]
+        );
+        input.name = "web".to_string();
+        input.local_port = "8080".to_string();
+        input.remote_host = "localhost".to_string();
+        input.remote_port = "80".to_string();
+        input.ssh_host = "server".to_string();
+        assert!(input.is_valid());
+        assert!(input.to_preset().unwrap().key.is_none());
+
+        input.key = "ab".to_string();
+        assert_eq!(input.invalid_field_names(), vec!["Key"]);
+        assert!(input.to_preset().is_none());
+
+        input.key = "a".to_string();
+        input.local_port = "0".to_string();
+        assert_eq!(input.invalid_field_names(), vec!["Local Port"]);
+    }
+
+    #[test]
+    fn test_preset_input_from_entry() {
         let entry = PortEntry {
             source: PortSource::Ssh,
             local_port: 9000,
-            remote_host: Some("localhost".to_string()),
-            remote_port: Some(80),
+            remote_host: Some("db.internal".to_string()),
+            remote_port: Some(5432),
             process_name: "ssh".to_string(),
             pid: Some(4567),
-            container_id: None,
-            container_name: None,
-            ssh_host: Some("myserver".to_string()),
+            ssh_host: Some("bastion".to_string()),
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            ..PortEntry::default()
         };
-        let input = ForwardInput::from_entry(&entry);
+        let input = PresetInput::from_entry(&entry, Some("ignored"));
         assert_eq!(input.local_port, "9000");
+        assert_eq!(input.remote_host, "db.internal");
+        assert_eq!(input.remote_port, "5432");
+        assert_eq!(input.ssh_host, "bastion");
+        assert_eq!(input.active_field, PresetField::Name);
+
+        let entry = PortEntry {
+            source: PortSource::Local,
+            local_port: 3000,
+            process_name: "node".to_string(),
+            pid: Some(1234),
+            is_open: true,
+            ..PortEntry::default()
+        };
+        let input = PresetInput::from_entry(&entry, Some("user@server"));
         assert_eq!(input.remote_host, "localhost");
-        assert_eq!(input.remote_port, "9000");
-        assert_eq!(input.ssh_host, "myserver");
-        assert_eq!(input.active_field, ForwardField::LocalPort);
+        assert_eq!(input.remote_port, "3000");
+        assert_eq!(input.ssh_host, "user@server");
     }
 
     #[test]
-    fn test_forward_input_to_spec() {
-        let input = ForwardInput {
-            local_port: "8080".to_string(),
-            remote_host: "localhost".to_string(),
-            remote_port: "80".to_string(),
-            ssh_host: "myserver".to_string(),
-            active_field: ForwardField::LocalPort,
+    fn test_connection_input_from_connection() {
+        let conn = Connection {
+            name: "Lab".to_string(),
+            remote_host: Some("ailab".to_string()),
+            docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
         };
-        let (spec, host) = input.to_spec().unwrap();
-        assert_eq!(spec, "8080:localhost:80");
-        assert_eq!(host, "myserver");
+        let input = ConnectionInput::from_connection(&conn);
+        assert_eq!(input.name, "Lab");
+        assert_eq!(input.remote_host, "ailab");
+        assert_eq!(input.docker_target, "");
+        let out = input.to_connection().unwrap();
+        assert_eq!(out.remote_host.as_deref(), Some("ailab"));
+        assert!(out.docker_target.is_none());
     }
 
     #[test]
-    fn test_forward_input_to_spec_invalid() {
-        let input = ForwardInput::new();
-        assert!(input.to_spec().is_none());
-    }
+    fn test_swap_connections_keeps_state_attached() {
+        let mut app = App::new();
+        for name in ["A", "B"] {
+            app.connections.push(Connection {
+                name: name.to_string(),
+                remote_host: Some(name.to_lowercase()),
+                docker_target: None,
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
+            });
+        }
+        app.ssh_forwards.insert(1, HashMap::from([(3000, 3000)]));
+        app.active_connection = 1;
+        app.connection_selected = 1;
 
-    #[test]
-    fn test_connection_input_valid() {
-        let input = ConnectionInput {
-            name: "Test".to_string(),
-            remote_host: String::new(),
-            docker_target: String::new(),
-            active_field: ConnectionField::Name,
-        };
-        assert!(input.is_valid());
-        assert!(input.is_name_valid());
+        app.swap_connections(1, 2);
+        assert_eq!(app.connections[1].name, "B");
+        assert_eq!(app.connections[2].name, "A");
+        assert_eq!(app.active_connection, 2);
+        assert_eq!(app.connection_selected, 2);
+        assert!(!app.ssh_forwards.contains_key(&1));
+        assert_eq!(app.ssh_forwards[&2][&3000], 3000);
+
+        // Out of range is a no-op
+        app.swap_connections(2, 5);
+        assert_eq!(app.connections[2].name, "A");
     }
 
     #[test]
-    fn test_connection_input_empty_name_invalid() {
-        let input = ConnectionInput::new();
-        assert!(!input.is_valid());
-        assert!(!input.is_name_valid());
+    fn test_remote_hosts_and_health() {
+        let mut app = App::new();
+        for (name, host) in [("A", "shared"), ("B", "shared"), ("C", "other")] {
+            app.connections.push(Connection {
+                name: name.to_string(),
+                remote_host: Some(host.to_string()),
+                docker_target: None,
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
+            });
+        }
+        assert_eq!(app.remote_hosts(), vec!["shared", "other"]);
+
+        app.connection_health
+            .insert("shared".to_string(), Health::AuthFailed);
+        assert_eq!(app.health_of(&app.connections[0]), None);
+        assert_eq!(
+            app.health_of(&app.connections[1]),
+            Some(&Health::AuthFailed)
+        );
+        assert_eq!(
+            app.health_of(&app.connections[2]),
+            Some(&Health::AuthFailed)
+        );
+        assert_eq!(app.health_of(&app.connections[3]), None);
     }
 
     #[test]
-    fn test_connection_input_whitespace_name_invalid() {
-        let input = ConnectionInput {
-            name: "   ".to_string(),
-            remote_host: String::new(),
-            docker_target: String::new(),
-            active_field: ConnectionField::Name,
-        };
-        assert!(!input.is_valid());
+    fn test_set_connections_appends_aggregate() {
+        let mut app = App::new();
+        app.set_connections(vec![Connection::local()]);
+        assert_eq!(app.connections.len(), 1);
+        assert!(app.aggregate_connection.is_none());
+
+        let prod = connection_with_defaults("Prod", ConnectionDefaults::default());
+        app.set_connections(vec![Connection::local(), prod.clone()]);
+        assert_eq!(app.connections.len(), 3);
+        assert_eq!(app.aggregate_connection, Some(2));
+        assert_eq!(app.connections[2].name, "All connections");
+        let sources: Vec<usize> = app.aggregate_sources().map(|(i, _)| i).collect();
+        assert_eq!(sources, vec![0, 1]);
+
+        // The aggregate view stays active when a connection is added before it
+        app.active_connection = 2;
+        app.connection_selected = 2;
+        assert!(app.is_aggregate());
+        let lab = connection_with_defaults("Lab", ConnectionDefaults::default());
+        app.set_connections(vec![Connection::local(), prod, lab]);
+        assert_eq!(app.aggregate_connection, Some(3));
+        assert_eq!(app.active_connection, 3);
+        assert_eq!(app.connection_selected, 3);
+        assert!(app.is_aggregate());
+    }
+
+    fn connection_with_defaults(name: &str, defaults: ConnectionDefaults) -> Connection {
+        Connection {
+            name: name.to_string(),
+            remote_host: Some(format!("{}-host", name.to_lowercase())),
+            docker_target: None,
+            docker_context: None,
+            defaults,
+        }
     }
 
     #[test]
-    fn test_connection_input_to_connection() {
-        let input = ConnectionInput {
-            name: "My Server".to_string(),
-            remote_host: "user@server".to_string(),
-            docker_target: String::new(),
-            active_field: ConnectionField::Name,
+    fn test_apply_connection_defaults() {
+        let mut app = App::new();
+        app.view_defaults = ViewDefaults {
+            filter: Filter::All,
+            auto_refresh: true,
+            refresh_ticks: 8,
+            remote_refresh_ticks: 40,
         };
-        let conn = input.to_connection().unwrap();
-        assert_eq!(conn.name, "My Server");
-        assert_eq!(conn.remote_host, Some("user@server".to_string()));
-        assert!(conn.docker_target.is_none());
+        app.connections.push(connection_with_defaults(
+            "Bastion",
+            ConnectionDefaults {
+                default_filter: Some("ssh".to_string()),
+                auto_refresh: None,
+                refresh_interval: Some(30),
+                presets: None,
+            },
+        ));
+
+        app.active_connection = 1;
+        app.apply_connection();
+        assert_eq!(app.filter, Filter::Ssh);
+        assert!(app.auto_refresh);
+        assert_eq!(app.refresh_ticks, 120);
+
+        // Back to Local: global defaults apply again
+        app.active_connection = 0;
+        app.apply_connection();
+        assert_eq!(app.filter, Filter::All);
+        assert_eq!(app.refresh_ticks, 8);
+
+        // Remote connections without their own interval refresh less often
+        app.connections.push(connection_with_defaults(
+            "Lab",
+            ConnectionDefaults::default(),
+        ));
+        app.active_connection = 2;
+        app.apply_connection();
+        assert_eq!(app.refresh_ticks, 40);
     }
 
     #[test]
-    fn test_connection_input_to_connection_with_docker() {
-        let input = ConnectionInput {
-            name: "Docker".to_string(),
-            remote_host: "ailab".to_string(),
-            docker_target: "syntopic-dev".to_string(),
-            active_field: ConnectionField::Name,
+    fn test_connection_preset_visibility() {
+        let preset = |name: &str, key: Option<&str>| Preset {
+            name: name.to_string(),
+            key: key.map(str::to_string),
+            local_port: 5432,
+            remote_host: "localhost".to_string(),
+            remote_port: 5432,
+            ssh_host: "bastion".to_string(),
+            group: None,
+            ssh_options: Vec::new(),
         };
-        let conn = input.to_connection().unwrap();
-        assert_eq!(conn.name, "Docker");
-        assert_eq!(conn.remote_host, Some("ailab".to_string()));
-        assert_eq!(conn.docker_target, Some("syntopic-dev".to_string()));
-    }
+        let mut app = App::new();
+        app.presets = vec![
+            preset("local db", Some("1")),
+            preset("prod db", Some("2")),
+            preset("prod redis", None),
+        ];
+        app.connections.push(connection_with_defaults(
+            "Prod",
+            ConnectionDefaults {
+                presets: Some(vec!["prod db".to_string(), "prod redis".to_string()]),
+                ..ConnectionDefaults::default()
+            },
+        ));
 
-    #[test]
-    fn test_connection_input_to_connection_invalid() {
-        let input = ConnectionInput::new();
-        assert!(input.to_connection().is_none());
+        assert_eq!(app.visible_preset_indices(), vec![0, 1, 2]);
+
+        app.active_connection = 1;
+        app.apply_connection();
+        assert_eq!(app.visible_preset_indices(), vec![1, 2]);
+        assert_eq!(app.preset_selected, 1);
+        app.preset_next();
+        assert_eq!(app.preset_selected, 2);
+        app.preset_next();
+        assert_eq!(app.preset_selected, 1);
+        app.preset_previous();
+        assert_eq!(app.preset_selected, 2);
+        assert_eq!(app.preset_for_key('1'), None);
+        assert_eq!(app.preset_for_key('2'), Some(1));
+
+        app.preset_selected = 0;
+        assert!(app.selected_preset().is_none());
     }
 
     #[test]
-    fn test_connection_field_next() {
-        assert_eq!(ConnectionField::Name.next(), ConnectionField::RemoteHost);
+    fn test_error_report_and_scroll() {
+        let err = anyhow::Error::new(CommandError {
+            command: "ssh lab lsof -i".to_string(),
+            exit_code: Some(255),
+            stderr: "line one\nline two".to_string(),
+        })
+        .context("Port scan on lab failed");
+        let report = ErrorReport::new("Refresh failed", &err);
+        assert_eq!(report.command.as_deref(), Some("ssh lab lsof -i"));
+        assert_eq!(report.exit_code, Some(255));
         assert_eq!(
-            ConnectionField::RemoteHost.next(),
-            ConnectionField::DockerTarget
+            report.summary,
+            "Port scan on lab failed: exited with code 255: line one"
         );
-        assert_eq!(ConnectionField::DockerTarget.next(), ConnectionField::Name);
+        assert_eq!(report.line_count(), 8);
+
+        let plain = ErrorReport::new("Lab", &anyhow::anyhow!("no route"));
+        assert!(plain.command.is_none());
+        assert_eq!(plain.line_count(), 5);
+
+        let mut app = App::new();
+        app.report_errors(vec![report, plain]);
+        assert!(app.status_message.as_ref().unwrap().0.contains("(+1 more)"));
+        app.scroll_errors(100);
+        assert_eq!(app.error_scroll, 12);
+        app.scroll_errors(-3);
+        assert_eq!(app.error_scroll, 9);
+        app.report_errors(Vec::new());
+        assert_eq!(app.error_scroll, 0);
     }
 
     #[test]
-    fn test_connection_field_prev() {
-        assert_eq!(ConnectionField::Name.prev(), ConnectionField::DockerTarget);
-        assert_eq!(ConnectionField::RemoteHost.prev(), ConnectionField::Name);
+    fn test_established_ages() {
+        let mut app = App::new();
+        let mut entry = testing::row(PortSource::Local, 5432);
+        entry.is_open = true;
+        app.set_entries(vec![entry]);
+        let connection = |peer: &str| Established {
+            local_port: 5432,
+            peer: peer.to_string(),
+            pid: Some(812),
+            process: Some("postgres".to_string()),
+        };
+        let list = |port, peers: &[&str]| EstablishedList {
+            port,
+            connections: Ok(peers.iter().map(|p| connection(p)).collect()),
+        };
+        let start = Instant::now();
+        let later = start + Duration::from_secs(30);
+
+        app.apply_established(list(5432, &["10.0.0.2:50000"]), start);
+        assert!(app.established.is_none(), "popup closed");
+
+        app.open_established();
+        app.apply_established(list(6379, &["10.0.0.2:50000"]), start);
+        assert!(app.established.is_none(), "stale entry");
+        app.apply_established(list(5432, &["10.0.0.2:50000"]), start);
         assert_eq!(
-            ConnectionField::DockerTarget.prev(),
-            ConnectionField::RemoteHost
+            app.established_age(5432, "10.0.0.2:50000", later),
+            Some((Duration::from_secs(30), true))
+        );
+
+        // A new client is timed from when it showed up; a closed one is forgotten
+        app.apply_established(list(5432, &["10.0.0.3:41000"]), later);
+        let end = later + Duration::from_secs(5);
+        assert_eq!(
+            app.established_age(5432, "10.0.0.3:41000", end),
+            Some((Duration::from_secs(5), false))
         );
+        assert_eq!(app.established_age(5432, "10.0.0.2:50000", end), None);
     }
 
     #[test]
-    fn test_has_multiple_connections() {
+    fn test_raw_details_follow_the_selection() {
         let mut app = App::new();
-        assert!(!app.has_multiple_connections());
-        app.connections.push(Connection {
-            name: "Test".to_string(),
-            remote_host: None,
-            docker_target: None,
-        });
-        assert!(app.has_multiple_connections());
+        let open = |port: u16| {
+            let mut entry = testing::row(PortSource::Local, port);
+            entry.is_open = true;
+            entry
+        };
+        app.set_entries(vec![open(3000), open(5173)]);
+        let raw = |port| RawDetails {
+            port,
+            sections: Vec::new(),
+        };
+
+        app.apply_raw_details(raw(3000));
+        assert!(app.details_raw.is_none(), "popup closed");
+
+        app.open_details();
+        app.apply_raw_details(raw(5173));
+        assert!(app.details_raw.is_none(), "stale entry");
+        app.apply_raw_details(raw(3000));
+        assert_eq!(app.details_raw, Some(raw(3000)));
+
+        app.scroll_details(10, 4);
+        assert_eq!(app.details_scroll, 4);
+        app.scroll_details(-1, 4);
+        assert_eq!(app.details_scroll, 3);
+        app.open_details();
+        assert_eq!(app.details_scroll, 0);
+        assert!(app.details_raw.is_none());
     }
 
     #[test]
-    fn test_next_prev_connection() {
+    fn test_context_menu_offers_applicable_actions() {
         let mut app = App::new();
-        app.connections.push(Connection {
-            name: "A".to_string(),
-            remote_host: None,
-            docker_target: None,
-        });
-        app.connections.push(Connection {
-            name: "B".to_string(),
-            remote_host: None,
-            docker_target: None,
-        });
-
-        assert_eq!(app.active_connection, 0);
-        app.next_connection();
-        assert_eq!(app.active_connection, 1);
-        app.next_connection();
-        assert_eq!(app.active_connection, 2);
-        app.next_connection();
-        assert_eq!(app.active_connection, 0); // wraps
+        let mut entry = testing::row(PortSource::Local, 3000);
+        entry.is_open = true;
+        entry.pid = Some(42);
+        app.set_entries(vec![entry]);
+        let action = |name: &str, command: &str, sources: Vec<PortSource>| CustomAction {
+            name: name.to_string(),
+            command: command.to_string(),
+            sources,
+            key: None,
+            background: false,
+        };
+        app.custom_actions = vec![
+            action("logs", "docker logs {container_id}", Vec::new()),
+            action("trace", "strace -p {pid}", vec![PortSource::Local]),
+            action("curl", "curl localhost:{port}", Vec::new()),
+            action("exec", "docker exec {port}", vec![PortSource::Docker]),
+        ];
+
+        assert!(app.open_context_menu());
+        assert_eq!(app.popup, Popup::ContextMenu);
+        let labels: Vec<&str> = app.context_menu.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Show details",
+                "Who is connected",
+                "Show output",
+                "Kill",
+                "Restart process",
+                "New SSH forward",
+                "Pin",
+                "Copy localhost:3000",
+                "Open in browser",
+                "trace",
+                "curl",
+            ]
+        );
+        let trace = &app.context_menu[9];
+        assert!(matches!(trace.action, Action::RunCustom(1)));
+        assert_eq!(trace.command.as_deref(), Some("strace -p 42"));
+        for _ in 0..20 {
+            app.context_menu_next();
+        }
+        assert_eq!(app.context_selected, 10);
 
-        app.prev_connection();
-        assert_eq!(app.active_connection, 2); // wraps
-        app.prev_connection();
-        assert_eq!(app.active_connection, 1);
+        assert_eq!(app.prepare_action(1).unwrap().command, "strace -p 42");
+        assert_eq!(
+            app.prepare_action(0).unwrap_err(),
+            "logs: no {container_id} for this entry"
+        );
+        assert!(app.prepare_action(3).is_err());
     }
 
     #[test]
-    fn test_apply_connection() {
+    fn test_context_menu_for_remote_container() {
         let mut app = App::new();
-        app.connections.push(Connection {
-            name: "Remote".to_string(),
-            remote_host: Some("user@server".to_string()),
-            docker_target: Some("container".to_string()),
-        });
-        app.active_connection = 1;
-        app.apply_connection();
-        assert_eq!(app.remote_host, Some("user@server".to_string()));
-        assert_eq!(app.docker_target, Some("container".to_string()));
-        assert!(app.container_ip.is_none());
+        app.remote_host = Some("deploy@lab".to_string());
+        let mut entry = testing::row(PortSource::Docker, 5432);
+        entry.is_open = true;
+        app.set_entries(vec![entry.clone()]);
+
+        assert!(app.open_context_menu());
+        let actions: Vec<Action> = app.context_menu.iter().map(|i| i.action).collect();
+        assert!(actions.contains(&Action::QuickForward));
+        assert!(actions.contains(&Action::RestartContainer));
+        assert!(!actions.contains(&Action::TmuxShell));
+        assert_eq!(app.entry_address(&entry), "lab:5432");
+
+        app.in_tmux = true;
+        assert!(app.open_context_menu());
+        let actions: Vec<Action> = app.context_menu.iter().map(|i| i.action).collect();
+        assert!(actions.contains(&Action::TmuxShell));
+        assert!(actions.contains(&Action::TmuxForward));
+        assert_eq!(app.tmux_shell_command().unwrap(), "ssh 'deploy@lab'");
+        app.tmux.shell_command = Some("ssh -t {host} top".to_string());
+        assert_eq!(app.tmux_shell_command().unwrap(), "ssh -t 'deploy@lab' top");
+
+        entry.forwarded_port = Some(15432);
+        assert_eq!(app.entry_address(&entry), "localhost:15432");
     }
 
     #[test]
-    fn test_is_remote() {
+    fn test_export_path() {
+        use chrono::TimeZone;
         let mut app = App::new();
-        assert!(!app.is_remote());
-        app.remote_host = Some("user@server".to_string());
-        assert!(app.is_remote());
+        let at = chrono::Local
+            .with_ymd_and_hms(2026, 10, 16, 9, 30, 0)
+            .unwrap();
+        assert_eq!(
+            app.export_path(Format::Markdown, at),
+            PathBuf::from("quay-local-20261016-093000.md")
+        );
+        app.set_connections(vec![
+            Connection::local(),
+            Connection {
+                name: "Staging Box".to_string(),
+                remote_host: Some("staging".to_string()),
+                ..Connection::local()
+            },
+        ]);
+        app.active_connection = 1;
+        assert_eq!(
+            app.export_path(Format::Csv, at),
+            PathBuf::from("quay-staging-box-20261016-093000.csv")
+        );
     }
 
     #[test]
-    fn test_is_docker_target() {
+    fn test_save_snapshot() {
         let mut app = App::new();
-        assert!(!app.is_docker_target());
-        app.docker_target = Some("my-container".to_string());
-        assert!(app.is_docker_target());
+        app.set_entries(vec![testing::row(PortSource::Local, 3000)]);
+        assert!(app.save_snapshot("work").is_err());
+
+        let mut tunnel = testing::row(PortSource::Ssh, 5432);
+        tunnel.remote_host = Some("localhost".to_string());
+        tunnel.remote_port = Some(5432);
+        tunnel.ssh_host = Some("prod".to_string());
+        app.set_entries(vec![tunnel.clone(), tunnel]);
+        assert_eq!(app.save_snapshot("work"), Ok(1));
+        assert_eq!(app.save_snapshot("home"), Ok(1));
+        assert_eq!(app.snapshot_selected, 0, "sorted by name");
+        app.snapshot_next();
+        let (name, snapshot) = app.selected_snapshot().unwrap();
+        assert_eq!(name, "work");
+        assert_eq!(snapshot.forward[0].spec, "5432:localhost:5432");
+        assert!(snapshot.forward[0].is_running(&app.entries));
     }
 
     #[test]
-    fn test_forward_input_for_remote_entry() {
-        let entry = PortEntry {
-            source: PortSource::Local,
-            local_port: 18080,
-            remote_host: None,
-            remote_port: None,
-            process_name: "python".to_string(),
-            pid: Some(5555),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
-            is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+    fn test_scan_results_merge() {
+        let mut local = testing::row(PortSource::Local, 3000);
+        local.is_open = true;
+        let mut app = App::new();
+        app.set_entries(vec![local.clone()]);
+        app.set_scan_results(&[3000, 8080]);
+        let sources = |app: &App| -> Vec<(u16, PortSource)> {
+            app.entries
+                .iter()
+                .map(|e| (e.local_port, e.source.clone()))
+                .collect()
         };
-        let input = ForwardInput::for_remote_entry(&entry, "user@server");
-        assert_eq!(input.local_port, "18080");
-        assert_eq!(input.remote_host, "localhost");
-        assert_eq!(input.remote_port, "18080");
-        assert_eq!(input.ssh_host, "user@server");
-        assert_eq!(input.active_field, ForwardField::LocalPort);
+        assert_eq!(
+            sources(&app),
+            vec![(3000, PortSource::Local), (8080, PortSource::Scan)]
+        );
+
+        // Kept across refreshes, hidden while a collector lists the port itself
+        app.set_entries(vec![local.clone()]);
+        assert_eq!(app.entries.len(), 2);
+        local.local_port = 8080;
+        app.set_entries(vec![local]);
+        assert_eq!(
+            sources(&app),
+            vec![(3000, PortSource::Scan), (8080, PortSource::Local)]
+        );
+
+        app.set_scan_results(&[]);
+        assert!(app.scan_entries.is_empty());
     }
 
     #[test]
-    fn test_forward_input_invalid_field_names() {
-        let input = ForwardInput::new();
-        let names = input.invalid_field_names();
-        assert_eq!(names.len(), 4);
+    fn test_status_history_and_sticky_errors() {
+        let mut app = App::new();
+        app.set_status("Refreshed");
+        app.set_error("Save failed: disk full");
+        for _ in 0..=STATUS_MESSAGE_TICKS {
+            app.tick();
+        }
+        assert!(app.status_message.is_some(), "errors stay until dismissed");
+        assert!(app.dismiss_error());
+        assert!(app.status_message.is_none());
+        assert!(!app.dismiss_error());
+
+        app.set_status("Pinned :3000");
+        for _ in 0..=STATUS_MESSAGE_TICKS {
+            app.tick();
+        }
+        assert!(app.status_message.is_none());
+        assert!(!app.dismiss_error(), "info messages are not sticky");
 
-        let input = ForwardInput {
-            local_port: "8080".to_string(),
-            remote_host: "localhost".to_string(),
-            remote_port: "80".to_string(),
-            ssh_host: String::new(),
-            active_field: ForwardField::LocalPort,
-        };
-        let names = input.invalid_field_names();
-        assert_eq!(names, vec!["SSH Host"]);
+        let messages: Vec<_> = app.status_history.iter().map(|e| e.is_error).collect();
+        assert_eq!(messages, vec![false, true, false]);
+        for i in 0..STATUS_HISTORY_LEN {
+            app.set_status(&format!("message {i}"));
+        }
+        assert_eq!(app.status_history.len(), STATUS_HISTORY_LEN);
+        assert_eq!(app.status_history[0].message, "message 0");
+
+        app.scroll_messages(isize::MAX);
+        assert_eq!(app.messages_scroll, STATUS_HISTORY_LEN - 1);
     }
 }
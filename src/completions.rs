@@ -0,0 +1,108 @@
+//! Shell completions (`quay completions <shell>`).
+//!
+//! The installed script calls back into `quay` on every completion, so preset
+//! names and groups, snapshot names, connection hosts, and scenario names
+//! always reflect the current config.
+
+use crate::connection::Connections;
+use crate::dev::{builtin_scenarios, load_scenarios};
+use crate::preset::{self, Presets};
+use crate::snapshot::Snapshots;
+use crate::state::StateFile;
+use clap_complete::env::Shells;
+use clap_complete::{CompletionCandidate, Shell};
+use std::io::{self, Write};
+
+/// Environment variable the completion script sets when calling back into quay.
+pub const COMPLETE_ENV: &str = "COMPLETE";
+
+/// Write the registration script for `shell`.
+pub fn write_script(shell: Shell, out: &mut dyn Write) -> io::Result<()> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .ok_or_else(|| io::Error::other(format!("completions are not supported for {name}")))?;
+    completer.write_registration(COMPLETE_ENV, "quay", "quay", "quay", out)
+}
+
+/// Preset group names, for `quay up` / `quay down`.
+pub fn preset_groups() -> Vec<CompletionCandidate> {
+    let presets = Presets::load().unwrap_or_default();
+    preset::groups(&presets.preset)
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Preset names, for `quay preset up` / `quay preset down`.
+pub fn preset_names() -> Vec<CompletionCandidate> {
+    let presets = Presets::load().unwrap_or_default();
+    presets
+        .preset
+        .into_iter()
+        .map(|p| {
+            let help = format!("{} via {}", p.spec(), p.ssh_host);
+            CompletionCandidate::new(p.name).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Snapshot names, for `quay snapshot restore` / `quay snapshot delete`.
+pub fn snapshot_names() -> Vec<CompletionCandidate> {
+    let stored = Snapshots::load().unwrap_or_default();
+    stored
+        .snapshots
+        .into_iter()
+        .map(|(name, snapshot)| {
+            let help = format!("{} forward(s)", snapshot.forward.len());
+            CompletionCandidate::new(name).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Remote hosts of saved connections, labelled with the connection name.
+pub fn connection_hosts() -> Vec<CompletionCandidate> {
+    let connections = Connections::load().unwrap_or_default();
+    connections
+        .all_with_local()
+        .iter()
+        .filter_map(|c| {
+            let host = c.remote_host.as_deref()?;
+            Some(CompletionCandidate::new(host).help(Some(c.name.clone().into())))
+        })
+        .collect()
+}
+
+/// Built-in `quay dev scenario` names.
+pub fn scenario_names() -> Vec<CompletionCandidate> {
+    load_scenarios()
+        .unwrap_or_else(|_| builtin_scenarios())
+        .into_iter()
+        .map(|s| CompletionCandidate::new(s.name).help(Some(s.description.into())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_call_back_into_quay() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = Vec::new();
+            write_script(shell, &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains(COMPLETE_ENV), "{shell}: {script}");
+        }
+    }
+
+    #[test]
+    fn test_scenario_names() {
+        let names: Vec<_> = scenario_names()
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert!(names.contains(&"web".to_string()));
+    }
+}
@@ -0,0 +1,358 @@
+//! Expected ports from a Docker Compose file.
+//!
+//! Only the `ports` of each service are read, so a small line-based reader
+//! stands in for a YAML parser: short (`"127.0.0.1:8080:80"`), long
+//! (`published: 8080`), and flow (`ports: ["8080:80"]`) syntax, port ranges,
+//! and `${VAR:-default}` interpolation from the environment and `.env`.
+//! Anchors, merge keys, and `extends` are not followed.
+
+use crate::env_ports;
+use anyhow::{Context, bail};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Names `docker compose` looks for, in its order of preference.
+pub const FILE_NAMES: [&str; 4] = [
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+/// Longest published range expanded; larger ones are skipped.
+const MAX_RANGE: usize = 1024;
+
+/// A service and the host ports it publishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeService {
+    pub name: String,
+    pub ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ComposeFile {
+    pub path: PathBuf,
+    /// Services that publish at least one port, in file order.
+    pub services: Vec<ComposeService>,
+}
+
+impl ComposeFile {
+    /// `path`, or the compose file of the current directory when `None`.
+    pub fn resolve(path: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+        if let Some(path) = path {
+            return Ok(path);
+        }
+        let dir = std::env::current_dir().context("cannot read the current directory")?;
+        match find(&dir) {
+            Some(path) => Ok(path),
+            None => bail!(
+                "no compose file in {} (looked for {})",
+                dir.display(),
+                FILE_NAMES.join(", ")
+            ),
+        }
+    }
+
+    /// Read `path`, interpolating variables from the environment, then from
+    /// the `.env` next to it.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let dotenv: HashMap<String, String> = path
+            .parent()
+            .and_then(|dir| std::fs::read_to_string(dir.join(".env")).ok())
+            .map(|content| env_ports::parse_dotenv(&content).into_iter().collect())
+            .unwrap_or_default();
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .or_else(|| dotenv.get(name).cloned())
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            services: parse(&content, &env),
+        })
+    }
+
+    /// Service publishing `port`.
+    pub fn service_of(&self, port: u16) -> Option<&str> {
+        self.services
+            .iter()
+            .find(|s| s.ports.contains(&port))
+            .map(|s| s.name.as_str())
+    }
+
+    /// Every published port, in file order.
+    pub fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        self.services.iter().flat_map(|s| s.ports.iter().copied())
+    }
+}
+
+/// First of [`FILE_NAMES`] present in `dir`.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Services of a compose file with the host ports they publish. Services
+/// without published ports are left out.
+pub fn parse(content: &str, env: &dyn Fn(&str) -> Option<String>) -> Vec<ComposeService> {
+    let mut services: Vec<ComposeService> = Vec::new();
+    let mut in_services = false;
+    let mut service_indent = None;
+    let mut key_indent = None;
+    let mut ports_indent = None;
+    for line in content.lines() {
+        let text = strip_comment(line.trim());
+        if text.is_empty() || text == "---" {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_services = text == "services:";
+            service_indent = None;
+            ports_indent = None;
+            continue;
+        }
+        if !in_services {
+            continue;
+        }
+        if indent <= *service_indent.get_or_insert(indent) {
+            let name = unquote(text.trim_end_matches(':'));
+            services.push(ComposeService {
+                name: name.to_string(),
+                ports: Vec::new(),
+            });
+            key_indent = None;
+            ports_indent = None;
+            continue;
+        }
+        let Some(service) = services.last_mut() else {
+            continue;
+        };
+        if let Some(ports) = ports_indent {
+            if indent > ports || (indent == ports && text.starts_with('-')) {
+                service.ports.extend(port_item(text, env));
+                continue;
+            }
+            ports_indent = None;
+        }
+        if indent != *key_indent.get_or_insert(indent) {
+            continue;
+        }
+        if let Some(value) = text.strip_prefix("ports:").map(str::trim) {
+            if let Some(flow) = value.strip_prefix('[') {
+                let flow = flow.trim_end_matches(']');
+                for spec in flow.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                    service
+                        .ports
+                        .extend(short_syntax(&interpolate(unquote(spec), env)));
+                }
+            } else {
+                ports_indent = Some(indent);
+            }
+        }
+    }
+    for service in &mut services {
+        let mut seen = Vec::new();
+        service.ports.retain(|p| {
+            let new = !seen.contains(p);
+            seen.push(*p);
+            new
+        });
+    }
+    services.retain(|s| !s.ports.is_empty());
+    services
+}
+
+/// Ports of one line of a `ports` list: a `- spec` item of the short syntax,
+/// or a line of a long-syntax mapping of which only `published` matters.
+fn port_item(text: &str, env: &dyn Fn(&str) -> Option<String>) -> Vec<u16> {
+    let item = text.strip_prefix('-').map_or(text, str::trim_start);
+    match item.split_once(':') {
+        Some((key, value))
+            if is_key(key) && (value.is_empty() || value.starts_with(char::is_whitespace)) =>
+        {
+            if key == "published" {
+                port_range(&interpolate(unquote(value.trim()), env))
+            } else {
+                Vec::new()
+            }
+        }
+        _ if text.starts_with('-') => short_syntax(&interpolate(unquote(item), env)),
+        _ => Vec::new(),
+    }
+}
+
+fn is_key(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Host ports of `[IP:]HOST:CONTAINER[/PROTOCOL]`; none when only the
+/// container port is given, since Docker then picks a random host port.
+fn short_syntax(spec: &str) -> Vec<u16> {
+    let spec = spec.split('/').next().unwrap_or_default();
+    // An IPv6 host IP is bracketed: [::1]:8080:80
+    let spec = match spec.strip_prefix('[') {
+        Some(rest) => rest.split_once("]:").map_or(rest, |(_, rest)| rest),
+        None => spec,
+    };
+    let parts: Vec<&str> = spec.split(':').collect();
+    match parts.as_slice() {
+        [host, _] | [_, host, _] => port_range(host),
+        _ => Vec::new(),
+    }
+}
+
+/// `8080` or `8000-8002`.
+fn port_range(text: &str) -> Vec<u16> {
+    let parse = |s: &str| s.trim().parse::<u16>().ok().filter(|&p| p > 0);
+    match text.split_once('-') {
+        Some((start, end)) => match (parse(start), parse(end)) {
+            (Some(start), Some(end)) if start <= end && usize::from(end - start) < MAX_RANGE => {
+                (start..=end).collect()
+            }
+            _ => Vec::new(),
+        },
+        None => parse(text).into_iter().collect(),
+    }
+}
+
+/// Replace `${NAME}`, `${NAME:-default}`, `${NAME-default}`, and `$NAME`.
+fn interpolate(text: &str, env: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some((expr, tail)) = after.strip_prefix('{').and_then(|b| b.split_once('}')) {
+            let (name, default, empty_is_unset) = if let Some((name, d)) = expr.split_once(":-") {
+                (name, Some(d), true)
+            } else if let Some((name, d)) = expr.split_once('-') {
+                (name, Some(d), false)
+            } else {
+                (expr, None, false)
+            };
+            let value = env(name).filter(|v| !(empty_is_unset && v.is_empty()));
+            out.push_str(&value.or(default.map(str::to_string)).unwrap_or_default());
+            rest = tail;
+        } else if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            let len = after
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(after.len());
+            out.push_str(&env(&after[..len]).unwrap_or_default());
+            rest = &after[len..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `text` up to a ` #` comment.
+fn strip_comment(text: &str) -> &str {
+    if text.starts_with('#') {
+        return "";
+    }
+    text.find(" #").map_or(text, |i| text[..i].trim_end())
+}
+
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|&q| text.strip_prefix(q).and_then(|t| t.strip_suffix(q)))
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSE: &str = r#"
+name: storefront
+
+services:
+  web:
+    build: .
+    ports:
+      - "3000:3000"
+      - 127.0.0.1:9229:9229   # inspector
+      - "8000-8002:8000-8002/tcp"
+      - "4000"
+    depends_on: [db]
+  db:
+    image: postgres:16
+    ports:
+    - target: 5432
+      published: "${DB_PORT:-5432}"
+      protocol: tcp
+    environment:
+      PORTS: "1234:1234"
+  "cache":
+    image: redis
+    ports: ["[::1]:6379:6379", '${CACHE_PORT}:6379']
+  worker:
+    image: storefront-worker
+
+volumes:
+  data:
+    ports:
+      - "9999:9999"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let env = |name: &str| (name == "CACHE_PORT").then(|| "16379".to_string());
+        let services = parse(COMPOSE, &env);
+        let summary: Vec<(&str, &[u16])> = services
+            .iter()
+            .map(|s| (s.name.as_str(), s.ports.as_slice()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("web", &[3000, 9229, 8000, 8001, 8002][..]),
+                ("db", &[5432][..]),
+                ("cache", &[6379, 16379][..]),
+            ]
+        );
+        let file = ComposeFile {
+            path: PathBuf::from("compose.yaml"),
+            services,
+        };
+        assert_eq!(file.service_of(8001), Some("web"));
+        assert_eq!(file.service_of(4000), None);
+        assert_eq!(file.ports().count(), 8);
+        assert!(parse("services: {}\n", &|_| None).is_empty());
+    }
+
+    #[test]
+    fn test_short_syntax() {
+        assert_eq!(short_syntax("8080:80"), [8080]);
+        assert_eq!(short_syntax("0.0.0.0:8080:80/udp"), [8080]);
+        assert_eq!(short_syntax("127.0.0.1::80"), Vec::<u16>::new());
+        assert_eq!(short_syntax("80"), Vec::<u16>::new());
+        assert_eq!(short_syntax("9090-9091:8080-8081"), [9090, 9091]);
+        assert_eq!(short_syntax("1-65535:1-65535"), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let env = |name: &str| match name {
+            "PORT" => Some("3001".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(interpolate("${PORT}:3000", &env), "3001:3000");
+        assert_eq!(interpolate("$PORT:3000", &env), "3001:3000");
+        assert_eq!(interpolate("${MISSING:-8080}:80", &env), "8080:80");
+        assert_eq!(interpolate("${EMPTY:-8080}:80", &env), "8080:80");
+        assert_eq!(interpolate("${EMPTY-8080}:80", &env), ":80");
+        assert_eq!(interpolate("cost $5", &env), "cost $5");
+    }
+}
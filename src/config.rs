@@ -1,6 +1,20 @@
+use crate::connection::Connections;
+use crate::dev::Scenarios;
+use crate::forward::Forwards;
+use crate::pin::Pins;
+use crate::port::PortSource;
+pub use crate::port::{ProbeConfig, SourcesConfig, SshBackend, SshConfig};
+use crate::preset::Presets;
+use crate::registry::PortRegistry;
+use crate::snapshot::Snapshots;
+use crate::state::StateFile;
+use clap::{Subcommand, ValueEnum};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
@@ -8,6 +22,28 @@ pub struct Config {
     pub general: GeneralConfig,
     #[serde(default)]
     pub ui: UiConfig,
+    #[serde(default)]
+    pub ssh: SshConfig,
+    #[serde(default)]
+    pub probe: ProbeConfig,
+    #[serde(default)]
+    pub sources: SourcesConfig,
+    #[serde(default)]
+    pub tmux: TmuxConfig,
+    #[serde(default)]
+    pub tunnels: TunnelsConfig,
+    /// `[services]`: service names by port, over the built-in table.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub services: BTreeMap<String, String>,
+    /// `[hosts]`: names shown for remote hosts, by address or host name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, String>,
+    /// `[[actions]]`: user-defined commands for the selected entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CustomAction>,
+    /// `[[remote_group]]`: hosts `quay list --remote-group` scans together.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_group: Vec<RemoteGroup>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,18 +52,156 @@ pub struct GeneralConfig {
     pub auto_refresh: bool,
     #[serde(default = "default_refresh_interval")]
     pub refresh_interval: u32,
+    /// Refresh interval for remote hosts and the "All connections" view,
+    /// whose scans go over SSH; `refresh_interval` when unset.
+    #[serde(default)]
+    pub remote_refresh_interval: Option<u32>,
     #[serde(default = "default_filter")]
     pub default_filter: String,
     #[serde(default)]
     pub remote_host: Option<String>,
     #[serde(default)]
     pub docker_target: Option<String>,
+    /// Docker context for docker commands (`docker --context`); a
+    /// connection's own `docker_context` takes precedence.
+    #[serde(default)]
+    pub docker_context: Option<String>,
+    /// Offer to retry a kill through sudo when the process belongs to another user.
+    #[serde(default)]
+    pub allow_sudo_kill: bool,
+    /// Ports hidden from the TUI table (e.g. 631 for CUPS).
+    #[serde(default)]
+    pub ignore_ports: Vec<u16>,
+    /// Process or container names hidden from the TUI table.
+    #[serde(default)]
+    pub ignore_processes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct UiConfig {
     #[serde(default)]
     pub mouse_enabled: bool,
+    /// Start in compact mode: borderless header, filter bar, and table.
+    #[serde(default)]
+    pub compact: bool,
+    #[serde(default)]
+    pub layout: UiLayout,
+    /// Show the LATENCY column: how long each probe took to connect.
+    #[serde(default)]
+    pub latency_column: bool,
+    /// Show the CPU and MEM columns: what the process or container behind
+    /// each port uses.
+    #[serde(default)]
+    pub usage_columns: bool,
+    /// Name remote addresses found nowhere else with a reverse DNS lookup.
+    #[serde(default)]
+    pub reverse_dns: bool,
+}
+
+/// How the main screen is arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UiLayout {
+    /// Full-width table; Enter opens details in a popup.
+    #[default]
+    Table,
+    /// Table on the left, details of the selected entry on the right.
+    Split,
+}
+
+/// Shells and forwards opened in tmux from the TUI.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TmuxConfig {
+    #[serde(default)]
+    pub layout: TmuxLayout,
+    /// Command for "Shell in tmux", with `[[actions]]` placeholders; by
+    /// default a shell on the entry's host or in its container.
+    #[serde(default)]
+    pub shell_command: Option<String>,
+}
+
+/// Where tmux opens a new terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TmuxLayout {
+    /// A new window.
+    #[default]
+    Window,
+    /// A pane below quay.
+    Split,
+    /// A pane beside quay.
+    Vsplit,
+}
+
+/// Forgotten SSH forwards: when they count as idle, and whether to close them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelsConfig {
+    /// Minutes without an established connection after which an SSH `-L`
+    /// forward is flagged idle; 0 never flags.
+    #[serde(default = "default_idle_minutes")]
+    pub idle_minutes: u32,
+    /// Stop the ssh process of idle forwards on refresh.
+    #[serde(default)]
+    pub close_idle: bool,
+}
+
+fn default_idle_minutes() -> u32 {
+    60
+}
+
+impl Default for TunnelsConfig {
+    fn default() -> Self {
+        Self {
+            idle_minutes: default_idle_minutes(),
+            close_idle: false,
+        }
+    }
+}
+
+impl TunnelsConfig {
+    /// How long a forward must go unused to count as idle, if ever.
+    pub fn idle_after(&self) -> Option<Duration> {
+        (self.idle_minutes > 0).then(|| Duration::from_secs(u64::from(self.idle_minutes) * 60))
+    }
+}
+
+/// A shell command run for the selected entry, from the actions menu or its own key.
+///
+/// ```toml
+/// [[actions]]
+/// name = "tail logs"
+/// command = "docker logs -f {container_id}"
+/// sources = ["docker"]
+/// key = "D"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomAction {
+    pub name: String,
+    /// Shell command; `{port}`, `{pid}`, `{container_id}`, ... are filled from the entry.
+    pub command: String,
+    /// Sources the action is offered for; all when empty.
+    #[serde(default)]
+    pub sources: Vec<PortSource>,
+    /// Normal-mode key that runs the action directly; built-in keys take precedence.
+    #[serde(default)]
+    pub key: Option<char>,
+    /// Run detached without the terminal (e.g. `open http://localhost:{port}`).
+    #[serde(default)]
+    pub background: bool,
+}
+
+/// Hosts scanned together by `quay list --remote-group <name>`.
+///
+/// ```toml
+/// [[remote_group]]
+/// name = "web"
+/// hosts = ["web1", "web2", "deploy@web3"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteGroup {
+    pub name: String,
+    pub hosts: Vec<String>,
 }
 
 fn default_refresh_interval() -> u32 {
@@ -43,9 +217,14 @@ impl Default for GeneralConfig {
         Self {
             auto_refresh: false,
             refresh_interval: default_refresh_interval(),
+            remote_refresh_interval: None,
             default_filter: default_filter(),
             remote_host: None,
             docker_target: None,
+            docker_context: None,
+            allow_sudo_kill: false,
+            ignore_ports: Vec::new(),
+            ignore_processes: Vec::new(),
         }
     }
 }
@@ -59,17 +238,541 @@ impl Config {
         Self::config_dir().map(|p| p.join("config.toml"))
     }
 
-    pub fn load() -> Self {
-        Self::config_path()
-            .and_then(|path| {
-                if path.exists() {
-                    fs::read_to_string(&path).ok()
+    pub fn load() -> Result<Self, ConfigDiagnostic> {
+        load_file(Self::config_path())
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Hosts of the `[[remote_group]]` called `name`.
+    pub fn remote_group(&self, name: &str) -> anyhow::Result<&[String]> {
+        let group = self
+            .remote_group
+            .iter()
+            .find(|g| g.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.remote_group.iter().map(|g| g.name.as_str()).collect();
+                if names.is_empty() {
+                    anyhow::anyhow!("No [[remote_group]] named '{name}' in config.toml")
                 } else {
-                    None
+                    anyhow::anyhow!(
+                        "No [[remote_group]] named '{name}' (groups: {})",
+                        names.join(", ")
+                    )
                 }
-            })
-            .and_then(|content| toml::from_str(&content).ok())
-            .unwrap_or_default()
+            })?;
+        Ok(&group.hosts)
+    }
+}
+
+/// A config file that exists but could not be read or parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub path: PathBuf,
+    /// 1-based line of the error, when known.
+    pub line: Option<usize>,
+    /// One-line description of the problem.
+    pub message: String,
+    /// Full error text, including the offending source snippet.
+    pub detail: String,
+}
+
+impl ConfigDiagnostic {
+    pub fn from_toml(path: &Path, content: &str, err: &toml::de::Error) -> Self {
+        let line = err.span().map(|span| {
+            content[..span.start.min(content.len())]
+                .matches('\n')
+                .count()
+                + 1
+        });
+        Self {
+            path: path.to_path_buf(),
+            line,
+            message: err.message().trim().to_string(),
+            detail: err.to_string().trim_end().to_string(),
+        }
+    }
+
+    pub fn from_io(path: &Path, err: &std::io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line: None,
+            message: err.to_string(),
+            detail: err.to_string(),
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    /// Short form for banners, e.g. `config.toml line 7: invalid type`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {line}: {}", self.file_name(), self.message),
+            None => write!(f, "{}: {}", self.file_name(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigDiagnostic {}
+
+/// Load a TOML file, falling back to the default when it does not exist.
+///
+/// Unlike a missing file, an unreadable or invalid file is reported so callers
+/// can warn instead of silently running with defaults.
+pub fn load_file<T: DeserializeOwned + Default>(
+    path: Option<PathBuf>,
+) -> Result<T, ConfigDiagnostic> {
+    let Some(path) = path.filter(|p| p.exists()) else {
+        return Ok(T::default());
+    };
+    let content = fs::read_to_string(&path).map_err(|e| ConfigDiagnostic::from_io(&path, &e))?;
+    toml::from_str(&content).map_err(|e| ConfigDiagnostic::from_toml(&path, &content, &e))
+}
+
+/// Where an effective config value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSource {
+    Default,
+    File,
+    Flag,
+}
+
+impl ValueSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::File => "config.toml",
+            Self::Flag => "command line",
+        }
+    }
+}
+
+/// A single `section.key` of the effective configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValue {
+    pub section: String,
+    pub key: String,
+    pub value: toml::Value,
+    pub source: ValueSource,
+}
+
+/// Command-line flags that override config values.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub remote_host: Option<String>,
+    pub docker_target: Option<String>,
+    pub docker_context: Option<String>,
+    /// `--no-docker`
+    pub no_docker: bool,
+    /// `--no-ssh`
+    pub no_ssh: bool,
+}
+
+impl ConfigOverrides {
+    /// `[sources]` of `config` with the `--no-docker` and `--no-ssh` flags
+    /// applied.
+    pub fn sources(&self, config: &SourcesConfig) -> SourcesConfig {
+        SourcesConfig {
+            local: config.local,
+            docker: config.docker && !self.no_docker,
+            ssh: config.ssh && !self.no_ssh,
+        }
+    }
+}
+
+/// Flatten the effective config into `section.key` values, tagged with their origin.
+///
+/// `raw` is the parsed config file (if any); keys present there are attributed
+/// to the file, everything else to the built-in defaults. Unset optional values
+/// are omitted.
+pub fn effective_values(
+    raw: Option<&toml::Table>,
+    overrides: &ConfigOverrides,
+) -> anyhow::Result<Vec<ConfigValue>> {
+    let mut config = match raw {
+        Some(table) => table.clone().try_into::<Config>()?,
+        None => Config::default(),
+    };
+    let mut flagged = Vec::new();
+    if let Some(host) = &overrides.remote_host {
+        config.general.remote_host = Some(host.clone());
+        flagged.push(("general", "remote_host"));
+    }
+    if let Some(target) = &overrides.docker_target {
+        config.general.docker_target = Some(target.clone());
+        flagged.push(("general", "docker_target"));
+    }
+    if let Some(context) = &overrides.docker_context {
+        config.general.docker_context = Some(context.clone());
+        flagged.push(("general", "docker_context"));
+    }
+    if overrides.no_docker {
+        flagged.push(("sources", "docker"));
+    }
+    if overrides.no_ssh {
+        flagged.push(("sources", "ssh"));
+    }
+    config.sources = overrides.sources(&config.sources);
+
+    let effective = toml::Table::try_from(&config)?;
+    let mut values = Vec::new();
+    for (section, table) in &effective {
+        let Some(table) = table.as_table() else {
+            continue;
+        };
+        for (key, value) in table {
+            let source = if flagged.contains(&(section.as_str(), key.as_str())) {
+                ValueSource::Flag
+            } else if raw.is_some_and(|r| lookup(r, section, key).is_some()) {
+                ValueSource::File
+            } else {
+                ValueSource::Default
+            };
+            values.push(ConfigValue {
+                section: section.clone(),
+                key: key.clone(),
+                value: value.clone(),
+                source,
+            });
+        }
+    }
+    Ok(values)
+}
+
+/// Keys in `raw` that quay does not recognize (they are silently ignored on load).
+pub fn unknown_keys(raw: &toml::Table) -> Vec<String> {
+    let Ok(config) = raw.clone().try_into::<Config>() else {
+        return Vec::new();
+    };
+    let Ok(known) = toml::Table::try_from(&config) else {
+        return Vec::new();
+    };
+    let mut unknown = Vec::new();
+    for (section, value) in raw {
+        match (
+            value.as_table(),
+            known.get(section).and_then(|v| v.as_table()),
+        ) {
+            (Some(table), Some(_)) => {
+                for key in table.keys() {
+                    if lookup(&known, section, key).is_none() {
+                        unknown.push(format!("{section}.{key}"));
+                    }
+                }
+            }
+            // Arrays of tables such as `[[actions]]`
+            (None, None)
+                if value.is_array() && known.get(section).is_some_and(toml::Value::is_array) => {}
+            _ => unknown.push(section.clone()),
+        }
+    }
+    unknown
+}
+
+/// Warnings for custom actions whose key is already a built-in binding.
+fn shadowed_action_keys(actions: &[CustomAction]) -> Vec<String> {
+    actions
+        .iter()
+        .filter_map(|action| {
+            let key = action.key?;
+            crate::event::KEYMAP
+                .iter()
+                .any(|b| b.keys.contains(&crossterm::event::KeyCode::Char(key)))
+                .then(|| {
+                    format!(
+                        "action `{}`: key `{key}` is a built-in binding and is ignored",
+                        action.name
+                    )
+                })
+        })
+        .collect()
+}
+
+fn lookup<'a>(table: &'a toml::Table, section: &str, key: &str) -> Option<&'a toml::Value> {
+    table.get(section)?.as_table()?.get(key)
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the effective configuration and where each value came from
+    Show,
+    /// Open a config file in $EDITOR and validate it afterwards
+    Edit {
+        /// Which file to edit
+        #[arg(value_enum, default_value = "config")]
+        file: ConfigFile,
+    },
+    /// Check that all config files parse, reporting error locations
+    Validate,
+    /// Print the location of each config file
+    Path,
+}
+
+/// The TOML files quay reads from its config directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigFile {
+    Config,
+    Presets,
+    Connections,
+    Forwards,
+    Ports,
+    Pins,
+    Snapshots,
+    Scenarios,
+}
+
+impl ConfigFile {
+    const ALL: [Self; 8] = [
+        Self::Config,
+        Self::Presets,
+        Self::Connections,
+        Self::Forwards,
+        Self::Ports,
+        Self::Pins,
+        Self::Snapshots,
+        Self::Scenarios,
+    ];
+
+    pub fn path(self) -> Option<PathBuf> {
+        match self {
+            Self::Config => Config::config_path(),
+            Self::Presets => Presets::presets_path(),
+            Self::Connections => Connections::connections_path(),
+            Self::Forwards => Forwards::path(),
+            Self::Ports => PortRegistry::registry_path(),
+            Self::Pins => Pins::pins_path(),
+            Self::Snapshots => Snapshots::path(),
+            Self::Scenarios => Scenarios::scenarios_path(),
+        }
+    }
+
+    /// Parse `content` as this file, returning warnings on success.
+    pub fn check(self, content: &str) -> anyhow::Result<Vec<String>> {
+        match self {
+            Self::Config => {
+                let raw: toml::Table = toml::from_str(content)?;
+                let config = Config::parse(content)?;
+                let mut warnings: Vec<String> = unknown_keys(&raw)
+                    .into_iter()
+                    .map(|key| format!("unknown key `{key}` is ignored"))
+                    .collect();
+                warnings.extend(shadowed_action_keys(&config.actions));
+                Ok(warnings)
+            }
+            Self::Presets => {
+                let presets: Presets = toml::from_str(content)?;
+                Ok(crate::preset::key_conflicts(
+                    &presets.preset,
+                    crate::event::PRESET_POPUP_KEYS,
+                ))
+            }
+            Self::Connections => toml::from_str::<Connections>(content)
+                .map(|_| Vec::new())
+                .map_err(Into::into),
+            Self::Forwards => toml::from_str::<Forwards>(content)
+                .map(|_| Vec::new())
+                .map_err(Into::into),
+            Self::Ports => PortRegistry::parse(content)
+                .map(|registry| registry.warnings())
+                .map_err(Into::into),
+            Self::Pins => toml::from_str::<Pins>(content)
+                .map(|_| Vec::new())
+                .map_err(Into::into),
+            Self::Snapshots => toml::from_str::<Snapshots>(content)
+                .map(|_| Vec::new())
+                .map_err(Into::into),
+            Self::Scenarios => toml::from_str::<Scenarios>(content)
+                .map(|_| Vec::new())
+                .map_err(Into::into),
+        }
+    }
+}
+
+pub fn run_config(cmd: &ConfigCommands, overrides: &ConfigOverrides) -> anyhow::Result<()> {
+    match cmd {
+        ConfigCommands::Show => run_show(overrides),
+        ConfigCommands::Edit { file } => run_edit(*file),
+        ConfigCommands::Validate => run_validate(),
+        ConfigCommands::Path => {
+            run_path();
+            Ok(())
+        }
+    }
+}
+
+fn run_show(overrides: &ConfigOverrides) -> anyhow::Result<()> {
+    let path = Config::config_path();
+    let raw = match path.as_ref().filter(|p| p.exists()) {
+        Some(p) => {
+            let content = fs::read_to_string(p)?;
+            Some(toml::from_str::<toml::Table>(&content).map_err(|e| {
+                anyhow::anyhow!(
+                    "{}: {e}\nRun `quay config validate` for details.",
+                    p.display()
+                )
+            })?)
+        }
+        None => None,
+    };
+
+    match &path {
+        Some(p) if raw.is_some() => println!("# {}", p.display()),
+        Some(p) => println!("# {} (not found, using defaults)", p.display()),
+        None => println!("# config directory unavailable, using defaults"),
+    }
+
+    let values = effective_values(raw.as_ref(), overrides)?;
+    let width = values
+        .iter()
+        .map(|v| v.key.len() + v.value.to_string().len() + 3)
+        .max()
+        .unwrap_or(0);
+    let mut section = "";
+    for value in &values {
+        if value.section != section {
+            section = &value.section;
+            println!("\n[{section}]");
+        }
+        let line = format!("{} = {}", value.key, value.value);
+        println!("{line:<width$}  # {}", value.source.label());
+    }
+    Ok(())
+}
+
+fn run_edit(file: ConfigFile) -> anyhow::Result<()> {
+    let Some(path) = file.path() else {
+        anyhow::bail!("Could not determine config directory");
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let Some(program) = parts.next() else {
+        anyhow::bail!("$EDITOR is empty");
+    };
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to launch editor '{program}': {e}"))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {status}");
+    }
+
+    if !path.exists() {
+        return Ok(());
+    }
+    if validate_path(file, &path) {
+        Ok(())
+    } else {
+        anyhow::bail!("{} is invalid; quay will ignore it", path.display())
+    }
+}
+
+/// Every config file quay may read, including the project's ports.toml.
+pub fn config_files() -> Vec<(ConfigFile, PathBuf)> {
+    let mut files: Vec<(ConfigFile, PathBuf)> = ConfigFile::ALL
+        .into_iter()
+        .filter_map(|file| file.path().map(|path| (file, path)))
+        .collect();
+    if let Some(project) = PortRegistry::project_path() {
+        files.push((ConfigFile::Ports, project));
+    }
+    files
+}
+
+/// What `quay config validate` would report, one line per error or warning,
+/// for `--strict-config`.
+pub fn problems() -> Vec<String> {
+    config_files()
+        .into_iter()
+        .filter(|(_, path)| path.exists())
+        .flat_map(|(file, path)| {
+            let result = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| file.check(&content));
+            match result {
+                Ok(warnings) => warnings
+                    .into_iter()
+                    .map(|warning| format!("{}: warning: {warning}", path.display()))
+                    .collect(),
+                Err(e) => vec![format!("{}: {e}", path.display())],
+            }
+        })
+        .collect()
+}
+
+fn run_validate() -> anyhow::Result<()> {
+    let files = config_files();
+    let mut failed = 0;
+    for (file, path) in &files {
+        if !path.exists() {
+            println!("skip   {} (not found)", path.display());
+        } else if !validate_path(*file, path) {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} config file(s) failed to validate");
+    }
+    Ok(())
+}
+
+/// Validate one file and print the result. Returns `false` on errors.
+fn validate_path(file: ConfigFile, path: &Path) -> bool {
+    let result = fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| file.check(&content));
+    match result {
+        Ok(warnings) => {
+            println!("ok     {}", path.display());
+            for warning in warnings {
+                println!("  warning: {warning}");
+            }
+            true
+        }
+        Err(e) => {
+            println!("error  {}", path.display());
+            for line in e.to_string().lines() {
+                println!("  {line}");
+            }
+            false
+        }
+    }
+}
+
+fn run_path() {
+    let Some(dir) = Config::config_dir() else {
+        println!("Could not determine config directory");
+        return;
+    };
+    println!("{:<12} {}", "dir", dir.display());
+    if let Some(state) = crate::state::dir() {
+        println!("{:<12} {}", "state dir", state.display());
+    }
+    for file in ConfigFile::ALL {
+        if let Some(path) = file.path() {
+            let name = format!("{file:?}").to_lowercase();
+            let status = if path.exists() { "" } else { " (not found)" };
+            println!("{name:<12} {}{status}", path.display());
+        }
+    }
+    if let Some(project) = PortRegistry::project_path() {
+        let status = if project.exists() { "" } else { " (not found)" };
+        println!("{:<12} {}{status}", "ports (cwd)", project.display());
     }
 }
 
@@ -86,6 +789,104 @@ mod tests {
         assert!(config.general.remote_host.is_none());
         assert!(config.general.docker_target.is_none());
         assert!(!config.ui.mouse_enabled);
+        assert_eq!(config.ui.layout, UiLayout::Table);
+        assert_eq!(config.ssh.backend, SshBackend::Openssh);
+        assert!(config.ssh.identity_file.is_none());
+    }
+
+    #[test]
+    fn test_parse_ssh_backend() {
+        let config = Config::parse(
+            r#"
+[ssh]
+backend = "embedded"
+identity_file = "~/.ssh/id_quay"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.ssh.backend, SshBackend::Embedded);
+        assert_eq!(config.ssh.identity_file.as_deref(), Some("~/.ssh/id_quay"));
+        assert!(Config::parse("[ssh]\nbackend = \"russh\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_probe() {
+        assert_eq!(Config::default().probe, ProbeConfig::default());
+        assert!(Config::default().probe.enabled);
+        let config = Config::parse("[probe]\nenabled = false\ntimeout_ms = 1000\n").unwrap();
+        assert!(!config.probe.enabled);
+        assert_eq!(config.probe.timeout_ms, 1000);
+        assert_eq!(config.probe.concurrency, 64);
+    }
+
+    #[test]
+    fn test_parse_tunnels() {
+        let defaults = Config::default().tunnels;
+        assert_eq!(defaults.idle_after(), Some(Duration::from_secs(3600)));
+        assert!(!defaults.close_idle);
+        let config = Config::parse(
+            "[tunnels]
+idle_minutes = 0
+close_idle = true
+",
+        )
+        .unwrap();
+        assert_eq!(config.tunnels.idle_after(), None);
+        assert!(config.tunnels.close_idle);
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(Config::default().sources, SourcesConfig::default());
+        let config = Config::parse("[sources]\ndocker = false\n").unwrap();
+        assert!(config.sources.local && config.sources.ssh);
+        assert!(!config.sources.docker);
+
+        let overrides = ConfigOverrides {
+            no_ssh: true,
+            ..ConfigOverrides::default()
+        };
+        let sources = overrides.sources(&config.sources);
+        assert_eq!(
+            (sources.local, sources.docker, sources.ssh),
+            (true, false, false)
+        );
+    }
+
+    #[test]
+    fn test_parse_hosts() {
+        let content = "[ui]\nreverse_dns = true\n\n[hosts]\n\"10.0.0.12\" = \"db-primary\"\n";
+        let config = Config::parse(content).unwrap();
+        assert!(config.ui.reverse_dns);
+        assert_eq!(
+            config.hosts.get("10.0.0.12").map(String::as_str),
+            Some("db-primary")
+        );
+        let raw: toml::Table = toml::from_str(content).unwrap();
+        assert!(unknown_keys(&raw).is_empty());
+    }
+
+    #[test]
+    fn test_parse_remote_groups() {
+        let content = "[[remote_group]]\nname = \"web\"\nhosts = [\"web1\", \"web2\"]\n";
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.remote_group("web").unwrap(), ["web1", "web2"]);
+        let err = config.remote_group("db").unwrap_err().to_string();
+        assert!(err.contains("groups: web"), "{err}");
+        let raw: toml::Table = toml::from_str(content).unwrap();
+        assert!(unknown_keys(&raw).is_empty());
+    }
+
+    #[test]
+    fn test_parse_services() {
+        let content = "[services]\n3000 = \"grafana\"\n";
+        let config = Config::parse(content).unwrap();
+        assert_eq!(
+            config.services.get("3000").map(String::as_str),
+            Some("grafana")
+        );
+        let raw: toml::Table = toml::from_str(content).unwrap();
+        assert!(unknown_keys(&raw).is_empty());
     }
 
     #[test]
@@ -94,16 +895,28 @@ mod tests {
 [general]
 auto_refresh = true
 refresh_interval = 10
+remote_refresh_interval = 60
 default_filter = "local"
+ignore_ports = [631, 5353]
+ignore_processes = ["rapportd"]
 
 [ui]
 mouse_enabled = true
+layout = "split"
+latency_column = true
+usage_columns = true
 "#;
         let config: Config = toml::from_str(toml).unwrap();
         assert!(config.general.auto_refresh);
         assert_eq!(config.general.refresh_interval, 10);
+        assert_eq!(config.general.remote_refresh_interval, Some(60));
         assert_eq!(config.general.default_filter, "local");
+        assert_eq!(config.general.ignore_ports, vec![631, 5353]);
+        assert_eq!(config.general.ignore_processes, vec!["rapportd"]);
         assert!(config.ui.mouse_enabled);
+        assert_eq!(config.ui.layout, UiLayout::Split);
+        assert!(config.ui.latency_column);
+        assert!(config.ui.usage_columns);
     }
 
     #[test]
@@ -143,4 +956,196 @@ docker_target = "syntopic-dev"
             Some("syntopic-dev".to_string())
         );
     }
+
+    fn value<'a>(values: &'a [ConfigValue], section: &str, key: &str) -> &'a ConfigValue {
+        values
+            .iter()
+            .find(|v| v.section == section && v.key == key)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_reports_error_location() {
+        let err = Config::parse("[general]\nrefresh_interval = \"fast\"\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_effective_values_sources() {
+        let raw: toml::Table = toml::from_str("[general]\nrefresh_interval = 10\n").unwrap();
+        let overrides = ConfigOverrides {
+            remote_host: Some("server".to_string()),
+            docker_target: None,
+            docker_context: Some("lab".to_string()),
+            no_docker: true,
+            no_ssh: false,
+        };
+        let values = effective_values(Some(&raw), &overrides).unwrap();
+
+        let interval = value(&values, "general", "refresh_interval");
+        assert_eq!(interval.value, toml::Value::Integer(10));
+        assert_eq!(interval.source, ValueSource::File);
+
+        let filter = value(&values, "general", "default_filter");
+        assert_eq!(filter.source, ValueSource::Default);
+
+        let remote = value(&values, "general", "remote_host");
+        assert_eq!(remote.value, toml::Value::String("server".to_string()));
+        assert_eq!(remote.source, ValueSource::Flag);
+
+        assert!(!values.iter().any(|v| v.key == "docker_target"));
+        assert_eq!(
+            value(&values, "general", "docker_context").source,
+            ValueSource::Flag
+        );
+        assert_eq!(
+            value(&values, "ui", "mouse_enabled").source,
+            ValueSource::Default
+        );
+        let docker = value(&values, "sources", "docker");
+        assert_eq!(docker.value, toml::Value::Boolean(false));
+        assert_eq!(docker.source, ValueSource::Flag);
+        assert_eq!(
+            value(&values, "sources", "ssh").source,
+            ValueSource::Default
+        );
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        let content = r#"
+[[actions]]
+name = "tail logs"
+command = "docker logs -f {container_id}"
+sources = ["docker"]
+key = "D"
+
+[[actions]]
+name = "open"
+command = "open http://localhost:{port}"
+background = true
+key = "K"
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.actions.len(), 2);
+        assert_eq!(config.actions[0].sources, vec![PortSource::Docker]);
+        assert_eq!(config.actions[0].key, Some('D'));
+        assert!(config.actions[1].background);
+        assert!(config.actions[1].sources.is_empty());
+
+        let warnings = ConfigFile::Config.check(content).unwrap();
+        assert_eq!(
+            warnings,
+            vec!["action `open`: key `K` is a built-in binding and is ignored"]
+        );
+    }
+
+    #[test]
+    fn test_parse_tmux() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.tmux.layout, TmuxLayout::Window);
+        assert!(config.tmux.shell_command.is_none());
+
+        let config = Config::parse(
+            r#"
+[tmux]
+layout = "vsplit"
+shell_command = "ssh -t {host} htop"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.tmux.layout, TmuxLayout::Vsplit);
+        assert_eq!(
+            config.tmux.shell_command.as_deref(),
+            Some("ssh -t {host} htop")
+        );
+        assert!(Config::parse("[tmux]\nlayout = \"tab\"").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let raw: toml::Table = toml::from_str(
+            r"
+[general]
+auto_refresh = true
+refresh_intreval = 3
+
+[theme]
+accent = 1
+",
+        )
+        .unwrap();
+        assert_eq!(
+            unknown_keys(&raw),
+            vec!["general.refresh_intreval", "theme"]
+        );
+    }
+
+    #[test]
+    fn test_config_file_check() {
+        assert!(
+            ConfigFile::Config
+                .check("[ui]\nmouse_enabled = true\n")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            ConfigFile::Config
+                .check("[ui]\nmouse = true\n")
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(ConfigFile::Presets.check("[[preset]]\nname = 1\n").is_err());
+        assert!(ConfigFile::Ports.check("3000 = \"web\"\n").is_ok());
+        assert_eq!(ConfigFile::Ports.check("web = \"web\"\n").unwrap().len(), 1);
+        let preset = "[[preset]]\nname = \"db\"\nkey = \"q\"\nlocal_port = 5432\n\
+                      remote_host = \"localhost\"\nremote_port = 5432\nssh_host = \"bastion\"\n";
+        assert_eq!(ConfigFile::Presets.check(preset).unwrap().len(), 1);
+    }
+
+    fn temp_config(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("quay-config-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_file_missing_uses_default() {
+        let path = std::env::temp_dir().join("quay-config-does-not-exist.toml");
+        let config: Config = load_file(Some(path)).unwrap();
+        assert_eq!(config.general.refresh_interval, 5);
+        let config: Config = load_file(None).unwrap();
+        assert!(!config.ui.mouse_enabled);
+    }
+
+    #[test]
+    fn test_load_file_reports_invalid_toml() {
+        let path = temp_config(
+            "invalid",
+            "[general]\nauto_refresh = true\n\n[ui]\nmouse_enabled = \"yes\"\n",
+        );
+        let diagnostic = load_file::<Config>(Some(path.clone())).unwrap_err();
+        assert_eq!(diagnostic.path, path);
+        assert_eq!(diagnostic.line, Some(5));
+        assert!(diagnostic.message.contains("expected a boolean"));
+        assert!(diagnostic.detail.contains("mouse_enabled = \"yes\""));
+        assert!(
+            diagnostic
+                .to_string()
+                .starts_with("config.toml line 5: invalid type")
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_display_without_line() {
+        let diagnostic = ConfigDiagnostic::from_io(
+            Path::new("/tmp/quay/presets.toml"),
+            &std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+        assert!(diagnostic.line.is_none());
+        assert!(diagnostic.to_string().starts_with("presets.toml: "));
+    }
 }
@@ -1,7 +1,14 @@
-use crate::config::Config;
+use crate::config::{self, Config, ConfigDiagnostic, SshBackend};
+use crate::port;
+use crate::state;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Seconds ssh may spend connecting before a probe counts as timed out.
+const PROBE_CONNECT_TIMEOUT: u64 = 5;
+/// Hard limit on a probe, covering slow authentication after the TCP connect.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(8);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Connection {
@@ -10,6 +17,27 @@ pub struct Connection {
     pub remote_host: Option<String>,
     #[serde(default)]
     pub docker_target: Option<String>,
+    /// Docker context (`docker context ls`) for this connection's docker
+    /// commands, e.g. one whose daemon runs on another machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_context: Option<String>,
+    #[serde(flatten)]
+    pub defaults: ConnectionDefaults,
+}
+
+/// View settings applied when switching to a connection.
+/// Unset values fall back to `[general]` in config.toml.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionDefaults {
+    #[serde(default)]
+    pub default_filter: Option<String>,
+    #[serde(default)]
+    pub auto_refresh: Option<bool>,
+    #[serde(default)]
+    pub refresh_interval: Option<u32>,
+    /// Names of the presets offered for this connection; all presets when unset.
+    #[serde(default)]
+    pub presets: Option<Vec<String>>,
 }
 
 impl Connection {
@@ -18,14 +46,150 @@ impl Connection {
             name: "Local".to_string(),
             remote_host: None,
             docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        }
+    }
+
+    /// Synthetic connection for the merged view of every configured connection.
+    pub fn aggregate() -> Self {
+        Self {
+            name: "All connections".to_string(),
+            remote_host: None,
+            docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        }
+    }
+}
+
+/// A connection per `~/.ssh/config` host matching a pattern, so a fleet
+/// doesn't need one `[[connection]]` per host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionGroup {
+    pub name: String,
+    /// `Host` pattern list, e.g. `prod-*` or `prod-* !prod-legacy`.
+    pub ssh_hosts: String,
+    #[serde(default)]
+    pub docker_target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_context: Option<String>,
+    #[serde(flatten)]
+    pub defaults: ConnectionDefaults,
+}
+
+impl ConnectionGroup {
+    /// One connection per matching host of `ssh_config`, named `<group>: <host>`.
+    pub fn expand(&self, ssh_config: &str) -> Vec<Connection> {
+        port::ssh_hosts::matching(ssh_config, &self.ssh_hosts)
+            .into_iter()
+            .map(|host| Connection {
+                name: format!("{}: {host}", self.name),
+                remote_host: Some(host),
+                docker_target: self.docker_target.clone(),
+                docker_context: self.docker_context.clone(),
+                defaults: self.defaults.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Reachability of a connection's SSH host, as reported by [`probe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    Checking,
+    Reachable(Duration),
+    AuthFailed,
+    /// The host key is missing from or doesn't match `known_hosts`; logging
+    /// in would not help.
+    HostKey,
+    TimedOut,
+    /// Any other ssh failure, with the last line of its stderr.
+    Unreachable(String),
+}
+
+impl Health {
+    pub fn label(&self) -> String {
+        match self {
+            Health::Checking => "checking".to_string(),
+            Health::Reachable(latency) => format!("reachable ({} ms)", latency.as_millis()),
+            Health::AuthFailed => "auth failed".to_string(),
+            Health::HostKey => "host key not verified".to_string(),
+            Health::TimedOut => "timed out".to_string(),
+            Health::Unreachable(reason) if reason.is_empty() => "unreachable".to_string(),
+            Health::Unreachable(reason) => format!("unreachable: {reason}"),
+        }
+    }
+
+    /// Classify a finished `ssh host true` run from its exit status and stderr.
+    pub fn from_ssh_result(success: bool, stderr: &str, elapsed: Duration) -> Self {
+        if success {
+            return Health::Reachable(elapsed);
+        }
+        let lower = stderr.to_lowercase();
+        if lower.contains("host key verification failed") {
+            Health::HostKey
+        } else if lower.contains("permission denied") {
+            Health::AuthFailed
+        } else if lower.contains("timed out") {
+            Health::TimedOut
+        } else {
+            let reason = stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("");
+            Health::Unreachable(reason.trim().to_string())
         }
     }
 }
 
+/// Check that `host` accepts a non-interactive SSH login.
+///
+/// Runs `ssh -o BatchMode=yes host true`, so password prompts fail fast
+/// instead of blocking; hosts with a `ControlMaster` (including the one quay
+/// opens after interactive authentication) reuse the open session.
+/// The embedded backend runs `true` over its own session instead.
+pub async fn probe(host: &str) -> Health {
+    let start = Instant::now();
+    let output = async {
+        if port::ssh_backend() == SshBackend::Embedded {
+            return port::remote_output(host, &["true"]).await;
+        }
+        tokio::process::Command::new("ssh")
+            .args(port::ssh::batch_args(host))
+            .args([
+                "-o",
+                &format!("ConnectTimeout={PROBE_CONNECT_TIMEOUT}"),
+                host,
+                "true",
+            ])
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+    };
+    let health = match tokio::time::timeout(PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) => Health::from_ssh_result(
+            output.status.success(),
+            &String::from_utf8_lossy(&output.stderr),
+            start.elapsed(),
+        ),
+        // The embedded backend fails with the error ssh would print
+        Ok(Err(e)) if e.to_string().contains("Host key verification failed") => Health::HostKey,
+        Ok(Err(e)) => Health::Unreachable(e.to_string()),
+        Err(_) => Health::TimedOut,
+    };
+    tracing::debug!(host, health = %health.label(), "connection probe");
+    health
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Connections {
     #[serde(default)]
     pub connection: Vec<Connection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connection_group: Vec<ConnectionGroup>,
+    /// Set when connections.toml exists but could not be read; saving would
+    /// replace the user's file with what little was loaded.
+    #[serde(skip)]
+    pub load_failed: bool,
 }
 
 impl Connections {
@@ -33,43 +197,101 @@ impl Connections {
         Config::config_dir().map(|p| p.join("connections.toml"))
     }
 
-    pub fn load() -> Self {
-        Self::connections_path()
-            .and_then(|path| {
-                if path.exists() {
-                    fs::read_to_string(&path).ok()
-                } else {
-                    None
-                }
-            })
-            .and_then(|content| toml::from_str(&content).ok())
-            .unwrap_or_default()
+    pub fn load() -> Result<Self, ConfigDiagnostic> {
+        config::load_file(Self::connections_path())
+    }
+
+    /// The stored connections, or none with `load_failed` set after
+    /// recording why connections.toml could not be read in `diagnostics`.
+    pub fn load_or_default(diagnostics: &mut Vec<ConfigDiagnostic>) -> Self {
+        Self::load().unwrap_or_else(|d| {
+            diagnostics.push(d);
+            Self {
+                load_failed: true,
+                ..Self::default()
+            }
+        })
     }
 
     pub fn save(&self) -> anyhow::Result<()> {
+        if self.load_failed {
+            anyhow::bail!("connections.toml has errors; fix it before changing connections here");
+        }
         let Some(path) = Self::connections_path() else {
             anyhow::bail!("Could not determine config directory");
         };
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
         let content = toml::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+        state::replace(&path, &content)?;
         Ok(())
     }
 
-    /// Returns all connections with Local auto-inserted at index 0.
+    /// Returns all connections with Local auto-inserted at index 0, followed
+    /// by the ones `[[connection_group]]` generates from `~/.ssh/config`.
     pub fn all_with_local(&self) -> Vec<Connection> {
+        if self.connection_group.is_empty() {
+            return self.all_with("");
+        }
+        self.all_with(&port::ssh_hosts::read_config())
+    }
+
+    fn all_with(&self, ssh_config: &str) -> Vec<Connection> {
         let mut result = Vec::with_capacity(1 + self.connection.len());
         result.push(Connection::local());
         result.extend(self.connection.iter().cloned());
+        // A host that already has a connection isn't added again
+        for group in &self.connection_group {
+            for conn in group.expand(ssh_config) {
+                if !result.iter().any(|c| c.remote_host == conn.remote_host) {
+                    result.push(conn);
+                }
+            }
+        }
         result
     }
 
+    /// Whether the connection at `index` of [`Self::all_with_local`] comes
+    /// from a group, so it can't be edited, moved, or deleted on its own.
+    pub fn is_generated(&self, index: usize) -> bool {
+        index > self.connection.len()
+    }
+
     pub fn add(&mut self, conn: Connection) {
         self.connection.push(conn);
     }
 
+    /// Replace a connection by index in the user-defined list (not including Local).
+    /// Returns true if the connection existed.
+    pub fn update(&mut self, index: usize, conn: Connection) -> bool {
+        if let Some(slot) = self.connection.get_mut(index) {
+            *slot = conn;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Swap the connection at `index` with the one before it.
+    /// Returns true if it moved.
+    pub fn move_up(&mut self, index: usize) -> bool {
+        if index > 0 && index < self.connection.len() {
+            self.connection.swap(index - 1, index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Swap the connection at `index` with the one after it.
+    /// Returns true if it moved.
+    pub fn move_down(&mut self, index: usize) -> bool {
+        if index + 1 < self.connection.len() {
+            self.connection.swap(index, index + 1);
+            true
+        } else {
+            false
+        }
+    }
+
     /// Remove a connection by index in the user-defined list (not including Local).
     /// Returns true if the connection was removed.
     pub fn remove(&mut self, index: usize) -> bool {
@@ -86,6 +308,75 @@ impl Connections {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_save_refused_after_failed_load() {
+        let connections = Connections {
+            load_failed: true,
+            ..Connections::default()
+        };
+        let error = connections.save().unwrap_err();
+        assert!(error.to_string().contains("connections.toml has errors"));
+    }
+
+    #[test]
+    fn test_health_from_ssh_result() {
+        let elapsed = Duration::from_millis(120);
+        assert_eq!(
+            Health::from_ssh_result(true, "", elapsed),
+            Health::Reachable(elapsed)
+        );
+        assert_eq!(
+            Health::from_ssh_result(
+                false,
+                "user@host: Permission denied (publickey).\n",
+                elapsed
+            ),
+            Health::AuthFailed
+        );
+        assert_eq!(
+            Health::from_ssh_result(
+                false,
+                "@@@@@@@@@@@\nREMOTE HOST IDENTIFICATION HAS CHANGED!\n\
+                 Host key verification failed.\n",
+                elapsed
+            ),
+            Health::HostKey
+        );
+        assert_eq!(
+            Health::from_ssh_result(
+                false,
+                "ssh: connect to host 10.0.0.1 port 22: Connection timed out\n",
+                elapsed
+            ),
+            Health::TimedOut
+        );
+        assert_eq!(
+            Health::from_ssh_result(
+                false,
+                "ssh: Could not resolve hostname nope: Name or service not known\n",
+                elapsed
+            ),
+            Health::Unreachable(
+                "ssh: Could not resolve hostname nope: Name or service not known".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_health_label() {
+        assert_eq!(
+            Health::Reachable(Duration::from_millis(42)).label(),
+            "reachable (42 ms)"
+        );
+        assert_eq!(Health::AuthFailed.label(), "auth failed");
+        assert_eq!(Health::HostKey.label(), "host key not verified");
+        assert_eq!(Health::Unreachable(String::new()).label(), "unreachable");
+        assert_eq!(
+            Health::Unreachable("refused".to_string()).label(),
+            "unreachable: refused"
+        );
+    }
+
     #[test]
     fn test_local_connection() {
         let local = Connection::local();
@@ -107,7 +398,10 @@ mod tests {
                 name: "Production".to_string(),
                 remote_host: Some("user@prod".to_string()),
                 docker_target: None,
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
             }],
+            ..Connections::default()
         };
         let all = conns.all_with_local();
         assert_eq!(all.len(), 2);
@@ -123,6 +417,41 @@ mod tests {
         assert_eq!(all[0].name, "Local");
     }
 
+    #[test]
+    fn test_connection_groups() {
+        let toml = r#"
+[[connection]]
+name = "Web 1"
+remote_host = "prod-web1"
+
+[[connection_group]]
+name = "Prod"
+ssh_hosts = "prod-* !prod-legacy"
+docker_target = "app"
+auto_refresh = true
+"#;
+        let conns: Connections = toml::from_str(toml).unwrap();
+        let ssh_config =
+            "Host prod-web1 prod-web2 prod-legacy\nHost prod-*\n    User deploy\nHost lab\n";
+        let all = conns.all_with(ssh_config);
+        let names: Vec<&str> = all.iter().map(|c| c.name.as_str()).collect();
+        // prod-web1 already has a connection of its own
+        assert_eq!(names, ["Local", "Web 1", "Prod: prod-web2"]);
+        assert_eq!(all[2].remote_host.as_deref(), Some("prod-web2"));
+        assert_eq!(all[2].docker_target.as_deref(), Some("app"));
+        assert_eq!(all[2].defaults.auto_refresh, Some(true));
+        assert!(!conns.is_generated(1));
+        assert!(conns.is_generated(2));
+
+        let content = toml::to_string_pretty(&conns).unwrap();
+        assert!(content.contains("[[connection_group]]"));
+        assert!(
+            !toml::to_string_pretty(&Connections::default())
+                .unwrap()
+                .contains("connection_group")
+        );
+    }
+
     #[test]
     fn test_add_connection() {
         let mut conns = Connections::default();
@@ -130,6 +459,8 @@ mod tests {
             name: "Test".to_string(),
             remote_host: Some("test@host".to_string()),
             docker_target: None,
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
         });
         assert_eq!(conns.connection.len(), 1);
         assert_eq!(conns.connection[0].name, "Test");
@@ -143,13 +474,18 @@ mod tests {
                     name: "A".to_string(),
                     remote_host: None,
                     docker_target: None,
+                    docker_context: None,
+                    defaults: ConnectionDefaults::default(),
                 },
                 Connection {
                     name: "B".to_string(),
                     remote_host: None,
                     docker_target: None,
+                    docker_context: None,
+                    defaults: ConnectionDefaults::default(),
                 },
             ],
+            ..Connections::default()
         };
         assert!(conns.remove(0));
         assert_eq!(conns.connection.len(), 1);
@@ -173,9 +509,13 @@ remote_host = "user@prod-server"
 name = "AI Lab + Docker"
 remote_host = "ailab"
 docker_target = "syntopic-dev"
+
+[[connection]]
+name = "Build farm"
+docker_context = "farm"
 "#;
         let conns: Connections = toml::from_str(toml).unwrap();
-        assert_eq!(conns.connection.len(), 2);
+        assert_eq!(conns.connection.len(), 3);
         assert_eq!(conns.connection[0].name, "Production");
         assert_eq!(
             conns.connection[0].remote_host,
@@ -188,6 +528,9 @@ docker_target = "syntopic-dev"
             conns.connection[1].docker_target,
             Some("syntopic-dev".to_string())
         );
+        assert!(conns.connection[1].docker_context.is_none());
+        assert_eq!(conns.connection[2].docker_context.as_deref(), Some("farm"));
+        assert!(conns.connection[2].remote_host.is_none());
     }
 
     #[test]
@@ -197,11 +540,92 @@ docker_target = "syntopic-dev"
                 name: "Test".to_string(),
                 remote_host: Some("host".to_string()),
                 docker_target: None,
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
             }],
+            ..Connections::default()
         };
         let serialized = toml::to_string_pretty(&conns).unwrap();
         assert!(serialized.contains("[[connection]]"));
         assert!(serialized.contains("name = \"Test\""));
         assert!(serialized.contains("remote_host = \"host\""));
     }
+
+    fn named(names: &[&str]) -> Connections {
+        Connections {
+            connection: names
+                .iter()
+                .map(|name| Connection {
+                    name: (*name).to_string(),
+                    remote_host: Some(format!("{name}-host")),
+                    docker_target: None,
+                    docker_context: None,
+                    defaults: ConnectionDefaults::default(),
+                })
+                .collect(),
+            ..Connections::default()
+        }
+    }
+
+    fn names(conns: &Connections) -> Vec<&str> {
+        conns.connection.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_update_connection() {
+        let mut conns = named(&["a", "b"]);
+        let edited = Connection {
+            name: "b2".to_string(),
+            remote_host: None,
+            docker_target: Some("app".to_string()),
+            docker_context: None,
+            defaults: ConnectionDefaults::default(),
+        };
+        assert!(conns.update(1, edited));
+        assert_eq!(names(&conns), vec!["a", "b2"]);
+        assert_eq!(conns.connection[1].docker_target.as_deref(), Some("app"));
+        assert!(!conns.update(2, Connection::local()));
+    }
+
+    #[test]
+    fn test_move_connections() {
+        let mut conns = named(&["a", "b", "c"]);
+        assert!(conns.move_up(2));
+        assert_eq!(names(&conns), vec!["a", "c", "b"]);
+        assert!(!conns.move_up(0));
+        assert!(conns.move_down(0));
+        assert_eq!(names(&conns), vec!["c", "a", "b"]);
+        assert!(!conns.move_down(2));
+        assert!(!conns.move_down(5));
+        assert_eq!(names(&conns), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_connection_defaults() {
+        let toml = r#"
+[[connection]]
+name = "Prod bastion"
+remote_host = "prod"
+default_filter = "ssh"
+auto_refresh = true
+refresh_interval = 30
+presets = ["Prod DB"]
+
+[[connection]]
+name = "Lab"
+remote_host = "lab"
+"#;
+        let conns: Connections = toml::from_str(toml).unwrap();
+        let prod = &conns.connection[0].defaults;
+        assert_eq!(prod.default_filter.as_deref(), Some("ssh"));
+        assert_eq!(prod.auto_refresh, Some(true));
+        assert_eq!(prod.refresh_interval, Some(30));
+        assert_eq!(prod.presets, Some(vec!["Prod DB".to_string()]));
+        assert_eq!(conns.connection[1].defaults, ConnectionDefaults::default());
+
+        let content = toml::to_string_pretty(&conns).unwrap();
+        let parsed: Connections = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.connection[0].defaults, *prod);
+        assert_eq!(parsed.connection[1].remote_host.as_deref(), Some("lab"));
+    }
 }
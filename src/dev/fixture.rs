@@ -0,0 +1,131 @@
+//! Docker containers a scenario starts for the TUI session.
+//!
+//! ```toml
+//! [[scenario]]
+//! name = "stack"
+//! ports = [{ port = 3000, label = "web-app" }]
+//! containers = [
+//!   { image = "redis:7", ports = ["6379:6379"] },
+//!   { image = "postgres:16", ports = ["5432:5432"], env = { POSTGRES_PASSWORD = "dev" } },
+//! ]
+//! ```
+//!
+//! Containers run with `--rm` and a `quay.scenario` label, and are stopped
+//! when the TUI exits.
+
+use crate::port::CommandError;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Container {
+    pub image: String,
+    /// Published ports, as for `docker run -p` (`6379:6379`, `127.0.0.1:8080:80`).
+    #[serde(default)]
+    pub ports: Vec<String>,
+    /// Container name; `quay-<scenario>-<n>` by default.
+    pub name: Option<String>,
+    /// Environment variables passed with `-e`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Container {
+    /// Arguments of the `docker run` that starts this container as `name`.
+    fn run_args(&self, scenario: &str, name: &str) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "run",
+            "--detach",
+            "--rm",
+            "--label",
+            &format!("quay.scenario={scenario}"),
+            "--name",
+            name,
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        for port in &self.ports {
+            args.extend(["--publish".to_string(), port.clone()]);
+        }
+        for (key, value) in &self.env {
+            args.extend(["--env".to_string(), format!("{key}={value}")]);
+        }
+        args.push(self.image.clone());
+        args
+    }
+}
+
+/// The containers started for a scenario.
+pub struct Fixtures {
+    names: Vec<String>,
+}
+
+impl Fixtures {
+    /// Start `containers` for `scenario`; if one fails, those already started are stopped.
+    pub async fn start(scenario: &str, containers: &[Container]) -> Result<Self> {
+        let mut fixtures = Self { names: Vec::new() };
+        for (index, container) in containers.iter().enumerate() {
+            let name = container
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("quay-{scenario}-{}", index + 1));
+            println!("Starting container {name} ({})", container.image);
+            if let Err(e) = docker(&container.run_args(scenario, &name)).await {
+                fixtures.stop().await;
+                return Err(e.context(format!("Failed to start {}", container.image)));
+            }
+            fixtures.names.push(name);
+        }
+        Ok(fixtures)
+    }
+
+    pub async fn stop(self) {
+        if self.names.is_empty() {
+            return;
+        }
+        println!("Stopping {}", self.names.join(", "));
+        let mut args = vec!["stop".to_string(), "--time".to_string(), "2".to_string()];
+        args.extend(self.names);
+        if let Err(e) = docker(&args).await {
+            eprintln!("Warning: {e:#}");
+        }
+    }
+}
+
+async fn docker(args: &[String]) -> Result<()> {
+    let output = Command::new("docker")
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .context("Failed to run docker")?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Err(CommandError::new("docker", &args, None, &output).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_args() {
+        let container = Container {
+            image: "postgres:16".to_string(),
+            ports: vec!["5432:5432".to_string()],
+            name: None,
+            env: BTreeMap::from([("POSTGRES_PASSWORD".to_string(), "dev".to_string())]),
+        };
+        assert_eq!(
+            container.run_args("stack", "quay-stack-1").join(" "),
+            "run --detach --rm --label quay.scenario=stack --name quay-stack-1 \
+             --publish 5432:5432 --env POSTGRES_PASSWORD=dev postgres:16"
+        );
+    }
+}
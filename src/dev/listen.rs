@@ -1,25 +1,134 @@
 use anyhow::Result;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::io::AsyncWriteExt;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::task::JoinHandle;
 
+/// How the dev listeners answer, to exercise probing and health checks.
+#[derive(clap::Args, Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ListenArgs {
+    /// Respond with HTTP 200 to connections
+    #[arg(long)]
+    pub http: bool,
+    /// Also bind UDP on the same ports and answer each datagram
+    #[arg(long)]
+    pub udp: bool,
+    /// Text sent to every connection (the response body with --http)
+    #[arg(long, value_name = "TEXT")]
+    pub banner: Option<String>,
+    /// Wait this many milliseconds before responding
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub latency: u64,
+    /// Accept, then drop this percentage of connections without a response
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub flaky: Option<u8>,
+}
+
+impl ListenArgs {
+    fn describe(&self) -> String {
+        let mut traits = Vec::new();
+        if self.http {
+            traits.push("http".to_string());
+        }
+        if self.banner.is_some() {
+            traits.push("banner".to_string());
+        }
+        if self.latency > 0 {
+            traits.push(format!("{}ms latency", self.latency));
+        }
+        if let Some(percent) = self.flaky {
+            traits.push(format!("drops {percent}%"));
+        }
+        if traits.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", traits.join(", "))
+        }
+    }
+
+    /// What to send back: the banner, or the HTTP response carrying it.
+    fn response(&self, port: u16) -> Option<String> {
+        if self.http {
+            let body = match &self.banner {
+                Some(banner) => format!("{banner}\n"),
+                None => format!("quay dev listener on :{port}\n"),
+            };
+            Some(format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ))
+        } else {
+            self.banner.as_ref().map(|banner| format!("{banner}\n"))
+        }
+    }
+}
+
+/// Decides which connections a `--flaky` listener drops: exactly `percent`
+/// of them, spread evenly, so a test sees the same pattern every run.
+struct Flaky {
+    percent: u64,
+    seen: AtomicU64,
+}
+
+impl Flaky {
+    fn new(percent: u8) -> Self {
+        Self {
+            percent: u64::from(percent),
+            seen: AtomicU64::new(0),
+        }
+    }
+
+    fn drop_next(&self) -> bool {
+        let n = self.seen.fetch_add(1, Ordering::Relaxed) + 1;
+        n * self.percent / 100 > (n - 1) * self.percent / 100
+    }
+}
+
 /// Bind and spawn TCP listeners on the given ports, returning their `JoinHandles`.
 /// Binding failures are warned and skipped; returns Err only if no port could be bound.
-pub async fn spawn_listeners(ports: Vec<u16>, http: bool) -> Result<Vec<JoinHandle<()>>> {
+pub async fn spawn_listeners(ports: Vec<u16>, args: &ListenArgs) -> Result<Vec<JoinHandle<()>>> {
     let mut tasks = Vec::new();
+    let args = Arc::new(args.clone());
 
     for port in &ports {
         let port = *port;
+        let flaky = Arc::new(Flaky::new(args.flaky.unwrap_or(0)));
         match TcpListener::bind(format!("127.0.0.1:{port}")).await {
             Ok(listener) => {
-                println!("Listening on :{port}");
-                let task = tokio::spawn(accept_loop(listener, port, http));
+                println!("Listening on :{port}{}", args.describe());
+                let task = tokio::spawn(accept_loop(
+                    listener,
+                    port,
+                    Arc::clone(&args),
+                    Arc::clone(&flaky),
+                ));
                 tasks.push(task);
             }
             Err(e) => {
                 eprintln!("Warning: failed to bind :{port} — {e}");
             }
         }
+        if args.udp {
+            match UdpSocket::bind(format!("127.0.0.1:{port}")).await {
+                Ok(socket) => {
+                    println!("Listening on :{port}/udp{}", args.describe());
+                    tasks.push(tokio::spawn(udp_loop(
+                        socket,
+                        port,
+                        Arc::clone(&args),
+                        flaky,
+                    )));
+                }
+                Err(e) => {
+                    eprintln!("Warning: failed to bind :{port}/udp — {e}");
+                }
+            }
+        }
     }
 
     if tasks.is_empty() {
@@ -29,12 +138,12 @@ pub async fn spawn_listeners(ports: Vec<u16>, http: bool) -> Result<Vec<JoinHand
     Ok(tasks)
 }
 
-pub async fn run(ports: Vec<u16>, http: bool) -> Result<()> {
+pub async fn run(ports: Vec<u16>, args: &ListenArgs) -> Result<()> {
     if ports.is_empty() {
         anyhow::bail!("No ports specified. Usage: quay dev listen <port1> <port2> ...");
     }
 
-    let tasks = spawn_listeners(ports, http).await?;
+    let tasks = spawn_listeners(ports, args).await?;
 
     println!("Press Ctrl+C to stop");
     tokio::signal::ctrl_c().await?;
@@ -47,23 +156,12 @@ pub async fn run(ports: Vec<u16>, http: bool) -> Result<()> {
     Ok(())
 }
 
-async fn accept_loop(listener: TcpListener, port: u16, http: bool) {
+async fn accept_loop(listener: TcpListener, port: u16, args: Arc<ListenArgs>, flaky: Arc<Flaky>) {
     loop {
         match listener.accept().await {
-            Ok((mut stream, addr)) => {
-                if http {
-                    let body = format!("quay dev listener on :{port}\n");
-                    let response = format!(
-                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\n{}",
-                        body.len(),
-                        body
-                    );
-                    let _ = stream.write_all(response.as_bytes()).await;
-                    let _ = stream.shutdown().await;
-                }
-                // Without --http, accept and drop (sufficient for probe detection)
-                drop(stream);
-                let _ = addr; // suppress unused warning in non-http mode
+            Ok((stream, _)) => {
+                // A slow response mustn't hold up the next connection
+                tokio::spawn(respond(stream, port, Arc::clone(&args), Arc::clone(&flaky)));
             }
             Err(e) => {
                 eprintln!("Accept error on :{port}: {e}");
@@ -71,3 +169,101 @@ async fn accept_loop(listener: TcpListener, port: u16, http: bool) {
         }
     }
 }
+
+async fn respond(mut stream: TcpStream, port: u16, args: Arc<ListenArgs>, flaky: Arc<Flaky>) {
+    if flaky.drop_next() {
+        // Accepted, then gone before a byte is sent
+        return;
+    }
+    if args.latency > 0 {
+        tokio::time::sleep(Duration::from_millis(args.latency)).await;
+    }
+    // Without --http or --banner, accept and drop (sufficient for probe detection)
+    if let Some(response) = args.response(port) {
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+/// Answer each datagram with the banner (or echo it back), after the latency.
+async fn udp_loop(socket: UdpSocket, port: u16, args: Arc<ListenArgs>, flaky: Arc<Flaky>) {
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(e) => {
+                eprintln!("Receive error on :{port}/udp: {e}");
+                continue;
+            }
+        };
+        if flaky.drop_next() {
+            continue;
+        }
+        if args.latency > 0 {
+            tokio::time::sleep(Duration::from_millis(args.latency)).await;
+        }
+        let reply = match &args.banner {
+            Some(banner) => format!("{banner}\n").into_bytes(),
+            None => buf[..len].to_vec(),
+        };
+        let _ = socket.send_to(&reply, peer).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_flaky_drops_the_given_share() {
+        let flaky = Flaky::new(25);
+        let dropped: Vec<bool> = (0..8).map(|_| flaky.drop_next()).collect();
+        assert_eq!(
+            dropped,
+            vec![false, false, false, true, false, false, false, true]
+        );
+        assert!((0..10).all(|_| !Flaky::new(0).drop_next()));
+        let always = Flaky::new(100);
+        assert!((0..10).all(|_| always.drop_next()));
+    }
+
+    #[test]
+    fn test_banner_latency_and_flaky() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let args = ListenArgs {
+                banner: Some("SSH-2.0-quay".to_string()),
+                latency: 30,
+                flaky: Some(50),
+                ..ListenArgs::default()
+            };
+            let server = tokio::spawn(accept_loop(
+                listener,
+                addr.port(),
+                Arc::new(args),
+                Arc::new(Flaky::new(50)),
+            ));
+
+            let mut replies = Vec::new();
+            for _ in 0..2 {
+                let mut stream = TcpStream::connect(addr).await.unwrap();
+                let started = std::time::Instant::now();
+                let mut reply = String::new();
+                let _ = stream.read_to_string(&mut reply).await;
+                replies.push((reply, started.elapsed()));
+            }
+            server.abort();
+
+            // The first connection is answered late, the second dropped
+            assert_eq!(replies[0].0, "SSH-2.0-quay\n");
+            assert!(replies[0].1 >= Duration::from_millis(30));
+            assert_eq!(replies[1].0, "");
+        });
+    }
+}
@@ -1,6 +1,16 @@
-use crate::port::{PortEntry, PortSource, dedup_entries};
+use crate::port::established::Established;
+use crate::port::usage::Usage;
+use crate::port::{Collection, PortEntry, PortSource, SourceStatus, merge_entries};
 use anyhow::Result;
 
+/// Usage as ps or docker stats reports it: percent of one core, resident KiB.
+fn usage(cpu: f32, rss_kib: u64) -> Usage {
+    Usage {
+        cpu,
+        memory: rss_kib * 1024,
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 pub fn generate_mock_entries() -> Vec<PortEntry> {
     let mut entries = vec![
@@ -8,74 +18,48 @@ pub fn generate_mock_entries() -> Vec<PortEntry> {
         PortEntry {
             source: PortSource::Local,
             local_port: 3000,
-            remote_host: None,
-            remote_port: None,
             process_name: "node".to_string(),
             pid: Some(1234),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(3.2, 182_400)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Local,
             local_port: 8080,
-            remote_host: None,
-            remote_port: None,
             process_name: "python".to_string(),
             pid: Some(2345),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.4, 61_200)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Local,
             local_port: 4200,
-            remote_host: None,
-            remote_port: None,
             process_name: "ng".to_string(),
             pid: Some(3456),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
-            is_open: false,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(11.8, 412_800)),
+            ..PortEntry::default()
         },
         // Duplicate LOCAL entries that overlap with SSH/Docker
         // (simulates lsof detecting the ssh/docker-proxy LISTEN socket)
         PortEntry {
             source: PortSource::Local,
             local_port: 9000,
-            remote_host: None,
-            remote_port: None,
             process_name: "ssh".to_string(),
             pid: Some(4567),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.0, 8_900)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Local,
             local_port: 5432,
-            remote_host: None,
-            remote_port: None,
             process_name: "docker-proxy".to_string(),
             pid: Some(9876),
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.1, 4_100)),
+            ..PortEntry::default()
         },
         // SSH x 2
         PortEntry {
@@ -85,12 +69,10 @@ pub fn generate_mock_entries() -> Vec<PortEntry> {
             remote_port: Some(5432),
             process_name: "ssh".to_string(),
             pid: Some(4567),
-            container_id: None,
-            container_name: None,
             ssh_host: Some("bastion.example.com".to_string()),
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.0, 8_900)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Ssh,
@@ -99,69 +81,315 @@ pub fn generate_mock_entries() -> Vec<PortEntry> {
             remote_port: Some(9090),
             process_name: "ssh -R".to_string(),
             pid: Some(5678),
-            container_id: None,
-            container_name: None,
             ssh_host: Some("gateway.internal".to_string()),
-            is_open: false,
-            is_loopback: false,
-            forwarded_port: None,
+            // Bound on loopback only, as with GatewayPorts off
+            reverse_bind: Some(vec!["127.0.0.1".to_string()]),
+            usage: Some(usage(0.0, 7_600)),
+            ..PortEntry::default()
         },
         // Docker x 3
         PortEntry {
             source: PortSource::Docker,
             local_port: 5432,
-            remote_host: None,
             remote_port: Some(5432),
             process_name: "postgres:15".to_string(),
-            pid: None,
             container_id: Some("abc123def456".to_string()),
             container_name: Some("postgres".to_string()),
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(1.2, 98_300)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Docker,
             local_port: 6379,
-            remote_host: None,
             remote_port: Some(6379),
             process_name: "redis:7".to_string(),
-            pid: None,
             container_id: Some("def456abc789".to_string()),
             container_name: Some("redis".to_string()),
-            ssh_host: None,
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.3, 12_700)),
+            ..PortEntry::default()
         },
         PortEntry {
             source: PortSource::Docker,
             local_port: 27017,
-            remote_host: None,
             remote_port: Some(27017),
             process_name: "mongo:6".to_string(),
-            pid: None,
             container_id: Some("789abc123def".to_string()),
             container_name: Some("mongo".to_string()),
-            ssh_host: None,
-            is_open: false,
-            is_loopback: false,
-            forwarded_port: None,
+            usage: Some(usage(0.8, 184_000)),
+            ..PortEntry::default()
         },
     ];
 
-    // Deduplicate: remove LOCAL entries that overlap with SSH/Docker
-    dedup_entries(&mut entries);
+    // Merge LOCAL entries into the SSH/Docker entries on the same port
+    merge_entries(&mut entries, false);
+    for entry in entries.iter_mut().filter(|e| e.is_open) {
+        entry.established = Some(mock_established(entry).len());
+    }
 
     // Sort: open first, then by port number (same as collect_all)
     entries.sort_by_key(|e| (!e.is_open, e.local_port));
     entries
 }
 
-pub async fn run() -> Result<()> {
-    let entries = generate_mock_entries();
-    crate::run_tui_with_entries(Some(entries), None, None).await
+/// Made-up clients of an open `entry` for the Established popup: one to three,
+/// fixed per port.
+pub fn mock_established(entry: &PortEntry) -> Vec<Established> {
+    if !entry.is_open {
+        return Vec::new();
+    }
+    let clients = [
+        ("127.0.0.1", 51234, 4312, "psql"),
+        ("10.0.0.12", 60412, 0, ""),
+        ("[::1]", 49822, 7781, "node"),
+    ];
+    clients
+        .iter()
+        .take(usize::from(entry.local_port % 3) + 1)
+        .map(|&(addr, port, pid, process)| Established {
+            local_port: entry.local_port,
+            peer: format!("{addr}:{port}"),
+            // Remote clients have no local process
+            pid: (pid > 0).then_some(pid),
+            process: (!process.is_empty()).then(|| process.to_string()),
+        })
+        .collect()
+}
+
+/// Made-up log lines of `entry` for the Output popup.
+pub fn mock_output(entry: &PortEntry) -> Vec<String> {
+    let port = entry.local_port;
+    let mut lines = vec![
+        format!("{} starting", entry.process_name),
+        format!("listening on 0.0.0.0:{port}"),
+    ];
+    lines.extend((1..=40).map(|i| {
+        let status = if i % 7 == 0 { 404 } else { 200 };
+        format!("GET /api/items/{i} {status} {}ms", i * 3 % 50 + 1)
+    }));
+    lines
+}
+
+/// Parse a `--sources` value.
+pub fn parse_source(value: &str) -> Result<PortSource, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "local" => Ok(PortSource::Local),
+        "docker" => Ok(PortSource::Docker),
+        "ssh" => Ok(PortSource::Ssh),
+        _ => Err(format!(
+            "unknown source '{value}' (expected local, docker, or ssh)"
+        )),
+    }
+}
+
+/// A splitmix64 generator, so a `--seed` reproduces the same dataset and churn.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        usize::try_from(self.next() % n as u64).unwrap_or(0)
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// Ports below this are left out, like on a dev machine without root.
+const FIRST_PORT: u16 = 1024;
+/// The generator can't make more entries than there are free ports.
+const MAX_ENTRIES: usize = 10_000;
+
+const PROCESSES: &[&str] = &[
+    "node", "python", "ruby", "java", "deno", "vite", "uvicorn", "rails",
+];
+const IMAGES: &[(&str, &str)] = &[
+    ("postgres", "postgres:16"),
+    ("redis", "redis:7"),
+    ("mongo", "mongo:7"),
+    ("nginx", "nginx:1.27"),
+    ("rabbitmq", "rabbitmq:3"),
+    ("minio", "minio/minio"),
+    ("elastic", "elasticsearch:8"),
+    ("mysql", "mysql:8"),
+];
+const SSH_HOSTS: &[&str] = &[
+    "bastion.example.com",
+    "db.internal",
+    "gpu-01.lab",
+    "staging.example.com",
+];
+
+/// Randomized mock data for `quay dev mock --entries N`; with `--churn` every
+/// refresh changes it, to stress the table and the refresh logic.
+pub struct MockGenerator {
+    rng: Rng,
+    sources: Vec<PortSource>,
+    entries: Vec<PortEntry>,
+    /// Entries made so far, for unique PIDs and container IDs.
+    made: u32,
+}
+
+impl MockGenerator {
+    /// `count` entries spread over `sources` (all of Local, Docker, and SSH when empty).
+    pub fn new(count: usize, sources: &[PortSource], seed: u64) -> Self {
+        // In the order the header lists sources
+        let sources = [PortSource::Local, PortSource::Docker, PortSource::Ssh]
+            .into_iter()
+            .filter(|source| sources.is_empty() || sources.contains(source))
+            .collect();
+        let mut generator = Self {
+            rng: Rng(seed),
+            sources,
+            entries: Vec::new(),
+            made: 0,
+        };
+        for _ in 0..count.min(MAX_ENTRIES) {
+            generator.add();
+        }
+        generator.sort();
+        generator
+    }
+
+    pub fn entries(&self) -> Vec<PortEntry> {
+        self.entries.clone()
+    }
+
+    /// The entries as a collection in which every generated source succeeded.
+    pub fn collection(&self) -> Collection {
+        Collection {
+            entries: self.entries(),
+            sources: self
+                .sources
+                .iter()
+                .map(|source| (source.clone(), SourceStatus::Ok))
+                .collect(),
+            timings: Vec::new(),
+        }
+    }
+
+    /// One refresh worth of change: about 5% of the entries go away, as many
+    /// new ones appear, and a few others open or close.
+    pub fn churn(&mut self) {
+        let changes = (self.entries.len() / 20).max(1);
+        for _ in 0..changes.min(self.entries.len()) {
+            let index = self.rng.below(self.entries.len());
+            self.entries.swap_remove(index);
+        }
+        for _ in 0..changes {
+            self.add();
+        }
+        let rng = &mut self.rng;
+        for entry in &mut self.entries {
+            if rng.chance(2) {
+                entry.is_open = !entry.is_open;
+            }
+        }
+        self.sort();
+    }
+
+    fn add(&mut self) {
+        if self.entries.len() >= MAX_ENTRIES {
+            return;
+        }
+        let span = usize::from(u16::MAX - FIRST_PORT) + 1;
+        let port = loop {
+            let port = FIRST_PORT + u16::try_from(self.rng.below(span)).unwrap_or(0);
+            if self.entries.iter().all(|e| e.local_port != port) {
+                break port;
+            }
+        };
+        self.made += 1;
+        let pid = 10_000 + self.made;
+        let source = self.sources[self.rng.below(self.sources.len())].clone();
+        let mut entry = PortEntry {
+            source: source.clone(),
+            local_port: port,
+            process_name: String::new(),
+            pid: Some(pid),
+            is_open: self.rng.chance(85),
+            is_loopback: self.rng.chance(30),
+            ..PortEntry::default()
+        };
+        match source {
+            PortSource::Docker => {
+                let (name, image) = IMAGES[self.rng.below(IMAGES.len())];
+                entry.process_name = image.to_string();
+                entry.pid = None;
+                entry.container_id = Some(format!("{:012x}", self.rng.next() >> 16));
+                entry.container_name = Some(format!("{name}-{}", self.made));
+                entry.remote_port = Some(port);
+            }
+            PortSource::Ssh => {
+                let host = SSH_HOSTS[self.rng.below(SSH_HOSTS.len())];
+                entry.process_name = "ssh".to_string();
+                entry.ssh_host = Some(host.to_string());
+                entry.remote_host = Some("localhost".to_string());
+                entry.remote_port = Some(port);
+            }
+            PortSource::Local | PortSource::Scan => {
+                entry.process_name = PROCESSES[self.rng.below(PROCESSES.len())].to_string();
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    /// Open first, then by port number (same as `collect_all`).
+    fn sort(&mut self) {
+        self.entries.sort_by_key(|e| (!e.is_open, e.local_port));
+    }
+}
+
+/// How `quay dev mock` fills the TUI: the fixed demo data, or a generator.
+pub struct MockOptions {
+    pub entries: Option<usize>,
+    pub sources: Vec<PortSource>,
+    pub churn: bool,
+    pub seed: Option<u64>,
+}
+
+pub async fn run(options: MockOptions) -> Result<()> {
+    if options.entries.is_none() && options.sources.is_empty() && !options.churn {
+        let entries = generate_mock_entries();
+        return crate::run_tui_with_entries(Some(entries.into()), None, None, None, None).await;
+    }
+    let seed = options.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    });
+    let generator = MockGenerator::new(options.entries.unwrap_or(50), &options.sources, seed);
+    let feed = MockFeed {
+        entries: generator.entries(),
+        churn: options.churn.then_some(generator),
+    };
+    crate::run_tui_with_entries(Some(feed), None, None, None, None).await
+}
+
+/// Entries the TUI starts with instead of scanning, and, with `--churn`, the
+/// generator that changes them on every refresh.
+pub struct MockFeed {
+    pub entries: Vec<PortEntry>,
+    pub churn: Option<MockGenerator>,
+}
+
+impl From<Vec<PortEntry>> for MockFeed {
+    fn from(entries: Vec<PortEntry>) -> Self {
+        Self {
+            entries,
+            churn: None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +397,37 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn test_generator_is_reproducible() {
+        let sources = vec![PortSource::Docker, PortSource::Ssh];
+        let mut a = MockGenerator::new(200, &sources, 7);
+        let mut b = MockGenerator::new(200, &sources, 7);
+        let ports = |g: &MockGenerator| g.entries.iter().map(|e| e.local_port).collect::<Vec<_>>();
+        assert_eq!(a.entries.len(), 200);
+        assert_eq!(ports(&a), ports(&b));
+        assert!(
+            a.entries
+                .iter()
+                .all(|e| matches!(e.source, PortSource::Docker | PortSource::Ssh))
+        );
+        let unique: HashSet<u16> = ports(&a).into_iter().collect();
+        assert_eq!(unique.len(), 200);
+
+        let before: HashSet<u16> = ports(&a).into_iter().collect();
+        a.churn();
+        b.churn();
+        assert_eq!(ports(&a), ports(&b));
+        let after: HashSet<u16> = ports(&a).into_iter().collect();
+        assert_eq!(after.len(), 200);
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(parse_source("Docker"), Ok(PortSource::Docker));
+        assert!(parse_source("scan").is_err());
+    }
+
     #[test]
     fn test_mock_entries_not_empty() {
         let entries = generate_mock_entries();
@@ -1,10 +1,17 @@
 pub mod check;
+pub mod fixture;
 pub mod listen;
 pub mod mock;
+pub mod record;
+pub mod replay;
 
+use crate::config::{self, Config, ConfigDiagnostic};
 use crate::port::{PortEntry, PortSource};
 use anyhow::Result;
 use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Subcommand)]
 pub enum DevCommands {
@@ -12,13 +19,13 @@ pub enum DevCommands {
     Listen {
         /// Ports to listen on
         ports: Vec<u16>,
-        /// Respond with HTTP 200 to connections
-        #[arg(long)]
-        http: bool,
+        #[command(flatten)]
+        args: listen::ListenArgs,
     },
     /// Run a predefined scenario (set of listeners)
     Scenario {
-        /// Scenario name (web, micro, full)
+        /// Scenario name (web, micro, full, or one from scenarios.toml)
+        #[arg(add = ArgValueCandidates::new(crate::completions::scenario_names))]
         name: Option<String>,
         /// List available scenarios
         #[arg(long)]
@@ -30,126 +37,219 @@ pub enum DevCommands {
         ports: Vec<u16>,
     },
     /// Launch TUI with mock data (no real port scanning)
-    Mock,
+    Mock {
+        /// Generate this many random entries instead of the fixed demo data
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..=10_000))]
+        entries: Option<u16>,
+        /// Sources to generate entries for (local, docker, ssh)
+        #[arg(long, value_delimiter = ',', value_parser = mock::parse_source)]
+        sources: Vec<PortSource>,
+        /// Add and remove entries on every refresh
+        #[arg(long)]
+        churn: bool,
+        /// Seed for a reproducible dataset
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Launch the mock TUI and press the keys of a JSON script
+    Replay {
+        /// Script of key presses with delays
+        script: std::path::PathBuf,
+    },
+    /// Save the raw output of a real scan (lsof/ss, docker ps, ps) as a fixture
+    Record {
+        /// Fixture directory to write
+        dir: PathBuf,
+        /// Scan this host over SSH instead of this machine
+        #[arg(short, long)]
+        remote: Option<String>,
+    },
+    /// Launch the TUI with the entries parsed from a recorded fixture
+    ReplayFixture {
+        /// Fixture directory written by `quay dev record`
+        dir: PathBuf,
+    },
 }
 
+/// One port of a scenario.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ScenarioEntry {
     pub port: u16,
-    pub label: &'static str,
+    #[serde(default)]
+    pub label: String,
+    /// Start a listener on the port; otherwise it shows as a closed row.
+    #[serde(rename = "listen", default = "default_listen")]
     pub should_listen: bool,
 }
 
+fn default_listen() -> bool {
+    true
+}
+
+/// A set of ports `quay dev scenario` listens on and shows.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Scenario {
-    pub name: &'static str,
-    pub description: &'static str,
-    pub entries: &'static [ScenarioEntry],
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "ports", default)]
+    pub entries: Vec<ScenarioEntry>,
+    /// How the listeners answer (`http`, `banner`, `latency`, ...).
+    #[serde(flatten)]
+    pub listen: listen::ListenArgs,
+    /// Docker containers started for the session.
+    #[serde(default)]
+    pub containers: Vec<fixture::Container>,
+    /// Defined in scenarios.toml rather than built in.
+    #[serde(skip)]
+    pub custom: bool,
 }
 
-pub const SCENARIOS: &[Scenario] = &[
-    Scenario {
-        name: "web",
-        description: "Web app + DB + Cache",
-        entries: &[
-            ScenarioEntry {
-                port: 3000,
-                label: "web-app",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 5432,
-                label: "postgres",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 6379,
-                label: "redis",
-                should_listen: true,
-            },
+/// User scenarios (scenarios.toml).
+///
+/// ```toml
+/// [[scenario]]
+/// name = "shop"
+/// description = "Storefront + API + DB"
+/// http = true
+/// ports = [
+///   { port = 3000, label = "storefront" },
+///   { port = 4000, label = "api" },
+///   { port = 9090, label = "metrics", listen = false },
+/// ]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenarios {
+    #[serde(rename = "scenario", default)]
+    pub scenarios: Vec<Scenario>,
+}
+
+impl Scenarios {
+    pub fn scenarios_path() -> Option<PathBuf> {
+        Config::config_dir().map(|p| p.join("scenarios.toml"))
+    }
+
+    pub fn load() -> Result<Self, ConfigDiagnostic> {
+        config::load_file(Self::scenarios_path())
+    }
+}
+
+/// A built-in scenario's ports: port, label, and whether it listens.
+type BuiltinPorts = &'static [(u16, &'static str, bool)];
+
+/// Built-in scenarios: name, description, and ports.
+const BUILTIN: &[(&str, &str, BuiltinPorts)] = &[
+    (
+        "web",
+        "Web app + DB + Cache",
+        &[
+            (3000, "web-app", true),
+            (5432, "postgres", true),
+            (6379, "redis", true),
         ],
-    },
-    Scenario {
-        name: "micro",
-        description: "5 microservices",
-        entries: &[
-            ScenarioEntry {
-                port: 3001,
-                label: "svc-auth",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 3002,
-                label: "svc-users",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 3003,
-                label: "svc-orders",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 3004,
-                label: "svc-payments",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 3005,
-                label: "svc-notifications",
-                should_listen: true,
-            },
+    ),
+    (
+        "micro",
+        "5 microservices",
+        &[
+            (3001, "svc-auth", true),
+            (3002, "svc-users", true),
+            (3003, "svc-orders", true),
+            (3004, "svc-payments", true),
+            (3005, "svc-notifications", true),
         ],
-    },
-    Scenario {
-        name: "full",
-        description: "Mixed open/closed ports",
-        entries: &[
-            ScenarioEntry {
-                port: 3000,
-                label: "web-app",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 5432,
-                label: "postgres",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 6379,
-                label: "redis",
-                should_listen: true,
-            },
-            ScenarioEntry {
-                port: 8080,
-                label: "proxy (inactive)",
-                should_listen: false,
-            },
-            ScenarioEntry {
-                port: 9090,
-                label: "metrics (inactive)",
-                should_listen: false,
-            },
+    ),
+    (
+        "full",
+        "Mixed open/closed ports",
+        &[
+            (3000, "web-app", true),
+            (5432, "postgres", true),
+            (6379, "redis", true),
+            (8080, "proxy (inactive)", false),
+            (9090, "metrics (inactive)", false),
         ],
-    },
+    ),
 ];
 
-pub fn find_scenario(name: &str) -> Option<&'static Scenario> {
-    SCENARIOS.iter().find(|s| s.name == name)
+pub fn builtin_scenarios() -> Vec<Scenario> {
+    BUILTIN
+        .iter()
+        .map(|(name, description, ports)| Scenario {
+            name: (*name).to_string(),
+            description: (*description).to_string(),
+            entries: ports
+                .iter()
+                .map(|(port, label, should_listen)| ScenarioEntry {
+                    port: *port,
+                    label: (*label).to_string(),
+                    should_listen: *should_listen,
+                })
+                .collect(),
+            listen: listen::ListenArgs::default(),
+            containers: Vec::new(),
+            custom: false,
+        })
+        .collect()
+}
+
+/// Built-in scenarios merged with `custom` ones; a custom scenario replaces
+/// the built-in of the same name.
+pub fn merge_scenarios(custom: Vec<Scenario>) -> Vec<Scenario> {
+    let mut scenarios = builtin_scenarios();
+    for mut scenario in custom {
+        scenario.custom = true;
+        match scenarios.iter_mut().find(|s| s.name == scenario.name) {
+            Some(existing) => *existing = scenario,
+            None => scenarios.push(scenario),
+        }
+    }
+    scenarios
+}
+
+/// Built-in and scenarios.toml scenarios.
+pub fn load_scenarios() -> Result<Vec<Scenario>, ConfigDiagnostic> {
+    Ok(merge_scenarios(Scenarios::load()?.scenarios))
+}
+
+pub fn find_scenario<'a>(scenarios: &'a [Scenario], name: &str) -> Option<&'a Scenario> {
+    scenarios.iter().find(|s| s.name == name)
 }
 
-pub async fn run_dev(cmd: DevCommands) -> Result<()> {
+pub async fn run_dev(cmd: DevCommands, color: bool) -> Result<()> {
     match cmd {
-        DevCommands::Listen { ports, http } => listen::run(ports, http).await,
+        DevCommands::Listen { ports, args } => listen::run(ports, &args).await,
         DevCommands::Scenario { name, list } => run_scenario(name, list).await,
-        DevCommands::Check { ports } => check::run(ports).await,
-        DevCommands::Mock => mock::run().await,
+        DevCommands::Check { ports } => check::run(ports, color).await,
+        DevCommands::Mock {
+            entries,
+            sources,
+            churn,
+            seed,
+        } => {
+            mock::run(mock::MockOptions {
+                entries: entries.map(usize::from),
+                sources,
+                churn,
+                seed,
+            })
+            .await
+        }
+        DevCommands::Replay { script } => replay::run(&script).await,
+        DevCommands::Record { dir, remote } => record::record(&dir, remote.as_deref()).await,
+        DevCommands::ReplayFixture { dir } => record::replay(&dir).await,
     }
 }
 
 async fn run_scenario(name: Option<String>, list: bool) -> Result<()> {
+    let scenarios = load_scenarios()?;
     if list {
         println!("Available scenarios:");
         println!("{:<10} {:<30} PORTS", "NAME", "DESCRIPTION");
         println!("{}", "-".repeat(60));
-        for scenario in SCENARIOS {
+        for scenario in &scenarios {
             let ports: Vec<String> = scenario
                 .entries
                 .iter()
@@ -161,12 +261,19 @@ async fn run_scenario(name: Option<String>, list: bool) -> Result<()> {
                     }
                 })
                 .collect();
-            println!(
-                "{:<10} {:<30} {}",
-                scenario.name,
-                scenario.description,
-                ports.join(", ")
-            );
+            let ports = match scenario.containers.len() {
+                0 => ports.join(", "),
+                n => format!("{} + {n} container(s)", ports.join(", ")),
+            };
+            let description = if scenario.custom {
+                format!("{} (custom)", scenario.description)
+            } else {
+                scenario.description.clone()
+            };
+            println!("{:<10} {:<30} {}", scenario.name, description, ports);
+        }
+        if let Some(path) = Scenarios::scenarios_path() {
+            println!("\nCustom scenarios: {}", path.display());
         }
         return Ok(());
     }
@@ -174,7 +281,7 @@ async fn run_scenario(name: Option<String>, list: bool) -> Result<()> {
     let name = name.ok_or_else(|| {
         anyhow::anyhow!("Scenario name required. Use --list to see available scenarios.")
     })?;
-    let scenario = find_scenario(&name).ok_or_else(|| {
+    let scenario = find_scenario(&scenarios, &name).ok_or_else(|| {
         anyhow::anyhow!("Unknown scenario '{name}'. Use --list to see available scenarios.")
     })?;
 
@@ -195,7 +302,7 @@ async fn run_scenario(name: Option<String>, list: bool) -> Result<()> {
     let handles = if listen_ports.is_empty() {
         Vec::new()
     } else {
-        match listen::spawn_listeners(listen_ports, false).await {
+        match listen::spawn_listeners(listen_ports, &scenario.listen).await {
             Ok(h) => h,
             Err(e) => {
                 eprintln!("Note: could not bind listeners ({e}), showing scenario entries only");
@@ -204,36 +311,51 @@ async fn run_scenario(name: Option<String>, list: bool) -> Result<()> {
         }
     };
 
-    // Build PortEntry list from all scenario entries
+    // Containers make the scenario live: the TUI collects for real, so the
+    // Docker collection runs end to end against them
+    let result = if scenario.containers.is_empty() {
+        crate::run_tui_with_entries(
+            Some(scenario_entries(scenario).into()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+    } else {
+        match fixture::Fixtures::start(&scenario.name, &scenario.containers).await {
+            Ok(fixtures) => {
+                let result = crate::run_tui(None, None, false, crate::ComposeArgs::default()).await;
+                fixtures.stop().await;
+                result
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    // Abort listeners on TUI exit
+    for handle in handles {
+        handle.abort();
+    }
+
+    result
+}
+
+/// Rows for every port of `scenario`, open or closed as declared.
+fn scenario_entries(scenario: &Scenario) -> Vec<PortEntry> {
     let mut entries: Vec<PortEntry> = scenario
         .entries
         .iter()
         .map(|e| PortEntry {
             source: PortSource::Local,
             local_port: e.port,
-            remote_host: None,
-            remote_port: None,
-            process_name: e.label.to_string(),
-            pid: None,
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
+            process_name: e.label.clone(),
             is_open: e.should_listen,
-            is_loopback: false,
-            forwarded_port: None,
+            ..PortEntry::default()
         })
         .collect();
     entries.sort_by_key(|e| (!e.is_open, e.local_port));
-
-    // Launch TUI with the scenario entries
-    let result = crate::run_tui_with_entries(Some(entries), None, None).await;
-
-    // Abort listeners on TUI exit
-    for handle in handles {
-        handle.abort();
-    }
-
-    result
+    entries
 }
 
 #[cfg(test)]
@@ -242,28 +364,32 @@ mod tests {
 
     #[test]
     fn test_scenario_lookup() {
-        assert!(find_scenario("web").is_some());
-        assert!(find_scenario("micro").is_some());
-        assert!(find_scenario("full").is_some());
-        assert!(find_scenario("nonexistent").is_none());
+        let scenarios = builtin_scenarios();
+        assert!(find_scenario(&scenarios, "web").is_some());
+        assert!(find_scenario(&scenarios, "micro").is_some());
+        assert!(find_scenario(&scenarios, "full").is_some());
+        assert!(find_scenario(&scenarios, "nonexistent").is_none());
     }
 
     #[test]
     fn test_scenario_web_ports() {
-        let scenario = find_scenario("web").unwrap();
+        let scenarios = builtin_scenarios();
+        let scenario = find_scenario(&scenarios, "web").unwrap();
         let ports: Vec<u16> = scenario.entries.iter().map(|e| e.port).collect();
         assert_eq!(ports, vec![3000, 5432, 6379]);
     }
 
     #[test]
     fn test_scenario_micro_has_five() {
-        let scenario = find_scenario("micro").unwrap();
+        let scenarios = builtin_scenarios();
+        let scenario = find_scenario(&scenarios, "micro").unwrap();
         assert_eq!(scenario.entries.len(), 5);
     }
 
     #[test]
     fn test_scenario_full_has_inactive() {
-        let scenario = find_scenario("full").unwrap();
+        let scenarios = builtin_scenarios();
+        let scenario = find_scenario(&scenarios, "full").unwrap();
         let inactive: Vec<_> = scenario
             .entries
             .iter()
@@ -272,4 +398,60 @@ mod tests {
         assert!(!inactive.is_empty());
         assert_eq!(inactive.len(), 2);
     }
+
+    #[test]
+    fn test_custom_scenarios() {
+        let custom: Scenarios = toml::from_str(
+            r#"
+            [[scenario]]
+            name = "shop"
+            description = "Storefront + API"
+            http = true
+            latency = 200
+            ports = [
+              { port = 3000, label = "storefront" },
+              { port = 9090, label = "metrics", listen = false },
+            ]
+
+            [[scenario]]
+            name = "web"
+            ports = [{ port = 8000 }]
+            containers = [{ image = "redis:7", ports = ["6379:6379"] }]
+            "#,
+        )
+        .unwrap();
+        let scenarios = merge_scenarios(custom.scenarios);
+        assert_eq!(scenarios.len(), 4);
+
+        let shop = find_scenario(&scenarios, "shop").unwrap();
+        assert!(shop.custom);
+        assert!(shop.listen.http);
+        assert_eq!(shop.listen.latency, 200);
+        assert_eq!(
+            shop.entries,
+            vec![
+                ScenarioEntry {
+                    port: 3000,
+                    label: "storefront".to_string(),
+                    should_listen: true,
+                },
+                ScenarioEntry {
+                    port: 9090,
+                    label: "metrics".to_string(),
+                    should_listen: false,
+                },
+            ]
+        );
+        // A custom scenario replaces the built-in of the same name
+        let web = find_scenario(&scenarios, "web").unwrap();
+        assert!(web.custom);
+        assert_eq!(web.entries.len(), 1);
+        assert_eq!(web.containers[0].image, "redis:7");
+        assert!(shop.containers.is_empty());
+
+        assert!(
+            toml::from_str::<Scenarios>("[[scenario]]\nname = \"x\"\nports = [{ prot = 1 }]\n")
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,34 @@
+//! `quay dev record` and `quay dev replay-fixture`: save what a real scan's
+//! commands print, and show it again offline through the same parsers.
+
+use anyhow::{Context, Result};
+use quay_core::port::recording::{self, Recording};
+use std::path::Path;
+
+/// Record a scan of `remote_host` (or this machine) into `dir`.
+pub async fn record(dir: &Path, remote_host: Option<&str>) -> Result<()> {
+    let recording = recording::record(remote_host).await?;
+    recording.save(dir)?;
+    println!(
+        "Recorded listeners ({}){}{} to {}",
+        recording.collector,
+        if recording.docker.is_some() {
+            ", docker ps"
+        } else {
+            ""
+        },
+        if recording.ps.is_some() { ", ps" } else { "" },
+        dir.display()
+    );
+    println!("Replay with: quay dev replay-fixture {}", dir.display());
+    Ok(())
+}
+
+/// Launch the TUI with the entries parsed from the recording in `dir`.
+pub async fn replay(dir: &Path) -> Result<()> {
+    let recording = Recording::load(dir)?;
+    let entries = recording
+        .entries()
+        .with_context(|| format!("Failed to parse the fixture in {}", dir.display()))?;
+    crate::run_tui_with_entries(Some(entries.into()), None, None, None, None).await
+}
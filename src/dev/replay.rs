@@ -0,0 +1,197 @@
+//! `quay dev replay`: drive the mock TUI from a script of key presses.
+//!
+//! ```json
+//! {
+//!   "delay_ms": 400,
+//!   "steps": [
+//!     { "key": "j" },
+//!     { "key": "Enter", "delay_ms": 1000 },
+//!     { "key": "Esc" },
+//!     { "key": "/" },
+//!     { "text": "postgres" },
+//!     { "key": "q", "delay_ms": 2000 }
+//!   ]
+//! }
+//! ```
+//!
+//! Each step waits its `delay_ms` (or the script's) and then presses `key`, or
+//! types every character of `text`. The TUI stays open after the last step
+//! unless the script quits it.
+
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const DEFAULT_DELAY_MS: u64 = 500;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptFile {
+    delay_ms: Option<u64>,
+    steps: Vec<StepFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepFile {
+    key: Option<String>,
+    text: Option<String>,
+    delay_ms: Option<u64>,
+}
+
+/// Key presses, each after a pause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    steps: Vec<(Duration, KeyEvent)>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid replay script {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: ScriptFile = serde_json::from_str(content)?;
+        let default_delay = file.delay_ms.unwrap_or(DEFAULT_DELAY_MS);
+        let mut steps = Vec::new();
+        for (index, step) in file.steps.into_iter().enumerate() {
+            let delay = Duration::from_millis(step.delay_ms.unwrap_or(default_delay));
+            match (step.key, step.text) {
+                (Some(key), None) => {
+                    let key = parse_key(&key).ok_or_else(|| {
+                        anyhow::anyhow!("step {}: unknown key '{key}'", index + 1)
+                    })?;
+                    steps.push((delay, key));
+                }
+                (None, Some(text)) => {
+                    // The pause comes before the first character; the rest type at once
+                    for (i, c) in text.chars().enumerate() {
+                        let pause = if i == 0 { delay } else { Duration::ZERO };
+                        steps.push((pause, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)));
+                    }
+                }
+                _ => anyhow::bail!("step {}: needs exactly one of `key` or `text`", index + 1),
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// Send the key presses on `keys`, pausing before each.
+    pub async fn play(self, keys: mpsc::Sender<KeyEvent>) {
+        for (delay, key) in self.steps {
+            tokio::time::sleep(delay).await;
+            if keys.send(key).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Parse a key name: a character (`j`, `K`, `/`), a named key (`Enter`,
+/// `Esc`, `Tab`, `Up`, `PageDown`, `F1`, ...), optionally prefixed with
+/// `Ctrl+`, `Alt+`, or `Shift+`.
+fn parse_key(name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    // A lone "+" is the plus key, not a separator
+    while let Some((prefix, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match prefix.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        rest = key;
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f => KeyCode::F(f.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+/// Run the mock TUI with `script` pressing the keys.
+pub async fn run(path: &Path) -> Result<()> {
+    let script = Script::load(path)?;
+    let entries = super::mock::generate_mock_entries();
+    crate::run_tui_with_entries(Some(entries.into()), None, None, Some(script), None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(parse_key("j"), Some(key(KeyCode::Char('j'))));
+        assert_eq!(parse_key("+"), Some(key(KeyCode::Char('+'))));
+        assert_eq!(parse_key("enter"), Some(key(KeyCode::Enter)));
+        assert_eq!(parse_key("PageDown"), Some(key(KeyCode::PageDown)));
+        assert_eq!(parse_key("F5"), Some(key(KeyCode::F(5))));
+        assert_eq!(
+            parse_key("Ctrl+c"),
+            Some(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            parse_key("Shift+Tab"),
+            Some(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT))
+        );
+        assert_eq!(parse_key("Hyper+x"), None);
+        assert_eq!(parse_key("Enterr"), None);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = Script::parse(
+            r#"{"delay_ms": 100, "steps": [
+                {"key": "/"},
+                {"text": "pg", "delay_ms": 300},
+                {"key": "q"}
+            ]}"#,
+        )
+        .unwrap();
+        let steps: Vec<_> = script
+            .steps
+            .iter()
+            .map(|(delay, key)| (delay.as_millis(), key.code))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (100, KeyCode::Char('/')),
+                (300, KeyCode::Char('p')),
+                (0, KeyCode::Char('g')),
+                (100, KeyCode::Char('q')),
+            ]
+        );
+
+        let err = Script::parse(r#"{"steps": [{"key": "q", "text": "x"}]}"#).unwrap_err();
+        assert!(err.to_string().contains("step 1"));
+        assert!(Script::parse(r#"{"steps": [{"key": "Nope"}]}"#).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! `quay doctor`: check the environment quay depends on.
+//!
+//! Looks for the external tools quay runs, parses every config file, and
+//! probes each configured remote, printing a suggested fix for anything that
+//! fails. The output is meant to be pasted into bug reports as is.
+
+use crate::config::{self, ConfigFile};
+use crate::connection::{self, Connections, Health};
+use crate::port;
+use crate::port::listeners::{self, Collector};
+use anyhow::Result;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Error,
+    Skip,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Error => "error",
+            Status::Skip => "skip",
+        };
+        f.pad(label)
+    }
+}
+
+/// Outcome of one check, with a suggested fix when it didn't pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn new(status: Status, name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            name: name.into(),
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    fn print(&self) {
+        println!("  {:<6} {}: {}", self.status, self.name, self.detail);
+        if let Some(fix) = &self.fix {
+            println!("         fix: {fix}");
+        }
+    }
+}
+
+/// Full path of `program` in the directories of `path` (a `PATH` value).
+fn find_in_path(program: &str, path: &std::ffi::OsStr) -> Option<PathBuf> {
+    let names: Vec<String> = if cfg!(windows) {
+        vec![format!("{program}.exe"), program.to_string()]
+    } else {
+        vec![program.to_string()]
+    };
+    std::env::split_paths(path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Check for an external tool; a missing `required` tool is an error.
+fn tool_check(program: &str, found: Option<&Path>, required: bool, purpose: &str) -> Check {
+    match found {
+        Some(path) => Check::new(Status::Ok, program, path.display().to_string()),
+        None if required => Check::new(Status::Error, program, format!("not found; {purpose}"))
+            .fix(format!("install {program} and make sure it is on PATH")),
+        None => Check::new(Status::Warn, program, format!("not found; {purpose}"))
+            .fix(format!("install {program} if you need this")),
+    }
+}
+
+fn tool_checks(needs_ssh: bool) -> Vec<Check> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let find = |program| find_in_path(program, &path);
+    vec![
+        // Linux falls back to ss, netstat, or /proc/net/tcp
+        tool_check(
+            "lsof",
+            find("lsof").as_deref(),
+            cfg!(unix) && !cfg!(target_os = "linux"),
+            if cfg!(target_os = "linux") {
+                "ports are listed with a fallback, without process owners"
+            } else {
+                "local ports can't be listed"
+            },
+        ),
+        tool_check(
+            "ss",
+            find("ss").as_deref(),
+            false,
+            "the Details popup shows lsof output only",
+        ),
+        tool_check(
+            "docker",
+            find("docker").as_deref(),
+            false,
+            "container ports won't be listed",
+        ),
+        tool_check(
+            "ssh",
+            find("ssh").as_deref(),
+            needs_ssh,
+            "remote connections and forwards won't work",
+        ),
+    ]
+}
+
+/// Whether the Docker daemon answers, when the CLI is installed.
+async fn docker_daemon_check() -> Option<Check> {
+    let output = port::docker::run(&["version", "--format", "{{.Server.Version}}"], None)
+        .await
+        .ok()?;
+    let check = if output.status.success() {
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Check::new(Status::Ok, "docker daemon", format!("server {version}"))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        Check::new(Status::Warn, "docker daemon", reason.trim().to_string())
+            .fix("start Docker, or check that your user may access its socket")
+    };
+    Some(check)
+}
+
+fn config_checks() -> Vec<Check> {
+    config::config_files()
+        .into_iter()
+        .map(|(file, path)| config_check(file, &path))
+        .collect()
+}
+
+fn config_check(file: ConfigFile, path: &Path) -> Check {
+    let name = path.display().to_string();
+    if !path.exists() {
+        return Check::new(Status::Skip, name, "not found");
+    }
+    let edit = format!(
+        "run `quay config edit {}`",
+        format!("{file:?}").to_lowercase()
+    );
+    match fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| file.check(&content))
+    {
+        Ok(warnings) if warnings.is_empty() => Check::new(Status::Ok, name, "parses"),
+        Ok(warnings) => Check::new(Status::Warn, name, warnings.join("; ")).fix(edit),
+        Err(e) => {
+            let first = e.to_string().lines().next().unwrap_or_default().to_string();
+            Check::new(Status::Error, name, first).fix(format!(
+                "{edit}; `quay config validate` shows the error location"
+            ))
+        }
+    }
+}
+
+/// Suggested fix for an unhealthy SSH host.
+fn health_fix(host: &str, health: &Health) -> Option<String> {
+    match health {
+        Health::Checking | Health::Reachable(_) => None,
+        Health::AuthFailed => Some(format!(
+            "set up key login (`ssh-copy-id {host}`), or log in once with `A` in the TUI"
+        )),
+        Health::HostKey => Some(format!(
+            "check the host key: `ssh {host}` shows the mismatch; `ssh-keygen -R {host}` drops a stale one"
+        )),
+        Health::TimedOut => Some(format!(
+            "check that {host} is up and reachable from here (VPN, firewall)"
+        )),
+        Health::Unreachable(_) => Some(format!("check that `ssh {host} true` works in a shell")),
+    }
+}
+
+async fn connection_checks(hosts: Vec<(String, String)>) -> Vec<Check> {
+    let probes = hosts.into_iter().map(|(name, host)| async move {
+        let health = connection::probe(&host).await;
+        let status = match health {
+            Health::Reachable(_) => Status::Ok,
+            _ => Status::Error,
+        };
+        let check = Check::new(status, format!("{name} ({host})"), health.label());
+        match health_fix(&host, &health) {
+            Some(fix) => check.fix(fix),
+            None => check,
+        }
+    });
+    futures::future::join_all(probes).await
+}
+
+/// Which collector lists ports inside the Docker target container.
+async fn docker_target_check(target: &str, remote_host: Option<&str>) -> Check {
+    let name = format!("docker target {target}");
+    let chain = &Collector::CONTAINER_CHAIN;
+    match listeners::collect(listeners::Target::Container(target, remote_host), chain).await {
+        Ok((Collector::Ss, _)) => Check::new(Status::Ok, name, "ports listed with ss"),
+        Ok((collector, _)) => Check::new(
+            Status::Warn,
+            name,
+            format!("ss not found; ports listed with {collector}"),
+        )
+        .fix("install iproute2 (ss) in the image for process names"),
+        Err(e) => Check::new(Status::Error, name, e.to_string())
+            .fix("make sure the container is running and docker is installed where it runs"),
+    }
+}
+
+fn print_section(title: &str, checks: &[Check]) {
+    println!("{title}");
+    for check in checks {
+        check.print();
+    }
+    println!();
+}
+
+pub async fn run(remote_host: Option<&str>, docker_target: Option<&str>) -> Result<()> {
+    println!("quay {}\n", env!("CARGO_PKG_VERSION"));
+
+    let connections = Connections::load().unwrap_or_default();
+    let mut hosts: Vec<(String, String)> = connections
+        .all_with_local()
+        .iter()
+        .filter_map(|c| Some((c.name.clone(), c.remote_host.clone()?)))
+        .collect();
+    if let Some(host) = remote_host {
+        if !hosts.iter().any(|(_, h)| h == host) {
+            hosts.insert(0, ("--remote".to_string(), host.to_string()));
+        }
+    }
+
+    let mut tools = tool_checks(!hosts.is_empty());
+    if tools
+        .iter()
+        .any(|c| c.name == "docker" && c.status == Status::Ok)
+    {
+        tools.extend(docker_daemon_check().await);
+    }
+    if let Some(target) = docker_target {
+        tools.push(docker_target_check(target, remote_host).await);
+    }
+    print_section("Tools", &tools);
+
+    let configs = config_checks();
+    print_section("Config files", &configs);
+
+    let remotes = if hosts.is_empty() {
+        vec![Check::new(
+            Status::Skip,
+            "connections",
+            "no remote hosts configured",
+        )]
+    } else {
+        connection_checks(hosts).await
+    };
+    print_section("Connections", &remotes);
+
+    let problems = [&tools, &configs, &remotes]
+        .into_iter()
+        .flatten()
+        .filter(|c| c.status == Status::Error)
+        .count();
+    if problems > 0 {
+        anyhow::bail!("{problems} problem(s) found");
+    }
+    println!("No problems found");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_check() {
+        let ok = tool_check("lsof", Some(Path::new("/usr/bin/lsof")), true, "x");
+        assert_eq!(ok.status, Status::Ok);
+        assert!(ok.fix.is_none());
+        assert_eq!(tool_check("lsof", None, true, "x").status, Status::Error);
+        let optional = tool_check("docker", None, false, "container ports won't be listed");
+        assert_eq!(optional.status, Status::Warn);
+        assert_eq!(
+            optional.detail,
+            "not found; container ports won't be listed"
+        );
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let dir = std::env::temp_dir().join(format!("quay-doctor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("quay-fake-tool"), "").unwrap();
+        let path = std::env::join_paths([Path::new("/nonexistent"), &dir]).unwrap();
+        assert_eq!(
+            find_in_path("quay-fake-tool", &path),
+            Some(dir.join("quay-fake-tool"))
+        );
+        assert!(find_in_path("quay-missing-tool", &path).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_health_fix() {
+        assert!(health_fix("lab", &Health::Reachable(std::time::Duration::ZERO)).is_none());
+        let fix = health_fix("lab", &Health::AuthFailed).unwrap();
+        assert!(fix.contains("ssh-copy-id lab"));
+        let fix = health_fix("lab", &Health::HostKey).unwrap();
+        assert!(fix.contains("ssh-keygen -R lab"));
+        assert!(
+            health_fix("lab", &Health::Unreachable(String::new()))
+                .unwrap()
+                .contains("ssh lab true")
+        );
+    }
+}
@@ -1,42 +1,474 @@
-use crate::app::{ConnectionInput, ForwardField, ForwardInput};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use crate::app::{
+    App, ConnectionInput, ConnectionPopupMode, ForwardField, ForwardInput, Popup, PresetInput,
+    PresetPopupMode,
+};
+use crate::config::CustomAction;
+use crate::output::Format;
+use crate::ui::ScreenLayout;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::time::{Duration, Instant};
+
+/// Two clicks on the same row within this interval open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
 pub enum AppEvent {
     Key(KeyEvent),
     Mouse(MouseEvent),
+    /// The terminal changed size; the next draw lays the screen out again.
+    Resize,
     Tick,
 }
 
+/// Section of the Help popup a binding is listed under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCategory {
+    Navigation,
+    Filtering,
+    Actions,
+    Presets,
+    Connections,
+    View,
+}
+
+impl KeyCategory {
+    /// Display order in the Help popup.
+    pub const ALL: [KeyCategory; 6] = [
+        KeyCategory::Navigation,
+        KeyCategory::Filtering,
+        KeyCategory::Actions,
+        KeyCategory::Presets,
+        KeyCategory::Connections,
+        KeyCategory::View,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            KeyCategory::Navigation => "Navigation",
+            KeyCategory::Filtering => "Filtering",
+            KeyCategory::Actions => "Actions",
+            KeyCategory::Presets => "Presets",
+            KeyCategory::Connections => "Connections",
+            KeyCategory::View => "View",
+        }
+    }
+}
+
+/// A normal-mode key binding.
+pub struct KeyBinding {
+    pub keys: &'static [KeyCode],
+    pub action: Action,
+    pub category: KeyCategory,
+    pub description: &'static str,
+}
+
+const fn bind(
+    keys: &'static [KeyCode],
+    action: Action,
+    category: KeyCategory,
+    description: &'static str,
+) -> KeyBinding {
+    KeyBinding {
+        keys,
+        action,
+        category,
+        description,
+    }
+}
+
+/// Normal-mode bindings: `handle_key` looks keys up here, and the Help popup
+/// lists them, so the two can't disagree. Ctrl-C always quits as well.
+pub const KEYMAP: &[KeyBinding] = {
+    use Action as A;
+    use KeyCategory::{Actions, Connections, Filtering, Navigation, Presets, View};
+    use KeyCode::{Char, Down, End, Enter, Esc, Home, Left, Right, Up};
+    &[
+        bind(&[Char('j'), Down], A::Down, Navigation, "Move down"),
+        bind(&[Char('k'), Up], A::Up, Navigation, "Move up"),
+        bind(&[Char('g'), Home], A::First, Navigation, "Go to first"),
+        bind(&[Char('G'), End], A::Last, Navigation, "Go to last"),
+        bind(
+            &[Char('/')],
+            A::EnterSearch,
+            Filtering,
+            "Search (text or field:value)",
+        ),
+        bind(
+            &[Esc],
+            A::ClearSearch,
+            Filtering,
+            "Clear search / dismiss error",
+        ),
+        bind(&[Char('0')], A::FilterAll, Filtering, "Show all"),
+        bind(&[Char('1')], A::FilterLocal, Filtering, "Local only"),
+        bind(&[Char('2')], A::FilterSsh, Filtering, "SSH only"),
+        bind(&[Char('3')], A::FilterDocker, Filtering, "Docker only"),
+        bind(
+            &[Char('x')],
+            A::Hide,
+            Filtering,
+            "Hide/unhide port (this session)",
+        ),
+        bind(
+            &[Char('X')],
+            A::ToggleShowHidden,
+            Filtering,
+            "Show hidden ports",
+        ),
+        bind(&[Enter], A::Select, Actions, "Show details / toggle group"),
+        bind(&[Right], A::Expand, Actions, "Expand group"),
+        bind(&[Left], A::Collapse, Actions, "Collapse group"),
+        bind(&[Char('K')], A::Kill, Actions, "Kill process"),
+        bind(
+            &[Char('B')],
+            A::RestartProcess,
+            Actions,
+            "Restart process (kill, wait for the port, run again); start an expected service",
+        ),
+        bind(
+            &[Char(' ')],
+            A::ShowContextMenu,
+            Actions,
+            "Context menu (all actions for the entry)",
+        ),
+        bind(
+            &[Char('*')],
+            A::TogglePin,
+            Actions,
+            "Pin/unpin port to the top",
+        ),
+        bind(&[Char('f')], A::StartForward, Actions, "New SSH forward"),
+        bind(
+            &[Char('F')],
+            A::QuickForward,
+            Actions,
+            "Quick forward (remote/docker, same port)",
+        ),
+        bind(
+            &[Char('!')],
+            A::TmuxShell,
+            Actions,
+            "Shell on the entry's host/container in tmux",
+        ),
+        bind(
+            &[Char('T')],
+            A::TmuxForward,
+            Actions,
+            "Quick forward in a tmux pane",
+        ),
+        bind(
+            &[Char('I')],
+            A::CloseIdleTunnels,
+            Actions,
+            "Close idle SSH tunnels",
+        ),
+        bind(
+            &[Char('O')],
+            A::ShowOutput,
+            Actions,
+            "Output of the process (container logs, systemd unit, stdout file)",
+        ),
+        bind(
+            &[Char('w')],
+            A::ShowEstablished,
+            Actions,
+            "Who is connected to the port",
+        ),
+        bind(
+            &[Char('u')],
+            A::Undo,
+            Actions,
+            "Undo the last kill or forward (within a minute)",
+        ),
+        bind(&[Char('r')], A::Refresh, Actions, "Refresh"),
+        bind(
+            &[Char('R')],
+            A::RecheckEntry,
+            Actions,
+            "Recheck the selected port only",
+        ),
+        bind(
+            &[Char('s')],
+            A::ShowScan,
+            Actions,
+            "Scan a port range (finds ports lsof/ss can't list)",
+        ),
+        bind(
+            &[Char('a')],
+            A::ToggleAutoRefresh,
+            Actions,
+            "Toggle auto-refresh",
+        ),
+        bind(&[Char('p')], A::ShowPresets, Presets, "Show presets"),
+        bind(
+            &[Char('S')],
+            A::ShowSnapshots,
+            Presets,
+            "Snapshots (save/restore running forwards)",
+        ),
+        bind(
+            &[Char('P')],
+            A::SaveAsPreset,
+            Presets,
+            "Save entry as preset",
+        ),
+        bind(
+            &[Char('\'')],
+            A::PresetLeader,
+            Presets,
+            "Launch preset by hotkey ('<key>)",
+        ),
+        bind(
+            &[Char('h')],
+            A::PrevConnection,
+            Connections,
+            "Previous connection",
+        ),
+        bind(
+            &[Char('l')],
+            A::NextConnection,
+            Connections,
+            "Next connection",
+        ),
+        bind(
+            &[Char('c')],
+            A::ShowConnections,
+            Connections,
+            "Connection manager",
+        ),
+        bind(
+            &[Char('A')],
+            A::Authenticate,
+            Connections,
+            "Log in to remote (password/2FA)",
+        ),
+        bind(
+            &[Char('E')],
+            A::ShowErrors,
+            View,
+            "Errors from the last scan",
+        ),
+        bind(&[Char('L')], A::ToggleLogs, View, "Toggle log pane"),
+        bind(
+            &[Char('e')],
+            A::ShowExport,
+            View,
+            "Export the table to a file (Markdown/CSV/JSON)",
+        ),
+        bind(
+            &[Char('m')],
+            A::ShowMessages,
+            View,
+            "Status message history",
+        ),
+        bind(
+            &[Char('i')],
+            A::ShowInsights,
+            View,
+            "Insights: anomalies, with a jump to each entry",
+        ),
+        bind(
+            &[Char('H')],
+            A::ShowHistory,
+            View,
+            "Kills and forwards, also from earlier sessions",
+        ),
+        bind(
+            &[Char('o')],
+            A::CycleSort,
+            View,
+            "Sort by port / CPU / memory",
+        ),
+        bind(
+            &[Char('t')],
+            A::ToggleActivity,
+            View,
+            "Activity view: busiest ports first, live",
+        ),
+        bind(
+            &[Char('z')],
+            A::ToggleCompact,
+            View,
+            "Toggle compact layout",
+        ),
+        bind(&[Char('?')], A::ShowHelp, View, "Help"),
+        bind(&[Char('q')], A::Quit, View, "Quit"),
+    ]
+};
+
+/// How a key is written in the Help popup.
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Down => "\u{2193}".to_string(),
+        KeyCode::Up => "\u{2191}".to_string(),
+        KeyCode::Right => "\u{2192}".to_string(),
+        KeyCode::Left => "\u{2190}".to_string(),
+        other => other.to_string(),
+    }
+}
+
 pub fn handle_key(key: KeyEvent) -> Option<Action> {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Some(Action::Quit);
+    }
+    KEYMAP
+        .iter()
+        .find(|binding| binding.keys.contains(&key.code))
+        .map(|binding| binding.action)
+}
+
+/// Custom action bound to `key` in config.toml; only consulted for keys
+/// `handle_key` doesn't know.
+pub fn handle_custom_key(key: KeyEvent, actions: &[CustomAction]) -> Option<Action> {
+    let KeyCode::Char(c) = key.code else {
+        return None;
+    };
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return None;
+    }
+    actions
+        .iter()
+        .position(|a| a.key == Some(c))
+        .map(Action::RunCustom)
+}
+
+pub fn handle_auth_key(key: KeyEvent) -> Option<Action> {
     match key.code {
-        KeyCode::Char('q') => Some(Action::Quit),
-        KeyCode::Esc => Some(Action::ClearSearch),
+        KeyCode::Enter => Some(Action::Authenticate),
+        KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+pub fn handle_sudo_kill_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => Some(Action::SudoKill),
+        KeyCode::Char('n' | 'N' | 'q') | KeyCode::Esc => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+pub fn handle_rerun_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => Some(Action::Undo),
+        KeyCode::Char('n' | 'N' | 'q') | KeyCode::Esc => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+pub fn handle_delete_preset_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => Some(Action::DeletePreset),
+        KeyCode::Char('n' | 'N' | 'q') | KeyCode::Esc => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+pub fn handle_start_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Char('y' | 'Y' | 's') | KeyCode::Enter => Some(Action::StartService),
+        KeyCode::Char('d') => Some(Action::ShowDetails),
+        KeyCode::Char('n' | 'N' | 'q') | KeyCode::Esc => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+pub fn handle_error_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Some(Action::ClosePopup),
         KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
         KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
         KeyCode::Char('g') | KeyCode::Home => Some(Action::First),
         KeyCode::Char('G') | KeyCode::End => Some(Action::Last),
-        KeyCode::Char('/') => Some(Action::EnterSearch),
-        KeyCode::Char('?') => Some(Action::ShowHelp),
+        KeyCode::Char('r') => Some(Action::Retry),
+        _ => None,
+    }
+}
+
+/// Established and Output popups: `r` collects again, other keys scroll.
+pub fn handle_established_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
         KeyCode::Char('r') => Some(Action::Refresh),
-        KeyCode::Char('a') => Some(Action::ToggleAutoRefresh),
-        KeyCode::Char('f') => Some(Action::StartForward),
-        KeyCode::Char('F') => Some(Action::QuickForward),
-        KeyCode::Char('p') => Some(Action::ShowPresets),
-        KeyCode::Char('h') => Some(Action::PrevConnection),
-        KeyCode::Char('l') => Some(Action::NextConnection),
-        KeyCode::Char('c') => {
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                Some(Action::Quit)
-            } else {
-                Some(Action::ShowConnections)
-            }
-        }
-        KeyCode::Char('0') => Some(Action::FilterAll),
-        KeyCode::Char('1') => Some(Action::FilterLocal),
-        KeyCode::Char('2') => Some(Action::FilterSsh),
-        KeyCode::Char('3') => Some(Action::FilterDocker),
-        KeyCode::Char('K') => Some(Action::Kill),
+        _ => handle_scroll_key(key),
+    }
+}
+
+pub fn handle_scroll_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Some(Action::ClosePopup),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
+        KeyCode::Char('g') | KeyCode::Home => Some(Action::First),
+        KeyCode::Char('G') | KeyCode::End => Some(Action::Last),
+        _ => None,
+    }
+}
+
+pub fn handle_menu_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePopup),
         KeyCode::Enter => Some(Action::Select),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
+        KeyCode::Char('g') | KeyCode::Home => Some(Action::First),
+        KeyCode::Char('G') | KeyCode::End => Some(Action::Last),
+        _ => None,
+    }
+}
+
+pub fn handle_snapshot_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePopup),
+        KeyCode::Enter => Some(Action::RestoreSnapshot),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
+        KeyCode::Char('s') => Some(Action::SaveSnapshot),
+        KeyCode::Char('d') => Some(Action::DeleteSnapshot),
+        _ => None,
+    }
+}
+
+/// Typing the name of a new snapshot; Enter saves unless it is blank.
+pub fn handle_snapshot_name_key(key: KeyEvent, name: &mut String) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc => Some(Action::ClosePopup),
+        KeyCode::Enter if !name.trim().is_empty() => Some(Action::SubmitSnapshot),
+        KeyCode::Backspace => {
+            name.pop();
+            None
+        }
+        KeyCode::Char(c) => {
+            name.push(c);
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Typing the port range to scan; Enter scans, or clears the results when empty.
+pub fn handle_scan_key(key: KeyEvent, range: &mut String) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc => Some(Action::ClosePopup),
+        KeyCode::Enter => Some(Action::SubmitScan),
+        KeyCode::Backspace => {
+            range.pop();
+            None
+        }
+        KeyCode::Char(c) if c.is_ascii_digit() || matches!(c, '-' | ',' | ' ') => {
+            range.push(c);
+            None
+        }
+        _ => None,
+    }
+}
+
+pub fn handle_export_key(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePopup),
+        KeyCode::Char('m') => Some(Action::Export(Format::Markdown)),
+        KeyCode::Char('c') => Some(Action::Export(Format::Csv)),
+        KeyCode::Char('j') => Some(Action::Export(Format::Json)),
         _ => None,
     }
 }
@@ -48,6 +480,51 @@ pub fn handle_popup_key(key: KeyEvent) -> Option<Action> {
     }
 }
 
+/// The action `key` stands for in the open popup. Typing into a form or
+/// name field edits it in place.
+pub fn handle_open_popup_key(key: KeyEvent, app: &mut App) -> Option<Action> {
+    match app.popup {
+        Popup::Forward => {
+            let remote_mode = app.is_remote();
+            let docker_mode = app.is_docker_target();
+            handle_forward_key(key, &mut app.forward_input, remote_mode, docker_mode)
+        }
+        Popup::Presets => match app.preset_popup_mode {
+            PresetPopupMode::List => handle_preset_key(key),
+            PresetPopupMode::Form => handle_preset_input_key(key, &mut app.preset_input),
+            PresetPopupMode::ConfirmDelete => handle_delete_preset_key(key),
+        },
+        Popup::Snapshots => match app.snapshot_name.as_mut() {
+            Some(name) => handle_snapshot_name_key(key, name),
+            None => handle_snapshot_key(key),
+        },
+        Popup::Connections if app.connection_popup_mode == ConnectionPopupMode::List => {
+            handle_connection_key(key)
+        }
+        Popup::Connections => handle_connection_input_key(key, &mut app.connection_input),
+        Popup::Auth => handle_auth_key(key),
+        Popup::SudoKill => handle_sudo_kill_key(key),
+        Popup::Rerun => handle_rerun_key(key),
+        Popup::Error => handle_error_key(key),
+        Popup::Details | Popup::Help | Popup::Messages | Popup::History => handle_scroll_key(key),
+        Popup::Established | Popup::Output => handle_established_key(key),
+        Popup::Insights | Popup::ContextMenu => handle_menu_key(key),
+        Popup::Export => handle_export_key(key),
+        Popup::Scan => handle_scan_key(key, &mut app.scan_range),
+        Popup::StartService => handle_start_key(key),
+        Popup::None | Popup::LaunchResults => handle_popup_key(key),
+    }
+}
+
+/// The key after the preset leader: a preset's hotkey, or anything else to
+/// cancel.
+pub fn handle_leader_key(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char(c) => Action::PresetHotkey(c),
+        _ => Action::ClosePopup,
+    }
+}
+
 pub fn handle_search_key(key: KeyEvent, query: &mut String) -> Option<Action> {
     match key.code {
         KeyCode::Esc | KeyCode::Enter => Some(Action::ExitSearch),
@@ -105,30 +582,72 @@ pub fn handle_forward_key(
             }
             None
         }
+        KeyCode::Char(' ') if input.active_field == ForwardField::BindAll => {
+            input.bind_all = !input.bind_all;
+            None
+        }
         KeyCode::Backspace => {
             if is_locked(input.active_field) {
                 return None;
             }
-            input.active_value().pop();
+            input.active_value()?.pop();
             None
         }
         KeyCode::Char(c) => {
             if is_locked(input.active_field) {
                 return None;
             }
-            input.active_value().push(c);
+            input.active_value()?.push(c);
             None
         }
         _ => None,
     }
 }
 
+/// Character keys the Presets popup binds itself; presets cannot use them as hotkeys there.
+pub const PRESET_POPUP_KEYS: &[char] = &['q', 'j', 'k', 'a', 'e', 'd', 'g'];
+
 pub fn handle_preset_key(key: KeyEvent) -> Option<Action> {
     match key.code {
         KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePopup),
         KeyCode::Enter => Some(Action::LaunchPreset),
         KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
         KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
+        KeyCode::Char('a') => Some(Action::AddPreset),
+        KeyCode::Char('e') => Some(Action::EditPreset),
+        KeyCode::Char('d') => Some(Action::DeletePreset),
+        KeyCode::Char('g') => Some(Action::LaunchGroup),
+        KeyCode::Char(c) => Some(Action::PresetHotkey(c)),
+        _ => None,
+    }
+}
+
+pub fn handle_preset_input_key(key: KeyEvent, input: &mut PresetInput) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc => Some(Action::ClosePopup),
+        KeyCode::Enter => {
+            if input.is_valid() {
+                Some(Action::SubmitPreset)
+            } else {
+                None
+            }
+        }
+        KeyCode::Tab | KeyCode::Down => {
+            input.active_field = input.active_field.next();
+            None
+        }
+        KeyCode::BackTab | KeyCode::Up => {
+            input.active_field = input.active_field.prev();
+            None
+        }
+        KeyCode::Backspace => {
+            input.active_value().pop();
+            None
+        }
+        KeyCode::Char(c) => {
+            input.active_value().push(c);
+            None
+        }
         _ => None,
     }
 }
@@ -140,7 +659,11 @@ pub fn handle_connection_key(key: KeyEvent) -> Option<Action> {
         KeyCode::Char('j') | KeyCode::Down => Some(Action::Down),
         KeyCode::Char('k') | KeyCode::Up => Some(Action::Up),
         KeyCode::Char('a') => Some(Action::AddConnection),
+        KeyCode::Char('e') => Some(Action::EditConnection),
+        KeyCode::Char('t') => Some(Action::TestConnection),
         KeyCode::Char('d') => Some(Action::DeleteConnection),
+        KeyCode::Char('K') => Some(Action::MoveConnectionUp),
+        KeyCode::Char('J') => Some(Action::MoveConnectionDown),
         _ => None,
     }
 }
@@ -175,23 +698,47 @@ pub fn handle_connection_input_key(key: KeyEvent, input: &mut ConnectionInput) -
     }
 }
 
-pub fn handle_mouse(event: MouseEvent, table_top: u16, table_height: u16) -> Option<Action> {
+/// Remembers the last click so a second one on the same row opens it.
+#[derive(Debug, Default)]
+pub struct ClickTracker {
+    last: Option<(Instant, usize)>,
+}
+
+impl ClickTracker {
+    /// Record a click on `row` at `now`; true when it completes a double click.
+    pub fn click(&mut self, row: usize, now: Instant) -> bool {
+        let double = self
+            .last
+            .is_some_and(|(at, last)| last == row && now.duration_since(at) <= DOUBLE_CLICK);
+        self.last = if double { None } else { Some((now, row)) };
+        double
+    }
+}
+
+pub fn handle_mouse(
+    event: MouseEvent,
+    layout: &ScreenLayout,
+    clicks: &mut ClickTracker,
+) -> Option<Action> {
     match event.kind {
-        MouseEventKind::Down(_) => {
-            // Check if click is within table area (accounting for header row)
-            if event.row > table_top && event.row < table_top + table_height {
-                let row_index = (event.row - table_top - 1) as usize; // -1 for header
-                return Some(Action::SelectRow(row_index));
+        MouseEventKind::Down(MouseButton::Left) => {
+            let row = layout.row_at(event.column, event.row)?;
+            if clicks.click(row, Instant::now()) {
+                Some(Action::OpenRow(row))
+            } else {
+                Some(Action::SelectRow(row))
             }
-            None
         }
+        MouseEventKind::Down(MouseButton::Right) => layout
+            .row_at(event.column, event.row)
+            .map(Action::ContextMenuRow),
         MouseEventKind::ScrollDown => Some(Action::Down),
         MouseEventKind::ScrollUp => Some(Action::Up),
         _ => None,
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Quit,
     Up,
@@ -200,7 +747,10 @@ pub enum Action {
     Last,
     Select,
     SelectRow(usize),
+    OpenRow(usize),
     Refresh,
+    /// Re-probe the selected entry without a full rescan.
+    RecheckEntry,
     ToggleAutoRefresh,
     EnterSearch,
     ExitSearch,
@@ -216,6 +766,14 @@ pub enum Action {
     SubmitForward,
     ShowPresets,
     LaunchPreset,
+    AddPreset,
+    EditPreset,
+    DeletePreset,
+    SubmitPreset,
+    SaveAsPreset,
+    PresetHotkey(char),
+    LaunchGroup,
+    PresetLeader,
     QuickForward,
     PrevConnection,
     NextConnection,
@@ -224,7 +782,67 @@ pub enum Action {
     AddConnection,
     DeleteConnection,
     SubmitConnection,
+    EditConnection,
+    MoveConnectionUp,
+    MoveConnectionDown,
+    TestConnection,
+    Authenticate,
+    SudoKill,
+    ShowErrors,
+    Retry,
+    ToggleLogs,
+    ToggleCompact,
+    /// Sort the table by the next key: port, CPU, memory.
+    CycleSort,
+    /// Switch the top-like activity view on or off.
+    ToggleActivity,
+    ShowMessages,
+    /// Open the Insights popup of anomalies among the entries.
+    ShowInsights,
+    /// Open the Output popup: recent output of the selected entry's process.
+    ShowOutput,
+    /// Open the History popup of history.jsonl.
+    ShowHistory,
+    TogglePin,
+    Hide,
+    ToggleShowHidden,
+    Expand,
+    Collapse,
     ClearSearch,
+    ShowContextMenu,
+    /// Right-click: select the row and open the context menu.
+    ContextMenuRow(usize),
+    CopyAddress,
+    OpenBrowser,
+    ShowLogs,
+    RestartContainer,
+    /// Kill the selected process and run its command line again; restarts
+    /// a container entry's container.
+    RestartProcess,
+    /// Run the start command of the selected expected service.
+    StartService,
+    /// Open the Details popup, also on a row Enter would start.
+    ShowDetails,
+    /// Stop the ssh processes of forwards idle past `[tunnels] idle_minutes`.
+    CloseIdleTunnels,
+    /// Take back the last kill or forward.
+    Undo,
+    TmuxShell,
+    TmuxForward,
+    ShowSnapshots,
+    SaveSnapshot,
+    SubmitSnapshot,
+    RestoreSnapshot,
+    DeleteSnapshot,
+    ShowExport,
+    ShowScan,
+    SubmitScan,
+    /// List the established connections to the selected port.
+    ShowEstablished,
+    /// Write the filtered table to a file in this format.
+    Export(Format),
+    /// Run the custom action at this index of `[[actions]]`.
+    RunCustom(usize),
 }
 
 #[cfg(test)]
@@ -232,6 +850,22 @@ mod tests {
     use super::*;
     use crossterm::event::KeyEvent;
 
+    #[test]
+    fn test_keymap_has_no_conflicts() {
+        let mut seen = Vec::new();
+        for binding in KEYMAP {
+            for key in binding.keys {
+                assert!(!seen.contains(key), "{key:?} bound twice");
+                seen.push(*key);
+            }
+        }
+        for category in KeyCategory::ALL {
+            assert!(KEYMAP.iter().any(|b| b.category == category));
+        }
+        assert_eq!(key_label(KeyCode::Down), "\u{2193}");
+        assert_eq!(key_label(KeyCode::Home), "Home");
+    }
+
     #[test]
     fn test_c_key_shows_connections() {
         let key = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
@@ -244,6 +878,27 @@ mod tests {
         assert!(matches!(handle_key(key), Some(Action::Quit)));
     }
 
+    #[test]
+    fn test_custom_action_keys() {
+        let action = |key| CustomAction {
+            name: String::new(),
+            command: String::new(),
+            sources: Vec::new(),
+            key: Some(key),
+            background: false,
+        };
+        let actions = [action('T'), action('o')];
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_custom_key(key('o'), &actions),
+            Some(Action::RunCustom(1))
+        ));
+        assert!(handle_custom_key(key('v'), &actions).is_none());
+        let ctrl_o = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert!(handle_custom_key(ctrl_o, &actions).is_none());
+        assert_eq!(key_label(KeyCode::Char(' ')), "Space");
+    }
+
     #[test]
     fn test_h_key_prev_connection() {
         let key = KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE);
@@ -255,4 +910,258 @@ mod tests {
         let key = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
         assert!(matches!(handle_key(key), Some(Action::NextConnection)));
     }
+
+    #[test]
+    fn test_preset_popup_keys() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_preset_key(key('a')),
+            Some(Action::AddPreset)
+        ));
+        assert!(matches!(
+            handle_preset_key(key('e')),
+            Some(Action::EditPreset)
+        ));
+        assert!(matches!(
+            handle_preset_key(key('d')),
+            Some(Action::DeletePreset)
+        ));
+        assert!(matches!(handle_key(key('P')), Some(Action::SaveAsPreset)));
+        assert!(matches!(handle_key(key('\'')), Some(Action::PresetLeader)));
+    }
+
+    #[test]
+    fn test_preset_popup_hotkeys() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_preset_key(key('1')),
+            Some(Action::PresetHotkey('1'))
+        ));
+        // Popup commands take precedence over hotkeys
+        for &c in PRESET_POPUP_KEYS {
+            assert!(!matches!(
+                handle_preset_key(key(c)),
+                Some(Action::PresetHotkey(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_preset_input_submit_requires_valid() {
+        let mut input = PresetInput::new();
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(handle_preset_input_key(enter, &mut input).is_none());
+
+        for c in "db".chars() {
+            handle_preset_input_key(
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+                &mut input,
+            );
+        }
+        assert_eq!(input.name, "db");
+        input.local_port = "5432".to_string();
+        input.remote_host = "localhost".to_string();
+        input.remote_port = "5432".to_string();
+        input.ssh_host = "bastion".to_string();
+        assert!(matches!(
+            handle_preset_input_key(enter, &mut input),
+            Some(Action::SubmitPreset)
+        ));
+    }
+
+    #[test]
+    fn test_connection_popup_edit_and_move_keys() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_connection_key(key('e')),
+            Some(Action::EditConnection)
+        ));
+        assert!(matches!(
+            handle_connection_key(key('K')),
+            Some(Action::MoveConnectionUp)
+        ));
+        assert!(matches!(
+            handle_connection_key(key('J')),
+            Some(Action::MoveConnectionDown)
+        ));
+    }
+
+    #[test]
+    fn test_confirm_popup_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(matches!(
+            handle_sudo_kill_key(key(KeyCode::Char('y'))),
+            Some(Action::SudoKill)
+        ));
+        assert!(matches!(
+            handle_sudo_kill_key(key(KeyCode::Esc)),
+            Some(Action::ClosePopup)
+        ));
+        assert!(handle_sudo_kill_key(key(KeyCode::Char('x'))).is_none());
+        assert!(matches!(
+            handle_delete_preset_key(key(KeyCode::Char('y'))),
+            Some(Action::DeletePreset)
+        ));
+        assert!(matches!(
+            handle_delete_preset_key(key(KeyCode::Char('n'))),
+            Some(Action::ClosePopup)
+        ));
+        assert!(handle_delete_preset_key(key(KeyCode::Char('d'))).is_none());
+        assert!(matches!(
+            handle_rerun_key(key(KeyCode::Enter)),
+            Some(Action::Undo)
+        ));
+        assert!(matches!(
+            handle_start_key(key(KeyCode::Enter)),
+            Some(Action::StartService)
+        ));
+        assert!(matches!(
+            handle_start_key(key(KeyCode::Char('d'))),
+            Some(Action::ShowDetails)
+        ));
+        assert!(matches!(
+            handle_rerun_key(key(KeyCode::Char('n'))),
+            Some(Action::ClosePopup)
+        ));
+    }
+
+    #[test]
+    fn test_auth_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('A'))),
+            Some(Action::Authenticate)
+        ));
+        assert!(matches!(
+            handle_auth_key(key(KeyCode::Enter)),
+            Some(Action::Authenticate)
+        ));
+        assert!(matches!(
+            handle_auth_key(key(KeyCode::Esc)),
+            Some(Action::ClosePopup)
+        ));
+        assert!(handle_auth_key(key(KeyCode::Char('x'))).is_none());
+    }
+
+    #[test]
+    fn test_error_popup_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('E'))),
+            Some(Action::ShowErrors)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('L'))),
+            Some(Action::ToggleLogs)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('z'))),
+            Some(Action::ToggleCompact)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('m'))),
+            Some(Action::ShowMessages)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('i'))),
+            Some(Action::ShowInsights)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('H'))),
+            Some(Action::ShowHistory)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('B'))),
+            Some(Action::RestartProcess)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('O'))),
+            Some(Action::ShowOutput)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('o'))),
+            Some(Action::CycleSort)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('t'))),
+            Some(Action::ToggleActivity)
+        ));
+        assert!(matches!(
+            handle_key(key(KeyCode::Char('!'))),
+            Some(Action::TmuxShell)
+        ));
+        assert!(matches!(
+            handle_error_key(key(KeyCode::Char('r'))),
+            Some(Action::Retry)
+        ));
+        assert!(matches!(
+            handle_error_key(key(KeyCode::Char('j'))),
+            Some(Action::Down)
+        ));
+        assert!(matches!(
+            handle_error_key(key(KeyCode::Esc)),
+            Some(Action::ClosePopup)
+        ));
+    }
+
+    #[test]
+    fn test_connection_popup_test_key() {
+        let key = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_connection_key(key),
+            Some(Action::TestConnection)
+        ));
+    }
+
+    #[test]
+    fn test_mouse_hits_drawn_rows() {
+        let layout = ScreenLayout {
+            table_body: ratatui::layout::Rect::new(1, 8, 40, 5),
+            table_offset: 10,
+            ..ScreenLayout::default()
+        };
+        assert_eq!(layout.row_at(1, 8), Some(10));
+        assert_eq!(layout.row_at(40, 12), Some(14));
+        assert_eq!(layout.row_at(1, 7), None);
+        assert_eq!(layout.row_at(41, 9), None);
+        assert_eq!(ScreenLayout::default().row_at(0, 0), None);
+
+        let click = |row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 5,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let mut clicks = ClickTracker::default();
+        assert!(matches!(
+            handle_mouse(click(9), &layout, &mut clicks),
+            Some(Action::SelectRow(11))
+        ));
+        assert!(matches!(
+            handle_mouse(click(9), &layout, &mut clicks),
+            Some(Action::OpenRow(11))
+        ));
+        assert!(handle_mouse(click(2), &layout, &mut clicks).is_none());
+
+        let right_click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Right),
+            ..click(12)
+        };
+        assert_eq!(
+            handle_mouse(right_click, &layout, &mut clicks),
+            Some(Action::ContextMenuRow(14))
+        );
+    }
+
+    #[test]
+    fn test_double_click_needs_same_row_and_timing() {
+        let start = Instant::now();
+        let mut clicks = ClickTracker::default();
+        assert!(!clicks.click(3, start));
+        assert!(!clicks.click(4, start + Duration::from_millis(100)));
+        assert!(clicks.click(4, start + Duration::from_millis(200)));
+        // A third click starts over rather than opening again
+        assert!(!clicks.click(4, start + Duration::from_millis(300)));
+        assert!(!clicks.click(4, start + Duration::from_secs(2)));
+    }
 }
@@ -0,0 +1,275 @@
+//! Services a project expects to be running, from its `Procfile` and the
+//! `[[expected]]` entries of `.quay.toml`, both in the current directory.
+//!
+//! ```toml
+//! [[expected]]
+//! name = "web"
+//! port = 3000
+//! command = "npm run dev"
+//! ```
+//!
+//! A Procfile line names its port with `-p`/`--port` or a `PORT=` prefix;
+//! one that uses `$PORT` gets foreman's: 5000 for the first process type,
+//! 5100 for the second, and so on.
+//!
+//! A service started from quay is watched until its port opens
+//! ([`wait_until_up`]).
+
+use crate::config::ConfigDiagnostic;
+use crate::port;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const PROJECT_FILE: &str = ".quay.toml";
+pub const PROCFILE: &str = "Procfile";
+
+/// Port foreman gives the first process type; each next one gets 100 more.
+const FOREMAN_BASE_PORT: u16 = 5000;
+
+/// How long a started service gets to open its port.
+const START_WAIT: Duration = Duration::from_secs(60);
+const START_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedService {
+    pub name: String,
+    pub port: u16,
+    /// Shell command that starts the service, run in `dir`.
+    pub command: Option<String>,
+    pub dir: PathBuf,
+    /// File that declared it (`Procfile` or `.quay.toml`).
+    pub origin: &'static str,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Expected {
+    pub services: Vec<ExpectedService>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProjectFile {
+    #[serde(default)]
+    expected: Vec<RawExpected>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawExpected {
+    name: String,
+    port: u16,
+    #[serde(default)]
+    command: Option<String>,
+}
+
+impl Expected {
+    /// Read the Procfile and `.quay.toml` of the current directory. Entries
+    /// of `.quay.toml` replace Procfile processes of the same name; a file
+    /// that fails to parse is skipped and reported.
+    pub fn load() -> (Self, Vec<ConfigDiagnostic>) {
+        let Ok(dir) = std::env::current_dir() else {
+            return (Self::default(), Vec::new());
+        };
+        let mut expected = Self::default();
+        let mut diagnostics = Vec::new();
+        let procfile = dir.join(PROCFILE);
+        match std::fs::read_to_string(&procfile) {
+            Ok(content) => expected.merge(parse_procfile(&content, &dir)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => diagnostics.push(ConfigDiagnostic::from_io(&procfile, &e)),
+        }
+        let project = dir.join(PROJECT_FILE);
+        match std::fs::read_to_string(&project) {
+            Ok(content) => match parse_project(&content, &dir) {
+                Ok(services) => expected.merge(services),
+                Err(e) => diagnostics.push(ConfigDiagnostic::from_toml(&project, &content, &e)),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => diagnostics.push(ConfigDiagnostic::from_io(&project, &e)),
+        }
+        (expected, diagnostics)
+    }
+
+    /// Add `services`, replacing any of the same name.
+    pub fn merge(&mut self, services: Vec<ExpectedService>) {
+        for service in services {
+            match self.services.iter_mut().find(|s| s.name == service.name) {
+                Some(slot) => *slot = service,
+                None => self.services.push(service),
+            }
+        }
+    }
+
+    pub fn get(&self, port: u16) -> Option<&ExpectedService> {
+        self.services.iter().find(|s| s.port == port)
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        self.services.iter().map(|s| s.port)
+    }
+}
+
+/// Watch `child`, the start command of service `name`, until `port` opens.
+/// Fails when the command exits with an error first, or the port is still
+/// closed after a minute; the command keeps running either way.
+pub async fn wait_until_up(
+    mut child: tokio::process::Child,
+    name: &str,
+    port: u16,
+) -> Result<String, String> {
+    let started = Instant::now();
+    // A command may exit successfully after starting the service (`docker compose up -d`)
+    let mut exited = false;
+    loop {
+        tokio::select! {
+            status = child.wait(), if !exited => match status {
+                Ok(status) if status.success() => exited = true,
+                Ok(status) => return Err(format!("{name} exited ({status}) before :{port} opened")),
+                Err(e) => return Err(format!("{name}: {e}")),
+            },
+            () = tokio::time::sleep(START_POLL) => {
+                let open = port::open_ports(&[port], None, None).await;
+                if open.is_ok_and(|open| open.contains(&port)) {
+                    let secs = started.elapsed().as_secs_f32();
+                    return Ok(format!("{name} is up on :{port} ({secs:.1}s)"));
+                }
+                if started.elapsed() >= START_WAIT {
+                    return Err(format!(
+                        "{name} started, but :{port} is still closed after {}s",
+                        START_WAIT.as_secs()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// `[[expected]]` entries of `.quay.toml`; other tables are left to their
+/// own readers.
+pub fn parse_project(content: &str, dir: &Path) -> Result<Vec<ExpectedService>, toml::de::Error> {
+    let file: ProjectFile = toml::from_str(content)?;
+    Ok(file
+        .expected
+        .into_iter()
+        .filter(|raw| raw.port > 0)
+        .map(|raw| ExpectedService {
+            name: raw.name,
+            port: raw.port,
+            command: raw.command.filter(|c| !c.trim().is_empty()),
+            dir: dir.to_path_buf(),
+            origin: PROJECT_FILE,
+        })
+        .collect())
+}
+
+/// `name: command` lines with a port; the others are not listed.
+pub fn parse_procfile(content: &str, dir: &Path) -> Vec<ExpectedService> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, command)| (name.trim(), command.trim()))
+        .filter(|(name, command)| !name.is_empty() && !command.is_empty())
+        .enumerate()
+        .filter_map(|(index, (name, command))| {
+            let foreman = u16::try_from(index)
+                .ok()
+                .and_then(|i| FOREMAN_BASE_PORT.checked_add(i.checked_mul(100)?));
+            let port =
+                explicit_port(command).or_else(|| foreman.filter(|_| uses_port_env(command)))?;
+            Some(ExpectedService {
+                name: name.to_string(),
+                port,
+                command: Some(command.to_string()),
+                dir: dir.to_path_buf(),
+                origin: PROCFILE,
+            })
+        })
+        .collect()
+}
+
+/// Port given by `-p N`, `--port N`, `--port=N`, or a leading `PORT=N`.
+fn explicit_port(command: &str) -> Option<u16> {
+    let parse = |s: &str| s.parse::<u16>().ok().filter(|&p| p > 0);
+    let words: Vec<&str> = command.split_whitespace().collect();
+    for (i, word) in words.iter().enumerate() {
+        if let Some(value) = word.strip_prefix("PORT=") {
+            return parse(value);
+        }
+        if let Some(value) = word.strip_prefix("--port=") {
+            return parse(value);
+        }
+        if matches!(*word, "-p" | "--port") {
+            return words.get(i + 1).and_then(|v| parse(v));
+        }
+    }
+    None
+}
+
+fn uses_port_env(command: &str) -> bool {
+    command.contains("$PORT") || command.contains("${PORT}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_procfile() {
+        let dir = Path::new("/srv/app");
+        let services = parse_procfile(
+            "\
+# processes
+web: bundle exec rails server -p $PORT
+api: PORT=4000 node api.js
+worker: bundle exec sidekiq
+docs: mkdocs serve --port=8001
+",
+            dir,
+        );
+        let ports: Vec<(&str, u16)> = services.iter().map(|s| (s.name.as_str(), s.port)).collect();
+        assert_eq!(ports, [("web", 5000), ("api", 4000), ("docs", 8001)]);
+        assert_eq!(
+            services[0].command.as_deref(),
+            Some("bundle exec rails server -p $PORT")
+        );
+        assert_eq!(services[0].dir, dir);
+        assert_eq!(services[0].origin, PROCFILE);
+        // The second process type would get 5100
+        assert_eq!(
+            parse_procfile("a: sleep 1\nb: serve --port $PORT\n", dir)[0].port,
+            5100
+        );
+    }
+
+    #[test]
+    fn test_parse_project_overrides_procfile() {
+        let dir = Path::new("/srv/app");
+        let mut expected = Expected::default();
+        expected.merge(parse_procfile("web: rails s -p 3000\n", dir));
+        expected.merge(
+            parse_project(
+                r#"
+[[expected]]
+name = "web"
+port = 3001
+command = "bin/dev"
+
+[[expected]]
+name = "db"
+port = 5432
+"#,
+                dir,
+            )
+            .unwrap(),
+        );
+        assert_eq!(expected.services.len(), 2);
+        let web = expected.get(3001).unwrap();
+        assert_eq!(web.command.as_deref(), Some("bin/dev"));
+        assert_eq!(web.origin, PROJECT_FILE);
+        assert_eq!(expected.get(5432).unwrap().command, None);
+        assert!(expected.get(3000).is_none());
+        assert!(parse_project("[[expected]]\nname = 1\n", dir).is_err());
+        assert!(parse_project("[other]\nkey = 1\n", dir).unwrap().is_empty());
+    }
+}
@@ -1,10 +1,34 @@
-use crate::config::Config;
 use crate::connection::Connection;
+use crate::history;
+use crate::output::{self, Format, OutputOptions};
+use crate::port::docker::VmBackend;
+use crate::port::{self, PortEntry, Signal};
+use crate::schema::SCHEMA_VERSION;
+use crate::state::StateFile;
+use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::io::{self, Write};
 use std::net::TcpListener;
-use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ForwardCommands {
+    /// List SSH tunnels running on this machine
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Stop SSH tunnels by local port or ssh PID
+    Kill {
+        /// Local port of the tunnel, or PID of its ssh process
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        target: Option<u32>,
+        /// Stop every tunnel
+        #[arg(long)]
+        all: bool,
+    },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForwardMapping {
@@ -19,36 +43,11 @@ pub struct Forwards {
     pub forward: Vec<ForwardMapping>,
 }
 
-impl Forwards {
-    pub fn forwards_path() -> Option<PathBuf> {
-        Config::config_dir().map(|p| p.join("forwards.toml"))
-    }
-
-    pub fn load() -> Self {
-        Self::forwards_path()
-            .and_then(|path| {
-                if path.exists() {
-                    fs::read_to_string(&path).ok()
-                } else {
-                    None
-                }
-            })
-            .and_then(|content| toml::from_str(&content).ok())
-            .unwrap_or_default()
-    }
-
-    pub fn save(&self) -> anyhow::Result<()> {
-        let Some(path) = Self::forwards_path() else {
-            anyhow::bail!("Could not determine config directory");
-        };
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let content = toml::to_string_pretty(self)?;
-        fs::write(&path, content)?;
-        Ok(())
-    }
+impl StateFile for Forwards {
+    const NAME: &'static str = "forwards.toml";
+}
 
+impl Forwards {
     pub fn to_runtime(&self, connections: &[Connection]) -> HashMap<usize, HashMap<u16, u16>> {
         let mut result: HashMap<usize, HashMap<u16, u16>> = HashMap::new();
         for fwd in &self.forward {
@@ -86,6 +85,34 @@ impl Forwards {
         Self { forward }
     }
 
+    /// Replace the mappings of `connections` with `ssh_forwards`, keeping
+    /// those of connections this config doesn't have.
+    pub fn set_runtime(
+        &mut self,
+        ssh_forwards: &HashMap<usize, HashMap<u16, u16>>,
+        connections: &[Connection],
+    ) {
+        let mut merged = Self::from_runtime(ssh_forwards, connections);
+        merged.forward.extend(
+            self.forward
+                .drain(..)
+                .filter(|fwd| !connections.iter().any(|c| c.name == fwd.connection)),
+        );
+        merged.forward.sort_by(|a, b| {
+            a.connection
+                .cmp(&b.connection)
+                .then(a.container_port.cmp(&b.container_port))
+        });
+        *self = merged;
+    }
+
+    /// Drop mappings for the given local ports; returns whether anything was removed.
+    pub fn remove_ports(&mut self, ports: &[u16]) -> bool {
+        let original_len = self.forward.len();
+        self.forward.retain(|fwd| !ports.contains(&fwd.local_port));
+        self.forward.len() != original_len
+    }
+
     pub fn remove_stale(&mut self) -> bool {
         let original_len = self.forward.len();
         self.forward.retain(|fwd| is_port_listening(fwd.local_port));
@@ -97,9 +124,198 @@ pub fn is_port_listening(port: u16) -> bool {
     TcpListener::bind(("127.0.0.1", port)).is_err()
 }
 
+/// Where a forward to `container_port` should point from the remote host:
+/// its published host port on localhost, or else the container's own IP,
+/// which doesn't exist outside a Docker VM (`vm`).
+pub fn resolve_docker_forward(
+    container_port: u16,
+    docker_port_mappings: &HashMap<u16, u16>,
+    container_ip: Option<&str>,
+    vm: Option<VmBackend>,
+) -> Option<(String, u16)> {
+    if let Some(&host_port) = docker_port_mappings.get(&container_port) {
+        return Some(("localhost".to_string(), host_port));
+    }
+    container_ip
+        .filter(|_| vm.is_none())
+        .map(|ip| (ip.to_string(), container_port))
+}
+
+/// Why [`resolve_docker_forward`] found nothing to forward `container_port` to.
+pub fn docker_forward_error(container_port: u16, vm: Option<VmBackend>) -> String {
+    match vm {
+        Some(vm) => format!(
+            "Port {container_port} isn't published, and container IPs are only reachable inside the {vm} VM"
+        ),
+        None => "Container IP not available".to_string(),
+    }
+}
+
+pub async fn run_forward_command(command: ForwardCommands, color: bool) -> anyhow::Result<()> {
+    let stored = Forwards::load().unwrap_or_else(|d| {
+        tracing::warn!("{d}");
+        Forwards::default()
+    });
+    let mut tunnels = port::ssh::collect().await?;
+    label_tunnels(&mut tunnels, &stored);
+
+    match command {
+        ForwardCommands::List { format } => {
+            let mut stdout = io::stdout().lock();
+            if format == Format::Table {
+                write_table(&mut stdout, &tunnels, color)?;
+            } else {
+                let options = OutputOptions {
+                    format,
+                    version: SCHEMA_VERSION,
+                    color,
+                };
+                output::write_entries(&mut stdout, &tunnels, options)?;
+            }
+            Ok(())
+        }
+        ForwardCommands::Kill { target, .. } => kill_tunnels(&tunnels, target).await,
+    }
+}
+
+/// Mark whether each tunnel's local port is listening and which saved
+/// connection mapping (if any) it belongs to.
+fn label_tunnels(tunnels: &mut [PortEntry], stored: &Forwards) {
+    for tunnel in tunnels {
+        tunnel.is_open = is_port_listening(tunnel.local_port);
+        tunnel.connection = stored
+            .forward
+            .iter()
+            .find(|fwd| fwd.local_port == tunnel.local_port)
+            .map(|fwd| fwd.connection.clone());
+    }
+}
+
+fn write_table(out: &mut impl Write, tunnels: &[PortEntry], color: bool) -> io::Result<()> {
+    if tunnels.is_empty() {
+        return writeln!(out, "No SSH tunnels running");
+    }
+    writeln!(
+        out,
+        "{:<7} {:<6} {:<28} {:<20} {:<8} CONNECTION",
+        "LOCAL", "OPEN", "TARGET", "VIA", "PID"
+    )?;
+    for tunnel in tunnels {
+        let open = if tunnel.is_open {
+            output::paint("●", output::GREEN, color)
+        } else {
+            output::paint("○", output::GRAY, color)
+        };
+        writeln!(
+            out,
+            "{:<7} {open}      {:<28} {:<20} {:<8} {}",
+            format!(":{}", tunnel.local_port),
+            tunnel.remote_display(),
+            tunnel.ssh_host.as_deref().unwrap_or("-"),
+            tunnel
+                .pid
+                .map_or_else(|| "-".to_string(), |p| p.to_string()),
+            tunnel.connection.as_deref().unwrap_or("-")
+        )?;
+    }
+    Ok(())
+}
+
+/// ssh processes to stop: the one serving `target` as a local port, else the
+/// one with `target` as its PID; every tunnel (possibly none) when `target` is `None`.
+///
+/// One ssh process may carry several tunnels, so PIDs are deduplicated.
+pub fn tunnel_pids(tunnels: &[PortEntry], target: Option<u32>) -> anyhow::Result<Vec<u32>> {
+    let selected: Vec<&PortEntry> = match target {
+        None => tunnels.iter().collect(),
+        Some(target) => {
+            let by_port: Vec<&PortEntry> = tunnels
+                .iter()
+                .filter(|t| u32::from(t.local_port) == target)
+                .collect();
+            if by_port.is_empty() {
+                tunnels.iter().filter(|t| t.pid == Some(target)).collect()
+            } else {
+                by_port
+            }
+        }
+    };
+    if let (Some(target), true) = (target, selected.is_empty()) {
+        anyhow::bail!("No SSH tunnel on port {target} or with PID {target}");
+    }
+    let mut pids: Vec<u32> = selected.iter().filter_map(|t| t.pid).collect();
+    pids.sort_unstable();
+    pids.dedup();
+    Ok(pids)
+}
+
+async fn kill_tunnels(tunnels: &[PortEntry], target: Option<u32>) -> anyhow::Result<()> {
+    let pids = tunnel_pids(tunnels, target)?;
+    if pids.is_empty() {
+        println!("No SSH tunnels running");
+        return Ok(());
+    }
+    let mut stopped_ports = Vec::new();
+    let mut failed = 0;
+    for pid in &pids {
+        let ports: Vec<u16> = tunnels
+            .iter()
+            .filter(|t| t.pid == Some(*pid))
+            .map(|t| t.local_port)
+            .collect();
+        let port_list: Vec<String> = ports.iter().map(|p| format!(":{p}")).collect();
+        let result = port::kill_by_pid(*pid, Signal::Term, None).await;
+        for tunnel in tunnels.iter().filter(|t| t.pid == Some(*pid)) {
+            history::record_kill(tunnel, None, &result);
+        }
+        match result {
+            Ok(()) => {
+                println!("Stopped ssh PID {pid} ({})", port_list.join(", "));
+                stopped_ports.extend(ports);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed to stop ssh PID {pid}: {e}");
+            }
+        }
+    }
+
+    if !stopped_ports.is_empty() {
+        Forwards::update(|stored| stored.remove_ports(&stopped_ports))?;
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} tunnel(s) could not be stopped", pids.len());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::connection::ConnectionDefaults;
+
+    #[test]
+    fn test_resolve_docker_forward() {
+        let mappings = HashMap::from([(80, 8080)]);
+        let ip = Some("172.17.0.2");
+        assert_eq!(
+            resolve_docker_forward(80, &mappings, ip, Some(VmBackend::Colima)),
+            Some(("localhost".to_string(), 8080))
+        );
+        assert_eq!(
+            resolve_docker_forward(5432, &mappings, ip, None),
+            Some(("172.17.0.2".to_string(), 5432))
+        );
+        // Behind a VM only published ports can be reached
+        assert_eq!(
+            resolve_docker_forward(5432, &mappings, ip, Some(VmBackend::Colima)),
+            None
+        );
+        assert!(
+            docker_forward_error(5432, Some(VmBackend::DockerDesktop))
+                .contains("Docker Desktop VM")
+        );
+    }
 
     #[test]
     fn test_default_forwards() {
@@ -145,6 +361,50 @@ local_port = 18080
         assert!(serialized.contains("local_port = 15432"));
     }
 
+    fn tunnel(local_port: u16, pid: u32) -> PortEntry {
+        PortEntry {
+            source: port::PortSource::Ssh,
+            local_port,
+            remote_host: Some("localhost".to_string()),
+            remote_port: Some(local_port),
+            process_name: "ssh".to_string(),
+            pid: Some(pid),
+            ssh_host: Some("prod".to_string()),
+            ..PortEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_tunnel_pids() {
+        // PID 3000 collides with a port number; the port wins
+        let tunnels = vec![tunnel(8080, 3000), tunnel(8081, 3000), tunnel(3000, 42)];
+        assert_eq!(tunnel_pids(&tunnels, Some(8081)).unwrap(), vec![3000]);
+        assert_eq!(tunnel_pids(&tunnels, Some(3000)).unwrap(), vec![42]);
+        assert_eq!(tunnel_pids(&tunnels, Some(42)).unwrap(), vec![42]);
+        assert_eq!(tunnel_pids(&tunnels, None).unwrap(), vec![42, 3000]);
+        assert!(tunnel_pids(&tunnels, Some(9999)).is_err());
+        assert!(tunnel_pids(&[], None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_label_tunnels_and_remove_ports() {
+        let mut stored = Forwards {
+            forward: vec![ForwardMapping {
+                connection: "AI Lab".to_string(),
+                container_port: 3000,
+                local_port: 13000,
+            }],
+        };
+        let mut tunnels = vec![tunnel(13000, 1), tunnel(13001, 1)];
+        label_tunnels(&mut tunnels, &stored);
+        assert_eq!(tunnels[0].connection.as_deref(), Some("AI Lab"));
+        assert_eq!(tunnels[1].connection, None);
+
+        assert!(!stored.remove_ports(&[13001]));
+        assert!(stored.remove_ports(&[13000, 13001]));
+        assert!(stored.forward.is_empty());
+    }
+
     #[test]
     fn test_to_runtime() {
         let fwds = Forwards {
@@ -167,6 +427,8 @@ local_port = 18080
                 name: "Remote".to_string(),
                 remote_host: Some("ailab".to_string()),
                 docker_target: Some("dev".to_string()),
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
             },
         ];
         let runtime = fwds.to_runtime(&connections);
@@ -198,6 +460,8 @@ local_port = 18080
                 name: "MyServer".to_string(),
                 remote_host: Some("host".to_string()),
                 docker_target: None,
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
             },
         ];
         let mut ssh_forwards = HashMap::new();
@@ -214,6 +478,28 @@ local_port = 18080
         assert!(fwds.forward.iter().all(|f| f.connection == "MyServer"));
     }
 
+    #[test]
+    fn test_set_runtime_keeps_unknown_connections() {
+        let connections = vec![Connection::local()];
+        let mut stored = Forwards {
+            forward: vec![
+                ForwardMapping {
+                    connection: "Local".to_string(),
+                    container_port: 3000,
+                    local_port: 3000,
+                },
+                ForwardMapping {
+                    connection: "Elsewhere".to_string(),
+                    container_port: 5432,
+                    local_port: 15432,
+                },
+            ],
+        };
+        stored.set_runtime(&HashMap::new(), &connections);
+        assert_eq!(stored.forward.len(), 1);
+        assert_eq!(stored.forward[0].connection, "Elsewhere");
+    }
+
     #[test]
     fn test_roundtrip() {
         let connections = vec![
@@ -222,6 +508,8 @@ local_port = 18080
                 name: "Remote".to_string(),
                 remote_host: Some("host".to_string()),
                 docker_target: Some("container".to_string()),
+                docker_context: None,
+                defaults: ConnectionDefaults::default(),
             },
         ];
         let mut ssh_forwards = HashMap::new();
@@ -0,0 +1,327 @@
+//! Log of what quay changed (history.jsonl), shown in the History popup.
+//!
+//! Kills, forwards started and stopped, container restarts, and services
+//! started from their start command are appended one JSON object per line,
+//! from the TUI and the CLI, so "what did I kill an hour ago" still has an
+//! answer after quay exits:
+//!
+//! ```json
+//! {"time":"2026-10-16T14:02:11+02:00","session":41230,"action":"kill","port":3000,"target":"node (PID 4242)"}
+//! {"time":"2026-10-16T14:03:40+02:00","session":41230,"action":"forward","port":8080,"target":"-L 8080:localhost:80 via devbox","error":"exited with code 255: ..."}
+//! ```
+//!
+//! `session` is the PID of the quay that acted, so one run's records can be
+//! told apart from earlier ones.
+
+use crate::port::{PortEntry, PortSource};
+use crate::state;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Records loaded into the History popup.
+const SHOWN: usize = 500;
+
+/// Records kept when the file is pruned at startup.
+const KEPT: usize = 5000;
+
+/// Forwards this quay started, for the statistics under the table.
+static FORWARDS_STARTED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Kill,
+    StopContainer,
+    Forward,
+    StopForward,
+    RestartContainer,
+    /// A killed process started again, by undo or a restart.
+    Rerun,
+    /// An expected or registered service started from its start command.
+    Start,
+}
+
+impl Kind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Kill => "kill",
+            Kind::StopContainer => "stop",
+            Kind::Forward => "forward",
+            Kind::StopForward => "close",
+            Kind::RestartContainer => "restart",
+            Kind::Rerun => "rerun",
+            Kind::Start => "start",
+        }
+    }
+
+    /// What killing `entry` does: stop its container or forward, or signal
+    /// its process.
+    pub fn of_kill(entry: &PortEntry) -> Self {
+        match entry.source {
+            PortSource::Docker => Kind::StopContainer,
+            PortSource::Ssh => Kind::StopForward,
+            _ => Kind::Kill,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub time: DateTime<Local>,
+    /// PID of the quay that acted.
+    pub session: u32,
+    pub action: Kind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// The process, container, or forward acted on.
+    pub target: String,
+    /// Host it happened on, with `--remote`; `None` for this machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Why it failed; `None` when it worked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Record {
+    pub fn new(action: Kind, port: Option<u16>, target: &str, error: Option<String>) -> Self {
+        Self {
+            time: Local::now(),
+            session: std::process::id(),
+            action,
+            port,
+            target: target.to_string(),
+            host: None,
+            error,
+        }
+    }
+
+    pub fn on_host(mut self, host: Option<&str>) -> Self {
+        self.host = host.map(str::to_string);
+        self
+    }
+
+    /// Whether this quay recorded it.
+    pub fn is_current_session(&self) -> bool {
+        self.session == std::process::id()
+    }
+}
+
+/// What an entry is, for a record: the container, the forward, or the process.
+pub fn target(entry: &PortEntry) -> String {
+    let process = match entry.pid {
+        Some(pid) => format!("{} (PID {pid})", entry.process_name),
+        None => entry.process_name.clone(),
+    };
+    match (&entry.source, &entry.container_name, &entry.ssh_host) {
+        (PortSource::Docker, Some(name), _) => format!("container {name}"),
+        (PortSource::Ssh, _, Some(host)) => {
+            format!("{} via {host}, {process}", entry.remote_display())
+        }
+        _ => process,
+    }
+}
+
+/// Record the outcome of killing `entry` found on `host` (`None`: this machine).
+pub fn record_kill(entry: &PortEntry, host: Option<&str>, result: &Result<()>) {
+    // SSH tunnels are local processes even when scanning a remote host
+    let host = host.filter(|_| entry.source != PortSource::Ssh);
+    let record = Record::new(
+        Kind::of_kill(entry),
+        Some(entry.local_port),
+        &target(entry),
+        failure(result),
+    );
+    append(&record.on_host(host));
+}
+
+/// Record the outcome of starting forward `spec` through `ssh_host`; `remote`
+/// for `-R`.
+pub fn record_forward<T>(spec: &str, ssh_host: &str, remote: bool, result: &Result<T>) {
+    if result.is_ok() {
+        FORWARDS_STARTED.fetch_add(1, Ordering::Relaxed);
+    }
+    let flag = if remote { "-R" } else { "-L" };
+    let target = format!("{flag} {spec} via {ssh_host}");
+    append(&Record::new(
+        Kind::Forward,
+        spec_port(spec),
+        &target,
+        failure(result),
+    ));
+}
+
+/// Forwards started successfully since quay started, restored ones included.
+pub fn forwards_started() -> usize {
+    FORWARDS_STARTED.load(Ordering::Relaxed)
+}
+
+/// The error of a failed `result`, for [`Record::error`].
+pub fn failure<T>(result: &Result<T>) -> Option<String> {
+    result.as_ref().err().map(|e| format!("{e:#}"))
+}
+
+/// Port a forward spec listens on: the first field that is a port, after an
+/// optional bind address.
+fn spec_port(spec: &str) -> Option<u16> {
+    spec.split(':').find_map(|field| field.parse().ok())
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    state::path("history.jsonl")
+}
+
+/// Append `record`; a log that can't be written is only reported in the debug log.
+pub fn append(record: &Record) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Err(e) = append_to(&path, record) {
+        tracing::warn!(error = %format!("{e:#}"), "history not written");
+    }
+}
+
+fn append_to(path: &Path, record: &Record) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// The newest records, oldest first; a missing file is an empty history.
+pub fn load() -> Result<Vec<Record>> {
+    match history_path() {
+        Some(path) => load_from(&path, SHOWN),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The last `limit` records of `path`, skipping lines that don't parse.
+fn load_from(path: &Path, limit: usize) -> Result<Vec<Record>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(anyhow::Error::new(e).context(format!("Failed to read {}", path.display())));
+        }
+    };
+    let records: Vec<Record> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = records.len().saturating_sub(limit);
+    Ok(records.into_iter().skip(skip).collect())
+}
+
+/// Drop all but the newest records, so the file doesn't grow forever.
+pub fn prune() {
+    if let Some(path) = history_path() {
+        if let Err(e) = prune_file(&path, KEPT) {
+            tracing::warn!(error = %format!("{e:#}"), "history not pruned");
+        }
+    }
+}
+
+fn prune_file(path: &Path, keep: usize) -> Result<()> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= keep {
+        return Ok(());
+    }
+    let mut kept = lines[lines.len() - keep..].join("\n");
+    kept.push('\n');
+    fs::write(path, kept).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::row;
+
+    #[test]
+    fn test_append_load_and_prune() {
+        let dir = std::env::temp_dir().join(format!("quay-history-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        let _ = fs::remove_dir_all(&dir);
+        assert!(load_from(&path, 10).unwrap().is_empty());
+
+        let kill = Record::new(Kind::Kill, Some(3000), "node (PID 4242)", None);
+        let forward = Record::new(
+            Kind::Forward,
+            Some(8080),
+            "8080:localhost:80 via devbox",
+            Some("exited with code 255".to_string()),
+        )
+        .on_host(Some("devbox"));
+        append_to(&path, &kill).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        append_to(&path, &forward).unwrap();
+
+        assert_eq!(load_from(&path, 10).unwrap(), vec![kill, forward.clone()]);
+        assert_eq!(load_from(&path, 1).unwrap(), vec![forward.clone()]);
+        assert!(forward.is_current_session());
+
+        prune_file(&path, 1).unwrap();
+        assert_eq!(load_from(&path, 10).unwrap(), vec![forward]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_format() {
+        let mut record = Record::new(Kind::StopContainer, Some(5432), "container db", None);
+        record.time = DateTime::parse_from_rfc3339("2026-10-16T14:02:11+02:00")
+            .unwrap()
+            .with_timezone(&Local);
+        record.session = 41230;
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""action":"stop-container""#));
+        assert!(json.contains(r#""port":5432"#));
+        assert!(!json.contains("error"), "{json}");
+    }
+
+    #[test]
+    fn test_target() {
+        let mut entry = row(PortSource::Local, 3000);
+        entry.process_name = "node".to_string();
+        entry.pid = Some(4242);
+        assert_eq!(target(&entry), "node (PID 4242)");
+        assert_eq!(Kind::of_kill(&entry), Kind::Kill);
+
+        entry.source = PortSource::Docker;
+        entry.container_name = Some("db".to_string());
+        assert_eq!(target(&entry), "container db");
+        assert_eq!(Kind::of_kill(&entry), Kind::StopContainer);
+
+        entry.source = PortSource::Ssh;
+        entry.process_name = "ssh".to_string();
+        entry.remote_host = Some("localhost".to_string());
+        entry.remote_port = Some(80);
+        entry.ssh_host = Some("devbox".to_string());
+        assert_eq!(target(&entry), "localhost:80 via devbox, ssh (PID 4242)");
+        assert_eq!(Kind::of_kill(&entry), Kind::StopForward);
+
+        assert_eq!(spec_port("8080:localhost:80"), Some(8080));
+        assert_eq!(spec_port("0.0.0.0:8080:localhost:80"), Some(8080));
+        assert_eq!(spec_port("[::]:8080:db:5432"), Some(8080));
+    }
+}
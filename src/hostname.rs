@@ -0,0 +1,224 @@
+//! Friendlier names for the hosts in the REMOTE column.
+//!
+//! An address is named, first match wins, by `[hosts]` in config.toml, by
+//! `/etc/hosts`, and with `[ui] reverse_dns = true` by a reverse (PTR) lookup
+//! done in the background. `[hosts]` keys can also be container names or
+//! hostnames:
+//!
+//! ```toml
+//! [hosts]
+//! "10.0.0.12" = "db-primary"
+//! "172.17.0.3" = "redis-dev"
+//! ```
+//!
+//! Names are looked up on this machine, even for an SSH forward whose target
+//! the server resolves; Details keeps the raw address.
+
+use crate::port::{PortEntry, is_reverse_forward};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a reverse lookup may take before the address stays unnamed.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Names of remote hosts: the user's aliases, `/etc/hosts`, and reverse lookups.
+#[derive(Debug, Clone, Default)]
+pub struct HostNames {
+    aliases: HashMap<String, String>,
+    hosts_file: HashMap<IpAddr, String>,
+    /// Look up addresses named nowhere else.
+    reverse_dns: bool,
+    /// Reverse lookups done or in flight; `None` until one answers with a name.
+    resolved: HashMap<IpAddr, Option<String>>,
+}
+
+impl HostNames {
+    /// From `[hosts]` and the content of a hosts file.
+    pub fn new(aliases: &BTreeMap<String, String>, hosts_file: &str, reverse_dns: bool) -> Self {
+        let aliases = aliases
+            .iter()
+            .filter(|(_, name)| !name.trim().is_empty())
+            .map(|(host, name)| (host.trim().to_string(), name.trim().to_string()))
+            .collect();
+        Self {
+            aliases,
+            hosts_file: parse_hosts(hosts_file),
+            reverse_dns,
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// From `[hosts]` and `/etc/hosts`, which may be missing.
+    pub fn load(aliases: &BTreeMap<String, String>, reverse_dns: bool) -> Self {
+        let hosts_file = std::fs::read_to_string(Path::new("/etc/hosts")).unwrap_or_default();
+        Self::new(aliases, &hosts_file, reverse_dns)
+    }
+
+    /// Name of `host`, when it has one other than itself.
+    pub fn name(&self, host: &str) -> Option<&str> {
+        let name = match self.aliases.get(host) {
+            Some(alias) => Some(alias.as_str()),
+            None => parse_ip(host).and_then(|ip| {
+                self.hosts_file
+                    .get(&ip)
+                    .or_else(|| self.resolved.get(&ip)?.as_ref())
+                    .map(String::as_str)
+            }),
+        };
+        name.filter(|name| *name != host)
+    }
+
+    /// REMOTE column text: `name:port` when the host has a name.
+    pub fn remote_display(&self, entry: &PortEntry) -> String {
+        match (
+            remote_address(entry).and_then(|host| self.name(host)),
+            entry.remote_port,
+        ) {
+            (Some(name), Some(port)) => format!("{name}:{port}"),
+            (Some(name), None) => name.to_string(),
+            (None, _) => entry.remote_display(),
+        }
+    }
+
+    /// Addresses of `entries` to look up, marked as in flight so each is
+    /// looked up once; empty unless `reverse_dns` is on.
+    pub fn take_unresolved(&mut self, entries: &[PortEntry]) -> Vec<IpAddr> {
+        if !self.reverse_dns {
+            return Vec::new();
+        }
+        let mut pending = Vec::new();
+        for host in entries.iter().filter_map(remote_address) {
+            let Some(ip) = parse_ip(host) else {
+                continue;
+            };
+            if self.aliases.contains_key(host)
+                || self.hosts_file.contains_key(&ip)
+                || self.resolved.contains_key(&ip)
+                || ip.is_loopback()
+                || ip.is_unspecified()
+            {
+                continue;
+            }
+            self.resolved.insert(ip, None);
+            pending.push(ip);
+        }
+        pending
+    }
+
+    /// Record the names found by [`lookup`].
+    pub fn apply_resolved(&mut self, names: Vec<(IpAddr, Option<String>)>) {
+        self.resolved.extend(names);
+    }
+}
+
+/// Host in `entry`'s REMOTE column: the target of an SSH `-L` forward or a
+/// container name. `-R` forwards point back at this machine.
+fn remote_address(entry: &PortEntry) -> Option<&str> {
+    if is_reverse_forward(entry) {
+        return None;
+    }
+    entry.remote_host.as_deref()
+}
+
+/// `10.0.0.1` or `[::1]`.
+fn parse_ip(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// First name of each address in a hosts file; earlier lines win, as for the
+/// resolver.
+fn parse_hosts(content: &str) -> HashMap<IpAddr, String> {
+    let mut hosts = HashMap::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let (Some(addr), Some(name)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        // Scoped addresses (fe80::1%lo0) can't be told apart by IP alone
+        if let Ok(ip) = addr.parse::<IpAddr>() {
+            hosts.entry(ip).or_insert_with(|| name.to_string());
+        }
+    }
+    hosts
+}
+
+/// Reverse-resolve `ips`; an address without a name, or whose lookup timed
+/// out, maps to `None`.
+pub async fn lookup(ips: Vec<IpAddr>) -> Vec<(IpAddr, Option<String>)> {
+    let lookups = ips.into_iter().map(|ip| async move {
+        let resolve = tokio::task::spawn_blocking(move || dns_lookup::lookup_addr(&ip));
+        let name = match tokio::time::timeout(LOOKUP_TIMEOUT, resolve).await {
+            Ok(Ok(Ok(name))) => Some(name.trim_end_matches('.').to_string()),
+            _ => None,
+        };
+        (ip, name)
+    });
+    futures::future::join_all(lookups).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::PortSource;
+    use crate::testing::row;
+
+    const HOSTS: &str = "\
+127.0.0.1 localhost
+# 10.0.0.9 commented
+10.0.0.12   db.internal db   # primary
+10.0.0.12   replica
+::1 ip6-localhost
+fe80::1%lo0 scoped
+";
+
+    #[test]
+    fn test_parse_hosts() {
+        let hosts = parse_hosts(HOSTS);
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[&"10.0.0.12".parse().unwrap()], "db.internal");
+        assert_eq!(hosts[&"::1".parse().unwrap()], "ip6-localhost");
+    }
+
+    #[test]
+    fn test_names_and_display() {
+        let aliases = BTreeMap::from([
+            ("10.0.0.12".to_string(), "db-primary".to_string()),
+            ("redis".to_string(), "cache".to_string()),
+            ("10.0.0.99".to_string(), " ".to_string()),
+        ]);
+        let mut names = HostNames::new(&aliases, HOSTS, true);
+        assert_eq!(names.name("10.0.0.12"), Some("db-primary"));
+        assert_eq!(names.name("redis"), Some("cache"));
+        assert_eq!(names.name("[::1]"), Some("ip6-localhost"));
+        assert_eq!(names.name("10.0.0.99"), None);
+        assert_eq!(names.name("localhost"), None);
+
+        let mut tunnel = row(PortSource::Ssh, 15432);
+        tunnel.remote_host = Some("10.0.0.12".to_string());
+        tunnel.remote_port = Some(5432);
+        assert_eq!(names.remote_display(&tunnel), "db-primary:5432");
+        tunnel.remote_host = Some("(R) 10.0.0.12:5432".to_string());
+        assert_eq!(names.remote_display(&tunnel), "(R) 10.0.0.12:5432:5432");
+
+        // Each address is looked up once, and only when nothing names it
+        let mut other = tunnel.clone();
+        other.remote_host = Some("192.0.2.7".to_string());
+        tunnel.remote_host = Some("10.0.0.12".to_string());
+        let entries = [tunnel, other.clone(), other.clone()];
+        let ip: IpAddr = "192.0.2.7".parse().unwrap();
+        assert_eq!(names.take_unresolved(&entries), vec![ip]);
+        assert!(names.take_unresolved(&entries).is_empty());
+        assert_eq!(names.remote_display(&other), "192.0.2.7:5432");
+        names.apply_resolved(vec![(ip, Some("web-7.example".to_string()))]);
+        assert_eq!(names.remote_display(&other), "web-7.example:5432");
+
+        let mut quiet = HostNames::new(&BTreeMap::new(), "", false);
+        assert!(quiet.take_unresolved(&entries).is_empty());
+    }
+}
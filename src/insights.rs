@@ -0,0 +1,286 @@
+//! Anomalies worth a look, listed in the Insights popup (`i`), where Enter
+//! jumps to the entry each one is about.
+
+use crate::env_ports::EnvPorts;
+use crate::port::{self, PortEntry, PortSource, TunnelHealth};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Connections in `TIME_WAIT` on one port that count as a storm: something
+/// opens and closes connections faster than the kernel retires them.
+pub const TIME_WAIT_STORM: usize = 500;
+
+/// What kind of anomaly, most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InsightKind {
+    /// A container whose health check fails.
+    Unhealthy,
+    /// An SSH tunnel that is down or can't reach its destination.
+    DeadTunnel,
+    /// Several processes listening on one port.
+    Duplicate,
+    /// A dev server bound to an address other machines can reach.
+    Public,
+    /// Many connections to one port in `TIME_WAIT`.
+    TimeWait,
+    /// A `.env` port variable nothing listens on.
+    Env,
+}
+
+impl fmt::Display for InsightKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsightKind::Unhealthy => write!(f, "unhealthy"),
+            InsightKind::DeadTunnel => write!(f, "tunnel"),
+            InsightKind::Duplicate => write!(f, "duplicate"),
+            InsightKind::Public => write!(f, "public"),
+            InsightKind::TimeWait => write!(f, "time_wait"),
+            InsightKind::Env => write!(f, "env"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Insight {
+    pub kind: InsightKind,
+    /// Port of the entry the anomaly is about.
+    pub port: u16,
+    pub message: String,
+}
+
+/// Insights for `entries`, most urgent kind first, then by port.
+pub fn collect(entries: &[PortEntry], env: &EnvPorts) -> Vec<Insight> {
+    let open: Vec<&PortEntry> = entries.iter().filter(|e| e.is_open).collect();
+    let mut insights = Vec::new();
+    insights.extend(unhealthy(entries));
+    insights.extend(entries.iter().filter_map(dead_tunnel));
+    insights.extend(duplicates(&open));
+    insights.extend(open.iter().filter_map(|e| public(e)));
+    insights.extend(time_wait(&open));
+    insights.extend(env.mismatches(entries).iter().map(|m| Insight {
+        kind: InsightKind::Env,
+        port: m.var.port,
+        message: m.message(),
+    }));
+    insights.sort_by_key(|i| (i.kind, i.port));
+    insights
+}
+
+/// One insight per container failing its health check.
+fn unhealthy(entries: &[PortEntry]) -> Vec<Insight> {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .filter(|e| e.health.as_deref() == Some("unhealthy"))
+        .filter(|e| seen.insert(e.container_id.clone()))
+        .map(|e| Insight {
+            kind: InsightKind::Unhealthy,
+            port: e.local_port,
+            message: format!(
+                "container {} fails its health check",
+                e.container_name.as_deref().unwrap_or(&e.process_name)
+            ),
+        })
+        .collect()
+}
+
+fn dead_tunnel(entry: &PortEntry) -> Option<Insight> {
+    if entry.source != PortSource::Ssh {
+        return None;
+    }
+    let via = entry.ssh_host.as_deref().unwrap_or("ssh");
+    let message = if port::is_reverse_forward(entry) {
+        // An empty listener scan of the server: it bound nothing
+        let port = entry.remote_port?;
+        entry
+            .reverse_bind
+            .as_ref()
+            .filter(|binds| binds.is_empty())
+            .map(|_| format!("-R tunnel via {via}: nothing listens on :{port} on the server"))?
+    } else {
+        let to = entry.remote_display();
+        match entry.tunnel {
+            _ if !entry.is_open => format!("tunnel to {to} via {via} is down"),
+            Some(TunnelHealth::Down) => format!("tunnel to {to} via {via} is down"),
+            Some(TunnelHealth::LocalOnly) => {
+                format!("tunnel to {to} via {via} can't reach its destination")
+            }
+            _ => return None,
+        }
+    };
+    Some(Insight {
+        kind: InsightKind::DeadTunnel,
+        port: entry.local_port,
+        message,
+    })
+}
+
+/// Ports several processes listen on, per host.
+fn duplicates(open: &[&PortEntry]) -> Vec<Insight> {
+    let mut by_port: BTreeMap<(u16, Option<&str>), Vec<&PortEntry>> = BTreeMap::new();
+    for entry in open.iter().filter(|e| e.pid.is_some()) {
+        by_port
+            .entry((entry.local_port, entry.connection.as_deref()))
+            .or_default()
+            .push(entry);
+    }
+    by_port
+        .into_iter()
+        .filter_map(|((port, _), mut listeners)| {
+            listeners.sort_by_key(|e| e.pid);
+            listeners.dedup_by_key(|e| e.pid);
+            (listeners.len() > 1).then(|| {
+                let names: Vec<String> = listeners
+                    .iter()
+                    .map(|e| format!("{} (PID {})", e.process_name, e.pid.unwrap_or_default()))
+                    .collect();
+                Insight {
+                    kind: InsightKind::Duplicate,
+                    port,
+                    message: format!(
+                        ":{port} has {} listeners: {}",
+                        listeners.len(),
+                        names.join(", ")
+                    ),
+                }
+            })
+        })
+        .collect()
+}
+
+/// A local dev server (one with a project) bound beyond loopback.
+fn public(entry: &PortEntry) -> Option<Insight> {
+    let project = entry.project.as_ref()?;
+    let bind = entry.bind.as_deref()?;
+    if entry.source != PortSource::Local || entry.is_loopback {
+        return None;
+    }
+    let address = if bind == "*" {
+        "all interfaces".to_string()
+    } else {
+        bind.to_string()
+    };
+    Some(Insight {
+        kind: InsightKind::Public,
+        port: entry.local_port,
+        message: format!("dev server {project} is reachable from the network ({address})"),
+    })
+}
+
+fn time_wait(open: &[&PortEntry]) -> Vec<Insight> {
+    let mut seen = HashSet::new();
+    open.iter()
+        .filter_map(|e| Some((e, e.time_wait.filter(|&n| n >= TIME_WAIT_STORM)?)))
+        .filter(|(e, _)| seen.insert((e.local_port, e.connection.clone())))
+        .map(|(e, count)| Insight {
+            kind: InsightKind::TimeWait,
+            port: e.local_port,
+            message: format!(
+                ":{} has {count} connections in TIME_WAIT (opened and closed faster than they expire)",
+                e.local_port
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::project::{Project, ProjectKind};
+    use crate::testing::row;
+
+    fn open(port: u16) -> PortEntry {
+        let mut entry = row(PortSource::Local, port);
+        entry.is_open = true;
+        entry
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut db = open(5432);
+        db.source = PortSource::Docker;
+        db.container_id = Some("abc123".to_string());
+        db.container_name = Some("db".to_string());
+        db.health = Some("unhealthy".to_string());
+        let mut db_admin = db.clone();
+        db_admin.local_port = 5433;
+
+        let mut tunnel = open(9000);
+        tunnel.source = PortSource::Ssh;
+        tunnel.remote_host = Some("localhost".to_string());
+        tunnel.remote_port = Some(9000);
+        tunnel.ssh_host = Some("devbox".to_string());
+        tunnel.tunnel = Some(TunnelHealth::LocalOnly);
+
+        let mut node = open(3000);
+        node.pid = Some(4242);
+        node.process_name = "node".to_string();
+        node.bind = Some("*".to_string());
+        node.project = Some(Project {
+            name: "storefront".to_string(),
+            kind: ProjectKind::Node,
+            dir: "/home/me/storefront".into(),
+        });
+        node.time_wait = Some(TIME_WAIT_STORM);
+        let mut python = open(3000);
+        python.pid = Some(5000);
+        python.process_name = "python3".to_string();
+        python.bind = Some("127.0.0.1".to_string());
+        python.is_loopback = true;
+
+        let insights = collect(&[db, db_admin, tunnel, node, python], &EnvPorts::default());
+        let summary: Vec<(InsightKind, u16, &str)> = insights
+            .iter()
+            .map(|i| (i.kind, i.port, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    InsightKind::Unhealthy,
+                    5432,
+                    "container db fails its health check"
+                ),
+                (
+                    InsightKind::DeadTunnel,
+                    9000,
+                    "tunnel to localhost:9000 via devbox can't reach its destination"
+                ),
+                (
+                    InsightKind::Duplicate,
+                    3000,
+                    ":3000 has 2 listeners: node (PID 4242), python3 (PID 5000)"
+                ),
+                (
+                    InsightKind::Public,
+                    3000,
+                    "dev server storefront (node) is reachable from the network (all interfaces)"
+                ),
+                (
+                    InsightKind::TimeWait,
+                    3000,
+                    ":3000 has 500 connections in TIME_WAIT (opened and closed faster than they expire)"
+                ),
+            ]
+        );
+        assert!(collect(&[open(8080)], &EnvPorts::default()).is_empty());
+    }
+
+    #[test]
+    fn test_reverse_tunnel_without_server_listener() {
+        let mut reverse = open(8080);
+        reverse.source = PortSource::Ssh;
+        reverse.remote_host = Some("(R) localhost".to_string());
+        reverse.remote_port = Some(8080);
+        reverse.ssh_host = Some("devbox".to_string());
+        reverse.reverse_bind = Some(Vec::new());
+        let insights = collect(std::slice::from_ref(&reverse), &EnvPorts::default());
+        assert_eq!(
+            insights[0].message,
+            "-R tunnel via devbox: nothing listens on :8080 on the server"
+        );
+
+        reverse.reverse_bind = Some(vec!["127.0.0.1".to_string()]);
+        assert!(collect(&[reverse], &EnvPorts::default()).is_empty());
+    }
+}
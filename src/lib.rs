@@ -0,0 +1,29 @@
+//! Port discovery and management behind the `quay` TUI.
+//!
+//! [`port`] lists listening ports from local processes (lsof), SSH tunnels,
+//! and Docker containers, on this machine, a remote host over SSH, or inside
+//! a container. It also creates SSH forwards ([`port::ssh::create_forward`]),
+//! probes whether ports accept connections ([`port::probe_ports`],
+//! [`port::open_ports`]), and kills whatever owns a port
+//! ([`port::kill_entry`]).
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let collection = quay_core::port::collect_all(None, None, &HashMap::new()).await?;
+//! for entry in collection.entries.iter().filter(|e| e.is_open) {
+//!     println!("{} {} {}", entry.local_port, entry.source, entry.process_display());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Remote commands go through the system `ssh` client unless
+//! [`port::configure_ssh`] selects the embedded backend (feature
+//! `embedded-ssh`).
+
+pub mod port;
+#[cfg(test)]
+#[allow(dead_code)]
+mod testing;
@@ -0,0 +1,225 @@
+use crate::port::signal::{self, Signal};
+use crate::state;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+#[cfg(windows)]
+use std::io::Read;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(3);
+const TAKEOVER_POLL: Duration = Duration::from_millis(100);
+
+/// Contents of the lock file, identifying the instance that owns it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+}
+
+/// Single-instance guard for the quay process that manages SSH forwards.
+///
+/// Only one manager may restore and persist forwards at a time; a second
+/// instance would re-create the same tunnels and overwrite `forwards.toml`.
+/// The lock is global rather than per connection: every instance restores
+/// the forwards of all connections at startup, writes `forwards.toml` from
+/// all of them, and can switch to any connection with `h`/`l`, so two
+/// instances started for different hosts would still fight over the same
+/// tunnels.
+///
+/// The guard holds an `flock` on the lock file, which the kernel releases
+/// when the process exits however it exits, so there are no stale locks to
+/// clean up. The file stays behind and names the PID of the last owner.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: Lock,
+}
+
+impl InstanceLock {
+    pub fn lock_path() -> Option<PathBuf> {
+        state::dir().map(|p| p.join("quay.lock"))
+    }
+
+    /// Acquire the global instance lock.
+    ///
+    /// A live owner is an error unless `takeover` is set, in which case the
+    /// owner is sent SIGTERM and the lock is taken once it lets go.
+    pub fn acquire(takeover: bool) -> anyhow::Result<Self> {
+        let Some(path) = Self::lock_path() else {
+            anyhow::bail!("Could not determine state directory");
+        };
+        Self::acquire_at(&path, takeover)
+    }
+
+    fn acquire_at(path: &Path, takeover: bool) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut terminated: Option<Instant> = None;
+        loop {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            if let Some(file) = try_lock(file)? {
+                let info = LockInfo {
+                    pid: std::process::id(),
+                };
+                file.set_len(0)?;
+                (&*file).write_all(toml::to_string(&info)?.as_bytes())?;
+                return Ok(Self { _file: file });
+            }
+
+            // Unknown while the owner is still writing its PID
+            let owner = read_lock(path).map(|info| info.pid);
+            let Some(since) = terminated else {
+                if !takeover {
+                    let owner = owner.map_or_else(String::new, |pid| format!(" at PID {pid}"));
+                    anyhow::bail!(
+                        "quay is already running{owner} (lock: {}). \
+                         Use --takeover to replace it.",
+                        path.display()
+                    );
+                }
+                if let Some(pid) = owner {
+                    signal::send(pid, Signal::Term)
+                        .with_context(|| format!("Failed to stop running instance (PID {pid})"))?;
+                }
+                terminated = Some(Instant::now());
+                continue;
+            };
+            if since.elapsed() >= TAKEOVER_TIMEOUT {
+                let owner = owner.map_or_else(String::new, |pid| format!(" (PID {pid})"));
+                anyhow::bail!("Running instance{owner} did not exit");
+            }
+            std::thread::sleep(TAKEOVER_POLL);
+        }
+    }
+}
+
+#[cfg(unix)]
+type Lock = nix::fcntl::Flock<fs::File>;
+
+/// `file` locked for this process, or `None` while another one holds it.
+#[cfg(unix)]
+fn try_lock(file: fs::File) -> io::Result<Option<Lock>> {
+    use nix::errno::Errno;
+    use nix::fcntl::{Flock, FlockArg};
+
+    match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => Ok(Some(lock)),
+        Err((_, Errno::EWOULDBLOCK)) => Ok(None),
+        Err((_, errno)) => Err(errno.into()),
+    }
+}
+
+#[cfg(windows)]
+type Lock = fs::File;
+
+/// Without `flock`, a live PID in the file stands for the lock.
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+fn try_lock(file: fs::File) -> io::Result<Option<Lock>> {
+    let mut content = String::new();
+    let _ = (&file).read_to_string(&mut content);
+    let owner = toml::from_str::<LockInfo>(&content).ok();
+    match owner {
+        Some(info) if info.pid != std::process::id() && signal::is_alive(info.pid) => Ok(None),
+        _ => Ok(Some(file)),
+    }
+}
+
+/// SIGTERM, which `--takeover` sends to the running instance. The TUI waits
+/// for it next to key presses and leaves through its normal shutdown, so the
+/// terminal is restored and the lock released on the way out.
+pub struct Terminate {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl Terminate {
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+        })
+    }
+
+    /// Wait for the next SIGTERM; never returns where there is none.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(path).ok()?;
+    toml::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_lock_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("quay-lock-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("quay.lock")
+    }
+
+    fn exited_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn test_acquire_writes_pid_and_releases() {
+        let path = test_lock_path("release");
+        let lock = InstanceLock::acquire_at(&path, false).unwrap();
+        assert_eq!(read_lock(&path).unwrap().pid, std::process::id());
+        drop(lock);
+        assert!(InstanceLock::acquire_at(&path, false).is_ok());
+    }
+
+    #[test]
+    fn test_acquire_replaces_stale_lock() {
+        let path = test_lock_path("stale");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("pid = {}\n", exited_pid())).unwrap();
+        let _lock = InstanceLock::acquire_at(&path, false).unwrap();
+        assert_eq!(read_lock(&path).unwrap().pid, std::process::id());
+    }
+
+    #[test]
+    fn test_acquire_replaces_unreadable_lock() {
+        let path = test_lock_path("garbage");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not toml").unwrap();
+        assert!(InstanceLock::acquire_at(&path, false).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_acquire_fails_while_held() {
+        let path = test_lock_path("held");
+        // Each open file description is locked on its own, so this process
+        // can play both instances
+        let _held = InstanceLock::acquire_at(&path, false).unwrap();
+
+        let err = InstanceLock::acquire_at(&path, false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("already running at PID {}", std::process::id()))
+        );
+        assert!(err.to_string().contains("--takeover"));
+    }
+}
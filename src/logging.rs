@@ -0,0 +1,121 @@
+//! Tracing setup: recent events are kept in memory for the TUI log pane and
+//! optionally appended to a file (`--log-file`).
+
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::{self, format::Writer, time::FormatTime};
+use tracing_subscriber::prelude::*;
+
+/// Lines kept for the log pane.
+const BUFFER_LINES: usize = 500;
+
+/// Environment variable overriding the default filter (e.g. `QUAY_LOG=trace`).
+pub const FILTER_ENV: &str = "QUAY_LOG";
+
+const DEFAULT_FILTER: &str = "quay=debug";
+
+static BUFFER: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Appends each formatted event to [`BUFFER`], dropping the oldest lines.
+struct BufferWriter;
+
+impl io::Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        push_lines(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn push_lines(text: &str) {
+    if let Ok(mut lines) = BUFFER.lock() {
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            if lines.len() == BUFFER_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+        }
+    }
+}
+
+struct LocalTime(&'static str);
+
+impl FormatTime for LocalTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        write!(w, "{}", chrono::Local::now().format(self.0))
+    }
+}
+
+/// Install the global subscriber. Call once, before any work is logged.
+pub fn init(log_file: Option<&Path>) -> anyhow::Result<()> {
+    let filter =
+        EnvFilter::try_from_env(FILTER_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+
+    let buffer_layer = fmt::layer()
+        .with_ansi(false)
+        .with_target(false)
+        .with_timer(LocalTime("%H:%M:%S"))
+        .with_writer(|| BufferWriter);
+
+    let file_layer = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_timer(LocalTime("%Y-%m-%d %H:%M:%S%.3f"))
+                    .with_writer(Mutex::new(file)),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(buffer_layer)
+        .with(file_layer)
+        .try_init()
+        .context("Failed to install the logger")
+}
+
+/// The most recent `count` log lines, oldest first.
+pub fn recent(count: usize) -> Vec<String> {
+    BUFFER
+        .lock()
+        .map(|lines| {
+            let skip = lines.len().saturating_sub(count);
+            lines.iter().skip(skip).cloned().collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_keeps_recent_lines() {
+        push_lines("first\n\nsecond\n");
+        for i in 0..BUFFER_LINES {
+            push_lines(&format!("filler {i}"));
+        }
+        push_lines("last");
+        assert_eq!(
+            recent(2),
+            vec![format!("filler {}", BUFFER_LINES - 1), "last".to_string()]
+        );
+        assert_eq!(recent(usize::MAX).len(), BUFFER_LINES);
+    }
+}
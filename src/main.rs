@@ -1,81 +1,95 @@
+mod action;
 mod app;
+mod completions;
+mod compose;
 mod config;
 mod connection;
 mod dev;
+mod doctor;
+mod env_ports;
 mod event;
+mod expected;
 mod forward;
-mod port;
+mod history;
+mod hostname;
+mod insights;
+mod lock;
+mod logging;
+mod output;
+mod pick;
+mod pin;
 mod preset;
+mod query;
+mod registry;
+mod scan;
+mod schema;
+mod service;
+mod snapshot;
+mod state;
+#[cfg(test)]
+mod testing;
 mod theme;
+mod tmux;
 mod ui;
+#[cfg(test)]
+mod ui_snapshots;
+mod undo;
+mod update;
+mod wait;
+mod worker;
 
 use anyhow::Result;
-use app::{App, ConnectionPopupMode, Filter, ForwardInput, InputMode, Popup};
-use clap::{Parser, Subcommand};
+use app::{App, ConnectionPopupMode, ErrorReport, Filter, InputMode, Popup, PresetPopupMode};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use event::{
-    Action, AppEvent, handle_connection_input_key, handle_connection_key, handle_forward_key,
-    handle_key, handle_mouse, handle_popup_key, handle_preset_key, handle_search_key,
+    AppEvent, handle_custom_key, handle_key, handle_leader_key, handle_mouse,
+    handle_open_popup_key, handle_search_key,
 };
 use futures::StreamExt;
-use port::PortEntry;
+use quay_core::port::{self, PortEntry};
 use ratatui::prelude::*;
-use std::collections::HashMap;
-use std::io::{self, stdout};
-use std::time::Duration;
+use state::StateFile;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal, stdout};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use undo::UndoAction;
+use update::{Command, GENERATED_CONNECTION};
+use worker::{CollectRequest, CollectionResult, Trigger, Worker};
 
 fn save_forwards(app: &mut app::App) {
-    let persisted = forward::Forwards::from_runtime(&app.ssh_forwards, &app.connections);
-    if let Err(e) = persisted.save() {
-        app.set_status(&format!("Forward save failed: {e}"));
-    }
-}
-
-async fn refresh_and_save(app: &mut App) {
-    match port::collect_all(
-        app.remote_host.as_deref(),
-        app.docker_target.as_deref(),
-        app.known_forwards(),
-    )
-    .await
-    {
-        Ok(entries) => {
-            if app.set_entries(entries) {
-                save_forwards(app);
-            }
-        }
-        Err(e) => app.set_status(&format!("Refresh failed: {e}")),
+    let saved = forward::Forwards::update(|stored| {
+        stored.set_runtime(&app.ssh_forwards, &app.connections);
+    });
+    if let Err(e) = saved {
+        app.set_error(&format!("Forward save failed: {e}"));
     }
 }
 
+/// Look up the Docker VM and, in docker target mode, the container's IP and
+/// published ports.
 async fn resolve_container_info(app: &mut App) {
+    if !app.mock_mode {
+        app.docker_vm = port::docker::vm_backend(app.remote_host.as_deref()).await;
+    }
     if let Some(ref target) = app.docker_target {
         match port::docker::get_container_info(target, app.remote_host.as_deref()).await {
             Ok(info) => {
                 app.container_ip = Some(info.ip);
                 app.docker_port_mappings = info.port_mappings;
             }
-            Err(e) => app.set_status(&format!("Container info lookup failed: {e}")),
+            Err(e) => app.set_error(&format!("Container info lookup failed: {e}")),
         }
     }
 }
 
-fn resolve_docker_forward(
-    container_port: u16,
-    docker_port_mappings: &HashMap<u16, u16>,
-    container_ip: Option<&str>,
-) -> Option<(String, u16)> {
-    if let Some(&host_port) = docker_port_mappings.get(&container_port) {
-        return Some(("localhost".to_string(), host_port));
-    }
-    container_ip.map(|ip| (ip.to_string(), container_port))
-}
-
-#[allow(clippy::unused_async)]
 async fn restore_forwards(app: &mut App) {
     let Some(host) = app.remote_host.clone() else {
         return;
@@ -83,54 +97,483 @@ async fn restore_forwards(app: &mut App) {
     let Some(forwards) = app.ssh_forwards.get(&app.active_connection).cloned() else {
         return;
     };
-    if forwards.is_empty() {
-        return;
+    let status = restore_forwards_standalone(
+        &host,
+        &forwards,
+        app.is_docker_target(),
+        app.container_ip.as_deref(),
+        &app.docker_port_mappings,
+        app.docker_vm,
+    )
+    .await;
+    if let Some(status) = status {
+        app.set_status(&status);
     }
+}
 
-    let mut restored = 0u32;
-    let mut failed = 0u32;
+/// A forward to start: `ssh [options] -L|-R spec host`.
+struct ForwardJob {
+    spec: String,
+    host: String,
+    remote: bool,
+    options: Vec<String>,
+}
 
-    for (&container_port, &local_port) in &forwards {
-        if forward::is_port_listening(local_port) {
-            continue;
+impl ForwardJob {
+    fn of_preset(preset: &preset::Preset) -> Self {
+        Self {
+            spec: preset.spec(),
+            host: preset.ssh_host.clone(),
+            remote: false,
+            options: preset.ssh_options.clone(),
         }
-        let (remote_target, remote_port) = if app.is_docker_target() {
-            match resolve_docker_forward(
-                container_port,
-                &app.docker_port_mappings,
-                app.container_ip.as_deref(),
-            ) {
-                Some(pair) => pair,
-                None => continue,
+    }
+}
+
+/// What the UI loop does with forwards once they are up.
+enum AfterForward {
+    /// One forward: report it as "`label` (PID …)" or "`failure`: error",
+    /// keep it for undo on `undo_port`, and persist `mapping` (connection,
+    /// container port, local port).
+    Single {
+        label: String,
+        failure: &'static str,
+        undo_port: Option<u16>,
+        mapping: Option<(usize, u16, u16)>,
+    },
+    /// A group or snapshot: fill in `rows` of `results` and show them in the
+    /// launch results popup titled `title`.
+    Launch {
+        title: String,
+        results: Vec<app::LaunchResult>,
+        rows: Vec<usize>,
+    },
+}
+
+/// Forwards started off the UI loop, one outcome per job.
+struct ForwardResult {
+    after: AfterForward,
+    outcomes: Vec<anyhow::Result<port::ssh::Started>>,
+}
+
+/// Start `jobs` one after another in the background, since ssh can take
+/// seconds to connect, and send their outcomes to the UI loop.
+fn spawn_forwards(
+    jobs: Vec<ForwardJob>,
+    after: AfterForward,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let mut outcomes = Vec::new();
+        for job in jobs {
+            let result =
+                port::ssh::start_forward(&job.spec, &job.host, job.remote, &job.options).await;
+            history::record_forward(&job.spec, &job.host, job.remote, &result);
+            outcomes.push(result);
+        }
+        let _ = tx.send(ForwardResult { after, outcomes }).await;
+    });
+}
+
+/// Apply started forwards. Returns true if a refresh is needed.
+fn apply_forward_result(app: &mut App, result: ForwardResult) -> bool {
+    let started = result.outcomes.iter().filter(|r| r.is_ok()).count();
+    match result.after {
+        AfterForward::Single {
+            label,
+            failure,
+            undo_port,
+            mapping,
+        } => {
+            for outcome in result.outcomes {
+                match outcome {
+                    Ok(started) => {
+                        if let Some((connection, container_port, local_port)) = mapping {
+                            app.ssh_forwards
+                                .entry(connection)
+                                .or_default()
+                                .insert(container_port, local_port);
+                            save_forwards(app);
+                        }
+                        if let Some(port) = undo_port {
+                            remember_forward(app, port, started);
+                        }
+                        app.set_status(&format!("{label} ({started})"));
+                    }
+                    Err(e) => app.set_error(&format!("{failure}: {e}")),
+                }
             }
+        }
+        AfterForward::Launch {
+            title,
+            mut results,
+            rows,
+        } => {
+            for (row, outcome) in rows.into_iter().zip(result.outcomes) {
+                if let Some(slot) = results.get_mut(row) {
+                    slot.outcome = outcome
+                        .map(|started| format!("started ({started})"))
+                        .map_err(|e| e.to_string());
+                }
+            }
+            show_launch_results(app, title, results);
+        }
+    }
+    started > 0
+}
+
+/// Show the outcome of a group or snapshot launch, unless another popup
+/// opened in the meantime.
+fn show_launch_results(app: &mut App, title: String, results: Vec<app::LaunchResult>) {
+    if !matches!(app.popup, Popup::None | Popup::LaunchResults) {
+        let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+        app.set_status(&format!("{title}: {failed} of {} failed", results.len()));
+        return;
+    }
+    app.launch_title = title;
+    app.launch_results = results;
+    app.popup = Popup::LaunchResults;
+}
+
+/// Start `jobs` in the background for `rows` of `results`, or show
+/// `results` right away when there is nothing to start.
+fn launch(
+    app: &mut App,
+    title: String,
+    results: Vec<app::LaunchResult>,
+    jobs: Vec<ForwardJob>,
+    rows: Vec<usize>,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) {
+    if jobs.is_empty() {
+        show_launch_results(app, title, results);
+        return;
+    }
+    app.set_status(&format!("{title}: starting {} forward(s)...", jobs.len()));
+    spawn_forwards(
+        jobs,
+        AfterForward::Launch {
+            title,
+            results,
+            rows,
+        },
+        tx,
+    );
+}
+
+/// Start the SSH forward for the preset at `index` in the background.
+fn launch_preset(
+    app: &mut App,
+    index: usize,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) {
+    let Some(preset) = app.presets.get(index) else {
+        return;
+    };
+    if mock_mode {
+        app.set_status(&format!("[mock] Forward created: {}", preset.name));
+        return;
+    }
+    let after = AfterForward::Single {
+        label: format!("Forward created: {}", preset.name),
+        failure: "Forward failed",
+        undo_port: Some(preset.local_port),
+        mapping: None,
+    };
+    let job = ForwardJob::of_preset(preset);
+    app.set_status(&format!("Starting {}...", preset.name));
+    spawn_forwards(vec![job], after, tx);
+}
+
+/// Start every preset in `group` and show the per-forward outcome.
+fn launch_group(
+    app: &mut App,
+    group: &str,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) {
+    let mut results = Vec::new();
+    let mut jobs = Vec::new();
+    let mut rows = Vec::new();
+    for preset in preset::group_members(&app.presets, group) {
+        let outcome = if mock_mode {
+            Ok("[mock] started".to_string())
+        } else if forward::is_port_listening(preset.local_port) {
+            Err(format!("port {} already in use", preset.local_port))
         } else {
-            ("localhost".to_string(), container_port)
+            rows.push(results.len());
+            jobs.push(ForwardJob::of_preset(preset));
+            Ok(String::new())
         };
-        let spec = format!("{local_port}:{remote_target}:{remote_port}");
-        match port::ssh::create_forward(&spec, &host, false) {
-            Ok(_) => restored += 1,
-            Err(_) => failed += 1,
+        results.push(app::LaunchResult {
+            name: preset.name.clone(),
+            outcome,
+        });
+    }
+    launch(app, format!("Group: {group}"), results, jobs, rows, tx);
+}
+
+/// Start the forwards of the selected snapshot that aren't running and show
+/// the per-forward outcome.
+fn restore_snapshot(app: &mut App, mock_mode: bool, tx: &tokio::sync::mpsc::Sender<ForwardResult>) {
+    let Some((name, snapshot)) = app.selected_snapshot() else {
+        return;
+    };
+    let mut results = Vec::new();
+    let mut jobs = Vec::new();
+    let mut rows = Vec::new();
+    for forward in &snapshot.forward {
+        let outcome = if mock_mode {
+            Ok("[mock] started".to_string())
+        } else if forward.is_running(&app.entries) {
+            Ok("already running".to_string())
+        } else if let Some(reason) = forward.skip_reason(&app.entries) {
+            Err(reason)
+        } else {
+            rows.push(results.len());
+            jobs.push(ForwardJob {
+                spec: forward.spec.clone(),
+                host: forward.ssh_host.clone(),
+                remote: forward.remote,
+                options: Vec::new(),
+            });
+            Ok(String::new())
+        };
+        results.push(app::LaunchResult {
+            name: forward.label(),
+            outcome,
+        });
+    }
+    let title = format!("Snapshot: {name}");
+    launch(app, title, results, jobs, rows, tx);
+}
+
+/// Save the tunnels in the table under the name typed in the Snapshots popup.
+fn handle_submit_snapshot(app: &mut App, mock_mode: bool) {
+    let Some(name) = app.snapshot_name.take() else {
+        return;
+    };
+    let name = name.trim();
+    match app.save_snapshot(name) {
+        Ok(count) => {
+            if !mock_mode {
+                if let Err(e) = app.snapshots.save() {
+                    app.set_error(&format!("Save failed: {e}"));
+                    return;
+                }
+            }
+            app.set_status(&format!("Saved snapshot {name} ({count} forward(s))"));
         }
+        Err(e) => app.set_status(&e),
     }
+}
 
-    if restored > 0 && failed > 0 {
-        app.set_status(&format!("Restored {restored} forward(s), {failed} failed"));
-    } else if restored > 0 {
-        app.set_status(&format!("Restored {restored} forward(s)"));
+fn handle_delete_snapshot(app: &mut App, mock_mode: bool) {
+    let Some(name) = app.selected_snapshot().map(|(name, _)| name.clone()) else {
+        return;
+    };
+    app.snapshots.snapshots.remove(&name);
+    app.snapshot_selected = app
+        .snapshot_selected
+        .min(app.snapshots.snapshots.len().saturating_sub(1));
+    if !mock_mode {
+        if let Err(e) = app.snapshots.save() {
+            app.set_error(&format!("Save failed: {e}"));
+            return;
+        }
+    }
+    app.set_status(&format!("Deleted snapshot {name}"));
+}
+
+/// Save the preset form. `stored` and `app.presets` change only once the
+/// file is written; on failure the form stays open with its input.
+fn handle_submit_preset(app: &mut App, stored: &mut preset::Presets) {
+    let Some(preset) = app.preset_input.to_preset() else {
+        return;
+    };
+    let name = preset.name.clone();
+    let mut updated = stored.clone();
+    let (verb, index) = match app.preset_editing {
+        Some(index) if updated.update(index, preset.clone()) => ("Updated", index),
+        _ => {
+            updated.add(preset);
+            ("Saved", updated.preset.len() - 1)
+        }
+    };
+    if let Err(e) = updated.save() {
+        app.set_error(&format!("Save failed: {e}"));
+        return;
+    }
+    *stored = updated;
+    let conflicts = preset::key_conflicts(&stored.preset, event::PRESET_POPUP_KEYS);
+    if let Some(conflict) = conflicts.first() {
+        app.set_status(&format!("{verb} preset: {name} (warning: {conflict})"));
+    } else {
+        app.set_status(&format!("{verb} preset: {name}"));
+    }
+    app.presets.clone_from(&stored.preset);
+    app.preset_selected = index;
+    app.preset_popup_mode = PresetPopupMode::List;
+    app.reset_preset_input();
+}
+
+fn handle_delete_preset(app: &mut App, stored: &mut preset::Presets) {
+    let index = app.preset_selected;
+    if app.selected_preset().is_none() {
+        return;
     }
+    let Some(name) = stored.preset.get(index).map(|p| p.name.clone()) else {
+        return;
+    };
+    let mut updated = stored.clone();
+    updated.remove(index);
+    if let Err(e) = updated.save() {
+        app.set_error(&format!("Save failed: {e}"));
+        return;
+    }
+    *stored = updated;
+    app.set_status(&format!("Deleted preset: {name}"));
+    app.presets.clone_from(&stored.preset);
+    app.preset_selected = app.preset_selected.min(app.presets.len().saturating_sub(1));
+    if !app.is_preset_visible(app.preset_selected) {
+        app.preset_first();
+    }
+}
+
+/// Save the connection form over the selected connection.
+/// Returns true if the active connection now points somewhere else and must be re-activated.
+fn handle_edit_connection(app: &mut App, stored: &mut connection::Connections) -> bool {
+    let Some(mut conn) = app.connection_input.to_connection() else {
+        return false;
+    };
+    let index = app.connection_selected;
+    let Some(user_index) = index.checked_sub(1) else {
+        return false;
+    };
+    // The form only covers name and targets; keep the context and defaults
+    if let Some(old) = stored.connection.get(user_index) {
+        conn.docker_context.clone_from(&old.docker_context);
+        conn.defaults = old.defaults.clone();
+    }
+    let retarget = index == app.active_connection
+        && app.connections.get(index).is_some_and(|old| {
+            old.remote_host != conn.remote_host || old.docker_target != conn.docker_target
+        });
+    let name = conn.name.clone();
+    if !stored.update(user_index, conn) {
+        return false;
+    }
+    if let Err(e) = stored.save() {
+        app.set_error(&format!("Save failed: {e}"));
+        return false;
+    }
+    app.set_connections(stored.all_with_local());
+    app.set_status(&format!("Updated connection: {name}"));
+    retarget
+}
+
+/// Save the connection form, as a new connection or over the one being edited.
+/// Returns true if the active connection now points somewhere else and must be re-activated.
+fn handle_submit_connection(
+    app: &mut App,
+    stored: &mut connection::Connections,
+    mock_mode: bool,
+    health_tx: &tokio::sync::mpsc::Sender<HealthResult>,
+) -> bool {
+    let retarget = if app.connection_popup_mode == ConnectionPopupMode::Edit {
+        let retarget = handle_edit_connection(app, stored);
+        if !mock_mode {
+            save_forwards(app);
+            probe_new_hosts(app, health_tx);
+        }
+        if retarget {
+            app.activate_connection_ui();
+        }
+        retarget && !mock_mode
+    } else {
+        let Some(conn) = app.connection_input.to_connection() else {
+            return false;
+        };
+        let name = conn.name.clone();
+        stored.add(conn);
+        if let Err(e) = stored.save() {
+            app.set_error(&format!("Save failed: {e}"));
+        } else {
+            app.set_connections(stored.all_with_local());
+            app.set_status(&format!("Added connection: {name}"));
+            if !mock_mode {
+                probe_new_hosts(app, health_tx);
+            }
+        }
+        false
+    };
+    app.connection_popup_mode = ConnectionPopupMode::List;
+    app.reset_connection_input();
+    retarget
+}
+
+/// Delete the selected connection; deleting the active one switches to Local.
+fn handle_delete_connection(app: &mut App, stored: &mut connection::Connections) {
+    if stored.is_generated(app.connection_selected) {
+        app.set_status(GENERATED_CONNECTION);
+        return;
+    }
+    let user_index = app.connection_selected - 1;
+    let name = stored
+        .connection
+        .get(user_index)
+        .map_or("Unknown".to_string(), |c| c.name.clone());
+    if !stored.remove(user_index) {
+        return;
+    }
+    if let Err(e) = stored.save() {
+        app.set_error(&format!("Save failed: {e}"));
+        return;
+    }
+    app.set_connections(stored.all_with_local());
+    // Adjust active_connection if needed
+    if app.active_connection >= app.connections.len() {
+        app.active_connection = app.connections.len().saturating_sub(1);
+        app.apply_connection();
+    } else if app.active_connection == app.connection_selected {
+        // Deleted the active connection, switch to Local
+        app.active_connection = 0;
+        app.apply_connection();
+    }
+    // Adjust selection cursor
+    if app.connection_selected >= app.connections.len() {
+        app.connection_selected = app.connections.len().saturating_sub(1);
+    }
+    app.set_status(&format!("Deleted connection: {name}"));
 }
 
-fn activate_connection_ui(app: &mut App) {
-    app.apply_connection();
-    app.entries.clear();
-    app.apply_filter();
-    app.selected = 0;
-    app.loading = true;
-    let name = app
-        .active_connection()
-        .map_or("Unknown", |c| c.name.as_str())
-        .to_string();
-    app.set_status(&format!("Switched to: {name}"));
+/// Move the selected connection one slot up or down. Local always stays first.
+/// Returns true if the order changed.
+fn handle_move_connection(app: &mut App, stored: &mut connection::Connections, up: bool) -> bool {
+    let index = app.connection_selected;
+    let Some(user_index) = index.checked_sub(1) else {
+        app.set_status("Local connection is always first");
+        return false;
+    };
+    if stored.is_generated(index) {
+        app.set_status(GENERATED_CONNECTION);
+        return false;
+    }
+    let (moved, target) = if up {
+        (stored.move_up(user_index), index - 1)
+    } else {
+        (stored.move_down(user_index), index + 1)
+    };
+    if !moved {
+        return false;
+    }
+    if let Err(e) = stored.save() {
+        app.set_error(&format!("Save failed: {e}"));
+    }
+    app.swap_connections(index, target);
+    true
 }
 
 struct ActivationInput {
@@ -138,21 +581,48 @@ struct ActivationInput {
     docker_target: Option<String>,
     is_docker_target: bool,
     ssh_forwards_for_conn: Option<HashMap<u16, u16>>,
-    known_forwards: HashMap<u16, u16>,
     active_connection: usize,
+    /// Sent to the worker once forwards are restored.
+    collect: CollectRequest,
 }
 
 struct ActivationResult {
     active_connection: usize,
     container_ip: Option<String>,
     docker_port_mappings: HashMap<u16, u16>,
+    docker_vm: Option<port::docker::VmBackend>,
     restore_status: Option<String>,
-    entries: anyhow::Result<Vec<PortEntry>>,
 }
 
-struct RefreshResult {
-    active_connection: usize,
-    entries: anyhow::Result<Vec<PortEntry>>,
+struct HealthResult {
+    host: String,
+    health: connection::Health,
+    /// Set for probes the user asked for; the result goes to the status line.
+    report: bool,
+}
+
+/// Apply collected entries and keep every failure for the Error popup.
+fn apply_collection(
+    app: &mut App,
+    entries: anyhow::Result<port::Collection>,
+    failed: &[(String, anyhow::Error)],
+) {
+    let mut errors: Vec<ErrorReport> = failed
+        .iter()
+        .map(|(name, e)| ErrorReport::new(&format!("Collect failed on {name}"), e))
+        .collect();
+    match entries {
+        Ok(collection) => {
+            if app.set_collection(collection) && !app.mock_mode {
+                save_forwards(app);
+            }
+        }
+        Err(e) => errors.insert(0, ErrorReport::new("Refresh failed", &e)),
+    }
+    for error in &errors {
+        tracing::warn!(title = %error.title, error = %error.summary, "collection failed");
+    }
+    app.report_errors(errors);
 }
 
 fn extract_activation_input(app: &App) -> ActivationInput {
@@ -161,17 +631,18 @@ fn extract_activation_input(app: &App) -> ActivationInput {
         docker_target: app.docker_target.clone(),
         is_docker_target: app.is_docker_target(),
         ssh_forwards_for_conn: app.ssh_forwards.get(&app.active_connection).cloned(),
-        known_forwards: app.known_forwards().clone(),
         active_connection: app.active_connection,
+        collect: CollectRequest::of(app, Trigger::Manual),
     }
 }
 
-fn restore_forwards_standalone(
+async fn restore_forwards_standalone(
     host: &str,
     forwards: &HashMap<u16, u16>,
     is_docker_target: bool,
     container_ip: Option<&str>,
     docker_port_mappings: &HashMap<u16, u16>,
+    docker_vm: Option<port::docker::VmBackend>,
 ) -> Option<String> {
     if forwards.is_empty() {
         return None;
@@ -185,7 +656,12 @@ fn restore_forwards_standalone(
             continue;
         }
         let (remote_target, remote_port) = if is_docker_target {
-            match resolve_docker_forward(container_port, docker_port_mappings, container_ip) {
+            match forward::resolve_docker_forward(
+                container_port,
+                docker_port_mappings,
+                container_ip,
+                docker_vm,
+            ) {
                 Some(pair) => pair,
                 None => continue,
             }
@@ -193,7 +669,9 @@ fn restore_forwards_standalone(
             ("localhost".to_string(), container_port)
         };
         let spec = format!("{local_port}:{remote_target}:{remote_port}");
-        match port::ssh::create_forward(&spec, host, false) {
+        let result = port::ssh::start_forward(&spec, host, false, &[]).await;
+        history::record_forward(&spec, host, false, &result);
+        match result {
             Ok(_) => restored += 1,
             Err(_) => failed += 1,
         }
@@ -208,8 +686,9 @@ fn restore_forwards_standalone(
     }
 }
 
-async fn run_activation(input: ActivationInput) -> ActivationResult {
-    // 1. Resolve container info (IP + port mappings)
+async fn run_activation(input: ActivationInput, worker: &Worker) -> ActivationResult {
+    // 1. Resolve the Docker VM and container info (IP + port mappings)
+    let docker_vm = port::docker::vm_backend(input.remote_host.as_deref()).await;
     let (container_ip, docker_port_mappings) = if let Some(ref target) = input.docker_target {
         match port::docker::get_container_info(target, input.remote_host.as_deref()).await {
             Ok(info) => (Some(info.ip), info.port_mappings),
@@ -219,7 +698,7 @@ async fn run_activation(input: ActivationInput) -> ActivationResult {
         (None, HashMap::new())
     };
 
-    // 2. Restore forwards (sync, fast)
+    // 2. Restore forwards
     let restore_status = if let (Some(ref host), Some(ref forwards)) =
         (&input.remote_host, &input.ssh_forwards_for_conn)
     {
@@ -229,25 +708,22 @@ async fn run_activation(input: ActivationInput) -> ActivationResult {
             input.is_docker_target,
             container_ip.as_deref(),
             &docker_port_mappings,
+            docker_vm,
         )
+        .await
     } else {
         None
     };
 
-    // 3. Collect all ports (heavy I/O)
-    let entries = port::collect_all(
-        input.remote_host.as_deref(),
-        input.docker_target.as_deref(),
-        &input.known_forwards,
-    )
-    .await;
+    // 3. Collect all ports (heavy I/O) on the worker
+    worker.request(input.collect);
 
     ActivationResult {
         active_connection: input.active_connection,
         container_ip,
         docker_port_mappings,
+        docker_vm,
         restore_status,
-        entries,
     }
 }
 
@@ -255,94 +731,152 @@ fn apply_activation_result(app: &mut App, result: ActivationResult) {
     if app.active_connection != result.active_connection {
         return; // stale result, discard
     }
-    app.loading = false;
     app.container_ip = result.container_ip.or(app.container_ip.take());
+    app.docker_vm = result.docker_vm;
     if !result.docker_port_mappings.is_empty() {
         app.docker_port_mappings = result.docker_port_mappings;
     }
     if let Some(status) = result.restore_status {
         app.set_status(&status);
     }
-    match result.entries {
-        Ok(entries) => {
-            if app.set_entries(entries) {
-                save_forwards(app);
-            }
-        }
-        Err(e) => app.set_status(&format!("Refresh failed: {e}")),
-    }
 }
 
-fn apply_refresh_result(app: &mut App, result: RefreshResult) {
+fn apply_collection_result(app: &mut App, result: CollectionResult) {
     if app.active_connection != result.active_connection {
         return;
     }
     app.loading = false;
-    match result.entries {
-        Ok(entries) => {
-            if app.set_entries(entries) {
-                save_forwards(app);
-            }
-        }
-        Err(e) => app.set_status(&format!("Refresh failed: {e}")),
-    }
+    app.last_refresh = Some(result.elapsed);
+    apply_collection(app, result.entries, &result.failed);
 }
 
 fn spawn_activation(
     app: &App,
     handle: &mut Option<tokio::task::JoinHandle<()>>,
-    refresh_handle: &mut Option<tokio::task::JoinHandle<()>>,
+    worker: &Worker,
     tx: &tokio::sync::mpsc::Sender<ActivationResult>,
 ) {
     if let Some(h) = handle.take() {
         h.abort();
     }
-    if let Some(h) = refresh_handle.take() {
-        h.abort();
-    }
     let input = extract_activation_input(app);
+    let worker = worker.clone();
     let tx = tx.clone();
     *handle = Some(tokio::spawn(async move {
-        let result = run_activation(input).await;
+        let result = run_activation(input, &worker).await;
         let _ = tx.send(result).await;
     }));
 }
 
-fn spawn_refresh(
+/// Ask the worker to collect the current view again.
+fn request_refresh(
     app: &App,
-    refresh_handle: &mut Option<tokio::task::JoinHandle<()>>,
+    worker: &Worker,
     activation_handle: Option<&tokio::task::JoinHandle<()>>,
-    tx: &tokio::sync::mpsc::Sender<RefreshResult>,
+    trigger: Trigger,
 ) {
-    // activation 実行中なら refresh は不要 (activation が collect_all を含む)
+    // An activation in progress ends with a collection of its own
     if activation_handle.is_some_and(|h| !h.is_finished()) {
         return;
     }
-    if let Some(h) = refresh_handle.take() {
-        h.abort();
+    worker.request(CollectRequest::of(app, trigger));
+}
+
+/// Probe `host` in the background, marking it as checking until the result arrives.
+fn spawn_health_probe(
+    app: &mut App,
+    host: &str,
+    report: bool,
+    tx: &tokio::sync::mpsc::Sender<HealthResult>,
+) {
+    if !report && app.connection_health.get(host) == Some(&connection::Health::Checking) {
+        return;
     }
-    let remote_host = app.remote_host.clone();
-    let docker_target = app.docker_target.clone();
-    let known_forwards = app.known_forwards().clone();
-    let active_connection = app.active_connection;
+    app.connection_health
+        .insert(host.to_string(), connection::Health::Checking);
+    let host = host.to_string();
     let tx = tx.clone();
-    *refresh_handle = Some(tokio::spawn(async move {
-        let entries = port::collect_all(
-            remote_host.as_deref(),
-            docker_target.as_deref(),
-            &known_forwards,
-        )
-        .await;
+    tokio::spawn(async move {
+        let health = connection::probe(&host).await;
         let _ = tx
-            .send(RefreshResult {
-                active_connection,
-                entries,
+            .send(HealthResult {
+                host,
+                health,
+                report,
             })
             .await;
-    }));
+    });
+}
+
+/// Probe hosts that were added or edited in and have no result yet.
+fn probe_new_hosts(app: &mut App, tx: &tokio::sync::mpsc::Sender<HealthResult>) {
+    for host in app.remote_hosts() {
+        if !app.connection_health.contains_key(&host) {
+            spawn_health_probe(app, &host, false, tx);
+        }
+    }
+}
+
+fn apply_health_result(app: &mut App, result: HealthResult) {
+    if result.report {
+        app.set_status(&format!("{}: {}", result.host, result.health.label()));
+    }
+    // Offer an interactive login when the active connection needs a password or 2FA
+    if result.health == connection::Health::AuthFailed
+        && app.remote_host.as_deref() == Some(result.host.as_str())
+        && app.popup == Popup::None
+        && !app.auth_declined.contains(&result.host)
+    {
+        app.auth_host = Some(result.host.clone());
+        app.popup = Popup::Auth;
+    }
+    // A login would not get past a host key, so say what to fix instead
+    if result.health == connection::Health::HostKey
+        && !result.report
+        && app.remote_host.as_deref() == Some(result.host.as_str())
+        && app.connection_health.get(&result.host) != Some(&result.health)
+    {
+        app.set_error(&format!(
+            "{}: host key not verified; run `ssh {}` to see why, then fix ~/.ssh/known_hosts",
+            result.host, result.host
+        ));
+    }
+    app.connection_health.insert(result.host, result.health);
+}
+
+/// Leave the TUI, run `task` on the plain terminal, then restore the TUI.
+///
+/// For interactive prompts (ssh login, sudo). The outer error is a terminal
+/// failure; the inner value is the task's result.
+async fn with_released_terminal<T>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mouse_enabled: bool,
+    notice: &str,
+    task: impl std::future::Future<Output = T>,
+) -> Result<T> {
+    disable_raw_mode()?;
+    if mouse_enabled {
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    } else {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    }
+    println!("quay: {notice}; the TUI resumes when it is done.");
+    let result = task.await;
+    enable_raw_mode()?;
+    if mouse_enabled {
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    } else {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+    }
+    terminal.clear()?;
+    Ok(result)
 }
 
-fn handle_submit_forward(app: &mut App, mock_mode: bool) -> bool {
+fn handle_submit_forward(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) -> bool {
     let mut needs_refresh = false;
     if mock_mode {
         if app.forward_input.to_spec().is_some() {
@@ -354,12 +888,9 @@ fn handle_submit_forward(app: &mut App, mock_mode: bool) -> bool {
                 remote_port: app.forward_input.remote_port.parse().ok(),
                 process_name: "ssh".to_string(),
                 pid: Some(99999),
-                container_id: None,
-                container_name: None,
                 ssh_host: Some(app.forward_input.ssh_host.clone()),
                 is_open: true,
-                is_loopback: false,
-                forwarded_port: None,
+                ..PortEntry::default()
             };
             let mut entries = app.entries.clone();
             entries.push(mock_entry);
@@ -389,28 +920,28 @@ fn handle_submit_forward(app: &mut App, mock_mode: bool) -> bool {
             app.set_status("Forward already active, registered mapping");
             needs_refresh = true;
         } else {
-            match port::ssh::create_forward(&spec, &host, false) {
-                Ok(pid) => {
-                    if app.is_remote() {
-                        if let (Ok(rp), Ok(lp)) = (
-                            app.forward_input.remote_port.parse::<u16>(),
-                            app.forward_input.local_port.parse::<u16>(),
-                        ) {
-                            app.ssh_forwards
-                                .entry(app.active_connection)
-                                .or_default()
-                                .insert(rp, lp);
-                            save_forwards(app);
-                        }
-                    }
-                    app.set_status(&format!("Forward created (PID: {pid})"));
-                    needs_refresh = true;
-                }
-                Err(e) => {
-                    app.set_status(&format!("Forward failed: {e}"));
-                }
-            }
-        }
+            let mapping = match (
+                app.forward_input.remote_port.parse::<u16>(),
+                app.forward_input.local_port.parse::<u16>(),
+            ) {
+                (Ok(rp), Ok(lp)) if app.is_remote() => Some((app.active_connection, rp, lp)),
+                _ => None,
+            };
+            let job = ForwardJob {
+                spec,
+                host,
+                remote: false,
+                options: app.forward_input.options(),
+            };
+            let after = AfterForward::Single {
+                label: "Forward created".to_string(),
+                failure: "Forward failed",
+                undo_port: local_port,
+                mapping,
+            };
+            app.set_status("Starting forward...");
+            spawn_forwards(vec![job], after, tx);
+        }
     } else {
         app.set_status("Invalid forward specification");
     }
@@ -419,17 +950,211 @@ fn handle_submit_forward(app: &mut App, mock_mode: bool) -> bool {
     needs_refresh
 }
 
+/// Row actions need the row's own connection, so the merged view is read-only.
+/// Collect raw output for the Details popup of the selected entry.
+fn load_details(app: &mut App, mock_mode: bool, tx: &tokio::sync::mpsc::Sender<app::RawDetails>) {
+    let Some(entry) = app.selected_entry().cloned() else {
+        return;
+    };
+    if mock_mode {
+        app.details_raw = Some(app::RawDetails {
+            port: entry.local_port,
+            sections: Vec::new(),
+        });
+        return;
+    }
+    let remote_host = app.remote_host.clone();
+    let docker_target = app.docker_target.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let sections =
+            port::inspect::inspect(&entry, remote_host.as_deref(), docker_target.as_deref()).await;
+        let _ = tx
+            .send(app::RawDetails {
+                port: entry.local_port,
+                sections,
+            })
+            .await;
+    });
+}
+
+/// List the connections to the selected entry's port for the Established popup.
+fn load_established(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<app::EstablishedList>,
+) {
+    let Some(entry) = app.selected_entry().cloned() else {
+        return;
+    };
+    if mock_mode {
+        let list = app::EstablishedList {
+            port: entry.local_port,
+            connections: Ok(dev::mock::mock_established(&entry)),
+        };
+        app.apply_established(list, Instant::now());
+        return;
+    }
+    // SSH tunnels are local processes even when scanning a remote host
+    let host = if entry.source == port::PortSource::Ssh {
+        None
+    } else {
+        app.remote_host.clone()
+    };
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let port = entry.local_port;
+        let connections = port::established::collect(host.as_deref())
+            .await
+            .map(|all| all.into_iter().filter(|c| c.local_port == port).collect())
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(app::EstablishedList { port, connections }).await;
+    });
+}
+
+/// Read the recent output of the selected entry for the Output popup.
+fn load_output(app: &mut App, mock_mode: bool, tx: &tokio::sync::mpsc::Sender<app::OutputView>) {
+    let Some(entry) = app.selected_entry().cloned() else {
+        return;
+    };
+    let port = entry.local_port;
+    if mock_mode {
+        app.apply_output(app::OutputView {
+            port,
+            source: Some(format!("/tmp/{}.log", entry.process_name)),
+            lines: Ok(dev::mock::mock_output(&entry)),
+        });
+        return;
+    }
+    let host = app.remote_host.clone();
+    // Processes in the --docker target write to the container's log
+    let target = app.docker_target.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let source = match target {
+            Some(container) => Ok(port::tail::Source::Container(container)),
+            None => port::tail::find(&entry, host.as_deref()).await,
+        };
+        let view = match source {
+            Ok(source) => {
+                let host = host.filter(|_| entry.source != port::PortSource::Ssh);
+                let lines = port::tail::read(&source, host.as_deref(), port::tail::LINES)
+                    .await
+                    .map_err(|e| format!("{e:#}"));
+                app::OutputView {
+                    port,
+                    source: Some(source.to_string()),
+                    lines,
+                }
+            }
+            Err(e) => app::OutputView {
+                port,
+                source: None,
+                lines: Err(format!("{e:#}")),
+            },
+        };
+        let _ = tx.send(view).await;
+    });
+}
+
+/// Read `docker stats` for the Docker entries of the active connection in
+/// the background, unless a read is still running.
+fn fetch_container_stats(app: &mut App, tx: &tokio::sync::mpsc::Sender<app::ContainerStats>) {
+    let has_containers = app
+        .entries
+        .iter()
+        .any(|e| e.source == port::PortSource::Docker);
+    if app.container_stats_loading || app.is_aggregate() || !has_containers {
+        return;
+    }
+    app.container_stats_loading = true;
+    let remote_host = app.remote_host.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let usage = match port::docker::stats(remote_host.as_deref()).await {
+            Ok(usage) => usage,
+            Err(e) => {
+                tracing::warn!(error = %format!("{e:#}"), "docker stats failed");
+                HashMap::new()
+            }
+        };
+        let _ = tx.send(app::ContainerStats { remote_host, usage }).await;
+    });
+}
+
+/// Reverse-resolve the remote addresses no name was found for yet.
+fn resolve_host_names(
+    app: &mut App,
+    tx: &tokio::sync::mpsc::Sender<Vec<(std::net::IpAddr, Option<String>)>>,
+) {
+    let ips = app.host_names.take_unresolved(&app.entries);
+    if ips.is_empty() {
+        return;
+    }
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let _ = tx.send(hostname::lookup(ips).await).await;
+    });
+}
+
+/// A kill from the TUI that failed, reported back to the main loop.
+struct KillFailure {
+    port: u16,
+    pid: Option<u32>,
+    process: String,
+    /// Where the process runs; `None` for this machine.
+    host: Option<String>,
+    error: anyhow::Error,
+}
+
+fn apply_kill_failure(app: &mut App, failure: KillFailure) {
+    let port = failure.port;
+    app.undo.forget(port);
+    match failure.pid {
+        Some(pid) if port::signal::is_permission_denied(&failure.error) => {
+            if app.allow_sudo_kill && app.popup == Popup::None {
+                app.sudo_kill = Some(app::SudoKill {
+                    port,
+                    pid,
+                    process: failure.process,
+                    host: failure.host,
+                    owner: None,
+                });
+                app.popup = Popup::SudoKill;
+            } else {
+                app.set_error(&format!(
+                    "Kill :{port} failed: permission denied (set general.allow_sudo_kill = true to retry with sudo)"
+                ));
+            }
+        }
+        _ => app.report_errors(vec![ErrorReport::new(
+            &format!("Kill :{port} failed"),
+            &failure.error,
+        )]),
+    }
+}
+
 fn handle_kill_action(
     app: &mut App,
     mock_mode: bool,
-    tx: &tokio::sync::mpsc::Sender<RefreshResult>,
+    worker: &Worker,
+    failure_tx: &tokio::sync::mpsc::Sender<KillFailure>,
 ) {
     let Some(entry) = app.selected_entry() else {
         return;
     };
+    let killed = entry.clone();
     let port = entry.local_port;
     let pid = entry.pid;
+    let process = entry.process_name.clone();
     let is_ssh = entry.source == port::PortSource::Ssh;
+    let owner = app.foreign_owner(entry).map(str::to_string);
+    // SSH tunnels are local processes even when scanning a remote host
+    let kill_host = if is_ssh {
+        None
+    } else {
+        app.remote_host.clone()
+    };
 
     if mock_mode {
         let entries: Vec<_> = app
@@ -443,13 +1168,29 @@ fn handle_kill_action(
         return;
     }
 
-    // Pre-remove from ssh_forwards (if kill fails, the forward is already broken)
-    if is_ssh {
-        if let Some(map) = app.ssh_forwards.get_mut(&app.active_connection) {
-            map.retain(|_, &mut lp| lp != port);
-            save_forwards(app);
+    // Another user's process will refuse the signal; offer sudo before trying
+    if let (Some(owner), Some(pid), false) = (&owner, pid, app.is_docker_target()) {
+        if app.allow_sudo_kill {
+            app.sudo_kill = Some(app::SudoKill {
+                port,
+                pid,
+                process,
+                host: kill_host,
+                owner: Some(owner.clone()),
+            });
+            app.popup = Popup::SudoKill;
+            return;
         }
-    } else if app.is_docker_target() {
+    }
+
+    let undo = kill_undo_action(app);
+    let undo_hint = if undo.is_some() { " (u: undo)" } else { "" };
+    if let Some(action) = undo {
+        app.undo.push(action, Instant::now());
+    }
+
+    // Pre-remove from ssh_forwards (if kill fails, the forward is already broken)
+    if is_ssh || app.is_docker_target() {
         if let Some(map) = app.ssh_forwards.get_mut(&app.active_connection) {
             map.retain(|_, &mut lp| lp != port);
             save_forwards(app);
@@ -459,101 +1200,711 @@ fn handle_kill_action(
     let is_docker = app.is_docker_target();
     let remote_host = app.remote_host.clone();
     let docker_target = app.docker_target.clone();
-    let known_forwards = app.known_forwards().clone();
-    let active_connection = app.active_connection;
-    let tx = tx.clone();
-
-    app.set_status(&format!("Killing port {port}..."));
+    let refresh = CollectRequest::of(app, Trigger::Manual);
+    let worker = worker.clone();
+    let failure_tx = failure_tx.clone();
+
+    match &owner {
+        Some(owner) => app.set_status(&format!(
+            "Killing port {port} (owned by {owner}; set general.allow_sudo_kill = true to use sudo)..."
+        )),
+        None => app.set_status(&format!("Killing port {port}...{undo_hint}")),
+    }
 
     tokio::spawn(async move {
-        let killed = if is_docker {
-            if let Some(pid) = pid {
-                if let Some(ref target) = docker_target {
-                    let pid_str = pid.to_string();
-                    let result = match remote_host.as_deref() {
-                        Some(host) => {
-                            port::ssh_cmd_tokio(host, &["docker", "exec", target, "kill", &pid_str])
-                                .status()
-                                .await
-                        }
-                        None => {
-                            tokio::process::Command::new("docker")
-                                .args(["exec", target, "kill", &pid_str])
-                                .status()
-                                .await
-                        }
-                    };
-                    matches!(result, Ok(status) if status.success())
-                } else {
-                    false
+        let result = if is_docker {
+            match (pid, docker_target.as_deref()) {
+                (Some(pid), Some(target)) => {
+                    docker_exec_kill(pid, target, remote_host.as_deref()).await
                 }
-            } else {
-                false
+                _ => Err(anyhow::anyhow!("No PID found for port {port}")),
             }
         } else {
-            let kill_host = if is_ssh { None } else { remote_host.as_deref() };
-            port::kill_by_port(port, kill_host).await.is_ok()
+            port::kill_by_port(port, port::Signal::Term, kill_host.as_deref()).await
         };
+        let host = if is_docker { &remote_host } else { &kill_host };
+        history::record_kill(&killed, host.as_deref(), &result);
+
+        match result {
+            Ok(()) => worker.request(refresh),
+            Err(error) => {
+                let _ = failure_tx
+                    .send(KillFailure {
+                        port,
+                        pid,
+                        process,
+                        host: kill_host,
+                        error,
+                    })
+                    .await;
+            }
+        }
+    });
+}
 
-        if killed {
-            let entries = port::collect_all(
-                remote_host.as_deref(),
-                docker_target.as_deref(),
-                &known_forwards,
-            )
+/// How to take back killing the selected entry: start its forward again, or
+/// rerun a process on this machine. Docker target and remote processes can't
+/// be brought back.
+fn kill_undo_action(app: &App) -> Option<UndoAction> {
+    let entry = app.selected_entry()?;
+    let port = entry.local_port;
+    if entry.source == port::PortSource::Ssh {
+        let forward = snapshot::SnapshotForward::from_tunnel(entry)?;
+        return Some(UndoAction::StartForward { port, forward });
+    }
+    if entry.source != port::PortSource::Local || app.remote_host.is_some() {
+        return None;
+    }
+    let (command, cwd) = undo::capture(entry.pid?)?;
+    Some(UndoAction::Rerun(undo::Rerun {
+        port,
+        process: entry.process_name.clone(),
+        command,
+        cwd,
+    }))
+}
+
+/// Remember a forward started from the TUI, so `u` can stop it.
+fn remember_forward(app: &mut App, port: u16, started: port::ssh::Started) {
+    app.undo
+        .push(UndoAction::StopForward { port, started }, Instant::now());
+}
+
+/// Take back a kill or forward. Returns true if a refresh is needed.
+fn handle_undo(
+    app: &mut App,
+    action: UndoAction,
+    tx: &tokio::sync::mpsc::Sender<Result<String, String>>,
+    forward_tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) -> bool {
+    match action {
+        UndoAction::StartForward { forward, .. } => {
+            let after = AfterForward::Single {
+                label: format!("Undo: started {} again", forward.label()),
+                failure: "Undo failed",
+                undo_port: None,
+                mapping: None,
+            };
+            let job = ForwardJob {
+                spec: forward.spec,
+                host: forward.ssh_host,
+                remote: forward.remote,
+                options: Vec::new(),
+            };
+            app.set_status("Undo: starting forward...");
+            spawn_forwards(vec![job], after, forward_tx);
+            false
+        }
+        UndoAction::StopForward { port, started } => {
+            if let Some(map) = app.ssh_forwards.get_mut(&app.active_connection) {
+                map.retain(|_, &mut lp| lp != port);
+                save_forwards(app);
+            }
+            let port::ssh::Started::Process(pid) = started else {
+                // Served inside quay: only the embedded backend can stop it
+                match port::ssh::stop_embedded_forward(port) {
+                    Ok(()) => app.set_status(&format!("Undo: stopped forward :{port}")),
+                    Err(e) => app.set_error(&format!("Undo: could not stop forward :{port}: {e}")),
+                }
+                return true;
+            };
+            let mut entry = registry::placeholder_entry(port);
+            entry.source = port::PortSource::Ssh;
+            entry.pid = Some(pid);
+            app.set_status(&format!("Undo: stopping forward :{port}..."));
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let result = port::kill_entry(&entry, port::Signal::Term, None).await;
+                history::record_kill(&entry, None, &result);
+                let outcome = match result {
+                    Ok(()) => Ok(format!("Undo: stopped forward :{port}")),
+                    Err(e) => Err(format!("Undo: could not stop forward :{port}: {e}")),
+                };
+                let _ = tx.send(outcome).await;
+            });
+            false
+        }
+        UndoAction::Rerun(rerun) => {
+            let result = undo::rerun(&rerun.command, rerun.cwd.as_deref());
+            let record = history::Record::new(
+                history::Kind::Rerun,
+                Some(rerun.port),
+                &rerun.command_line(),
+                history::failure(&result),
+            );
+            history::append(&record);
+            match result {
+                Ok(pid) => {
+                    app.set_status(&format!(
+                        "Undo: started {} again (PID: {pid})",
+                        rerun.process
+                    ));
+                    true
+                }
+                Err(e) => {
+                    app.set_error(&format!("Undo failed: {e:#}"));
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Kill `pid` inside the `--docker` target container.
+async fn docker_exec_kill(pid: u32, target: &str, remote_host: Option<&str>) -> Result<()> {
+    let pid_str = pid.to_string();
+    let args = ["exec", target, "kill", &pid_str];
+    let output = port::docker::run(&args, remote_host).await?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::Error::new(port::CommandError::new(
+            "docker",
+            &args,
+            remote_host,
+            &output,
+        )))
+    }
+}
+
+/// Start custom action `index` for the selected entry. Actions that need the
+/// terminal are returned, to run once the TUI has released it.
+fn start_custom_action(app: &mut App, index: usize, mock_mode: bool) -> Option<app::PendingAction> {
+    let pending = match app.prepare_action(index) {
+        Ok(pending) => pending,
+        Err(e) => {
+            app.set_error(&e);
+            return None;
+        }
+    };
+    if mock_mode {
+        app.set_status(&format!("[mock] Would run: {}", pending.command));
+        return None;
+    }
+    if !pending.background {
+        return Some(pending);
+    }
+    match action::spawn_background(&pending.command) {
+        Ok(()) => app.set_status(&format!("Started {}", pending.name)),
+        Err(e) => app.set_error(&format!("{} failed: {e}", pending.name)),
+    }
+    None
+}
+
+fn handle_copy_address(app: &mut App) {
+    let Some(address) = app.selected_entry().map(|e| app.entry_address(e)) else {
+        return;
+    };
+    match action::copy_to_clipboard(&address) {
+        Ok(()) => app.set_status(&format!("Copied {address}")),
+        Err(e) => app.set_error(&format!("Copy failed: {e}")),
+    }
+}
+
+fn handle_open_browser(app: &mut App, mock_mode: bool) {
+    let Some(address) = app.selected_entry().map(|e| app.entry_address(e)) else {
+        return;
+    };
+    let url = format!("http://{address}");
+    if mock_mode {
+        app.set_status(&format!("[mock] Would open {url}"));
+        return;
+    }
+    match action::open_url(&url) {
+        Ok(()) => app.set_status(&format!("Opened {url}")),
+        Err(e) => app.set_error(&format!("Could not open {url}: {e}")),
+    }
+}
+
+/// Run `command` in a new tmux window or pane named `name`.
+fn open_in_tmux(app: &mut App, mock_mode: bool, name: &str, command: &str) {
+    if mock_mode {
+        app.set_status(&format!("[mock] Would open in tmux: {command}"));
+        return;
+    }
+    if !app.in_tmux {
+        app.set_status("Not inside tmux");
+        return;
+    }
+    match tmux::open(app.tmux.layout, name, command) {
+        Ok(()) => app.set_status(&format!("Opened {name} in tmux")),
+        Err(e) => app.set_error(&format!("tmux failed: {e}")),
+    }
+}
+
+fn handle_tmux_shell(app: &mut App, mock_mode: bool) {
+    match app.tmux_shell_command() {
+        Ok(command) => {
+            let port = app.selected_entry().map_or(0, |e| e.local_port);
+            open_in_tmux(app, mock_mode, &format!("shell :{port}"), &command);
+        }
+        Err(e) => app.set_status(&e),
+    }
+}
+
+/// Quick forward held open by `ssh -N` in a tmux pane; closing the pane ends it.
+fn handle_tmux_forward(app: &mut App, mock_mode: bool) {
+    let Some((port, spec, host)) = quick_forward_spec(app) else {
+        return;
+    };
+    let command = tmux::forward_command(&spec, &host);
+    open_in_tmux(app, mock_mode, &format!("forward :{port}"), &command);
+}
+
+/// Write the filtered table to a file in the current directory.
+fn handle_export(app: &mut App, format: output::Format) {
+    let path = app.export_path(format, chrono::Local::now());
+    let options = output::OutputOptions {
+        format,
+        version: schema::SCHEMA_VERSION,
+        color: false,
+    };
+    let count = app.filtered_entries.len();
+    match output::write_file(&path, &app.filtered_entries, options) {
+        Ok(()) => app.set_status(&format!("Exported {count} entries to {}", path.display())),
+        Err(e) => app.set_error(&format!("Export to {} failed: {e}", path.display())),
+    }
+}
+
+/// Outcome of rechecking the selected entry.
+struct RecheckResult {
+    active_connection: usize,
+    entry: PortEntry,
+    fresh: anyhow::Result<PortEntry>,
+}
+
+/// Re-probe the selected entry in the background, without a full rescan.
+fn spawn_recheck(app: &mut App, tx: &tokio::sync::mpsc::Sender<RecheckResult>) {
+    if app.is_docker_target() {
+        app.set_status("Recheck isn't available in docker target mode; press r to refresh");
+        return;
+    }
+    let Some(entry) = app.selected_entry().cloned() else {
+        return;
+    };
+    let remote_host = app.host_of(&entry).map(str::to_string);
+    app.set_status(&format!("Rechecking :{}...", entry.local_port));
+    let active_connection = app.active_connection;
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let fresh = port::recheck_entry(&entry, remote_host.as_deref()).await;
+        let _ = tx
+            .send(RecheckResult {
+                active_connection,
+                entry,
+                fresh,
+            })
             .await;
-            let _ = tx
-                .send(RefreshResult {
-                    active_connection,
-                    entries,
-                })
-                .await;
+    });
+}
+
+fn apply_recheck_result(app: &mut App, result: RecheckResult) {
+    if app.active_connection != result.active_connection {
+        return;
+    }
+    let port = result.entry.local_port;
+    match result.fresh {
+        Ok(fresh) => {
+            let message = match (fresh.is_open, fresh.pid) {
+                (false, _) => format!(":{port} is closed"),
+                (true, Some(pid)) => format!(":{port} is open ({}, pid {pid})", fresh.process_name),
+                (true, None) => format!(":{port} is open"),
+            };
+            app.replace_entry(&result.entry, fresh);
+            app.set_status(&message);
         }
+        Err(e) => app.report_errors(vec![ErrorReport::new(
+            &format!("Recheck of :{port} failed"),
+            &e,
+        )]),
+    }
+}
+
+/// Outcome of a scan started from the Scan popup.
+struct ScanResult {
+    active_connection: usize,
+    /// Host as shown to the user.
+    host: String,
+    ports: usize,
+    open: anyhow::Result<Vec<u16>>,
+}
+
+/// Scan the range typed in the Scan popup on the active connection's host in
+/// the background; an empty range clears the last results.
+fn spawn_scan(app: &mut App, tx: &tokio::sync::mpsc::Sender<ScanResult>) {
+    app.popup = Popup::None;
+    if app.scan_range.trim().is_empty() {
+        app.set_scan_results(&[]);
+        app.set_status("Scan results cleared");
+        return;
+    }
+    if app.scanning {
+        app.set_status("A scan is already running");
+        return;
+    }
+    let ports = match port::scan::parse_ports(&app.scan_range) {
+        Ok(ports) => ports,
+        Err(e) => {
+            app.set_error(&e.to_string());
+            return;
+        }
+    };
+    let remote_host = app.remote_host.clone();
+    let host = remote_host
+        .clone()
+        .unwrap_or_else(|| "localhost".to_string());
+    app.set_status(&format!("Scanning {} port(s) on {host}...", ports.len()));
+    app.scanning = true;
+    let active_connection = app.active_connection;
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        // Connection hosts are ssh aliases; connect where ssh would
+        let address = match remote_host {
+            Some(remote) => port::scan::resolve_ssh_host(&remote).await,
+            None => "127.0.0.1".to_string(),
+        };
+        let options = port::scan::ScanOptions::default();
+        let open = port::scan::scan(&[address], &ports, &options)
+            .await
+            .map(|open| open.into_iter().map(|(_, port)| port).collect());
+        let _ = tx
+            .send(ScanResult {
+                active_connection,
+                host,
+                ports: ports.len(),
+                open,
+            })
+            .await;
     });
 }
 
-fn handle_quick_forward(app: &mut App, mock_mode: bool) -> bool {
-    let Some(entry) = app.selected_entry() else {
-        return false;
+fn apply_scan_result(app: &mut App, result: ScanResult) {
+    app.scanning = false;
+    if app.active_connection != result.active_connection {
+        return;
+    }
+    match result.open {
+        Ok(open) => {
+            app.set_status(&format!(
+                "Scan of {}: {} of {} port(s) open",
+                result.host,
+                open.len(),
+                result.ports
+            ));
+            app.set_scan_results(&open);
+        }
+        Err(e) => app.set_error(&format!("Scan of {} failed: {e}", result.host)),
+    }
+}
+
+/// Container of the selected entry: its ID (or name) and display name.
+fn selected_container(app: &mut App) -> Option<(String, String)> {
+    let container = app
+        .selected_entry()
+        .filter(|e| e.source == port::PortSource::Docker)
+        .and_then(|e| {
+            let id = e
+                .container_id
+                .clone()
+                .or_else(|| e.container_name.clone())?;
+            Some((id.clone(), e.container_name.clone().unwrap_or(id)))
+        });
+    if container.is_none() {
+        app.set_status("Not a container");
+    }
+    container
+}
+
+/// `docker logs -f` for the selected container, to run on the released terminal.
+fn container_logs_action(app: &mut App, mock_mode: bool) -> Option<app::PendingAction> {
+    let (id, name) = selected_container(app)?;
+    let logs = port::docker::command(&["logs", "-f", "--tail", "200", &id]);
+    let logs = action::quote_command(&logs.iter().map(String::as_str).collect::<Vec<_>>());
+    let command = match app.remote_host.as_deref() {
+        Some(host) => action::quote_command(&["ssh", "-t", host, &logs]),
+        None => logs,
+    };
+    if mock_mode {
+        app.set_status(&format!("[mock] Would run: {command}"));
+        return None;
+    }
+    Some(app::PendingAction {
+        name: format!("logs of {name}"),
+        command,
+        background: false,
+    })
+}
+
+/// Restart the selected container in the background; the outcome arrives on `tx`.
+fn handle_restart_container(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<Result<String, String>>,
+) {
+    let Some((id, name)) = selected_container(app) else {
+        return;
     };
+    if mock_mode {
+        app.set_status(&format!("[mock] Would restart {name}"));
+        return;
+    }
+    app.set_status(&format!("Restarting {name}..."));
+    let port = app.selected_entry().map(|e| e.local_port);
+    let host = app.remote_host.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let args = ["restart", id.as_str()];
+        let output = port::docker::run(&args, host.as_deref()).await;
+        let outcome = match output {
+            Ok(output) if output.status.success() => Ok(format!("Restarted {name}")),
+            Ok(output) => Err(format!(
+                "Restart of {name} failed: {}",
+                port::CommandError::new("docker", &args, host.as_deref(), &output)
+            )),
+            Err(e) => Err(format!("Restart of {name} failed: {e}")),
+        };
+        let record = history::Record::new(
+            history::Kind::RestartContainer,
+            port,
+            &format!("container {name}"),
+            outcome.as_ref().err().cloned(),
+        );
+        history::append(&record.on_host(host.as_deref()));
+        let _ = tx.send(outcome).await;
+    });
+}
+
+/// Kill the selected process and run its command line again once its port
+/// is free. A container entry restarts its container instead.
+fn handle_restart_process(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<Result<String, String>>,
+) {
+    let Some(entry) = app.selected_entry().cloned() else {
+        return;
+    };
+    if entry.source == port::PortSource::Docker && !app.is_docker_target() {
+        handle_restart_container(app, mock_mode, tx);
+        return;
+    }
     let port = entry.local_port;
+    if entry.source != port::PortSource::Local || app.is_remote() || app.is_docker_target() {
+        app.set_status("Only processes on this machine can be restarted");
+        return;
+    }
+    if let Some(owner) = app.foreign_owner(&entry) {
+        app.set_error(&format!(":{port} is owned by {owner}; not restarted"));
+        return;
+    }
+    if mock_mode {
+        app.set_status(&format!("[mock] Would restart {}", entry.process_name));
+        return;
+    }
+    let Some((command, cwd)) = entry.pid.and_then(undo::capture) else {
+        app.set_error(&format!(
+            "Restart :{port} failed: command line of {} not readable",
+            entry.process_display()
+        ));
+        return;
+    };
+    let rerun = undo::Rerun {
+        port,
+        process: entry.process_name.clone(),
+        command,
+        cwd,
+    };
+    app.set_status(&format!("Restarting {} on :{port}...", rerun.process));
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let outcome = match undo::restart(&entry, &rerun).await {
+            Ok(pid) => Ok(format!(
+                "Restarted {} on :{port} (PID: {pid})",
+                rerun.process
+            )),
+            Err(e) => Err(format!("Restart :{port} failed: {e:#}")),
+        };
+        let _ = tx.send(outcome).await;
+    });
+}
+
+/// Run the start command of the confirmed or selected service (Procfile,
+/// `.quay.toml`, or a `ports.toml` `start`) and watch it until its port opens.
+fn handle_start_service(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<Result<String, String>>,
+) {
+    let Some(service) = app.start_prompt.take().or_else(|| app.startable_service()) else {
+        app.set_status("No start command for this port");
+        return;
+    };
+    let command = service.command.as_deref().unwrap_or_default();
+    let (name, port) = (service.name.clone(), service.port);
+    if mock_mode {
+        app.set_status(&format!("[mock] Would start {name}: {command}"));
+        return;
+    }
+    let started = action::start_service(command, &service.dir, port)
+        .map_err(|e| anyhow::anyhow!("Failed to run {command}: {e}"));
+    history::append(&history::Record::new(
+        history::Kind::Start,
+        Some(port),
+        &format!("{name}: {command}"),
+        history::failure(&started),
+    ));
+    let child = match started {
+        Ok(child) => child,
+        Err(e) => {
+            app.set_error(&format!("Start {name} failed: {e:#}"));
+            return;
+        }
+    };
+    let pid = child.id().unwrap_or_default();
+    let pending = Arc::new(AtomicBool::new(true));
+    app.started.insert(
+        port,
+        app::StartedService {
+            name: name.clone(),
+            pid,
+            pending: Arc::clone(&pending),
+        },
+    );
+    app.set_status(&format!(
+        "Starting {name} (PID: {pid}); waiting for :{port}"
+    ));
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let outcome = expected::wait_until_up(child, &name, port).await;
+        pending.store(false, Ordering::Relaxed);
+        let _ = tx.send(outcome).await;
+    });
+}
+
+/// Stop the ssh processes of idle forwards (see `App::idle_tunnels`). `auto`
+/// is the `[tunnels] close_idle` policy, which stays quiet with nothing to close.
+fn handle_close_idle_tunnels(
+    app: &mut App,
+    mock_mode: bool,
+    auto: bool,
+    tx: &tokio::sync::mpsc::Sender<Result<String, String>>,
+) {
+    let idle = app.idle_tunnels(Instant::now());
+    if idle.is_empty() {
+        if !auto {
+            app.set_status(if app.tunnels.idle_after().is_some() {
+                "No idle tunnels"
+            } else {
+                "Idle tunnels aren't tracked (tunnels.idle_minutes = 0)"
+            });
+        }
+        return;
+    }
+    let ports: Vec<u16> = idle.iter().map(|e| e.local_port).collect();
+    let list = ports
+        .iter()
+        .map(|p| format!(":{p}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Not again before the next refresh shows whether they are gone
+    for entry in &idle {
+        app.tunnel_activity.remove(&(entry.pid, entry.local_port));
+    }
+    if mock_mode {
+        app.set_status(&format!("[mock] Would close idle tunnels {list}"));
+        return;
+    }
+    if let Some(map) = app.ssh_forwards.get_mut(&app.active_connection) {
+        map.retain(|_, local_port| !ports.contains(local_port));
+        save_forwards(app);
+    }
+    app.set_status(&format!("Closing idle tunnels {list}..."));
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let mut stopped = HashSet::new();
+        let mut failed = Vec::new();
+        for entry in &idle {
+            // One ssh process may serve several of them; embedded forwards
+            // have no PID and are stopped one by one
+            if entry.pid.is_some() && !stopped.insert(entry.pid) {
+                continue;
+            }
+            let result = port::kill_entry(entry, port::Signal::Term, None).await;
+            history::record_kill(entry, None, &result);
+            if let Err(e) = result {
+                failed.push(format!(":{} ({e})", entry.local_port));
+            }
+        }
+        let outcome = if failed.is_empty() {
+            Ok(format!("Closed idle tunnels {list}"))
+        } else {
+            Err(format!("Could not close {}", failed.join(", ")))
+        };
+        let _ = tx.send(outcome).await;
+    });
+}
+
+/// The selected entry forwarded to the same local port: `(port, -L spec, ssh host)`.
+///
+/// Reports why not in the status bar when it can't be forwarded.
+fn quick_forward_spec(app: &mut App) -> Option<(u16, String, String)> {
+    let port = app.selected_entry()?.local_port;
 
     let Some(host) = app.remote_host.clone() else {
-        if app.is_docker_target() {
+        if let (true, Some(vm)) = (app.is_docker_target(), app.docker_vm) {
+            let status = match app.docker_port_mappings.get(&port) {
+                Some(host_port) => {
+                    format!("Published on localhost:{host_port} through {vm}; no forward needed")
+                }
+                None => forward::docker_forward_error(port, Some(vm)),
+            };
+            app.set_status(&status);
+        } else if app.is_docker_target() {
             app.set_status("Quick Forward for local Docker not yet supported");
         } else {
             app.set_status("Quick Forward requires --remote mode");
         }
-        return false;
+        return None;
     };
 
     let (forward_target, remote_port) = if app.is_docker_target() {
-        match resolve_docker_forward(port, &app.docker_port_mappings, app.container_ip.as_deref()) {
-            Some(pair) => pair,
-            None => {
-                app.set_status("Container IP not available");
-                return false;
-            }
+        if let Some(pair) = forward::resolve_docker_forward(
+            port,
+            &app.docker_port_mappings,
+            app.container_ip.as_deref(),
+            app.docker_vm,
+        ) {
+            pair
+        } else {
+            app.set_status(&forward::docker_forward_error(port, app.docker_vm));
+            return None;
         }
     } else {
         ("localhost".to_string(), port)
     };
-    let spec = format!("{port}:{forward_target}:{remote_port}");
+    Some((port, format!("{port}:{forward_target}:{remote_port}"), host))
+}
+
+fn handle_quick_forward(
+    app: &mut App,
+    mock_mode: bool,
+    tx: &tokio::sync::mpsc::Sender<ForwardResult>,
+) -> bool {
+    let Some((port, spec, host)) = quick_forward_spec(app) else {
+        return false;
+    };
 
     if mock_mode {
         let mock_entry = PortEntry {
             source: port::PortSource::Ssh,
             local_port: port,
-            remote_host: Some(forward_target.clone()),
+            remote_host: spec.split(':').nth(1).map(str::to_string),
             remote_port: Some(port),
             process_name: "ssh".to_string(),
             pid: Some(99999),
-            container_id: None,
-            container_name: None,
             ssh_host: Some(host.clone()),
             is_open: true,
-            is_loopback: false,
-            forwarded_port: None,
+            ..PortEntry::default()
         };
         let mut entries = app.entries.clone();
         entries.push(mock_entry);
@@ -570,21 +1921,21 @@ fn handle_quick_forward(app: &mut App, mock_mode: bool) -> bool {
         app.set_status("Forward already active, registered mapping");
         true
     } else {
-        match port::ssh::create_forward(&spec, &host, false) {
-            Ok(pid) => {
-                app.ssh_forwards
-                    .entry(app.active_connection)
-                    .or_default()
-                    .insert(port, port);
-                save_forwards(app);
-                app.set_status(&format!("Forward :{port} -> {host}:{port} (PID: {pid})"));
-                true
-            }
-            Err(e) => {
-                app.set_status(&format!("Forward failed: {e}"));
-                false
-            }
-        }
+        let after = AfterForward::Single {
+            label: format!("Forward :{port} -> {host}:{port}"),
+            failure: "Forward failed",
+            undo_port: Some(port),
+            mapping: Some((app.active_connection, port, port)),
+        };
+        let job = ForwardJob {
+            spec,
+            host,
+            remote: false,
+            options: Vec::new(),
+        };
+        app.set_status(&format!("Starting forward :{port}..."));
+        spawn_forwards(vec![job], after, tx);
+        false
     }
 }
 
@@ -597,7 +1948,7 @@ fn handle_connection_switch(app: &mut App, direction: i32, mock_mode: bool) -> b
     } else {
         app.prev_connection();
     }
-    activate_connection_ui(app);
+    app.activate_connection_ui();
     !mock_mode
 }
 
@@ -605,26 +1956,96 @@ fn handle_connection_switch(app: &mut App, direction: i32, mock_mode: bool) -> b
 #[command(name = "quay")]
 #[command(about = "A TUI port manager for local processes, SSH forwards, and Docker containers")]
 #[command(version)]
+#[allow(clippy::struct_excessive_bools)]
 struct Cli {
     /// Remote host (e.g., user@server) to scan ports via SSH
-    #[arg(short, long)]
+    #[arg(short, long, add = ArgValueCandidates::new(completions::connection_hosts))]
     remote: Option<String>,
 
     /// Docker container to scan ports inside (e.g., syntopic-dev)
     #[arg(short = 'd', long)]
     docker: Option<String>,
 
+    /// Docker context for docker commands (see `docker context ls`)
+    #[arg(long, value_name = "NAME")]
+    docker_context: Option<String>,
+
+    /// Skip the Docker collector (overrides `[sources] docker`)
+    #[arg(long, global = true)]
+    no_docker: bool,
+
+    /// Skip the SSH tunnel collector (overrides `[sources] ssh`)
+    #[arg(long, global = true)]
+    no_ssh: bool,
+
+    #[command(flatten)]
+    compose: ComposeArgs,
+
+    /// Replace an already running quay instance instead of exiting
+    #[arg(long)]
+    takeover: bool,
+
+    /// Exit with an error if any config file has errors or warnings instead of
+    /// using defaults (see `quay config validate`)
+    #[arg(long)]
+    strict_config: bool,
+
+    /// Disable colored output (also honored: the `NO_COLOR` environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Append debug logs to this file (override the filter with e.g. `QUAY_LOG=trace`)
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(clap::Args, Default)]
+struct ComposeArgs {
+    /// Show the ports of the compose file in the current directory as
+    /// expected, flagging services that are down
+    #[arg(long)]
+    compose: bool,
+
+    /// Compose file to read expected ports from (implies --compose)
+    #[arg(long, value_name = "PATH")]
+    compose_file: Option<std::path::PathBuf>,
+}
+
+impl ComposeArgs {
+    /// The compose file of `--compose-file`, or of the current directory
+    /// with `--compose`.
+    fn load(self) -> Result<Option<compose::ComposeFile>> {
+        if !self.compose && self.compose_file.is_none() {
+            return Ok(None);
+        }
+        let path = compose::ComposeFile::resolve(self.compose_file)?;
+        let compose = compose::ComposeFile::load(&path)?;
+        if compose.services.is_empty() {
+            anyhow::bail!("{} has no services with published ports", path.display());
+        }
+        Ok(Some(compose))
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List all ports (non-interactive)
     List {
-        /// Output as JSON
-        #[arg(long)]
+        /// Output format [default: table, or from the --out file extension]
+        #[arg(long, value_enum)]
+        format: Option<output::Format>,
+        /// Write to this file instead of stdout (e.g. ports.md, ports.csv, ports.json)
+        #[arg(long, value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+        /// Output as JSON (same as `--format json`)
+        #[arg(long, conflicts_with = "format")]
         json: bool,
+        /// JSON schema version to emit; pin it in scripts
+        #[arg(long, value_name = "N", default_value_t = schema::SCHEMA_VERSION, value_parser = schema::parse_version)]
+        output_version: u32,
         /// Show only local ports
         #[arg(long)]
         local: bool,
@@ -634,25 +2055,102 @@ enum Commands {
         /// Show only Docker ports
         #[arg(long)]
         docker: bool,
+        /// Scan several hosts concurrently, one per flag (-r web1 -r web2),
+        /// into one list with a HOST column
+        #[arg(short, long = "remote", value_name = "HOST", add = ArgValueCandidates::new(completions::connection_hosts))]
+        remotes: Vec<String>,
+        /// Scan the hosts of a `[[remote_group]]` from config.toml, like -r
+        #[arg(long, value_name = "NAME")]
+        remote_group: Option<String>,
     },
-    /// Create an SSH port forward
+    /// Create an SSH port forward, or list and stop running ones
+    ///
+    /// Example: quay forward 8080:localhost:80 prod; quay forward kill --all
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Forward {
+        #[command(subcommand)]
+        command: Option<forward::ForwardCommands>,
         /// Port specification (e.g., 8080:localhost:80)
-        spec: String,
+        #[arg(required = true)]
+        spec: Option<String>,
         /// Remote host
-        host: String,
+        #[arg(required = true, add = ArgValueCandidates::new(completions::connection_hosts))]
+        host: Option<String>,
         /// Remote forward (-R instead of -L)
         #[arg(short = 'R', long)]
         remote: bool,
+        /// Listen on every interface (0.0.0.0), not just loopback, so other
+        /// machines can connect; -R needs `GatewayPorts clientspecified` on the server
+        #[arg(long)]
+        bind_all: bool,
+        /// Extra ssh arguments, e.g. "-C -o ServerAliveInterval=30 -i ~/.ssh/deploy"
+        #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+        ssh_options: Option<String>,
+    },
+    /// Kill processes holding ports
+    ///
+    /// Example: quay kill 3000 3001 --name node --dry-run
+    Kill(KillArgs),
+    /// Print entries one per line for fzf, or act on a selected one
+    ///
+    /// Example: quay pick | fzf | quay pick --kill
+    #[command(group = clap::ArgGroup::new("action").multiple(false))]
+    Pick {
+        /// Fuzzy-match this instead of reading a selection from stdin
+        query: Option<String>,
+        /// Kill the process holding the selected port
+        #[arg(long, group = "action")]
+        kill: bool,
+        /// Forward the selected port from the remote host (same port number)
+        #[arg(long, group = "action")]
+        forward: bool,
+        /// Print the selected port number
+        #[arg(long, group = "action")]
+        print_port: bool,
+    },
+    /// Start every preset forward in a group
+    Up {
+        /// Preset group name
+        #[arg(add = ArgValueCandidates::new(completions::preset_groups))]
+        group: String,
+    },
+    /// Stop every preset forward in a group
+    Down {
+        /// Preset group name
+        #[arg(add = ArgValueCandidates::new(completions::preset_groups))]
+        group: String,
+    },
+    /// List presets and start or stop them by name
+    ///
+    /// Example: quay preset up api db
+    Preset {
+        #[command(subcommand)]
+        command: preset::PresetCommands,
+    },
+    /// Save the running SSH forwards and start them again later
+    ///
+    /// Example: quay snapshot save work; quay snapshot restore work
+    Snapshot {
+        #[command(subcommand)]
+        command: snapshot::SnapshotCommands,
     },
-    /// Kill process on a port
-    Kill {
-        /// Port number
-        port: u16,
-        /// Kill by PID instead of port
-        #[arg(long)]
-        pid: Option<u32>,
+    /// Block until ports are open (or closed) locally, on --remote, or in --docker
+    ///
+    /// Example: quay --remote prod wait -p 5432,6379 --timeout 120
+    Wait(wait::WaitArgs),
+    /// Probe a port range (or a subnet) from here and list the open ports
+    ///
+    /// Example: quay scan 8000-9000 --host 10.0.0.5; quay scan 22,80,443 --host 10.0.0.0/24
+    Scan(scan::ScanArgs),
+    /// Show, edit, or validate configuration files
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommands,
     },
+    /// Check external tools, config files, and connections, suggesting fixes
+    Doctor,
+    /// Print a shell completion script (e.g. `source <(quay completions bash)`)
+    Completions { shell: clap_complete::Shell },
     /// Developer tools for testing and debugging
     Dev {
         #[command(subcommand)]
@@ -660,157 +2158,777 @@ enum Commands {
     },
 }
 
+/// Load config.toml and apply SSH settings, honoring `--strict-config`.
+///
+/// With `--strict-config`, problems in the other files (presets,
+/// connections, ports, ...) are fatal too, not only config.toml's.
+fn load_config(cli: &Cli) -> Result<config::Config> {
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        // `quay config` reports problems itself
+        Err(_) if matches!(cli.command, Some(Commands::Config { .. })) => config::Config::default(),
+        Err(diagnostic) if cli.strict_config => {
+            anyhow::bail!("{}\n{}", diagnostic.path.display(), diagnostic.detail);
+        }
+        Err(diagnostic) => {
+            // The TUI shows a banner instead
+            if cli.command.is_some() {
+                eprintln!("warning: {diagnostic} (using defaults)");
+            }
+            config::Config::default()
+        }
+    };
+    port::configure_probe(&config.probe);
+    port::configure_sources(&config_overrides(cli).sources(&config.sources));
+    port::docker::configure_context(
+        cli.docker_context
+            .as_deref()
+            .or(config.general.docker_context.as_deref()),
+    );
+    if cli.strict_config && !matches!(cli.command, Some(Commands::Config { .. })) {
+        let problems = config::problems();
+        if !problems.is_empty() {
+            anyhow::bail!(
+                "{}\nRun `quay config validate` for details.",
+                problems.join("\n")
+            );
+        }
+    }
+    if let Some(warning) = port::configure_ssh(&config.ssh) {
+        if cli.strict_config {
+            anyhow::bail!("{warning}");
+        }
+        if cli.command.is_some() && !matches!(cli.command, Some(Commands::Config { .. })) {
+            eprintln!("warning: {warning}");
+        }
+    }
+    Ok(config)
+}
+
+/// Config values set by command-line flags, for `quay config`.
+fn config_overrides(cli: &Cli) -> config::ConfigOverrides {
+    config::ConfigOverrides {
+        remote_host: cli.remote.clone(),
+        docker_target: cli.docker.clone(),
+        docker_context: cli.docker_context.clone(),
+        no_docker: cli.no_docker,
+        no_ssh: cli.no_ssh,
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Answers completion requests from the script `quay completions` installs
+    CompleteEnv::with_factory(Cli::command)
+        .var(completions::COMPLETE_ENV)
+        .complete();
+
     let cli = Cli::parse();
+    logging::init(cli.log_file.as_deref())?;
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "quay starting");
 
     // Resolve remote_host and docker_target: CLI flags take precedence over config
-    let config = config::Config::load();
-    let remote_host = cli.remote.or(config.general.remote_host);
-    let docker_target = cli.docker.or(config.general.docker_target);
+    let config = load_config(&cli)?;
+    let overrides = config_overrides(&cli);
+    let remote_host = cli.remote.or_else(|| config.general.remote_host.clone());
+    let docker_target = cli.docker.or_else(|| config.general.docker_target.clone());
 
     match cli.command {
         Some(Commands::List {
+            format,
+            out,
             json,
+            output_version,
             local,
             ssh,
             docker,
+            remotes,
+            remote_group,
         }) => {
+            let options = list_options(format, json, out.as_deref(), output_version, cli.no_color);
+            let hosts = list_hosts(remotes, remote_group.as_deref(), &config)?;
+            let target = ListTarget::new(&hosts, remote_group.is_some()).unwrap_or(
+                ListTarget::One(remote_host.as_deref(), docker_target.as_deref()),
+            );
             run_list(
-                json,
-                local,
-                ssh,
-                docker,
+                options,
+                out.as_deref(),
+                list_source(local, ssh, docker),
+                target,
+            )
+            .await
+        }
+        Some(Commands::Forward {
+            command: Some(command),
+            ..
+        }) => forward::run_forward_command(command, output::color_enabled(cli.no_color)).await,
+        Some(Commands::Forward {
+            spec,
+            host,
+            remote,
+            bind_all,
+            ssh_options,
+            ..
+        }) => {
+            // clap requires both when no subcommand is given
+            let (Some(spec), Some(host)) = (spec, host) else {
+                anyhow::bail!("forward needs a port specification and a host");
+            };
+            let options = port::ssh::split_options(ssh_options.as_deref().unwrap_or_default());
+            run_forward(&spec, &host, remote, bind_all, &options).await?;
+            hold_embedded_forwards().await
+        }
+        Some(Commands::Kill(args)) => {
+            run_kill(
+                &args,
+                remote_host.as_deref(),
+                config.general.allow_sudo_kill,
+            )
+            .await
+        }
+        Some(Commands::Pick {
+            query,
+            kill,
+            forward,
+            print_port,
+        }) => {
+            run_pick(
+                query.as_deref(),
+                PickAction::from_flags(kill, forward, print_port),
                 remote_host.as_deref(),
                 docker_target.as_deref(),
             )
             .await
         }
-        Some(Commands::Forward { spec, host, remote }) => run_forward(&spec, &host, remote).await,
-        Some(Commands::Kill { port, pid }) => run_kill(port, pid, remote_host.as_deref()).await,
-        Some(Commands::Dev { command }) => dev::run_dev(command).await,
-        None => run_tui(remote_host, docker_target).await,
+        Some(Commands::Up { group }) => {
+            run_up(&group).await?;
+            hold_embedded_forwards().await
+        }
+        Some(Commands::Down { group }) => run_down(&group).await,
+        Some(Commands::Preset { command }) => run_preset(command).await,
+        Some(Commands::Snapshot { command }) => run_snapshot(command).await,
+        Some(Commands::Wait(args)) => {
+            wait::run(&args, remote_host.as_deref(), docker_target.as_deref()).await
+        }
+        Some(Commands::Scan(args)) => scan::run(&args, remote_host.as_deref(), cli.no_color).await,
+        Some(Commands::Config { command }) => config::run_config(&command, &overrides),
+        Some(Commands::Doctor) => {
+            doctor::run(remote_host.as_deref(), docker_target.as_deref()).await
+        }
+        Some(Commands::Completions { shell }) => {
+            completions::write_script(shell, &mut io::stdout())?;
+            Ok(())
+        }
+        Some(Commands::Dev { command }) => {
+            dev::run_dev(command, output::color_enabled(cli.no_color)).await
+        }
+        None => run_tui(remote_host, docker_target, cli.takeover, cli.compose).await,
     }
 }
 
-#[allow(clippy::fn_params_excessive_bools)]
-async fn run_list(
+/// Output options for `quay list`: `--json` wins, then `--format`, then the
+/// `--out` file extension. Files get no colors.
+fn list_options(
+    format: Option<output::Format>,
     json: bool,
-    local: bool,
-    ssh: bool,
-    docker: bool,
-    remote_host: Option<&str>,
-    docker_target: Option<&str>,
+    out: Option<&std::path::Path>,
+    version: u32,
+    no_color: bool,
+) -> output::OutputOptions {
+    let format = if json {
+        output::Format::Json
+    } else {
+        format
+            .or_else(|| out.and_then(output::Format::from_path))
+            .unwrap_or_default()
+    };
+    output::OutputOptions {
+        format,
+        version,
+        color: out.is_none() && output::color_enabled(no_color),
+    }
+}
+
+/// What `quay list` scans.
+enum ListTarget<'a> {
+    /// This machine, or one remote host and/or container.
+    One(Option<&'a str>, Option<&'a str>),
+    /// Several hosts at once (`-r a -r b`, `--remote-group`).
+    Hosts(&'a [String]),
+}
+
+impl<'a> ListTarget<'a> {
+    /// The target of `-r` and `--remote-group` hosts; `None` without any.
+    /// One `-r` host is scanned like `--remote`.
+    fn new(hosts: &'a [String], group: bool) -> Option<Self> {
+        match hosts {
+            [] => None,
+            [host] if !group => Some(ListTarget::One(Some(host), None)),
+            hosts => Some(ListTarget::Hosts(hosts)),
+        }
+    }
+}
+
+/// The source `--local`, `--ssh`, or `--docker` limits `quay list` to.
+fn list_source(local: bool, ssh: bool, docker: bool) -> Option<port::PortSource> {
+    if local {
+        Some(port::PortSource::Local)
+    } else if ssh {
+        Some(port::PortSource::Ssh)
+    } else if docker {
+        Some(port::PortSource::Docker)
+    } else {
+        None
+    }
+}
+
+/// The hosts of `-r` flags and then of `--remote-group`, each once.
+fn list_hosts(
+    remotes: Vec<String>,
+    group: Option<&str>,
+    config: &config::Config,
+) -> Result<Vec<String>> {
+    let mut hosts = remotes;
+    if let Some(group) = group {
+        hosts.extend(config.remote_group(group)?.iter().cloned());
+    }
+    let mut seen = HashSet::new();
+    hosts.retain(|host| seen.insert(host.clone()));
+    Ok(hosts)
+}
+
+async fn run_list(
+    options: output::OutputOptions,
+    out: Option<&std::path::Path>,
+    source: Option<port::PortSource>,
+    target: ListTarget<'_>,
 ) -> Result<()> {
-    let entries = port::collect_all(remote_host, docker_target, &HashMap::new()).await?;
+    let (entries, failed) = match target {
+        ListTarget::One(remote_host, docker_target) => {
+            let entries = port::collect_all(remote_host, docker_target, &HashMap::new())
+                .await?
+                .entries;
+            (entries, Vec::new())
+        }
+        ListTarget::Hosts(hosts) => collect_hosts(hosts).await,
+    };
 
     let filtered: Vec<_> = entries
         .into_iter()
-        .filter(|e| {
-            if local {
-                e.source == port::PortSource::Local
-            } else if ssh {
-                e.source == port::PortSource::Ssh
-            } else if docker {
-                e.source == port::PortSource::Docker
-            } else {
-                true
-            }
+        .filter(|e| source.as_ref().is_none_or(|source| e.source == *source))
+        .collect();
+
+    if let Some(path) = out {
+        output::write_file(path, &filtered, options)?;
+        println!("Wrote {} entries to {}", filtered.len(), path.display());
+    } else {
+        let mut stdout = io::stdout().lock();
+        output::write_entries(&mut stdout, &filtered, options)?;
+    }
+    // The hosts that answered are listed; the exit code still tells CI
+    for (host, e) in &failed {
+        eprintln!("quay: {host}: {e:#}");
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("{} host(s) could not be scanned", failed.len());
+    }
+    Ok(())
+}
+
+/// Ports of every host in `hosts`, scanned concurrently and tagged with
+/// their host (`PortEntry::connection`), and the hosts that failed. This
+/// machine's SSH tunnels are listed once, as `local`.
+async fn collect_hosts(hosts: &[String]) -> (Vec<port::PortEntry>, Vec<(String, anyhow::Error)>) {
+    let targets = hosts
+        .iter()
+        .map(|host| port::CollectTarget {
+            name: host.clone(),
+            remote_host: Some(host.clone()),
+            docker_target: None,
+            docker_context: None,
+            known_forwards: HashMap::new(),
         })
         .collect();
+    let (collection, failed) = port::collect_connections(targets).await;
+    (collection.entries, failed)
+}
 
-    if json {
-        let json_entries: Vec<_> = filtered
-            .iter()
-            .map(|e| {
-                serde_json::json!({
-                    "source": format!("{:?}", e.source),
-                    "local_port": e.local_port,
-                    "is_open": e.is_open,
-                    "remote_host": e.remote_host,
-                    "remote_port": e.remote_port,
-                    "process_name": e.process_name,
-                    "pid": e.pid,
-                    "container_id": e.container_id,
-                    "container_name": e.container_name,
-                    "ssh_host": e.ssh_host,
-                    "is_loopback": e.is_loopback,
-                })
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&json_entries)?);
+#[allow(clippy::unused_async)]
+async fn run_forward(
+    spec: &str,
+    host: &str,
+    remote: bool,
+    bind_all: bool,
+    options: &[String],
+) -> Result<()> {
+    let spec = if bind_all {
+        eprintln!(
+            "Warning: listening on {}: anyone who can reach {} can use this forward",
+            port::ssh::BIND_ALL_ADDRESS,
+            if remote { "the server" } else { "this machine" }
+        );
+        &port::ssh::bind_all(spec)
     } else {
+        spec
+    };
+    println!(
+        "Creating SSH forward: ssh {}",
+        port::ssh::forward_args(spec, host, remote, options).join(" ")
+    );
+
+    let result = port::ssh::start_forward(spec, host, remote, options).await;
+    history::record_forward(spec, host, remote, &result);
+    match result {
+        Ok(port::ssh::Started::Process(pid)) => {
+            println!("Started with PID: {pid}");
+            Ok(())
+        }
+        Ok(port::ssh::Started::Embedded) => {
+            println!("Started in-process");
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to create forward: {e}");
+            Err(e)
+        }
+    }
+}
+
+/// Embedded forwards die with the process, so keep a CLI invocation running while they serve.
+#[cfg_attr(not(feature = "embedded-ssh"), allow(clippy::unused_async))]
+async fn hold_embedded_forwards() -> Result<()> {
+    #[cfg(feature = "embedded-ssh")]
+    if !port::embedded::forwards().is_empty() {
+        println!("Forwarding in-process (embedded SSH backend); press Ctrl-C to stop");
+        tokio::signal::ctrl_c().await?;
+    }
+    Ok(())
+}
+
+/// Load presets and select the members of `group`, with a helpful error if there are none.
+fn load_group(group: &str) -> Result<Vec<preset::Preset>> {
+    let presets = preset::Presets::load()?.preset;
+    let members: Vec<preset::Preset> = preset::group_members(&presets, group)
+        .into_iter()
+        .cloned()
+        .collect();
+    if members.is_empty() {
+        let groups = preset::groups(&presets);
+        if groups.is_empty() {
+            anyhow::bail!("No preset group '{group}' (no presets define a group)");
+        }
+        anyhow::bail!(
+            "No preset group '{group}'. Available groups: {}",
+            groups.join(", ")
+        );
+    }
+    Ok(members)
+}
+
+async fn run_up(group: &str) -> Result<()> {
+    let members = load_group(group)?;
+    start_presets(&format!("group '{group}'"), &members).await
+}
+
+async fn run_down(group: &str) -> Result<()> {
+    let members = load_group(group)?;
+    stop_presets(&format!("group '{group}'"), &members).await
+}
+
+async fn run_preset(command: preset::PresetCommands) -> Result<()> {
+    let presets = preset::Presets::load()?.preset;
+    match command {
+        preset::PresetCommands::List => run_preset_list(&presets).await,
+        preset::PresetCommands::Up { names } => {
+            let selected: Vec<preset::Preset> = preset::by_names(&presets, &names)?
+                .into_iter()
+                .cloned()
+                .collect();
+            start_presets(&names.join(", "), &selected).await?;
+            hold_embedded_forwards().await
+        }
+        preset::PresetCommands::Down { names } => {
+            let selected: Vec<preset::Preset> = preset::by_names(&presets, &names)?
+                .into_iter()
+                .cloned()
+                .collect();
+            stop_presets(&names.join(", "), &selected).await
+        }
+    }
+}
+
+async fn run_snapshot(command: snapshot::SnapshotCommands) -> Result<()> {
+    let restore = matches!(command, snapshot::SnapshotCommands::Restore { .. });
+    snapshot::run_snapshot_command(command).await?;
+    if restore {
+        hold_embedded_forwards().await?;
+    }
+    Ok(())
+}
+
+async fn run_preset_list(presets: &[preset::Preset]) -> Result<()> {
+    if presets.is_empty() {
+        println!("No presets defined (add them in the TUI with `p` or in presets.toml)");
+        return Ok(());
+    }
+    let forwards = port::ssh::collect().await?;
+    println!(
+        "{:<20} {:<4} {:<10} {:<32} STATUS",
+        "NAME", "KEY", "GROUP", "FORWARD"
+    );
+    for preset in presets {
+        let running = forwards.iter().find(|e| preset.is_tunnel(e)).map(|e| e.pid);
+        let status = match running {
+            Some(Some(pid)) => format!("running (PID {pid})"),
+            Some(None) => "running".to_string(),
+            None if forward::is_port_listening(preset.local_port) => "port in use".to_string(),
+            None => "stopped".to_string(),
+        };
         println!(
-            "{:<8} {:<6} {:<8} {:<20} PROCESS",
-            "TYPE", "OPEN", "LOCAL", "REMOTE"
+            "{:<20} {:<4} {:<10} {:<32} {status}",
+            preset.name,
+            preset.key.as_deref().unwrap_or("-"),
+            preset.group.as_deref().unwrap_or("-"),
+            format!("{} via {}", preset.spec(), preset.ssh_host),
         );
-        println!("{}", "-".repeat(66));
-        for entry in filtered {
-            let open_indicator = if entry.is_open { "●" } else { "○" };
-            let local_display = if let Some(fwd) = entry.forwarded_port {
-                format!(":{}→:{}", entry.local_port, fwd)
-            } else {
-                format!(":{}", entry.local_port)
-            };
+    }
+    Ok(())
+}
+
+/// Start `members`. A preset whose tunnel already runs is skipped; one whose
+/// local port is held by something else counts as failed.
+async fn start_presets(label: &str, members: &[preset::Preset]) -> Result<()> {
+    let forwards = port::ssh::collect().await?;
+    println!("Starting {label} ({} preset(s))", members.len());
+
+    let mut failed = 0;
+    for preset in members {
+        let target = format!("{} via {}", preset.spec(), preset.ssh_host);
+        if forwards.iter().any(|e| preset.is_tunnel(e)) {
+            println!("  skip  {:<20} already running", preset.name);
+            continue;
+        }
+        if forward::is_port_listening(preset.local_port) {
+            failed += 1;
             println!(
-                "{:<8} {:<6} {:<14} {:<20} {}",
-                entry.source,
-                open_indicator,
-                local_display,
-                entry.remote_display(),
-                entry.process_display()
+                "  fail  {:<20} port {} already in use",
+                preset.name, preset.local_port
             );
+            continue;
+        }
+        let spec = preset.spec();
+        let result =
+            port::ssh::create_forward_checked(&spec, &preset.ssh_host, false, &preset.ssh_options);
+        history::record_forward(&spec, &preset.ssh_host, false, &result);
+        match result {
+            Ok(()) => println!("  ok    {:<20} {target}", preset.name),
+            Err(e) => {
+                failed += 1;
+                println!("  fail  {:<20} {target}: {e}", preset.name);
+            }
         }
     }
 
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} forward(s) failed", members.len());
+    }
     Ok(())
 }
 
-#[allow(clippy::unused_async)]
-async fn run_forward(spec: &str, host: &str, remote: bool) -> Result<()> {
-    let flag = if remote { "-R" } else { "-L" };
-    println!("Creating SSH forward: ssh -f -N {flag} {spec} {host}");
+async fn stop_presets(label: &str, members: &[preset::Preset]) -> Result<()> {
+    let forwards = port::ssh::collect().await?;
+    println!("Stopping {label} ({} preset(s))", members.len());
+
+    let mut failed = 0;
+    for preset in members {
+        let tunnel = forwards.iter().find(|e| preset.is_tunnel(e));
+        match tunnel.and_then(|e| Some((e, e.pid?))) {
+            None if forward::is_port_listening(preset.local_port) => println!(
+                "  skip  {:<20} :{} is held by something else",
+                preset.name, preset.local_port
+            ),
+            None => println!("  skip  {:<20} not running", preset.name),
+            Some((tunnel, pid)) => {
+                let result = port::kill_by_pid(pid, port::Signal::Term, None).await;
+                history::record_kill(tunnel, None, &result);
+                match result {
+                    Ok(()) => println!("  ok    {:<20} stopped PID {pid}", preset.name),
+                    Err(e) => {
+                        failed += 1;
+                        println!("  fail  {:<20} {e}", preset.name);
+                    }
+                }
+            }
+        }
+    }
 
-    match port::ssh::create_forward(spec, host, remote) {
-        Ok(pid) => {
-            println!("Started with PID: {pid}");
-            Ok(())
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} forward(s) could not be stopped",
+            members.len()
+        );
+    }
+    Ok(())
+}
+
+#[derive(clap::Args)]
+#[command(group = clap::ArgGroup::new("target").required(true).multiple(true))]
+struct KillArgs {
+    /// Port numbers
+    #[arg(group = "target")]
+    ports: Vec<u16>,
+    /// Kill by PID instead of port
+    #[arg(long, group = "target")]
+    pid: Option<u32>,
+    /// Kill every process (or container) whose name contains PROCESS
+    #[arg(long, value_name = "PROCESS", group = "target")]
+    name: Option<String>,
+    /// Signal to send (TERM, KILL, INT, HUP, ... or 1, 2, 3, 9, 15)
+    #[arg(short, long, default_value = "TERM")]
+    signal: port::Signal,
+    /// Print what would be killed without sending anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+async fn run_kill(request: &KillArgs, remote_host: Option<&str>, allow_sudo: bool) -> Result<()> {
+    let signal = request.signal;
+    if let Some(pid) = request.pid {
+        if request.dry_run {
+            println!("Would send {signal} to PID {pid}");
+        } else {
+            let result = port::kill_by_pid(pid, signal, remote_host).await;
+            let result = sudo_fallback(result, pid, signal, remote_host, allow_sudo).await;
+            let record = history::Record::new(
+                history::Kind::Kill,
+                None,
+                &format!("PID {pid}"),
+                history::failure(&result),
+            );
+            history::append(&record.on_host(remote_host));
+            result?;
+            println!("Sent {signal} to PID {pid}");
         }
-        Err(e) => {
-            eprintln!("Failed to create forward: {e}");
-            Err(e)
+    }
+    if request.ports.is_empty() && request.name.is_none() {
+        return Ok(());
+    }
+
+    let entries = port::collect_entries(remote_host).await?.entries;
+    let targets = port::select_kill_targets(&entries, &request.ports, request.name.as_deref())?;
+    if request.dry_run {
+        for entry in &targets {
+            println!(
+                "Would send {signal} to :{} {}",
+                entry.local_port,
+                entry.process_display()
+            );
+        }
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for entry in &targets {
+        let mut result = port::kill_entry(entry, signal, remote_host).await;
+        if let Some(pid) = entry.pid {
+            // SSH tunnels are local processes even when scanning a remote host
+            let host = remote_host.filter(|_| entry.source != port::PortSource::Ssh);
+            result = sudo_fallback(result, pid, signal, host, allow_sudo).await;
+        }
+        history::record_kill(entry, remote_host, &result);
+        match result {
+            Ok(()) => println!(
+                "Sent {signal} to :{} {}",
+                entry.local_port,
+                entry.process_display()
+            ),
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed on :{}: {e}", entry.local_port);
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} kill(s) failed", targets.len());
+    }
+    Ok(())
+}
+
+/// Retry a kill that failed for lack of permission through sudo, when
+/// `general.allow_sudo_kill` is set and there is a terminal to prompt on.
+async fn sudo_fallback(
+    result: Result<()>,
+    pid: u32,
+    signal: port::Signal,
+    host: Option<&str>,
+    allow_sudo: bool,
+) -> Result<()> {
+    let Err(e) = result else {
+        return Ok(());
+    };
+    if !port::signal::is_permission_denied(&e) {
+        return Err(e);
+    }
+    if !allow_sudo {
+        anyhow::bail!("{e} (set general.allow_sudo_kill = true to retry with sudo)");
+    }
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("{e} (retrying with sudo needs a terminal)");
+    }
+    eprintln!("{e}; retrying with sudo");
+    port::sudo_kill(pid, signal, host).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PickAction {
+    Kill,
+    Forward,
+    PrintPort,
+}
+
+impl PickAction {
+    fn from_flags(kill: bool, forward: bool, print_port: bool) -> Option<Self> {
+        if kill {
+            Some(Self::Kill)
+        } else if forward {
+            Some(Self::Forward)
+        } else if print_port {
+            Some(Self::PrintPort)
+        } else {
+            None
         }
     }
 }
 
-async fn run_kill(port: u16, pid: Option<u32>, remote_host: Option<&str>) -> Result<()> {
-    if let Some(pid) = pid {
-        println!("Killing process with PID: {pid}...");
-        port::kill_by_pid(pid, remote_host).await?;
-        println!("Done.");
+async fn run_pick(
+    query: Option<&str>,
+    action: Option<PickAction>,
+    remote_host: Option<&str>,
+    docker_target: Option<&str>,
+) -> Result<()> {
+    let entries = port::collect_all(remote_host, docker_target, &HashMap::new())
+        .await?
+        .entries;
+
+    let Some(action) = action else {
+        for entry in &entries {
+            if query.is_none_or(|q| pick::fuzzy_score(q, &pick::line(entry)).is_some()) {
+                println!("{}", pick::line(entry));
+            }
+        }
+        return Ok(());
+    };
+
+    let selected: Vec<&PortEntry> = if let Some(query) = query {
+        vec![
+            pick::best_match(&entries, query)
+                .ok_or_else(|| anyhow::anyhow!("No entry matches '{query}'"))?,
+        ]
+    } else if io::stdin().is_terminal() {
+        anyhow::bail!(
+            "Nothing selected: pipe a line from `quay pick` (e.g. through fzf) or pass a query"
+        );
     } else {
-        println!("Killing process on port: {port}...");
-        port::kill_by_port(port, remote_host).await?;
-        println!("Done.");
+        let mut selected = Vec::new();
+        for line in io::stdin().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            selected.push(
+                pick::find(&entries, &line)
+                    .ok_or_else(|| anyhow::anyhow!("No entry for selection '{line}'"))?,
+            );
+        }
+        selected
+    };
+
+    for entry in selected {
+        let port = entry.local_port;
+        match action {
+            PickAction::PrintPort => println!("{port}"),
+            PickAction::Kill => {
+                if docker_target.is_some() {
+                    anyhow::bail!("--kill is not supported for Docker targets");
+                }
+                let result = port::kill_entry(entry, port::Signal::Term, remote_host).await;
+                history::record_kill(entry, remote_host, &result);
+                result?;
+                println!("Killed :{port} ({})", entry.process_display());
+            }
+            PickAction::Forward => {
+                let Some(host) = remote_host else {
+                    anyhow::bail!("--forward needs a remote host (--remote)");
+                };
+                let (target, remote_port) = match docker_target {
+                    Some(container) => {
+                        let info = port::docker::get_container_info(container, Some(host)).await?;
+                        let vm = port::docker::vm_backend(Some(host)).await;
+                        let ip = Some(info.ip.as_str());
+                        forward::resolve_docker_forward(port, &info.port_mappings, ip, vm)
+                            .ok_or_else(|| {
+                                anyhow::anyhow!(forward::docker_forward_error(port, vm))
+                            })?
+                    }
+                    None => ("localhost".to_string(), port),
+                };
+                if forward::is_port_listening(port) {
+                    println!("Port {port} is already in use locally; skipped");
+                    continue;
+                }
+                let spec = format!("{port}:{target}:{remote_port}");
+                let result = port::ssh::start_forward(&spec, host, false, &[]).await;
+                history::record_forward(&spec, host, false, &result);
+                result?;
+                println!("Forwarding :{port} -> {host} ({target}:{remote_port})");
+            }
+        }
+    }
+    if action == PickAction::Forward {
+        hold_embedded_forwards().await?;
     }
     Ok(())
 }
 
-async fn run_tui(remote_host: Option<String>, docker_target: Option<String>) -> Result<()> {
-    run_tui_with_entries(None, remote_host, docker_target).await
+async fn run_tui(
+    remote_host: Option<String>,
+    docker_target: Option<String>,
+    takeover: bool,
+    compose: ComposeArgs,
+) -> Result<()> {
+    let compose = compose.load()?;
+    // Only one instance may manage (restore/persist) forwards at a time
+    let _lock = lock::InstanceLock::acquire(takeover)?;
+    run_tui_with_entries(None, remote_host, docker_target, None, compose).await
 }
 
 #[allow(clippy::too_many_lines)]
 pub(crate) async fn run_tui_with_entries(
-    initial: Option<Vec<PortEntry>>,
+    initial: Option<dev::mock::MockFeed>,
     remote_host: Option<String>,
     docker_target: Option<String>,
+    replay: Option<dev::replay::Script>,
+    compose: Option<compose::ComposeFile>,
 ) -> Result<()> {
     let mock_mode = initial.is_some();
+    let mut terminate = lock::Terminate::new()?;
 
     // Load config first (needed for terminal setup)
-    let config = config::Config::load();
+    let mut diagnostics = Vec::new();
+    let config = config::Config::load().unwrap_or_else(|d| {
+        diagnostics.push(d);
+        config::Config::default()
+    });
     let mouse_enabled = config.ui.mouse_enabled;
+    port::configure_probe(&config.probe);
+    if let Some(warning) = port::configure_ssh(&config.ssh) {
+        diagnostics.push(config::ConfigDiagnostic {
+            path: config::Config::config_path().unwrap_or_default(),
+            line: None,
+            message: warning.clone(),
+            detail: warning,
+        });
+    }
 
     // Setup terminal
     enable_raw_mode()?;
@@ -825,46 +2943,88 @@ pub(crate) async fn run_tui_with_entries(
 
     // Create app state
     let mut app = App::new();
+    app.config_diagnostics = diagnostics;
+    app.mock_mode = mock_mode;
     app.remote_host = remote_host;
     app.docker_target = docker_target;
+    app.allow_sudo_kill = config.general.allow_sudo_kill;
+    app.custom_actions = config.actions.clone();
+    app.tmux = config.tmux.clone();
+    app.tunnels = config.tunnels.clone();
+    app.services = service::Services::new(&config.services);
+    app.host_names = hostname::HostNames::load(&config.hosts, config.ui.reverse_dns && !mock_mode);
+    app.in_tmux = tmux::inside();
+    app.local_user = port::signal::current_user();
+    app.compact = config.ui.compact;
+    app.layout = config.ui.layout;
+    app.latency_column = config.ui.latency_column;
+    app.usage_columns = config.ui.usage_columns;
+    app.ignore_ports = config.general.ignore_ports.iter().copied().collect();
+    app.ignore_processes = config.general.ignore_processes.clone();
 
     // Resolve container info (IP + port mappings) for docker target mode
     resolve_container_info(&mut app).await;
 
-    // Apply config settings
-    if !mock_mode {
-        app.auto_refresh = config.general.auto_refresh;
-    }
-    app.refresh_ticks = config.general.refresh_interval.saturating_mul(4).max(1);
-    match config.general.default_filter.as_str() {
-        "local" => app.filter = Filter::Local,
-        "ssh" => app.filter = Filter::Ssh,
-        "docker" => app.filter = Filter::Docker,
-        _ => app.filter = Filter::All,
-    }
+    // Apply config settings (connections may override them, see apply_connection_defaults)
+    app.view_defaults = app::ViewDefaults {
+        filter: Filter::from_name(&config.general.default_filter),
+        auto_refresh: !mock_mode && config.general.auto_refresh,
+        refresh_ticks: app::refresh_ticks_for(config.general.refresh_interval),
+        remote_refresh_ticks: app::refresh_ticks_for(
+            config
+                .general
+                .remote_refresh_interval
+                .unwrap_or(config.general.refresh_interval),
+        ),
+    };
 
     // Load presets
-    let presets = preset::Presets::load();
-    app.presets = presets.preset;
+    let mut stored_presets = preset::Presets::load_or_default(&mut app.config_diagnostics);
+    app.presets = stored_presets.preset.clone();
+    if let Some(path) = preset::Presets::presets_path() {
+        for conflict in preset::key_conflicts(&app.presets, event::PRESET_POPUP_KEYS) {
+            app.config_diagnostics.push(config::ConfigDiagnostic {
+                path: path.clone(),
+                line: None,
+                message: conflict.clone(),
+                detail: conflict,
+            });
+        }
+    }
+
+    // Load named port registry (global + per-project)
+    let (registry, registry_diagnostics) = registry::PortRegistry::load();
+    app.registry = registry;
+    app.config_diagnostics.extend(registry_diagnostics);
+    app.compose = compose;
+    let (expected, expected_diagnostics) = expected::Expected::load();
+    app.expected = expected;
+    app.config_diagnostics.extend(expected_diagnostics);
+    app.env_ports = env_ports::EnvPorts::load();
 
     // Load connections
-    let mut stored_connections = connection::Connections::load();
-    let all_connections = stored_connections.all_with_local();
-    app.connections = all_connections;
+    let mut stored_connections =
+        connection::Connections::load_or_default(&mut app.config_diagnostics);
+    let mut all_connections = stored_connections.all_with_local();
 
     // In mock mode, add sample connections for testing h/l switching
-    if mock_mode && app.connections.len() <= 1 {
-        app.connections.push(connection::Connection {
+    if mock_mode && all_connections.len() <= 1 {
+        all_connections.push(connection::Connection {
             name: "Production".to_string(),
             remote_host: Some("user@prod-server".to_string()),
             docker_target: None,
+            docker_context: None,
+            defaults: connection::ConnectionDefaults::default(),
         });
-        app.connections.push(connection::Connection {
+        all_connections.push(connection::Connection {
             name: "AI Lab".to_string(),
             remote_host: Some("ailab".to_string()),
             docker_target: Some("syntopic-dev".to_string()),
+            docker_context: None,
+            defaults: connection::ConnectionDefaults::default(),
         });
     }
+    app.set_connections(all_connections);
 
     // CLI args: find matching connection or keep Local with overrides
     if app.remote_host.is_some() || app.docker_target.is_some() {
@@ -880,38 +3040,182 @@ pub(crate) async fn run_tui_with_entries(
             // Keep Local (index 0) but CLI values already override remote_host/docker_target
         }
     }
+    app.apply_connection_defaults();
 
     // Load persisted forward mappings
     if !mock_mode {
-        let mut stored_forwards = forward::Forwards::load();
-        if stored_forwards.remove_stale() {
-            let _ = stored_forwards.save();
-        }
-        app.ssh_forwards = stored_forwards.to_runtime(&app.connections);
+        let stored_forwards = forward::Forwards::update(|stored| {
+            stored.remove_stale();
+            stored.to_runtime(&app.connections)
+        });
+        app.ssh_forwards = stored_forwards.unwrap_or_else(|e| {
+            match e.downcast::<config::ConfigDiagnostic>() {
+                Ok(d) => app.config_diagnostics.push(d),
+                Err(e) => tracing::warn!("forwards.toml: {e}"),
+            }
+            HashMap::new()
+        });
+        history::prune();
+        app.pins = pin::Pins::load_or_default(&mut app.config_diagnostics);
+        app.snapshots = snapshot::Snapshots::load_or_default(&mut app.config_diagnostics);
     }
 
     // Load initial data
-    if let Some(entries) = initial {
-        app.set_entries(entries);
-        app.loading = false;
-        app.set_status("[mock] Loaded mock data");
-    } else {
+    let (worker, mut collection_rx) = match initial {
+        Some(feed) => {
+            app.set_entries(feed.entries);
+            app.loading = false;
+            app.set_status("[mock] Loaded mock data");
+            match feed.churn {
+                Some(generator) => {
+                    app.mock_churn = true;
+                    app.auto_refresh = true;
+                    Worker::spawn_mock(generator)
+                }
+                None => Worker::spawn(),
+            }
+        }
+        None => Worker::spawn(),
+    };
+    if !mock_mode {
         restore_forwards(&mut app).await;
-        refresh_and_save(&mut app).await;
-        app.loading = false;
+        worker.request(CollectRequest::of(&app, Trigger::Manual));
+    }
+
+    // Probe connection health in the background
+    let (health_tx, mut health_rx) = tokio::sync::mpsc::channel::<HealthResult>(8);
+    let (kill_failure_tx, mut kill_failure_rx) = tokio::sync::mpsc::channel::<KillFailure>(4);
+    let (details_tx, mut details_rx) = tokio::sync::mpsc::channel::<app::RawDetails>(4);
+    let (established_tx, mut established_rx) =
+        tokio::sync::mpsc::channel::<app::EstablishedList>(4);
+    let (output_tx, mut output_rx) = tokio::sync::mpsc::channel::<app::OutputView>(4);
+    let (names_tx, mut names_rx) = tokio::sync::mpsc::channel(4);
+    let (stats_tx, mut stats_rx) = tokio::sync::mpsc::channel::<app::ContainerStats>(1);
+    // Outcome of a container restart or of closing idle tunnels
+    let (outcome_tx, mut outcome_rx) = tokio::sync::mpsc::channel::<Result<String, String>>(4);
+    let (scan_tx, mut scan_rx) = tokio::sync::mpsc::channel::<ScanResult>(1);
+    let (recheck_tx, mut recheck_rx) = tokio::sync::mpsc::channel::<RecheckResult>(4);
+    let (forward_tx, mut forward_rx) = tokio::sync::mpsc::channel::<ForwardResult>(4);
+    if mock_mode {
+        for (host, health) in app.remote_hosts().into_iter().zip([
+            connection::Health::Reachable(Duration::from_millis(42)),
+            connection::Health::AuthFailed,
+        ]) {
+            app.connection_health.insert(host, health);
+        }
+    } else {
+        for host in app.remote_hosts() {
+            spawn_health_probe(&mut app, &host, false, &health_tx);
+        }
+    }
+
+    // Main loop
+    let (activation_tx, mut activation_rx) = tokio::sync::mpsc::channel::<ActivationResult>(1);
+    let mut activation_handle: Option<tokio::task::JoinHandle<()>> = None;
+    let mut reader = EventStream::new();
+    // Key presses of a `quay dev replay` script, handled like typed ones
+    let (replay_tx, mut replay_rx) = tokio::sync::mpsc::channel(1);
+    if let Some(script) = replay {
+        tokio::spawn(script.play(replay_tx));
     }
+    let mut tick_interval = tokio::time::interval(Duration::from_millis(250));
+    tick_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    // Host to log in to interactively, and hosts with a master opened that way
+    let mut pending_auth: Option<String> = None;
+    let mut authenticated_hosts: Vec<String> = Vec::new();
+    // Kill confirmed in the SudoKill popup, run once the terminal is released
+    let mut pending_sudo: Option<app::SudoKill> = None;
+    // Custom action to run on the released terminal
+    let mut pending_action: Option<app::PendingAction> = None;
+    // Where the table was last drawn, for mouse hit-testing
+    let mut layout = ui::ScreenLayout::default();
+    let mut clicks = event::ClickTracker::default();
+
+    loop {
+        if let Some(host) = pending_auth.take() {
+            if port::ssh_backend() == config::SshBackend::Embedded {
+                app.set_status(&format!(
+                    "Interactive login to {host} needs ssh.backend = \"openssh\""
+                ));
+                continue;
+            }
+            // The event reader would swallow the keys typed at ssh's prompt
+            drop(reader);
+            let result = with_released_terminal(
+                &mut terminal,
+                mouse_enabled,
+                &format!("logging in to {host}"),
+                port::ssh::authenticate_interactive(&host),
+            )
+            .await?;
+            reader = EventStream::new();
+            match result {
+                Ok(()) => {
+                    app.set_status(&format!("Authenticated to {host}"));
+                    app.auth_declined.remove(&host);
+                    if !authenticated_hosts.contains(&host) {
+                        authenticated_hosts.push(host.clone());
+                    }
+                    spawn_health_probe(&mut app, &host, false, &health_tx);
+                    app.loading = true;
+                    spawn_activation(&app, &mut activation_handle, &worker, &activation_tx);
+                }
+                Err(e) => app.set_error(&format!("Authentication to {host} failed: {e}")),
+            }
+        }
 
-    // Main loop
-    let (activation_tx, mut activation_rx) = tokio::sync::mpsc::channel::<ActivationResult>(1);
-    let mut activation_handle: Option<tokio::task::JoinHandle<()>> = None;
-    let (refresh_tx, mut refresh_rx) = tokio::sync::mpsc::channel::<RefreshResult>(1);
-    let mut refresh_handle: Option<tokio::task::JoinHandle<()>> = None;
-    let mut reader = EventStream::new();
-    let mut tick_interval = tokio::time::interval(Duration::from_millis(250));
-    tick_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        if let Some(request) = pending_sudo.take() {
+            // The event reader would swallow the keys typed at sudo's prompt
+            drop(reader);
+            let result = with_released_terminal(
+                &mut terminal,
+                mouse_enabled,
+                &format!("sudo kill of PID {}", request.pid),
+                port::sudo_kill(request.pid, port::Signal::Term, request.host.as_deref()),
+            )
+            .await?;
+            reader = EventStream::new();
+            let record = history::Record::new(
+                history::Kind::Kill,
+                Some(request.port),
+                &format!("{} (PID {}) with sudo", request.process, request.pid),
+                history::failure(&result),
+            );
+            history::append(&record.on_host(request.host.as_deref()));
+            match result {
+                Ok(()) => {
+                    app.set_status(&format!("Killed :{} with sudo", request.port));
+                    request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
+                }
+                Err(e) => app.set_error(&format!("Kill :{} failed: {e}", request.port)),
+            }
+        }
 
-    loop {
-        terminal.draw(|f| ui::draw(f, &app))?;
+        if let Some(pending) = pending_action.take() {
+            // The event reader would swallow the keys meant for the command
+            drop(reader);
+            let result = with_released_terminal(
+                &mut terminal,
+                mouse_enabled,
+                &format!("running {}", pending.name),
+                action::run_interactive(&pending.command),
+            )
+            .await?;
+            reader = EventStream::new();
+            match result {
+                Ok(status) if status.success() => {
+                    app.set_status(&format!("{} finished", pending.name));
+                }
+                Ok(status) => app.set_error(&format!("{} exited with {status}", pending.name)),
+                Err(e) => app.set_error(&format!("{} failed: {e}", pending.name)),
+            }
+            // The command may have started or stopped listeners
+            request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
+        }
+
+        terminal.draw(|f| layout = ui::draw(f, &app))?;
+        app.table_offset = layout.table_offset;
+        app.popup_max_scroll = layout.popup_max_scroll;
 
         let event = tokio::select! {
             event = reader.next() => match event {
@@ -919,376 +3223,256 @@ pub(crate) async fn run_tui_with_entries(
                     AppEvent::Key(key)
                 }
                 Some(Ok(Event::Mouse(mouse))) => AppEvent::Mouse(mouse),
+                Some(Ok(Event::Resize(..))) => AppEvent::Resize,
                 Some(Ok(_) | Err(_)) => continue,
                 None => break,
             },
+            Some(key) = replay_rx.recv() => AppEvent::Key(key),
+            () = terminate.recv() => break,
             result = activation_rx.recv() => {
                 if let Some(result) = result {
                     apply_activation_result(&mut app, result);
                 }
                 continue;
             },
-            result = refresh_rx.recv() => {
+            result = collection_rx.recv() => {
+                if let Some(result) = result {
+                    apply_collection_result(&mut app, result);
+                    resolve_host_names(&mut app, &names_tx);
+                    if !mock_mode {
+                        fetch_container_stats(&mut app, &stats_tx);
+                    }
+                    if app.tunnels.close_idle && !app.is_aggregate() {
+                        handle_close_idle_tunnels(&mut app, mock_mode, true, &outcome_tx);
+                    }
+                }
+                continue;
+            },
+            result = health_rx.recv() => {
+                if let Some(result) = result {
+                    apply_health_result(&mut app, result);
+                }
+                continue;
+            },
+            failure = kill_failure_rx.recv() => {
+                if let Some(failure) = failure {
+                    apply_kill_failure(&mut app, failure);
+                }
+                continue;
+            },
+            outcome = outcome_rx.recv() => {
+                match outcome {
+                    Some(Ok(message)) => {
+                        app.set_status(&message);
+                        request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
+                    }
+                    Some(Err(message)) => app.set_error(&message),
+                    None => {}
+                }
+                continue;
+            },
+            result = scan_rx.recv() => {
+                if let Some(result) = result {
+                    apply_scan_result(&mut app, result);
+                }
+                continue;
+            },
+            result = recheck_rx.recv() => {
+                if let Some(result) = result {
+                    apply_recheck_result(&mut app, result);
+                }
+                continue;
+            },
+            result = forward_rx.recv() => {
                 if let Some(result) = result {
-                    apply_refresh_result(&mut app, result);
+                    if apply_forward_result(&mut app, result) {
+                        request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
+                    }
+                }
+                continue;
+            },
+            raw = details_rx.recv() => {
+                if let Some(raw) = raw {
+                    app.apply_raw_details(raw);
+                }
+                continue;
+            },
+            stats = stats_rx.recv() => {
+                if let Some(stats) = stats {
+                    app.apply_container_stats(stats);
+                }
+                continue;
+            },
+            names = names_rx.recv() => {
+                if let Some(names) = names {
+                    app.host_names.apply_resolved(names);
+                }
+                continue;
+            },
+            list = established_rx.recv() => {
+                if let Some(list) = list {
+                    app.apply_established(list, Instant::now());
+                }
+                continue;
+            },
+            view = output_rx.recv() => {
+                if let Some(view) = view {
+                    app.apply_output(view);
                 }
                 continue;
             },
             _ = tick_interval.tick() => AppEvent::Tick,
         };
 
+        // Side effects of the action the event turned into, run below
+        let mut commands = Vec::new();
         match event {
             AppEvent::Key(key) => {
-                // Handle Forward popup specially (needs input handling)
-                if app.popup == Popup::Forward {
-                    let remote_mode = app.is_remote();
-                    let docker_mode = app.is_docker_target();
-                    if let Some(action) =
-                        handle_forward_key(key, &mut app.forward_input, remote_mode, docker_mode)
-                    {
-                        match action {
-                            Action::ClosePopup => {
-                                app.popup = Popup::None;
-                                app.reset_forward_input();
-                            }
-                            Action::SubmitForward => {
-                                let needs_refresh = handle_submit_forward(&mut app, mock_mode);
-                                if needs_refresh {
-                                    spawn_refresh(
-                                        &app,
-                                        &mut refresh_handle,
-                                        activation_handle.as_ref(),
-                                        &refresh_tx,
-                                    );
-                                }
-                            }
-                            _ => {}
+                let action = if app.popup == Popup::None {
+                    match app.input_mode {
+                        InputMode::Search => handle_search_key(key, &mut app.search_query),
+                        InputMode::Normal => {
+                            handle_key(key).or_else(|| handle_custom_key(key, &app.custom_actions))
                         }
+                        InputMode::PresetLeader => Some(handle_leader_key(key)),
                     }
+                } else {
+                    handle_open_popup_key(key, &mut app)
+                };
+                let Some(action) = action else {
                     continue;
+                };
+                commands = app.update(action);
+            }
+            AppEvent::Mouse(mouse) => {
+                // Only handle mouse if enabled and in normal mode without popup
+                if mouse_enabled && app.popup == Popup::None && app.input_mode == InputMode::Normal
+                {
+                    if let Some(action) = handle_mouse(mouse, &layout, &mut clicks) {
+                        commands = app.update(action);
+                    }
+                }
+            }
+            AppEvent::Resize => {
+                // Rows may have moved; don't hit-test clicks against the old layout
+                terminal.autoresize()?;
+                layout = ui::ScreenLayout::default();
+            }
+            AppEvent::Tick => {
+                app.tick();
+                if (!mock_mode || app.mock_churn) && app.should_refresh() {
+                    request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Auto);
                 }
+            }
+        }
 
-                // Handle Presets popup
-                if app.popup == Popup::Presets {
-                    if let Some(action) = handle_preset_key(key) {
-                        match action {
-                            Action::ClosePopup => {
-                                app.popup = Popup::None;
-                            }
-                            Action::Up => app.preset_previous(),
-                            Action::Down => app.preset_next(),
-                            Action::LaunchPreset => {
-                                if mock_mode {
-                                    app.set_status("[mock] Forward created");
-                                } else if let Some(preset) = app.selected_preset() {
-                                    let spec = format!(
-                                        "{}:{}:{}",
-                                        preset.local_port, preset.remote_host, preset.remote_port
-                                    );
-                                    let host = preset.ssh_host.clone();
-                                    match port::ssh::create_forward(&spec, &host, false) {
-                                        Ok(pid) => {
-                                            app.set_status(&format!(
-                                                "Forward created (PID: {pid})"
-                                            ));
-                                            spawn_refresh(
-                                                &app,
-                                                &mut refresh_handle,
-                                                activation_handle.as_ref(),
-                                                &refresh_tx,
-                                            );
-                                        }
-                                        Err(e) => {
-                                            app.set_status(&format!("Forward failed: {e}"));
-                                        }
-                                    }
-                                }
-                                app.popup = Popup::None;
-                            }
-                            _ => {}
-                        }
+        for command in commands {
+            match command {
+                Command::Refresh => {
+                    request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
+                }
+                Command::Recheck => spawn_recheck(&mut app, &recheck_tx),
+                Command::Kill => {
+                    handle_kill_action(&mut app, mock_mode, &worker, &kill_failure_tx);
+                }
+                Command::LoadDetails => load_details(&mut app, mock_mode, &details_tx),
+                Command::LoadEstablished => {
+                    load_established(&mut app, mock_mode, &established_tx);
+                }
+                Command::LoadOutput => load_output(&mut app, mock_mode, &output_tx),
+                Command::QuickForward => {
+                    if handle_quick_forward(&mut app, mock_mode, &forward_tx) {
+                        request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
                     }
-                    continue;
                 }
-
-                // Handle Connections popup
-                if app.popup == Popup::Connections {
-                    if app.connection_popup_mode == ConnectionPopupMode::AddNew {
-                        if let Some(action) =
-                            handle_connection_input_key(key, &mut app.connection_input)
-                        {
-                            match action {
-                                Action::ClosePopup => {
-                                    // Go back to List mode
-                                    app.connection_popup_mode = ConnectionPopupMode::List;
-                                    app.reset_connection_input();
-                                }
-                                Action::SubmitConnection => {
-                                    if let Some(conn) = app.connection_input.to_connection() {
-                                        let name = conn.name.clone();
-                                        stored_connections.add(conn);
-                                        if let Err(e) = stored_connections.save() {
-                                            app.set_status(&format!("Save failed: {e}"));
-                                        } else {
-                                            app.connections = stored_connections.all_with_local();
-                                            app.set_status(&format!("Added connection: {name}"));
-                                        }
-                                        app.connection_popup_mode = ConnectionPopupMode::List;
-                                        app.reset_connection_input();
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                    } else if let Some(action) = handle_connection_key(key) {
-                        match action {
-                            Action::ClosePopup => {
-                                app.popup = Popup::None;
-                            }
-                            Action::Up => app.connection_previous(),
-                            Action::Down => app.connection_next(),
-                            Action::ActivateConnection => {
-                                app.active_connection = app.connection_selected;
-                                activate_connection_ui(&mut app);
-                                if !mock_mode {
-                                    spawn_activation(
-                                        &app,
-                                        &mut activation_handle,
-                                        &mut refresh_handle,
-                                        &activation_tx,
-                                    );
-                                }
-                                app.popup = Popup::None;
-                            }
-                            Action::AddConnection => {
-                                app.connection_popup_mode = ConnectionPopupMode::AddNew;
-                                app.reset_connection_input();
-                            }
-                            Action::DeleteConnection => {
-                                if app.connection_selected == 0 {
-                                    app.set_status("Cannot delete Local connection");
-                                } else {
-                                    let user_index = app.connection_selected - 1;
-                                    let name = stored_connections
-                                        .connection
-                                        .get(user_index)
-                                        .map_or("Unknown".to_string(), |c| c.name.clone());
-                                    if stored_connections.remove(user_index) {
-                                        if let Err(e) = stored_connections.save() {
-                                            app.set_status(&format!("Save failed: {e}"));
-                                        } else {
-                                            app.connections = stored_connections.all_with_local();
-                                            // Adjust active_connection if needed
-                                            if app.active_connection >= app.connections.len() {
-                                                app.active_connection =
-                                                    app.connections.len().saturating_sub(1);
-                                                app.apply_connection();
-                                            } else if app.active_connection
-                                                == app.connection_selected
-                                            {
-                                                // Deleted the active connection, switch to Local
-                                                app.active_connection = 0;
-                                                app.apply_connection();
-                                            }
-                                            // Adjust selection cursor
-                                            if app.connection_selected >= app.connections.len() {
-                                                app.connection_selected =
-                                                    app.connections.len().saturating_sub(1);
-                                            }
-                                            app.set_status(&format!("Deleted connection: {name}"));
-                                        }
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
+                Command::SwitchConnection(direction) => {
+                    if handle_connection_switch(&mut app, direction, mock_mode) {
+                        spawn_activation(&app, &mut activation_handle, &worker, &activation_tx);
                     }
-                    continue;
                 }
-
-                // Handle other popups
-                if app.popup != Popup::None {
-                    if let Some(Action::ClosePopup) = handle_popup_key(key) {
-                        app.popup = Popup::None;
+                Command::Authenticate(host) => pending_auth = Some(host),
+                Command::SavePins => {
+                    if let Err(e) = app.pins.save() {
+                        app.set_error(&format!("Pin save failed: {e}"));
                     }
-                    continue;
                 }
-
-                let action = match app.input_mode {
-                    InputMode::Search => handle_search_key(key, &mut app.search_query),
-                    InputMode::Normal => handle_key(key),
-                };
-
-                if let Some(action) = action {
-                    match action {
-                        Action::Quit => {
-                            app.should_quit = true;
-                        }
-                        Action::Up => app.previous(),
-                        Action::Down => app.next(),
-                        Action::First => app.first(),
-                        Action::Last => app.last(),
-                        Action::EnterSearch => {
-                            app.input_mode = InputMode::Search;
-                        }
-                        Action::ExitSearch => {
-                            app.input_mode = InputMode::Normal;
-                        }
-                        Action::UpdateSearch => {
-                            app.apply_filter();
-                        }
-                        Action::FilterAll => app.set_filter(Filter::All),
-                        Action::FilterLocal => app.set_filter(Filter::Local),
-                        Action::FilterSsh => app.set_filter(Filter::Ssh),
-                        Action::FilterDocker => app.set_filter(Filter::Docker),
-                        Action::Refresh => {
-                            if !mock_mode {
-                                app.loading = true;
-                                spawn_refresh(
-                                    &app,
-                                    &mut refresh_handle,
-                                    activation_handle.as_ref(),
-                                    &refresh_tx,
-                                );
-                                app.set_status("Refreshing...");
-                            }
-                        }
-                        Action::ToggleAutoRefresh => {
-                            if !mock_mode {
-                                app.auto_refresh = !app.auto_refresh;
-                                if app.auto_refresh {
-                                    app.set_status("Auto-refresh ON");
-                                } else {
-                                    app.set_status("Auto-refresh OFF");
-                                }
-                            }
-                        }
-                        Action::Kill => {
-                            handle_kill_action(&mut app, mock_mode, &refresh_tx);
-                        }
-                        Action::Select => {
-                            app.popup = Popup::Details;
-                        }
-                        Action::ShowHelp => {
-                            app.popup = Popup::Help;
-                        }
-                        Action::StartForward => {
-                            app.forward_input = match (
-                                app.selected_entry(),
-                                app.remote_host.as_deref(),
-                            ) {
-                                (Some(entry), Some(host)) if app.is_docker_target() => {
-                                    let mut input = ForwardInput::for_remote_entry(entry, host);
-                                    if let Some((target, rport)) = resolve_docker_forward(
-                                        entry.local_port,
-                                        &app.docker_port_mappings,
-                                        app.container_ip.as_deref(),
-                                    ) {
-                                        input.remote_host = target;
-                                        input.remote_port = rport.to_string();
-                                    }
-                                    input
-                                }
-                                (Some(entry), Some(host)) => {
-                                    ForwardInput::for_remote_entry(entry, host)
-                                }
-                                (Some(entry), None) => ForwardInput::from_entry(entry),
-                                _ => ForwardInput::new(),
-                            };
-                            app.popup = Popup::Forward;
-                        }
-                        Action::ShowPresets => {
-                            app.preset_selected = 0;
-                            app.popup = Popup::Presets;
-                        }
-                        Action::ClosePopup => {
-                            app.popup = Popup::None;
-                        }
-                        Action::QuickForward => {
-                            let needs_refresh = handle_quick_forward(&mut app, mock_mode);
-                            if needs_refresh {
-                                spawn_refresh(
-                                    &app,
-                                    &mut refresh_handle,
-                                    activation_handle.as_ref(),
-                                    &refresh_tx,
-                                );
-                            }
-                        }
-                        Action::PrevConnection => {
-                            if handle_connection_switch(&mut app, -1, mock_mode) {
-                                spawn_activation(
-                                    &app,
-                                    &mut activation_handle,
-                                    &mut refresh_handle,
-                                    &activation_tx,
-                                );
-                            }
-                        }
-                        Action::NextConnection => {
-                            if handle_connection_switch(&mut app, 1, mock_mode) {
-                                spawn_activation(
-                                    &app,
-                                    &mut activation_handle,
-                                    &mut refresh_handle,
-                                    &activation_tx,
-                                );
-                            }
-                        }
-                        Action::ShowConnections => {
-                            app.connection_selected = app.active_connection;
-                            app.connection_popup_mode = ConnectionPopupMode::List;
-                            app.popup = Popup::Connections;
-                        }
-                        Action::ClearSearch => {
-                            app.search_query.clear();
-                            app.apply_filter();
-                        }
-                        Action::SubmitForward
-                        | Action::LaunchPreset
-                        | Action::SelectRow(_)
-                        | Action::ActivateConnection
-                        | Action::AddConnection
-                        | Action::DeleteConnection
-                        | Action::SubmitConnection => {
-                            // Handled elsewhere (popup handlers or mouse handler)
-                        }
+                Command::CopyAddress => handle_copy_address(&mut app),
+                Command::OpenBrowser => handle_open_browser(&mut app, mock_mode),
+                Command::ContainerLogs => {
+                    pending_action = container_logs_action(&mut app, mock_mode);
+                }
+                Command::RestartContainer => {
+                    handle_restart_container(&mut app, mock_mode, &outcome_tx);
+                }
+                Command::RestartProcess => {
+                    handle_restart_process(&mut app, mock_mode, &outcome_tx);
+                }
+                Command::StartService => handle_start_service(&mut app, mock_mode, &outcome_tx),
+                Command::CloseIdleTunnels => {
+                    handle_close_idle_tunnels(&mut app, mock_mode, false, &outcome_tx);
+                }
+                Command::TmuxShell => handle_tmux_shell(&mut app, mock_mode),
+                Command::TmuxForward => handle_tmux_forward(&mut app, mock_mode),
+                Command::RunCustom(index) => {
+                    pending_action = start_custom_action(&mut app, index, mock_mode);
+                }
+                Command::Undo(action) => {
+                    if handle_undo(&mut app, action, &outcome_tx, &forward_tx) {
+                        request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
                     }
                 }
-            }
-            AppEvent::Mouse(mouse) => {
-                // Only handle mouse if enabled and in normal mode without popup
-                if mouse_enabled && app.popup == Popup::None && app.input_mode == InputMode::Normal
-                {
-                    // Calculate table area: header(3) + filter(3) = 6 rows before table
-                    let table_top = 6_u16;
-                    let term_height = terminal.size()?.height;
-                    let table_height = term_height.saturating_sub(8); // minus header, filter, footer
-
-                    if let Some(action) = handle_mouse(mouse, table_top, table_height) {
-                        match action {
-                            Action::Up => app.previous(),
-                            Action::Down => app.next(),
-                            Action::SelectRow(row) => {
-                                if row < app.filtered_entries.len() {
-                                    app.selected = row;
-                                }
-                            }
-                            _ => {}
-                        }
+                Command::LoadHistory => match history::load() {
+                    Ok(records) => app.history = records,
+                    Err(e) => app.set_error(&format!("History: {e:#}")),
+                },
+                Command::Activate => {
+                    spawn_activation(&app, &mut activation_handle, &worker, &activation_tx);
+                }
+                Command::CreateForward => {
+                    if handle_submit_forward(&mut app, mock_mode, &forward_tx) {
+                        request_refresh(&app, &worker, activation_handle.as_ref(), Trigger::Manual);
                     }
                 }
-            }
-            AppEvent::Tick => {
-                app.tick();
-                if !mock_mode && app.should_refresh() {
-                    spawn_refresh(
-                        &app,
-                        &mut refresh_handle,
-                        activation_handle.as_ref(),
-                        &refresh_tx,
-                    );
+                Command::LaunchPreset(index) => {
+                    launch_preset(&mut app, index, mock_mode, &forward_tx);
+                }
+                Command::LaunchGroup(group) => {
+                    launch_group(&mut app, &group, mock_mode, &forward_tx);
+                }
+                Command::SavePreset => handle_submit_preset(&mut app, &mut stored_presets),
+                Command::DeletePreset => handle_delete_preset(&mut app, &mut stored_presets),
+                Command::SaveSnapshot => handle_submit_snapshot(&mut app, mock_mode),
+                Command::RestoreSnapshot => restore_snapshot(&mut app, mock_mode, &forward_tx),
+                Command::DeleteSnapshot => handle_delete_snapshot(&mut app, mock_mode),
+                Command::EditConnection => {
+                    let generated = stored_connections.is_generated(app.connection_selected);
+                    app.edit_connection(generated);
+                }
+                Command::SaveConnection => {
+                    if handle_submit_connection(
+                        &mut app,
+                        &mut stored_connections,
+                        mock_mode,
+                        &health_tx,
+                    ) {
+                        spawn_activation(&app, &mut activation_handle, &worker, &activation_tx);
+                    }
+                }
+                Command::MoveConnection(up) => {
+                    if handle_move_connection(&mut app, &mut stored_connections, up) && !mock_mode {
+                        save_forwards(&mut app);
+                    }
+                }
+                Command::DeleteConnection => {
+                    handle_delete_connection(&mut app, &mut stored_connections);
                 }
+                Command::TestConnection(host) => {
+                    spawn_health_probe(&mut app, &host, true, &health_tx);
+                    app.set_status(&format!("Testing {host}..."));
+                }
+                Command::SudoKill(request) => pending_sudo = Some(request),
+                Command::Export(format) => handle_export(&mut app, format),
+                Command::Scan => spawn_scan(&mut app, &scan_tx),
             }
         }
 
@@ -1297,6 +3481,10 @@ pub(crate) async fn run_tui_with_entries(
         }
     }
 
+    for host in &authenticated_hosts {
+        port::ssh::close_master(host);
+    }
+
     // Restore terminal
     disable_raw_mode()?;
     if mouse_enabled {
@@ -1318,6 +3506,55 @@ mod tests {
         assert!(cli.command.is_none());
         assert!(cli.remote.is_none());
         assert!(cli.docker.is_none());
+        assert!(!cli.takeover);
+    }
+
+    #[test]
+    fn test_preset_changes_roll_back_when_save_fails() {
+        let mut stored: preset::Presets = toml::from_str(
+            "[[preset]]\nname = \"db\"\nlocal_port = 5432\n\
+             remote_host = \"localhost\"\nremote_port = 5432\nssh_host = \"bastion\"\n",
+        )
+        .unwrap();
+        // A broken presets.toml makes save() refuse without touching the disk
+        stored.load_failed = true;
+        let mut app = App::new();
+        app.presets.clone_from(&stored.preset);
+        app.preset_popup_mode = PresetPopupMode::Form;
+        app.preset_input = app::PresetInput {
+            name: "api".to_string(),
+            local_port: "8080".to_string(),
+            remote_host: "localhost".to_string(),
+            remote_port: "80".to_string(),
+            ssh_host: "bastion".to_string(),
+            ..app::PresetInput::new()
+        };
+
+        handle_submit_preset(&mut app, &mut stored);
+        assert_eq!(stored.preset.len(), 1);
+        assert_eq!(app.presets.len(), 1);
+        assert_eq!(app.preset_popup_mode, PresetPopupMode::Form);
+        assert_eq!(app.preset_input.name, "api");
+
+        app.preset_popup_mode = PresetPopupMode::List;
+        app.preset_selected = 0;
+        handle_delete_preset(&mut app, &mut stored);
+        assert_eq!(stored.preset.len(), 1);
+        assert_eq!(app.presets.len(), 1);
+    }
+
+    #[test]
+    fn test_cli_parse_takeover() {
+        let cli = Cli::try_parse_from(["quay", "--takeover"]).unwrap();
+        assert!(cli.takeover);
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_strict_config() {
+        let cli = Cli::try_parse_from(["quay", "--strict-config", "list"]).unwrap();
+        assert!(cli.strict_config);
+        assert!(matches!(cli.command, Some(Commands::List { .. })));
     }
 
     #[test]
@@ -1343,21 +3580,249 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_cli_parse_list_format() {
+        let cli = Cli::try_parse_from(["quay", "list", "--format", "jsonl", "--no-color"]).unwrap();
+        assert!(cli.no_color);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::List {
+                format: Some(output::Format::Jsonl),
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["quay", "list", "--json", "--format", "csv"]).is_err());
+    }
+
+    #[test]
+    fn test_list_options() {
+        use std::path::Path;
+        let md = Some(Path::new("ports.md"));
+        let options = list_options(None, false, md, 1, false);
+        assert_eq!(options.format, output::Format::Markdown);
+        assert!(!options.color);
+        let options = list_options(Some(output::Format::Csv), false, md, 1, false);
+        assert_eq!(options.format, output::Format::Csv);
+        let options = list_options(Some(output::Format::Csv), true, None, 1, false);
+        assert_eq!(options.format, output::Format::Json);
+        let options = list_options(None, false, Some(Path::new("ports")), 1, false);
+        assert_eq!(options.format, output::Format::Table);
+    }
+
+    #[test]
+    fn test_cli_parse_pick() {
+        let cli = Cli::try_parse_from(["quay", "pick", "postgres", "--kill"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Pick {
+                kill: true,
+                forward: false,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["quay", "pick", "--kill", "--print-port"]).is_err());
+    }
+
     #[test]
     fn test_cli_parse_forward() {
         let cli =
             Cli::try_parse_from(["quay", "forward", "8080:localhost:80", "remote-host"]).unwrap();
-        assert!(matches!(cli.command, Some(Commands::Forward { .. })));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Forward { command: None, spec: Some(ref spec), host: Some(ref host), .. })
+                if spec == "8080:localhost:80" && host == "remote-host"
+        ));
+        assert!(Cli::try_parse_from(["quay", "forward", "8080:localhost:80"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "forward"]).is_err());
+        let cli = Cli::try_parse_from([
+            "quay",
+            "forward",
+            "--bind-all",
+            "8080:localhost:80",
+            "remote-host",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Forward { bind_all: true, .. })
+        ));
+        let cli = Cli::try_parse_from([
+            "quay",
+            "forward",
+            "--ssh-options",
+            "-C -o ServerAliveInterval=30",
+            "8080:localhost:80",
+            "remote-host",
+        ])
+        .unwrap();
+        let Some(Commands::Forward { ssh_options, .. }) = cli.command else {
+            panic!("expected forward");
+        };
+        assert_eq!(ssh_options.as_deref(), Some("-C -o ServerAliveInterval=30"));
+    }
+
+    #[test]
+    fn test_cli_parse_forward_subcommands() {
+        let cli = Cli::try_parse_from(["quay", "forward", "list", "--format", "json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Forward {
+                command: Some(forward::ForwardCommands::List {
+                    format: output::Format::Json
+                }),
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(["quay", "forward", "kill", "8080"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Forward {
+                command: Some(forward::ForwardCommands::Kill {
+                    target: Some(8080),
+                    all: false
+                }),
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["quay", "forward", "kill", "--all"]).is_ok());
+        assert!(Cli::try_parse_from(["quay", "forward", "kill"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "forward", "kill", "8080", "--all"]).is_err());
     }
 
     #[test]
     fn test_cli_parse_kill() {
         let cli = Cli::try_parse_from(["quay", "kill", "3000"]).unwrap();
+        let Some(Commands::Kill(args)) = cli.command else {
+            panic!("expected kill");
+        };
+        assert_eq!(args.ports, vec![3000]);
+        assert_eq!(args.pid, None);
+        assert_eq!(args.signal, port::Signal::Term);
+        assert!(!args.dry_run);
+
+        let cli = Cli::try_parse_from([
+            "quay",
+            "kill",
+            "3000",
+            "3001",
+            "--name",
+            "node",
+            "-s",
+            "KILL",
+            "--dry-run",
+        ])
+        .unwrap();
+        let Some(Commands::Kill(args)) = cli.command else {
+            panic!("expected kill");
+        };
+        assert_eq!(args.ports, vec![3000, 3001]);
+        assert_eq!(args.name.as_deref(), Some("node"));
+        assert_eq!(args.signal, port::Signal::Kill);
+        assert!(args.dry_run);
+
+        assert!(Cli::try_parse_from(["quay", "kill"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "kill", "--dry-run"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "kill", "3000", "--signal", "BOGUS"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_up_down() {
+        let cli = Cli::try_parse_from(["quay", "up", "staging"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Up { ref group }) if group == "staging"));
+        let cli = Cli::try_parse_from(["quay", "down", "staging"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Down { ref group }) if group == "staging"));
+        assert!(Cli::try_parse_from(["quay", "up"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_snapshot() {
+        let cli = Cli::try_parse_from(["quay", "snapshot", "restore", "work"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Snapshot {
+                command: snapshot::SnapshotCommands::Restore { ref name }
+            }) if name == "work"
+        ));
+        assert!(Cli::try_parse_from(["quay", "snapshot", "save"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_scan() {
+        let cli =
+            Cli::try_parse_from(["quay", "scan", "8000-9000", "--host", "10.0.0.0/24"]).unwrap();
+        let Some(Commands::Scan(args)) = cli.command else {
+            panic!("expected scan");
+        };
+        assert_eq!(args.ports, "8000-9000");
+        assert_eq!(args.host.as_deref(), Some("10.0.0.0/24"));
+        assert_eq!(args.rate, 1000);
+        assert!(Cli::try_parse_from(["quay", "scan"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_wait() {
+        let cli =
+            Cli::try_parse_from(["quay", "wait", "-p", "5432,6379", "--port", "3000"]).unwrap();
+        let Some(Commands::Wait(args)) = cli.command else {
+            panic!("expected wait");
+        };
+        assert_eq!(args.ports, vec![5432, 6379, 3000]);
+        assert!(!args.closed && !args.any);
+        assert_eq!(args.timeout, 60);
+
+        assert!(Cli::try_parse_from(["quay", "wait"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "wait", "-p", "1", "--open", "--closed"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_preset() {
+        let cli = Cli::try_parse_from(["quay", "preset", "list"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Preset {
+                command: preset::PresetCommands::List
+            })
+        ));
+        let cli = Cli::try_parse_from(["quay", "preset", "up", "api", "db"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Preset {
+                command: preset::PresetCommands::Up { ref names }
+            }) if names == &["api", "db"]
+        ));
+        assert!(Cli::try_parse_from(["quay", "preset", "down"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_config_show() {
+        let cli = Cli::try_parse_from(["quay", "config", "show"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Config {
+                command: config::ConfigCommands::Show
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_config_edit() {
+        let cli = Cli::try_parse_from(["quay", "config", "edit"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Config {
+                command: config::ConfigCommands::Edit {
+                    file: config::ConfigFile::Config
+                }
+            })
+        ));
+
+        let cli = Cli::try_parse_from(["quay", "config", "edit", "presets"]).unwrap();
         assert!(matches!(
             cli.command,
-            Some(Commands::Kill {
-                port: 3000,
-                pid: None
+            Some(Commands::Config {
+                command: config::ConfigCommands::Edit {
+                    file: config::ConfigFile::Presets
+                }
             })
         ));
     }
@@ -1374,6 +3839,36 @@ mod tests {
         assert!(matches!(cli.command, Some(Commands::Dev { .. })));
     }
 
+    #[test]
+    fn test_cli_parse_dev_listen_flaky() {
+        let cli = Cli::try_parse_from([
+            "quay",
+            "dev",
+            "listen",
+            "3000",
+            "--udp",
+            "--banner",
+            "hello",
+            "--latency",
+            "200",
+            "--flaky",
+            "30",
+        ])
+        .unwrap();
+        let Some(Commands::Dev {
+            command: dev::DevCommands::Listen { ports, args },
+        }) = cli.command
+        else {
+            panic!("expected dev listen");
+        };
+        assert_eq!(ports, vec![3000]);
+        assert!(args.udp);
+        assert_eq!(args.banner.as_deref(), Some("hello"));
+        assert_eq!(args.latency, 200);
+        assert_eq!(args.flaky, Some(30));
+        assert!(Cli::try_parse_from(["quay", "dev", "listen", "3000", "--flaky", "101"]).is_err());
+    }
+
     #[test]
     fn test_cli_parse_dev_scenario() {
         let cli = Cli::try_parse_from(["quay", "dev", "scenario", "web"]).unwrap();
@@ -1398,6 +3893,76 @@ mod tests {
         assert!(matches!(cli.command, Some(Commands::Dev { .. })));
     }
 
+    #[test]
+    fn test_cli_parse_dev_mock_generator() {
+        let cli = Cli::try_parse_from([
+            "quay",
+            "dev",
+            "mock",
+            "--entries",
+            "200",
+            "--sources",
+            "docker,ssh",
+            "--churn",
+        ])
+        .unwrap();
+        let Some(Commands::Dev {
+            command:
+                dev::DevCommands::Mock {
+                    entries,
+                    sources,
+                    churn,
+                    seed,
+                },
+        }) = cli.command
+        else {
+            panic!("expected dev mock");
+        };
+        assert_eq!(entries, Some(200));
+        assert_eq!(
+            sources,
+            vec![port::PortSource::Docker, port::PortSource::Ssh]
+        );
+        assert!(churn);
+        assert_eq!(seed, None);
+        assert!(Cli::try_parse_from(["quay", "dev", "mock", "--sources", "scan"]).is_err());
+        assert!(Cli::try_parse_from(["quay", "dev", "mock", "--entries", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_dev_replay() {
+        let cli = Cli::try_parse_from(["quay", "dev", "replay", "demo.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Dev {
+                command: dev::DevCommands::Replay { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_dev_record() {
+        let cli = Cli::try_parse_from(["quay", "dev", "record", "fixtures/devbox", "-r", "devbox"])
+            .unwrap();
+        let Some(Commands::Dev {
+            command: dev::DevCommands::Record { dir, remote },
+        }) = cli.command
+        else {
+            panic!("expected dev record");
+        };
+        assert_eq!(dir, std::path::PathBuf::from("fixtures/devbox"));
+        assert_eq!(remote.as_deref(), Some("devbox"));
+
+        let cli =
+            Cli::try_parse_from(["quay", "dev", "replay-fixture", "fixtures/devbox"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Dev {
+                command: dev::DevCommands::ReplayFixture { .. }
+            })
+        ));
+    }
+
     #[test]
     fn test_cli_parse_docker() {
         let cli = Cli::try_parse_from(["quay", "--docker", "my-container"]).unwrap();
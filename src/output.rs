@@ -0,0 +1,359 @@
+//! Formatters for `quay list` and shared terminal color handling.
+
+use crate::port::{PortEntry, PortSource};
+use crate::schema::PortRecord;
+use clap::ValueEnum;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// A single pretty-printed JSON array
+    Json,
+    /// One JSON object per line, for jq or fzf
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+    /// A Markdown table, for tickets and chat
+    Markdown,
+}
+
+impl Format {
+    /// The format a file name suggests, e.g. `ports.md` for Markdown.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "txt" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    /// File extension for exports in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Table => "txt",
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+pub const GREEN: &str = "\x1b[32m";
+pub const GRAY: &str = "\x1b[90m";
+const CYAN: &str = "\x1b[36m";
+const MAGENTA: &str = "\x1b[35m";
+const BLUE: &str = "\x1b[34m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether table output should use ANSI colors.
+///
+/// Disabled by `--no-color`, a non-empty `NO_COLOR` (<https://no-color.org>),
+/// or when stdout is not a terminal.
+pub fn color_enabled(no_color_flag: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color_flag && !no_color_env && io::stdout().is_terminal()
+}
+
+/// How entries are rendered.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    pub format: Format,
+    /// Schema version for JSON formats (see [`crate::schema`]).
+    pub version: u32,
+    pub color: bool,
+}
+
+pub fn write_entries(
+    out: &mut impl Write,
+    entries: &[PortEntry],
+    options: OutputOptions,
+) -> io::Result<()> {
+    let records = || entries.iter().map(|e| PortRecord::new(e, options.version));
+    match options.format {
+        Format::Table => write_table(out, entries, options.color),
+        Format::Json => {
+            let records: Vec<_> = records().collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&records)?)
+        }
+        Format::Jsonl => {
+            for record in records() {
+                writeln!(out, "{}", serde_json::to_string(&record)?)?;
+            }
+            Ok(())
+        }
+        Format::Csv => write_csv(out, entries),
+        Format::Markdown => write_markdown(out, entries),
+    }
+}
+
+/// Write `entries` to a new file at `path`.
+pub fn write_file(path: &Path, entries: &[PortEntry], options: OutputOptions) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_entries(&mut out, entries, options)?;
+    out.flush()
+}
+
+/// Wrap `text` in an ANSI color when `color` is set.
+pub fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{code}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Aligned columns; entries from several hosts (`quay list -r a -r b`) get
+/// a HOST column first.
+fn write_table(out: &mut impl Write, entries: &[PortEntry], color: bool) -> io::Result<()> {
+    let host_width = entries
+        .iter()
+        .filter_map(|e| e.connection.as_deref())
+        .map(|host| host.chars().count().max("HOST".len()) + 2)
+        .max();
+    let host_cell = |host: &str| match host_width {
+        Some(width) => format!("{host:<width$}"),
+        None => String::new(),
+    };
+    writeln!(
+        out,
+        "{}{:<8} {:<6} {:<8} {:<20} PROCESS",
+        host_cell("HOST"),
+        "TYPE",
+        "OPEN",
+        "LOCAL",
+        "REMOTE"
+    )?;
+    writeln!(out, "{}", "-".repeat(66 + host_width.unwrap_or(0)))?;
+    for entry in entries {
+        // Pad before painting so escape codes don't disturb the alignment
+        let source = format!("{:<8}", entry.source.to_string());
+        let source = match entry.source {
+            PortSource::Local => paint(&source, CYAN, color),
+            PortSource::Ssh => paint(&source, MAGENTA, color),
+            PortSource::Docker => paint(&source, BLUE, color),
+            PortSource::Scan => paint(&source, YELLOW, color),
+        };
+        let open = if entry.is_open {
+            paint("●", GREEN, color)
+        } else {
+            paint("○", GRAY, color)
+        };
+        let local_display = if let Some(fwd) = entry.forwarded_port {
+            format!("{}→:{}", entry.local_display(), fwd)
+        } else {
+            entry.local_display()
+        };
+        writeln!(
+            out,
+            "{}{source} {open}      {:<14} {:<20} {}",
+            host_cell(entry.connection.as_deref().unwrap_or_default()),
+            local_display,
+            entry.remote_display(),
+            entry.process_display()
+        )?;
+    }
+    Ok(())
+}
+
+const CSV_HEADER: &[&str] = &[
+    "source",
+    "local_port",
+    "is_open",
+    "remote_host",
+    "remote_port",
+    "process_name",
+    "pid",
+    "container_id",
+    "container_name",
+    "ssh_host",
+    "is_loopback",
+    "forwarded_port",
+    "connection",
+    "user",
+    "bind",
+];
+
+/// Quote a field when it contains a separator, quote, or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_csv(out: &mut impl Write, entries: &[PortEntry]) -> io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER.join(","))?;
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    for e in entries {
+        let fields = [
+            format!("{:?}", e.source),
+            e.local_port.to_string(),
+            e.is_open.to_string(),
+            opt(e.remote_host.clone()),
+            opt(e.remote_port.map(|p| p.to_string())),
+            e.process_name.clone(),
+            opt(e.pid.map(|p| p.to_string())),
+            opt(e.container_id.clone()),
+            opt(e.container_name.clone()),
+            opt(e.ssh_host.clone()),
+            e.is_loopback.to_string(),
+            opt(e.forwarded_port.map(|p| p.to_string())),
+            opt(e.connection.clone()),
+            opt(e.user.clone()),
+            opt(e.bind.clone()),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Escape a Markdown table cell: pipes would end the cell, line breaks the row.
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn write_markdown(out: &mut impl Write, entries: &[PortEntry]) -> io::Result<()> {
+    writeln!(
+        out,
+        "| Type | Open | Local | Remote | Process | User | Connection |"
+    )?;
+    writeln!(out, "| --- | --- | --- | --- | --- | --- | --- |")?;
+    for e in entries {
+        let local = match e.forwarded_port {
+            Some(fwd) => format!("{} → :{fwd}", e.local_display()),
+            None => e.local_display(),
+        };
+        let cells = [
+            e.source.to_string(),
+            if e.is_open { "yes" } else { "no" }.to_string(),
+            local,
+            e.remote_display(),
+            e.process_display(),
+            e.user.clone().unwrap_or_default(),
+            e.connection.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = cells.iter().map(|c| markdown_cell(c)).collect();
+        writeln!(out, "| {} |", row.join(" | "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SCHEMA_VERSION;
+    use crate::testing;
+
+    fn entry(port: u16, process: &str) -> PortEntry {
+        testing::entry(PortSource::Local, port, process)
+    }
+
+    fn render(format: Format, color: bool) -> String {
+        let entries = [entry(3000, "node"), entry(5432, "postgres, \"main\"")];
+        let mut out = Vec::new();
+        let options = OutputOptions {
+            format,
+            version: SCHEMA_VERSION,
+            color,
+        };
+        write_entries(&mut out, &entries, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_jsonl_one_object_per_line() {
+        let output = render(Format::Jsonl, false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["local_port"], 3000);
+        assert_eq!(first["process_name"], "node");
+        assert_eq!(first["schema_version"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let output = render(Format::Csv, false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert_eq!(lines[1], "Local,3000,true,,,node,42,,,,false,,,,");
+        assert_eq!(
+            lines[2],
+            "Local,5432,true,,,\"postgres, \"\"main\"\"\",42,,,,false,,,,"
+        );
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let output = render(Format::Markdown, false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| Type | Open | Local |"));
+        assert!(
+            lines[2].starts_with("| LOCAL | yes | :3000 | "),
+            "{}",
+            lines[2]
+        );
+
+        let mut piped = entry(8080, "sh -c a|b");
+        piped.forwarded_port = Some(18080);
+        let mut out = Vec::new();
+        write_markdown(&mut out, &[piped]).unwrap();
+        let row = String::from_utf8(out).unwrap();
+        assert!(row.contains(":8080 → :18080"));
+        assert!(row.contains("a\\|b"));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            Format::from_path(Path::new("ports.MD")),
+            Some(Format::Markdown)
+        );
+        assert_eq!(
+            Format::from_path(Path::new("/tmp/out.jsonl")),
+            Some(Format::Jsonl)
+        );
+        assert_eq!(Format::from_path(Path::new("ports")), None);
+        assert_eq!(Format::from_path(Path::new("ports.xlsx")), None);
+        assert_eq!(Format::Csv.extension(), "csv");
+    }
+
+    #[test]
+    fn test_table_color() {
+        let plain = render(Format::Table, false);
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains("LOCAL    ●      :3000"));
+
+        let colored = render(Format::Table, true);
+        assert!(colored.contains(&format!("{GREEN}●{RESET}")));
+    }
+
+    #[test]
+    fn test_table_host_column() {
+        let mut web = entry(3000, "node");
+        web.connection = Some("web1".to_string());
+        let mut db = entry(5432, "postgres");
+        db.connection = Some("db-primary".to_string());
+        let mut out = Vec::new();
+        write_table(&mut out, &[web, db], false).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("HOST        TYPE "), "{}", lines[0]);
+        assert!(lines[2].starts_with("web1        LOCAL "), "{}", lines[2]);
+        assert!(lines[3].starts_with("db-primary  LOCAL "), "{}", lines[3]);
+    }
+}
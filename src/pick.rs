@@ -0,0 +1,124 @@
+//! Line format and matching for `quay pick`.
+//!
+//! `quay pick` prints one entry per line for tools like fzf; a selected line
+//! piped back into `quay pick --kill|--forward|--print-port` is mapped to its
+//! entry again. Without a picker, a query argument is fuzzy-matched instead.
+
+use crate::port::{PortEntry, PortSource};
+
+/// Tab-separated line for one entry: port, source, process, remote.
+///
+/// The port comes first so `cut -f1` and fzf's `--with-nth` work on it.
+pub fn line(entry: &PortEntry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        entry.local_port,
+        entry.source,
+        entry.process_display(),
+        entry.remote_display()
+    )
+}
+
+/// Port and (if present) source of a line produced by [`line`].
+///
+/// Accepts a bare port too, so `echo 3000 | quay pick --kill` works.
+pub fn parse_selection(selection: &str) -> Option<(u16, Option<PortSource>)> {
+    let mut fields = selection.split('\t');
+    let port = fields.next()?.trim().trim_start_matches(':').parse().ok()?;
+    let source = match fields.next().map(str::trim) {
+        Some("LOCAL") => Some(PortSource::Local),
+        Some("SSH") => Some(PortSource::Ssh),
+        Some("DOCKER") => Some(PortSource::Docker),
+        Some("SCAN") => Some(PortSource::Scan),
+        _ => None,
+    };
+    Some((port, source))
+}
+
+/// Entry a selection refers to.
+pub fn find<'a>(entries: &'a [PortEntry], selection: &str) -> Option<&'a PortEntry> {
+    let (port, source) = parse_selection(selection)?;
+    entries
+        .iter()
+        .find(|e| e.local_port == port && source.as_ref().is_none_or(|s| &e.source == s))
+}
+
+/// Score `text` against `query` as an ordered, case-insensitive subsequence.
+///
+/// Consecutive matches and matches at word starts score higher; `None` means
+/// some query character is missing.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[pos..].iter().position(|&c| c == q)?;
+        let index = pos + offset;
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        prev_match = Some(index);
+        pos = index + 1;
+    }
+    Some(score)
+}
+
+/// Best fuzzy match for `query`; ties go to the earlier entry.
+pub fn best_match<'a>(entries: &'a [PortEntry], query: &str) -> Option<&'a PortEntry> {
+    let mut best: Option<(i64, &PortEntry)> = None;
+    for entry in entries {
+        if let Some(score) = fuzzy_score(query, &line(entry)) {
+            if best.is_none_or(|(s, _)| score > s) {
+                best = Some((score, entry));
+            }
+        }
+    }
+    best.map(|(_, entry)| entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::entry;
+
+    #[test]
+    fn test_line_round_trip() {
+        let entries = vec![
+            entry(PortSource::Local, 3000, "node"),
+            entry(PortSource::Ssh, 3000, "ssh"),
+        ];
+        let selected = line(&entries[1]);
+        assert_eq!(selected, "3000\tSSH\tssh (pid:42)\t");
+        assert_eq!(find(&entries, &selected).unwrap().source, PortSource::Ssh);
+        assert_eq!(find(&entries, "3000").unwrap().source, PortSource::Local);
+        assert_eq!(find(&entries, ":3000\n").unwrap().local_port, 3000);
+        assert!(find(&entries, "nonsense").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("pg", "5432\tLOCAL\tpostgres").is_some());
+        assert!(fuzzy_score("xyz", "5432\tLOCAL\tpostgres").is_none());
+        let contiguous = fuzzy_score("post", "postgres").unwrap();
+        let scattered = fuzzy_score("post", "p-o-s-t").unwrap();
+        assert!(contiguous > scattered);
+    }
+
+    #[test]
+    fn test_best_match() {
+        let entries = vec![
+            entry(PortSource::Local, 3000, "node"),
+            entry(PortSource::Local, 5432, "postgres"),
+            entry(PortSource::Local, 6379, "redis-server"),
+        ];
+        assert_eq!(best_match(&entries, "postgres").unwrap().local_port, 5432);
+        assert_eq!(best_match(&entries, "redis").unwrap().local_port, 6379);
+        assert_eq!(best_match(&entries, "3000").unwrap().local_port, 3000);
+        assert!(best_match(&entries, "mysql").is_none());
+    }
+}
@@ -0,0 +1,119 @@
+//! Pinned ports (pins.toml), kept at the top of the table.
+//!
+//! Pins are stored per connection name, so the same port can be pinned on one
+//! host and not another:
+//!
+//! ```toml
+//! [pins]
+//! Local = [3000, 5432]
+//! Prod = [8080]
+//! ```
+
+use crate::config::{self, Config, ConfigDiagnostic};
+use crate::state;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pins {
+    /// Connection name to its pinned local ports.
+    #[serde(default)]
+    pub pins: BTreeMap<String, BTreeSet<u16>>,
+    /// Set when pins.toml exists but could not be read; saving would
+    /// replace the user's file with the pins toggled since.
+    #[serde(skip)]
+    pub load_failed: bool,
+}
+
+impl Pins {
+    pub fn pins_path() -> Option<PathBuf> {
+        Config::config_dir().map(|p| p.join("pins.toml"))
+    }
+
+    pub fn load() -> Result<Self, ConfigDiagnostic> {
+        config::load_file(Self::pins_path())
+    }
+
+    /// The stored pins, or none with `load_failed` set after recording why
+    /// pins.toml could not be read in `diagnostics`.
+    pub fn load_or_default(diagnostics: &mut Vec<ConfigDiagnostic>) -> Self {
+        Self::load().unwrap_or_else(|d| {
+            diagnostics.push(d);
+            Self {
+                load_failed: true,
+                ..Self::default()
+            }
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if self.load_failed {
+            anyhow::bail!("pins.toml has errors; fix it before pinning here");
+        }
+        let Some(path) = Self::pins_path() else {
+            anyhow::bail!("Could not determine config directory");
+        };
+        let content = toml::to_string_pretty(self)?;
+        state::replace(&path, &content)?;
+        Ok(())
+    }
+
+    pub fn contains(&self, connection: &str, port: u16) -> bool {
+        self.pins
+            .get(connection)
+            .is_some_and(|ports| ports.contains(&port))
+    }
+
+    /// Ports pinned on `connection`, in ascending order.
+    pub fn ports(&self, connection: &str) -> impl Iterator<Item = u16> + '_ {
+        self.pins.get(connection).into_iter().flatten().copied()
+    }
+
+    /// Pin `port` on `connection`, or unpin it if already pinned.
+    /// Returns whether the port is pinned afterwards.
+    pub fn toggle(&mut self, connection: &str, port: u16) -> bool {
+        let ports = self.pins.entry(connection.to_string()).or_default();
+        let pinned = ports.insert(port);
+        if !pinned {
+            ports.remove(&port);
+            if ports.is_empty() {
+                self.pins.remove(connection);
+            }
+        }
+        pinned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_and_round_trip() {
+        let mut pins = Pins::default();
+        assert!(pins.toggle("Local", 3000));
+        assert!(pins.toggle("Local", 5432));
+        assert!(pins.toggle("All connections", 80));
+        assert!(pins.contains("Local", 3000));
+        assert!(!pins.contains("Prod", 3000));
+        assert_eq!(pins.ports("Local").collect::<Vec<_>>(), vec![3000, 5432]);
+
+        let content = toml::to_string_pretty(&pins).unwrap();
+        let parsed: Pins = toml::from_str(&content).unwrap();
+        assert_eq!(parsed, pins);
+
+        assert!(!pins.toggle("All connections", 80));
+        assert!(!pins.pins.contains_key("All connections"));
+    }
+
+    #[test]
+    fn test_save_refused_after_failed_load() {
+        let pins = Pins {
+            load_failed: true,
+            ..Pins::default()
+        };
+        let error = pins.save().unwrap_err();
+        assert!(error.to_string().contains("pins.toml has errors"));
+    }
+}
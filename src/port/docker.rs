@@ -1,9 +1,14 @@
-use super::{PortEntry, PortSource, ssh_cmd_tokio};
+use super::listeners::{self, Collector, Listener, Target};
+use super::usage::Usage;
+use super::{CommandError, PortEntry, PortSource, dedup_listeners, remote_output, sources_config};
 use anyhow::Result;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::process::Output;
+use std::sync::RwLock;
 use std::time::Duration;
-use tokio::process::Command;
 
 pub struct ContainerInfo {
     pub ip: String,
@@ -13,46 +18,157 @@ pub struct ContainerInfo {
 const TUNNEL_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
 const TUNNEL_PROPAGATION_DELAY: Duration = Duration::from_millis(100);
 
+/// `--docker-context` / `[general] docker_context`, for every connection.
+static DEFAULT_CONTEXT: RwLock<Option<String>> = RwLock::new(None);
+/// The active connection's `docker_context`, over the default.
+static CONNECTION_CONTEXT: RwLock<Option<String>> = RwLock::new(None);
+/// Whether the daemon of a context (`None`: docker's own choice, which
+/// follows `DOCKER_HOST` and `docker context use`) runs on another machine.
+static REMOTE_DAEMONS: RwLock<Option<HashMap<Option<String>, bool>>> = RwLock::new(None);
+
+tokio::task_local! {
+    /// Context of one connection scanned by the aggregate view.
+    static SCOPED_CONTEXT: Option<String>;
+}
+
+/// Set the Docker context used unless a connection names its own.
+pub fn configure_context(context: Option<&str>) {
+    if let Ok(mut slot) = DEFAULT_CONTEXT.write() {
+        *slot = context.map(str::to_string);
+    }
+}
+
+/// Set the active connection's Docker context; `None` falls back to the default.
+pub fn set_connection_context(context: Option<&str>) {
+    if let Ok(mut slot) = CONNECTION_CONTEXT.write() {
+        *slot = context.map(str::to_string);
+    }
+}
+
+/// Run `future` with docker commands using `context` (or the default when
+/// `None`), whatever the active connection is.
+pub async fn with_context<F: Future>(context: Option<String>, future: F) -> F::Output {
+    SCOPED_CONTEXT.scope(context, future).await
+}
+
+/// The Docker context docker commands run with, if quay picks one.
+pub fn context() -> Option<String> {
+    let read = |slot: &RwLock<Option<String>>| slot.read().ok().and_then(|c| c.clone());
+    SCOPED_CONTEXT
+        .try_with(Clone::clone)
+        .ok()
+        .flatten()
+        .or_else(|| read(&CONNECTION_CONTEXT))
+        .or_else(|| read(&DEFAULT_CONTEXT))
+}
+
+/// `docker [--context <name>] <args>`.
+pub fn command(args: &[&str]) -> Vec<String> {
+    let mut words = vec!["docker".to_string()];
+    if let Some(context) = context() {
+        words.extend(["--context".to_string(), context]);
+    }
+    words.extend(args.iter().map(|arg| (*arg).to_string()));
+    words
+}
+
+/// Run `docker <args>` on this machine or `host`, in the Docker context.
+pub async fn run(args: &[&str], host: Option<&str>) -> std::io::Result<Output> {
+    let words = command(args);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    listeners::output(&words, host).await
+}
+
+/// Whether the local docker CLI talks to a daemon on another machine
+/// (`tcp://` or `ssh://` endpoint), whose published ports can't be probed
+/// on localhost. Asked once per context.
+pub async fn daemon_is_remote() -> bool {
+    let context = context();
+    let cached = REMOTE_DAEMONS
+        .read()
+        .ok()
+        .and_then(|d| d.as_ref()?.get(&context).copied());
+    if let Some(remote) = cached {
+        return remote;
+    }
+    let args = [
+        "context",
+        "inspect",
+        "--format",
+        "{{.Endpoints.docker.Host}}",
+    ];
+    let remote = match run(&args, None).await {
+        Ok(output) if output.status.success() => {
+            is_remote_endpoint(String::from_utf8_lossy(&output.stdout).trim())
+        }
+        _ => false,
+    };
+    if let Ok(mut daemons) = REMOTE_DAEMONS.write() {
+        daemons
+            .get_or_insert_with(HashMap::new)
+            .insert(context, remote);
+    }
+    remote
+}
+
+/// Docker endpoints other than local sockets and pipes.
+fn is_remote_endpoint(endpoint: &str) -> bool {
+    !endpoint.is_empty() && !endpoint.starts_with("unix://") && !endpoint.starts_with("npipe://")
+}
+
+/// Published container ports on this machine or `remote_host`.
+///
+/// Fails with a short reason ("not installed", "daemon down", ...) when
+/// Docker can't be asked, with the command's error as context.
 pub async fn collect(remote_host: Option<&str>) -> Result<Vec<PortEntry>> {
+    let stdout = ps_output(remote_host).await?;
+    let remote = remote_host.is_some() || daemon_is_remote().await;
+    parse_docker_ps(&stdout, remote)
+}
+
+/// Output of `docker ps` on this machine or `remote_host`, failing as [`collect`] does.
+pub(super) async fn ps_output(remote_host: Option<&str>) -> Result<String> {
+    let args = [
+        "ps",
+        "--format",
+        "{{.ID}}\t{{.Names}}\t{{.Ports}}\t{{.Status}}",
+    ];
     let output = match remote_host {
-        Some(host) => {
-            match ssh_cmd_tokio(
-                host,
-                &[
-                    "docker",
-                    "ps",
-                    "--format",
-                    "{{.ID}}\t{{.Names}}\t{{.Ports}}",
-                ],
-            )
-            .output()
+        Some(host) => run(&args, Some(host))
             .await
-            {
-                Ok(o) => o,
-                Err(_) => return Ok(Vec::new()),
+            .map_err(|e| anyhow::Error::new(e).context("could not run ssh"))?,
+        None => match run(&args, None).await {
+            Ok(o) => o,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow::Error::new(e).context("not installed"));
             }
-        }
-        None => {
-            match Command::new("docker")
-                .args(["ps", "--format", "{{.ID}}\t{{.Names}}\t{{.Ports}}"])
-                .output()
-                .await
-            {
-                Ok(o) => o,
-                Err(_) => return Ok(Vec::new()), // Docker not installed
-            }
-        }
+            Err(e) => return Err(anyhow::Error::new(e).context("could not run docker")),
+        },
     };
 
     if !output.status.success() {
-        return Ok(Vec::new()); // Docker daemon not running
+        let error = CommandError::new("docker", &args, remote_host, &output);
+        return Err(anyhow::Error::new(error).context(failure_reason(&output)));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    parse_docker_ps(&stdout, remote_host.is_some())
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Why `docker ps` failed, in a few words.
+fn failure_reason(output: &Output) -> &'static str {
+    let stderr = String::from_utf8_lossy(&output.stderr).to_lowercase();
+    if output.status.code() == Some(127) {
+        "not installed"
+    } else if stderr.contains("permission denied") {
+        "permission denied"
+    } else if stderr.contains("cannot connect") || stderr.contains("daemon running") {
+        "daemon down"
+    } else {
+        "docker ps failed"
+    }
 }
 
-fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
+pub(super) fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
     let mut entries = Vec::new();
     // Match single port: 0.0.0.0:5432->5432/tcp or :::5432->5432/tcp
     // Match port range:  0.0.0.0:3000-3001->3000-3001/tcp or :::3000-3001->3000-3001/tcp
@@ -71,6 +187,8 @@ fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
         let container_id = parts[0].to_string();
         let container_name = parts[1].to_string();
         let ports_str = parts[2];
+        // Recordings made before the status column have three
+        let health = parts.get(3).and_then(|status| health_of(status));
         let mut seen_ports = std::collections::HashSet::new();
 
         for cap in port_re.captures_iter(ports_str) {
@@ -82,10 +200,8 @@ fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
             match (local_end, remote_end) {
                 // Range: 3000-3001->3000-3001/tcp → expand to individual ports
                 (Some(le), Some(re)) if le >= local_start && re >= remote_start => {
-                    let count = (le - local_start + 1).min(re - remote_start + 1);
-                    for i in 0..count {
-                        let lp = local_start + i;
-                        let rp = remote_start + i;
+                    // Pairs up to the shorter range; inclusive ranges can't overflow at 65535
+                    for (lp, rp) in (local_start..=le).zip(remote_start..=re) {
                         if lp > 0 && seen_ports.insert(lp) {
                             entries.push(PortEntry {
                                 source: PortSource::Docker,
@@ -93,13 +209,11 @@ fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
                                 remote_host: Some(container_name.clone()),
                                 remote_port: Some(rp),
                                 process_name: container_name.clone(),
-                                pid: None,
                                 container_id: Some(container_id.clone()),
                                 container_name: Some(container_name.clone()),
-                                ssh_host: None,
                                 is_open: remote_mode,
-                                is_loopback: false,
-                                forwarded_port: None,
+                                health: health.clone(),
+                                ..PortEntry::default()
                             });
                         }
                     }
@@ -113,13 +227,11 @@ fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
                             remote_host: Some(container_name.clone()),
                             remote_port: Some(remote_start),
                             process_name: container_name.clone(),
-                            pid: None,
                             container_id: Some(container_id.clone()),
                             container_name: Some(container_name.clone()),
-                            ssh_host: None,
                             is_open: remote_mode,
-                            is_loopback: false,
-                            forwarded_port: None,
+                            health: health.clone(),
+                            ..PortEntry::default()
                         });
                     }
                 }
@@ -130,158 +242,199 @@ fn parse_docker_ps(output: &str, remote_mode: bool) -> Result<Vec<PortEntry>> {
     Ok(entries)
 }
 
-/// Collect LISTEN ports from inside a Docker container via `ss -tln`.
+/// Health check status in a `docker ps` Status column, e.g. `unhealthy`
+/// from `Up 2 hours (unhealthy)` or `starting` from `(health: starting)`.
+fn health_of(status: &str) -> Option<String> {
+    let (_, rest) = status.rsplit_once('(')?;
+    let health = rest.strip_suffix(')')?;
+    let health = health.strip_prefix("health: ").unwrap_or(health);
+    matches!(health, "healthy" | "unhealthy" | "starting").then(|| health.to_string())
+}
+
+/// Collect LISTEN ports from inside a Docker container with ss, or the first
+/// fallback installed in the image (see [`listeners`]).
 /// When `remote_host` is Some, the command is run via SSH on the remote host.
 pub async fn collect_from_container(
     container: &str,
     remote_host: Option<&str>,
 ) -> Result<Vec<PortEntry>> {
-    let output = match remote_host {
-        Some(host) => {
-            match ssh_cmd_tokio(host, &["docker", "exec", container, "ss", "-tln"])
-                .output()
-                .await
-            {
-                Ok(o) => o,
-                Err(e) => anyhow::bail!("Failed to run ss in container via SSH: {e}"),
-            }
-        }
-        None => {
-            match Command::new("docker")
-                .args(["exec", container, "ss", "-tln"])
-                .output()
-                .await
-            {
-                Ok(o) => o,
-                Err(e) => anyhow::bail!("Failed to run ss in container: {e}"),
-            }
-        }
-    };
+    let target = Target::Container(container, remote_host);
+    let (collector, stdout) = listeners::collect(target, &Collector::CONTAINER_CHAIN)
+        .await
+        .map_err(|e| e.context(format!("Port scan failed in container '{container}'")))?;
+    let mut listeners = listeners::parse(collector, &stdout);
+    let processes = container_processes(container, remote_host)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::debug!(container, error = %e, "could not map container PIDs");
+            Vec::new()
+        });
+    resolve_host_pids(&mut listeners, &processes);
+    let mut entries = container_entries(listeners, container);
+    for entry in &mut entries {
+        entry.collector = Some(collector);
+    }
+    Ok(entries)
+}
+
+/// A process of a container as the host sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ContainerProcess {
+    host_pid: u32,
+    /// PID inside the container's namespace (the last `NSpid` value).
+    ns_pid: Option<u32>,
+    name: Option<String>,
+}
 
+/// The processes of `container`: host PIDs from `docker top`, matched to
+/// their in-container PIDs through `/proc/<pid>/status` on the host.
+async fn container_processes(
+    container: &str,
+    remote_host: Option<&str>,
+) -> Result<Vec<ContainerProcess>> {
+    let top = ["top", container, "-o", "pid"];
+    let output = run(&top, remote_host).await?;
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!(
-            "ss command failed in container '{}': {}",
-            container,
-            stderr.trim()
-        );
+        return Err(CommandError::new("docker", &top, remote_host, &output).into());
+    }
+    let host_pids = parse_docker_top_pids(&String::from_utf8_lossy(&output.stdout));
+    if host_pids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(parse_ss_output(&stdout, container))
+    let paths: Vec<String> = host_pids
+        .iter()
+        .map(|pid| format!("/proc/{pid}/status"))
+        .collect();
+    let mut grep = vec!["grep", "-H", "-e", "^Name:", "-e", "^NSpid:"];
+    grep.extend(paths.iter().map(String::as_str));
+    // Processes may exit in between; grep still prints what it could read
+    let output = listeners::output(&grep, remote_host).await?;
+    let mut processes = parse_proc_status(&String::from_utf8_lossy(&output.stdout));
+    // Without a readable /proc (Docker Desktop), the PIDs alone still count
+    for pid in host_pids {
+        if !processes.iter().any(|p| p.host_pid == pid) {
+            processes.push(ContainerProcess {
+                host_pid: pid,
+                ns_pid: None,
+                name: None,
+            });
+        }
+    }
+    Ok(processes)
 }
 
-/// Parse `ss -tln` output from inside a container.
+/// PIDs of `docker top CONTAINER -o pid` output.
+fn parse_docker_top_pids(output: &str) -> Vec<u32> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect()
+}
+
+/// Parse `grep -H -e ^Name: -e ^NSpid: /proc/<pid>/status...` output.
 ///
-/// Example output:
 /// ```text
-/// State  Recv-Q Send-Q  Local Address:Port   Peer Address:Port Process
-/// LISTEN 0      511           *:3000              *:*
-/// LISTEN 0      511     0.0.0.0:5173        0.0.0.0:*
-/// LISTEN 0      128     127.0.0.1:5432      0.0.0.0:*
-/// LISTEN 0      511        [::]:3000           [::]:*
+/// /proc/4242/status:Name:  node
+/// /proc/4242/status:NSpid: 4242  7
 /// ```
-fn parse_ss_output(output: &str, container_name: &str) -> Vec<PortEntry> {
-    let mut entries = Vec::new();
-    let mut seen_ports = HashSet::new();
-
+fn parse_proc_status(output: &str) -> Vec<ContainerProcess> {
+    let mut processes: Vec<ContainerProcess> = Vec::new();
     for line in output.lines() {
-        let trimmed = line.trim();
-        // Skip header and empty lines
-        if trimmed.is_empty() || trimmed.starts_with("State") {
+        let Some((path, field)) = line.split_once("/status:") else {
             continue;
-        }
-        if !trimmed.starts_with("LISTEN") {
+        };
+        let Some(host_pid) = path.strip_prefix("/proc/").and_then(|p| p.parse().ok()) else {
             continue;
+        };
+        // grep prints the lines of one file together
+        if processes.last().is_none_or(|p| p.host_pid != host_pid) {
+            processes.push(ContainerProcess {
+                host_pid,
+                ns_pid: None,
+                name: None,
+            });
         }
-
-        let fields: Vec<&str> = trimmed.split_whitespace().collect();
-        if fields.len() < 4 {
+        let Some(process) = processes.last_mut() else {
             continue;
-        }
-
-        let local_addr = fields[3];
-        // Extract port: last segment after ':'
-        let port = match local_addr
-            .rsplit(':')
-            .next()
-            .and_then(|p| p.parse::<u16>().ok())
-        {
-            Some(p) if p > 0 => p,
-            _ => continue,
         };
-
-        // Determine bind address for forwardability
-        let bind_addr = &local_addr[..local_addr.rfind(':').unwrap_or(0)];
-        let is_loopback = bind_addr == "127.0.0.1" || bind_addr == "[::1]";
-
-        // Deduplicate IPv4/IPv6 entries for the same port
-        if !seen_ports.insert(port) {
-            continue;
+        if let Some(name) = field.strip_prefix("Name:") {
+            process.name = Some(name.trim().to_string());
+        } else if let Some(pids) = field.strip_prefix("NSpid:") {
+            process.ns_pid = pids.split_whitespace().last().and_then(|p| p.parse().ok());
         }
+    }
+    processes
+}
 
-        // Extract process name from Process column if available
-        let process_name = if fields.len() > 5 {
-            // Process column may contain "users:(("name",...))"
-            let proc_field = fields[5..].join(" ");
-            if let Some(start) = proc_field.find("((\"") {
-                if let Some(end) = proc_field[start + 3..].find('"') {
-                    proc_field[start + 3..start + 3 + end].to_string()
-                } else {
-                    container_name.to_string()
-                }
-            } else {
-                container_name.to_string()
-            }
-        } else {
-            container_name.to_string()
+/// Replace the in-container PIDs of `listeners` with host PIDs.
+///
+/// A listener whose PID isn't known (no `ss -p` info, or a `/proc/net/tcp`
+/// fallback) is owned by the container's only process, if it has just one.
+/// PIDs that can't be mapped are dropped; they mean nothing on the host.
+fn resolve_host_pids(listeners: &mut [Listener], processes: &[ContainerProcess]) {
+    let only = match processes {
+        [process] => Some(process),
+        _ => None,
+    };
+    for listener in listeners {
+        let process = match listener.pid {
+            Some(ns_pid) => processes.iter().find(|p| p.ns_pid == Some(ns_pid)),
+            None => only,
         };
+        listener.pid = process.map(|p| p.host_pid);
+        if listener.process.is_none() {
+            listener.process = process.and_then(|p| p.name.clone());
+        }
+    }
+}
 
-        entries.push(PortEntry {
+/// Entries for the sockets listening inside `container_name`, one per port,
+/// address, and process (see [`dedup_listeners`]).
+fn container_entries(listeners: Vec<Listener>, container_name: &str) -> Vec<PortEntry> {
+    let mut entries: Vec<PortEntry> = listeners
+        .into_iter()
+        .map(|listener| PortEntry {
             source: PortSource::Docker,
-            local_port: port,
+            local_port: listener.port,
             remote_host: Some(container_name.to_string()),
-            remote_port: Some(port),
-            process_name,
-            pid: None,
-            container_id: None,
+            remote_port: Some(listener.port),
+            is_loopback: listener.is_loopback(),
+            process_name: listener
+                .process
+                .unwrap_or_else(|| container_name.to_string()),
+            // Host PID, see `resolve_host_pids`
+            pid: listener.pid,
             container_name: Some(container_name.to_string()),
-            ssh_host: None,
             is_open: true,
-            is_loopback,
-            forwarded_port: None,
-        });
-    }
-
+            bind: Some(listener.bind),
+            ..PortEntry::default()
+        })
+        .collect();
+    dedup_listeners(&mut entries);
     entries
 }
 
 /// Get the IP address and port mappings of a Docker container.
 /// Uses `docker inspect` to retrieve the container's IP and port mappings in one call.
-pub async fn get_container_info(container: &str, remote_host: Option<&str>) -> Result<ContainerInfo> {
-    let inspect_fmt = r#"{{range .NetworkSettings.Networks}}IP:{{.IPAddress}}
+pub async fn get_container_info(
+    container: &str,
+    remote_host: Option<&str>,
+) -> Result<ContainerInfo> {
+    let inspect_fmt = r"{{range .NetworkSettings.Networks}}IP:{{.IPAddress}}
 {{end}}{{range $p, $conf := .NetworkSettings.Ports}}{{range $conf}}PORT:{{$p}}->{{.HostIp}}:{{.HostPort}}
-{{end}}{{end}}"#;
-    let output = match remote_host {
-        Some(host) => {
-            ssh_cmd_tokio(host, &["docker", "inspect", "-f", inspect_fmt, container])
-                .output()
-                .await?
-        }
-        None => {
-            Command::new("docker")
-                .args(["inspect", "-f", inspect_fmt, container])
-                .output()
-                .await?
-        }
-    };
+{{end}}{{end}}";
+    let output = run(&["inspect", "-f", inspect_fmt, container], remote_host).await?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!(
-            "Failed to inspect container '{}': {}",
-            container,
-            stderr.trim()
+        let err = CommandError::new(
+            "docker",
+            &["inspect", "-f", inspect_fmt, container],
+            remote_host,
+            &output,
+        );
+        return Err(
+            anyhow::Error::new(err).context(format!("Failed to inspect container '{container}'"))
         );
     }
 
@@ -320,6 +473,120 @@ fn parse_container_info(output: &str) -> Result<ContainerInfo> {
     Ok(ContainerInfo { ip, port_mappings })
 }
 
+/// A VM the Docker daemon runs in (macOS and Windows). Published ports reach
+/// the host through the VM's port forwarding, but container IPs only exist
+/// inside the VM, so forwards can't point at them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmBackend {
+    DockerDesktop,
+    Colima,
+    OrbStack,
+    RancherDesktop,
+}
+
+impl fmt::Display for VmBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VmBackend::DockerDesktop => "Docker Desktop",
+            VmBackend::Colima => "Colima",
+            VmBackend::OrbStack => "OrbStack",
+            VmBackend::RancherDesktop => "Rancher Desktop",
+        })
+    }
+}
+
+/// The VM behind the Docker daemon of this machine or `remote_host`; `None`
+/// for a daemon running directly on the host, when docker can't be asked, or
+/// when `[sources] docker` is off.
+pub async fn vm_backend(remote_host: Option<&str>) -> Option<VmBackend> {
+    if !sources_config().docker {
+        return None;
+    }
+    let args = ["info", "--format", "{{.OperatingSystem}}\t{{.Name}}"];
+    let output = run(&args, remote_host).await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_vm_backend(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `operating system, daemon host name` from `docker info`. Docker Desktop
+/// and `OrbStack` report themselves as the OS; Colima and Rancher Desktop,
+/// both Lima VMs, show in the VM's host name.
+fn parse_vm_backend(output: &str) -> Option<VmBackend> {
+    let (os, name) = output.trim().split_once('\t')?;
+    if os.contains("Docker Desktop") {
+        Some(VmBackend::DockerDesktop)
+    } else if os.contains("OrbStack") {
+        Some(VmBackend::OrbStack)
+    } else if name == "colima" || name.starts_with("colima-") {
+        Some(VmBackend::Colima)
+    } else if name.contains("rancher-desktop") {
+        Some(VmBackend::RancherDesktop)
+    } else {
+        None
+    }
+}
+
+/// CPU and memory of the running containers on this machine or
+/// `remote_host`, by short ID and by name. `docker stats --no-stream` samples
+/// for about two seconds, so this runs apart from the scan.
+pub async fn stats(remote_host: Option<&str>) -> Result<HashMap<String, Usage>> {
+    let args = [
+        "stats",
+        "--no-stream",
+        "--format",
+        "{{.ID}}\t{{.Name}}\t{{.CPUPerc}}\t{{.MemUsage}}",
+    ];
+    let output = run(&args, remote_host).await?;
+    if !output.status.success() {
+        return Err(CommandError::new("docker", &args, remote_host, &output).into());
+    }
+    Ok(parse_stats(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `id, name, CPU%, used / limit` lines of `docker stats`; containers that
+/// report `--` (stopping, or no cgroup data) are left out.
+fn parse_stats(output: &str) -> HashMap<String, Usage> {
+    let mut stats = HashMap::new();
+    for line in output.lines() {
+        let [id, name, cpu, memory] = line.split('\t').collect::<Vec<_>>()[..] else {
+            continue;
+        };
+        let cpu = cpu.trim().trim_end_matches('%').parse().ok();
+        let memory = memory.split('/').next().and_then(parse_size);
+        let (Some(cpu), Some(memory)) = (cpu, memory) else {
+            continue;
+        };
+        let usage = Usage { cpu, memory };
+        stats.insert(id.trim().to_string(), usage);
+        stats.insert(name.trim().to_string(), usage);
+    }
+    stats
+}
+
+/// Bytes in a size as docker prints it: `48.2MiB`, `1.5GB`, `0B`.
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let split = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.trim().parse().ok()?;
+    let factor: f64 = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        _ => return None,
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Some((number * factor) as u64)
+}
+
 /// Parse `ss -tn state established dst IP` output to count connections per peer port.
 ///
 /// The peer port corresponds to the container port that received the connection.
@@ -373,11 +640,10 @@ async fn get_host_to_container_port_counts(
     container_ip: &str,
     remote_host: &str,
 ) -> Result<HashMap<u16, usize>> {
-    let output = ssh_cmd_tokio(
+    let output = remote_output(
         remote_host,
         &["ss", "-tn", "state", "established", "dst", container_ip],
     )
-    .output()
     .await?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -456,15 +722,137 @@ pub async fn detect_forward_mappings(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_vm_backend() {
+        assert_eq!(
+            parse_vm_backend("Docker Desktop\tdocker-desktop\n"),
+            Some(VmBackend::DockerDesktop)
+        );
+        assert_eq!(
+            parse_vm_backend("Ubuntu 24.04 LTS\tcolima\n"),
+            Some(VmBackend::Colima)
+        );
+        assert_eq!(
+            parse_vm_backend("Ubuntu 24.04 LTS\tcolima-work\n"),
+            Some(VmBackend::Colima)
+        );
+        assert_eq!(
+            parse_vm_backend("OrbStack\torbstack\n"),
+            Some(VmBackend::OrbStack)
+        );
+        assert_eq!(
+            parse_vm_backend("Alpine Linux v3.20\tlima-rancher-desktop\n"),
+            Some(VmBackend::RancherDesktop)
+        );
+        assert_eq!(parse_vm_backend("Debian GNU/Linux 12\tbuild01\n"), None);
+        assert_eq!(parse_vm_backend(""), None);
+        assert_eq!(VmBackend::DockerDesktop.to_string(), "Docker Desktop");
+    }
+
+    #[test]
+    fn test_command_context() {
+        let words = SCOPED_CONTEXT.sync_scope(Some("lab".to_string()), || command(&["ps", "-q"]));
+        assert_eq!(words, ["docker", "--context", "lab", "ps", "-q"]);
+
+        assert!(is_remote_endpoint("ssh://deploy@lab"));
+        assert!(is_remote_endpoint("tcp://10.0.0.5:2376"));
+        assert!(!is_remote_endpoint("unix:///var/run/docker.sock"));
+        assert!(!is_remote_endpoint("npipe:////./pipe/docker_engine"));
+        assert!(!is_remote_endpoint(""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failure_reason() {
+        use std::os::unix::process::ExitStatusExt;
+        let output = |code: i32, stderr: &str| Output {
+            status: std::process::ExitStatus::from_raw(code << 8),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        assert_eq!(
+            failure_reason(&output(
+                1,
+                "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. \
+                 Is the docker daemon running?"
+            )),
+            "daemon down"
+        );
+        assert_eq!(
+            failure_reason(&output(
+                1,
+                "permission denied while trying to connect to the Docker daemon socket"
+            )),
+            "permission denied"
+        );
+        assert_eq!(
+            failure_reason(&output(127, "sh: docker: command not found")),
+            "not installed"
+        );
+    }
+
+    fn parse_ss_output(output: &str, container_name: &str) -> Vec<PortEntry> {
+        let mut listeners = listeners::parse_ss(output);
+        resolve_host_pids(&mut listeners, &[]);
+        container_entries(listeners, container_name)
+    }
+
+    #[test]
+    fn test_resolve_host_pids() {
+        let status = "/proc/4242/status:Name:\tnode\n\
+            /proc/4242/status:NSpid:\t4242\t7\n\
+            /proc/4300/status:Name:\tnginx\n\
+            /proc/4300/status:NSpid:\t4300\t1\n";
+        let processes = parse_proc_status(status);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].ns_pid, Some(7));
+        assert_eq!(processes[1].name.as_deref(), Some("nginx"));
+        assert_eq!(parse_docker_top_pids("PID\n4242\n4300\n"), vec![4242, 4300]);
+
+        let ss = "LISTEN 0 511 0.0.0.0:3000 0.0.0.0:* users:((\"node\",pid=7,fd=20))\n\
+            LISTEN 0 511 0.0.0.0:8080 0.0.0.0:* users:((\"other\",pid=99,fd=3))\n\
+            LISTEN 0 511 0.0.0.0:80 0.0.0.0:*\n";
+        let mut listeners = listeners::parse_ss(ss);
+        resolve_host_pids(&mut listeners, &processes);
+        assert_eq!(listeners[0].pid, Some(4242));
+        // Not a process of this container
+        assert_eq!(listeners[1].pid, None);
+        // Unknown owner, and more than one process to choose from
+        assert_eq!(listeners[2].pid, None);
+
+        let mut listeners = listeners::parse_proc_net_tcp(
+            "0: 00000000:0050 00000000:0000 0A 00000000:00000000 00:00000000 00000000 0 0 1\n",
+        );
+        resolve_host_pids(&mut listeners, &processes[1..]);
+        assert_eq!(listeners[0].pid, Some(4300));
+        assert_eq!(listeners[0].process.as_deref(), Some("nginx"));
+    }
+
+    #[test]
+    fn test_parse_docker_ps_full_range() {
+        let output = "abc123def456\tall\t0.0.0.0:0-65535->0-65535/tcp";
+        let entries = parse_docker_ps(output, false).unwrap();
+        // Port 0 is skipped
+        assert_eq!(entries.len(), 65535);
+        assert_eq!(entries.last().map(|e| e.local_port), Some(65535));
+    }
+
     #[test]
     fn test_parse_docker_ps() {
-        let output = "abc123def456\tpostgres\t0.0.0.0:5432->5432/tcp\n\
+        let output = "abc123def456\tpostgres\t0.0.0.0:5432->5432/tcp\tUp 2 hours (unhealthy)\n\
                       def456abc123\tredis\t0.0.0.0:6379->6379/tcp";
         let entries = parse_docker_ps(output, false).unwrap();
         assert_eq!(entries.len(), 2);
         assert_eq!(entries[0].local_port, 5432);
         assert_eq!(entries[0].container_name, Some("postgres".to_string()));
+        assert_eq!(entries[0].health.as_deref(), Some("unhealthy"));
         assert_eq!(entries[1].local_port, 6379);
+        assert_eq!(entries[1].health, None);
+        assert_eq!(
+            health_of("Up 5 seconds (health: starting)").as_deref(),
+            Some("starting")
+        );
+        assert_eq!(health_of("Up 3 days"), None);
     }
 
     #[test]
@@ -563,11 +951,25 @@ LISTEN 0      128     127.0.0.1:5432      0.0.0.0:*
 LISTEN 0      511     0.0.0.0:3000        0.0.0.0:*
 ";
         let entries = parse_ss_output(output, "test");
+        // Sorted by port
         assert_eq!(entries.len(), 2);
-        assert_eq!(entries[0].local_port, 5432);
-        assert!(entries[0].is_loopback);
-        assert_eq!(entries[1].local_port, 3000);
-        assert!(!entries[1].is_loopback);
+        assert_eq!(entries[0].local_port, 3000);
+        assert!(!entries[0].is_loopback);
+        assert_eq!(entries[1].local_port, 5432);
+        assert!(entries[1].is_loopback);
+    }
+
+    #[test]
+    fn test_parse_ss_output_multiple_listeners() {
+        let output = "\
+State  Recv-Q Send-Q  Local Address:Port   Peer Address:Port Process
+LISTEN 0      511     0.0.0.0:8080        0.0.0.0:*
+LISTEN 0      511        [::]:8080           [::]:*
+LISTEN 0      128     127.0.0.1:8080      0.0.0.0:*
+";
+        let entries = parse_ss_output(output, "test");
+        let binds: Vec<_> = entries.iter().map(|e| e.bind.as_deref()).collect();
+        assert_eq!(binds, vec![Some("*"), Some("127.0.0.1")]);
     }
 
     #[test]
@@ -596,7 +998,7 @@ LISTEN 0      511     0.0.0.0:5173        0.0.0.0:*
         assert!(entries.iter().all(|e| e.is_open));
 
         // Simulate the reset that collect_all() performs
-        for entry in entries.iter_mut() {
+        for entry in &mut entries {
             entry.is_open = false;
         }
         assert!(entries.iter().all(|e| !e.is_open));
@@ -656,6 +1058,27 @@ Recv-Q Send-Q  Local Address:Port   Peer Address:Port Process
         assert_eq!(counts.get(&3000), Some(&1));
     }
 
+    #[test]
+    fn test_parse_stats() {
+        let output = "abc123def456\tdb\t12.50%\t48.2MiB / 7.668GiB\n\
+                      0123456789ab\tcache\t0.00%\t1.5GB / 2GB\n\
+                      fedcba987654\tgone\t--\t-- / --\n\
+                      malformed line\n";
+        let stats = parse_stats(output);
+        assert_eq!(stats.len(), 4);
+        let db = stats["db"];
+        assert!((db.cpu - 12.5).abs() < f32::EPSILON);
+        assert_eq!(db.memory_display(), "48M");
+        assert_eq!(stats["abc123def456"], db);
+        assert_eq!(stats["cache"].memory, 1_500_000_000);
+        assert!(!stats.contains_key("gone"));
+
+        assert_eq!(parse_size("0B"), Some(0));
+        assert_eq!(parse_size(" 512KiB "), Some(512 * 1024));
+        assert_eq!(parse_size("--"), None);
+        assert_eq!(parse_size("12 parsecs"), None);
+    }
+
     #[test]
     fn test_parse_container_info_basic() {
         let output = "\
@@ -721,4 +1144,53 @@ PORT:5173/tcp->::: :5173
         assert_eq!(info.port_mappings.len(), 1);
         assert_eq!(info.port_mappings.get(&5173), Some(&5173));
     }
+
+    mod props {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// A `docker ps` Ports column: mappings (single or ranges, IPv4 or
+        /// IPv6, TCP or UDP) and unpublished ports, comma-separated.
+        fn ports_column() -> impl Strategy<Value = String> {
+            let mapping = prop_oneof![
+                (any::<u16>(), any::<u16>()).prop_map(|(l, r)| format!("0.0.0.0:{l}->{r}/tcp")),
+                (any::<u16>(), any::<u16>()).prop_map(|(l, r)| format!("[::]:{l}->{r}/tcp")),
+                (any::<u16>(), any::<u16>(), any::<u16>(), any::<u16>())
+                    .prop_map(|(a, b, c, d)| format!(":::{a}-{b}->{c}-{d}/tcp")),
+                any::<u16>().prop_map(|p| format!("{p}/udp")),
+                "\\PC{0,20}",
+            ];
+            prop::collection::vec(mapping, 0..6).prop_map(|m| m.join(", "))
+        }
+
+        proptest! {
+            #[test]
+            fn prop_parse_docker_ps_never_panics(
+                lines in prop::collection::vec(
+                    prop_oneof![
+                        ("[0-9a-f]{12}", "\\PC{0,20}", ports_column())
+                            .prop_map(|(id, name, ports)| format!("{id}\t{name}\t{ports}")),
+                        "\\PC{0,60}",
+                    ],
+                    0..8,
+                ),
+            ) {
+                parse_docker_ps(&lines.join("\n"), false).unwrap();
+            }
+
+            #[test]
+            fn prop_parse_docker_ps_expands_ranges(
+                start in 1u16..,
+                len in 0u16..64,
+                name in "[a-zA-Z0-9][a-zA-Z0-9_.-]{0,20}",
+            ) {
+                let end = start.saturating_add(len);
+                let output = format!("abc123def456\t{name}\t0.0.0.0:{start}-{end}->{start}-{end}/tcp");
+                let entries = parse_docker_ps(&output, false).unwrap();
+                let ports: Vec<u16> = entries.iter().map(|e| e.local_port).collect();
+                prop_assert_eq!(ports, (start..=end).collect::<Vec<_>>());
+                prop_assert!(entries.iter().all(|e| e.container_name.as_deref() == Some(name.as_str())));
+            }
+        }
+    }
 }
@@ -0,0 +1,430 @@
+//! In-process SSH client (libssh2) used when `[ssh] backend = "embedded"`.
+//!
+//! Covers what quay needs from `ssh`: running remote commands and local
+//! (`-L`) forwards. Host aliases are resolved from `~/.ssh/config`
+//! (`HostName`, `User`, `Port`, `IdentityFile`), host keys are checked
+//! against `~/.ssh/known_hosts`, and authentication tries the SSH agent
+//! before key files. Forwards live inside the quay process and stop with it.
+//!
+//! It uses libssh2 through the `ssh2` crate, not a pure-Rust client such as
+//! `russh`. That trades a C dependency (built by `libssh2-sys`, so the
+//! feature needs a C compiler) for a blocking API that fits quay's
+//! synchronous command and forward helpers without a second SSH runtime.
+//! Nothing outside this module depends on libssh2.
+
+use super::ssh_hosts::{expand_home, host_matches, read_config};
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Limit for blocking libssh2 calls, so a stalled server cannot hang a scan.
+const SESSION_TIMEOUT_MS: u32 = 15_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const DEFAULT_IDENTITIES: [&str; 3] = ["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+
+/// Connection parameters for a host alias, after applying `~/.ssh/config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    user: String,
+    hostname: String,
+    port: u16,
+    identity_files: Vec<PathBuf>,
+}
+
+/// Settings from the `Host` blocks of an ssh config that match an alias.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct HostConfig {
+    hostname: Option<String>,
+    user: Option<String>,
+    port: Option<u16>,
+    identity_files: Vec<String>,
+}
+
+/// Collect the settings for `alias`. As in OpenSSH, the first value found wins,
+/// except `IdentityFile`, which accumulates. `Match` blocks are not evaluated.
+fn parse_ssh_config(content: &str, alias: &str) -> HostConfig {
+    let mut config = HostConfig::default();
+    let mut active = true;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(|c: char| c == '=' || c.is_whitespace()) {
+            Some((key, value)) => (key, value.trim_start_matches([' ', '\t', '=']).trim()),
+            None => (line, ""),
+        };
+        let value = value.trim_matches('"');
+        match key.to_lowercase().as_str() {
+            "host" => active = host_matches(value, alias),
+            "match" => active = false,
+            _ if !active => {}
+            "hostname" => {
+                config.hostname.get_or_insert_with(|| value.to_string());
+            }
+            "user" => {
+                config.user.get_or_insert_with(|| value.to_string());
+            }
+            "port" if config.port.is_none() => config.port = value.parse().ok(),
+            "identityfile" => config.identity_files.push(value.to_string()),
+            _ => {}
+        }
+    }
+    config
+}
+
+/// Resolve `[user@]alias` the way `ssh` would, as far as quay needs it.
+fn resolve_target(host: &str, ssh_config: &str, identity_file: Option<&str>) -> Target {
+    let (user, alias) = match host.split_once('@') {
+        Some((user, alias)) => (Some(user.to_string()), alias),
+        None => (None, host),
+    };
+    let config = parse_ssh_config(ssh_config, alias);
+    let user = user
+        .or(config.user)
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "root".to_string());
+    let identity_files = identity_file
+        .into_iter()
+        .chain(config.identity_files.iter().map(String::as_str))
+        .chain(DEFAULT_IDENTITIES)
+        .map(expand_home)
+        .collect();
+    Target {
+        user,
+        hostname: config.hostname.unwrap_or_else(|| alias.to_string()),
+        port: config.port.unwrap_or(22),
+        identity_files,
+    }
+}
+
+fn target_for(host: &str) -> Target {
+    let ssh_config = read_config();
+    let settings = super::ssh_config();
+    resolve_target(host, &ssh_config, settings.identity_file.as_deref())
+}
+
+/// Open an authenticated session to `target`.
+fn connect(target: &Target) -> io::Result<Session> {
+    tracing::debug!(
+        user = %target.user,
+        hostname = %target.hostname,
+        port = target.port,
+        "embedded ssh connect"
+    );
+    let addrs: Vec<SocketAddr> = (target.hostname.as_str(), target.port)
+        .to_socket_addrs()?
+        .collect();
+    let mut last_err = io::Error::new(ErrorKind::NotFound, "no addresses found");
+    let mut tcp = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                tcp = Some(stream);
+                break;
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    let tcp = tcp.ok_or(last_err)?;
+
+    let mut session = Session::new()?;
+    session.set_timeout(SESSION_TIMEOUT_MS);
+    session.set_tcp_stream(tcp);
+    session.handshake()?;
+    verify_host_key(&session, target)?;
+
+    if session.userauth_agent(&target.user).is_err() || !session.authenticated() {
+        for key in target.identity_files.iter().filter(|k| k.exists()) {
+            if session
+                .userauth_pubkey_file(&target.user, None, key, None)
+                .is_ok()
+                && session.authenticated()
+            {
+                break;
+            }
+        }
+    }
+    if !session.authenticated() {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "{}@{}: Permission denied (agent, publickey)",
+                target.user, target.hostname
+            ),
+        ));
+    }
+    Ok(session)
+}
+
+/// Reject servers whose key is missing from or differs from `~/.ssh/known_hosts`.
+fn verify_host_key(session: &Session, target: &Target) -> io::Result<()> {
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| io::Error::other("server sent no host key"))?;
+    let mut known_hosts = session.known_hosts()?;
+    let path = expand_home("~/.ssh/known_hosts");
+    if path.exists() {
+        known_hosts.read_file(&path, KnownHostFileKind::OpenSSH)?;
+    }
+    let failure = match known_hosts.check_port(&target.hostname, target.port, key) {
+        CheckResult::Match => return Ok(()),
+        CheckResult::NotFound => "not in known_hosts (connect once with ssh to add it)",
+        CheckResult::Mismatch => "does not match known_hosts",
+        CheckResult::Failure => "could not be checked",
+    };
+    Err(io::Error::new(
+        ErrorKind::PermissionDenied,
+        format!(
+            "Host key verification failed: key for {} {failure}",
+            target.hostname
+        ),
+    ))
+}
+
+/// Run an already escaped command line on `host` and capture its output.
+pub fn exec(host: &str, command: &str) -> io::Result<Output> {
+    let session = connect(&target_for(host))?;
+    let mut channel = session.channel_session()?;
+    channel.exec(command)?;
+    let mut stdout = Vec::new();
+    channel.read_to_end(&mut stdout)?;
+    let mut stderr = Vec::new();
+    channel.stderr().read_to_end(&mut stderr)?;
+    channel.wait_close()?;
+    let code = channel.exit_status()?;
+    Ok(Output {
+        status: ExitStatus::from_raw(code << 8),
+        stdout,
+        stderr,
+    })
+}
+
+/// A local forward served by this process.
+#[derive(Debug, Clone)]
+pub struct EmbeddedForward {
+    pub local_port: u16,
+    pub remote_host: String,
+    pub remote_port: u16,
+    pub ssh_host: String,
+    stop: Arc<AtomicBool>,
+}
+
+static FORWARDS: Mutex<Vec<EmbeddedForward>> = Mutex::new(Vec::new());
+
+/// Forwards currently served by this process.
+pub fn forwards() -> Vec<EmbeddedForward> {
+    FORWARDS.lock().map(|f| f.clone()).unwrap_or_default()
+}
+
+/// Stop the forward listening on `local_port`. Returns false if there is none.
+pub fn stop_forward(local_port: u16) -> bool {
+    let Ok(mut forwards) = FORWARDS.lock() else {
+        return false;
+    };
+    let Some(index) = forwards.iter().position(|f| f.local_port == local_port) else {
+        return false;
+    };
+    forwards.remove(index).stop.store(true, Ordering::Relaxed);
+    true
+}
+
+/// Start a `local_port:remote_host:remote_port` forward through `host`.
+///
+/// The port is bound and a session authenticated before returning, so bind
+/// and auth errors are reported; each accepted connection then gets its own
+/// session on a background thread.
+pub fn start_forward(spec: &str, host: &str) -> anyhow::Result<()> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    let (bind, local_port, remote_host, remote_port) = match parts.as_slice() {
+        [local_port, remote_host, remote_port] => {
+            ("127.0.0.1", local_port, remote_host, remote_port)
+        }
+        [bind, local_port, remote_host, remote_port] => {
+            (*bind, local_port, remote_host, remote_port)
+        }
+        _ => anyhow::bail!("Invalid forward spec: {spec}"),
+    };
+    let local_port: u16 = local_port.parse()?;
+    let remote_port: u16 = remote_port.parse()?;
+
+    let target = target_for(host);
+    connect(&target)?;
+    let listener = TcpListener::bind((bind, local_port))?;
+    listener.set_nonblocking(true)?;
+
+    let forward = EmbeddedForward {
+        local_port,
+        remote_host: (*remote_host).to_string(),
+        remote_port,
+        ssh_host: host.to_string(),
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+    if let Ok(mut forwards) = FORWARDS.lock() {
+        forwards.push(forward.clone());
+    }
+    tracing::info!(host, spec, "embedded forward listening");
+    std::thread::spawn(move || serve_forward(&listener, &target, &forward));
+    Ok(())
+}
+
+fn serve_forward(listener: &TcpListener, target: &Target, forward: &EmbeddedForward) {
+    while !forward.stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let target = target.clone();
+                let remote_host = forward.remote_host.clone();
+                let remote_port = forward.remote_port;
+                std::thread::spawn(move || {
+                    let _ = tunnel(stream, &target, &remote_host, remote_port);
+                });
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+            Err(_) => break,
+        }
+    }
+}
+
+/// Copy bytes between a local client and a `direct-tcpip` channel until either side closes.
+fn tunnel(
+    mut stream: TcpStream,
+    target: &Target,
+    remote_host: &str,
+    remote_port: u16,
+) -> io::Result<()> {
+    let session = connect(target)?;
+    let mut channel = session.channel_direct_tcpip(remote_host, remote_port, None)?;
+    session.set_blocking(false);
+    stream.set_nonblocking(true)?;
+
+    let mut buf = [0u8; 16 * 1024];
+    loop {
+        let mut progressed = false;
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                write_all_polling(&mut channel, &buf[..n])?;
+                progressed = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => break,
+            Ok(0) => {}
+            Ok(n) => {
+                write_all_polling(&mut stream, &buf[..n])?;
+                progressed = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        if !progressed {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+    let _ = channel.send_eof();
+    Ok(())
+}
+
+/// `write_all` for non-blocking writers: retry until everything is written.
+fn write_all_polling(writer: &mut impl Write, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match writer.write(data) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(n) => data = &data[n..],
+            Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(e),
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSH_CONFIG: &str = r"
+# Lab machines
+Host ailab lab-*
+    HostName 10.0.0.5
+    User researcher
+    Port 2222
+    IdentityFile ~/.ssh/id_lab
+
+Host lab-gpu
+    HostName 10.0.0.9
+
+Host * !bastion
+    User fallback
+    IdentityFile=~/.ssh/id_default
+
+Match host prod
+    User ignored
+";
+
+    #[test]
+    fn test_parse_ssh_config_first_value_wins() {
+        let config = parse_ssh_config(SSH_CONFIG, "lab-gpu");
+        assert_eq!(config.hostname.as_deref(), Some("10.0.0.5"));
+        assert_eq!(config.user.as_deref(), Some("researcher"));
+        assert_eq!(config.port, Some(2222));
+        assert_eq!(
+            config.identity_files,
+            vec!["~/.ssh/id_lab", "~/.ssh/id_default"]
+        );
+    }
+
+    #[test]
+    fn test_parse_ssh_config_negation_and_match() {
+        let config = parse_ssh_config(SSH_CONFIG, "bastion");
+        assert_eq!(config, HostConfig::default());
+
+        let config = parse_ssh_config(SSH_CONFIG, "prod");
+        assert_eq!(config.user.as_deref(), Some("fallback"));
+        assert!(config.hostname.is_none());
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let target = resolve_target("ailab", SSH_CONFIG, None);
+        assert_eq!(target.user, "researcher");
+        assert_eq!(target.hostname, "10.0.0.5");
+        assert_eq!(target.port, 2222);
+
+        let target = resolve_target("admin@ailab", SSH_CONFIG, Some("/keys/quay"));
+        assert_eq!(target.user, "admin");
+        assert_eq!(target.identity_files[0], PathBuf::from("/keys/quay"));
+        assert!(
+            target
+                .identity_files
+                .contains(&expand_home("~/.ssh/id_ed25519"))
+        );
+
+        let target = resolve_target("user@plain.example", "", None);
+        assert_eq!(target.user, "user");
+        assert_eq!(target.hostname, "plain.example");
+        assert_eq!(target.port, 22);
+    }
+
+    #[test]
+    fn test_start_forward_rejects_bad_spec() {
+        assert!(start_forward("8080", "host").is_err());
+        assert!(start_forward("x:localhost:80", "host").is_err());
+    }
+
+    #[test]
+    fn test_stop_unknown_forward() {
+        assert!(!stop_forward(1));
+    }
+}
@@ -0,0 +1,236 @@
+//! Established TCP connections, for telling used ports from idle ones.
+//!
+//! lsof lists them on Linux and macOS; hosts without lsof fall back to ss.
+//! Each connection is seen from both ends when both are on the host, so
+//! callers count by local port: a connection to `:5432` shows up once with
+//! local port 5432 (the server's socket) and once with an ephemeral one.
+//!
+//! Closed connections still waiting in `TIME_WAIT` are counted too; ss lists
+//! them on Linux and netstat elsewhere, as they no longer have a process.
+
+use super::listeners::output;
+use anyhow::Context;
+use std::collections::HashMap;
+use std::process::Output;
+
+const LSOF_ARGS: [&str; 5] = ["lsof", "-nP", "-iTCP", "-sTCP:ESTABLISHED", "-Fpcn"];
+const SS_ARGS: [&str; 4] = ["ss", "-Htnp", "state", "established"];
+const SS_TIME_WAIT_ARGS: [&str; 4] = ["ss", "-Htn", "state", "time-wait"];
+const NETSTAT_ARGS: [&str; 2] = ["netstat", "-an"];
+
+/// One established TCP connection, from the side of the socket's owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Established {
+    pub local_port: u16,
+    /// `addr:port` of the other end.
+    pub peer: String,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// Established connections on `host` (this machine when `None`). Without
+/// root, lsof only sees the current user's sockets.
+pub async fn collect(host: Option<&str>) -> anyhow::Result<Vec<Established>> {
+    match output(&LSOF_ARGS, host).await {
+        Ok(out) if !is_missing(&out) => {
+            // lsof exits 1 when there is nothing to list
+            return Ok(parse_lsof(&String::from_utf8_lossy(&out.stdout)));
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(anyhow::Error::new(e).context("Could not run lsof"));
+        }
+        _ => {}
+    }
+    let out = output(&SS_ARGS, host)
+        .await
+        .context("Could not run lsof or ss")?;
+    if is_missing(&out) {
+        anyhow::bail!("Neither lsof nor ss is available");
+    }
+    Ok(parse_ss(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Connections in `TIME_WAIT` per local port on `host` (this machine when
+/// `None`).
+pub async fn time_wait_counts(host: Option<&str>) -> anyhow::Result<HashMap<u16, usize>> {
+    match output(&SS_TIME_WAIT_ARGS, host).await {
+        Ok(out) if !is_missing(&out) => {
+            return Ok(counts(&parse_ss(&String::from_utf8_lossy(&out.stdout))));
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(anyhow::Error::new(e).context("Could not run ss"));
+        }
+        _ => {}
+    }
+    let out = output(&NETSTAT_ARGS, host)
+        .await
+        .context("Could not run ss or netstat")?;
+    if is_missing(&out) {
+        anyhow::bail!("Neither ss nor netstat is available");
+    }
+    Ok(parse_netstat_time_wait(&String::from_utf8_lossy(
+        &out.stdout,
+    )))
+}
+
+/// Whether the program wasn't found on a remote host (the shell's exit code).
+fn is_missing(output: &Output) -> bool {
+    output.status.code() == Some(127)
+}
+
+/// Number of connections per local port.
+pub fn counts(connections: &[Established]) -> HashMap<u16, usize> {
+    let mut counts = HashMap::new();
+    for connection in connections {
+        *counts.entry(connection.local_port).or_default() += 1;
+    }
+    counts
+}
+
+/// Split `local->peer` and return the local port and the peer.
+fn split_pair(name: &str) -> Option<(u16, &str)> {
+    let (local, peer) = name.split_once("->")?;
+    let port = local.rsplit_once(':')?.1.parse().ok()?;
+    Some((port, peer))
+}
+
+/// Parse `lsof -Fpcn` output for established sockets.
+///
+/// ```text
+/// p812
+/// cssh
+/// n127.0.0.1:5432->127.0.0.1:51234
+/// ```
+pub fn parse_lsof(output: &str) -> Vec<Established> {
+    let mut connections = Vec::new();
+    let mut pid = None;
+    let mut process = None;
+    for line in output.lines() {
+        let Some(field) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[field.len_utf8()..];
+        match field {
+            'p' => {
+                pid = value.parse().ok();
+                process = None;
+            }
+            'c' => process = Some(value.to_string()),
+            'n' => {
+                if let Some((local_port, peer)) = split_pair(value) {
+                    connections.push(Established {
+                        local_port,
+                        peer: peer.to_string(),
+                        pid,
+                        process: process.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    connections
+}
+
+/// Parse `ss -Htnp state established` output.
+///
+/// ```text
+/// 0      0      127.0.0.1:5432   127.0.0.1:51234 users:(("ssh",pid=812,fd=5))
+/// ```
+pub fn parse_ss(output: &str) -> Vec<Established> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(2)?.rsplit_once(':')?.1.parse().ok()?;
+            let users = fields.get(4..).map(|rest| rest.join(" "));
+            let users = users.as_deref().unwrap_or_default();
+            Some(Established {
+                local_port,
+                peer: (*fields.get(3)?).to_string(),
+                pid: users
+                    .split_once("pid=")
+                    .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+                    .and_then(|pid| pid.parse().ok()),
+                process: users
+                    .split_once("((\"")
+                    .and_then(|(_, rest)| rest.split_once('"'))
+                    .map(|(name, _)| name.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Count the `TIME_WAIT` lines of `netstat -an` by local port, which
+/// follows the last `:` on Linux and the last `.` on macOS.
+///
+/// ```text
+/// tcp4       0      0  127.0.0.1.3000         127.0.0.1.51234        TIME_WAIT
+/// ```
+pub fn parse_netstat_time_wait(output: &str) -> HashMap<u16, usize> {
+    let mut counts = HashMap::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.last() != Some(&"TIME_WAIT") {
+            continue;
+        }
+        let port = fields
+            .get(3)
+            .and_then(|local| local.rsplit([':', '.']).next())
+            .and_then(|port| port.parse::<u16>().ok());
+        if let Some(port) = port {
+            *counts.entry(port).or_default() += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lsof() {
+        let output = "p812\ncssh\nn127.0.0.1:5432->127.0.0.1:51234\nn[::1]:5432->[::1]:51240\n\
+                      p900\ncpsql\nn127.0.0.1:51234->127.0.0.1:5432\n";
+        let connections = parse_lsof(output);
+        assert_eq!(connections.len(), 3);
+        assert_eq!(connections[0].local_port, 5432);
+        assert_eq!(connections[0].peer, "127.0.0.1:51234");
+        assert_eq!(connections[0].pid, Some(812));
+        assert_eq!(connections[1].peer, "[::1]:51240");
+        assert_eq!(connections[2].process.as_deref(), Some("psql"));
+
+        let counts = counts(&connections);
+        assert_eq!(counts.get(&5432), Some(&2));
+        assert_eq!(counts.get(&51234), Some(&1));
+    }
+
+    #[test]
+    fn test_parse_ss() {
+        let output = "0 0 127.0.0.1:5432 127.0.0.1:51234 users:((\"ssh\",pid=812,fd=5))\n\
+                      0 0 [::ffff:10.0.0.2]:22 [::ffff:10.0.0.9]:60000\n";
+        let connections = parse_ss(output);
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].pid, Some(812));
+        assert_eq!(connections[0].process.as_deref(), Some("ssh"));
+        assert_eq!(connections[1].local_port, 22);
+        assert_eq!(connections[1].pid, None);
+    }
+
+    #[test]
+    fn test_parse_netstat_time_wait() {
+        let output = "\
+Active Internet connections (including servers)
+Proto Recv-Q Send-Q  Local Address          Foreign Address        (state)
+tcp4       0      0  127.0.0.1.3000         127.0.0.1.51234        TIME_WAIT
+tcp4       0      0  127.0.0.1.3000         127.0.0.1.51236        TIME_WAIT
+tcp        0      0 0.0.0.0:8080            10.0.0.9:60000         TIME_WAIT
+tcp4       0      0  *.3000                 *.*                    LISTEN
+";
+        let counts = parse_netstat_time_wait(output);
+        assert_eq!(counts.get(&3000), Some(&2));
+        assert_eq!(counts.get(&8080), Some(&1));
+        assert_eq!(counts.len(), 2);
+    }
+}
@@ -0,0 +1,178 @@
+//! Raw data behind an entry, for the Details popup.
+//!
+//! Runs the underlying tools again, narrowed to one port, so advanced users
+//! can see the full lsof/ss line, bind addresses, socket state, and the
+//! container's port bindings without leaving the TUI.
+
+use super::listeners::{self, Collector};
+use super::{PortEntry, PortSource, docker, escape_ssh_args, remote_output};
+use tokio::process::Command;
+
+/// Output of one command run for the Details popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawSection {
+    /// The command line as run, shown as the section heading.
+    pub command: String,
+    /// Output lines, or the error when the command failed.
+    pub lines: Vec<String>,
+}
+
+/// Run the commands describing `entry`.
+///
+/// SSH tunnels are inspected on this machine; other entries on `remote_host`
+/// (or inside `docker_target`) where they were collected.
+pub async fn inspect(
+    entry: &PortEntry,
+    remote_host: Option<&str>,
+    docker_target: Option<&str>,
+) -> Vec<RawSection> {
+    let host = if entry.source == PortSource::Ssh {
+        None
+    } else {
+        remote_host
+    };
+    let port = entry.local_port.to_string();
+    let sport = format!(":{port}");
+    let mut sections = Vec::new();
+
+    if let Some(target) = docker_target {
+        if entry.collector == Some(Collector::HostProcNet) {
+            // Distroless images have no tools; read the socket tables from the host
+            sections.extend(match listeners::container_pid(target, host).await {
+                Ok(pid) => {
+                    let words = listeners::host_proc_net_command(pid);
+                    let args: Vec<&str> = words[1..].iter().map(String::as_str).collect();
+                    run(&words[0], &args, host, true).await
+                }
+                Err(e) => Some(RawSection {
+                    command: format!("docker inspect {target}"),
+                    lines: vec![format!("{e:#}")],
+                }),
+            });
+        } else {
+            let mut args = vec!["exec", target];
+            match entry.collector {
+                // Images without ss were collected with a fallback; show its full output
+                Some(collector) if collector != Collector::Ss => args.extend(collector.command()),
+                _ => args.extend(["ss", "-tlnp", "sport", "=", &sport]),
+            }
+            sections.extend(run_docker(&args, host).await);
+        }
+        // Container processes are listed with their host PIDs
+        if let Some(pid) = entry.pid {
+            sections.extend(ps(pid, host).await);
+        }
+        return sections;
+    }
+
+    if entry.source == PortSource::Docker {
+        let container = entry
+            .container_id
+            .as_deref()
+            .or(entry.container_name.as_deref())
+            .unwrap_or_default();
+        let args = ["inspect", "--format", DOCKER_INSPECT_FORMAT, container];
+        if let Some(mut section) = run_docker(&args, host).await {
+            section.lines = section.lines.iter().flat_map(|l| pretty_json(l)).collect();
+            sections.push(section);
+        }
+    }
+
+    let tcp = format!("-iTCP:{port}");
+    // Without lsof the entry came from a fallback; don't report lsof missing again
+    let lsof_required = matches!(entry.collector, None | Some(Collector::Lsof));
+    sections.extend(run("lsof", &["-nP", &tcp, "-sTCP:LISTEN"], host, lsof_required).await);
+    // ss is Linux-only; leave it out quietly elsewhere
+    sections.extend(run("ss", &["-tlnp", "sport", "=", &sport], host, false).await);
+    if let Some(pid) = entry.pid.filter(|_| entry.source != PortSource::Docker) {
+        sections.extend(ps(pid, host).await);
+    }
+    sections
+}
+
+/// `ps` line of `pid`.
+async fn ps(pid: u32, host: Option<&str>) -> Option<RawSection> {
+    let pid = pid.to_string();
+    let args = ["-ww", "-o", "pid,user,etime,args", "-p", &pid];
+    run("ps", &args, host, true).await
+}
+
+/// Image, state, and port bindings of a container, one item per line.
+const DOCKER_INSPECT_FORMAT: &str = "image: {{.Config.Image}}\nstate: {{.State.Status}} since {{.State.StartedAt}}\n{{json .NetworkSettings.Ports}}";
+
+/// Pretty-print a line of JSON; other lines are kept as they are.
+fn pretty_json(line: &str) -> Vec<String> {
+    serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .filter(|v| v.is_object() || v.is_array())
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .map_or_else(
+            || vec![line.to_string()],
+            |pretty| pretty.lines().map(str::to_string).collect(),
+        )
+}
+
+/// Run `docker <args>` in the Docker context, like [`run`] with `required`.
+async fn run_docker(args: &[&str], host: Option<&str>) -> Option<RawSection> {
+    let words = docker::command(args);
+    let args: Vec<&str> = words[1..].iter().map(String::as_str).collect();
+    run(&words[0], &args, host, true).await
+}
+
+/// Run `program` locally or on `host`. A failed command yields its error
+/// when `required`, and is left out otherwise.
+async fn run(
+    program: &str,
+    args: &[&str],
+    host: Option<&str>,
+    required: bool,
+) -> Option<RawSection> {
+    let mut words = vec![program];
+    words.extend_from_slice(args);
+    let command = match host {
+        Some(host) => format!("ssh {host} {}", escape_ssh_args(&words)),
+        None => escape_ssh_args(&words),
+    };
+    let output = match host {
+        Some(host) => remote_output(host, &words).await,
+        None => Command::new(program).args(args).output().await,
+    };
+    let lines = match output {
+        Ok(output) if output.status.success() => output_lines(&output.stdout),
+        Ok(_) | Err(_) if !required => return None,
+        Ok(output) => {
+            let mut lines = output_lines(&output.stderr);
+            if lines.is_empty() {
+                lines.push(match output.status.code() {
+                    Some(code) => format!("(exit code {code}, no output)"),
+                    None => "(killed by a signal)".to_string(),
+                });
+            }
+            lines
+        }
+        Err(e) => vec![format!("(could not run: {e})")],
+    };
+    Some(RawSection { command, lines })
+}
+
+fn output_lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .trim_end()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_json() {
+        let lines = pretty_json(r#"{"80/tcp":[{"HostIp":"0.0.0.0","HostPort":"8080"}]}"#);
+        assert_eq!(lines[0], "{");
+        assert!(lines.iter().any(|l| l.trim() == r#""HostPort": "8080""#));
+        assert_eq!(pretty_json("state: running"), vec!["state: running"]);
+        assert_eq!(pretty_json("null"), vec!["null"]);
+    }
+}
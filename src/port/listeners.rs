@@ -0,0 +1,695 @@
+//! Listening TCP sockets from whichever tool a host or container has.
+//!
+//! Minimal remote hosts often lack lsof, and containers often lack ss, so each
+//! collection tries a chain of [`Collector`]s and uses the first one that is
+//! installed. Next to last reads `/proc/net/tcp` with `cat`, which every
+//! Linux userland has; distroless images don't even have that, so containers
+//! finally read `/proc/<pid>/net/tcp` of their main process from the host.
+//!
+//! On macOS, `lsof -i` walks every file of every process and can take seconds
+//! on a busy machine, so this machine is listed with `netstat -anv` first,
+//! which reads the kernel's socket table in one go.
+
+use super::{CommandError, docker, remote_output};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::process::Output;
+use tokio::process::Command;
+
+/// A tool that lists listening sockets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Collector {
+    Lsof,
+    Ss,
+    Netstat,
+    /// macOS `netstat -anv`, which prints the owning PID of each socket.
+    NetstatMacos,
+    ProcNet,
+    /// `/proc/<pid>/net/tcp` of a container's main process, read from the host.
+    HostProcNet,
+}
+
+impl fmt::Display for Collector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Collector::Lsof => "lsof",
+            Collector::Ss => "ss",
+            Collector::Netstat => "netstat",
+            Collector::NetstatMacos => "netstat -anv",
+            Collector::ProcNet => "/proc/net/tcp",
+            Collector::HostProcNet => "/proc/<pid>/net/tcp",
+        };
+        f.pad(name)
+    }
+}
+
+impl Collector {
+    /// Fallback order on a host.
+    pub const HOST_CHAIN: [Collector; 4] = [
+        Collector::Lsof,
+        Collector::Ss,
+        Collector::Netstat,
+        Collector::ProcNet,
+    ];
+    /// Fallback order on this machine when it runs macOS.
+    pub const MACOS_CHAIN: [Collector; 2] = [Collector::NetstatMacos, Collector::Lsof];
+    /// Fallback order inside a container, where lsof is rarely installed.
+    pub const CONTAINER_CHAIN: [Collector; 4] = [
+        Collector::Ss,
+        Collector::Netstat,
+        Collector::ProcNet,
+        Collector::HostProcNet,
+    ];
+
+    /// Fallback order on `host` (this machine when `None`).
+    pub fn host_chain(host: Option<&str>) -> &'static [Collector] {
+        if host.is_none() && cfg!(target_os = "macos") {
+            &Self::MACOS_CHAIN
+        } else {
+            &Self::HOST_CHAIN
+        }
+    }
+
+    /// Command line, program first. [`Collector::HostProcNet`] needs the
+    /// container's PID; see [`host_proc_net_command`].
+    pub fn command(self) -> &'static [&'static str] {
+        match self {
+            Collector::Lsof => &["lsof", "-iTCP", "-P", "-n", "-sTCP:LISTEN", "-FcpLtPn"],
+            Collector::Ss => &["ss", "-tlnp"],
+            Collector::Netstat => &["netstat", "-tlnp"],
+            Collector::NetstatMacos => &["netstat", "-anv", "-p", "tcp"],
+            Collector::ProcNet | Collector::HostProcNet => {
+                &["cat", "/proc/net/tcp", "/proc/net/tcp6"]
+            }
+        }
+    }
+
+    /// Whether `output` holds a usable listing.
+    fn succeeded(self, output: &Output) -> bool {
+        match self {
+            // lsof exits 1 without output when nothing is listening; only stderr means failure
+            Collector::Lsof => {
+                output.status.success() || output.stderr.iter().all(u8::is_ascii_whitespace)
+            }
+            // /proc/net/tcp6 is missing when IPv6 is disabled
+            Collector::ProcNet | Collector::HostProcNet => !output.stdout.is_empty(),
+            Collector::Ss | Collector::Netstat | Collector::NetstatMacos => output.status.success(),
+        }
+    }
+}
+
+/// Where to run a collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// This machine, or the host over SSH.
+    Host(Option<&'a str>),
+    /// Inside a container, on this machine or on the host over SSH.
+    Container(&'a str, Option<&'a str>),
+}
+
+impl<'a> Target<'a> {
+    /// Words that run `words` on the target, and the SSH host to run them on.
+    pub(super) fn wrap(&self, words: &[&str]) -> (Vec<String>, Option<&'a str>) {
+        match *self {
+            Target::Host(host) => (words.iter().map(|w| (*w).to_string()).collect(), host),
+            Target::Container(container, host) => {
+                let mut wrapped = vec!["exec", container];
+                wrapped.extend_from_slice(words);
+                (docker::command(&wrapped), host)
+            }
+        }
+    }
+}
+
+/// `cat` of the socket tables in the network namespace of host process `pid`.
+pub fn host_proc_net_command(pid: u32) -> Vec<String> {
+    vec![
+        "cat".to_string(),
+        format!("/proc/{pid}/net/tcp"),
+        format!("/proc/{pid}/net/tcp6"),
+    ]
+}
+
+/// Run `words` here or on `host`.
+pub(super) async fn output(words: &[&str], host: Option<&str>) -> std::io::Result<Output> {
+    match host {
+        Some(host) => remote_output(host, words).await,
+        None => Command::new(words[0]).args(&words[1..]).output().await,
+    }
+}
+
+/// Host PID of the main process of `container`, from `docker inspect`.
+pub async fn container_pid(container: &str, host: Option<&str>) -> anyhow::Result<u32> {
+    let args = ["inspect", "--format", "{{.State.Pid}}", container];
+    let output = docker::run(&args, host)
+        .await
+        .context("Could not run docker")?;
+    if !output.status.success() {
+        return Err(CommandError::new("docker", &args, host, &output).into());
+    }
+    parse_container_pid(container, &String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the `{{.State.Pid}}` of `container`; stopped containers report 0.
+fn parse_container_pid(container: &str, output: &str) -> anyhow::Result<u32> {
+    match output.trim().parse() {
+        Ok(0) => anyhow::bail!("Container '{container}' is not running"),
+        Ok(pid) => Ok(pid),
+        Err(_) => anyhow::bail!(
+            "Unexpected PID '{}' for container '{container}'",
+            output.trim()
+        ),
+    }
+}
+
+/// Command running `collector` on `target`, and the SSH host to run it on;
+/// `None` when the collector doesn't apply to the target.
+async fn command_for(
+    collector: Collector,
+    target: Target<'_>,
+) -> anyhow::Result<Option<(Vec<String>, Option<&str>)>> {
+    Ok(match (collector, target) {
+        (Collector::HostProcNet, Target::Container(container, host)) => {
+            let pid = container_pid(container, host).await?;
+            Some((host_proc_net_command(pid), host))
+        }
+        (Collector::HostProcNet, Target::Host(_)) => None,
+        _ => Some(target.wrap(collector.command())),
+    })
+}
+
+/// Output of the first collector of `chain` installed on `target`.
+///
+/// A collector counts as missing when its program can't be found (exit code
+/// 127 from a shell or `docker exec`); any other failure stops the chain, so an
+/// unreachable host isn't asked four times.
+pub async fn collect(
+    target: Target<'_>,
+    chain: &[Collector],
+) -> anyhow::Result<(Collector, String)> {
+    for &collector in chain {
+        let Some((words, host)) = command_for(collector, target).await? else {
+            continue;
+        };
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let output = match output(&words, host).await {
+            // Only the collector itself may be missing; a missing docker is an error
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && target == Target::Host(None) => {
+                tracing::debug!(%collector, "collector not installed");
+                continue;
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e).context(format!("Could not run {}", words[0])));
+            }
+            Ok(output) => output,
+        };
+        if collector.succeeded(&output) {
+            tracing::debug!(?target, %collector, "collected listeners");
+            return Ok((
+                collector,
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+            ));
+        }
+        if is_not_found(&output) {
+            tracing::debug!(?target, %collector, "collector not installed");
+            continue;
+        }
+        return Err(CommandError::new(words[0], &words[1..], host, &output).into());
+    }
+    let names: Vec<String> = chain.iter().map(ToString::to_string).collect();
+    anyhow::bail!("None of {} is available", names.join(", "))
+}
+
+/// Whether a command failed because its program isn't installed.
+fn is_not_found(output: &Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match output.status.code() {
+        Some(127) => true,
+        // Older docker reports a missing executable as 126
+        Some(126) => stderr.contains("not found"),
+        _ => false,
+    }
+}
+
+/// One listening socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listener {
+    pub port: u16,
+    /// Bind address as printed by the tool, e.g. `0.0.0.0`, `*`, or `[::1]`.
+    pub bind: String,
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+}
+
+impl Listener {
+    /// Bound to a loopback address only.
+    pub fn is_loopback(&self) -> bool {
+        is_loopback_bind(&self.bind)
+    }
+}
+
+/// Whether bind address `bind` (IPv6 in brackets or not) is a loopback address.
+pub fn is_loopback_bind(bind: &str) -> bool {
+    let bind = bind.trim_start_matches('[').trim_end_matches(']');
+    let bind = bind.split('%').next().unwrap_or(bind);
+    bind == "::1" || bind.starts_with("127.")
+}
+
+/// `bind` with the any-address spellings (`0.0.0.0`, `::`, `[::]`) as `*`, the
+/// way lsof prints them, so IPv4 and IPv6 wildcard sockets compare equal.
+pub fn normalize_bind(bind: &str) -> String {
+    match bind {
+        "0.0.0.0" | "::" | "[::]" | "*" | "" => "*".to_string(),
+        _ => bind.to_string(),
+    }
+}
+
+/// Split `addr:port` (IPv6 in brackets or not) at the last colon.
+fn split_addr(addr: &str) -> Option<(&str, u16)> {
+    let (bind, port) = addr.rsplit_once(':')?;
+    match port.parse() {
+        Ok(0) | Err(_) => None,
+        Ok(port) => Some((bind, port)),
+    }
+}
+
+/// Split an lsof internet address (`*:3000`, `127.0.0.1:8080`, `[::1]:80`,
+/// `[fe80::1%en0]:8080`) into its bind address, scope ID included, and port.
+///
+/// `None` for anything else lsof may print as a name: UNIX socket paths,
+/// `->0x...` socket pairs, connected `local->peer` sockets, and `*:*`.
+pub fn split_lsof_address(name: &str) -> Option<(&str, u16)> {
+    if name.contains("->") {
+        return None;
+    }
+    let (bind, port) = name.rsplit_once(':')?;
+    let port = port.parse().ok().filter(|&p| p > 0)?;
+    let valid = match bind.strip_prefix('[').and_then(|b| b.strip_suffix(']')) {
+        Some(v6) => v6
+            .split('%')
+            .next()
+            .is_some_and(|addr| addr.parse::<Ipv6Addr>().is_ok()),
+        None => bind == "*" || bind.parse::<Ipv4Addr>().is_ok(),
+    };
+    valid.then_some((bind, port))
+}
+
+/// Parse `ss -tlnp` output.
+///
+/// ```text
+/// State  Recv-Q Send-Q Local Address:Port Peer Address:Port Process
+/// LISTEN 0      511          0.0.0.0:3000      0.0.0.0:*     users:(("node",pid=1234,fd=20))
+/// LISTEN 0      128        127.0.0.1:5432      0.0.0.0:*
+/// ```
+pub fn parse_ss(output: &str) -> Vec<Listener> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.first() != Some(&"LISTEN") || fields.len() < 4 {
+                return None;
+            }
+            let (bind, port) = split_addr(fields[3])?;
+            // Taken from the line itself: the name may contain runs of spaces
+            let users = line.find("users:((").map_or("", |i| &line[i..]);
+            // The PID follows the name, which may itself contain "pid="
+            let (process, rest) = match users
+                .split_once("((\"")
+                .and_then(|(_, rest)| rest.split_once('"'))
+            {
+                Some((name, rest)) => (Some(name.to_string()), rest),
+                None => (None, users),
+            };
+            let pid = rest
+                .split_once("pid=")
+                .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+                .and_then(|pid| pid.parse().ok());
+            Some(Listener {
+                port,
+                bind: bind.to_string(),
+                process,
+                pid,
+            })
+        })
+        .collect()
+}
+
+/// Parse `netstat -tlnp` output (net-tools and busybox).
+///
+/// ```text
+/// Proto Recv-Q Send-Q Local Address  Foreign Address State  PID/Program name
+/// tcp        0      0 0.0.0.0:22     0.0.0.0:*       LISTEN 812/sshd
+/// tcp6       0      0 :::80          :::*            LISTEN -
+/// ```
+pub fn parse_netstat(output: &str) -> Vec<Listener> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if !fields.first()?.starts_with("tcp") || fields.get(5) != Some(&"LISTEN") {
+                return None;
+            }
+            let (bind, port) = split_addr(fields[3])?;
+            let (pid, process) = fields
+                .get(6)
+                .and_then(|owner| owner.split_once('/'))
+                .map_or((None, None), |(pid, name)| {
+                    (pid.parse().ok(), Some(name.to_string()))
+                });
+            Some(Listener {
+                port,
+                bind: bind.to_string(),
+                process,
+                pid,
+            })
+        })
+        .collect()
+}
+
+/// Parse macOS `netstat -anv -p tcp` output.
+///
+/// ```text
+/// Proto Recv-Q Send-Q  Local Address   Foreign Address (state)  rhiwat shiwat   pid epid ...
+/// tcp4       0      0  127.0.0.1.5432  *.*             LISTEN   131072 131072   812    0 ...
+/// tcp46      0      0  *.3000          *.*             LISTEN   131072 131072  4242    0 ...
+/// ```
+///
+/// Ports follow the address after a dot. The owner column is found by its
+/// header: `pid` (no process name), or `process:pid` since macOS 14, where
+/// the name may contain spaces.
+pub fn parse_netstat_macos(output: &str) -> Vec<Listener> {
+    let mut owner_column = None;
+    let mut listeners = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.first() == Some(&"Proto") {
+            // "Local Address" and "Foreign Address" are two words each
+            owner_column = fields
+                .iter()
+                .position(|f| *f == "pid" || *f == "process:pid")
+                .map(|i| (i.saturating_sub(2), fields[i] == "process:pid"));
+            continue;
+        }
+        if !fields.first().is_some_and(|f| f.starts_with("tcp")) || fields.get(5) != Some(&"LISTEN")
+        {
+            continue;
+        }
+        let Some((bind, port)) = split_dot_addr(fields[3]) else {
+            continue;
+        };
+        let (process, pid) = match owner_column {
+            Some((column, true)) => owner_with_name(fields.get(column..).unwrap_or_default()),
+            Some((column, false)) => (None, fields.get(column).and_then(|p| p.parse().ok())),
+            None => (None, None),
+        };
+        listeners.push(Listener {
+            port,
+            bind,
+            process,
+            pid,
+        });
+    }
+    listeners
+}
+
+/// Split macOS netstat's `addr.port`, bracketing IPv6 addresses like lsof.
+fn split_dot_addr(addr: &str) -> Option<(String, u16)> {
+    let (bind, port) = addr.rsplit_once('.')?;
+    let port = port.parse().ok().filter(|&p| p > 0)?;
+    let bind = if bind.contains(':') {
+        format!("[{bind}]")
+    } else {
+        bind.to_string()
+    };
+    Some((bind, port))
+}
+
+/// `name:pid` from the start of `fields`, rejoining a name split at spaces.
+fn owner_with_name(fields: &[&str]) -> (Option<String>, Option<u32>) {
+    for end in 1..=fields.len().min(8) {
+        let owner = fields[..end].join(" ");
+        if let Some((name, pid)) = owner.rsplit_once(':') {
+            if let Ok(pid) = pid.parse() {
+                return (Some(name.to_string()).filter(|n| !n.is_empty()), Some(pid));
+            }
+        }
+    }
+    (None, None)
+}
+
+/// Parse the contents of `/proc/net/tcp` and `/proc/net/tcp6`.
+///
+/// ```text
+///   sl  local_address rem_address   st tx_queue rx_queue ...
+///    0: 0100007F:1538 00000000:0000 0A 00000000:00000000 ...
+/// ```
+///
+/// Addresses are hex words in host byte order (little-endian on every
+/// platform quay runs on); state `0A` is LISTEN. Owners aren't listed.
+pub fn parse_proc_net_tcp(output: &str) -> Vec<Listener> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&"0A") {
+                return None;
+            }
+            let (addr, port) = fields[1].split_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok().filter(|&p| p > 0)?;
+            Some(Listener {
+                port,
+                bind: decode_proc_addr(addr)?,
+                process: None,
+                pid: None,
+            })
+        })
+        .collect()
+}
+
+/// `0100007F` as `127.0.0.1`; 32-digit IPv6 addresses in brackets.
+fn decode_proc_addr(hex: &str) -> Option<String> {
+    let words: Vec<u32> = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<_>>()?;
+    match words.as_slice() {
+        [word] if hex.len() == 8 => Some(Ipv4Addr::from(word.to_le_bytes()).to_string()),
+        [_, _, _, _] if hex.len() == 32 => {
+            let mut bytes = [0u8; 16];
+            for (chunk, word) in bytes.chunks_mut(4).zip(&words) {
+                chunk.copy_from_slice(&word.to_le_bytes());
+            }
+            Some(format!("[{}]", Ipv6Addr::from(bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// Parse the output of `collector`; lsof has its own parser in `local`.
+pub fn parse(collector: Collector, output: &str) -> Vec<Listener> {
+    match collector {
+        Collector::Lsof => Vec::new(),
+        Collector::Ss => parse_ss(output),
+        Collector::Netstat => parse_netstat(output),
+        Collector::NetstatMacos => parse_netstat_macos(output),
+        Collector::ProcNet | Collector::HostProcNet => parse_proc_net_tcp(output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lsof_address() {
+        assert_eq!(split_lsof_address("*:3000"), Some(("*", 3000)));
+        assert_eq!(
+            split_lsof_address("127.0.0.1:8080"),
+            Some(("127.0.0.1", 8080))
+        );
+        assert_eq!(split_lsof_address("[::1]:80"), Some(("[::1]", 80)));
+        // Scope IDs by interface name (Linux) and embedded in the address (macOS)
+        assert_eq!(
+            split_lsof_address("[fe80::1%en0]:8080"),
+            Some(("[fe80::1%en0]", 8080))
+        );
+        assert_eq!(
+            split_lsof_address("[fe80:4::1]:8080"),
+            Some(("[fe80:4::1]", 8080))
+        );
+        assert_eq!(split_lsof_address("invalid"), None);
+        assert_eq!(split_lsof_address("*:*"), None);
+        assert_eq!(split_lsof_address("*:0"), None);
+        assert_eq!(split_lsof_address("/tmp/.X11-unix/X0"), None);
+        assert_eq!(split_lsof_address("/run/user/1000/a:8080"), None);
+        assert_eq!(split_lsof_address("->0xabcdef"), None);
+        assert_eq!(split_lsof_address("127.0.0.1:5432->127.0.0.1:51234"), None);
+    }
+
+    #[test]
+    fn test_parse_ss() {
+        let output = "State  Recv-Q Send-Q Local Address:Port Peer Address:Port Process\n\
+            LISTEN 0 511 0.0.0.0:3000 0.0.0.0:* users:((\"node\",pid=1234,fd=20))\n\
+            LISTEN 0 128 [::1]:5432 [::]:*\n";
+        let listeners = parse_ss(output);
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(listeners[0].port, 3000);
+        assert_eq!(listeners[0].process.as_deref(), Some("node"));
+        assert_eq!(listeners[0].pid, Some(1234));
+        assert!(!listeners[0].is_loopback());
+        assert_eq!(listeners[1].bind, "[::1]");
+        assert!(listeners[1].is_loopback());
+        assert_eq!(listeners[1].pid, None);
+
+        // Names keep their spaces, and a "pid=" inside one isn't the PID
+        let output = "LISTEN 0 128 *:8080 *:* users:((\"Web  pid=9\",pid=77,fd=3))";
+        let listeners = parse_ss(output);
+        assert_eq!(listeners[0].process.as_deref(), Some("Web  pid=9"));
+        assert_eq!(listeners[0].pid, Some(77));
+    }
+
+    #[test]
+    fn test_parse_netstat() {
+        let output = "Active Internet connections (only servers)\n\
+            Proto Recv-Q Send-Q Local Address Foreign Address State PID/Program name\n\
+            tcp 0 0 127.0.0.1:6379 0.0.0.0:* LISTEN 812/redis-server\n\
+            tcp6 0 0 :::80 :::* LISTEN -\n\
+            udp 0 0 0.0.0.0:68 0.0.0.0:* 1/init\n";
+        let listeners = parse_netstat(output);
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(listeners[0].port, 6379);
+        assert_eq!(listeners[0].pid, Some(812));
+        assert_eq!(listeners[0].process.as_deref(), Some("redis-server"));
+        assert!(listeners[0].is_loopback());
+        assert_eq!(listeners[1].port, 80);
+        assert_eq!(listeners[1].bind, "::");
+        assert_eq!(listeners[1].process, None);
+    }
+
+    #[test]
+    fn test_parse_netstat_macos() {
+        let output = "Active Internet connections (including servers)\n\
+            Proto Recv-Q Send-Q  Local Address  Foreign Address  (state)  rhiwat  shiwat  pid  epid  state\n\
+            tcp4 0 0 127.0.0.1.5432 *.* LISTEN 131072 131072 812 0 0x0100\n\
+            tcp46 0 0 *.3000 *.* LISTEN 131072 131072 4242 0 0x0100\n\
+            tcp6 0 0 fe80::1%lo0.8080 *.* LISTEN 131072 131072 77 0 0x0100\n\
+            tcp4 0 0 127.0.0.1.5432 127.0.0.1.51234 ESTABLISHED 131072 131072 812 0 0x0102\n";
+        let listeners = parse_netstat_macos(output);
+        assert_eq!(listeners.len(), 3);
+        assert_eq!(listeners[0].port, 5432);
+        assert_eq!(listeners[0].bind, "127.0.0.1");
+        assert_eq!(listeners[0].pid, Some(812));
+        assert_eq!(listeners[0].process, None);
+        assert_eq!(listeners[1].bind, "*");
+        assert_eq!(listeners[2].bind, "[fe80::1%lo0]");
+        assert!(!listeners[2].is_loopback());
+
+        let sonoma = "Proto Recv-Q Send-Q  Local Address  Foreign Address  (state)  rxbytes  txbytes  rhiwat  shiwat  process:pid  state\n\
+            tcp4 0 0 *.7000 *.* LISTEN 0 0 131072 131072 ControlCenter:612 0x0100\n\
+            tcp6 0 0 ::1.9229 *.* LISTEN 0 0 131072 131072 Code Helper (Plugin):915 0x0100\n";
+        let listeners = parse_netstat_macos(sonoma);
+        assert_eq!(listeners[0].process.as_deref(), Some("ControlCenter"));
+        assert_eq!(listeners[0].pid, Some(612));
+        assert_eq!(
+            listeners[1].process.as_deref(),
+            Some("Code Helper (Plugin)")
+        );
+        assert_eq!(listeners[1].pid, Some(915));
+        assert!(listeners[1].is_loopback());
+    }
+
+    #[test]
+    fn test_parse_proc_net_tcp() {
+        let output = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+            0: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000 999 0 12345\n\
+            1: 00000000:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000 0 0 12346\n\
+            2: 0100007F:0BB8 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000 0 0 0\n\
+            0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 0\n";
+        let listeners = parse_proc_net_tcp(output);
+        assert_eq!(listeners.len(), 3);
+        assert_eq!(listeners[0].port, 5432);
+        assert_eq!(listeners[0].bind, "127.0.0.1");
+        assert!(listeners[0].is_loopback());
+        assert_eq!(listeners[1].port, 3000);
+        assert_eq!(listeners[1].bind, "0.0.0.0");
+        assert_eq!(listeners[2].port, 8080);
+        assert_eq!(listeners[2].bind, "[::1]");
+        assert!(listeners[2].is_loopback());
+    }
+
+    #[test]
+    fn test_target_wrap() {
+        let (words, host) = Target::Container("api", Some("lab")).wrap(Collector::Ss.command());
+        assert_eq!(words, ["docker", "exec", "api", "ss", "-tlnp"]);
+        assert_eq!(host, Some("lab"));
+        assert_eq!(Collector::ProcNet.to_string(), "/proc/net/tcp");
+    }
+
+    #[test]
+    fn test_normalize_bind() {
+        assert_eq!(normalize_bind("0.0.0.0"), "*");
+        assert_eq!(normalize_bind("[::]"), "*");
+        assert_eq!(normalize_bind("::"), "*");
+        assert_eq!(normalize_bind("127.0.0.1"), "127.0.0.1");
+        assert!(is_loopback_bind("[::1]"));
+        assert!(is_loopback_bind("127.0.0.53%lo"));
+        assert!(!is_loopback_bind("*"));
+    }
+
+    #[test]
+    fn test_host_proc_net() {
+        assert_eq!(
+            host_proc_net_command(4242),
+            vec!["cat", "/proc/4242/net/tcp", "/proc/4242/net/tcp6"]
+        );
+        assert_eq!(parse_container_pid("api", "4242\n").unwrap(), 4242);
+        let err = parse_container_pid("api", "0\n").unwrap_err().to_string();
+        assert_eq!(err, "Container 'api' is not running");
+        assert!(parse_container_pid("api", "<no value>").is_err());
+    }
+
+    mod props {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn prop_parse_ss_never_panics(
+                lines in prop::collection::vec(
+                    prop_oneof![
+                        "LISTEN +[0-9]+ +[0-9]+ +\\PC{0,24}:\\PC{0,6} +\\PC{0,12} +users:\\(\\(\\PC{0,30}",
+                        "\\PC{0,60}",
+                    ],
+                    0..12,
+                ),
+            ) {
+                parse_ss(&lines.join("\n"));
+            }
+
+            #[test]
+            fn prop_parse_ss_keeps_odd_names(
+                name in "[a-zA-Z0-9 :._/()éü日本=-]{1,24}",
+                pid in 1u32..,
+                port in 1u16..,
+                bind in prop_oneof!["0\\.0\\.0\\.0", "127\\.0\\.0\\.1", "\\[::1\\]", "\\*"],
+            ) {
+                let output = format!(
+                    "State  Recv-Q Send-Q Local Address:Port Peer Address:Port Process\n\
+                     LISTEN 0      511    {bind}:{port}      0.0.0.0:*     users:((\"{name}\",pid={pid},fd=20))\n"
+                );
+                let listeners = parse_ss(&output);
+                prop_assert_eq!(
+                    listeners,
+                    vec![Listener {
+                        port,
+                        bind,
+                        process: Some(name),
+                        pid: Some(pid),
+                    }]
+                );
+            }
+        }
+    }
+}
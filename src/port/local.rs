@@ -1,63 +1,120 @@
-use super::{PortEntry, PortSource, ssh_cmd_tokio};
+use super::listeners::{self, Collector, Listener, Target};
+use super::{PortEntry, PortSource, dedup_listeners};
 use anyhow::Result;
-use tokio::process::Command;
+use std::collections::HashMap;
 
+/// Listening ports on this machine or `remote_host`, from lsof or the first
+/// fallback that is installed (see [`listeners`]).
 pub async fn collect(remote_host: Option<&str>) -> Result<Vec<PortEntry>> {
-    let output = match remote_host {
-        Some(host) => {
-            ssh_cmd_tokio(host, &["lsof", "-i", "-P", "-n", "-sTCP:LISTEN", "-Fcpn"])
-                .output()
-                .await?
-        }
-        None => {
-            Command::new("lsof")
-                .args(["-i", "-P", "-n", "-sTCP:LISTEN", "-Fcpn"])
-                .output()
-                .await?
+    let chain = Collector::host_chain(remote_host);
+    let (collector, stdout) = listeners::collect(Target::Host(remote_host), chain)
+        .await
+        .map_err(|e| {
+            e.context(match remote_host {
+                Some(host) => format!("Port scan on {host} failed"),
+                None => "Port scan failed".to_string(),
+            })
+        })?;
+
+    let mut entries = parse(collector, &stdout, remote_host.is_some());
+    if collector == Collector::NetstatMacos {
+        if let Some(owners) = owners_output(&entries).await {
+            apply_owners(&mut entries, &owners);
         }
+    }
+    tracing::debug!(remote_host, %collector, count = entries.len(), "parsed listeners");
+    Ok(entries)
+}
+
+/// Entries for the `output` of `collector`.
+pub(super) fn parse(collector: Collector, output: &str, remote_mode: bool) -> Vec<PortEntry> {
+    let mut entries = match collector {
+        Collector::Lsof => parse_lsof_fields(output, remote_mode),
+        _ => listener_entries(listeners::parse(collector, output), remote_mode),
     };
+    for entry in &mut entries {
+        entry.collector = Some(collector);
+    }
+    entries
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(parse_lsof_fields(&stdout, remote_host.is_some()))
+/// Entries for sockets listed by ss, netstat, or /proc/net/tcp.
+fn listener_entries(listeners: Vec<Listener>, remote_mode: bool) -> Vec<PortEntry> {
+    let mut entries: Vec<PortEntry> = listeners
+        .into_iter()
+        .map(|listener| PortEntry {
+            source: PortSource::Local,
+            local_port: listener.port,
+            process_name: listener.process.unwrap_or_default(),
+            pid: listener.pid,
+            is_open: remote_mode,
+            is_loopback: listeners::is_loopback_bind(&listener.bind),
+            bind: Some(listener.bind),
+            ..PortEntry::default()
+        })
+        .collect();
+    dedup_listeners(&mut entries);
+    entries
 }
 
 fn parse_lsof_fields(output: &str, remote_mode: bool) -> Vec<PortEntry> {
     let mut entries = Vec::new();
     let mut current_pid: Option<u32> = None;
     let mut current_command: Option<String> = None;
+    let mut current_user: Option<String> = None;
+    // Type (IPv4, IPv6, unix, ...) and protocol of the current file, when
+    // lsof was asked for them
+    let mut current_type: Option<&str> = None;
+    let mut current_protocol: Option<&str> = None;
 
     for line in output.lines() {
         if line.is_empty() {
             continue;
         }
 
-        let field_type = line.chars().next().unwrap_or(' ');
-        let value = &line[1..];
+        // The field letter is ASCII, but garbage may start with any character
+        let mut chars = line.chars();
+        let field_type = chars.next().unwrap_or(' ');
+        let value = chars.as_str();
 
         match field_type {
             'p' => {
                 current_pid = value.parse().ok();
+                // L is omitted when lsof cannot resolve the owner
+                current_user = None;
+                current_type = None;
+                current_protocol = None;
             }
             'c' => {
                 current_command = Some(value.to_string());
             }
+            'L' => {
+                current_user = Some(value.to_string());
+            }
+            'f' => {
+                current_type = None;
+                current_protocol = None;
+            }
+            't' => current_type = Some(value),
+            'P' => current_protocol = Some(value),
             'n' => {
-                // Parse address like "*:3000" or "127.0.0.1:8080" or "[::1]:8080"
-                if let Some(port) = extract_port(value) {
+                let internet = current_type.is_none_or(|t| t == "IPv4" || t == "IPv6");
+                let tcp = current_protocol.is_none_or(|p| p == "TCP");
+                if !internet || !tcp {
+                    continue;
+                }
+                if let Some((bind, port)) = listeners::split_lsof_address(value) {
                     entries.push(PortEntry {
                         source: PortSource::Local,
                         local_port: port,
-                        remote_host: None,
-                        remote_port: None,
                         process_name: current_command.clone().unwrap_or_default(),
                         pid: current_pid,
-                        container_id: None,
-                        container_name: None,
-                        ssh_host: None,
                         // Remote lsof LISTEN = definitely open on the remote side
                         is_open: remote_mode,
-                        is_loopback: false,
-                        forwarded_port: None,
+                        is_loopback: listeners::is_loopback_bind(bind),
+                        user: current_user.clone(),
+                        bind: Some(bind.to_string()),
+                        ..PortEntry::default()
                     });
                 }
             }
@@ -65,16 +122,65 @@ fn parse_lsof_fields(output: &str, remote_mode: bool) -> Vec<PortEntry> {
         }
     }
 
-    // Remove duplicates by port, keeping first occurrence
-    entries.sort_by_key(|e| e.local_port);
-    entries.dedup_by_key(|e| e.local_port);
-
+    dedup_listeners(&mut entries);
     entries
 }
 
-fn extract_port(addr: &str) -> Option<u16> {
-    // Handle IPv6 like "[::1]:8080" or "*:8080" or "127.0.0.1:8080"
-    addr.rsplit(':').next()?.parse().ok()
+/// Output of `ps` for the owners of entries that only know their PID (macOS
+/// netstat), from one call; `None` when there is nothing to look up.
+pub(super) async fn owners_output(entries: &[PortEntry]) -> Option<String> {
+    let mut pids: Vec<String> = entries
+        .iter()
+        .filter_map(|e| e.pid)
+        .map(|pid| pid.to_string())
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    if pids.is_empty() {
+        return None;
+    }
+    let output = tokio::process::Command::new("ps")
+        .args(["-o", "pid=,user=,comm=", "-p", &pids.join(",")])
+        .output()
+        .await;
+    match output {
+        Ok(out) => Some(String::from_utf8_lossy(&out.stdout).into_owned()),
+        Err(e) => {
+            tracing::debug!(error = %e, "could not look up listener owners");
+            None
+        }
+    }
+}
+
+/// Fill in process names and owners from the `ps` output of [`owners_output`].
+pub(super) fn apply_owners(entries: &mut [PortEntry], output: &str) {
+    let owners = parse_ps_owners(output);
+    for entry in entries {
+        let Some((user, command)) = entry.pid.and_then(|pid| owners.get(&pid)) else {
+            continue;
+        };
+        if entry.process_name.is_empty() {
+            entry.process_name.clone_from(command);
+        }
+        entry.user = Some(user.clone());
+    }
+}
+
+/// Parse `ps -o pid=,user=,comm=` into PID → (user, command name). macOS
+/// prints the full path of the executable, which may contain spaces.
+fn parse_ps_owners(output: &str) -> HashMap<u32, (String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_start().splitn(2, char::is_whitespace);
+            let pid = fields.next()?.parse().ok()?;
+            let mut rest = fields.next()?.trim_start().splitn(2, char::is_whitespace);
+            let user = rest.next()?.to_string();
+            let path = rest.next()?.trim();
+            let command = path.rsplit('/').next().unwrap_or(path).to_string();
+            Some((pid, (user, command)))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -83,15 +189,36 @@ mod tests {
 
     #[test]
     fn test_parse_lsof_fields() {
-        let output = "p12345\ncnode\nn*:3000\np5678\ncpython\nn127.0.0.1:8080\n";
+        let output = "p12345\ncnode\nLalice\nn*:3000\np5678\ncpython\nn127.0.0.1:8080\n";
         let entries = parse_lsof_fields(output, false);
         assert_eq!(entries.len(), 2);
         assert_eq!(entries[0].local_port, 3000);
         assert_eq!(entries[0].process_name, "node");
         assert_eq!(entries[0].pid, Some(12345));
+        assert_eq!(entries[0].user.as_deref(), Some("alice"));
         assert!(!entries[0].is_open);
         assert_eq!(entries[1].local_port, 8080);
         assert_eq!(entries[1].process_name, "python");
+        assert_eq!(entries[1].user, None);
+    }
+
+    #[test]
+    fn test_parse_lsof_multiple_listeners() {
+        // nginx on both stacks, a dev server on loopback only, same port
+        let output = "p100\ncnginx\nn*:8080\nn*:8080\np200\ncnode\nn127.0.0.1:8080\nn[::1]:8080\n";
+        let entries = parse_lsof_fields(output, false);
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|e| (e.pid, e.bind.as_deref(), e.is_loopback))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (Some(100), Some("*"), false),
+                (Some(200), Some("127.0.0.1"), true),
+                (Some(200), Some("[::1]"), true),
+            ]
+        );
     }
 
     #[test]
@@ -102,6 +229,34 @@ mod tests {
         assert_eq!(entries[0].local_port, 80);
     }
 
+    #[test]
+    fn test_parse_lsof_scope_ids_and_other_files() {
+        // macOS lsof with the type and protocol fields: a link-local listener,
+        // UDP and UNIX sockets, and a connected socket a looser -s filter let through
+        let output = "p512\ncrapportd\nLroot\n\
+                      f5\ntIPv6\nPTCP\nn[fe80:4::1]:49152\n\
+                      f6\ntIPv6\nPTCP\nn[fe80::aede:48ff:fe00:1122%en0]:7000\n\
+                      f7\ntIPv4\nPUDP\nn*:5353\n\
+                      f8\ntunix\nn/var/run/mDNSResponder\n\
+                      f9\ntIPv4\nPTCP\nn127.0.0.1:8080->127.0.0.1:50412\n\
+                      f10\ntIPv4\nPTCP\nn127.0.0.1:8080\n";
+        let entries = parse_lsof_fields(output, false);
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|e| (e.local_port, e.bind.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (7000, "[fe80::aede:48ff:fe00:1122%en0]"),
+                (8080, "127.0.0.1"),
+                (49152, "[fe80:4::1]"),
+            ]
+        );
+        assert!(!entries[0].is_loopback);
+        assert!(entries[1].is_loopback);
+    }
+
     #[test]
     fn test_parse_lsof_remote_mode() {
         let output = "p12345\ncpython\nn*:18080\n";
@@ -112,10 +267,76 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_port() {
-        assert_eq!(extract_port("*:3000"), Some(3000));
-        assert_eq!(extract_port("127.0.0.1:8080"), Some(8080));
-        assert_eq!(extract_port("[::1]:80"), Some(80));
-        assert_eq!(extract_port("invalid"), None);
+    fn test_listener_entries() {
+        let output = "LISTEN 0 128 [::]:22 [::]:*\n\
+            LISTEN 0 128 0.0.0.0:22 0.0.0.0:* users:((\"sshd\",pid=812,fd=3))\n";
+        let entries = listener_entries(listeners::parse_ss(output), true);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].local_port, 22);
+        assert_eq!(entries[0].process_name, "sshd");
+        assert_eq!(entries[0].pid, Some(812));
+        assert!(entries[0].is_open);
+    }
+
+    #[test]
+    fn test_parse_ps_owners() {
+        let output = "  812 _postgres /opt/homebrew/bin/postgres\n\
+            \x20 915 alice    /Applications/Visual Studio Code.app/Contents/MacOS/Code Helper\n";
+        let owners = parse_ps_owners(output);
+        assert_eq!(
+            owners.get(&812),
+            Some(&("_postgres".to_string(), "postgres".to_string()))
+        );
+        assert_eq!(
+            owners.get(&915),
+            Some(&("alice".to_string(), "Code Helper".to_string()))
+        );
+    }
+
+    mod props {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// lsof `-F` output: field lines (`p`, `c`, `L`, `n`, ...) mixed with garbage.
+        fn lsof_output() -> impl Strategy<Value = String> {
+            let line = prop_oneof![
+                "[pcLnfPT]\\PC{0,30}",
+                "n(\\*|127\\.0\\.0\\.1|\\[::1\\]):[0-9]{0,6}",
+                "\\PC{0,40}",
+            ];
+            prop::collection::vec(line, 0..16).prop_map(|lines| lines.join("\n"))
+        }
+
+        proptest! {
+            #[test]
+            fn prop_parse_lsof_fields_never_panics(output in lsof_output(), cut in any::<prop::sample::Index>()) {
+                parse_lsof_fields(&output, false);
+                // Output cut short, as when lsof is killed mid-listing
+                let end = (0..=cut.index(output.len() + 1))
+                    .rev()
+                    .find(|&i| output.is_char_boundary(i))
+                    .unwrap_or(0);
+                parse_lsof_fields(&output[..end], true);
+            }
+
+            #[test]
+            fn prop_parse_lsof_fields_keeps_odd_names(
+                pid in 1u32..,
+                name in "[a-zA-Z0-9 :._/()éü日本-]{1,24}",
+                user in "[a-z_][a-z0-9_-]{0,12}",
+                port in 1u16..,
+                loopback in any::<bool>(),
+            ) {
+                let bind = if loopback { "127.0.0.1" } else { "*" };
+                let output = format!("p{pid}\nc{name}\nL{user}\nf3\nn{bind}:{port}\n");
+                let entries = parse_lsof_fields(&output, false);
+                prop_assert_eq!(entries.len(), 1);
+                prop_assert_eq!(entries[0].local_port, port);
+                prop_assert_eq!(entries[0].pid, Some(pid));
+                prop_assert_eq!(&entries[0].process_name, &name);
+                prop_assert_eq!(entries[0].user.as_deref(), Some(user.as_str()));
+                prop_assert_eq!(entries[0].is_loopback, loopback);
+            }
+        }
     }
 }
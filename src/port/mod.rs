@@ -1,14 +1,36 @@
 pub mod docker;
+#[cfg(feature = "embedded-ssh")]
+pub mod embedded;
+pub mod established;
+pub mod inspect;
+pub mod listeners;
 pub mod local;
+pub mod project;
+pub mod recording;
+pub mod scan;
+pub mod signal;
 pub mod ssh;
+pub mod ssh_hosts;
+pub mod tail;
+pub mod usage;
 
+pub use signal::Signal;
+
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::time::Duration;
+use std::future::Future;
+use std::hash::Hash;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::process::Output;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
 
-const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+/// Process names (or prefixes) of Docker's userland proxies, which hold the
+/// host side of published ports: docker-proxy, rootless Docker, Docker Desktop.
+const DOCKER_PROXIES: [&str; 4] = ["docker-proxy", "rootlessport", "com.docker", "vpnkit"];
 
 fn escape_ssh_args(args: &[&str]) -> String {
     let escaped: Vec<String> = args
@@ -19,17 +41,283 @@ fn escape_ssh_args(args: &[&str]) -> String {
 }
 
 /// Build a `tokio::process::Command` for SSH that safely escapes each argument.
-pub fn ssh_cmd_tokio(host: &str, args: &[&str]) -> tokio::process::Command {
+fn ssh_cmd_tokio(host: &str, args: &[&str]) -> tokio::process::Command {
     let mut cmd = tokio::process::Command::new("ssh");
-    cmd.arg(host).arg(escape_ssh_args(args));
+    cmd.args(ssh::batch_args(host))
+        .arg(host)
+        .arg(escape_ssh_args(args))
+        .stdin(std::process::Stdio::null());
     cmd
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// `[ssh]` settings: which backend reaches remote hosts, and how it logs in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SshConfig {
+    #[serde(default)]
+    pub backend: SshBackend,
+    /// Private key for the embedded backend, tried after the SSH agent.
+    #[serde(default)]
+    pub identity_file: Option<String>,
+}
+
+/// How quay talks to remote hosts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SshBackend {
+    /// Spawn the system `ssh` client (honours `~/.ssh/config` fully).
+    #[default]
+    Openssh,
+    /// In-process client; requires a build with the `embedded-ssh` feature.
+    Embedded,
+}
+
+static SSH_CONFIG: RwLock<Option<SshConfig>> = RwLock::new(None);
+
+/// Select the SSH backend from `[ssh]` in config.toml.
+///
+/// Returns a warning when the embedded backend is requested but this build
+/// does not include it; the system `ssh` client is used instead.
+pub fn configure_ssh(config: &SshConfig) -> Option<String> {
+    let mut config = config.clone();
+    let warning = if config.backend == SshBackend::Embedded && !cfg!(feature = "embedded-ssh") {
+        config.backend = SshBackend::Openssh;
+        Some(
+            "ssh.backend = \"embedded\" needs a build with the embedded-ssh feature; using ssh"
+                .to_string(),
+        )
+    } else {
+        None
+    };
+    if let Ok(mut slot) = SSH_CONFIG.write() {
+        *slot = Some(config);
+    }
+    warning
+}
+
+/// The `[ssh]` settings in effect (defaults until [`configure_ssh`] runs).
+pub fn ssh_config() -> SshConfig {
+    SSH_CONFIG
+        .read()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// `[probe]` settings: how quay checks that a listed port accepts connections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeConfig {
+    /// Connect to ports to tell open from merely listed. Off for slow
+    /// environments, where every listening port counts as open.
+    #[serde(default = "default_probe_enabled")]
+    pub enabled: bool,
+    /// Milliseconds to wait for each connection.
+    #[serde(default = "default_probe_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Connection attempts in flight at once.
+    #[serde(default = "default_probe_concurrency")]
+    pub concurrency: usize,
+    /// Also connect to remote ports from the remote host (or inside the Docker
+    /// target) with bash's `/dev/tcp` or `nc`, instead of trusting the listing.
+    #[serde(default)]
+    pub remote: bool,
+    /// Milliseconds to hold a connection through an SSH forward open, waiting
+    /// for ssh to close it when the destination is unreachable; 0 skips the
+    /// tunnel health check.
+    #[serde(default = "default_tunnel_wait_ms")]
+    pub tunnel_wait_ms: u64,
+}
+
+fn default_probe_enabled() -> bool {
+    true
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    200
+}
+
+fn default_probe_concurrency() -> usize {
+    64
+}
+
+fn default_tunnel_wait_ms() -> u64 {
+    500
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_probe_enabled(),
+            timeout_ms: default_probe_timeout_ms(),
+            concurrency: default_probe_concurrency(),
+            remote: false,
+            tunnel_wait_ms: default_tunnel_wait_ms(),
+        }
+    }
+}
+
+static PROBE_CONFIG: RwLock<Option<ProbeConfig>> = RwLock::new(None);
+
+/// Apply `[probe]` from config.toml.
+pub fn configure_probe(config: &ProbeConfig) {
+    if let Ok(mut slot) = PROBE_CONFIG.write() {
+        *slot = Some(config.clone());
+    }
+}
+
+/// The `[probe]` settings in effect (defaults until [`configure_probe`] runs).
+pub fn probe_config() -> ProbeConfig {
+    PROBE_CONFIG
+        .read()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// `[sources]` settings: which collectors run. A disabled one is skipped
+/// entirely, with no status in the header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourcesConfig {
+    /// Listening sockets from lsof (on the remote host in remote mode).
+    #[serde(default = "default_source_enabled")]
+    pub local: bool,
+    /// Published ports from `docker ps`.
+    #[serde(default = "default_source_enabled")]
+    pub docker: bool,
+    /// SSH tunnels running on this machine.
+    #[serde(default = "default_source_enabled")]
+    pub ssh: bool,
+}
+
+fn default_source_enabled() -> bool {
+    true
+}
+
+impl Default for SourcesConfig {
+    fn default() -> Self {
+        Self {
+            local: true,
+            docker: true,
+            ssh: true,
+        }
+    }
+}
+
+static SOURCES_CONFIG: RwLock<Option<SourcesConfig>> = RwLock::new(None);
+
+/// Apply `[sources]` from config.toml, with `--no-docker` and `--no-ssh`
+/// already folded in.
+pub fn configure_sources(config: &SourcesConfig) {
+    if let Ok(mut slot) = SOURCES_CONFIG.write() {
+        *slot = Some(config.clone());
+    }
+}
+
+/// The `[sources]` settings in effect (all enabled until
+/// [`configure_sources`] runs).
+pub fn sources_config() -> SourcesConfig {
+    SOURCES_CONFIG
+        .read()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default()
+}
+
+pub fn ssh_backend() -> SshBackend {
+    ssh_config().backend
+}
+
+/// Run a command on `host` through the configured SSH backend.
+///
+/// Each argument is escaped, so the remote shell sees them as separate words.
+pub async fn remote_output(host: &str, args: &[&str]) -> std::io::Result<Output> {
+    let command = escape_ssh_args(args);
+    tracing::debug!(host, command, "remote command");
+    let started = Instant::now();
+    let result = run_remote(host, args).await;
+    match &result {
+        Ok(output) if output.status.success() => {
+            tracing::debug!(host, command, elapsed = ?started.elapsed(), "remote command finished");
+        }
+        Ok(output) => tracing::warn!(
+            host,
+            command,
+            status = %output.status,
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "remote command failed"
+        ),
+        Err(e) => tracing::warn!(host, command, error = %e, "remote command could not run"),
+    }
+    result
+}
+
+async fn run_remote(host: &str, args: &[&str]) -> std::io::Result<Output> {
+    #[cfg(feature = "embedded-ssh")]
+    if ssh_backend() == SshBackend::Embedded {
+        let host = host.to_string();
+        let command = escape_ssh_args(args);
+        return tokio::task::spawn_blocking(move || embedded::exec(&host, &command))
+            .await
+            .map_err(std::io::Error::other)?;
+    }
+    ssh_cmd_tokio(host, args).output().await
+}
+
+/// A command that exited unsuccessfully, kept whole for the Error popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandError {
+    /// The command line as it was run, including the `ssh host` prefix for remote commands.
+    pub command: String,
+    /// `None` when the process was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl CommandError {
+    pub fn new(program: &str, args: &[&str], remote_host: Option<&str>, output: &Output) -> Self {
+        let mut words = vec![program];
+        words.extend_from_slice(args);
+        let command = match remote_host {
+            Some(host) => format!("ssh {host} {}", escape_ssh_args(&words)),
+            None => escape_ssh_args(&words),
+        };
+        Self {
+            command,
+            exit_code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.exit_code {
+            Some(code) => write!(f, "exited with code {code}")?,
+            None => write!(f, "terminated by signal")?,
+        }
+        match self.stderr.lines().find(|l| !l.trim().is_empty()) {
+            Some(line) => write!(f, ": {}", line.trim()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Where a listening port was found.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PortSource {
+    #[default]
+    #[serde(alias = "local")]
     Local,
+    #[serde(alias = "ssh")]
     Ssh,
+    #[serde(alias = "docker")]
     Docker,
+    /// Found open by `quay scan`; nothing is known about its owner.
+    #[serde(alias = "scan")]
+    Scan,
 }
 
 impl fmt::Display for PortSource {
@@ -38,24 +326,94 @@ impl fmt::Display for PortSource {
             PortSource::Local => write!(f, "LOCAL"),
             PortSource::Ssh => write!(f, "SSH"),
             PortSource::Docker => write!(f, "DOCKER"),
+            PortSource::Scan => write!(f, "SCAN"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// How far a connection through an SSH `-L` forward gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelHealth {
+    /// The local port doesn't accept connections.
+    Down,
+    /// ssh accepts locally but can't reach the destination.
+    LocalOnly,
+    /// The connection reaches the destination.
+    Healthy,
+}
+
+impl fmt::Display for TunnelHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelHealth::Down => write!(f, "down"),
+            TunnelHealth::LocalOnly => write!(f, "local only"),
+            TunnelHealth::Healthy => write!(f, "healthy"),
+        }
+    }
+}
+
+/// One listening port: a process, an SSH tunnel, or a published container port.
+#[derive(Debug, Clone, Default)]
 pub struct PortEntry {
     pub source: PortSource,
+    /// Port listened on (inside the container in Docker target mode).
     pub local_port: u16,
+    /// Forward destination of an SSH tunnel, or the container side of a Docker mapping.
     pub remote_host: Option<String>,
     pub remote_port: Option<u16>,
     pub process_name: String,
     pub pid: Option<u32>,
     pub container_id: Option<String>,
     pub container_name: Option<String>,
+    /// Host an SSH tunnel connects to.
     pub ssh_host: Option<String>,
+    /// Whether the port accepts connections (see [`open_ports`]).
     pub is_open: bool,
+    /// Bound to a loopback address only.
     pub is_loopback: bool,
+    /// Local port this entry is reachable on through an SSH forward.
     pub forwarded_port: Option<u16>,
+    /// Connection the entry was collected from; only set in the aggregate view.
+    pub connection: Option<String>,
+    /// Login name of the process owner (lsof/ps); `None` for containers.
+    pub user: Option<String>,
+    /// Tool that listed a process or container-internal port; `None` when
+    /// the source implies it (docker ps, ps for SSH tunnels, a scan).
+    pub collector: Option<listeners::Collector>,
+    /// Address the socket is bound to, e.g. `127.0.0.1` or `[::1]`; `*` for
+    /// any address. `None` when the source doesn't say (docker ps, ps, a scan).
+    pub bind: Option<String>,
+    /// Whether the port accepted a connection made on its own host or inside
+    /// its container (`[probe] remote`); `None` when not checked.
+    pub remote_open: Option<bool>,
+    /// How far a connection through an SSH `-L` forward gets; `None` for
+    /// other entries and when not checked (`[probe] tunnel_wait_ms = 0`).
+    pub tunnel: Option<TunnelHealth>,
+    /// How long the probe took to connect, or for a tunnel, until the
+    /// destination answered; `None` when not probed or it didn't answer.
+    pub latency: Option<Duration>,
+    /// Established connections to the local port of an open entry or SSH
+    /// `-L` forward; `None` when not counted (closed ports, Docker target
+    /// mode) or they couldn't be listed.
+    pub established: Option<usize>,
+    /// Addresses the server of an SSH `-R` forward bound its port on, from a
+    /// listener scan there (empty when nothing listens); `None` for other
+    /// entries and when the server couldn't be scanned.
+    pub reverse_bind: Option<Vec<String>>,
+    /// Project a local process was started in, from its working directory;
+    /// `None` for other entries and when none was found.
+    pub project: Option<project::Project>,
+    /// CPU and memory of the process (see [`usage`]), or for a container
+    /// from `docker stats`, which the TUI reads in the background; `None`
+    /// when unknown.
+    pub usage: Option<usage::Usage>,
+    /// Health check status of a container (`healthy`, `unhealthy`,
+    /// `starting`) from `docker ps`; `None` without a health check.
+    pub health: Option<String>,
+    /// Connections to the local port waiting in `TIME_WAIT`, counted with
+    /// the established ones; `None` when not counted.
+    pub time_wait: Option<usize>,
 }
 
 impl PortEntry {
@@ -67,6 +425,15 @@ impl PortEntry {
         }
     }
 
+    /// `:port`, or `addr:port` when bound to one address, so listeners sharing
+    /// a port on different interfaces can be told apart.
+    pub fn local_display(&self) -> String {
+        match self.bind.as_deref() {
+            Some(bind) if bind != "*" => format!("{bind}:{}", self.local_port),
+            _ => format!(":{}", self.local_port),
+        }
+    }
+
     pub fn process_display(&self) -> String {
         match self.source {
             PortSource::Docker => {
@@ -86,92 +453,837 @@ impl PortEntry {
             }
         }
     }
+
+    /// Probe latency in whole milliseconds (`<1 ms` below that), or empty.
+    pub fn latency_display(&self) -> String {
+        match self.latency {
+            Some(latency) if latency < Duration::from_millis(1) => "<1 ms".to_string(),
+            Some(latency) => format!("{} ms", latency.as_millis()),
+            None => String::new(),
+        }
+    }
+
+    /// A LOCAL listener held by Docker's proxy for a published container port.
+    pub fn is_docker_proxy(&self) -> bool {
+        self.source == PortSource::Local
+            && DOCKER_PROXIES
+                .iter()
+                .any(|proxy| self.process_name.starts_with(proxy))
+    }
+
+    /// Whether the owner is known and is someone other than `user`.
+    ///
+    /// `ps` cuts long names short with a trailing `+`; a matching prefix counts as `user`.
+    pub fn is_owned_by_other(&self, user: &str) -> bool {
+        let Some(owner) = self.user.as_deref() else {
+            return false;
+        };
+        let truncated = owner
+            .strip_suffix('+')
+            .is_some_and(|prefix| user.starts_with(prefix));
+        owner != user && !truncated
+    }
 }
 
-async fn collect_entries(remote_host: Option<&str>) -> anyhow::Result<Vec<PortEntry>> {
-    let mut entries = Vec::new();
+/// How listing one source went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceStatus {
+    Ok,
+    /// The source couldn't be listed, with a short reason such as "daemon down".
+    Failed(String),
+}
 
-    if let Ok(local) = local::collect(remote_host).await {
-        entries.extend(local);
+impl SourceStatus {
+    fn of<T>(result: &anyhow::Result<T>) -> Self {
+        match result {
+            Ok(_) => SourceStatus::Ok,
+            Err(e) => SourceStatus::Failed(e.to_string()),
+        }
     }
+}
 
-    if let Ok(docker) = docker::collect(remote_host).await {
-        entries.extend(docker);
+/// The ports of one scan, with how each source fared, so an empty Docker
+/// list can be told apart from a Docker that isn't running.
+#[derive(Debug, Default)]
+pub struct Collection {
+    pub entries: Vec<PortEntry>,
+    /// Status of each source that was listed, in Local, Docker, Ssh order;
+    /// empty in docker target mode, which lists one container.
+    pub sources: Vec<(PortSource, SourceStatus)>,
+    /// How long each phase took (`local`, `docker`, `ssh`, `probe`,
+    /// `details`), in the order they first ran; in the aggregate view each
+    /// is prefixed with its connection.
+    pub timings: Vec<(String, Duration)>,
+}
+
+/// Await `phase` and add how long it took to `name` in `timings`.
+async fn timed<T>(
+    timings: &mut Vec<(String, Duration)>,
+    name: &str,
+    phase: impl Future<Output = T>,
+) -> T {
+    let started = Instant::now();
+    let output = phase.await;
+    let elapsed = started.elapsed();
+    match timings.iter_mut().find(|(n, _)| n == name) {
+        Some((_, total)) => *total += elapsed,
+        None => timings.push((name.to_string(), elapsed)),
     }
+    output
+}
+
+/// Listening ports on `remote_host` (or this machine), plus local SSH
+/// tunnels; sources disabled in `[sources]` are skipped.
+pub async fn collect_entries(remote_host: Option<&str>) -> anyhow::Result<Collection> {
+    collect_sources(remote_host, Scope::All).await
+}
+
+/// Which part of a connection a scan covers, so that [`collect_connections`]
+/// lists this machine's SSH tunnels once however many connections it scans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// Listeners and the SSH tunnels.
+    All,
+    /// Listeners only: tunnels are local processes, found by another scan.
+    WithoutTunnels,
+    /// The SSH tunnels only.
+    Tunnels,
+}
 
+async fn collect_sources(remote_host: Option<&str>, scope: Scope) -> anyhow::Result<Collection> {
+    let enabled = sources_config();
+    let listeners = scope != Scope::Tunnels;
+    let mut timings = Vec::new();
+    let local = if enabled.local && listeners {
+        Some(timed(&mut timings, "local", local::collect(remote_host)).await)
+    } else {
+        None
+    };
+    let docker = if enabled.docker && listeners {
+        Some(timed(&mut timings, "docker", docker::collect(remote_host)).await)
+    } else {
+        None
+    };
     // SSH tunnels are always local processes
-    if let Ok(ssh) = ssh::collect().await {
-        entries.extend(ssh);
+    let ssh = if enabled.ssh && scope != Scope::WithoutTunnels {
+        Some(timed(&mut timings, "ssh", ssh::collect()).await)
+    } else {
+        None
+    };
+    let sources = [
+        (PortSource::Local, &local),
+        (PortSource::Docker, &docker),
+        (PortSource::Ssh, &ssh),
+    ]
+    .into_iter()
+    .filter_map(|(source, result)| Some((source, SourceStatus::of(result.as_ref()?))))
+    .collect();
+    let mut entries = Vec::new();
+
+    match local {
+        Some(Ok(local)) => entries.extend(local),
+        // Remote listeners come only from lsof; without them the scan is meaningless
+        Some(Err(e)) if remote_host.is_some() => return Err(e),
+        Some(Err(e)) => tracing::warn!(error = %format!("{e:#}"), "lsof scan failed"),
+        None => {}
+    }
+
+    match docker {
+        Some(Ok(docker)) => entries.extend(docker),
+        Some(Err(e)) => tracing::debug!(error = %format!("{e:#}"), "docker scan failed"),
+        None => {}
+    }
+
+    match ssh {
+        Some(Ok(ssh)) => entries.extend(ssh),
+        Some(Err(e)) => tracing::warn!(error = %format!("{e:#}"), "ssh tunnel scan failed"),
+        None => {}
+    }
+
+    merge_entries(&mut entries, remote_host.is_some());
+
+    Ok(Collection {
+        entries,
+        sources,
+        timings,
+    })
+}
+
+/// Keep one entry per listening socket: port, bind address, and process.
+///
+/// A process listening on `0.0.0.0` and `[::]` shows once (both are `*`), but
+/// two processes on one port, or one process on `127.0.0.1` and `[::1]`, keep
+/// their own rows. A row without a PID is dropped when another row for the
+/// same port and address has one (ss only sees other users' PIDs as root).
+pub fn dedup_listeners(entries: &mut Vec<PortEntry>) {
+    for entry in entries.iter_mut() {
+        entry.bind = entry.bind.as_deref().map(listeners::normalize_bind);
+    }
+    entries.sort_by(|a, b| {
+        (a.local_port, &a.bind, a.pid.is_none(), a.pid).cmp(&(
+            b.local_port,
+            &b.bind,
+            b.pid.is_none(),
+            b.pid,
+        ))
+    });
+    entries.dedup_by(|later, kept| {
+        later.local_port == kept.local_port
+            && later.bind == kept.bind
+            && (later.pid == kept.pid || later.pid.is_none())
+    });
+}
+
+/// Merge entries that describe the same listening port into one.
+///
+/// SSH/Docker processes listen locally (visible via lsof), so the same port
+/// also shows up as LOCAL. The LOCAL entry is folded into the SSH or DOCKER
+/// one (an SSH tunnel also absorbs a DOCKER entry), which keeps the most
+/// specific source and fills its gaps:
+///
+/// - PID, bind address, owner, and collector from LOCAL, which saw the socket
+/// - container ID and name from DOCKER
+/// - tunnel spec (`remote_host`, `remote_port`, `ssh_host`) from SSH
+///
+/// Entries that both know their bind address only merge when it is the same,
+/// so a second listener on `[::1]` keeps its row next to a tunnel on
+/// `127.0.0.1`.
+///
+/// In `remote_mode` SSH tunnels run here but LOCAL/DOCKER entries describe the
+/// remote host, so nothing merges into a tunnel; a LOCAL entry on a tunnel's
+/// port is still dropped.
+pub fn merge_entries(entries: &mut Vec<PortEntry>, remote_mode: bool) {
+    // Port to the entries the others on that port may merge into
+    let mut bases: HashMap<u16, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if merge_rank(&entry.source, remote_mode).is_some() {
+            bases.entry(entry.local_port).or_default().push(index);
+        }
+    }
+
+    let mut absorbed = vec![false; entries.len()];
+    for index in 0..entries.len() {
+        let donor = &entries[index];
+        if !matches!(donor.source, PortSource::Local | PortSource::Docker) {
+            continue;
+        }
+        // A base only absorbs entries it outranks, the earlier one on a tie
+        let own = merge_rank(&donor.source, remote_mode).map(|rank| (rank, index));
+        let base = bases
+            .get(&donor.local_port)
+            .into_iter()
+            .flatten()
+            .filter(|&&base| !absorbed[base] && same_bind(&entries[base], donor))
+            .filter_map(|&base| Some((merge_rank(&entries[base].source, remote_mode)?, base)))
+            .filter(|&key| own.is_none_or(|own| key < own))
+            .min();
+        let Some((_, base)) = base else {
+            continue;
+        };
+        absorbed[index] = true;
+        let donor = entries[index].clone();
+        merge_into(&mut entries[base], donor);
     }
+    if remote_mode {
+        let tunnel_ports: HashSet<u16> = entries
+            .iter()
+            .filter(|e| e.source == PortSource::Ssh)
+            .map(|e| e.local_port)
+            .collect();
+        for (index, entry) in entries.iter().enumerate() {
+            if entry.source == PortSource::Local && tunnel_ports.contains(&entry.local_port) {
+                absorbed[index] = true;
+            }
+        }
+    }
+    let mut absorbed = absorbed.into_iter();
+    entries.retain(|_| !absorbed.next().unwrap_or(false));
+}
 
-    dedup_entries(&mut entries);
+/// Whether `a` and `b` can be the same socket: the same bind address, or
+/// one of them doesn't know its address.
+fn same_bind(a: &PortEntry, b: &PortEntry) -> bool {
+    match (&a.bind, &b.bind) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
 
-    Ok(entries)
+/// Which entry others on its port merge into; lower wins. LOCAL entries are
+/// only merged into others, SCAN entries are left alone.
+fn merge_rank(source: &PortSource, remote_mode: bool) -> Option<u8> {
+    match source {
+        PortSource::Ssh if !remote_mode => Some(0),
+        PortSource::Docker => Some(1),
+        PortSource::Ssh | PortSource::Local | PortSource::Scan => None,
+    }
 }
 
-/// Remove LOCAL entries whose port overlaps with SSH or Docker entries.
-/// SSH/Docker processes listen locally (visible via lsof), so the LOCAL
-/// duplicate is redundant and would cause double-counting in the TUI.
-pub fn dedup_entries(entries: &mut Vec<PortEntry>) {
-    let non_local_ports: HashSet<u16> = entries
+/// Fill `base` from `donor`, another entry for the same port.
+fn merge_into(base: &mut PortEntry, donor: PortEntry) {
+    match donor.source {
+        PortSource::Local => {
+            base.pid = donor.pid.or(base.pid);
+            base.is_loopback = donor.is_loopback;
+            base.collector = donor.collector.or(base.collector);
+            // docker-proxy runs as root, but containers are stopped through docker
+            if !donor.is_docker_proxy() {
+                base.user = donor.user.or(base.user.take());
+            }
+            if base.process_name.is_empty() {
+                base.process_name = donor.process_name;
+            }
+            base.bind = donor.bind.or(base.bind.take());
+        }
+        PortSource::Docker => {
+            base.container_id = donor.container_id.or(base.container_id.take());
+            base.container_name = donor.container_name.or(base.container_name.take());
+        }
+        PortSource::Ssh | PortSource::Scan => {}
+    }
+    base.is_open |= donor.is_open;
+}
+
+/// Addresses to probe for a port bound to `bind`: loopback for any address
+/// (IPv4 first, then IPv6 for v6-only sockets), else the address itself. Empty
+/// when the address can't be dialled as written (an IPv6 scope such as `%eth0`).
+fn probe_addrs(bind: Option<&str>, port: u16) -> Vec<SocketAddr> {
+    match bind {
+        None | Some("*") => vec![
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port),
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port),
+        ],
+        Some(bind) => bind
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| vec![SocketAddr::new(ip, port)])
+            .unwrap_or_default(),
+    }
+}
+
+/// Run `check` on the addresses of each target, at most `[probe] concurrency`
+/// at once.
+async fn probe_targets<K, T, F, Fut>(
+    targets: impl IntoIterator<Item = (K, Vec<SocketAddr>)>,
+    check: F,
+) -> HashMap<K, T>
+where
+    K: Eq + Hash + Send + 'static,
+    T: Send + 'static,
+    F: Fn(Vec<SocketAddr>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let permits = Arc::new(tokio::sync::Semaphore::new(
+        probe_config().concurrency.max(1),
+    ));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (key, addrs) in targets {
+        let permits = Arc::clone(&permits);
+        let check = check(addrs);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            Some((key, check.await))
+        });
+    }
+
+    let mut results = HashMap::new();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(Some((key, value))) = result {
+            results.insert(key, value);
+        }
+    }
+    results
+}
+
+/// The first of `addrs` to accept a connection within `[probe] timeout_ms`,
+/// and how long that took.
+async fn dial(addrs: &[SocketAddr]) -> Option<(TcpStream, Duration)> {
+    let timeout = Duration::from_millis(probe_config().timeout_ms);
+    for &addr in addrs {
+        let started = Instant::now();
+        if let Ok(Ok(stream)) = tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            return Some((stream, started.elapsed()));
+        }
+    }
+    None
+}
+
+/// Connect through a tunnel's local port and wait up to `wait` for the far
+/// end. ssh accepts right away and closes the connection once it fails to
+/// reach the destination, so an early EOF means only the local end works;
+/// data from the service, or a connection still open after `wait`, means the
+/// destination answered. The latency is the time to that data, unknown for
+/// services that wait for the client to speak first.
+async fn check_tunnel(addrs: &[SocketAddr], wait: Duration) -> (TunnelHealth, Option<Duration>) {
+    use tokio::io::AsyncReadExt;
+
+    let started = Instant::now();
+    let Some((mut stream, _)) = dial(addrs).await else {
+        return (TunnelHealth::Down, None);
+    };
+    match tokio::time::timeout(wait, stream.read(&mut [0; 1])).await {
+        Ok(Ok(0) | Err(_)) => (TunnelHealth::LocalOnly, None),
+        Ok(Ok(_)) => (TunnelHealth::Healthy, Some(started.elapsed())),
+        Err(_) => (TunnelHealth::Healthy, None),
+    }
+}
+
+/// Try a TCP connection to each of `ports` on localhost, concurrently.
+pub async fn probe_ports(ports: impl IntoIterator<Item = u16>) -> HashMap<u16, bool> {
+    let ports: HashSet<u16> = ports.into_iter().collect();
+    probe_targets(
+        ports
+            .into_iter()
+            .map(|port| (port, probe_addrs(None, port))),
+        |addrs| async move { dial(&addrs).await.is_some() },
+    )
+    .await
+}
+
+/// Whether `entry` is an SSH `-L` forward, whose tunnel can be checked from
+/// here (`-R` forwards listen on the far side).
+pub fn is_local_forward(entry: &PortEntry) -> bool {
+    entry.source == PortSource::Ssh
+        && !entry
+            .remote_host
+            .as_deref()
+            .is_some_and(|host| host.starts_with("(R) "))
+}
+
+/// Whether `entry` is an SSH `-R` forward, whose port listens on the server.
+pub fn is_reverse_forward(entry: &PortEntry) -> bool {
+    entry.source == PortSource::Ssh && !is_local_forward(entry)
+}
+
+/// Listener collectors for checking `-R` ports on a server. lsof comes last:
+/// it only sees the user's own sockets, and sshd may listen as another.
+const REVERSE_BIND_CHAIN: [listeners::Collector; 4] = [
+    listeners::Collector::Ss,
+    listeners::Collector::Netstat,
+    listeners::Collector::ProcNet,
+    listeners::Collector::Lsof,
+];
+
+/// What a scan of `remote_host` (this machine when `None`) adds to the
+/// listeners it found: connection counts (also in `TIME_WAIT`), process
+/// usage, the server side of `-R` forwards, and local projects.
+async fn annotate(entries: &mut [PortEntry], remote_host: Option<&str>) {
+    count_established(entries, remote_host).await;
+    count_time_wait(entries, remote_host).await;
+    measure_usage(entries, remote_host).await;
+    check_reverse_binds(entries).await;
+    if remote_host.is_none() {
+        project::annotate(entries).await;
+    }
+}
+
+/// Fill in `reverse_bind` for SSH `-R` forwards from a listener scan of each
+/// server, so a port the server bound to loopback only (`GatewayPorts no`)
+/// or not at all shows as such.
+async fn check_reverse_binds(entries: &mut [PortEntry]) {
+    let servers: HashSet<String> = entries
         .iter()
-        .filter(|e| e.source != PortSource::Local)
-        .map(|e| e.local_port)
+        .filter(|e| is_reverse_forward(e))
+        .filter_map(|e| e.ssh_host.clone())
         .collect();
-    entries.retain(|e| e.source != PortSource::Local || !non_local_ports.contains(&e.local_port));
+    let mut tasks = tokio::task::JoinSet::new();
+    for server in servers {
+        tasks.spawn(async move {
+            let scan =
+                listeners::collect(listeners::Target::Host(Some(&server)), &REVERSE_BIND_CHAIN)
+                    .await;
+            (server, scan)
+        });
+    }
+    let mut binds: HashMap<String, HashMap<u16, Vec<String>>> = HashMap::new();
+    while let Some(result) = tasks.join_next().await {
+        let Ok((server, scan)) = result else {
+            continue;
+        };
+        match scan {
+            Ok((collector, output)) => {
+                binds.insert(server, binds_by_port(collector, &output));
+            }
+            Err(e) => {
+                tracing::warn!(server, error = %format!("{e:#}"), "could not check -R forwards");
+            }
+        }
+    }
+    for entry in entries.iter_mut().filter(|e| is_reverse_forward(e)) {
+        let ports = entry.ssh_host.as_ref().and_then(|server| binds.get(server));
+        if let (Some(ports), Some(port)) = (ports, entry.remote_port) {
+            entry.reverse_bind = Some(ports.get(&port).cloned().unwrap_or_default());
+        }
+    }
+}
+
+/// Bind addresses of each listening port in a collector's output.
+fn binds_by_port(collector: listeners::Collector, output: &str) -> HashMap<u16, Vec<String>> {
+    let mut ports: HashMap<u16, Vec<String>> = HashMap::new();
+    for listener in local::parse(collector, output, true) {
+        ports
+            .entry(listener.local_port)
+            .or_default()
+            .extend(listener.bind);
+    }
+    for binds in ports.values_mut() {
+        binds.sort();
+        binds.dedup();
+    }
+    ports
+}
+
+/// Fill in `established` for open entries: SSH `-L` forwards listen here,
+/// the others on `remote_host` (this machine when `None`).
+///
+/// Connections are counted by local port, so a port whose socket lsof can't
+/// see (another user's, without root) counts as unused.
+async fn count_established(entries: &mut [PortEntry], remote_host: Option<&str>) {
+    let on_host = |e: &PortEntry| e.is_open && !is_local_forward(e);
+    let local = entries.iter().any(is_local_forward);
+    let host = entries.iter().any(on_host);
+    // Without a remote host, one listing covers both
+    let (local_counts, host_counts) = match remote_host {
+        None if local || host => {
+            let counts = established_counts(None).await;
+            (counts.clone(), counts)
+        }
+        None => return,
+        Some(_) => {
+            let local_counts = if local {
+                established_counts(None).await
+            } else {
+                None
+            };
+            let host_counts = if host {
+                established_counts(remote_host).await
+            } else {
+                None
+            };
+            (local_counts, host_counts)
+        }
+    };
+    for entry in entries.iter_mut() {
+        let counts = if is_local_forward(entry) {
+            &local_counts
+        } else if on_host(entry) {
+            &host_counts
+        } else {
+            continue;
+        };
+        if let Some(counts) = counts {
+            entry.established = Some(counts.get(&entry.local_port).copied().unwrap_or(0));
+        }
+    }
+}
+
+/// Fill in `time_wait` for open entries on `remote_host` (this machine
+/// when `None`); SSH `-L` forwards are left out like their destinations.
+async fn count_time_wait(entries: &mut [PortEntry], remote_host: Option<&str>) {
+    let on_host = |e: &PortEntry| e.is_open && !is_local_forward(e);
+    if !entries.iter().any(on_host) {
+        return;
+    }
+    let counts = match established::time_wait_counts(remote_host).await {
+        Ok(counts) => counts,
+        Err(e) => {
+            tracing::warn!(host = remote_host, error = %format!("{e:#}"), "could not count TIME_WAIT connections");
+            return;
+        }
+    };
+    for entry in entries.iter_mut().filter(|e| on_host(e)) {
+        entry.time_wait = Some(counts.get(&entry.local_port).copied().unwrap_or(0));
+    }
+}
+
+/// Established connections per local port on `host`, `None` when they
+/// couldn't be listed.
+async fn established_counts(host: Option<&str>) -> Option<HashMap<u16, usize>> {
+    match established::collect(host).await {
+        Ok(connections) => Some(established::counts(&connections)),
+        Err(e) => {
+            tracing::warn!(host, error = %format!("{e:#}"), "could not list established connections");
+            None
+        }
+    }
+}
+
+/// Fill in `usage` for entries with a process: SSH tunnels run here, the
+/// other processes on `remote_host` (this machine when `None`).
+async fn measure_usage(entries: &mut [PortEntry], remote_host: Option<&str>) {
+    let on_host = |e: &PortEntry| remote_host.is_some() && e.source != PortSource::Ssh;
+    let pids = |remote: bool| {
+        let mut pids: Vec<u32> = entries
+            .iter()
+            .filter(|e| e.source != PortSource::Docker && on_host(e) == remote)
+            .filter_map(|e| e.pid)
+            .collect();
+        pids.sort_unstable();
+        pids.dedup();
+        pids
+    };
+    let (local_pids, host_pids) = (pids(false), pids(true));
+    let local = usage_of(&local_pids, None).await;
+    let host = usage_of(&host_pids, remote_host).await;
+    for entry in entries
+        .iter_mut()
+        .filter(|e| e.source != PortSource::Docker)
+    {
+        let usage = if on_host(entry) { &host } else { &local };
+        entry.usage = entry.pid.and_then(|pid| usage.get(&pid).copied());
+    }
+}
+
+/// [`usage::collect`], empty when ps couldn't run.
+async fn usage_of(pids: &[u32], host: Option<&str>) -> HashMap<u32, usage::Usage> {
+    usage::collect(pids, host).await.unwrap_or_else(|e| {
+        tracing::warn!(host, error = %format!("{e:#}"), "could not read process usage");
+        HashMap::new()
+    })
 }
 
 async fn probe_open_ports(entries: &mut [PortEntry], remote_mode: bool) {
     // In remote mode, only probe SSH tunnel entries (which are local).
     // Remote Local/Docker entries already have is_open set from lsof/docker output.
-    let probe_ports: Vec<u16> = {
-        let mut seen = HashSet::new();
-        for e in entries.iter() {
-            if remote_mode && e.source != PortSource::Ssh {
-                continue;
-            }
-            seen.insert(e.local_port);
+    // Published ports of a daemon on another machine (docker context, DOCKER_HOST) likewise
+    let remote_docker = !remote_mode
+        && entries.iter().any(|e| e.source == PortSource::Docker)
+        && docker::daemon_is_remote().await;
+    let probed = |e: &PortEntry| {
+        e.source == PortSource::Ssh
+            || !(remote_mode || remote_docker && e.source == PortSource::Docker)
+    };
+    let config = probe_config();
+    if !config.enabled {
+        for entry in entries.iter_mut().filter(|e| probed(e)) {
+            entry.is_open = true;
         }
-        seen.into_iter().collect()
+        return;
+    }
+    // Probe where each socket is bound, once per address and port; forwards
+    // are connected through to their destination instead
+    let wait = Some(Duration::from_millis(config.tunnel_wait_ms)).filter(|w| !w.is_zero());
+    let through = |e: &PortEntry| wait.is_some() && is_local_forward(e);
+    let targets = |tunnels: bool| {
+        entries
+            .iter()
+            .filter(|e| probed(e) && through(e) == tunnels)
+            .map(|e| (e.bind.clone(), e.local_port))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|(bind, port)| {
+                let addrs = probe_addrs(bind.as_deref(), port);
+                ((bind, port), addrs)
+            })
+            .collect::<Vec<_>>()
     };
-
-    let mut handles = Vec::new();
-    for port in probe_ports {
-        handles.push(tokio::spawn(async move {
-            let addr = format!("127.0.0.1:{port}");
-            let result = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(&addr)).await;
-            (port, result.is_ok() && result.unwrap().is_ok())
-        }));
+    let (ports, tunnels) = tokio::join!(
+        probe_targets(targets(false), |addrs| async move {
+            dial(&addrs).await.map(|(_, latency)| latency)
+        }),
+        probe_targets(targets(true), |addrs| async move {
+            check_tunnel(&addrs, wait.unwrap_or_default()).await
+        }),
+    );
+    for entry in entries.iter_mut().filter(|e| probed(e)) {
+        let key = (entry.bind.clone(), entry.local_port);
+        if probe_addrs(entry.bind.as_deref(), entry.local_port).is_empty() {
+            // Listed, but not dialable from here; trust the listing
+            entry.is_open = true;
+        } else if let Some(&(health, latency)) = tunnels.get(&key).filter(|_| through(entry)) {
+            entry.is_open = health != TunnelHealth::Down;
+            entry.tunnel = Some(health);
+            entry.latency = latency;
+        } else if let Some(&latency) = ports.get(&key) {
+            entry.is_open = latency.is_some();
+            entry.latency = latency;
+        }
     }
+}
 
-    let mut results = HashMap::new();
-    for handle in handles {
-        if let Ok((port, is_open)) = handle.await {
-            results.insert(port, is_open);
+/// Connects to each `host:port` argument on the machine it runs on and prints
+/// the ones that accept; exits 127 when neither bash nor nc is installed.
+const REMOTE_PROBE_SCRIPT: &str = r#"if command -v bash >/dev/null 2>&1; then
+  c() { bash -c ": </dev/tcp/$1/$2" 2>/dev/null; }
+elif command -v nc >/dev/null 2>&1; then
+  c() { nc -z "$1" "$2" 2>/dev/null; }
+else
+  exit 127
+fi
+for t; do c "${t%:*}" "${t##*:}" && echo "$t"; done
+exit 0"#;
+
+/// `ip:port` arguments for [`REMOTE_PROBE_SCRIPT`], per entry of `entries`.
+fn remote_probe_targets(entries: &[PortEntry]) -> Vec<Vec<String>> {
+    entries
+        .iter()
+        .map(|e| {
+            probe_addrs(e.bind.as_deref(), e.local_port)
+                .iter()
+                .map(|addr| format!("{}:{}", addr.ip(), addr.port()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Check `entries` on `target` itself (see [`ProbeConfig::remote`]) and store
+/// the result in `remote_open`. Leaves them unchecked when the target has
+/// neither bash nor nc, or can't be reached.
+async fn probe_remote(entries: &mut [PortEntry], target: listeners::Target<'_>) {
+    let targets = remote_probe_targets(entries);
+    let mut args = vec!["sh", "-c", REMOTE_PROBE_SCRIPT, "sh"];
+    args.extend(targets.iter().flatten().map(String::as_str));
+    let (words, host) = target.wrap(&args);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let output = match listeners::output(&words, host).await {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            let error = CommandError::new(words[0], &words[1..], host, &output);
+            tracing::warn!(?target, %error, "remote probe failed");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(?target, error = %e, "remote probe could not run");
+            return;
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let open: HashSet<&str> = stdout.lines().map(str::trim).collect();
+    for (entry, addrs) in entries.iter_mut().zip(&targets) {
+        if !addrs.is_empty() {
+            entry.remote_open = Some(addrs.iter().any(|a| open.contains(a.as_str())));
         }
     }
+}
 
-    for entry in entries.iter_mut() {
-        if let Some(&open) = results.get(&entry.local_port) {
-            entry.is_open = open;
+/// `entry` as it is now, without a full rescan: the process listening on its
+/// port after a listener scan of its host (for processes), then whether the
+/// port accepts connections. Answers "did my kill work?" quickly.
+pub async fn recheck_entry(
+    entry: &PortEntry,
+    remote_host: Option<&str>,
+) -> anyhow::Result<PortEntry> {
+    let mut fresh = entry.clone();
+    if entry.source == PortSource::Local {
+        let listeners: Vec<PortEntry> = local::collect(remote_host)
+            .await?
+            .into_iter()
+            .filter(|e| e.local_port == entry.local_port)
+            .collect();
+        let listener = listeners
+            .iter()
+            .find(|e| e.bind == entry.bind)
+            .or(listeners.first());
+        let Some(listener) = listener else {
+            fresh.is_open = false;
+            fresh.pid = None;
+            fresh.latency = None;
+            fresh.usage = None;
+            return Ok(fresh);
+        };
+        if listener.pid != entry.pid {
+            fresh.project = None;
+            fresh.usage = None;
+        }
+        fresh.pid = listener.pid;
+        fresh.process_name.clone_from(&listener.process_name);
+        fresh.user.clone_from(&listener.user);
+        fresh.bind.clone_from(&listener.bind);
+        fresh.is_open = listener.is_open;
+        if remote_host.is_none() && fresh.project.is_none() {
+            project::annotate(std::slice::from_mut(&mut fresh)).await;
         }
     }
+    if fresh.pid.is_some() {
+        measure_usage(std::slice::from_mut(&mut fresh), remote_host).await;
+    }
+    probe_open_ports(std::slice::from_mut(&mut fresh), remote_host.is_some()).await;
+    Ok(fresh)
 }
 
+/// Which of `ports` are open in the selected context.
+///
+/// Locally a port is open when it accepts a connection. A remote host or a
+/// container cannot be probed from here, so there a port counts as open while
+/// something LISTENs on it.
+pub async fn open_ports(
+    ports: &[u16],
+    remote_host: Option<&str>,
+    docker_target: Option<&str>,
+) -> anyhow::Result<HashSet<u16>> {
+    let listening: HashSet<u16> = match (remote_host, docker_target) {
+        (None, None) if probe_config().enabled => {
+            return Ok(probe_ports(ports.iter().copied())
+                .await
+                .into_iter()
+                .filter_map(|(port, open)| open.then_some(port))
+                .collect());
+        }
+        (_, Some(container)) => docker::collect_from_container(container, remote_host)
+            .await?
+            .iter()
+            .map(|e| e.local_port)
+            .collect(),
+        // SSH tunnels are local processes, not listeners on the remote host
+        (_, None) => collect_entries(remote_host)
+            .await?
+            .entries
+            .iter()
+            .filter(|e| remote_host.is_none() || e.source != PortSource::Ssh)
+            .map(|e| e.local_port)
+            .collect(),
+    };
+    Ok(ports
+        .iter()
+        .copied()
+        .filter(|p| listening.contains(p))
+        .collect())
+}
+
+/// Every listening port in one context, open ports first.
+///
+/// Without `docker_target` this scans `remote_host` (or this machine) plus the
+/// local SSH tunnels. With it, only the container is scanned, and its ports are
+/// matched against `known_forwards` (container port to local port) and the
+/// running tunnels to tell which are reachable from here.
 pub async fn collect_all(
     remote_host: Option<&str>,
     docker_target: Option<&str>,
     known_forwards: &HashMap<u16, u16>,
-) -> anyhow::Result<Vec<PortEntry>> {
+) -> anyhow::Result<Collection> {
+    collect_scan(remote_host, docker_target, known_forwards, Scope::All).await
+}
+
+#[allow(clippy::too_many_lines)]
+async fn collect_scan(
+    remote_host: Option<&str>,
+    docker_target: Option<&str>,
+    known_forwards: &HashMap<u16, u16>,
+    scope: Scope,
+) -> anyhow::Result<Collection> {
+    let started = Instant::now();
+    let mut sources = Vec::new();
+    let mut timings = Vec::new();
     let mut entries = if let Some(container) = docker_target {
         // Docker target mode: only collect from inside the specified container
-        let mut e = docker::collect_from_container(container, remote_host).await?;
+        let mut e = timed(
+            &mut timings,
+            "docker",
+            docker::collect_from_container(container, remote_host),
+        )
+        .await?;
         for entry in &mut e {
             entry.is_open = false;
         }
         if let Some(host) = remote_host {
             // Remote: SSH tunnel detection only (probe would false-positive)
-            if let Ok(ssh_entries) = ssh::collect().await {
+            let tunnels = if sources_config().ssh {
+                ssh::collect().await
+            } else {
+                Ok(Vec::new())
+            };
+            if let Ok(ssh_entries) = tunnels {
                 let ssh_port_map: HashMap<u16, u16> = ssh_entries
                     .iter()
                     .filter_map(|se| se.remote_port.map(|rp| (rp, se.local_port)))
@@ -225,27 +1337,236 @@ pub async fn collect_all(
                     }
                 }
             }
-        } else {
-            // Local: probe localhost (Docker port mappings)
-            probe_open_ports(&mut e, false).await;
+        } else if probe_config().enabled {
+            // Local: probe localhost (Docker port mappings); bind addresses
+            // inside the container don't apply here
+            let open = probe_ports(e.iter().map(|entry| entry.local_port)).await;
+            for entry in &mut e {
+                entry.is_open = open.get(&entry.local_port).copied().unwrap_or(false);
+            }
+        }
+        if probe_config().remote {
+            probe_remote(&mut e, listeners::Target::Container(container, remote_host)).await;
         }
         e
     } else {
-        let mut e = collect_entries(remote_host).await?;
-        probe_open_ports(&mut e, remote_host.is_some()).await;
+        let collection = collect_sources(remote_host, scope).await?;
+        sources = collection.sources;
+        timings = collection.timings;
+        let mut e = collection.entries;
+        timed(
+            &mut timings,
+            "probe",
+            probe_open_ports(&mut e, remote_host.is_some()),
+        )
+        .await;
+        timed(&mut timings, "details", annotate(&mut e, remote_host)).await;
+        if let Some(host) = remote_host.filter(|_| probe_config().remote) {
+            // SSH tunnels are local and were probed above
+            let (mut remote, tunnels): (Vec<_>, Vec<_>) =
+                e.into_iter().partition(|e| e.source != PortSource::Ssh);
+            timed(
+                &mut timings,
+                "probe",
+                probe_remote(&mut remote, listeners::Target::Host(Some(host))),
+            )
+            .await;
+            for entry in &mut remote {
+                entry.is_open = entry.remote_open.unwrap_or(entry.is_open);
+            }
+            e = remote;
+            e.extend(tunnels);
+        }
         e
     };
     entries.sort_by_key(|e| (!e.is_open, e.local_port));
-    Ok(entries)
+    tracing::debug!(
+        remote_host,
+        docker_target,
+        count = entries.len(),
+        elapsed = ?started.elapsed(),
+        ?timings,
+        "collected ports"
+    );
+    Ok(Collection {
+        entries,
+        sources,
+        timings,
+    })
 }
 
-pub async fn kill_by_pid(pid: u32, remote_host: Option<&str>) -> anyhow::Result<()> {
+/// One connection to scan in [`collect_connections`].
+#[derive(Debug, Clone)]
+pub struct CollectTarget {
+    pub name: String,
+    pub remote_host: Option<String>,
+    pub docker_target: Option<String>,
+    /// Docker context of the connection; the default context when `None`.
+    pub docker_context: Option<String>,
+    pub known_forwards: HashMap<u16, u16>,
+}
+
+/// Connection name of this machine's SSH tunnels when no scanned
+/// connection is local.
+pub const LOCAL_TUNNELS: &str = "local";
+
+/// Scan several connections concurrently and merge the results.
+///
+/// Entries are tagged with their connection name and kept in target order.
+/// A source counts as failed when it failed on any connection. Connections
+/// that fail are returned with their error instead of failing the scan.
+///
+/// This machine's SSH tunnels are listed once: with the local connection,
+/// or on their own as [`LOCAL_TUNNELS`] when every connection is remote.
+pub async fn collect_connections(
+    targets: Vec<CollectTarget>,
+) -> (Collection, Vec<(String, anyhow::Error)>) {
+    scan_connections(targets, |target, scope| async move {
+        let scan = collect_scan(
+            target.remote_host.as_deref(),
+            target.docker_target.as_deref(),
+            &target.known_forwards,
+            scope,
+        );
+        docker::with_context(target.docker_context.clone(), scan).await
+    })
+    .await
+}
+
+async fn scan_connections<F, Fut>(
+    mut targets: Vec<CollectTarget>,
+    scan: F,
+) -> (Collection, Vec<(String, anyhow::Error)>)
+where
+    F: Fn(CollectTarget, Scope) -> Fut,
+    Fut: Future<Output = anyhow::Result<Collection>> + Send + 'static,
+{
+    let local = targets
+        .iter()
+        .position(|t| t.remote_host.is_none() && t.docker_target.is_none());
+    let mut scopes = vec![Scope::WithoutTunnels; targets.len()];
+    match local {
+        Some(index) => scopes[index] = Scope::All,
+        None if !targets.is_empty() => {
+            targets.push(CollectTarget {
+                name: LOCAL_TUNNELS.to_string(),
+                remote_host: None,
+                docker_target: None,
+                docker_context: None,
+                known_forwards: HashMap::new(),
+            });
+            scopes.push(Scope::Tunnels);
+        }
+        None => {}
+    }
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (order, (target, scope)) in targets.into_iter().zip(scopes).enumerate() {
+        let name = target.name.clone();
+        let scan = scan(target, scope);
+        tasks.spawn(async move { (order, name, scan.await) });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(result) = joined {
+            results.push(result);
+        }
+    }
+    results.sort_by_key(|(order, _, _)| *order);
+    merge_connection_results(results.into_iter().map(|(_, name, result)| (name, result)))
+}
+
+fn merge_connection_results(
+    results: impl IntoIterator<Item = (String, anyhow::Result<Collection>)>,
+) -> (Collection, Vec<(String, anyhow::Error)>) {
+    let mut merged = Collection::default();
+    let mut failed = Vec::new();
+    for (name, result) in results {
+        let collected = match result {
+            Ok(collected) => collected,
+            Err(e) => {
+                failed.push((name, e));
+                continue;
+            }
+        };
+        merged.timings.extend(
+            collected
+                .timings
+                .into_iter()
+                .map(|(phase, elapsed)| (format!("{name} {phase}"), elapsed)),
+        );
+        merged
+            .entries
+            .extend(collected.entries.into_iter().map(|mut e| {
+                e.connection = Some(name.clone());
+                e
+            }));
+        for (source, status) in collected.sources {
+            let status = match status {
+                SourceStatus::Failed(reason) => SourceStatus::Failed(format!("{name}: {reason}")),
+                SourceStatus::Ok => SourceStatus::Ok,
+            };
+            match merged.sources.iter_mut().find(|(s, _)| *s == source) {
+                Some((_, kept @ SourceStatus::Ok)) => *kept = status,
+                Some(_) => {}
+                None => merged.sources.push((source, status)),
+            }
+        }
+    }
+    (merged, failed)
+}
+
+/// Send `signal` to `pid` on this machine or on `remote_host`.
+pub async fn kill_by_pid(
+    pid: u32,
+    signal: Signal,
+    remote_host: Option<&str>,
+) -> anyhow::Result<()> {
+    tracing::info!(pid, %signal, remote_host, "killing process");
+    let Some(host) = remote_host else {
+        return signal::send(pid, signal);
+    };
+    let pid_str = pid.to_string();
+    let args = ["kill", "-s", signal.name(), &pid_str];
+    let output = remote_output(host, &args).await?;
+    if output.status.success() {
+        return Ok(());
+    }
+    if String::from_utf8_lossy(&output.stderr).contains("not permitted") {
+        return Err(signal::PermissionDenied { pid }.into());
+    }
+    Err(
+        anyhow::Error::new(CommandError::new(args[0], &args[1..], Some(host), &output))
+            .context(format!("Failed to kill process {pid} on {host}")),
+    )
+}
+
+/// Send `signal` to `pid` through `sudo`, locally or over `ssh -t`.
+///
+/// sudo prompts on the terminal (locally it uses `SUDO_ASKPASS` when set), so
+/// the TUI has to release the terminal first.
+pub async fn sudo_kill(pid: u32, signal: Signal, remote_host: Option<&str>) -> anyhow::Result<()> {
+    tracing::info!(pid, %signal, remote_host, "killing process with sudo");
     let pid_str = pid.to_string();
+    let args = ["sudo", "kill", "-s", signal.name(), &pid_str];
     let status = match remote_host {
-        Some(host) => ssh_cmd_tokio(host, &["kill", &pid_str]).status().await?,
         None => {
-            tokio::process::Command::new("kill")
-                .arg(&pid_str)
+            let mut cmd = tokio::process::Command::new(args[0]);
+            if std::env::var_os("SUDO_ASKPASS").is_some() {
+                cmd.arg("-A");
+            }
+            cmd.args(&args[1..]).status().await?
+        }
+        Some(host) => {
+            if ssh_backend() == SshBackend::Embedded {
+                anyhow::bail!("sudo over ssh needs ssh.backend = \"openssh\"");
+            }
+            tokio::process::Command::new("ssh")
+                .arg("-t")
+                .args(ssh::batch_args(host))
+                .arg(host)
+                .arg(escape_ssh_args(&args))
                 .status()
                 .await?
         }
@@ -253,89 +1574,440 @@ pub async fn kill_by_pid(pid: u32, remote_host: Option<&str>) -> anyhow::Result<
     if status.success() {
         Ok(())
     } else {
-        anyhow::bail!("Failed to kill process {pid}")
+        anyhow::bail!("sudo kill of PID {pid} exited with {status}")
     }
 }
 
-pub async fn kill_by_port(port: u16, remote_host: Option<&str>) -> anyhow::Result<()> {
-    let entries = collect_entries(remote_host).await?;
+/// Signal the owner of the first entry listening on `port`.
+pub async fn kill_by_port(
+    port: u16,
+    signal: Signal,
+    remote_host: Option<&str>,
+) -> anyhow::Result<()> {
+    let entries = collect_entries(remote_host).await?.entries;
     let entry = entries
         .iter()
         .find(|e| e.local_port == port)
         .ok_or_else(|| anyhow::anyhow!("No process found on port {port}"))?;
+    kill_entry(entry, signal, remote_host).await
+}
+
+/// Signal whatever holds `entry`: the process, the local ssh tunnel, or the container.
+pub async fn kill_entry(
+    entry: &PortEntry,
+    signal: Signal,
+    remote_host: Option<&str>,
+) -> anyhow::Result<()> {
+    let port = entry.local_port;
+    tracing::info!(port, source = %entry.source, %signal, remote_host, "killing port owner");
 
     match entry.source {
         PortSource::Ssh => {
-            // SSH tunnel processes are always local
+            // Embedded forwards run inside this process; stop the listener instead
+            #[cfg(feature = "embedded-ssh")]
+            if embedded::stop_forward(port) {
+                return Ok(());
+            }
+            // SSH tunnel processes are always local; never signal quay itself
+            if entry.pid == Some(std::process::id()) {
+                anyhow::bail!("Port {port} is served by quay itself")
+            }
             if let Some(pid) = entry.pid {
-                kill_by_pid(pid, None).await
+                kill_by_pid(pid, signal, None).await
             } else {
                 anyhow::bail!("No PID found for port {port}")
             }
         }
         PortSource::Local => {
             if let Some(pid) = entry.pid {
-                kill_by_pid(pid, remote_host).await
+                kill_by_pid(pid, signal, remote_host).await
             } else {
                 anyhow::bail!("No PID found for port {port}")
             }
         }
         PortSource::Docker => {
             if let Some(ref container_id) = entry.container_id {
-                let status = match remote_host {
-                    Some(host) => {
-                        ssh_cmd_tokio(host, &["docker", "stop", container_id])
-                            .status()
-                            .await?
-                    }
-                    None => {
-                        tokio::process::Command::new("docker")
-                            .args(["stop", container_id])
-                            .status()
-                            .await?
-                    }
+                // SIGTERM keeps the graceful `docker stop`; other signals go through `docker kill`
+                let signal_arg = format!("--signal={}", signal.name());
+                let args: Vec<&str> = if signal == Signal::Term {
+                    vec!["stop", container_id]
+                } else {
+                    vec!["kill", &signal_arg, container_id]
                 };
-                if status.success() {
+                let output = docker::run(&args, remote_host).await?;
+                if output.status.success() {
                     Ok(())
                 } else {
                     anyhow::bail!("Failed to stop container {container_id}")
                 }
+            } else if let Some(pid) = entry.pid {
+                // A process inside the Docker target, by its host PID
+                kill_by_pid(pid, signal, remote_host).await
             } else {
-                anyhow::bail!("No container ID found for port {port}")
+                anyhow::bail!("No container ID or PID found for port {port}")
             }
         }
+        PortSource::Scan => anyhow::bail!("Port {port} was found by a scan; its owner is unknown"),
     }
 }
 
+/// Entries to kill for `ports` and processes whose name contains `name`.
+///
+/// A process or container holding several matching ports is listed once.
+/// Every requested port must be held by something.
+pub fn select_kill_targets(
+    entries: &[PortEntry],
+    ports: &[u16],
+    name: Option<&str>,
+) -> anyhow::Result<Vec<PortEntry>> {
+    let mut targets: Vec<PortEntry> = Vec::new();
+    for &port in ports {
+        let entry = entries
+            .iter()
+            .find(|e| e.local_port == port)
+            .ok_or_else(|| anyhow::anyhow!("No process found on port {port}"))?;
+        targets.push(entry.clone());
+    }
+    if let Some(name) = name {
+        let needle = name.to_lowercase();
+        let matched: Vec<&PortEntry> = entries
+            .iter()
+            .filter(|e| {
+                e.process_name.to_lowercase().contains(&needle)
+                    || e.container_name
+                        .as_deref()
+                        .is_some_and(|c| c.to_lowercase().contains(&needle))
+            })
+            .collect();
+        if matched.is_empty() {
+            anyhow::bail!("No process matching '{name}' holds a port");
+        }
+        targets.extend(matched.into_iter().cloned());
+    }
+
+    let mut seen = HashSet::new();
+    targets.retain(|e| {
+        let owner = match (&e.container_id, e.pid) {
+            (Some(id), _) => format!("container:{id}"),
+            (None, Some(pid)) => format!("pid:{pid}"),
+            (None, None) => format!("port:{}", e.local_port),
+        };
+        seen.insert(owner)
+    });
+    Ok(targets)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::row;
 
-    fn make_entry(source: PortSource, local_port: u16) -> PortEntry {
-        PortEntry {
-            source,
-            local_port,
-            remote_host: None,
-            remote_port: None,
-            process_name: String::new(),
-            pid: None,
-            container_id: None,
-            container_name: None,
-            ssh_host: None,
-            is_open: false,
-            is_loopback: false,
-            forwarded_port: None,
-        }
+    #[tokio::test]
+    async fn test_scan_connections_lists_tunnels_once() {
+        let scan = |target: CollectTarget, scope: Scope| async move {
+            let mut entries = Vec::new();
+            if scope != Scope::Tunnels {
+                let port = if target.remote_host.is_some() {
+                    8080
+                } else {
+                    3000
+                };
+                entries.push(row(PortSource::Local, port));
+            }
+            if scope != Scope::WithoutTunnels {
+                entries.push(row(PortSource::Ssh, 15432));
+            }
+            Ok(Collection {
+                entries,
+                ..Collection::default()
+            })
+        };
+        let target = |name: &str, remote_host: Option<&str>| CollectTarget {
+            name: name.to_string(),
+            remote_host: remote_host.map(str::to_string),
+            docker_target: None,
+            docker_context: None,
+            known_forwards: HashMap::new(),
+        };
+        let tunnels = |collection: &Collection| -> Vec<Option<String>> {
+            collection
+                .entries
+                .iter()
+                .filter(|e| e.source == PortSource::Ssh)
+                .map(|e| e.connection.clone())
+                .collect()
+        };
+
+        let (remote_only, _) = scan_connections(
+            vec![target("web", Some("web1")), target("db", Some("db1"))],
+            scan,
+        )
+        .await;
+        assert_eq!(tunnels(&remote_only), [Some(LOCAL_TUNNELS.to_string())]);
+        assert_eq!(remote_only.entries.len(), 3);
+
+        let (with_local, _) = scan_connections(
+            vec![
+                target("Prod", Some("web1")),
+                target("Local", None),
+                target("Lab", Some("lab")),
+            ],
+            scan,
+        )
+        .await;
+        assert_eq!(tunnels(&with_local), [Some("Local".to_string())]);
+        assert_eq!(with_local.entries.len(), 4);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_dedup_ssh_overrides_local() {
-        let mut entries = vec![
-            make_entry(PortSource::Local, 9000),
-            make_entry(PortSource::Ssh, 9000),
-        ];
+    fn test_command_error_remote() {
+        use std::os::unix::process::ExitStatusExt;
+        let output = Output {
+            status: std::process::ExitStatus::from_raw(255 << 8),
+            stdout: Vec::new(),
+            stderr: b"\nssh: connect to host lab port 22: Connection refused\n".to_vec(),
+        };
+        let err = CommandError::new("lsof", &["-i", "-Fcpn"], Some("lab"), &output);
+        assert_eq!(err.command, "ssh lab lsof -i -Fcpn");
+        assert_eq!(err.exit_code, Some(255));
+        assert_eq!(
+            err.to_string(),
+            "exited with code 255: ssh: connect to host lab port 22: Connection refused"
+        );
+    }
+
+    #[test]
+    fn test_select_kill_targets() {
+        let mut node_a = row(PortSource::Local, 3000);
+        node_a.process_name = "node".to_string();
+        node_a.pid = Some(10);
+        let mut node_b = row(PortSource::Local, 3001);
+        node_b.process_name = "Node".to_string();
+        node_b.pid = Some(10);
+        let mut vite = row(PortSource::Local, 5173);
+        vite.process_name = "node".to_string();
+        vite.pid = Some(11);
+        let mut pg = row(PortSource::Docker, 5432);
+        pg.container_id = Some("abc".to_string());
+        pg.container_name = Some("postgres".to_string());
+        let entries = vec![node_a, node_b, vite, pg];
+
+        let ports = |targets: Vec<PortEntry>| -> Vec<u16> {
+            targets.iter().map(|e| e.local_port).collect()
+        };
+        assert_eq!(
+            ports(select_kill_targets(&entries, &[], Some("node")).unwrap()),
+            vec![3000, 5173]
+        );
+        assert_eq!(
+            ports(select_kill_targets(&entries, &[5432, 3001], Some("NODE")).unwrap()),
+            vec![5432, 3001, 5173]
+        );
+        assert_eq!(
+            ports(select_kill_targets(&entries, &[], Some("postgres")).unwrap()),
+            vec![5432]
+        );
+        assert!(select_kill_targets(&entries, &[9999], None).is_err());
+        assert!(select_kill_targets(&entries, &[], Some("ruby")).is_err());
+    }
+
+    #[test]
+    fn test_is_owned_by_other() {
+        let mut entry = row(PortSource::Local, 80);
+        assert!(!entry.is_owned_by_other("alice"));
+        entry.user = Some("root".to_string());
+        assert!(entry.is_owned_by_other("alice"));
+        assert!(!entry.is_owned_by_other("root"));
+        entry.user = Some("develop+".to_string());
+        assert!(!entry.is_owned_by_other("developer"));
+        assert!(entry.is_owned_by_other("alice"));
+    }
+
+    #[test]
+    fn test_merge_connection_results() {
+        let collection = |entries: Vec<PortEntry>, docker: SourceStatus| Collection {
+            entries,
+            sources: vec![
+                (PortSource::Local, SourceStatus::Ok),
+                (PortSource::Docker, docker),
+            ],
+            timings: vec![("ssh".to_string(), Duration::from_millis(20))],
+        };
+        let (merged, failed) = merge_connection_results([
+            (
+                "Local".to_string(),
+                Ok(collection(
+                    vec![row(PortSource::Local, 3000)],
+                    SourceStatus::Ok,
+                )),
+            ),
+            ("Prod".to_string(), Err(anyhow::anyhow!("ssh failed"))),
+            (
+                "Lab".to_string(),
+                Ok(collection(
+                    vec![row(PortSource::Local, 8080), row(PortSource::Docker, 5432)],
+                    SourceStatus::Failed("daemon down".to_string()),
+                )),
+            ),
+        ]);
+        let tagged: Vec<_> = merged
+            .entries
+            .iter()
+            .map(|e| (e.connection.as_deref(), e.local_port))
+            .collect();
+        assert_eq!(
+            tagged,
+            vec![
+                (Some("Local"), 3000),
+                (Some("Lab"), 8080),
+                (Some("Lab"), 5432)
+            ]
+        );
+        assert_eq!(
+            merged.sources,
+            vec![
+                (PortSource::Local, SourceStatus::Ok),
+                (
+                    PortSource::Docker,
+                    SourceStatus::Failed("Lab: daemon down".to_string())
+                ),
+            ]
+        );
+        let phases: Vec<&str> = merged.timings.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(phases, ["Local ssh", "Lab ssh"]);
+        let failed: Vec<_> = failed
+            .iter()
+            .map(|(name, e)| format!("{name}: {e}"))
+            .collect();
+        assert_eq!(failed, vec!["Prod: ssh failed"]);
+    }
+
+    #[test]
+    fn test_probe_addrs() {
+        let addrs = probe_addrs(Some("*"), 8080);
+        assert_eq!(
+            addrs,
+            vec![
+                "127.0.0.1:8080".parse().unwrap(),
+                "[::1]:8080".parse().unwrap()
+            ]
+        );
+        assert_eq!(probe_addrs(None, 8080), addrs);
+        assert_eq!(
+            probe_addrs(Some("192.168.1.5"), 80),
+            vec!["192.168.1.5:80".parse().unwrap()]
+        );
+        assert_eq!(
+            probe_addrs(Some("[::1]"), 80),
+            vec!["[::1]:80".parse().unwrap()]
+        );
+        assert!(probe_addrs(Some("[fe80::1%eth0]"), 80).is_empty());
+    }
+
+    #[test]
+    fn test_check_tunnel() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        // Stand-ins for ssh's local end: one closing at once as ssh does when
+        // the destination is unreachable, one relaying a banner
+        let listen = |reply: Option<&'static [u8]>| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                if let Some(reply) = reply {
+                    stream.write_all(reply).unwrap();
+                }
+            });
+            addr
+        };
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
 
-        dedup_entries(&mut entries);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let wait = Duration::from_secs(5);
+        let check = |addr| runtime.block_on(check_tunnel(&[addr], wait));
+        assert_eq!(check(listen(None)), (TunnelHealth::LocalOnly, None));
+        let (health, latency) = check(listen(Some(b"SSH-2.0-x\r\n")));
+        assert_eq!(health, TunnelHealth::Healthy);
+        assert!(latency.is_some_and(|latency| latency < wait));
+        assert_eq!(check(closed), (TunnelHealth::Down, None));
+
+        let mut tunnel = row(PortSource::Ssh, 5432);
+        assert!(is_local_forward(&tunnel));
+        tunnel.remote_host = Some("(R) localhost:5432".to_string());
+        assert!(!is_local_forward(&tunnel));
+        assert!(!is_local_forward(&row(PortSource::Local, 5432)));
+        assert!(is_reverse_forward(&tunnel));
+        assert!(!is_reverse_forward(&row(PortSource::Local, 5432)));
+    }
+
+    #[test]
+    fn test_binds_by_port() {
+        // sshd of an -R forward with GatewayPorts off, and one with it on
+        let output = "State  Recv-Q Send-Q Local Address:Port Peer Address:Port Process\n\
+                      LISTEN 0      128    127.0.0.1:9090     0.0.0.0:*\n\
+                      LISTEN 0      128    [::1]:9090         [::]:*\n\
+                      LISTEN 0      128    0.0.0.0:8080       0.0.0.0:*\n\
+                      LISTEN 0      128    [::]:8080          [::]:*\n";
+        let binds = binds_by_port(listeners::Collector::Ss, output);
+        assert_eq!(
+            binds[&9090],
+            vec!["127.0.0.1".to_string(), "[::1]".to_string()]
+        );
+        assert_eq!(binds[&8080], vec!["*".to_string()]);
+        assert!(!binds.contains_key(&22));
+    }
+
+    #[test]
+    fn test_remote_probe_targets() {
+        let mut loopback = row(PortSource::Local, 5432);
+        loopback.bind = Some("127.0.0.1".to_string());
+        let mut scoped = row(PortSource::Local, 80);
+        scoped.bind = Some("fe80::1%eth0".to_string());
+        let targets = remote_probe_targets(&[row(PortSource::Docker, 8080), loopback, scoped]);
+        assert_eq!(
+            targets,
+            vec![
+                vec!["127.0.0.1:8080".to_string(), "::1:8080".to_string()],
+                vec!["127.0.0.1:5432".to_string()],
+                Vec::new(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_display() {
+        let mut entry = row(PortSource::Local, 8080);
+        assert_eq!(entry.local_display(), ":8080");
+        entry.bind = Some("*".to_string());
+        assert_eq!(entry.local_display(), ":8080");
+        entry.bind = Some("[::1]".to_string());
+        assert_eq!(entry.local_display(), "[::1]:8080");
+    }
+
+    #[test]
+    fn test_latency_display() {
+        let mut entry = row(PortSource::Ssh, 5432);
+        assert_eq!(entry.latency_display(), "");
+        entry.latency = Some(Duration::from_micros(300));
+        assert_eq!(entry.latency_display(), "<1 ms");
+        entry.latency = Some(Duration::from_micros(42_700));
+        assert_eq!(entry.latency_display(), "42 ms");
+    }
+
+    #[test]
+    fn test_merge_ssh_overrides_local() {
+        let mut entries = vec![row(PortSource::Local, 9000), row(PortSource::Ssh, 9000)];
+
+        merge_entries(&mut entries, false);
 
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].source, PortSource::Ssh);
@@ -343,13 +2015,10 @@ mod tests {
     }
 
     #[test]
-    fn test_dedup_docker_overrides_local() {
-        let mut entries = vec![
-            make_entry(PortSource::Local, 8080),
-            make_entry(PortSource::Docker, 8080),
-        ];
+    fn test_merge_docker_overrides_local() {
+        let mut entries = vec![row(PortSource::Local, 8080), row(PortSource::Docker, 8080)];
 
-        dedup_entries(&mut entries);
+        merge_entries(&mut entries, false);
 
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].source, PortSource::Docker);
@@ -357,37 +2026,160 @@ mod tests {
     }
 
     #[test]
-    fn test_dedup_no_overlap() {
+    fn test_merge_keeps_listeners_on_other_binds() {
+        let listener = |bind: &str, pid: u32| PortEntry {
+            bind: Some(bind.to_string()),
+            pid: Some(pid),
+            ..row(PortSource::Local, 5432)
+        };
+        for base in [PortSource::Docker, PortSource::Ssh] {
+            let mut merged = row(base.clone(), 5432);
+            if base == PortSource::Docker {
+                merged.bind = Some("127.0.0.1".to_string());
+            }
+            let mut entries = vec![listener("127.0.0.1", 100), listener("[::1]", 200), merged];
+
+            merge_entries(&mut entries, false);
+
+            let rows: Vec<_> = entries
+                .iter()
+                .map(|e| (e.source.clone(), e.bind.as_deref(), e.pid))
+                .collect();
+            assert_eq!(
+                rows,
+                vec![
+                    (PortSource::Local, Some("[::1]"), Some(200)),
+                    (base, Some("127.0.0.1"), Some(100)),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_docker_proxy_into_docker() {
+        let mut proxy = row(PortSource::Local, 5432);
+        proxy.process_name = "docker-proxy".to_string();
+        proxy.pid = Some(2100);
+        proxy.user = Some("root".to_string());
+        proxy.is_loopback = true;
+        let mut docker = row(PortSource::Docker, 5432);
+        docker.container_name = Some("db".to_string());
+        let mut other = row(PortSource::Local, 8080);
+        other.process_name = "node".to_string();
+        other.pid = Some(77);
+        other.user = Some("alice".to_string());
+        let mut entries = vec![proxy, docker, other, row(PortSource::Docker, 8080)];
+
+        merge_entries(&mut entries, false);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source, PortSource::Docker);
+        assert_eq!(entries[0].container_name.as_deref(), Some("db"));
+        assert_eq!(entries[0].pid, Some(2100));
+        assert_eq!(entries[0].user, None);
+        assert!(entries[0].is_loopback);
+        // Any other owner is kept
+        assert_eq!(entries[1].pid, Some(77));
+        assert_eq!(entries[1].user.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let mut local = row(PortSource::Local, 5432);
+        local.process_name = "ssh".to_string();
+        local.pid = Some(900);
+        local.is_open = true;
+        local.is_loopback = true;
+        let mut docker = row(PortSource::Docker, 5432);
+        docker.container_id = Some("abc123".to_string());
+        docker.container_name = Some("db".to_string());
+        let mut ssh = row(PortSource::Ssh, 5432);
+        ssh.process_name = "ssh".to_string();
+        ssh.pid = Some(1);
+        ssh.remote_host = Some("db.internal".to_string());
+        ssh.remote_port = Some(5432);
+        ssh.ssh_host = Some("bastion".to_string());
+        let mut entries = vec![local, docker, ssh];
+
+        merge_entries(&mut entries, false);
+
+        assert_eq!(entries.len(), 1);
+        let merged = &entries[0];
+        assert_eq!(merged.source, PortSource::Ssh);
+        // PID from LOCAL, container from DOCKER, tunnel spec from SSH
+        assert_eq!(merged.pid, Some(900));
+        assert_eq!(merged.container_name.as_deref(), Some("db"));
+        assert_eq!(merged.container_id.as_deref(), Some("abc123"));
+        assert_eq!(merged.remote_display(), "db.internal:5432");
+        assert_eq!(merged.ssh_host.as_deref(), Some("bastion"));
+        assert!(merged.is_open);
+        assert!(merged.is_loopback);
+    }
+
+    #[test]
+    fn test_merge_remote_mode_keeps_tunnels_apart() {
+        let mut local = row(PortSource::Local, 5432);
+        local.pid = Some(900);
+        let mut ssh = row(PortSource::Ssh, 5432);
+        ssh.pid = Some(1);
+        let mut entries = vec![
+            local,
+            ssh,
+            row(PortSource::Docker, 5432),
+            row(PortSource::Local, 8080),
+            row(PortSource::Docker, 8080),
+        ];
+
+        merge_entries(&mut entries, true);
+
+        // The remote container on 5432 is not the local tunnel
+        let sources: Vec<_> = entries
+            .iter()
+            .map(|e| (e.source.clone(), e.local_port))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (PortSource::Ssh, 5432),
+                (PortSource::Docker, 5432),
+                (PortSource::Docker, 8080)
+            ]
+        );
+        assert_eq!(entries[0].pid, Some(1));
+    }
+
+    #[test]
+    fn test_merge_no_overlap() {
         let mut entries = vec![
-            make_entry(PortSource::Local, 3000),
-            make_entry(PortSource::Ssh, 9000),
-            make_entry(PortSource::Docker, 8080),
+            row(PortSource::Local, 3000),
+            row(PortSource::Ssh, 9000),
+            row(PortSource::Docker, 8080),
         ];
 
-        dedup_entries(&mut entries);
+        merge_entries(&mut entries, false);
 
         assert_eq!(entries.len(), 3);
     }
 
     /// Simulates the SSH tunnel merge logic used in Docker Target remote mode:
     /// In remote mode, probe is skipped (it would false-positive on SSH tunnel
-    /// local_ports), so accessibility is determined solely by SSH tunnel
-    /// remote_port matching the container's listening port.
-    /// e.g. `ssh -L 3000:container_ip:8080` → remote_port=8080 matches Docker port 8080.
+    /// `local_port`s), so accessibility is determined solely by SSH tunnel
+    /// `remote_port` matching the container's listening port.
+    /// e.g. `ssh -L 3000:container_ip:8080` → `remote_port=8080` matches Docker port 8080.
     #[test]
     fn test_ssh_tunnel_merge_marks_matching_ports_open() {
         // Docker entries from remote container (all start with is_open=false, no probe)
         let mut docker_entries = vec![
-            make_entry(PortSource::Docker, 8080), // SSH tunnel targets this port
-            make_entry(PortSource::Docker, 3000), // SSH tunnel local_port=3000, but remote_port=8080
-            make_entry(PortSource::Docker, 5432), // no SSH tunnel
+            row(PortSource::Docker, 8080), // SSH tunnel targets this port
+            row(PortSource::Docker, 3000), // SSH tunnel local_port=3000, but remote_port=8080
+            row(PortSource::Docker, 5432), // no SSH tunnel
         ];
 
         // SSH tunnel: local_port=3000, remote_port=8080 (forwards to container port 8080)
         // Without the fix, probing 127.0.0.1:3000 would succeed (tunnel listens there)
         // and Docker port 3000 would be marked open — a false positive.
-        let ssh_entries = vec![{
-            let mut e = make_entry(PortSource::Ssh, 3000);
+        let ssh_entries = [{
+            let mut e = row(PortSource::Ssh, 3000);
             e.remote_port = Some(8080);
             e
         }];
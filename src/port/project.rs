@@ -0,0 +1,244 @@
+//! The project a local dev server was started from.
+//!
+//! A process's working directory (or the nearest parent below `$HOME`) with a
+//! package.json, Cargo.toml, or pyproject.toml names the repo it serves, so
+//! `:3000` reads "storefront (node)" instead of just "node". Working
+//! directories come from `/proc` on Linux and from `lsof -d cwd` elsewhere.
+
+use super::{PortEntry, PortSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Which manifest a project was recognized by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectKind {
+    /// package.json
+    Node,
+    /// Cargo.toml
+    Rust,
+    /// pyproject.toml
+    Python,
+}
+
+impl ProjectKind {
+    const ALL: [Self; 3] = [Self::Node, Self::Rust, Self::Python];
+
+    fn manifest(self) -> &'static str {
+        match self {
+            ProjectKind::Node => "package.json",
+            ProjectKind::Rust => "Cargo.toml",
+            ProjectKind::Python => "pyproject.toml",
+        }
+    }
+
+    /// Package name declared in `content`, the manifest's text.
+    fn package_name(self, content: &str) -> Option<String> {
+        let name = match self {
+            ProjectKind::Node => serde_json::from_str::<serde_json::Value>(content)
+                .ok()?
+                .get("name")?
+                .as_str()?
+                .to_string(),
+            ProjectKind::Rust => {
+                let table: toml::Table = toml::from_str(content).ok()?;
+                table.get("package")?.get("name")?.as_str()?.to_string()
+            }
+            ProjectKind::Python => {
+                let table: toml::Table = toml::from_str(content).ok()?;
+                let poetry = table.get("tool").and_then(|t| t.get("poetry"));
+                table
+                    .get("project")
+                    .or(poetry)?
+                    .get("name")?
+                    .as_str()?
+                    .to_string()
+            }
+        };
+        Some(name).filter(|n| !n.trim().is_empty())
+    }
+}
+
+impl fmt::Display for ProjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectKind::Node => write!(f, "node"),
+            ProjectKind::Rust => write!(f, "rust"),
+            ProjectKind::Python => write!(f, "python"),
+        }
+    }
+}
+
+/// A project directory holding a known manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    /// Package name, or the directory name when the manifest has none
+    /// (e.g. a Cargo workspace root).
+    pub name: String,
+    pub kind: ProjectKind,
+    pub dir: PathBuf,
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.kind)
+    }
+}
+
+/// The project `dir` belongs to: the first of it and its parents with a
+/// manifest, never `/` or `home` itself.
+pub fn detect(dir: &Path, home: Option<&Path>) -> Option<Project> {
+    dir.ancestors()
+        .take_while(|d| d.parent().is_some() && Some(*d) != home)
+        .find_map(|d| {
+            ProjectKind::ALL.into_iter().find_map(|kind| {
+                let content = std::fs::read_to_string(d.join(kind.manifest())).ok()?;
+                let name = kind
+                    .package_name(&content)
+                    .or_else(|| d.file_name().map(|n| n.to_string_lossy().into_owned()))?;
+                Some(Project {
+                    name,
+                    kind,
+                    dir: d.to_path_buf(),
+                })
+            })
+        })
+}
+
+/// Working directories of `pids`; processes that can't be inspected (another
+/// user's, without root) are left out.
+async fn cwds(pids: &[u32]) -> HashMap<u32, PathBuf> {
+    if cfg!(target_os = "linux") {
+        return pids
+            .iter()
+            .filter_map(|&pid| {
+                let cwd = std::fs::read_link(format!("/proc/{pid}/cwd")).ok()?;
+                Some((pid, cwd))
+            })
+            .collect();
+    }
+    let list = pids
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let output = tokio::process::Command::new("lsof")
+        .args(["-a", "-d", "cwd", "-Fpn", "-p", &list])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await;
+    match output {
+        Ok(out) => parse_lsof_cwds(&String::from_utf8_lossy(&out.stdout)),
+        Err(e) => {
+            tracing::debug!(error = %e, "could not list working directories");
+            HashMap::new()
+        }
+    }
+}
+
+/// Parse `lsof -a -d cwd -Fpn` output.
+///
+/// ```text
+/// p4242
+/// fcwd
+/// n/home/alice/src/storefront
+/// ```
+fn parse_lsof_cwds(output: &str) -> HashMap<u32, PathBuf> {
+    let mut cwds = HashMap::new();
+    let mut pid = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.parse().ok();
+        } else if let (Some(path), Some(pid)) = (line.strip_prefix('n'), pid) {
+            cwds.insert(pid, PathBuf::from(path));
+        }
+    }
+    cwds
+}
+
+/// Set `project` on the local processes of `entries` (this machine's).
+pub async fn annotate(entries: &mut [PortEntry]) {
+    let mut pids: Vec<u32> = entries
+        .iter()
+        .filter(|e| e.source == PortSource::Local)
+        .filter_map(|e| e.pid)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    if pids.is_empty() {
+        return;
+    }
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut projects: HashMap<PathBuf, Option<Project>> = HashMap::new();
+    let cwds = cwds(&pids).await;
+    for entry in entries.iter_mut().filter(|e| e.source == PortSource::Local) {
+        let Some(cwd) = entry.pid.and_then(|pid| cwds.get(&pid)) else {
+            continue;
+        };
+        let project = projects
+            .entry(cwd.clone())
+            .or_insert_with(|| detect(cwd, home.as_deref()));
+        entry.project.clone_from(project);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(
+            ProjectKind::Node.package_name(r#"{"name": "storefront", "private": true}"#),
+            Some("storefront".to_string())
+        );
+        assert_eq!(
+            ProjectKind::Rust.package_name("[workspace]\nmembers = [\"api\"]\n"),
+            None
+        );
+        assert_eq!(
+            ProjectKind::Python.package_name("[tool.poetry]\nname = \"billing\"\n"),
+            Some("billing".to_string())
+        );
+        assert_eq!(
+            ProjectKind::Python.package_name("[project]\nname = \"search\"\n"),
+            Some("search".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let root = std::env::temp_dir().join(format!("quay-project-{}", std::process::id()));
+        let app = root.join("storefront");
+        let src = app.join("src/pages");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(app.join("package.json"), r#"{"name": "@acme/storefront"}"#).unwrap();
+        let workspace = root.join("tools");
+        fs::create_dir_all(&workspace).unwrap();
+        fs::write(workspace.join("Cargo.toml"), "[workspace]\n").unwrap();
+
+        let project = detect(&src, None).unwrap();
+        assert_eq!(project.to_string(), "@acme/storefront (node)");
+        assert_eq!(project.dir, app);
+        assert_eq!(
+            detect(&workspace, None).unwrap().to_string(),
+            "tools (rust)"
+        );
+        // Nothing above the home directory counts
+        assert_eq!(detect(&src, Some(&app)), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_lsof_cwds() {
+        let cwds = parse_lsof_cwds("p4242\nfcwd\nn/home/alice/src/storefront\np7\nfcwd\nn/\n");
+        assert_eq!(
+            cwds.get(&4242),
+            Some(&PathBuf::from("/home/alice/src/storefront"))
+        );
+        assert_eq!(cwds.get(&7), Some(&PathBuf::from("/")));
+    }
+}
@@ -0,0 +1,221 @@
+//! Raw command outputs of one scan, saved to replay it offline.
+//!
+//! [`record`] runs the commands [`collect_entries`](super::collect_entries)
+//! runs and keeps what they print; [`Recording::entries`] feeds those strings
+//! to the same parsers, so a parsing bug seen on someone else's machine can be
+//! reproduced from the files they send. A recording is a directory:
+//!
+//! ```text
+//! fixture.toml    collector used and whether the scan was remote
+//! listeners.txt   output of the listener collector (lsof, ss, netstat, ...)
+//! owners.txt      ps owners of macOS netstat sockets, when listed that way
+//! docker.txt      docker ps, when Docker answered
+//! ps.txt          ps aux, where the SSH tunnels are found
+//! ```
+
+use super::listeners::{self, Collector, Target};
+use super::{PortEntry, docker, local, merge_entries, ssh};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const MANIFEST: &str = "fixture.toml";
+const LISTENERS: &str = "listeners.txt";
+const OWNERS: &str = "owners.txt";
+const DOCKER: &str = "docker.txt";
+const PS: &str = "ps.txt";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    collector: Collector,
+    remote: bool,
+}
+
+/// What the commands of one scan printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// Tool that listed the listeners.
+    pub collector: Collector,
+    /// Scanned a remote host, whose listeners count as open.
+    pub remote: bool,
+    pub listeners: String,
+    pub owners: Option<String>,
+    /// `None` when Docker is missing or its daemon is down.
+    pub docker: Option<String>,
+    pub ps: Option<String>,
+}
+
+/// Run the scan commands on `remote_host` (or this machine) and keep their output.
+///
+/// Only the listener collector must succeed; Docker and `ps` are left out
+/// when they fail, as a collection would skip them.
+pub async fn record(remote_host: Option<&str>) -> Result<Recording> {
+    let chain = Collector::host_chain(remote_host);
+    let (collector, listeners) = listeners::collect(Target::Host(remote_host), chain)
+        .await
+        .context("Port scan failed")?;
+    let remote = remote_host.is_some();
+    let owners = if collector == Collector::NetstatMacos {
+        local::owners_output(&local::parse(collector, &listeners, remote)).await
+    } else {
+        None
+    };
+    let docker = docker::ps_output(remote_host)
+        .await
+        .inspect_err(|e| tracing::debug!(error = %format!("{e:#}"), "docker ps not recorded"))
+        .ok();
+    let ps = ssh::ps_output()
+        .await
+        .inspect_err(|e| tracing::debug!(error = %format!("{e:#}"), "ps not recorded"))
+        .ok();
+    Ok(Recording {
+        collector,
+        remote,
+        listeners,
+        owners,
+        docker,
+        ps,
+    })
+}
+
+impl Recording {
+    /// Write the recording into `dir`, creating it if needed.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let manifest = Manifest {
+            collector: self.collector,
+            remote: self.remote,
+        };
+        let files = [
+            (MANIFEST, Some(toml::to_string(&manifest)?)),
+            (LISTENERS, Some(self.listeners.clone())),
+            (OWNERS, self.owners.clone()),
+            (DOCKER, self.docker.clone()),
+            (PS, self.ps.clone()),
+        ];
+        for (name, content) in files {
+            let path = dir.join(name);
+            match content {
+                Some(content) => std::fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                // A stale file from an earlier recording would be replayed
+                None if path.exists() => std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a recording saved by [`Recording::save`].
+    pub fn load(dir: &Path) -> Result<Self> {
+        let manifest = dir.join(MANIFEST);
+        let content = std::fs::read_to_string(&manifest)
+            .with_context(|| format!("Failed to read {}", manifest.display()))?;
+        let manifest: Manifest = toml::from_str(&content)
+            .with_context(|| format!("Invalid fixture manifest {}", manifest.display()))?;
+        let read = |name: &str| -> Result<Option<String>> {
+            let path = dir.join(name);
+            match std::fs::read_to_string(&path) {
+                Ok(content) => Ok(Some(content)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => {
+                    Err(anyhow::Error::new(e).context(format!("Failed to read {}", path.display())))
+                }
+            }
+        };
+        Ok(Self {
+            collector: manifest.collector,
+            remote: manifest.remote,
+            listeners: read(LISTENERS)?.unwrap_or_default(),
+            owners: read(OWNERS)?,
+            docker: read(DOCKER)?,
+            ps: read(PS)?,
+        })
+    }
+
+    /// The entries a collection would have listed from these outputs, open
+    /// ports first.
+    ///
+    /// Nothing is probed offline, so every recorded listener counts as open.
+    pub fn entries(&self) -> Result<Vec<PortEntry>> {
+        let mut entries = local::parse(self.collector, &self.listeners, self.remote);
+        if let Some(owners) = &self.owners {
+            local::apply_owners(&mut entries, owners);
+        }
+        if let Some(output) = &self.docker {
+            entries.extend(docker::parse_docker_ps(output, self.remote)?);
+        }
+        if let Some(output) = &self.ps {
+            entries.extend(ssh::parse_ssh_forwards(output)?);
+        }
+        merge_entries(&mut entries, self.remote);
+        for entry in &mut entries {
+            entry.is_open = true;
+        }
+        entries.sort_by_key(|e| (!e.is_open, e.local_port));
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::PortSource;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let recording = Recording {
+            collector: Collector::Ss,
+            remote: false,
+            listeners: "State  Recv-Q Send-Q Local Address:Port Peer Address:Port Process\n\
+                        LISTEN 0      511    127.0.0.1:3000     0.0.0.0:*     users:((\"node\",pid=4242,fd=20))\n"
+                .to_string(),
+            owners: None,
+            docker: Some("abc123def456\tpostgres\t0.0.0.0:5432->5432/tcp\n".to_string()),
+            ps: Some(
+                "me  999  0.0  0.1  1234  567 ??  S  10:00  0:00.01 ssh -N -L 8080:localhost:80 devbox\n"
+                    .to_string(),
+            ),
+        };
+        let dir = std::env::temp_dir().join(format!("quay-recording-{}", std::process::id()));
+        // A file left over from an earlier recording must not be replayed
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(OWNERS), "1 root stale").unwrap();
+        recording.save(&dir).unwrap();
+        let loaded = Recording::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, recording);
+
+        let entries = loaded.entries().unwrap();
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.local_port,
+                    e.source.clone(),
+                    e.process_name.as_str(),
+                    e.is_open,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (3000, PortSource::Local, "node", true),
+                (5432, PortSource::Docker, "postgres", true),
+                (8080, PortSource::Ssh, "ssh", true),
+            ]
+        );
+        assert_eq!(entries[0].collector, Some(Collector::Ss));
+    }
+
+    #[test]
+    fn test_load_rejects_missing_manifest() {
+        let dir = std::env::temp_dir().join("quay-recording-does-not-exist");
+        let err = Recording::load(&dir).unwrap_err();
+        assert!(format!("{err:#}").contains("fixture.toml"));
+    }
+}
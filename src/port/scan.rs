@@ -0,0 +1,227 @@
+//! Active port scanning, for hosts where nothing lists the listeners.
+//!
+//! Instead of asking lsof/ss/docker, connect to every port of a range from
+//! here and keep the ones that accept. Attempts are capped both in flight
+//! (`concurrency`) and per second (`rate`), so scanning a subnet doesn't flood
+//! the network or trip an IDS. Results become `PortSource::Scan` entries.
+
+use super::{PortEntry, PortSource};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+
+/// Largest number of host/port pairs one scan may try.
+const MAX_TARGETS: usize = 1 << 20;
+
+/// How hard a scan may push.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Connection attempts in flight at once.
+    pub concurrency: usize,
+    /// Connection attempts started per second; `None` for no limit.
+    pub rate: Option<u32>,
+    /// How long to wait for a port to accept.
+    pub timeout: Duration,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 128,
+            rate: Some(1000),
+            timeout: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Ports of a spec like `8000-9000` or `22,80,8000-8100`, sorted and deduplicated.
+pub fn parse_ports(spec: &str) -> anyhow::Result<Vec<u16>> {
+    let mut ports = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let parse = |s: &str| -> anyhow::Result<u16> {
+            match s.trim().parse::<u16>() {
+                Ok(0) | Err(_) => anyhow::bail!("Invalid port '{}' in '{spec}'", s.trim()),
+                Ok(port) => Ok(port),
+            }
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start > end {
+            anyhow::bail!("Port range '{part}' runs backwards");
+        }
+        ports.extend(start..=end);
+    }
+    if ports.is_empty() {
+        anyhow::bail!("No ports given (e.g. 8000-9000 or 22,80,443)");
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(ports)
+}
+
+/// Hosts of `spec`: the addresses of an IPv4 CIDR block like `10.0.0.0/24`
+/// (without network and broadcast addresses), or `spec` itself.
+pub fn parse_hosts(spec: &str) -> anyhow::Result<Vec<String>> {
+    let Some((addr, prefix)) = spec.split_once('/') else {
+        return Ok(vec![spec.to_string()]);
+    };
+    let addr: Ipv4Addr = addr
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid CIDR '{spec}': only IPv4 blocks are supported"))?;
+    let prefix: u32 = match prefix.parse() {
+        Ok(prefix) if prefix <= 32 => prefix,
+        _ => anyhow::bail!("Invalid prefix length in '{spec}'"),
+    };
+    if prefix < 16 {
+        anyhow::bail!("CIDR block '{spec}' is too large; use /16 or smaller");
+    }
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    let network = u32::from(addr) & mask;
+    let broadcast = network | !mask;
+    let hosts = if prefix >= 31 {
+        network..=broadcast
+    } else {
+        network + 1..=broadcast - 1
+    };
+    Ok(hosts.map(|ip| Ipv4Addr::from(ip).to_string()).collect())
+}
+
+/// Try every port of `ports` on every host of `hosts`; returns the open pairs
+/// in host order, then port order.
+pub async fn scan(
+    hosts: &[String],
+    ports: &[u16],
+    options: &ScanOptions,
+) -> anyhow::Result<Vec<(String, u16)>> {
+    let total = hosts.len().saturating_mul(ports.len());
+    if total > MAX_TARGETS {
+        anyhow::bail!("Scan of {total} host/port pairs is too large (limit {MAX_TARGETS})");
+    }
+    tracing::info!(
+        hosts = hosts.len(),
+        ports = ports.len(),
+        ?options,
+        "scanning"
+    );
+
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut ticker = options
+        .rate
+        .filter(|&rate| rate > 0)
+        .map(|rate| tokio::time::interval(Duration::from_secs(1) / rate));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, host) in hosts.iter().enumerate() {
+        for &port in ports {
+            if let Some(ticker) = ticker.as_mut() {
+                ticker.tick().await;
+            }
+            let permit = Arc::clone(&permits).acquire_owned().await?;
+            let addr = format!("{}:{port}", bracket_ipv6(host));
+            let timeout = options.timeout;
+            tasks.spawn(async move {
+                let result = tokio::time::timeout(timeout, TcpStream::connect(addr)).await;
+                drop(permit);
+                (index, port, matches!(result, Ok(Ok(_))))
+            });
+        }
+    }
+
+    let mut open = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        if let (index, port, true) = result? {
+            open.push((index, port));
+        }
+    }
+    open.sort_unstable();
+    Ok(open
+        .into_iter()
+        .map(|(index, port)| (hosts[index].clone(), port))
+        .collect())
+}
+
+/// `host` as it goes before `:port`.
+fn bracket_ipv6(host: &str) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{ip}]"),
+        _ => host.to_string(),
+    }
+}
+
+/// Address `host` (an ssh alias, possibly `user@host`) connects to, from `ssh -G`.
+pub async fn resolve_ssh_host(host: &str) -> String {
+    let name = host.rsplit_once('@').map_or(host, |(_, name)| name);
+    let output = tokio::process::Command::new("ssh")
+        .args(["-G", name])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await;
+    output
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .find_map(|line| line.strip_prefix("hostname "))
+                .map(|h| h.trim().to_string())
+        })
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Entry for an open `port` found by a scan. `host` is shown in the REMOTE
+/// column; leave it out when the scanned host is implied (the active connection).
+pub fn scan_entry(host: Option<&str>, port: u16) -> PortEntry {
+    PortEntry {
+        source: PortSource::Scan,
+        local_port: port,
+        remote_host: host.map(str::to_string),
+        process_name: String::new(),
+        is_open: true,
+        // The bind address isn't known, only that the port answered
+        ..PortEntry::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ports() {
+        assert_eq!(
+            parse_ports("8000-8003").unwrap(),
+            vec![8000, 8001, 8002, 8003]
+        );
+        assert_eq!(
+            parse_ports("443, 22,80-81,22").unwrap(),
+            vec![22, 80, 81, 443]
+        );
+        assert!(parse_ports("9000-8000").is_err());
+        assert!(parse_ports("0-10").is_err());
+        assert!(parse_ports("80-70000").is_err());
+        assert!(parse_ports(",").is_err());
+    }
+
+    #[test]
+    fn test_parse_hosts() {
+        assert_eq!(parse_hosts("lab").unwrap(), vec!["lab"]);
+        let hosts = parse_hosts("10.0.0.17/30").unwrap();
+        assert_eq!(hosts, vec!["10.0.0.17", "10.0.0.18"]);
+        assert_eq!(parse_hosts("10.0.0.0/24").unwrap().len(), 254);
+        assert_eq!(parse_hosts("10.0.0.5/32").unwrap(), vec!["10.0.0.5"]);
+        assert!(parse_hosts("10.0.0.0/8").is_err());
+        assert!(parse_hosts("fe80::/64").is_err());
+        assert!(parse_hosts("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn test_scan_entry() {
+        let entry = scan_entry(Some("10.0.0.5"), 8080);
+        assert_eq!(entry.source, PortSource::Scan);
+        assert_eq!(entry.remote_display(), "10.0.0.5");
+        assert_eq!(scan_entry(None, 8080).remote_display(), "");
+        assert_eq!(bracket_ipv6("::1"), "[::1]");
+        assert_eq!(bracket_ipv6("10.0.0.5"), "10.0.0.5");
+    }
+}
@@ -0,0 +1,245 @@
+//! Signals for `quay kill`, sent with a syscall instead of the `kill` binary.
+//!
+//! Minimal containers often lack `kill(1)`, and Windows has no such binary; on
+//! Windows every signal terminates the process (`TerminateProcess`).
+
+use std::fmt;
+
+/// Signal sent by `quay kill`; only names that mean the same on Linux and macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Signal {
+    Hup,
+    Int,
+    Quit,
+    Kill,
+    Usr1,
+    Usr2,
+    #[default]
+    Term,
+    Stop,
+    Cont,
+}
+
+impl Signal {
+    const ALL: [Signal; 9] = [
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Kill,
+        Signal::Usr1,
+        Signal::Usr2,
+        Signal::Term,
+        Signal::Stop,
+        Signal::Cont,
+    ];
+
+    /// Name without the `SIG` prefix, as `kill -s` and `docker kill --signal` expect.
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Hup => "HUP",
+            Signal::Int => "INT",
+            Signal::Quit => "QUIT",
+            Signal::Kill => "KILL",
+            Signal::Usr1 => "USR1",
+            Signal::Usr2 => "USR2",
+            Signal::Term => "TERM",
+            Signal::Stop => "STOP",
+            Signal::Cont => "CONT",
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SIG{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Signal {
+    type Err = String;
+
+    /// Accepts `TERM`, `SIGTERM`, `term`, or one of the portable numbers (1, 2, 3, 9, 15).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        let by_number = match name {
+            "1" => Some(Signal::Hup),
+            "2" => Some(Signal::Int),
+            "3" => Some(Signal::Quit),
+            "9" => Some(Signal::Kill),
+            "15" => Some(Signal::Term),
+            _ => None,
+        };
+        by_number
+            .or_else(|| Self::ALL.into_iter().find(|sig| sig.name() == name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|sig| sig.name()).collect();
+                format!(
+                    "unknown signal '{s}' (expected one of {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The process belongs to another user; retrying through sudo may work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermissionDenied {
+    pub pid: u32,
+}
+
+impl fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Permission denied to signal PID {}", self.pid)
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
+/// Whether `error` (or anything it wraps) is a [`PermissionDenied`].
+pub fn is_permission_denied(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(<dyn std::error::Error>::is::<PermissionDenied>)
+}
+
+/// Send `signal` to local process `pid`.
+#[cfg(unix)]
+pub fn send(pid: u32, signal: Signal) -> anyhow::Result<()> {
+    use nix::errno::Errno;
+    use nix::sys::signal::{self as nix_signal, Signal as NixSignal};
+
+    let nix_signal_value = match signal {
+        Signal::Hup => NixSignal::SIGHUP,
+        Signal::Int => NixSignal::SIGINT,
+        Signal::Quit => NixSignal::SIGQUIT,
+        Signal::Kill => NixSignal::SIGKILL,
+        Signal::Usr1 => NixSignal::SIGUSR1,
+        Signal::Usr2 => NixSignal::SIGUSR2,
+        Signal::Term => NixSignal::SIGTERM,
+        Signal::Stop => NixSignal::SIGSTOP,
+        Signal::Cont => NixSignal::SIGCONT,
+    };
+    match nix_signal::kill(unix_pid(pid)?, nix_signal_value) {
+        Ok(()) => Ok(()),
+        Err(Errno::ESRCH) => anyhow::bail!("No process with PID {pid}"),
+        Err(Errno::EPERM) => Err(PermissionDenied { pid }.into()),
+        Err(e) => anyhow::bail!("Failed to send {signal} to PID {pid}: {e}"),
+    }
+}
+
+/// Send `signal` to local process `pid`; any signal terminates it.
+#[cfg(windows)]
+pub fn send(pid: u32, signal: Signal) -> anyhow::Result<()> {
+    let mut system = sysinfo::System::new();
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[sys_pid]), true);
+    let Some(process) = system.process(sys_pid) else {
+        anyhow::bail!("No process with PID {pid}");
+    };
+    if process.kill() {
+        Ok(())
+    } else {
+        anyhow::bail!("Failed to send {signal} to PID {pid}")
+    }
+}
+
+/// Whether local process `pid` exists (it may belong to another user).
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    use nix::errno::Errno;
+
+    let Ok(pid) = unix_pid(pid) else {
+        return false;
+    };
+    matches!(
+        nix::sys::signal::kill(pid, None),
+        Ok(()) | Err(Errno::EPERM)
+    )
+}
+
+/// Whether local process `pid` exists.
+#[cfg(windows)]
+pub fn is_alive(pid: u32) -> bool {
+    let mut system = sysinfo::System::new();
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[sys_pid]), true);
+    system.process(sys_pid).is_some()
+}
+
+/// Login name quay runs as; signalling another user's process needs escalation.
+#[cfg(unix)]
+pub fn current_user() -> Option<String> {
+    let uid = nix::unistd::geteuid();
+    match nix::unistd::User::from_uid(uid) {
+        Ok(Some(user)) => Some(user.name),
+        _ => std::env::var("USER").ok(),
+    }
+}
+
+/// Login name quay runs as; signalling another user's process needs escalation.
+#[cfg(windows)]
+pub fn current_user() -> Option<String> {
+    std::env::var("USERNAME").ok()
+}
+
+#[cfg(unix)]
+fn unix_pid(pid: u32) -> anyhow::Result<nix::unistd::Pid> {
+    // 0 and negative values address process groups, never a single process
+    match i32::try_from(pid) {
+        Ok(raw) if raw > 0 => Ok(nix::unistd::Pid::from_raw(raw)),
+        _ => anyhow::bail!("Invalid PID {pid}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_parse() {
+        assert_eq!("TERM".parse::<Signal>(), Ok(Signal::Term));
+        assert_eq!("sigkill".parse::<Signal>(), Ok(Signal::Kill));
+        assert_eq!("9".parse::<Signal>(), Ok(Signal::Kill));
+        assert_eq!(" hup ".parse::<Signal>(), Ok(Signal::Hup));
+        assert!("10".parse::<Signal>().is_err());
+        assert!("BOGUS".parse::<Signal>().unwrap_err().contains("TERM"));
+        assert_eq!(Signal::Int.to_string(), "SIGINT");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_to_child() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        assert!(is_alive(pid));
+        send(pid, Signal::Kill).unwrap();
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+        assert!(!is_alive(pid));
+        assert!(
+            send(pid, Signal::Term)
+                .unwrap_err()
+                .to_string()
+                .contains("No process")
+        );
+        assert!(send(0, Signal::Term).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_denied_is_detected() {
+        let error = anyhow::Error::from(PermissionDenied { pid: 1 }).context("Kill :80 failed");
+        assert!(is_permission_denied(&error));
+        assert!(!is_permission_denied(&anyhow::anyhow!(
+            "No process with PID 1"
+        )));
+        // SIGCONT is harmless; it succeeds when the tests may signal PID 1 (e.g. as root)
+        if let Err(e) = send(1, Signal::Cont) {
+            assert!(is_permission_denied(&e), "{e}");
+        }
+    }
+}
@@ -1,18 +1,369 @@
-use super::{PortEntry, PortSource};
-use anyhow::Result;
+use super::{CommandError, PortEntry, PortSource};
+use anyhow::{Context, Result};
 use regex::Regex;
 use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long [`create_forward`] waits for ssh to set the forward up and go to
+/// the background before reporting it as started.
+const FORWARD_WAIT: Duration = Duration::from_secs(3);
+
+/// What serves a forward started by [`create_forward`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Started {
+    /// A background ssh process with this PID.
+    Process(u32),
+    /// The embedded backend, inside quay; it has no PID of its own.
+    Embedded,
+}
+
+impl fmt::Display for Started {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Started::Process(pid) => write!(f, "PID {pid}"),
+            Started::Embedded => write!(f, "in-process"),
+        }
+    }
+}
 
 /// Create an SSH port forward
 /// spec format: "`local_port:remote_host:remote_port`"
-pub fn create_forward(spec: &str, host: &str, remote: bool) -> Result<u32> {
+///
+/// `options` are extra ssh arguments (`-C`, `-i key`, `-o Name=value`),
+/// passed before the forward.
+///
+/// With `ExitOnForwardFailure`, ssh only goes to the background once the
+/// forward is set up, so an exit within [`FORWARD_WAIT`] is a failure (port
+/// taken, authentication refused), reported with what ssh printed. Returns the
+/// PID of the background ssh; one still connecting when the wait ends is
+/// reported as started.
+///
+/// With the embedded backend the forward is served by this process, and
+/// [`Started::Embedded`] is returned instead of a PID.
+pub fn create_forward(spec: &str, host: &str, remote: bool, options: &[String]) -> Result<Started> {
+    #[cfg(feature = "embedded-ssh")]
+    if super::ssh_backend() == super::SshBackend::Embedded {
+        start_embedded_forward(spec, host, remote, options)?;
+        return Ok(Started::Embedded);
+    }
+    let args = forward_args(spec, host, remote, options);
+
+    tracing::info!(host, spec, remote, ?options, "starting ssh forward");
+    // stderr goes to an unlinked file rather than a pipe: the background ssh
+    // inherits it and would fail writing once nobody reads the pipe.
+    let log_path = std::env::temp_dir().join(format!(
+        "quay-ssh-{}-{}.log",
+        std::process::id(),
+        NEXT_LOG.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut log = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to create {}", log_path.display()))?;
+    let spawned = std::process::Command::new("ssh")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stderr(log.try_clone()?)
+        .spawn();
+    let _ = std::fs::remove_file(&log_path);
+    let mut child = spawned.context("Failed to run ssh")?;
+
+    let deadline = Instant::now() + FORWARD_WAIT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let pid = child.id();
+            tracing::info!(host, spec, pid, "ssh still connecting, reported as started");
+            std::thread::spawn(move || child.wait());
+            return Ok(Started::Process(pid));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    if !status.success() {
+        let mut stderr = String::new();
+        log.seek(SeekFrom::Start(0))?;
+        log.read_to_string(&mut stderr)?;
+        tracing::warn!(host, spec, %status, stderr, "ssh forward failed");
+        let output = Output {
+            status,
+            stdout: Vec::new(),
+            stderr: failure_message(&stderr).into_bytes(),
+        };
+        let words: Vec<&str> = args.iter().map(String::as_str).collect();
+        return Err(CommandError::new("ssh", &words, None, &output).into());
+    }
+    let ps = std::process::Command::new("ps")
+        .args(["-ww", "-eo", "pid=,args="])
+        .output()
+        .ok();
+    let pid = ps.and_then(|ps| background_pid(&String::from_utf8_lossy(&ps.stdout), &args));
+    Ok(Started::Process(pid.unwrap_or_else(|| child.id())))
+}
+
+/// [`create_forward`] on a blocking thread, so an async caller keeps running
+/// while ssh connects.
+pub async fn start_forward(
+    spec: &str,
+    host: &str,
+    remote: bool,
+    options: &[String],
+) -> Result<Started> {
+    let (spec, host, options) = (spec.to_string(), host.to_string(), options.to_vec());
+    tokio::task::spawn_blocking(move || create_forward(&spec, &host, remote, &options)).await?
+}
+
+/// Stop the forward the embedded backend serves on `local_port`.
+pub fn stop_embedded_forward(local_port: u16) -> Result<()> {
+    #[cfg(feature = "embedded-ssh")]
+    if super::embedded::stop_forward(local_port) {
+        return Ok(());
+    }
+    anyhow::bail!("No in-process forward on port {local_port}")
+}
+
+/// What ssh printed about a failure, without the warnings (new host keys,
+/// deprecated options) that come before it.
+fn failure_message(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .filter(|l| !l.starts_with("Warning:"))
+        .collect();
+    lines.join("\n")
+}
+
+/// Numbers the stderr files of concurrent [`create_forward`] calls.
+static NEXT_LOG: AtomicU32 = AtomicU32::new(0);
+
+/// Arguments of the `ssh` that serves a forward in the background and exits
+/// with an error when the forward can't be set up.
+pub fn forward_args(spec: &str, host: &str, remote: bool, options: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "-f".to_string(),
+        "-N".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+    ];
+    args.extend(options.iter().cloned());
     let flag = if remote { "-R" } else { "-L" };
+    args.extend([flag.to_string(), spec.to_string(), host.to_string()]);
+    args
+}
+
+/// PID of the ssh run with `args` in `ps -eo pid=,args=` output: the process
+/// `ssh -f` left in the background after its parent exited.
+fn background_pid(ps: &str, args: &[String]) -> Option<u32> {
+    let command = args.join(" ");
+    ps.lines().rev().find_map(|line| {
+        let (pid, line_command) = line.trim_start().split_once(char::is_whitespace)?;
+        let program = line_command.trim().strip_suffix(command.as_str())?;
+        let program = program.trim_end();
+        (program.rsplit('/').next() == Some("ssh"))
+            .then(|| pid.parse().ok())
+            .flatten()
+    })
+}
+
+/// Extra ssh arguments typed as one line, e.g. `-C -o ServerAliveInterval=30`.
+pub fn split_options(line: &str) -> Vec<String> {
+    line.split_whitespace().map(ToString::to_string).collect()
+}
+
+/// Address a forward binds to when other machines should reach it.
+pub const BIND_ALL_ADDRESS: &str = "0.0.0.0";
+
+/// `spec` bound to every IPv4 interface instead of loopback: what `-g` does
+/// for `-L`, and for `-R` a request the server honors only with
+/// `GatewayPorts clientspecified` (or `yes`).
+pub fn bind_all(spec: &str) -> String {
+    format!("{BIND_ALL_ADDRESS}:{spec}")
+}
+
+/// Create an SSH port forward and wait for ssh to go to the background.
+///
+/// Unlike [`create_forward`], there is no time limit, and ssh keeps the
+/// terminal for password prompts and its messages.
+pub fn create_forward_checked(
+    spec: &str,
+    host: &str,
+    remote: bool,
+    options: &[String],
+) -> Result<()> {
+    #[cfg(feature = "embedded-ssh")]
+    if super::ssh_backend() == super::SshBackend::Embedded {
+        return start_embedded_forward(spec, host, remote, options);
+    }
+    let args = forward_args(spec, host, remote, options);
+
+    tracing::info!(
+        host,
+        spec,
+        remote,
+        ?options,
+        "starting ssh forward (checked)"
+    );
+    let status = std::process::Command::new("ssh").args(args).status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        tracing::warn!(host, spec, %status, "ssh forward failed");
+        anyhow::bail!("ssh exited with {status}")
+    }
+}
+
+#[cfg(feature = "embedded-ssh")]
+fn start_embedded_forward(spec: &str, host: &str, remote: bool, options: &[String]) -> Result<()> {
+    if remote {
+        anyhow::bail!("Remote (-R) forwards are not supported by the embedded SSH backend");
+    }
+    if !options.is_empty() {
+        anyhow::bail!(
+            "SSH options ({}) are not supported by the embedded SSH backend",
+            options.join(" ")
+        );
+    }
+    super::embedded::start_forward(spec, host)
+}
+
+/// How long the master connection of [`authenticate_interactive`] outlives
+/// its last command, so a login expires once quay stops using it.
+const CONTROL_PERSIST: &str = "ControlPersist=10m";
+
+/// Directory for quay's control sockets: `quay` in `$XDG_RUNTIME_DIR`, or
+/// else in `~/.ssh`. It is created private, and `None` when it is not
+/// private to this user, so no one else can plant or reach a socket there.
+fn control_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".ssh")))?;
+    let dir = base.join("quay");
+    if let Err(e) = create_private_dir(&dir) {
+        tracing::warn!(error = %e, dir = %dir.display(), "no ssh control directory");
+        return None;
+    }
+    is_private(&dir).then_some(dir)
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+/// Whether `path` belongs to this user and, for a directory, is closed to
+/// everyone else.
+#[cfg(unix)]
+#[allow(clippy::verbose_bit_mask)]
+fn is_private(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return false;
+    };
+    let private = meta.uid() == nix::unistd::getuid().as_raw()
+        && (!meta.is_dir() || meta.mode() & 0o077 == 0);
+    if !private {
+        tracing::warn!(path = %path.display(), "ssh control path is not private; ignoring it");
+    }
+    private
+}
+
+#[cfg(not(unix))]
+fn is_private(path: &std::path::Path) -> bool {
+    path.exists()
+}
+
+/// Control socket of the master connection quay opens for `host` after
+/// interactive authentication (see [`authenticate_interactive`]); `None`
+/// without a private directory for it.
+pub fn control_path(host: &str) -> Option<PathBuf> {
+    Some(control_dir()?.join(socket_name(host)))
+}
+
+/// File name of the control socket for `host`, with anything but a few
+/// safe characters replaced.
+fn socket_name(host: &str) -> String {
+    let name: String = host
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '@') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{name}.sock")
+}
+
+/// Options for background ssh commands to `host`.
+///
+/// `BatchMode` makes password and 2FA prompts fail fast instead of waiting on
+/// the terminal the TUI owns; once quay holds an authenticated master for the
+/// host, commands reuse it, if its socket is still this user's.
+pub fn batch_args(host: &str) -> Vec<String> {
+    let mut args = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+    if let Some(socket) = control_path(host).filter(|s| s.exists() && is_private(s)) {
+        args.push("-o".to_string());
+        args.push(format!("ControlPath={}", socket.display()));
+    }
+    args
+}
 
-    let child = std::process::Command::new("ssh")
-        .args(["-f", "-N", flag, spec, host])
-        .spawn()?;
+/// Authenticate to `host` on the terminal and keep the session open as a
+/// master connection for later background commands.
+///
+/// Runs with inherited stdio, so the caller must release the terminal first.
+pub async fn authenticate_interactive(host: &str) -> Result<()> {
+    let Some(socket) = control_path(host) else {
+        anyhow::bail!("No private directory for the ssh control socket");
+    };
+    tracing::info!(host, socket = %socket.display(), "interactive ssh login");
+    let status = tokio::process::Command::new("ssh")
+        .args(["-M", "-S"])
+        .arg(&socket)
+        .args(["-o", CONTROL_PERSIST, "-f", "-N", host])
+        .status()
+        .await?;
+    if status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!("ssh exited with {status}")
+    }
+}
 
-    Ok(child.id())
+/// Close the master connection opened by [`authenticate_interactive`], if any.
+pub fn close_master(host: &str) {
+    if let Some(socket) = control_path(host).filter(|s| s.exists()) {
+        tracing::debug!(host, "closing ssh master");
+        let _ = std::process::Command::new("ssh")
+            .args(["-O", "exit", "-S"])
+            .arg(&socket)
+            .arg(host)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
 }
 
 /// Get the PID of the SSH `ControlMaster` for a given remote host.
@@ -20,6 +371,7 @@ pub fn create_forward(spec: &str, host: &str, remote: bool) -> Result<u32> {
 /// Runs `ssh -O check host` and parses "Master running (pid=NNNNN)" from stderr.
 fn get_control_master_pid(host: &str) -> Option<u32> {
     let output = std::process::Command::new("ssh")
+        .args(batch_args(host))
         .args(["-O", "check", host])
         .output()
         .ok()?;
@@ -73,14 +425,11 @@ fn parse_lsof_listen_ports(output: &str) -> Vec<u16> {
     for line in output.lines() {
         // lsof -F format: lines starting with 'n' contain network addresses
         // e.g. "n*:1235" or "n127.0.0.1:1235" or "n[::1]:1235"
-        if let Some(addr) = line.strip_prefix('n') {
-            if let Some(port_str) = addr.rsplit(':').next() {
-                if let Ok(port) = port_str.parse::<u16>() {
-                    if port > 0 {
-                        ports.insert(port);
-                    }
-                }
-            }
+        if let Some((_, port)) = line
+            .strip_prefix('n')
+            .and_then(super::listeners::split_lsof_address)
+        {
+            ports.insert(port);
         }
     }
 
@@ -90,13 +439,42 @@ fn parse_lsof_listen_ports(output: &str) -> Vec<u16> {
 }
 
 pub async fn collect() -> Result<Vec<PortEntry>> {
+    let entries = parse_ssh_forwards(&ps_output().await?)?;
+    #[cfg(feature = "embedded-ssh")]
+    let entries = [entries, embedded_forward_entries()].concat();
+    Ok(entries)
+}
+
+/// Output of `ps aux`, where the `ssh -L`/`-R` processes are found.
+pub(super) async fn ps_output() -> Result<String> {
     let output = tokio::process::Command::new("ps")
         .args(["aux"])
         .output()
         .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    parse_ssh_forwards(&stdout)
+/// Process name of the rows of forwards the embedded backend serves.
+pub const EMBEDDED_PROCESS: &str = "quay (embedded ssh)";
+
+/// Forwards served in-process by the embedded backend, shown like `ssh -L` tunnels.
+#[cfg(feature = "embedded-ssh")]
+fn embedded_forward_entries() -> Vec<PortEntry> {
+    super::embedded::forwards()
+        .into_iter()
+        .map(|f| PortEntry {
+            source: PortSource::Ssh,
+            local_port: f.local_port,
+            remote_host: Some(f.remote_host),
+            remote_port: Some(f.remote_port),
+            process_name: EMBEDDED_PROCESS.to_string(),
+            // No process of its own: killing quay would stop every forward
+            pid: None,
+            ssh_host: Some(f.ssh_host),
+            user: super::signal::current_user(),
+            ..PortEntry::default()
+        })
+        .collect()
 }
 
 /// Extract the SSH host from the command tokens (everything after `ssh`).
@@ -118,12 +496,12 @@ fn extract_ssh_host(line: &str) -> Option<String> {
     }
 }
 
-fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
+pub(super) fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
     let mut entries = Vec::new();
-    // -L local_port:remote_host:remote_port
-    let local_forward_re = Regex::new(r"-L\s*(\d+):([^:\s]+):(\d+)")?;
-    // -R remote_port:local_host:local_port (reverse)
-    let remote_forward_re = Regex::new(r"-R\s*(\d+):([^:\s]+):(\d+)")?;
+    // -L [bind_address:]local_port:remote_host:remote_port
+    let local_forward_re = Regex::new(r"-L\s*(?:[^:\s]+:)?(\d+):([^:\s]+):(\d+)")?;
+    // -R [bind_address:]remote_port:local_host:local_port (reverse)
+    let remote_forward_re = Regex::new(r"-R\s*(?:[^:\s]+:)?(\d+):([^:\s]+):(\d+)")?;
 
     for line in output.lines() {
         if !line.contains("ssh") {
@@ -138,6 +516,7 @@ fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
             continue;
         }
 
+        let user = Some(parts[0].to_string());
         let pid = parts[1].parse::<u32>().ok();
         let ssh_host = extract_ssh_host(line);
 
@@ -155,12 +534,9 @@ fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
                     remote_port,
                     process_name: "ssh".to_string(),
                     pid,
-                    container_id: None,
-                    container_name: None,
                     ssh_host: ssh_host.clone(),
-                    is_open: false,
-                    is_loopback: false,
-                    forwarded_port: None,
+                    user: user.clone(),
+                    ..PortEntry::default()
                 });
             }
         }
@@ -179,12 +555,9 @@ fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
                     remote_port: Some(remote_port),
                     process_name: "ssh -R".to_string(),
                     pid,
-                    container_id: None,
-                    container_name: None,
                     ssh_host: ssh_host.clone(),
-                    is_open: false,
-                    is_loopback: false,
-                    forwarded_port: None,
+                    user: user.clone(),
+                    ..PortEntry::default()
                 });
             }
         }
@@ -197,6 +570,88 @@ fn parse_ssh_forwards(output: &str) -> Result<Vec<PortEntry>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_started_display() {
+        assert_eq!(Started::Process(4242).to_string(), "PID 4242");
+        assert_eq!(Started::Embedded.to_string(), "in-process");
+    }
+
+    #[test]
+    fn test_socket_name_is_sanitized() {
+        assert_eq!(socket_name("user@host:22/../x"), "user@host_22_.._x.sock");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("quay-ssh-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        create_private_dir(&dir).unwrap();
+        assert!(is_private(&dir));
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(!is_private(&dir));
+        assert!(!is_private(&dir.join("missing.sock")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_args_without_master() {
+        assert_eq!(
+            batch_args("quay-test-no-master.invalid"),
+            vec!["-o", "BatchMode=yes"]
+        );
+    }
+
+    #[test]
+    fn test_forward_args_with_options() {
+        let options = split_options(" -C  -o ServerAliveInterval=30 -i ~/.ssh/deploy ");
+        assert_eq!(
+            forward_args("8080:localhost:80", "devbox", false, &options).join(" "),
+            "-f -N -o ExitOnForwardFailure=yes -C -o ServerAliveInterval=30 \
+             -i ~/.ssh/deploy -L 8080:localhost:80 devbox"
+        );
+        assert_eq!(
+            forward_args("9090:localhost:3000", "devbox", true, &[]).join(" "),
+            "-f -N -o ExitOnForwardFailure=yes -R 9090:localhost:3000 devbox"
+        );
+
+        // The options don't hide the forward or its host from the ps scan
+        let line = format!(
+            "user  12345  0.0  0.1 123456 7890 ?  Ss  10:00  0:00 ssh {}",
+            forward_args("8080:localhost:80", "devbox", false, &options).join(" ")
+        );
+        let entries = parse_ssh_forwards(&line).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].local_port, 8080);
+        assert_eq!(entries[0].ssh_host.as_deref(), Some("devbox"));
+    }
+
+    #[test]
+    fn test_background_pid() {
+        let args = forward_args("8080:localhost:80", "devbox", false, &[]);
+        let ps = "\
+    1 /sbin/init
+  700 ssh -f -N -o ExitOnForwardFailure=yes -L 8080:localhost:80 devbox2
+  812 /usr/bin/ssh -f -N -o ExitOnForwardFailure=yes -L 8080:localhost:80 devbox
+  813 vim ssh -f -N -o ExitOnForwardFailure=yes -L 8080:localhost:80 devbox
+";
+        assert_eq!(background_pid(ps, &args), Some(812));
+        assert_eq!(background_pid("    1 /sbin/init\n", &args), None);
+    }
+
+    #[test]
+    fn test_failure_message_skips_warnings() {
+        let stderr = "Warning: Permanently added 'devbox' (ED25519) to the list of known hosts.\n\
+                      bind [127.0.0.1]:8080: Address already in use\n\
+                      channel_setup_fwd_listener_tcpip: cannot listen to port: 8080\n";
+        assert_eq!(
+            failure_message(stderr),
+            "bind [127.0.0.1]:8080: Address already in use\n\
+             channel_setup_fwd_listener_tcpip: cannot listen to port: 8080"
+        );
+    }
+
     #[test]
     fn test_parse_ssh_local_forward() {
         let output =
@@ -208,6 +663,7 @@ mod tests {
         assert_eq!(entries[0].remote_port, Some(80));
         assert_eq!(entries[0].process_name, "ssh");
         assert_eq!(entries[0].ssh_host, Some("remote".to_string()));
+        assert_eq!(entries[0].user.as_deref(), Some("user"));
     }
 
     #[test]
@@ -231,6 +687,22 @@ mod tests {
         assert_eq!(entries[1].ssh_host, Some("remote".to_string()));
     }
 
+    #[test]
+    fn test_parse_ssh_forwards_with_bind_address() {
+        let spec = bind_all("8080:10.0.0.5:80");
+        assert_eq!(spec, "0.0.0.0:8080:10.0.0.5:80");
+        let output = format!(
+            "user  12345  0.0  0.1 123456 7890 ?  Ss  10:00  0:00 ssh -f -N -L {spec} -R *:9090:localhost:3000 remote"
+        );
+        let entries = parse_ssh_forwards(&output).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].local_port, 8080);
+        assert_eq!(entries[0].remote_host.as_deref(), Some("10.0.0.5"));
+        assert_eq!(entries[0].remote_port, Some(80));
+        assert_eq!(entries[1].local_port, 3000);
+        assert_eq!(entries[1].remote_port, Some(9090));
+    }
+
     #[test]
     fn test_parse_ssh_no_forwards() {
         let output = "user  12345  0.0  0.1 123456 7890 ?  Ss  10:00  0:00 ssh remote";
@@ -302,4 +774,46 @@ mod tests {
         let ports = parse_lsof_listen_ports(output);
         assert_eq!(ports, vec![1235, 3108]);
     }
+
+    mod props {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn prop_parse_ssh_forwards_never_panics(
+                lines in prop::collection::vec(
+                    prop_oneof![
+                        "\\PC{0,12} +[0-9]{0,8} +\\PC{0,30} ssh +-[LR] *[0-9]{0,6}:\\PC{0,12}:[0-9]{0,6} *\\PC{0,20}",
+                        "\\PC{0,60}",
+                    ],
+                    0..12,
+                ),
+            ) {
+                parse_ssh_forwards(&lines.join("\n")).unwrap();
+            }
+
+            #[test]
+            fn prop_parse_ssh_forwards_reads_generated_tunnels(
+                user in "[a-z_][a-z0-9_-]{0,12}",
+                pid in 1u32..,
+                local_port in 1u16..,
+                remote_host in "[a-z0-9][a-z0-9.-]{0,20}",
+                remote_port in any::<u16>(),
+                host in "[a-z0-9][a-z0-9@.-]{0,20}",
+            ) {
+                let output = format!(
+                    "{user}  {pid}  0.0  0.1  1234  567 ??  S  10:00  0:00.01 /usr/bin/ssh -N -L {local_port}:{remote_host}:{remote_port} {host}\n"
+                );
+                let entries = parse_ssh_forwards(&output).unwrap();
+                prop_assert_eq!(entries.len(), 1);
+                prop_assert_eq!(entries[0].local_port, local_port);
+                prop_assert_eq!(entries[0].remote_host.as_deref(), Some(remote_host.as_str()));
+                prop_assert_eq!(entries[0].remote_port, Some(remote_port));
+                prop_assert_eq!(entries[0].pid, Some(pid));
+                prop_assert_eq!(entries[0].user.as_deref(), Some(user.as_str()));
+                prop_assert_eq!(entries[0].ssh_host.as_deref(), Some(host.as_str()));
+            }
+        }
+    }
 }
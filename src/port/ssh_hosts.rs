@@ -0,0 +1,136 @@
+//! Host aliases and `Host` patterns of `~/.ssh/config`.
+//!
+//! Used by the embedded backend to resolve aliases and by connection groups
+//! to list the hosts matching a pattern. `Include` files are not read.
+
+use std::path::PathBuf;
+
+/// Content of `~/.ssh/config`, empty when it is missing or unreadable.
+pub fn read_config() -> String {
+    std::fs::read_to_string(expand_home("~/.ssh/config")).unwrap_or_default()
+}
+
+/// Concrete aliases named on `Host` lines, in file order: the names without
+/// wildcards or negation, each once.
+pub fn aliases(content: &str) -> Vec<String> {
+    let mut aliases: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let Some((key, value)) = line.split_once(|c: char| c == '=' || c.is_whitespace()) else {
+            continue;
+        };
+        if !key.eq_ignore_ascii_case("host") {
+            continue;
+        }
+        let value = value.trim_start_matches([' ', '\t', '=']);
+        for name in value.split_whitespace().map(|name| name.trim_matches('"')) {
+            if !name.contains(['*', '?', '!']) && !aliases.iter().any(|a| a == name) {
+                aliases.push(name.to_string());
+            }
+        }
+    }
+    aliases
+}
+
+/// Aliases of `content` that `patterns` matches, e.g. every `prod-*` host.
+pub fn matching(content: &str, patterns: &str) -> Vec<String> {
+    aliases(content)
+        .into_iter()
+        .filter(|alias| host_matches(patterns, alias))
+        .collect()
+}
+
+/// OpenSSH `Host` pattern list: any positive match and no negated (`!`) match.
+pub fn host_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_match(negated, alias) {
+                return false;
+            }
+        } else if glob_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Glob with `*` and `?`, as used in ssh config patterns.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p.split_first() {
+            None => t.is_empty(),
+            Some(('*', rest)) => (0..=t.len()).any(|i| matches(rest, &t[i..])),
+            Some(('?', rest)) => !t.is_empty() && matches(rest, &t[1..]),
+            Some((c, rest)) => t.first() == Some(c) && matches(rest, &t[1..]),
+        }
+    }
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    matches(&p, &t)
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSH_CONFIG: &str = "\
+Host prod-web1 prod-web2
+    HostName 10.0.1.1
+
+Host=prod-db
+    User postgres
+
+Host prod-* !prod-legacy
+    User deploy
+
+host staging prod-legacy
+Match host prod-web1
+    User ignored
+";
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(
+            aliases(SSH_CONFIG),
+            [
+                "prod-web1",
+                "prod-web2",
+                "prod-db",
+                "staging",
+                "prod-legacy"
+            ]
+        );
+        assert!(aliases("").is_empty());
+    }
+
+    #[test]
+    fn test_matching() {
+        assert_eq!(
+            matching(SSH_CONFIG, "prod-*"),
+            ["prod-web1", "prod-web2", "prod-db", "prod-legacy"]
+        );
+        assert_eq!(
+            matching(SSH_CONFIG, "prod-* !prod-legacy !*-db"),
+            ["prod-web1", "prod-web2"]
+        );
+        assert!(matching(SSH_CONFIG, "qa-*").is_empty());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("lab-*", "lab-gpu"));
+        assert!(glob_match("lab-?", "lab-1"));
+        assert!(!glob_match("lab-?", "lab-10"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("AiLab", "ailab"));
+        assert!(!glob_match("ailab", "ailab2"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Recent output of the process behind a port, for the Output popup.
+//!
+//! Where it comes from depends on the process: `docker logs` for a
+//! container, `journalctl` for a process that runs in a systemd unit, and
+//! otherwise the file its stdout is redirected to, found through
+//! `/proc/<pid>/fd/1` or, without `/proc`, lsof. A process writing to a
+//! terminal or a pipe keeps no output that could be shown.
+
+use super::listeners::output;
+use super::{CommandError, PortEntry, PortSource, docker};
+use anyhow::Context;
+use regex::Regex;
+
+/// Lines read from the end of the output.
+pub const LINES: usize = 500;
+
+/// Where a process's output can be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `docker logs` of the container (name or ID).
+    Container(String),
+    /// A systemd unit; `user` for one of the user's service manager.
+    Unit { name: String, user: bool },
+    /// The file stdout is redirected to.
+    File(String),
+}
+
+impl Source {
+    /// The command that reads the last `lines` lines, stderr included.
+    fn command(&self, lines: usize) -> Vec<String> {
+        let lines = lines.to_string();
+        let words: Vec<&str> = match self {
+            // Containers keep stderr apart; interleave it as written
+            Source::Container(container) => {
+                let mut words: Vec<String> = ["sh", "-c", "\"$@\" 2>&1", "sh"]
+                    .map(str::to_string)
+                    .to_vec();
+                words.extend(docker::command(&["logs", "--tail", &lines, container]));
+                return words;
+            }
+            Source::Unit { name, user: false } => {
+                vec!["journalctl", "-u", name, "-n", &lines, "--no-pager"]
+            }
+            Source::Unit { name, user: true } => {
+                vec![
+                    "journalctl",
+                    "--user",
+                    "-u",
+                    name,
+                    "-n",
+                    &lines,
+                    "--no-pager",
+                ]
+            }
+            Source::File(path) => vec!["tail", "-n", &lines, path],
+        };
+        words.into_iter().map(str::to_string).collect()
+    }
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Container(container) => write!(f, "docker logs {container}"),
+            Source::Unit { name, user: false } => write!(f, "journalctl -u {name}"),
+            Source::Unit { name, user: true } => write!(f, "journalctl --user -u {name}"),
+            Source::File(path) => f.write_str(path),
+        }
+    }
+}
+
+/// Where the output of `entry` on `host` (this machine when `None`) is kept.
+///
+/// SSH tunnels are local processes even when scanning a remote host.
+pub async fn find(entry: &PortEntry, host: Option<&str>) -> anyhow::Result<Source> {
+    if entry.source == PortSource::Docker {
+        let container = entry
+            .container_id
+            .clone()
+            .or_else(|| entry.container_name.clone())
+            .context("No container for this entry")?;
+        return Ok(Source::Container(container));
+    }
+    let host = host.filter(|_| entry.source != PortSource::Ssh);
+    let pid = entry
+        .pid
+        .with_context(|| format!("No PID for :{}", entry.local_port))?;
+    let pid_str = pid.to_string();
+
+    let cgroup = format!("/proc/{pid}/cgroup");
+    if let Ok(out) = output(&["cat", &cgroup], host).await {
+        if let Some((name, user)) = parse_cgroup_unit(&String::from_utf8_lossy(&out.stdout)) {
+            return Ok(Source::Unit { name, user });
+        }
+    }
+
+    let fd = format!("/proc/{pid}/fd/1");
+    let stdout = match output(&["readlink", &fd], host).await {
+        Ok(out) if out.status.success() => {
+            Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+        }
+        // No /proc (macOS): ask lsof
+        _ => {
+            let words = ["lsof", "-a", "-p", &pid_str, "-d", "1", "-Ftn"];
+            let out = output(&words, host).await.context("Could not run lsof")?;
+            parse_lsof_stdout(&String::from_utf8_lossy(&out.stdout))
+        }
+    };
+    match stdout {
+        Some(path) if is_output_file(&path) => Ok(Source::File(path)),
+        Some(target) => anyhow::bail!(
+            "{} writes its output to {target}, not to a file or a systemd unit",
+            entry.process_name
+        ),
+        None => anyhow::bail!(
+            "Output of {} (PID {pid}) not found; it may belong to another user",
+            entry.process_name
+        ),
+    }
+}
+
+/// The last `lines` lines of `source` on `host`, oldest first, with terminal
+/// escapes and tabs made printable.
+pub async fn read(
+    source: &Source,
+    host: Option<&str>,
+    lines: usize,
+) -> anyhow::Result<Vec<String>> {
+    let command = source.command(lines);
+    let words: Vec<&str> = command.iter().map(String::as_str).collect();
+    let out = output(&words, host)
+        .await
+        .with_context(|| format!("Could not run {}", words[0]))?;
+    if !out.status.success() {
+        return Err(CommandError::new(words[0], &words[1..], host, &out).into());
+    }
+    Ok(clean_lines(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// The service a process runs in, from `/proc/<pid>/cgroup`: the innermost
+/// `.service` of its cgroup path, and whether the user's manager runs it.
+/// The manager's own `user@1000.service` isn't one; sessions and scopes
+/// have none.
+fn parse_cgroup_unit(content: &str) -> Option<(String, bool)> {
+    // cgroup v2 has one "0::/path" line; on v1 the systemd hierarchy names units
+    let path = content
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2).map(|path| (line, path)))
+        .find(|(line, _)| line.starts_with("0::") || line.contains(":name=systemd:"))
+        .map(|(_, path)| path)?;
+    let unit_type = |segment: &str| segment.rsplit_once('.').map(|(_, t)| t.to_string());
+    let segments: Vec<&str> = path.split('/').collect();
+    let unit = segments
+        .iter()
+        .rposition(|s| matches!(unit_type(s).as_deref(), Some("service" | "scope")))?;
+    let name = segments[unit];
+    if unit_type(name).as_deref() != Some("service") || name.starts_with("user@") {
+        return None;
+    }
+    let user = segments[..unit].iter().any(|s| s.starts_with("user@"));
+    Some((name.to_string(), user))
+}
+
+/// What fd 1 refers to in `lsof -Ftn` output: the path of a regular file,
+/// or the kind of anything else.
+fn parse_lsof_stdout(output: &str) -> Option<String> {
+    let mut kind = None;
+    for line in output.lines() {
+        if let Some(t) = line.strip_prefix('t') {
+            kind = Some(t);
+        } else if let Some(name) = line.strip_prefix('n') {
+            return Some(match kind {
+                Some("REG") | None => name.to_string(),
+                Some(kind) => format!("{} ({name})", kind.to_ascii_lowercase()),
+            });
+        }
+    }
+    None
+}
+
+/// Whether stdout points at a file that can be tailed, not a terminal,
+/// `/dev/null`, a pipe, or a socket.
+fn is_output_file(target: &str) -> bool {
+    target.starts_with('/') && !target.starts_with("/dev/") && !target.ends_with(" (deleted)")
+}
+
+/// Lines without terminal escapes (colors, cursor movement), tabs expanded.
+fn clean_lines(output: &str) -> Vec<String> {
+    let escape = Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07]*\x07|.)").expect("valid regex");
+    output
+        .lines()
+        .map(|line| {
+            escape
+                .replace_all(line, "")
+                .replace('\t', "    ")
+                .replace('\r', "")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup_unit() {
+        assert_eq!(
+            parse_cgroup_unit("0::/system.slice/nginx.service\n"),
+            Some(("nginx.service".to_string(), false))
+        );
+        assert_eq!(
+            parse_cgroup_unit(
+                "0::/user.slice/user-1000.slice/user@1000.service/app.slice/api.service\n"
+            ),
+            Some(("api.service".to_string(), true))
+        );
+        // cgroup v1
+        assert_eq!(
+            parse_cgroup_unit(
+                "12:pids:/system.slice\n1:name=systemd:/system.slice/redis.service\n"
+            ),
+            Some(("redis.service".to_string(), false))
+        );
+        // A shell in a login session, a terminal under the user's manager, no systemd
+        assert_eq!(
+            parse_cgroup_unit("0::/user.slice/user-1000.slice/session-3.scope\n"),
+            None
+        );
+        assert_eq!(
+            parse_cgroup_unit(
+                "0::/user.slice/user-1000.slice/user@1000.service/app.slice/tmux-spawn-1.scope\n"
+            ),
+            None
+        );
+        assert_eq!(parse_cgroup_unit("0::/\n"), None);
+        assert_eq!(parse_cgroup_unit(""), None);
+    }
+
+    #[test]
+    fn test_stdout_target() {
+        assert_eq!(
+            parse_lsof_stdout("p4242\nf1\ntREG\nn/home/me/app/server.log\n"),
+            Some("/home/me/app/server.log".to_string())
+        );
+        assert_eq!(
+            parse_lsof_stdout("p4242\nf1\ntCHR\nn/dev/ttys003\n"),
+            Some("chr (/dev/ttys003)".to_string())
+        );
+        assert_eq!(parse_lsof_stdout(""), None);
+
+        assert!(is_output_file("/home/me/app/server.log"));
+        assert!(!is_output_file("/dev/pts/3"));
+        assert!(!is_output_file("/dev/null"));
+        assert!(!is_output_file("pipe:[81234]"));
+        assert!(!is_output_file("/tmp/out.log (deleted)"));
+        assert!(!is_output_file("chr (/dev/ttys003)"));
+    }
+
+    #[test]
+    fn test_command_and_clean_lines() {
+        let container = Source::Container("db".to_string());
+        assert_eq!(container.to_string(), "docker logs db");
+        assert_eq!(
+            container.command(50)[2..],
+            ["\"$@\" 2>&1", "sh", "docker", "logs", "--tail", "50", "db"]
+        );
+        let unit = Source::Unit {
+            name: "api.service".to_string(),
+            user: true,
+        };
+        assert_eq!(unit.to_string(), "journalctl --user -u api.service");
+        assert_eq!(
+            Source::File("/tmp/app.log".to_string()).command(10),
+            ["tail", "-n", "10", "/tmp/app.log"]
+        );
+
+        assert_eq!(
+            clean_lines("\x1b[32mINFO\x1b[0m\tready\r\n\x1b]0;title\x07plain\n"),
+            ["INFO    ready", "plain"]
+        );
+    }
+}
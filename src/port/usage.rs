@@ -0,0 +1,111 @@
+//! CPU and memory use of the processes behind ports, from `ps`.
+//!
+//! One `ps -o pid=,%cpu=,rss= -p <pids>` per host covers every process of a
+//! scan. `%cpu` is what ps reports: on Linux the CPU time a process used over
+//! its lifetime divided by its age, on macOS a decaying recent average.
+
+use super::listeners::output;
+use std::collections::HashMap;
+
+/// What a process uses, as of the last scan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// Percent of one core, so busy multithreaded processes exceed 100.
+    pub cpu: f32,
+    /// Resident memory in bytes.
+    pub memory: u64,
+}
+
+impl Usage {
+    pub fn cpu_display(&self) -> String {
+        format!("{:.1}%", self.cpu)
+    }
+
+    pub fn memory_display(&self) -> String {
+        memory_display(self.memory)
+    }
+}
+
+/// `bytes` in the largest unit that keeps it at one or more, e.g. `48.2M`.
+pub fn memory_display(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{unit}")
+    } else {
+        format!("{value:.0}{unit}")
+    }
+}
+
+/// Usage of each of `pids` on `host` (this machine when `None`). Processes
+/// that exited or that ps couldn't read are missing from the map.
+pub async fn collect(pids: &[u32], host: Option<&str>) -> anyhow::Result<HashMap<u32, Usage>> {
+    if pids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let list: Vec<String> = pids.iter().map(u32::to_string).collect();
+    let list = list.join(",");
+    // ps exits with 1 when any of the PIDs is gone; the others are still listed
+    let out = output(&["ps", "-o", "pid=,%cpu=,rss=", "-p", &list], host).await?;
+    Ok(parse_ps(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// `pid %cpu rss` lines, rss in KiB.
+fn parse_ps(output: &str) -> HashMap<u32, Usage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            // Some locales print a decimal comma
+            let cpu = fields.next()?.replace(',', ".").parse().ok()?;
+            let rss: u64 = fields.next()?.parse().ok()?;
+            Some((
+                pid,
+                Usage {
+                    cpu,
+                    memory: rss * 1024,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ps() {
+        let usage = parse_ps("  4242  12.5  49320\n 811   0,3 1048576\nbogus line\n");
+        assert_eq!(usage.len(), 2);
+        assert_eq!(
+            usage[&4242],
+            Usage {
+                cpu: 12.5,
+                memory: 49320 * 1024
+            }
+        );
+        assert_eq!(usage[&811].cpu_display(), "0.3%");
+        assert_eq!(usage[&811].memory_display(), "1.0G");
+        assert!(parse_ps("").is_empty());
+    }
+
+    #[test]
+    fn test_memory_display() {
+        assert_eq!(memory_display(0), "0.0K");
+        assert_eq!(memory_display(900 * 1024), "900K");
+        assert_eq!(memory_display(49320 * 1024), "48M");
+        assert_eq!(memory_display(5 * 1024 * 1024 + 300 * 1024), "5.3M");
+        assert_eq!(memory_display(3 * 1024 * 1024 * 1024), "3.0G");
+    }
+}
@@ -1,8 +1,30 @@
-use crate::config::Config;
+use crate::completions;
+use crate::config::{self, Config, ConfigDiagnostic};
+use crate::port::{PortEntry, PortSource};
+use crate::state;
+use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
 
+#[derive(Subcommand)]
+pub enum PresetCommands {
+    /// List presets and whether their forward is running
+    List,
+    /// Start the forward of one or more presets
+    Up {
+        /// Preset names
+        #[arg(required = true, add = ArgValueCandidates::new(completions::preset_names))]
+        names: Vec<String>,
+    },
+    /// Stop the forward of one or more presets
+    Down {
+        /// Preset names
+        #[arg(required = true, add = ArgValueCandidates::new(completions::preset_names))]
+        names: Vec<String>,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Preset {
     pub name: String,
@@ -12,12 +34,122 @@ pub struct Preset {
     pub remote_host: String,
     pub remote_port: u16,
     pub ssh_host: String,
+    /// Presets sharing a group are launched together.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Extra ssh arguments for the forward, e.g. `["-C", "-o", "ServerAliveInterval=30"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ssh_options: Vec<String>,
+}
+
+impl Preset {
+    /// `local_port:remote_host:remote_port` for `ssh -L`.
+    pub fn spec(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.local_port, self.remote_host, self.remote_port
+        )
+    }
+
+    /// Whether `tunnel` is this preset's forward: an ssh tunnel on its local
+    /// port to the same host and target, not just any tunnel on the port.
+    pub fn is_tunnel(&self, tunnel: &PortEntry) -> bool {
+        tunnel.source == PortSource::Ssh
+            && tunnel.local_port == self.local_port
+            && tunnel.ssh_host.as_deref() == Some(self.ssh_host.as_str())
+            && tunnel.remote_host.as_deref() == Some(self.remote_host.as_str())
+            && tunnel.remote_port == Some(self.remote_port)
+    }
+
+    /// The hotkey, if `key` is a single character.
+    pub fn hotkey(&self) -> Option<char> {
+        let mut chars = self.key.as_deref()?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// Distinct group names, sorted.
+pub fn groups(presets: &[Preset]) -> Vec<&str> {
+    let mut groups: Vec<&str> = presets.iter().filter_map(|p| p.group.as_deref()).collect();
+    groups.sort_unstable();
+    groups.dedup();
+    groups
+}
+
+pub fn group_members<'a>(presets: &'a [Preset], group: &str) -> Vec<&'a Preset> {
+    presets
+        .iter()
+        .filter(|p| p.group.as_deref() == Some(group))
+        .collect()
+}
+
+/// Presets called `names`, in the order given.
+pub fn by_names<'a>(presets: &'a [Preset], names: &[String]) -> anyhow::Result<Vec<&'a Preset>> {
+    names
+        .iter()
+        .map(|name| {
+            presets.iter().find(|p| &p.name == name).ok_or_else(|| {
+                if presets.is_empty() {
+                    anyhow::anyhow!("No preset '{name}' (presets.toml defines none)")
+                } else {
+                    let available: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+                    anyhow::anyhow!(
+                        "No preset '{name}'. Available presets: {}",
+                        available.join(", ")
+                    )
+                }
+            })
+        })
+        .collect()
+}
+
+/// Index of the preset bound to `key`. The first preset wins on duplicates.
+pub fn find_by_key(presets: &[Preset], key: char) -> Option<usize> {
+    presets.iter().position(|p| p.hotkey() == Some(key))
+}
+
+/// Describe hotkeys that cannot work as configured: keys longer than one
+/// character, keys shared by several presets, and keys in `reserved`
+/// (taken by the Presets popup itself; still reachable via the leader key).
+pub fn key_conflicts(presets: &[Preset], reserved: &[char]) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for (i, preset) in presets.iter().enumerate() {
+        let Some(key) = preset.key.as_deref() else {
+            continue;
+        };
+        let Some(c) = preset.hotkey() else {
+            conflicts.push(format!(
+                "key '{key}' of \"{}\" must be a single character",
+                preset.name
+            ));
+            continue;
+        };
+        if let Some(first) = find_by_key(presets, c).filter(|&first| first < i) {
+            conflicts.push(format!(
+                "key '{c}' of \"{}\" is already used by \"{}\"",
+                preset.name, presets[first].name
+            ));
+        } else if reserved.contains(&c) {
+            conflicts.push(format!(
+                "key '{c}' of \"{}\" is reserved in the Presets popup",
+                preset.name
+            ));
+        }
+    }
+    conflicts
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Presets {
     #[serde(default)]
     pub preset: Vec<Preset>,
+    /// Set when presets.toml exists but could not be read; saving would
+    /// replace the user's file with what little was loaded.
+    #[serde(skip)]
+    pub load_failed: bool,
 }
 
 impl Presets {
@@ -25,17 +157,56 @@ impl Presets {
         Config::config_dir().map(|p| p.join("presets.toml"))
     }
 
-    pub fn load() -> Self {
-        Self::presets_path()
-            .and_then(|path| {
-                if path.exists() {
-                    fs::read_to_string(&path).ok()
-                } else {
-                    None
-                }
-            })
-            .and_then(|content| toml::from_str(&content).ok())
-            .unwrap_or_default()
+    pub fn load() -> Result<Self, ConfigDiagnostic> {
+        config::load_file(Self::presets_path())
+    }
+
+    /// The stored presets, or none with `load_failed` set after recording
+    /// why presets.toml could not be read in `diagnostics`.
+    pub fn load_or_default(diagnostics: &mut Vec<ConfigDiagnostic>) -> Self {
+        Self::load().unwrap_or_else(|d| {
+            diagnostics.push(d);
+            Self {
+                load_failed: true,
+                ..Self::default()
+            }
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if self.load_failed {
+            anyhow::bail!("presets.toml has errors; fix it before changing presets here");
+        }
+        let Some(path) = Self::presets_path() else {
+            anyhow::bail!("Could not determine config directory");
+        };
+        let content = toml::to_string_pretty(self)?;
+        state::replace(&path, &content)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, preset: Preset) {
+        self.preset.push(preset);
+    }
+
+    /// Replace the preset at `index`. Returns true if it existed.
+    pub fn update(&mut self, index: usize, preset: Preset) -> bool {
+        if let Some(slot) = self.preset.get_mut(index) {
+            *slot = preset;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove the preset at `index`. Returns true if it was removed.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index < self.preset.len() {
+            self.preset.remove(index);
+            true
+        } else {
+            false
+        }
     }
 }
 
@@ -49,6 +220,44 @@ mod tests {
         assert!(presets.preset.is_empty());
     }
 
+    #[test]
+    fn test_is_tunnel() {
+        let preset = Preset {
+            name: "db".to_string(),
+            key: None,
+            local_port: 5432,
+            remote_host: "localhost".to_string(),
+            remote_port: 5432,
+            ssh_host: "prod".to_string(),
+            group: None,
+            ssh_options: Vec::new(),
+        };
+        let mut tunnel = PortEntry {
+            remote_host: Some("localhost".to_string()),
+            remote_port: Some(5432),
+            ssh_host: Some("prod".to_string()),
+            ..crate::testing::entry(PortSource::Ssh, 5432, "ssh")
+        };
+        assert!(preset.is_tunnel(&tunnel));
+
+        // Another forward on the same local port is left alone
+        tunnel.ssh_host = Some("staging".to_string());
+        assert!(!preset.is_tunnel(&tunnel));
+        tunnel.ssh_host = Some("prod".to_string());
+        tunnel.remote_port = Some(6543);
+        assert!(!preset.is_tunnel(&tunnel));
+    }
+
+    #[test]
+    fn test_save_refused_after_failed_load() {
+        let presets = Presets {
+            load_failed: true,
+            ..Presets::default()
+        };
+        let error = presets.save().unwrap_err();
+        assert!(error.to_string().contains("presets.toml has errors"));
+    }
+
     #[test]
     fn test_parse_presets() {
         let toml = r#"
@@ -66,6 +275,7 @@ local_port = 6379
 remote_host = "localhost"
 remote_port = 6379
 ssh_host = "staging-bastion"
+ssh_options = ["-C", "-o", "ServerAliveInterval=30"]
 "#;
         let presets: Presets = toml::from_str(toml).unwrap();
         assert_eq!(presets.preset.len(), 2);
@@ -74,5 +284,160 @@ ssh_host = "staging-bastion"
         assert_eq!(presets.preset[0].local_port, 5432);
         assert_eq!(presets.preset[1].name, "Staging Redis");
         assert_eq!(presets.preset[1].key, None);
+        assert!(presets.preset[0].ssh_options.is_empty());
+        assert_eq!(
+            presets.preset[1].ssh_options,
+            ["-C", "-o", "ServerAliveInterval=30"]
+        );
+        // Presets without options are saved without the key
+        assert!(
+            !toml::to_string(&presets.preset[0])
+                .unwrap()
+                .contains("ssh_options")
+        );
+    }
+
+    fn preset(name: &str, local_port: u16) -> Preset {
+        Preset {
+            name: name.to_string(),
+            key: None,
+            local_port,
+            remote_host: "localhost".to_string(),
+            remote_port: local_port,
+            ssh_host: "bastion".to_string(),
+            group: None,
+            ssh_options: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_add_update_remove() {
+        let mut presets = Presets::default();
+        presets.add(preset("db", 5432));
+        presets.add(preset("redis", 6379));
+        assert_eq!(presets.preset.len(), 2);
+
+        assert!(presets.update(1, preset("cache", 6380)));
+        assert_eq!(presets.preset[1].name, "cache");
+        assert!(!presets.update(5, preset("missing", 1)));
+
+        assert!(presets.remove(0));
+        assert!(!presets.remove(5));
+        assert_eq!(presets.preset.len(), 1);
+        assert_eq!(presets.preset[0].name, "cache");
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let mut presets = Presets::default();
+        let mut with_key = preset("db", 5432);
+        with_key.key = Some("1".to_string());
+        presets.add(with_key);
+        presets.add(preset("web", 8080));
+
+        let content = toml::to_string_pretty(&presets).unwrap();
+        let parsed: Presets = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.preset.len(), 2);
+        assert_eq!(parsed.preset[0].key.as_deref(), Some("1"));
+        assert!(parsed.preset[1].key.is_none());
+        assert_eq!(parsed.preset[1].local_port, 8080);
+    }
+
+    #[test]
+    fn test_hotkey() {
+        let mut p = preset("db", 5432);
+        assert_eq!(p.hotkey(), None);
+        p.key = Some("1".to_string());
+        assert_eq!(p.hotkey(), Some('1'));
+        p.key = Some("ab".to_string());
+        assert_eq!(p.hotkey(), None);
+    }
+
+    #[test]
+    fn test_find_by_key() {
+        let mut a = preset("a", 1000);
+        a.key = Some("1".to_string());
+        let mut b = preset("b", 2000);
+        b.key = Some("2".to_string());
+        let presets = vec![preset("none", 3000), a, b];
+        assert_eq!(find_by_key(&presets, '2'), Some(2));
+        assert_eq!(find_by_key(&presets, '1'), Some(1));
+        assert_eq!(find_by_key(&presets, '3'), None);
+    }
+
+    #[test]
+    fn test_key_conflicts() {
+        let with_key = |name: &str, key: &str| {
+            let mut p = preset(name, 1000);
+            p.key = Some(key.to_string());
+            p
+        };
+        let presets = vec![
+            with_key("db", "1"),
+            with_key("cache", "1"),
+            with_key("web", "j"),
+            with_key("long", "ctrl-1"),
+            with_key("ok", "2"),
+            preset("unbound", 3000),
+        ];
+        let conflicts = key_conflicts(&presets, &['j', 'k']);
+        assert_eq!(conflicts.len(), 3);
+        assert!(conflicts[0].contains("\"cache\" is already used by \"db\""));
+        assert!(conflicts[1].contains("reserved"));
+        assert!(conflicts[2].contains("single character"));
+    }
+
+    #[test]
+    fn test_parse_group() {
+        let toml = r#"
+[[preset]]
+name = "API"
+local_port = 8080
+remote_host = "localhost"
+remote_port = 80
+ssh_host = "staging"
+group = "staging"
+"#;
+        let presets: Presets = toml::from_str(toml).unwrap();
+        assert_eq!(presets.preset[0].group.as_deref(), Some("staging"));
+        assert_eq!(presets.preset[0].spec(), "8080:localhost:80");
+    }
+
+    #[test]
+    fn test_groups_and_members() {
+        let grouped = |name: &str, group: &str| {
+            let mut p = preset(name, 1000);
+            p.group = Some(group.to_string());
+            p
+        };
+        let presets = vec![
+            grouped("api", "staging"),
+            preset("solo", 2000),
+            grouped("db", "prod"),
+            grouped("cache", "staging"),
+        ];
+        assert_eq!(groups(&presets), vec!["prod", "staging"]);
+        let members: Vec<&str> = group_members(&presets, "staging")
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(members, vec!["api", "cache"]);
+        assert!(group_members(&presets, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_by_names() {
+        let presets = vec![preset("api", 8080), preset("db", 5432)];
+        let names =
+            |list: &[&str]| -> Vec<String> { list.iter().map(ToString::to_string).collect() };
+        let found: Vec<u16> = by_names(&presets, &names(&["db", "api"]))
+            .unwrap()
+            .iter()
+            .map(|p| p.local_port)
+            .collect();
+        assert_eq!(found, vec![5432, 8080]);
+        let err = by_names(&presets, &names(&["api", "cache"])).unwrap_err();
+        assert!(err.to_string().contains("Available presets: api, db"));
+        assert!(by_names(&[], &names(&["api"])).is_err());
     }
 }
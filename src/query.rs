@@ -0,0 +1,255 @@
+//! Search query language for the TUI filter (`/`).
+//!
+//! A query is whitespace-separated terms that must all match. `field:value`
+//! terms filter on one field; anything else (including a field term whose value
+//! doesn't parse) is a case-insensitive substring matched against the process,
+//! port, label, service, project, remote host, and connection, as plain search
+//! always did.
+//!
+//! ```text
+//! source:docker  port:>3000  port:8000-8999  proc:node  open:false  user:root
+//! service:postgres  project:storefront
+//! ```
+
+use crate::port::{PortEntry, PortSource};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PortMatch {
+    Exact(u16),
+    Above(u16),
+    AtLeast(u16),
+    Below(u16),
+    AtMost(u16),
+    Range(u16, u16),
+}
+
+impl PortMatch {
+    fn parse(value: &str) -> Option<Self> {
+        let port = |s: &str| s.trim().parse::<u16>().ok();
+        if let Some(rest) = value.strip_prefix(">=") {
+            return port(rest).map(PortMatch::AtLeast);
+        }
+        if let Some(rest) = value.strip_prefix("<=") {
+            return port(rest).map(PortMatch::AtMost);
+        }
+        if let Some(rest) = value.strip_prefix('>') {
+            return port(rest).map(PortMatch::Above);
+        }
+        if let Some(rest) = value.strip_prefix('<') {
+            return port(rest).map(PortMatch::Below);
+        }
+        if let Some((low, high)) = value.split_once('-') {
+            let (low, high) = (port(low)?, port(high)?);
+            return Some(PortMatch::Range(low.min(high), low.max(high)));
+        }
+        port(value).map(PortMatch::Exact)
+    }
+
+    fn matches(&self, port: u16) -> bool {
+        match *self {
+            PortMatch::Exact(p) => port == p,
+            PortMatch::Above(p) => port > p,
+            PortMatch::AtLeast(p) => port >= p,
+            PortMatch::Below(p) => port < p,
+            PortMatch::AtMost(p) => port <= p,
+            PortMatch::Range(low, high) => (low..=high).contains(&port),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Source(PortSource),
+    Port(PortMatch),
+    Process(String),
+    Open(bool),
+    User(String),
+    Remote(String),
+    Connection(String),
+    Service(String),
+    Project(String),
+    Text(String),
+}
+
+impl Term {
+    fn parse(token: &str) -> Self {
+        let lower = token.to_lowercase();
+        let field_term = lower
+            .split_once(':')
+            .and_then(|(field, value)| match field {
+                "source" | "type" => match value {
+                    "local" => Some(Term::Source(PortSource::Local)),
+                    "ssh" => Some(Term::Source(PortSource::Ssh)),
+                    "docker" => Some(Term::Source(PortSource::Docker)),
+                    "scan" => Some(Term::Source(PortSource::Scan)),
+                    _ => None,
+                },
+                "port" => PortMatch::parse(value).map(Term::Port),
+                "open" => match value {
+                    "true" | "yes" | "1" => Some(Term::Open(true)),
+                    "false" | "no" | "0" => Some(Term::Open(false)),
+                    _ => None,
+                },
+                _ if value.is_empty() => None,
+                "proc" | "process" => Some(Term::Process(value.to_string())),
+                "user" => Some(Term::User(value.to_string())),
+                "host" | "remote" => Some(Term::Remote(value.to_string())),
+                "conn" | "connection" => Some(Term::Connection(value.to_string())),
+                "svc" | "service" => Some(Term::Service(value.to_string())),
+                "project" => Some(Term::Project(value.to_string())),
+                _ => None,
+            });
+        field_term.unwrap_or(Term::Text(lower))
+    }
+
+    fn matches(&self, entry: &PortEntry, label: Option<&str>, service: Option<&str>) -> bool {
+        let contains = |field: Option<&str>, needle: &str| {
+            field.is_some_and(|f| f.to_lowercase().contains(needle))
+        };
+        let project = entry.project.as_ref().map(|p| p.name.as_str());
+        match self {
+            Term::Source(source) => &entry.source == source,
+            Term::Port(port) => port.matches(entry.local_port),
+            Term::Process(name) => contains(Some(&entry.process_display()), name),
+            Term::Open(open) => entry.is_open == *open,
+            Term::User(user) => contains(entry.user.as_deref(), user),
+            Term::Remote(host) => {
+                contains(entry.remote_host.as_deref(), host)
+                    || contains(entry.ssh_host.as_deref(), host)
+            }
+            Term::Connection(name) => contains(entry.connection.as_deref(), name),
+            Term::Service(name) => contains(service, name),
+            Term::Project(name) => contains(project, name),
+            Term::Text(text) => {
+                contains(Some(&entry.process_name), text)
+                    || entry.local_port.to_string().contains(text.as_str())
+                    || contains(label, text)
+                    || contains(service, text)
+                    || contains(project, text)
+                    || contains(entry.remote_host.as_deref(), text)
+                    || contains(entry.connection.as_deref(), text)
+            }
+        }
+    }
+}
+
+/// A parsed search query; the empty query matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        Self {
+            terms: input.split_whitespace().map(Term::parse).collect(),
+        }
+    }
+
+    /// Whether `entry` (registered under `label` and named `service`, if
+    /// known) satisfies every term.
+    pub fn matches(&self, entry: &PortEntry, label: Option<&str>, service: Option<&str>) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.matches(entry, label, service))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn entry(source: PortSource, port: u16, process: &str, is_open: bool) -> PortEntry {
+        PortEntry {
+            is_open,
+            user: Some("alice".to_string()),
+            ..testing::entry(source, port, process)
+        }
+    }
+
+    fn matching(query: &str, entries: &[PortEntry]) -> Vec<u16> {
+        let query = Query::parse(query);
+        entries
+            .iter()
+            .filter(|e| query.matches(e, None, None))
+            .map(|e| e.local_port)
+            .collect()
+    }
+
+    #[test]
+    fn test_port_match_parse() {
+        assert_eq!(PortMatch::parse("3000"), Some(PortMatch::Exact(3000)));
+        assert_eq!(PortMatch::parse(">3000"), Some(PortMatch::Above(3000)));
+        assert_eq!(PortMatch::parse(">=3000"), Some(PortMatch::AtLeast(3000)));
+        assert_eq!(PortMatch::parse("<=80"), Some(PortMatch::AtMost(80)));
+        assert_eq!(
+            PortMatch::parse("9000-8000"),
+            Some(PortMatch::Range(8000, 9000))
+        );
+        assert_eq!(PortMatch::parse(">abc"), None);
+        assert_eq!(PortMatch::parse("70000"), None);
+    }
+
+    #[test]
+    fn test_field_filters() {
+        let mut db = entry(PortSource::Docker, 5432, "postgres", true);
+        db.container_name = Some("db".to_string());
+        let entries = vec![
+            entry(PortSource::Local, 3000, "node", true),
+            entry(PortSource::Local, 8080, "python", false),
+            db,
+            entry(PortSource::Ssh, 9000, "ssh", false),
+        ];
+        assert_eq!(matching("", &entries), vec![3000, 8080, 5432, 9000]);
+        assert_eq!(matching("source:docker", &entries), vec![5432]);
+        assert_eq!(matching("TYPE:SSH", &entries), vec![9000]);
+        assert_eq!(matching("port:>3000", &entries), vec![8080, 5432, 9000]);
+        assert_eq!(matching("port:5000-8999", &entries), vec![8080, 5432]);
+        assert_eq!(matching("proc:db", &entries), vec![5432]);
+        assert_eq!(matching("open:false", &entries), vec![8080, 9000]);
+        assert_eq!(matching("open:false port:<9000", &entries), vec![8080]);
+        assert_eq!(matching("user:bob", &entries), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn test_free_text_fallback() {
+        let entries = vec![
+            entry(PortSource::Local, 3000, "node", true),
+            entry(PortSource::Local, 8080, "python", true),
+        ];
+        assert_eq!(matching("NODE", &entries), vec![3000]);
+        assert_eq!(matching("80", &entries), vec![8080]);
+        assert_eq!(matching("local:3000", &entries), Vec::<u16>::new());
+        assert_eq!(matching("port:abc", &entries), Vec::<u16>::new());
+        assert_eq!(matching("source:local py", &entries), vec![8080]);
+
+        let query = Query::parse("admin");
+        assert!(query.matches(&entries[0], Some("Admin UI"), None));
+    }
+
+    #[test]
+    fn test_service_terms() {
+        let proxy = entry(PortSource::Docker, 15432, "docker-proxy", true);
+        assert!(Query::parse("service:postgres").matches(&proxy, None, Some("postgres")));
+        assert!(Query::parse("svc:post").matches(&proxy, None, Some("postgres")));
+        assert!(Query::parse("POSTGRES").matches(&proxy, None, Some("postgres")));
+        assert!(!Query::parse("service:redis").matches(&proxy, None, Some("postgres")));
+        assert!(!Query::parse("service:postgres").matches(&proxy, None, None));
+    }
+
+    #[test]
+    fn test_project_terms() {
+        use crate::port::project::{Project, ProjectKind};
+        let mut server = entry(PortSource::Local, 3000, "node", true);
+        server.project = Some(Project {
+            name: "storefront".to_string(),
+            kind: ProjectKind::Node,
+            dir: "/home/alice/src/storefront".into(),
+        });
+        let entries = vec![server, entry(PortSource::Local, 3001, "node", true)];
+        assert_eq!(matching("project:store", &entries), vec![3000]);
+        assert_eq!(matching("storefront", &entries), vec![3000]);
+        assert_eq!(matching("node", &entries), vec![3000, 3001]);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::config::{Config, ConfigDiagnostic};
+use crate::port::{PortEntry, PortSource};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// A port registered in `ports.toml` with a human-readable label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredPort {
+    pub label: String,
+    /// Expected process name; another process holding the port is flagged.
+    pub process: Option<String>,
+    /// Shell command that starts the service, offered while the port is closed.
+    pub start: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum RawRegisteredPort {
+    Label(String),
+    Full {
+        label: String,
+        #[serde(default)]
+        process: Option<String>,
+        #[serde(default)]
+        start: Option<String>,
+    },
+}
+
+/// Named port registry loaded from `ports.toml`.
+///
+/// ```toml
+/// 3000 = "storefront dev server"
+/// 5432 = { label = "postgres", process = "postgres" }
+/// 4000 = { label = "admin", start = "npm run dev" }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PortRegistry {
+    pub ports: BTreeMap<u16, RegisteredPort>,
+    /// Keys that are not a port number; their entries are skipped.
+    pub invalid_keys: Vec<String>,
+}
+
+impl PortRegistry {
+    pub fn registry_path() -> Option<PathBuf> {
+        Config::config_dir().map(|p| p.join("ports.toml"))
+    }
+
+    /// Per-project registry in the current working directory.
+    pub fn project_path() -> Option<PathBuf> {
+        std::env::current_dir().ok().map(|p| p.join("ports.toml"))
+    }
+
+    /// Load the global registry, then overlay the per-project one.
+    ///
+    /// A file that fails to parse is skipped and reported; the other one still applies.
+    /// Entries whose key is not a port are reported too.
+    pub fn load() -> (Self, Vec<ConfigDiagnostic>) {
+        let mut registry = Self::default();
+        let mut diagnostics = Vec::new();
+        for path in [Self::registry_path(), Self::project_path()]
+            .into_iter()
+            .flatten()
+            .filter(|p| p.exists())
+        {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    diagnostics.push(ConfigDiagnostic::from_io(&path, &e));
+                    continue;
+                }
+            };
+            match Self::parse(&content) {
+                Ok(other) => {
+                    diagnostics.extend(other.invalid_keys.iter().map(|key| {
+                        let message = invalid_key_message(key);
+                        ConfigDiagnostic {
+                            path: path.clone(),
+                            line: key_line(&content, key),
+                            message: message.clone(),
+                            detail: message,
+                        }
+                    }));
+                    registry.merge(other);
+                }
+                Err(e) => diagnostics.push(ConfigDiagnostic::from_toml(&path, &content, &e)),
+            }
+        }
+        (registry, diagnostics)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        let raw: HashMap<String, RawRegisteredPort> = toml::from_str(content)?;
+        let mut invalid_keys = Vec::new();
+        let ports = raw
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let Some(port) = key.trim().parse::<u16>().ok().filter(|&p| p > 0) else {
+                    invalid_keys.push(key);
+                    return None;
+                };
+                let entry = match value {
+                    RawRegisteredPort::Label(label) => RegisteredPort {
+                        label,
+                        process: None,
+                        start: None,
+                    },
+                    RawRegisteredPort::Full {
+                        label,
+                        process,
+                        start,
+                    } => RegisteredPort {
+                        label,
+                        process,
+                        start: start.filter(|s| !s.trim().is_empty()),
+                    },
+                };
+                Some((port, entry))
+            })
+            .collect();
+        invalid_keys.sort();
+        Ok(Self {
+            ports,
+            invalid_keys,
+        })
+    }
+
+    /// Warnings for the entries [`parse`](Self::parse) skipped.
+    pub fn warnings(&self) -> Vec<String> {
+        self.invalid_keys
+            .iter()
+            .map(|key| invalid_key_message(key))
+            .collect()
+    }
+
+    /// Entries in `other` take precedence. Its invalid keys are not kept.
+    pub fn merge(&mut self, other: Self) {
+        self.ports.extend(other.ports);
+    }
+
+    pub fn get(&self, port: u16) -> Option<&RegisteredPort> {
+        self.ports.get(&port)
+    }
+
+    pub fn label(&self, port: u16) -> Option<&str> {
+        self.get(port).map(|r| r.label.as_str())
+    }
+
+    /// Returns the expected process name when a different process holds a registered port.
+    pub fn unexpected_process(&self, entry: &PortEntry) -> Option<&str> {
+        let expected = self.get(entry.local_port)?.process.as_deref()?;
+        if entry.process_name.is_empty()
+            || entry
+                .process_name
+                .to_lowercase()
+                .contains(&expected.to_lowercase())
+        {
+            None
+        } else {
+            Some(expected)
+        }
+    }
+}
+
+/// A closed row standing in for a registered port that nothing is listening on.
+pub fn placeholder_entry(port: u16) -> PortEntry {
+    PortEntry {
+        source: PortSource::Local,
+        local_port: port,
+        ..PortEntry::default()
+    }
+}
+
+fn invalid_key_message(key: &str) -> String {
+    format!("`{key}` is not a port number (1-65535); entry ignored")
+}
+
+/// 1-based line that defines `key`, bare or quoted.
+fn key_line(content: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{key}\"");
+    content
+        .lines()
+        .position(|line| {
+            let line = line.trim_start();
+            [key, quoted.as_str()].iter().any(|k| {
+                line.strip_prefix(k)
+                    .is_some_and(|rest| rest.trim_start().starts_with('='))
+            })
+        })
+        .map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_labels() {
+        let registry = PortRegistry::parse(
+            r#"
+3000 = "storefront dev server"
+5432 = "postgres"
+"#,
+        )
+        .unwrap();
+        assert_eq!(registry.ports.len(), 2);
+        assert_eq!(registry.label(3000), Some("storefront dev server"));
+        assert!(registry.get(3000).unwrap().process.is_none());
+    }
+
+    #[test]
+    fn test_parse_table_form() {
+        let registry = PortRegistry::parse(
+            r#"
+5432 = { label = "db", process = "postgres" }
+3000 = { label = "storefront", start = "npm run dev" }
+"#,
+        )
+        .unwrap();
+        let entry = registry.get(5432).unwrap();
+        assert_eq!(entry.label, "db");
+        assert_eq!(entry.process.as_deref(), Some("postgres"));
+        assert!(entry.start.is_none());
+        assert_eq!(
+            registry.get(3000).unwrap().start.as_deref(),
+            Some("npm run dev")
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_invalid_ports() {
+        let registry = PortRegistry::parse(
+            r#"
+abc = "not a port"
+70000 = "too large"
+0 = "zero"
+8080 = "ok"
+"#,
+        )
+        .unwrap();
+        assert_eq!(registry.ports.len(), 1);
+        assert_eq!(registry.label(8080), Some("ok"));
+        assert_eq!(registry.invalid_keys, ["0", "70000", "abc"]);
+        assert_eq!(
+            registry.warnings()[2],
+            "`abc` is not a port number (1-65535); entry ignored"
+        );
+    }
+
+    #[test]
+    fn test_key_line() {
+        let content = "3000 = \"app\"\n\n\"web app\" = \"x\"\nabc=\"y\"\n";
+        assert_eq!(key_line(content, "web app"), Some(3));
+        assert_eq!(key_line(content, "abc"), Some(4));
+        assert_eq!(key_line(content, "missing"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_toml() {
+        assert!(PortRegistry::parse("3000 = ").is_err());
+    }
+
+    #[test]
+    fn test_merge_project_overrides_global() {
+        let mut global = PortRegistry::parse(
+            r#"
+3000 = "global app"
+6379 = "redis"
+"#,
+        )
+        .unwrap();
+        let project = PortRegistry::parse(r#"3000 = "storefront""#).unwrap();
+        global.merge(project);
+        assert_eq!(global.label(3000), Some("storefront"));
+        assert_eq!(global.label(6379), Some("redis"));
+    }
+
+    #[test]
+    fn test_unexpected_process() {
+        let registry =
+            PortRegistry::parse(r#"3000 = { label = "storefront", process = "node" }"#).unwrap();
+
+        let mut entry = placeholder_entry(3000);
+        assert_eq!(registry.unexpected_process(&entry), None);
+
+        entry.process_name = "node".to_string();
+        assert_eq!(registry.unexpected_process(&entry), None);
+
+        entry.process_name = "Node.js".to_string();
+        assert_eq!(registry.unexpected_process(&entry), None);
+
+        entry.process_name = "python".to_string();
+        assert_eq!(registry.unexpected_process(&entry), Some("node"));
+
+        entry.local_port = 3001;
+        assert_eq!(registry.unexpected_process(&entry), None);
+    }
+
+    #[test]
+    fn test_placeholder_entry_is_closed() {
+        let entry = placeholder_entry(3000);
+        assert_eq!(entry.source, PortSource::Local);
+        assert_eq!(entry.local_port, 3000);
+        assert!(!entry.is_open);
+        assert!(entry.pid.is_none());
+    }
+}
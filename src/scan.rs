@@ -0,0 +1,79 @@
+//! `quay scan`: probe a port range from here and list the open ports.
+//!
+//! For hosts where quay can't list listeners (no lsof/ss, a locked-down
+//! appliance, or a whole subnet), e.g. `quay scan 8000-9000 --host 10.0.0.0/24`.
+
+use crate::output;
+use crate::port::scan::{self, ScanOptions};
+use crate::schema;
+use anyhow::Result;
+use std::io;
+use std::time::Duration;
+
+#[derive(clap::Args)]
+pub struct ScanArgs {
+    /// Ports to probe, e.g. 8000-9000 or 22,80,443
+    #[arg(value_name = "PORTS")]
+    pub ports: String,
+    /// Host, IP, or IPv4 CIDR block to scan [default: --remote, or localhost]
+    #[arg(long, value_name = "HOST")]
+    pub host: Option<String>,
+    /// Connection attempts in flight at once
+    #[arg(long, value_name = "N", default_value_t = ScanOptions::default().concurrency)]
+    pub concurrency: usize,
+    /// Connection attempts started per second (0 for no limit)
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    pub rate: u32,
+    /// Milliseconds to wait for each port to accept
+    #[arg(long, value_name = "MS", default_value_t = 300)]
+    pub timeout: u64,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = output::Format::Table)]
+    pub format: output::Format,
+}
+
+impl ScanArgs {
+    fn options(&self) -> ScanOptions {
+        ScanOptions {
+            concurrency: self.concurrency,
+            rate: Some(self.rate).filter(|&rate| rate > 0),
+            timeout: Duration::from_millis(self.timeout),
+        }
+    }
+}
+
+pub async fn run(args: &ScanArgs, remote_host: Option<&str>, no_color: bool) -> Result<()> {
+    let ports = scan::parse_ports(&args.ports)?;
+    let (label, hosts) = match (&args.host, remote_host) {
+        (Some(host), _) => (host.clone(), scan::parse_hosts(host)?),
+        (None, Some(remote)) => (
+            remote.to_string(),
+            vec![scan::resolve_ssh_host(remote).await],
+        ),
+        (None, None) => ("localhost".to_string(), vec!["127.0.0.1".to_string()]),
+    };
+    eprintln!(
+        "Scanning {} port(s) on {label}{}",
+        ports.len(),
+        if hosts.len() > 1 {
+            format!(" ({} hosts)", hosts.len())
+        } else {
+            String::new()
+        }
+    );
+
+    let open = scan::scan(&hosts, &ports, &args.options()).await?;
+    // Without --host every hit is on the one default host
+    let entries: Vec<_> = open
+        .iter()
+        .map(|(host, port)| scan::scan_entry(args.host.as_ref().map(|_| host.as_str()), *port))
+        .collect();
+    let options = output::OutputOptions {
+        format: args.format,
+        version: schema::SCHEMA_VERSION,
+        color: output::color_enabled(no_color),
+    };
+    output::write_entries(&mut io::stdout().lock(), &entries, options)?;
+    eprintln!("{} open", entries.len());
+    Ok(())
+}
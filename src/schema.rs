@@ -0,0 +1,170 @@
+//! Stable machine-readable representation of port entries.
+//!
+//! Scripts consuming `quay list --format json|jsonl` can pin the shape with
+//! `--output-version`. Adding fields keeps the version; renaming, removing, or
+//! changing the meaning of a field requires a new one.
+
+use crate::port::project::Project;
+use crate::port::{PortEntry, PortSource, TunnelHealth};
+use serde::{Deserialize, Serialize};
+
+/// Current schema version, used when `--output-version` is not given.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Versions this build can still produce.
+pub const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+/// One port entry as written to JSON output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortRecord {
+    pub schema_version: u32,
+    /// `Local`, `Ssh`, `Docker`, or `Scan`.
+    pub source: PortSource,
+    pub local_port: u16,
+    pub is_open: bool,
+    pub remote_host: Option<String>,
+    pub remote_port: Option<u16>,
+    pub process_name: String,
+    pub pid: Option<u32>,
+    pub container_id: Option<String>,
+    pub container_name: Option<String>,
+    pub ssh_host: Option<String>,
+    pub is_loopback: bool,
+    /// Bound address (`*` for any), when the collector reports it.
+    #[serde(default)]
+    pub bind: Option<String>,
+    /// Local end of the SSH tunnel forwarding this port, if any.
+    pub forwarded_port: Option<u16>,
+    /// Connection the entry was collected from (aggregate scans only).
+    pub connection: Option<String>,
+    /// Login name of the process owner, when known.
+    pub user: Option<String>,
+    /// `down`, `local_only`, or `healthy` for SSH `-L` forwards, when checked.
+    #[serde(default)]
+    pub tunnel: Option<TunnelHealth>,
+    /// Probe latency in microseconds, when the probe connected.
+    #[serde(default)]
+    pub latency_us: Option<u64>,
+    /// `{name, kind, dir}` of the project a local process runs in, when found.
+    #[serde(default)]
+    pub project: Option<Project>,
+    /// CPU use of the process in percent of one core, as ps reports it.
+    #[serde(default)]
+    pub cpu_percent: Option<f32>,
+    /// Resident memory of the process in bytes.
+    #[serde(default)]
+    pub memory_bytes: Option<u64>,
+}
+
+impl PortRecord {
+    pub fn new(entry: &PortEntry, version: u32) -> Self {
+        Self {
+            schema_version: version,
+            source: entry.source.clone(),
+            local_port: entry.local_port,
+            is_open: entry.is_open,
+            remote_host: entry.remote_host.clone(),
+            remote_port: entry.remote_port,
+            process_name: entry.process_name.clone(),
+            pid: entry.pid,
+            container_id: entry.container_id.clone(),
+            container_name: entry.container_name.clone(),
+            ssh_host: entry.ssh_host.clone(),
+            is_loopback: entry.is_loopback,
+            bind: entry.bind.clone(),
+            forwarded_port: entry.forwarded_port,
+            connection: entry.connection.clone(),
+            user: entry.user.clone(),
+            tunnel: entry.tunnel,
+            latency_us: entry
+                .latency
+                .map(|latency| u64::try_from(latency.as_micros()).unwrap_or(u64::MAX)),
+            project: entry.project.clone(),
+            cpu_percent: entry.usage.map(|usage| usage.cpu),
+            memory_bytes: entry.usage.map(|usage| usage.memory),
+        }
+    }
+}
+
+/// Parse and validate an `--output-version` argument.
+pub fn parse_version(value: &str) -> Result<u32, String> {
+    let version: u32 = value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a version number"))?;
+    if SUPPORTED_VERSIONS.contains(&version) {
+        Ok(version)
+    } else {
+        let supported: Vec<String> = SUPPORTED_VERSIONS.iter().map(u32::to_string).collect();
+        Err(format!(
+            "unsupported output version {version} (supported: {})",
+            supported.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1"), Ok(1));
+        assert!(parse_version("99").unwrap_err().contains("supported: 1"));
+        assert!(parse_version("latest").is_err());
+    }
+
+    #[test]
+    fn test_record_field_names_are_stable() {
+        let entry = PortEntry {
+            source: PortSource::Docker,
+            local_port: 5432,
+            remote_host: Some("db".to_string()),
+            remote_port: Some(5432),
+            process_name: "db".to_string(),
+            container_id: Some("abc123".to_string()),
+            container_name: Some("db".to_string()),
+            is_open: true,
+            forwarded_port: Some(15432),
+            ..PortEntry::default()
+        };
+        let value = serde_json::to_value(PortRecord::new(&entry, SCHEMA_VERSION)).unwrap();
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            vec![
+                "bind",
+                "connection",
+                "container_id",
+                "container_name",
+                "cpu_percent",
+                "forwarded_port",
+                "is_loopback",
+                "is_open",
+                "latency_us",
+                "local_port",
+                "memory_bytes",
+                "pid",
+                "process_name",
+                "project",
+                "remote_host",
+                "remote_port",
+                "schema_version",
+                "source",
+                "ssh_host",
+                "tunnel",
+                "user",
+            ]
+        );
+        assert_eq!(value["source"], "Docker");
+        assert_eq!(value["schema_version"], 1);
+
+        let parsed: PortRecord = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.forwarded_port, Some(15432));
+    }
+}
@@ -0,0 +1,183 @@
+//! Well-known service names by port, for the SERVICE column and search.
+//!
+//! A built-in table (IANA registrations plus the defaults of common servers)
+//! names what usually listens on a port, so a row reads "postgres" even when
+//! the process is `docker-proxy` or `java`. `[services]` in config.toml adds
+//! names or replaces built-in ones; an empty name hides a built-in one:
+//!
+//! ```toml
+//! [services]
+//! 3000 = "grafana"
+//! 8080 = ""
+//! ```
+
+use crate::port::{PortEntry, PortSource, is_local_forward};
+use std::collections::{BTreeMap, HashMap};
+
+/// Built-in names, sorted by port.
+const WELL_KNOWN: &[(u16, &str)] = &[
+    (21, "ftp"),
+    (22, "ssh"),
+    (23, "telnet"),
+    (25, "smtp"),
+    (53, "dns"),
+    (80, "http"),
+    (110, "pop3"),
+    (111, "rpcbind"),
+    (143, "imap"),
+    (389, "ldap"),
+    (443, "https"),
+    (445, "smb"),
+    (465, "smtps"),
+    (587, "submission"),
+    (631, "ipp"),
+    (636, "ldaps"),
+    (873, "rsync"),
+    (993, "imaps"),
+    (995, "pop3s"),
+    (1433, "mssql"),
+    (1521, "oracle"),
+    (1883, "mqtt"),
+    (2049, "nfs"),
+    (2375, "docker"),
+    (2376, "docker-tls"),
+    (2379, "etcd"),
+    (2380, "etcd-peer"),
+    (3306, "mysql"),
+    (3389, "rdp"),
+    (4222, "nats"),
+    (5000, "registry"),
+    (5050, "pgadmin"),
+    (5353, "mdns"),
+    (5432, "postgres"),
+    (5601, "kibana"),
+    (5672, "amqp"),
+    (5900, "vnc"),
+    (5984, "couchdb"),
+    (6379, "redis"),
+    (6443, "kubernetes"),
+    (7474, "neo4j"),
+    (7687, "bolt"),
+    (8086, "influxdb"),
+    (8200, "vault"),
+    (8500, "consul"),
+    (8883, "mqtts"),
+    (9000, "minio"),
+    (9042, "cassandra"),
+    (9090, "prometheus"),
+    (9092, "kafka"),
+    (9093, "alertmanager"),
+    (9100, "node-exporter"),
+    (9200, "elasticsearch"),
+    (9300, "es-transport"),
+    (11211, "memcached"),
+    (15672, "rabbitmq-ui"),
+    (16686, "jaeger"),
+    (27017, "mongodb"),
+];
+
+/// Built-in name for `port`.
+fn well_known(port: u16) -> Option<&'static str> {
+    WELL_KNOWN
+        .binary_search_by_key(&port, |&(p, _)| p)
+        .ok()
+        .map(|i| WELL_KNOWN[i].1)
+}
+
+/// Port that says which service an entry is: the container port of a Docker
+/// mapping and the target of a local SSH forward, else the listening port.
+fn service_port(entry: &PortEntry) -> u16 {
+    let inner = match entry.source {
+        PortSource::Docker => entry.remote_port,
+        PortSource::Ssh if is_local_forward(entry) => entry.remote_port,
+        _ => None,
+    };
+    inner.unwrap_or(entry.local_port)
+}
+
+/// Service names: the built-in table with the user's `[services]` on top.
+#[derive(Debug, Clone, Default)]
+pub struct Services {
+    overrides: HashMap<u16, String>,
+}
+
+impl Services {
+    /// From `[services]`; keys that aren't ports are ignored, as in ports.toml.
+    pub fn new(config: &BTreeMap<String, String>) -> Self {
+        let overrides = config
+            .iter()
+            .filter_map(|(key, name)| {
+                let port = key.trim().parse::<u16>().ok().filter(|&p| p > 0)?;
+                Some((port, name.trim().to_string()))
+            })
+            .collect();
+        Self { overrides }
+    }
+
+    /// Name of the service usually on `port`.
+    pub fn name(&self, port: u16) -> Option<&str> {
+        match self.overrides.get(&port) {
+            Some(name) if name.is_empty() => None,
+            Some(name) => Some(name),
+            None => well_known(port),
+        }
+    }
+
+    /// Name of the service behind `entry`.
+    pub fn of(&self, entry: &PortEntry) -> Option<&str> {
+        self.name(service_port(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::row;
+
+    #[test]
+    fn test_well_known_is_sorted() {
+        assert!(WELL_KNOWN.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(well_known(5432), Some("postgres"));
+        assert_eq!(well_known(3000), None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let config = BTreeMap::from([
+            ("3000".to_string(), "grafana".to_string()),
+            ("6379".to_string(), "valkey".to_string()),
+            ("80".to_string(), String::new()),
+            ("web".to_string(), "ignored".to_string()),
+        ]);
+        let services = Services::new(&config);
+        assert_eq!(services.name(3000), Some("grafana"));
+        assert_eq!(services.name(6379), Some("valkey"));
+        assert_eq!(services.name(80), None);
+        assert_eq!(services.name(5432), Some("postgres"));
+    }
+
+    #[test]
+    fn test_service_of_entry() {
+        let services = Services::default();
+        let mut container = row(PortSource::Docker, 15432);
+        container.remote_port = Some(5432);
+        container.process_name = "docker-proxy".to_string();
+        assert_eq!(services.of(&container), Some("postgres"));
+
+        let mut tunnel = row(PortSource::Ssh, 16379);
+        tunnel.remote_host = Some("cache.internal".to_string());
+        tunnel.remote_port = Some(6379);
+        assert_eq!(services.of(&tunnel), Some("redis"));
+
+        // -R forwards serve the local port on the remote side
+        tunnel.remote_host = Some("(R) localhost:9200".to_string());
+        tunnel.local_port = 9200;
+        tunnel.remote_port = Some(19200);
+        assert_eq!(services.of(&tunnel), Some("elasticsearch"));
+
+        assert_eq!(
+            services.of(&row(PortSource::Local, 9090)),
+            Some("prometheus")
+        );
+    }
+}
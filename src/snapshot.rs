@@ -0,0 +1,350 @@
+//! Saved sets of SSH forwards (snapshots.toml in the state directory),
+//! restored after a reboot.
+//!
+//! A snapshot records the running tunnels as `ssh -L`/`-R` specs, so they can
+//! be started again with one command:
+//!
+//! ```toml
+//! [snapshots.work]
+//! saved_at = "2026-10-16 09:30"
+//!
+//! [[snapshots.work.forward]]
+//! spec = "5432:localhost:5432"
+//! ssh_host = "prod-bastion"
+//! ```
+
+use crate::completions;
+use crate::config::ConfigDiagnostic;
+use crate::forward;
+use crate::port::{self, PortEntry, PortSource};
+use crate::state::{self, StateFile};
+use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Save the running SSH forwards under a name
+    Save {
+        /// Snapshot name; an existing snapshot is replaced
+        name: String,
+    },
+    /// Start the forwards of a snapshot that aren't running
+    Restore {
+        /// Snapshot name
+        #[arg(add = ArgValueCandidates::new(completions::snapshot_names))]
+        name: String,
+    },
+    /// List saved snapshots
+    List,
+    /// Delete a snapshot
+    Delete {
+        /// Snapshot name
+        #[arg(add = ArgValueCandidates::new(completions::snapshot_names))]
+        name: String,
+    },
+}
+
+/// One forward of a snapshot, as passed to `ssh -L` (or `-R`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotForward {
+    /// `local_port:remote_host:remote_port` (`remote_port:local_host:local_port` for `-R`).
+    pub spec: String,
+    pub ssh_host: String,
+    /// Remote forward (`-R`).
+    #[serde(default)]
+    pub remote: bool,
+}
+
+impl SnapshotForward {
+    /// The forward a running tunnel was started with, if it is known.
+    pub fn from_tunnel(entry: &PortEntry) -> Option<Self> {
+        if entry.source != PortSource::Ssh {
+            return None;
+        }
+        let ssh_host = entry.ssh_host.clone()?;
+        let remote_port = entry.remote_port?;
+        let target = entry.remote_host.as_deref()?;
+        // `ssh::collect` shows -R forwards from the local side as "(R) host:port"
+        if let Some(reverse) = target.strip_prefix("(R) ") {
+            let (local_host, _) = reverse.rsplit_once(':')?;
+            return Some(Self {
+                spec: format!("{remote_port}:{local_host}:{}", entry.local_port),
+                ssh_host,
+                remote: true,
+            });
+        }
+        Some(Self {
+            spec: format!("{}:{target}:{remote_port}", entry.local_port),
+            ssh_host,
+            remote: false,
+        })
+    }
+
+    /// Port this forward listens on here; `-R` forwards listen on the remote side.
+    fn local_port(&self) -> Option<u16> {
+        if self.remote {
+            return None;
+        }
+        self.spec.split(':').next()?.parse().ok()
+    }
+
+    /// `spec via ssh_host`, with `-R` marked.
+    pub fn label(&self) -> String {
+        let flag = if self.remote { "-R " } else { "" };
+        format!("{flag}{} via {}", self.spec, self.ssh_host)
+    }
+
+    /// Whether one of `tunnels` is this forward.
+    pub fn is_running(&self, tunnels: &[PortEntry]) -> bool {
+        tunnels
+            .iter()
+            .any(|t| Self::from_tunnel(t).as_ref() == Some(self))
+    }
+
+    /// Why this forward should not be started, given the running `tunnels`.
+    pub fn skip_reason(&self, tunnels: &[PortEntry]) -> Option<String> {
+        if self.is_running(tunnels) {
+            return Some("already running".to_string());
+        }
+        self.local_port()
+            .filter(|&port| forward::is_port_listening(port))
+            .map(|port| format!("port {port} already in use"))
+    }
+}
+
+/// The forwards of `tunnels`, without duplicates (one ssh process may be listed
+/// once per connection in the merged view).
+pub fn forwards_of(tunnels: &[PortEntry]) -> Vec<SnapshotForward> {
+    let mut forwards: Vec<SnapshotForward> = Vec::new();
+    for forward in tunnels.iter().filter_map(SnapshotForward::from_tunnel) {
+        if !forwards.contains(&forward) {
+            forwards.push(forward);
+        }
+    }
+    forwards
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Local time the snapshot was saved, for `quay snapshot list`.
+    #[serde(default)]
+    pub saved_at: Option<String>,
+    #[serde(default)]
+    pub forward: Vec<SnapshotForward>,
+}
+
+impl Snapshot {
+    /// A snapshot of `forwards`, stamped with the current time.
+    pub fn now(forward: Vec<SnapshotForward>) -> Self {
+        Self {
+            saved_at: Some(chrono::Local::now().format("%Y-%m-%d %H:%M").to_string()),
+            forward,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshots {
+    /// Snapshot name to its forwards.
+    #[serde(default)]
+    pub snapshots: BTreeMap<String, Snapshot>,
+    /// Set when snapshots.toml exists but could not be read; saving would
+    /// replace the user's snapshots with the ones made since.
+    #[serde(skip)]
+    pub load_failed: bool,
+}
+
+impl StateFile for Snapshots {
+    const NAME: &'static str = "snapshots.toml";
+}
+
+impl Snapshots {
+    /// The stored snapshots, or none with `load_failed` set after recording
+    /// why snapshots.toml could not be read in `diagnostics`.
+    pub fn load_or_default(diagnostics: &mut Vec<ConfigDiagnostic>) -> Self {
+        Self::load().unwrap_or_else(|d| {
+            diagnostics.push(d);
+            Self {
+                load_failed: true,
+                ..Self::default()
+            }
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if self.load_failed {
+            anyhow::bail!("snapshots.toml has errors; fix it before changing snapshots here");
+        }
+        let Some(path) = Self::path() else {
+            anyhow::bail!("Could not determine state directory");
+        };
+        state::save_to(&path, self)
+    }
+
+    /// The snapshot called `name`, with a helpful error if there is none.
+    pub fn get(&self, name: &str) -> anyhow::Result<&Snapshot> {
+        self.snapshots.get(name).ok_or_else(|| {
+            if self.snapshots.is_empty() {
+                anyhow::anyhow!("No snapshot '{name}' (save one with `quay snapshot save <name>`)")
+            } else {
+                let names: Vec<&str> = self.snapshots.keys().map(String::as_str).collect();
+                anyhow::anyhow!(
+                    "No snapshot '{name}'. Available snapshots: {}",
+                    names.join(", ")
+                )
+            }
+        })
+    }
+}
+
+pub async fn run_snapshot_command(command: SnapshotCommands) -> anyhow::Result<()> {
+    let stored = Snapshots::load()?;
+    match command {
+        SnapshotCommands::Save { name } => {
+            let forwards = forwards_of(&port::ssh::collect().await?);
+            if forwards.is_empty() {
+                anyhow::bail!("No SSH forwards running; nothing to save");
+            }
+            println!("Saved snapshot '{name}' ({} forward(s))", forwards.len());
+            for forward in &forwards {
+                println!("  {}", forward.label());
+            }
+            Snapshots::update(|stored| stored.snapshots.insert(name, Snapshot::now(forwards)))?;
+            Ok(())
+        }
+        SnapshotCommands::Restore { name } => {
+            let snapshot = stored.get(&name)?;
+            let tunnels = port::ssh::collect().await?;
+            restore(&name, snapshot, &tunnels)
+        }
+        SnapshotCommands::List => {
+            if stored.snapshots.is_empty() {
+                println!("No snapshots saved (save one with `quay snapshot save <name>`)");
+                return Ok(());
+            }
+            println!("{:<20} {:<17} FORWARDS", "NAME", "SAVED");
+            for (name, snapshot) in &stored.snapshots {
+                println!(
+                    "{name:<20} {:<17} {}",
+                    snapshot.saved_at.as_deref().unwrap_or("-"),
+                    snapshot.forward.len()
+                );
+            }
+            Ok(())
+        }
+        SnapshotCommands::Delete { name } => {
+            stored.get(&name)?;
+            Snapshots::update(|stored| stored.snapshots.remove(&name))?;
+            println!("Deleted snapshot '{name}'");
+            Ok(())
+        }
+    }
+}
+
+/// Start the forwards of `snapshot` that aren't in `tunnels`, waiting for each.
+fn restore(name: &str, snapshot: &Snapshot, tunnels: &[PortEntry]) -> anyhow::Result<()> {
+    println!(
+        "Restoring snapshot '{name}' ({} forward(s))",
+        snapshot.forward.len()
+    );
+    let mut failed = 0;
+    for forward in &snapshot.forward {
+        let label = forward.label();
+        if let Some(reason) = forward.skip_reason(tunnels) {
+            println!("  skip  {label}: {reason}");
+            continue;
+        }
+        match port::ssh::create_forward_checked(
+            &forward.spec,
+            &forward.ssh_host,
+            forward.remote,
+            &[],
+        ) {
+            Ok(()) => println!("  ok    {label}"),
+            Err(e) => {
+                failed += 1;
+                println!("  fail  {label}: {e}");
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} forward(s) failed", snapshot.forward.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel(local_port: u16, target: &str, remote_port: u16, process: &str) -> PortEntry {
+        PortEntry {
+            source: PortSource::Ssh,
+            local_port,
+            remote_host: Some(target.to_string()),
+            remote_port: Some(remote_port),
+            process_name: process.to_string(),
+            pid: Some(100),
+            ssh_host: Some("bastion".to_string()),
+            is_open: true,
+            ..PortEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_forwards_of_tunnels() {
+        let mut no_host = tunnel(7000, "localhost", 7000, "ssh");
+        no_host.ssh_host = None;
+        let tunnels = vec![
+            tunnel(5432, "db.internal", 5432, "ssh"),
+            tunnel(5432, "db.internal", 5432, "ssh"),
+            tunnel(3000, "(R) localhost:9000", 9000, "ssh -R"),
+            no_host,
+        ];
+        let forwards = forwards_of(&tunnels);
+        assert_eq!(forwards.len(), 2);
+        assert_eq!(forwards[0].spec, "5432:db.internal:5432");
+        assert!(!forwards[0].remote);
+        assert_eq!(forwards[1].spec, "9000:localhost:3000");
+        assert!(forwards[1].remote);
+        assert_eq!(forwards[1].label(), "-R 9000:localhost:3000 via bastion");
+
+        assert_eq!(
+            forwards[0].skip_reason(&tunnels).as_deref(),
+            Some("already running")
+        );
+        assert_eq!(forwards[1].local_port(), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut stored = Snapshots::default();
+        let forward = vec![SnapshotForward {
+            spec: "8080:localhost:80".to_string(),
+            ssh_host: "staging".to_string(),
+            remote: false,
+        }];
+        stored
+            .snapshots
+            .insert("work".to_string(), Snapshot::now(forward));
+
+        let content = toml::to_string_pretty(&stored).unwrap();
+        let parsed: Snapshots = toml::from_str(&content).unwrap();
+        assert_eq!(parsed, stored);
+        assert_eq!(parsed.get("work").unwrap().forward.len(), 1);
+        let err = parsed.get("home").unwrap_err().to_string();
+        assert!(err.contains("Available snapshots: work"), "{err}");
+    }
+
+    #[test]
+    fn test_save_refused_after_failed_load() {
+        let snapshots = Snapshots {
+            load_failed: true,
+            ..Snapshots::default()
+        };
+        let error = snapshots.save().unwrap_err();
+        assert!(error.to_string().contains("snapshots.toml has errors"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Runtime state quay keeps between runs, as opposed to the configuration
+//! you write: the forwards to restore and the history log.
+//!
+//! It lives in `$XDG_STATE_HOME/quay` (`~/.local/state/quay` by default), in
+//! `~/Library/Application Support/quay` on macOS, and in
+//! `%LOCALAPPDATA%\quay` on Windows; `XDG_STATE_HOME` wins everywhere. A file
+//! still in the config directory, where older versions kept it, is moved
+//! over the first time it is used.
+//!
+//! A [`StateFile`] is read and written whole while holding a lock on a
+//! `.lock` file next to it, and saved through a temporary file and a rename,
+//! so two quay processes never see half a file or write over each other's
+//! temporary file. [`StateFile::update`] keeps the lock from the read to the
+//! rename, so a change made by another process in between is not lost.
+//!
+//! On Windows the lock file is created but not locked (`File::lock` needs a
+//! newer Rust than quay's minimum), so only the rename protects the file
+//! there, and concurrent updates can overwrite each other.
+
+use crate::config::{self, Config, ConfigDiagnostic};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A state file read and written whole, as TOML.
+pub trait StateFile: Serialize + DeserializeOwned + Default {
+    /// File name in the state directory.
+    const NAME: &'static str;
+
+    fn path() -> Option<PathBuf> {
+        path(Self::NAME)
+    }
+
+    /// The stored state; the default when there is none yet.
+    fn load() -> Result<Self, ConfigDiagnostic> {
+        match Self::path() {
+            Some(path) => load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Apply `change` to the stored state and save it, under one lock.
+    fn update<R>(change: impl FnOnce(&mut Self) -> R) -> anyhow::Result<R> {
+        let Some(path) = Self::path() else {
+            anyhow::bail!("Could not determine state directory");
+        };
+        update_at(&path, change)
+    }
+}
+
+/// `quay` in the state directory of this platform.
+pub fn dir() -> Option<PathBuf> {
+    resolve_dir(
+        std::env::var_os("XDG_STATE_HOME"),
+        std::env::var_os("HOME").map(PathBuf::from),
+        std::env::var_os("LOCALAPPDATA"),
+    )
+}
+
+fn resolve_dir(
+    xdg_state_home: Option<OsString>,
+    home: Option<PathBuf>,
+    local_app_data: Option<OsString>,
+) -> Option<PathBuf> {
+    // The XDG spec says to ignore a relative path
+    if let Some(dir) = xdg_state_home
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+    {
+        return Some(dir.join("quay"));
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(local_app_data?)
+    } else if cfg!(target_os = "macos") {
+        home?.join("Library").join("Application Support")
+    } else {
+        home?.join(".local").join("state")
+    };
+    Some(base.join("quay"))
+}
+
+/// Path of state file `name`, after moving it out of the config directory
+/// if an older version left it there.
+pub fn path(name: &str) -> Option<PathBuf> {
+    let path = dir()?.join(name);
+    if let Some(legacy) = Config::config_dir().map(|d| d.join(name)) {
+        migrate(&legacy, &path);
+    }
+    Some(path)
+}
+
+/// Move `legacy` to `path` unless `path` already exists. A file that can't
+/// be moved stays where it was, and `path` starts empty.
+fn migrate(legacy: &Path, path: &Path) {
+    if legacy == path || path.exists() || !legacy.is_file() {
+        return;
+    }
+    let moved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        // A rename fails across file systems
+        .and_then(|()| {
+            fs::rename(legacy, path)
+                .or_else(|_| fs::copy(legacy, path).and_then(|_| fs::remove_file(legacy)))
+        });
+    match moved {
+        Ok(()) => tracing::info!(from = %legacy.display(), to = %path.display(), "state moved"),
+        Err(e) => tracing::warn!(error = %e, path = %legacy.display(), "state not moved"),
+    }
+}
+
+/// Read `path` under its lock; a missing file is the default.
+pub fn load_from<T: DeserializeOwned + Default>(path: &Path) -> Result<T, ConfigDiagnostic> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let _lock = lock(path).map_err(|e| ConfigDiagnostic::from_io(path, &e))?;
+    config::load_file(Some(path.to_path_buf()))
+}
+
+/// Write `value` to `path` under its lock, replacing the file at once.
+pub fn save_to<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let content = toml::to_string_pretty(value)?;
+    let _lock = lock(path)?;
+    replace(path, &content)?;
+    Ok(())
+}
+
+/// Read `path`, apply `change`, and write it back, all under its lock. A
+/// file that can't be read is left alone rather than replaced.
+pub fn update_at<T, R>(path: &Path, change: impl FnOnce(&mut T) -> R) -> anyhow::Result<R>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let _lock = lock(path)?;
+    let mut value: T = config::load_file(Some(path.to_path_buf()))?;
+    let result = change(&mut value);
+    replace(path, &toml::to_string_pretty(&value)?)?;
+    Ok(result)
+}
+
+/// Write `content` to a temporary file next to `path` and rename it over
+/// `path`, so a failed write never leaves half a file behind.
+pub fn replace(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = sibling(path, "tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)
+}
+
+/// `path` with `.suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Hold the lock of `path` until the returned value is dropped.
+fn lock(path: &Path) -> io::Result<Lock> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, "lock"))?;
+    lock_file(file)
+}
+
+#[cfg(unix)]
+type Lock = nix::fcntl::Flock<fs::File>;
+
+#[cfg(unix)]
+fn lock_file(file: fs::File) -> io::Result<Lock> {
+    nix::fcntl::Flock::lock(file, nix::fcntl::FlockArg::LockExclusive)
+        .map_err(|(_, errno)| errno.into())
+}
+
+#[cfg(windows)]
+type Lock = fs::File;
+
+/// Only creates the lock file: `File::lock` needs a newer Rust than quay's
+/// minimum, and `LockFileEx` would need `unsafe`. Warns once per run.
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+fn lock_file(file: fs::File) -> io::Result<Lock> {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        tracing::warn!("state files are not locked on Windows; run one quay at a time");
+    });
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("quay-state-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_resolve_dir() {
+        let home = Some(PathBuf::from("/home/me"));
+        assert_eq!(
+            resolve_dir(Some("/var/state".into()), home.clone(), None),
+            Some(PathBuf::from("/var/state/quay"))
+        );
+        let default = resolve_dir(Some("relative".into()), home, Some("C:\\AppData".into()));
+        if cfg!(windows) {
+            assert_eq!(default, Some(PathBuf::from("C:\\AppData").join("quay")));
+        } else if cfg!(target_os = "macos") {
+            assert_eq!(
+                default,
+                Some(PathBuf::from("/home/me/Library/Application Support/quay"))
+            );
+        } else {
+            assert_eq!(default, Some(PathBuf::from("/home/me/.local/state/quay")));
+        }
+        assert_eq!(resolve_dir(None, None, None), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = test_dir("roundtrip").join("counter.toml");
+        assert_eq!(load_from::<Counter>(&path).unwrap(), Counter::default());
+        save_to(&path, &Counter { count: 3 }).unwrap();
+        assert_eq!(load_from::<Counter>(&path).unwrap(), Counter { count: 3 });
+        assert!(!sibling(&path, "tmp").exists());
+
+        fs::write(&path, "count = \"three\"\n").unwrap();
+        let diagnostic = load_from::<Counter>(&path).unwrap_err();
+        assert_eq!(diagnostic.line, Some(1));
+    }
+
+    #[test]
+    fn test_update() {
+        let path = test_dir("update").join("counter.toml");
+        let bump = |c: &mut Counter| {
+            c.count += 1;
+            c.count
+        };
+        assert_eq!(update_at(&path, bump).unwrap(), 1);
+        assert_eq!(update_at(&path, bump).unwrap(), 2);
+        assert_eq!(load_from::<Counter>(&path).unwrap(), Counter { count: 2 });
+
+        // A broken file is not overwritten
+        fs::write(&path, "count = \"three\"\n").unwrap();
+        assert!(update_at(&path, bump).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "count = \"three\"\n");
+    }
+
+    #[test]
+    fn test_migrate() {
+        let dir = test_dir("migrate");
+        let legacy = dir.join("config").join("forwards.toml");
+        let path = dir.join("state").join("forwards.toml");
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        fs::write(&legacy, "old").unwrap();
+
+        migrate(&legacy, &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(!legacy.exists());
+
+        // An existing state file is kept
+        fs::write(&legacy, "older").unwrap();
+        migrate(&legacy, &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(legacy.exists());
+    }
+}
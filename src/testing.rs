@@ -0,0 +1,23 @@
+//! Port rows for the unit tests of both the library and the binary, which
+//! each compile this file as their own `testing` module.
+
+use crate::port::{PortEntry, PortSource};
+
+/// A closed row of `source` on `port` that nothing else is known about.
+pub fn row(source: PortSource, port: u16) -> PortEntry {
+    PortEntry {
+        source,
+        local_port: port,
+        ..PortEntry::default()
+    }
+}
+
+/// An open row of `source` on `port`, owned by `process` with pid 42.
+pub fn entry(source: PortSource, port: u16, process: &str) -> PortEntry {
+    PortEntry {
+        process_name: process.to_string(),
+        pid: Some(42),
+        is_open: true,
+        ..row(source, port)
+    }
+}
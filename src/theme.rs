@@ -8,6 +8,7 @@ pub const ACCENT: Color = Color::Yellow;
 pub const SUCCESS: Color = Color::Green;
 pub const ERROR: Color = Color::Red;
 pub const MUTED: Color = Color::DarkGray;
+pub const FOREIGN: Color = Color::Magenta;
 pub const POPUP_BG: Color = Color::Black;
 pub const HIGHLIGHT_BG: Color = Color::Indexed(237);
 
@@ -36,6 +37,11 @@ pub fn error_bold() -> Style {
     Style::default().fg(ERROR).add_modifier(Modifier::BOLD)
 }
 
+/// Rows and labels for processes owned by another user.
+pub fn foreign() -> Style {
+    Style::default().fg(FOREIGN)
+}
+
 pub fn cursor(valid: bool) -> Style {
     let color = if valid { ACCENT } else { ERROR };
     Style::default()
@@ -0,0 +1,138 @@
+//! Opening shells and forwards for the selected entry in tmux.
+//!
+//! When quay runs inside tmux, a remote shell or a foreground `ssh -L` can
+//! get its own window or pane, so closing the pane ends the session or the
+//! forward.
+
+use crate::action;
+use crate::config::TmuxLayout;
+use crate::port::{self, CommandError, PortEntry, PortSource};
+
+/// Whether quay runs inside a tmux session.
+pub fn inside() -> bool {
+    std::env::var_os("TMUX").is_some()
+}
+
+/// Default shell for `entry`: inside its container, or on the host it runs on.
+///
+/// `host` is the active connection's remote host; `docker_target` its
+/// container. Local processes have no shell of their own.
+pub fn shell_command(
+    entry: &PortEntry,
+    host: Option<&str>,
+    docker_target: Option<&str>,
+) -> Option<String> {
+    let container = docker_target.or(match entry.source {
+        PortSource::Docker => entry
+            .container_id
+            .as_deref()
+            .or(entry.container_name.as_deref()),
+        _ => None,
+    });
+    if let Some(container) = container {
+        let exec = port::docker::command(&["exec", "-it", container, "sh"]);
+        let exec = action::quote_command(&exec.iter().map(String::as_str).collect::<Vec<_>>());
+        return Some(match host {
+            Some(host) => action::quote_command(&["ssh", "-t", host, &exec]),
+            None => exec,
+        });
+    }
+    match (&entry.source, host, entry.ssh_host.as_deref()) {
+        (PortSource::Ssh, _, Some(ssh_host)) => Some(action::quote_command(&["ssh", ssh_host])),
+        (PortSource::Ssh, _, None) | (_, None, _) => None,
+        (_, Some(host), _) => Some(action::quote_command(&["ssh", host])),
+    }
+}
+
+/// Foreground forward for a tmux pane; it ends when the pane closes.
+pub fn forward_command(spec: &str, host: &str) -> String {
+    action::quote_command(&[
+        "ssh",
+        "-N",
+        "-o",
+        "ExitOnForwardFailure=yes",
+        "-L",
+        spec,
+        host,
+    ])
+}
+
+/// Arguments to `tmux` that run `command` in a new window named `name`, or a new pane.
+fn open_args(layout: TmuxLayout, name: &str, command: &str) -> Vec<String> {
+    let mut args: Vec<String> = match layout {
+        TmuxLayout::Window => vec!["new-window".into(), "-n".into(), name.into()],
+        TmuxLayout::Split => vec!["split-window".into(), "-v".into()],
+        TmuxLayout::Vsplit => vec!["split-window".into(), "-h".into()],
+    };
+    args.push(command.to_string());
+    args
+}
+
+/// Run `command` in a new tmux window or pane.
+pub fn open(layout: TmuxLayout, name: &str, command: &str) -> anyhow::Result<()> {
+    let args = open_args(layout, name, command);
+    tracing::info!(?args, "opening tmux pane");
+    let output = std::process::Command::new("tmux").args(&args).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Err(CommandError::new("tmux", &args, None, &output).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn entry(source: PortSource) -> PortEntry {
+        PortEntry {
+            container_id: Some("abc123".to_string()),
+            container_name: Some("web".to_string()),
+            ssh_host: Some("bastion".to_string()),
+            ..testing::entry(source, 8080, "app")
+        }
+    }
+
+    #[test]
+    fn test_shell_command() {
+        assert_eq!(
+            shell_command(&entry(PortSource::Docker), None, None).unwrap(),
+            "docker exec -it abc123 sh"
+        );
+        assert_eq!(
+            shell_command(&entry(PortSource::Docker), Some("lab"), None).unwrap(),
+            "ssh -t lab 'docker exec -it abc123 sh'"
+        );
+        assert_eq!(
+            shell_command(&entry(PortSource::Local), Some("lab"), Some("dev")).unwrap(),
+            "ssh -t lab 'docker exec -it dev sh'"
+        );
+        assert_eq!(
+            shell_command(&entry(PortSource::Local), Some("lab"), None).unwrap(),
+            "ssh lab"
+        );
+        assert_eq!(
+            shell_command(&entry(PortSource::Ssh), None, None).unwrap(),
+            "ssh bastion"
+        );
+        assert!(shell_command(&entry(PortSource::Local), None, None).is_none());
+    }
+
+    #[test]
+    fn test_open_args() {
+        assert_eq!(
+            open_args(TmuxLayout::Window, ":8080", "ssh lab"),
+            vec!["new-window", "-n", ":8080", "ssh lab"]
+        );
+        assert_eq!(
+            open_args(TmuxLayout::Vsplit, ":8080", "ssh lab"),
+            vec!["split-window", "-h", "ssh lab"]
+        );
+        assert_eq!(
+            forward_command("8080:localhost:8080", "lab"),
+            "ssh -N -o ExitOnForwardFailure=yes -L '8080:localhost:8080' lab"
+        );
+    }
+}
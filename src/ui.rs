@@ -1,43 +1,290 @@
 use crate::app::{
-    App, ConnectionField, ConnectionPopupMode, Filter, ForwardField, InputMode, Popup,
+    App, ConnectionField, ConnectionPopupMode, Filter, ForwardField, GroupKey, InputMode, Popup,
+    PresetField, PresetPopupMode, SLOW_PHASE, SessionStats, SortKey, TableRow,
 };
+use crate::config::UiLayout;
+use crate::connection::Health;
+use crate::event::{Action, KEYMAP, KeyCategory, key_label};
+use crate::logging;
+use crate::output::Format;
+use crate::port::docker::VmBackend;
+use crate::port::{PortEntry, PortSource, SourceStatus, TunnelHealth, listeners};
 use crate::theme;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Cell, Clear, Paragraph, Row, Table, TableState},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
 };
+use std::time::{Duration, Instant};
+
+/// Rows taken by the log pane (including its border) when it is shown.
+const LOG_PANE_HEIGHT: u16 = 10;
+
+/// Terminals shorter than this are drawn compact regardless of the setting.
+const COMPACT_HEIGHT: u16 = 16;
+
+/// Narrower than this, the split layout falls back to the full-width table.
+const SPLIT_MIN_WIDTH: u16 = 100;
+
+/// A column of the ports table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Connection,
+    Type,
+    /// TYPE abbreviated to its first letter.
+    TypeShort,
+    Local,
+    /// Probe latency, with `ui.latency_column`.
+    Latency,
+    Remote,
+    User,
+    /// Well-known service name of the port.
+    Service,
+    /// CPU use of the process or container, with `ui.usage_columns` or
+    /// sorted by usage.
+    Cpu,
+    /// Resident memory of the process, likewise.
+    Memory,
+    Process,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Connection => "CONNECTION",
+            Column::Type => "TYPE",
+            Column::TypeShort => "T",
+            Column::Local => "LOCAL",
+            Column::Latency => "LATENCY",
+            Column::Remote => "REMOTE",
+            Column::User => "USER",
+            Column::Service => "SERVICE",
+            Column::Cpu => "CPU",
+            Column::Memory => "MEM",
+            Column::Process => "PROCESS/CONTAINER",
+        }
+    }
+
+    /// Width the column needs; PROCESS/CONTAINER takes whatever is left.
+    fn min_width(self) -> u16 {
+        match self {
+            Column::Connection | Column::Local => 16,
+            Column::Type | Column::Latency => 8,
+            Column::TypeShort => 3,
+            Column::Remote => 20,
+            Column::User => 10,
+            Column::Service => 14,
+            Column::Cpu => 7,
+            Column::Memory => 6,
+            Column::Process => 12,
+        }
+    }
+
+    fn constraint(self) -> Constraint {
+        match self {
+            Column::Process => Constraint::Min(self.min_width()),
+            _ => Constraint::Length(self.min_width()),
+        }
+    }
+}
+
+/// Columns that fit in a table `width` cells wide (inside its borders).
+///
+/// LATENCY (when `latency` asks for it) is dropped first, then SERVICE, MEM
+/// and CPU (with `usage`), USER, REMOTE, and CONNECTION; if that is still too
+/// wide, TYPE shrinks to one letter.
+fn columns(width: u16, aggregate: bool, latency: bool, usage: bool) -> Vec<Column> {
+    // The highlight symbol, and one space between columns
+    let fits = |columns: &[Column]| {
+        let cells: u16 = columns.iter().map(|c| c.min_width()).sum();
+        let gaps = u16::try_from(columns.len()).unwrap_or(u16::MAX) - 1;
+        cells + gaps + 2 <= width
+    };
+    let mut columns = vec![
+        Column::Type,
+        Column::Local,
+        Column::Remote,
+        Column::User,
+        Column::Service,
+        Column::Process,
+    ];
+    if usage {
+        columns.insert(columns.len() - 1, Column::Cpu);
+        columns.insert(columns.len() - 1, Column::Memory);
+    }
+    if latency {
+        columns.insert(2, Column::Latency);
+    }
+    if aggregate {
+        columns.insert(0, Column::Connection);
+    }
+    for drop in [
+        Column::Latency,
+        Column::Service,
+        Column::Memory,
+        Column::Cpu,
+        Column::User,
+        Column::Remote,
+        Column::Connection,
+    ] {
+        if fits(&columns) {
+            break;
+        }
+        columns.retain(|&c| c != drop);
+    }
+    if !fits(&columns) {
+        for column in &mut columns {
+            if *column == Column::Type {
+                *column = Column::TypeShort;
+            }
+        }
+    }
+    columns
+}
+
+/// Whether to draw without borders: asked for with `z`, or forced on short terminals.
+fn is_compact(app: &App, area: Rect) -> bool {
+    app.compact || area.height < COMPACT_HEIGHT
+}
+
+/// Border around the header and filter bar, dropped in compact mode.
+fn bar_block(compact: bool) -> Block<'static> {
+    if compact {
+        Block::default()
+    } else {
+        theme::plain_block()
+    }
+}
+
+fn log_pane_height(app: &App) -> u16 {
+    if app.show_logs { LOG_PANE_HEIGHT } else { 0 }
+}
+
+/// Where the ports table ended up on screen, for mouse hit-testing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScreenLayout {
+    /// Table rows below the border and column header; empty when no table is shown.
+    pub table_body: Rect,
+    /// Index of the first visible row; the table scrolls to keep the selection in view.
+    pub table_offset: usize,
+    /// Furthest the open Details or Help popup can scroll.
+    pub popup_max_scroll: usize,
+}
+
+impl ScreenLayout {
+    /// Index into `App::rows` of the row drawn at screen position (`column`, `row`).
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let body = self.table_body;
+        let inside = column >= body.x
+            && column < body.x + body.width
+            && row >= body.y
+            && row < body.y + body.height;
+        inside.then(|| self.table_offset + usize::from(row - body.y))
+    }
+}
 
-pub fn draw(frame: &mut Frame, app: &App) {
+pub fn draw(frame: &mut Frame, app: &App) -> ScreenLayout {
+    let compact = is_compact(app, frame.area());
+    let bar_height = if compact { 1 } else { 3 };
+    let banner_height = u16::from(!app.config_diagnostics.is_empty());
+    let log_height = log_pane_height(app);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Length(3), // Filter/Search
-            Constraint::Min(5),    // Table
-            Constraint::Length(2), // Footer
+            Constraint::Length(bar_height),             // Header
+            Constraint::Length(banner_height),          // Config warning
+            Constraint::Length(bar_height),             // Filter/Search
+            Constraint::Min(3),                         // Table
+            Constraint::Length(log_height),             // Log pane
+            Constraint::Length(2 - u16::from(compact)), // Footer
         ])
         .split(frame.area());
 
-    draw_header(frame, app, chunks[0]);
-    draw_filter_bar(frame, app, chunks[1]);
-    draw_table(frame, app, chunks[2]);
-    draw_footer(frame, app, chunks[3]);
+    draw_header(frame, app, chunks[0], compact);
+    if banner_height > 0 {
+        draw_config_banner(frame, app, chunks[1]);
+    }
+    draw_filter_bar(frame, app, chunks[2], compact);
+    let mut layout = if app.layout == UiLayout::Split && chunks[3].width >= SPLIT_MIN_WIDTH {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[3]);
+        draw_details_pane(frame, app, panes[1], compact);
+        draw_table(frame, app, panes[0], compact)
+    } else {
+        draw_table(frame, app, chunks[3], compact)
+    };
+    if log_height > 0 {
+        draw_log_pane(frame, chunks[4]);
+    }
+    draw_footer(frame, app, chunks[5]);
 
     // Draw popup if active
     match app.popup {
-        Popup::Details => draw_details_popup(frame, app),
-        Popup::Help => draw_help_popup(frame, app),
+        Popup::Details => layout.popup_max_scroll = draw_details_popup(frame, app),
+        Popup::Help => layout.popup_max_scroll = draw_help_popup(frame, app),
         Popup::Forward => draw_forward_popup(frame, app),
         Popup::Presets => draw_presets_popup(frame, app),
         Popup::Connections => draw_connections_popup(frame, app),
+        Popup::LaunchResults => draw_launch_results_popup(frame, app),
+        Popup::Auth => draw_auth_popup(frame, app),
+        Popup::Error => draw_error_popup(frame, app),
+        Popup::SudoKill => draw_sudo_kill_popup(frame, app),
+        Popup::Rerun => draw_rerun_popup(frame, app),
+        Popup::StartService => draw_start_popup(frame, app),
+        Popup::Messages => draw_messages_popup(frame, app),
+        Popup::Insights => draw_insights_popup(frame, app),
+        Popup::History => draw_history_popup(frame, app),
+        Popup::ContextMenu => draw_context_menu(frame, app),
+        Popup::Snapshots => draw_snapshots_popup(frame, app),
+        Popup::Export => draw_export_popup(frame, app),
+        Popup::Scan => draw_scan_popup(frame, app),
+        Popup::Established => layout.popup_max_scroll = draw_established_popup(frame, app),
+        Popup::Output => layout.popup_max_scroll = draw_output_popup(frame, app),
         Popup::None => {}
     }
+    layout
+}
+
+fn draw_config_banner(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(first) = app.config_diagnostics.first() else {
+        return;
+    };
+    let mut spans = vec![Span::styled(
+        format!(" \u{26a0} {first}"),
+        theme::error_bold(),
+    )];
+    let more = app.config_diagnostics.len() - 1;
+    if more > 0 {
+        spans.push(Span::styled(format!(" (+{more} more)"), theme::error()));
+    }
+    spans.push(Span::styled(
+        "  run `quay config validate` for details",
+        theme::muted(),
+    ));
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_log_pane(frame: &mut Frame, area: Rect) {
+    let block = theme::popup_block("Log (L to hide)");
+    let lines: Vec<Line> = logging::recent(usize::from(block.inner(area).height))
+        .into_iter()
+        .map(|line| {
+            let style = if line.contains(" ERROR ") || line.contains(" WARN ") {
+                theme::error()
+            } else {
+                theme::muted()
+            };
+            Line::from(Span::styled(line, style))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(block), area);
 }
 
-fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_header(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
     let content = if app.has_multiple_connections() {
         let conn_name = app
             .active_connection()
@@ -48,10 +295,16 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
         let mut spans = vec![
             Span::styled("\u{2693} Quay  ", theme::title()),
             Span::styled("\u{25c0} ", theme::muted()),
+        ];
+        if let Some(health) = app.active_connection().and_then(|c| app.health_of(c)) {
+            spans.push(health_dot(health));
+            spans.push(Span::raw(" "));
+        }
+        spans.extend([
             Span::styled(conn_name, theme::highlight()),
             Span::styled(" \u{25b6}", theme::muted()),
             Span::styled(format!("  [{index}/{total}]"), theme::muted()),
-        ];
+        ]);
 
         // Show remote/docker info
         match (&app.remote_host, &app.docker_target) {
@@ -75,6 +328,10 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
             }
             (None, None) => {}
         }
+        spans.extend(source_badges(&app.sources, &app.entries, app.docker_vm));
+        spans.extend(compose_badge(app));
+        spans.extend(insights_badge(app));
+        spans.extend(refresh_badge(app.last_refresh, app.slowest_phase()));
 
         Line::from(spans)
     } else {
@@ -86,14 +343,134 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
             (Some(host), None) => format!("\u{2693} Quay [remote: {host}]"),
             (None, None) => "\u{2693} Quay - Port Manager".to_string(),
         };
-        Line::from(Span::styled(title_text, theme::title()))
+        let mut spans = vec![Span::styled(title_text, theme::title())];
+        spans.extend(source_badges(&app.sources, &app.entries, app.docker_vm));
+        spans.extend(compose_badge(app));
+        spans.extend(insights_badge(app));
+        spans.extend(refresh_badge(app.last_refresh, app.slowest_phase()));
+        Line::from(spans)
     };
 
-    let title = Paragraph::new(content).block(theme::plain_block());
+    let title = Paragraph::new(content).block(bar_block(compact));
     frame.render_widget(title, area);
 }
 
-fn draw_filter_bar(frame: &mut Frame, app: &App, area: Rect) {
+/// `LOCAL ✓  DOCKER ✗ daemon down  SSH ✓ 3 tunnels`: how each source fared
+/// in the last collection, with the VM Docker runs in (`DOCKER ✓ Colima`).
+fn source_badges(
+    sources: &[(PortSource, SourceStatus)],
+    entries: &[PortEntry],
+    docker_vm: Option<VmBackend>,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for (source, status) in sources {
+        spans.push(Span::styled(format!("  {source} "), theme::muted()));
+        match status {
+            SourceStatus::Ok => {
+                spans.push(Span::styled(
+                    "\u{2713}",
+                    Style::default().fg(theme::SUCCESS),
+                ));
+                if *source == PortSource::Ssh {
+                    let tunnels = entries
+                        .iter()
+                        .filter(|e| e.source == PortSource::Ssh)
+                        .count();
+                    let noun = if tunnels == 1 { "tunnel" } else { "tunnels" };
+                    spans.push(Span::styled(format!(" {tunnels} {noun}"), theme::muted()));
+                }
+                if let (PortSource::Docker, Some(vm)) = (source, docker_vm) {
+                    spans.push(Span::styled(format!(" {vm}"), theme::muted()));
+                }
+            }
+            SourceStatus::Failed(reason) => spans.push(Span::styled(
+                format!("\u{2717} {reason}"),
+                Style::default().fg(theme::ERROR),
+            )),
+        }
+    }
+    spans
+}
+
+/// `COMPOSE 2/3 up`, in the error color while a service is down.
+fn compose_badge(app: &App) -> Option<Span<'static>> {
+    let (up, total) = app.compose_summary()?;
+    let style = if up < total {
+        theme::error()
+    } else {
+        Style::default().fg(theme::SUCCESS)
+    };
+    Some(Span::styled(format!("  COMPOSE {up}/{total} up"), style))
+}
+
+/// `[i] 2 insights` while the Insights popup has something to show.
+fn insights_badge(app: &App) -> Option<Span<'static>> {
+    let count = app.insights().len();
+    let noun = if count == 1 { "insight" } else { "insights" };
+    (count > 0).then(|| {
+        Span::styled(
+            format!("  [i] {count} {noun}"),
+            Style::default().fg(theme::ACCENT),
+        )
+    })
+}
+
+/// `last refresh: 3.2s (ssh: 2.9s)`, with the slowest phase; a warning
+/// while that phase takes [`SLOW_PHASE`] or longer.
+fn refresh_badge(
+    total: Option<Duration>,
+    slowest: Option<(&str, Duration)>,
+) -> Option<Span<'static>> {
+    let total = total?;
+    let phase = slowest.map_or(String::new(), |(name, elapsed)| {
+        format!(" ({name}: {:.1}s)", elapsed.as_secs_f32())
+    });
+    let text = format!("  last refresh: {:.1}s{phase}", total.as_secs_f32());
+    let style = if slowest.is_some_and(|(_, elapsed)| elapsed >= SLOW_PHASE) {
+        theme::error()
+    } else {
+        theme::muted()
+    };
+    Some(Span::styled(text, style))
+}
+
+/// `LOCAL 5  DOCKER 3 · 7 open, 1 closed · 2 forwards started` along the
+/// bottom border of the table.
+fn stats_line(stats: &SessionStats) -> Line<'static> {
+    let mut parts: Vec<String> = Vec::new();
+    if !stats.per_source.is_empty() {
+        let sources: Vec<String> = stats
+            .per_source
+            .iter()
+            .map(|(source, count)| format!("{source} {count}"))
+            .collect();
+        parts.push(sources.join("  "));
+    }
+    parts.push(format!("{} open, {} closed", stats.open, stats.closed));
+    match stats.forwards_started {
+        0 => {}
+        1 => parts.push("1 forward started".to_string()),
+        n => parts.push(format!("{n} forwards started")),
+    }
+    Line::from(Span::styled(
+        format!(" {} ", parts.join(" \u{b7} ")),
+        theme::muted(),
+    ))
+}
+
+/// Status dot for a connection: green reachable, red auth or host key
+/// failure, yellow timeout, gray while checking or for other failures.
+fn health_dot(health: &Health) -> Span<'static> {
+    let color = match health {
+        Health::Reachable(_) => theme::SUCCESS,
+        Health::AuthFailed | Health::HostKey => theme::ERROR,
+        Health::TimedOut => theme::ACCENT,
+        Health::Checking | Health::Unreachable(_) => theme::MUTED,
+    };
+    Span::styled("\u{25cf}", Style::default().fg(color))
+}
+
+fn draw_filter_bar(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
     let filter_text = match app.filter {
         Filter::All => "[0] All",
         Filter::Local => "[1] Local",
@@ -101,19 +478,30 @@ fn draw_filter_bar(frame: &mut Frame, app: &App, area: Rect) {
         Filter::Docker => "[3] Docker",
     };
 
-    let auto_refresh_indicator = if app.auto_refresh {
-        Span::styled(" [A] Auto", theme::success())
+    let auto_refresh_indicator = if app.activity {
+        let secs = app.current_refresh_ticks().div_ceil(4);
+        Span::styled(format!(" [t] Activity {secs}s"), theme::success())
+    } else if app.auto_refresh {
+        let secs = app.refresh_ticks.div_ceil(4);
+        Span::styled(format!(" [A] Auto {secs}s"), theme::success())
     } else {
         Span::styled(" [a] auto", theme::muted())
     };
 
     let content = match app.input_mode {
         InputMode::Search => {
-            vec![
+            let mut spans = vec![
                 Span::raw("Search: "),
                 Span::styled(&app.search_query, Style::default().fg(theme::ACCENT)),
                 Span::styled("_", theme::cursor(true)),
-            ]
+            ];
+            if app.search_query.is_empty() {
+                spans.push(Span::styled(
+                    "  text, or source:docker port:>3000 proc:node open:false user:root",
+                    theme::muted(),
+                ));
+            }
+            spans
         }
         InputMode::Normal => {
             let mut spans = vec![
@@ -127,12 +515,36 @@ fn draw_filter_bar(frame: &mut Frame, app: &App, area: Rect) {
                     Style::default().fg(theme::ACCENT),
                 ));
             }
+            if app.show_hidden {
+                spans.push(Span::styled("  [X] showing hidden", theme::muted()));
+            } else if app.hidden_count > 0 {
+                spans.push(Span::styled(
+                    format!("  {} hidden [X]", app.hidden_count),
+                    theme::muted(),
+                ));
+            }
+            if app.sort != SortKey::Port && !app.activity {
+                spans.push(Span::styled(
+                    format!("  [o] by {}", app.sort.label()),
+                    theme::success(),
+                ));
+            }
             spans.push(Span::raw("  [/] search  [?] help"));
             spans
         }
+        InputMode::PresetLeader => {
+            let mut spans = vec![Span::raw("Preset: ")];
+            for preset in &app.presets {
+                if let Some(key) = preset.hotkey() {
+                    spans.push(Span::styled(format!("[{key}] "), theme::highlight()));
+                    spans.push(Span::raw(format!("{}  ", preset.name)));
+                }
+            }
+            spans
+        }
     };
 
-    let paragraph = Paragraph::new(Line::from(content)).block(theme::plain_block());
+    let paragraph = Paragraph::new(Line::from(content)).block(bar_block(compact));
     frame.render_widget(paragraph, area);
 }
 
@@ -207,80 +619,355 @@ fn draw_empty_state(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
-fn draw_table(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_table(frame: &mut Frame, app: &App, area: Rect, compact: bool) -> ScreenLayout {
     if app.filtered_entries.is_empty() {
         draw_empty_state(frame, app, area);
-        return;
+        return ScreenLayout::default();
     }
 
-    let header_cells = ["TYPE", "LOCAL", "REMOTE", "PROCESS/CONTAINER"]
+    let total = app.rows.len();
+    let current = if total > 0 { app.selected + 1 } else { 0 };
+    let name = if app.activity { "Activity" } else { "Ports" };
+    let title = format!("{name} ({current}/{total})");
+    let block = if compact {
+        Block::default().title(title.as_str())
+    } else {
+        theme::block(&title).title_bottom(stats_line(&app.session_stats()).right_aligned())
+    };
+    // Inside the border (or below the title in compact mode)
+    let inner = block.inner(area);
+
+    // The aggregate view leads with the connection each row came from
+    let columns = columns(
+        inner.width,
+        app.is_aggregate(),
+        app.latency_column,
+        app.shows_usage(),
+    );
+    let header_cells = columns
         .iter()
-        .map(|h| Cell::from(*h).style(theme::highlight()));
+        .map(|c| Cell::from(c.header()).style(theme::highlight()));
     let header = Row::new(header_cells).height(1);
+    let widths: Vec<Constraint> = columns.iter().map(|c| c.constraint()).collect();
 
     let rows: Vec<Row> = app
-        .filtered_entries
+        .rows
         .iter()
-        .map(|entry| {
-            let (indicator, color) = if app.docker_target.is_some() {
-                if entry.is_open {
-                    ("\u{25cf}", theme::SUCCESS)
-                } else {
-                    ("\u{25cf}", theme::ACCENT)
-                }
-            } else if entry.is_open {
-                ("\u{25cf}", theme::SUCCESS)
-            } else {
-                ("\u{25cb}", theme::MUTED)
-            };
-            let local_cell = if let Some(fwd) = entry.forwarded_port {
-                Line::from(vec![
-                    Span::styled(indicator, Style::default().fg(color)),
-                    Span::raw(format!(" :{}", entry.local_port)),
-                    Span::styled(format!("\u{2192}:{fwd}"), Style::default().fg(theme::BRAND)),
-                ])
-            } else {
-                Line::from(vec![
-                    Span::styled(indicator, Style::default().fg(color)),
-                    Span::raw(format!(" :{}", entry.local_port)),
-                ])
-            };
-            Row::new(vec![
-                Cell::from(entry.source.to_string()),
-                Cell::from(local_cell),
-                Cell::from(entry.remote_display()),
-                Cell::from(entry.process_display()),
-            ])
+        .map(|row| match row {
+            TableRow::Entry(index) => {
+                entry_row(app, &app.filtered_entries[*index], false, &columns)
+            }
+            TableRow::Member(index) => {
+                entry_row(app, &app.filtered_entries[*index], true, &columns)
+            }
+            TableRow::Group {
+                key,
+                first,
+                count,
+                expanded,
+            } => group_row(
+                app,
+                key,
+                &app.filtered_entries[*first],
+                *count,
+                *expanded,
+                &columns,
+            ),
         })
         .collect();
 
-    let total = app.filtered_entries.len();
-    let current = if total > 0 { app.selected + 1 } else { 0 };
-    let title = format!("Ports ({current}/{total})");
-
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(8),
-            Constraint::Length(16),
-            Constraint::Length(20),
-            Constraint::Min(20),
-        ],
-    )
-    .header(header)
-    .block(theme::block(&title))
-    .row_highlight_style(theme::row_highlight())
-    .highlight_symbol("> ");
-
-    let mut state = TableState::default();
-    state.select(Some(app.selected));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(theme::row_highlight())
+        .highlight_symbol("> ");
+
+    let mut state = TableState::default()
+        .with_offset(app.table_offset)
+        .with_selected(Some(app.selected));
     frame.render_stateful_widget(table, area, &mut state);
+
+    // Below the column header
+    ScreenLayout {
+        table_body: Rect {
+            y: inner.y + 1,
+            height: inner.height.saturating_sub(1),
+            ..inner
+        },
+        table_offset: state.offset(),
+        popup_max_scroll: 0,
+    }
+}
+
+/// Open/closed dot for an entry, yellow for unreachable container ports; a
+/// half dot for an SSH tunnel that only works on the local end.
+fn open_indicator(app: &App, is_open: bool, tunnel: Option<TunnelHealth>) -> Span<'static> {
+    let (indicator, color) = if tunnel == Some(TunnelHealth::LocalOnly) {
+        ("\u{25d0}", theme::ACCENT)
+    } else if is_open {
+        ("\u{25cf}", theme::SUCCESS)
+    } else if app.docker_target.is_some() {
+        ("\u{25cf}", theme::ACCENT)
+    } else {
+        ("\u{25cb}", theme::MUTED)
+    };
+    Span::styled(indicator, Style::default().fg(color))
+}
+
+/// ` (4)` after the port of an entry with established connections.
+fn connection_badge(established: Option<usize>) -> Option<Span<'static>> {
+    established
+        .filter(|&count| count > 0)
+        .map(|count| Span::styled(format!(" ({count})"), Style::default().fg(theme::SUCCESS)))
+}
+
+/// How the server of an SSH `-R` forward bound its port.
+enum ReverseBind {
+    Missing,
+    LoopbackOnly,
+    Public,
+}
+
+fn reverse_bind_state(binds: &[String]) -> ReverseBind {
+    if binds.is_empty() {
+        ReverseBind::Missing
+    } else if binds.iter().all(|bind| listeners::is_loopback_bind(bind)) {
+        ReverseBind::LoopbackOnly
+    } else {
+        ReverseBind::Public
+    }
+}
+
+/// `127.0.0.1:9090, [::1]:9090`.
+fn bind_list(binds: &[String], port: u16) -> String {
+    binds
+        .iter()
+        .map(|bind| format!("{bind}:{port}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// REMOTE cell, led by a mark when the server of an SSH `-R` forward didn't
+/// bind the port (`✗`) or bound it to loopback only (`⚠`); Details says which.
+fn remote_cell(app: &App, entry: &PortEntry) -> Cell<'static> {
+    let mark = match entry.reverse_bind.as_deref().map(reverse_bind_state) {
+        Some(ReverseBind::Missing) => Some(("\u{2717} ", theme::ERROR)),
+        Some(ReverseBind::LoopbackOnly) => Some(("\u{26a0} ", theme::ACCENT)),
+        Some(ReverseBind::Public) | None => None,
+    };
+    let mut spans: Vec<Span> = mark
+        .map(|(mark, color)| Span::styled(mark, Style::default().fg(color)))
+        .into_iter()
+        .collect();
+    spans.push(Span::raw(app.host_names.remote_display(entry)));
+    Cell::from(Line::from(spans))
+}
+
+/// TYPE cell, starred when pinned; `short` keeps only the first letter
+/// (`?` for scan results, which would clash with SSH).
+fn source_line(entry: &PortEntry, pinned: bool, short: bool) -> Line<'static> {
+    let source = entry.source.to_string();
+    let source = match (short, &entry.source) {
+        (true, PortSource::Scan) => "?".to_string(),
+        (true, _) => source.chars().take(1).collect(),
+        (false, _) => source,
+    };
+    if pinned {
+        Line::from(vec![
+            Span::styled("\u{2605}", Style::default().fg(theme::ACCENT)),
+            Span::raw(if short { source } else { format!(" {source}") }),
+        ])
+    } else {
+        Line::from(source)
+    }
+}
+
+/// Table row for one entry; `member` indents it under its group row.
+fn entry_row<'a>(app: &'a App, entry: &PortEntry, member: bool, columns: &[Column]) -> Row<'a> {
+    let mut local_spans = Vec::new();
+    if member {
+        local_spans.push(Span::raw("  "));
+    }
+    local_spans.push(open_indicator(app, entry.is_open, entry.tunnel));
+    local_spans.push(Span::raw(format!(" {}", entry.local_display())));
+    local_spans.extend(connection_badge(entry.established));
+    if let Some(fwd) = entry.forwarded_port {
+        local_spans.push(Span::styled(
+            format!("\u{2192}:{fwd}"),
+            Style::default().fg(theme::BRAND),
+        ));
+    }
+    let pinned = app.is_pinned(entry);
+    let cells: Vec<Cell> = columns
+        .iter()
+        .map(|column| match column {
+            Column::Connection => connection_cell(entry),
+            Column::Type => Cell::from(source_line(entry, pinned, false)),
+            Column::TypeShort => Cell::from(source_line(entry, pinned, true)),
+            Column::Local => Cell::from(Line::from(local_spans.clone())),
+            Column::Latency => Cell::from(entry.latency_display()),
+            Column::Remote => remote_cell(app, entry),
+            Column::User => Cell::from(entry.user.clone().unwrap_or_default()),
+            Column::Service => Cell::from(app.services.of(entry).unwrap_or_default()),
+            Column::Cpu | Column::Memory => usage_cell(entry, *column),
+            Column::Process => Cell::from(process_line(app, entry)),
+        })
+        .collect();
+    let row = Row::new(cells);
+    // A pinned port or expected service that is down should stand out by
+    // looking absent
+    let expected = app.expected_service(entry.local_port).is_some();
+    if ((pinned || expected) && !entry.is_open) || app.is_hidden(entry) {
+        row.style(theme::muted())
+    } else if app.foreign_owner(entry).is_some() {
+        // Other users' processes can't be killed without escalation
+        row.style(theme::foreign())
+    } else {
+        row
+    }
+}
+
+/// CPU or MEM cell; empty when the usage is unknown.
+fn usage_cell(entry: &PortEntry, column: Column) -> Cell<'static> {
+    let text = entry.usage.map(|usage| match column {
+        Column::Cpu => usage.cpu_display(),
+        _ => usage.memory_display(),
+    });
+    Cell::from(text.unwrap_or_default())
+}
+
+/// CONNECTION cell in the aggregate view.
+fn connection_cell(entry: &PortEntry) -> Cell<'static> {
+    let name = entry.connection.clone().unwrap_or_default();
+    Cell::from(Span::styled(name, theme::muted()))
+}
+
+/// Collapsible row standing for every port of one process or container.
+fn group_row<'a>(
+    app: &'a App,
+    key: &GroupKey,
+    first: &PortEntry,
+    count: usize,
+    expanded: bool,
+    columns: &[Column],
+) -> Row<'a> {
+    let members: Vec<&PortEntry> = app
+        .filtered_entries
+        .iter()
+        .filter(|e| GroupKey::of(e).as_ref() == Some(key))
+        .collect();
+    let any_open = members.iter().any(|e| e.is_open);
+    let established = members.iter().filter_map(|e| e.established).sum();
+    let arrow = if expanded { "\u{25be}" } else { "\u{25b8}" };
+    let ports: Vec<String> = members.iter().map(|e| e.local_display()).collect();
+    let cells: Vec<Cell> = columns
+        .iter()
+        .map(|column| match column {
+            Column::Connection => connection_cell(first),
+            Column::Type => Cell::from(source_line(first, false, false)),
+            Column::TypeShort => Cell::from(source_line(first, false, true)),
+            Column::Local => {
+                let mut spans = vec![
+                    Span::raw(format!("{arrow} ")),
+                    open_indicator(app, any_open, None),
+                    Span::raw(format!(" {count} ports")),
+                ];
+                spans.extend(connection_badge(Some(established)));
+                Cell::from(Line::from(spans))
+            }
+            Column::Latency | Column::Service => Cell::from(""),
+            Column::Remote => Cell::from(Span::styled(ports.join(" "), theme::muted())),
+            Column::User => Cell::from(first.user.clone().unwrap_or_default()),
+            // The ports of a group share the process
+            Column::Cpu | Column::Memory => usage_cell(first, *column),
+            Column::Process => Cell::from(first.process_display()),
+        })
+        .collect();
+    let row = Row::new(cells);
+    if app.foreign_owner(first).is_some() {
+        row.style(theme::foreign())
+    } else {
+        row
+    }
+}
+
+/// Process/container text followed by the registry label and any mismatch warning.
+fn process_line<'a>(app: &'a App, entry: &PortEntry) -> Line<'a> {
+    let process = entry.process_display();
+    let mut spans = Vec::new();
+    if let Some(label) = app.port_label(entry.local_port) {
+        let style = if entry.is_open {
+            Style::default().fg(theme::BRAND)
+        } else {
+            theme::muted()
+        };
+        if !process.is_empty() {
+            spans.push(Span::raw(format!("{process}  ")));
+        }
+        spans.push(Span::styled(label, style));
+    } else {
+        spans.push(Span::raw(process));
+    }
+    if let Some(project) = &entry.project {
+        spans.push(Span::styled(format!("  {project}"), theme::muted()));
+    }
+    if let Some(service) = app.compose_service(entry.local_port) {
+        if entry.is_open {
+            spans.push(Span::styled(
+                format!("  {service}"),
+                Style::default().fg(theme::BRAND),
+            ));
+        } else {
+            spans.push(Span::styled(
+                format!("  \u{26a0} {service} down"),
+                theme::error(),
+            ));
+        }
+    }
+    if let Some(started) = app.starting(entry.local_port).filter(|_| !entry.is_open) {
+        spans.push(Span::styled(
+            format!("  {} starting (PID: {})", started.name, started.pid),
+            Style::default().fg(theme::ACCENT),
+        ));
+    } else if let Some(service) = app.expected_service(entry.local_port) {
+        if entry.is_open {
+            spans.push(Span::styled(
+                format!("  {}", service.name),
+                Style::default().fg(theme::BRAND),
+            ));
+        } else {
+            spans.push(Span::styled(
+                format!("  {}  expected, not running", service.name),
+                theme::muted(),
+            ));
+        }
+    }
+    if let Some(expected) = app.unexpected_process(entry) {
+        spans.push(Span::styled(
+            format!("  \u{26a0} expected {expected}"),
+            theme::error(),
+        ));
+    }
+    if let Some(idle) = app.idle_for(entry, Instant::now()) {
+        spans.push(Span::styled(
+            format!("  idle {}", idle_display(idle)),
+            Style::default().fg(theme::ACCENT),
+        ));
+    }
+    Line::from(spans)
 }
 
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
     // Show status message if present, otherwise show help text
     let content = if let Some((ref message, _)) = app.status_message {
-        Line::from(Span::styled(message, Style::default().fg(theme::ACCENT)))
+        if app.status_error {
+            Line::from(vec![
+                Span::styled(message, theme::error()),
+                Span::styled("  [Esc] dismiss  [m] messages", theme::muted()),
+            ])
+        } else {
+            Line::from(Span::styled(message, Style::default().fg(theme::ACCENT)))
+        }
     } else {
         match app.input_mode {
             InputMode::Search => {
@@ -289,6 +976,12 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                 spans.extend(theme::key_hint("Backspace", "Delete"));
                 Line::from(spans)
             }
+            InputMode::PresetLeader => {
+                let mut spans = Vec::new();
+                spans.extend(theme::key_hint("key", "Launch preset"));
+                spans.extend(theme::key_hint("Esc", "Cancel"));
+                Line::from(spans)
+            }
             InputMode::Normal => {
                 let mut spans = Vec::new();
                 if app.has_multiple_connections() {
@@ -335,49 +1028,194 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn draw_details_popup(frame: &mut Frame, app: &App) {
-    let area = centered_rect(60, 50, frame.area());
-    frame.render_widget(Clear, area);
-
-    let Some(entry) = app.selected_entry() else {
-        return;
-    };
-
-    let is_docker_target = app.docker_target.is_some();
-
-    let (open_text, open_color) = if is_docker_target || entry.is_open {
-        ("Yes", theme::SUCCESS)
+/// Right-hand pane of the split layout, following the selection.
+fn draw_details_pane(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+    // A rule keeps the pane apart from the borderless table
+    let block = if compact {
+        Block::default().borders(Borders::LEFT).title("Details")
     } else {
-        ("No", theme::MUTED)
+        theme::block("Details")
     };
-
-    let (accessible_text, accessible_color) = if entry.is_open {
-        ("Yes", theme::SUCCESS)
-    } else {
-        ("No", theme::ACCENT)
+    let lines = match app.selected_entry() {
+        Some(entry) => {
+            let mut lines = detail_lines(app, entry);
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Enter: raw lsof/ss/docker output",
+                theme::muted(),
+            )));
+            lines
+        }
+        None => vec![Line::from(Span::styled(
+            "No entry selected",
+            theme::muted(),
+        ))],
     };
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
 
-    let label = Style::default().fg(theme::ACCENT);
-    let mut lines = vec![
-        Line::from(vec![
-            Span::styled("Type: ", label),
-            Span::raw(entry.source.to_string()),
-        ]),
-        Line::from(vec![
-            Span::styled("Local Port: ", label),
-            Span::raw(format!("{}", entry.local_port)),
-        ]),
-        Line::from(vec![
-            Span::styled("Open: ", label),
-            Span::styled(open_text, Style::default().fg(open_color)),
-        ]),
-    ];
-    if is_docker_target {
+/// "Raw" section of the Details popup: each command run and its output.
+fn raw_lines(app: &App) -> Vec<Line<'_>> {
+    let mut lines = vec![Line::from(Span::styled("Raw", theme::highlight()))];
+    match &app.details_raw {
+        None => lines.push(Line::from(Span::styled("Collecting...", theme::muted()))),
+        Some(raw) if raw.sections.is_empty() => {
+            lines.push(Line::from(Span::styled(
+                "Nothing collected",
+                theme::muted(),
+            )));
+        }
+        Some(raw) => {
+            for section in &raw.sections {
+                lines.push(Line::from(Span::styled(
+                    format!("$ {}", section.command),
+                    Style::default().fg(theme::BRAND),
+                )));
+                lines.extend(section.lines.iter().map(|l| Line::from(l.as_str())));
+            }
+        }
+    }
+    lines
+}
+
+/// Draw the Details popup; returns how far it can scroll.
+/// "Tunnel:", "Latency:", "Connections:", and "Usage:" lines of the details,
+/// when the scan found them; the tunnel state is colored like the table's dot.
+fn health_lines(entry: &PortEntry, idle: Option<Duration>, label: Style) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if let Some(health) = entry.tunnel {
+        let color = match health {
+            TunnelHealth::Healthy => theme::SUCCESS,
+            TunnelHealth::LocalOnly => theme::ACCENT,
+            TunnelHealth::Down => theme::MUTED,
+        };
         lines.push(Line::from(vec![
-            Span::styled("Accessible: ", label),
-            Span::styled(accessible_text, Style::default().fg(accessible_color)),
+            Span::styled("Tunnel: ", label),
+            Span::styled(health.to_string(), Style::default().fg(color)),
         ]));
-        if let Some(fwd) = entry.forwarded_port {
+    }
+    if let Some(binds) = &entry.reverse_bind {
+        let port = entry.remote_port.unwrap_or_default();
+        let (text, color) = match reverse_bind_state(binds) {
+            ReverseBind::Missing => ("not listening".to_string(), theme::ERROR),
+            ReverseBind::LoopbackOnly => (
+                format!("{} only (GatewayPorts)", bind_list(binds, port)),
+                theme::ACCENT,
+            ),
+            ReverseBind::Public => (bind_list(binds, port), theme::SUCCESS),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Server bind: ", label),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+    if entry.latency.is_some() {
+        lines.push(Line::from(vec![
+            Span::styled("Latency: ", label),
+            Span::raw(entry.latency_display()),
+        ]));
+    }
+    if let Some(count) = entry.established {
+        let mut spans = vec![
+            Span::styled("Connections: ", label),
+            Span::raw(count.to_string()),
+        ];
+        if let Some(idle) = idle {
+            spans.push(Span::styled(
+                format!("  (idle {})", idle_display(idle)),
+                Style::default().fg(theme::ACCENT),
+            ));
+        }
+        if let Some(waiting) = entry.time_wait.filter(|&n| n > 0) {
+            spans.push(Span::styled(
+                format!("  ({waiting} in TIME_WAIT)"),
+                theme::muted(),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    if let Some(health) = &entry.health {
+        let style = if health == "unhealthy" {
+            theme::error()
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Health: ", label),
+            Span::styled(health.clone(), style),
+        ]));
+    }
+    if let Some(usage) = entry.usage {
+        lines.push(Line::from(vec![
+            Span::styled("Usage: ", label),
+            Span::raw(format!(
+                "{} CPU, {} memory",
+                usage.cpu_display(),
+                usage.memory_display()
+            )),
+        ]));
+    }
+    lines
+}
+
+/// `42m`, or `3h05m` from an hour on.
+fn idle_display(idle: Duration) -> String {
+    let minutes = idle.as_secs() / 60;
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Fields of `entry` shown in the Details popup and the split-layout pane.
+fn detail_lines<'a>(app: &'a App, entry: &'a PortEntry) -> Vec<Line<'a>> {
+    let is_docker_target = app.docker_target.is_some();
+
+    // In docker target mode is_open means reachable from here; the port itself
+    // is open when it LISTENs, unless the remote probe found otherwise
+    let listening_open = is_docker_target && entry.remote_open != Some(false);
+    let (open_text, open_color) = if listening_open || entry.is_open {
+        ("Yes", theme::SUCCESS)
+    } else {
+        ("No", theme::MUTED)
+    };
+
+    let (accessible_text, accessible_color) = if entry.is_open {
+        ("Yes", theme::SUCCESS)
+    } else {
+        ("No", theme::ACCENT)
+    };
+
+    let label = Style::default().fg(theme::ACCENT);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Type: ", label),
+            Span::raw(entry.source.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Local Port: ", label),
+            Span::raw(format!("{}", entry.local_port)),
+        ]),
+        Line::from(vec![
+            Span::styled("Open: ", label),
+            Span::styled(open_text, Style::default().fg(open_color)),
+        ]),
+    ];
+    lines.extend(health_lines(
+        entry,
+        app.idle_for(entry, Instant::now()),
+        label,
+    ));
+    if is_docker_target {
+        lines.push(Line::from(vec![
+            Span::styled("Accessible: ", label),
+            Span::styled(accessible_text, Style::default().fg(accessible_color)),
+        ]));
+        if let Some(fwd) = entry.forwarded_port {
             lines.push(Line::from(vec![
                 Span::styled("Forwarded: ", label),
                 Span::styled(
@@ -391,6 +1229,15 @@ fn draw_details_popup(frame: &mut Frame, app: &App) {
         Line::from(vec![
             Span::styled("Remote: ", label),
             Span::raw(entry.remote_display()),
+            Span::styled(
+                entry
+                    .remote_host
+                    .as_deref()
+                    .and_then(|host| app.host_names.name(host))
+                    .map(|name| format!("  ({name})"))
+                    .unwrap_or_default(),
+                theme::muted(),
+            ),
         ]),
         Line::from(vec![
             Span::styled("Process: ", label),
@@ -400,50 +1247,300 @@ fn draw_details_popup(frame: &mut Frame, app: &App) {
             Span::styled("PID: ", label),
             Span::raw(entry.pid.map_or_else(|| "-".to_string(), |p| p.to_string())),
         ]),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("[Esc] ", theme::muted()),
-            Span::raw("Close"),
-        ]),
     ]);
+    if let Some(user) = &entry.user {
+        let mut spans = vec![Span::styled("User: ", label), Span::raw(user.as_str())];
+        if app.foreign_owner(entry).is_some() {
+            spans.push(Span::styled(
+                "  (another user; kill needs sudo)",
+                theme::foreign(),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    if let Some(bind) = &entry.bind {
+        let text = if bind == "*" { "any address" } else { bind };
+        lines.push(Line::from(vec![
+            Span::styled("Bind: ", label),
+            Span::raw(text.to_string()),
+        ]));
+    }
+    if let Some(collector) = entry.collector {
+        lines.push(Line::from(vec![
+            Span::styled("Listed by: ", label),
+            Span::raw(collector.to_string()),
+        ]));
+    }
+    lines.extend(name_lines(app, entry, label));
+    lines.extend(vm_line(app, entry, label));
+    lines
+}
 
-    let paragraph = Paragraph::new(lines).block(theme::popup_block("Details"));
-    frame.render_widget(paragraph, area);
+/// Why a container's IP can't be reached from here, for container ports
+/// behind a Docker VM.
+fn vm_line(app: &App, entry: &PortEntry, label: Style) -> Option<Line<'static>> {
+    let vm = app.docker_vm?;
+    if entry.source != PortSource::Docker && !app.is_docker_target() {
+        return None;
+    }
+    Some(Line::from(vec![
+        Span::styled("Docker VM: ", label),
+        Span::raw(vm.to_string()),
+        Span::styled(
+            "  (published ports reach localhost; container IPs only exist inside the VM)",
+            theme::muted(),
+        ),
+    ]))
 }
 
-fn draw_help_popup(frame: &mut Frame, app: &App) {
-    let area = centered_rect(50, 70, frame.area());
+/// Details lines naming what the port is: its service, project, and registry label.
+fn name_lines<'a>(app: &'a App, entry: &PortEntry, label: Style) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+    if let Some(project) = &entry.project {
+        lines.push(Line::from(vec![
+            Span::styled("Project: ", label),
+            Span::raw(format!("{project}  ")),
+            Span::styled(project.dir.display().to_string(), theme::muted()),
+        ]));
+    }
+    if let Some(service) = app.services.of(entry) {
+        lines.push(Line::from(vec![
+            Span::styled("Service: ", label),
+            Span::raw(service),
+        ]));
+    }
+    if let Some(name) = app.port_label(entry.local_port) {
+        lines.push(Line::from(vec![
+            Span::styled("Label: ", label),
+            Span::styled(name, Style::default().fg(theme::BRAND)),
+        ]));
+    }
+    if let (Some(service), Some(compose)) = (app.compose_service(entry.local_port), &app.compose) {
+        let file = compose.path.file_name().map_or_else(
+            || compose.path.display().to_string(),
+            |f| f.to_string_lossy().to_string(),
+        );
+        let state = if entry.is_open {
+            Span::raw(format!("{service} ({file})"))
+        } else {
+            Span::styled(
+                format!("{service} ({file}) \u{26a0} expected, not running"),
+                theme::error(),
+            )
+        };
+        lines.push(Line::from(vec![Span::styled("Compose: ", label), state]));
+    }
+    if let Some(service) = app.expected_service(entry.local_port) {
+        let state = if entry.is_open {
+            format!("{} ({})", service.name, service.origin)
+        } else {
+            format!("{} ({}), not running", service.name, service.origin)
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Expected: ", label),
+            Span::raw(state),
+        ]));
+    }
+    if let Some(command) = app.startable_service().and_then(|s| s.command) {
+        lines.push(Line::from(vec![
+            Span::styled("Start: ", label),
+            Span::raw(command),
+        ]));
+    }
+    if let Some(expected) = app.unexpected_process(entry) {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "\u{26a0} Registered for {expected}, held by {}",
+                entry.process_name
+            ),
+            theme::error(),
+        )));
+    }
+    lines
+}
+
+fn draw_details_popup(frame: &mut Frame, app: &App) -> usize {
+    let area = centered_rect(80, 80, frame.area());
     frame.render_widget(Clear, area);
 
-    let help_key = |key: &str, desc: &str| -> Line<'static> {
-        Line::from(vec![
-            Span::styled(format!("  {key:<10}"), Style::default().fg(theme::BRAND)),
-            Span::raw(desc.to_string()),
-        ])
+    let Some(entry) = app.selected_entry() else {
+        return 0;
     };
 
-    let mut lines = vec![
-        Line::from(Span::styled("Navigation", theme::highlight())),
-        help_key("j/\u{2193}", "Move down"),
-        help_key("k/\u{2191}", "Move up"),
-        help_key("g/Home", "Go to first"),
-        help_key("G/End", "Go to last"),
-        Line::from(""),
-        Line::from(Span::styled("Filtering", theme::highlight())),
-        help_key("/", "Search mode"),
-        help_key("0", "Show all"),
-        help_key("1", "Local only"),
-        help_key("2", "SSH only"),
-        help_key("3", "Docker only"),
-        Line::from(""),
-        Line::from(Span::styled("Actions", theme::highlight())),
-        help_key("Enter", "Show details"),
-        help_key("K", "Kill process"),
-        help_key("f", "New SSH forward"),
-    ];
+    let mut lines = detail_lines(app, entry);
+    lines.push(Line::from(""));
+    lines.extend(raw_lines(app));
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("Details"), area);
+
+    // Scroll until the last line is at the bottom, not past it
+    let max_scroll = lines.len().saturating_sub(usize::from(chunks[0].height));
+    let scroll = u16::try_from(app.details_scroll.min(max_scroll)).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled("[j/k] Scroll  [Esc] Close", theme::muted())),
+        chunks[1],
+    );
+    max_scroll
+}
+
+/// Draw the Established popup: who is connected to the selected port;
+/// returns how far it can scroll.
+fn draw_established_popup(frame: &mut Frame, app: &App) -> usize {
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let Some(entry) = app.selected_entry() else {
+        return 0;
+    };
+    let now = Instant::now();
+    let mut lines = Vec::new();
+    match app.established.as_ref().map(|list| &list.connections) {
+        None => lines.push(Line::from(Span::styled("Collecting...", theme::muted()))),
+        Some(Err(error)) => lines.push(Line::from(Span::styled(error.as_str(), theme::error()))),
+        Some(Ok(connections)) if connections.is_empty() => {
+            lines.push(Line::from(Span::styled(
+                "No established connections; nothing is using the port right now",
+                theme::muted(),
+            )));
+        }
+        Some(Ok(connections)) => {
+            lines.push(Line::from(Span::styled(
+                format!("{:<42} {:<8} {:<20} {}", "PEER", "PID", "PROCESS", "AGE"),
+                theme::highlight(),
+            )));
+            for connection in connections {
+                let age = app
+                    .established_age(entry.local_port, &connection.peer, now)
+                    .map(|(age, before)| age_display(age, before))
+                    .unwrap_or_default();
+                lines.push(Line::from(format!(
+                    "{:<42} {:<8} {:<20} {age}",
+                    connection.peer,
+                    connection.pid.map(|p| p.to_string()).unwrap_or_default(),
+                    connection.process.as_deref().unwrap_or("-"),
+                )));
+            }
+        }
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    let count = match app.established.as_ref().map(|list| &list.connections) {
+        Some(Ok(connections)) => format!(" ({})", connections.len()),
+        _ => String::new(),
+    };
+    frame.render_widget(
+        theme::popup_block(&format!("Connected to :{}{count}", entry.local_port)),
+        area,
+    );
+
+    let max_scroll = lines.len().saturating_sub(usize::from(chunks[0].height));
+    let scroll = u16::try_from(app.established_scroll.min(max_scroll)).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "AGE+: already connected when first listed  [r] Refresh  [j/k] Scroll  [Esc] Close",
+            theme::muted(),
+        )),
+        chunks[1],
+    );
+    max_scroll
+}
+
+/// Draw the Output popup, scrolled to `output_scroll` (its end at first);
+/// returns how far it can scroll.
+fn draw_output_popup(frame: &mut Frame, app: &App) -> usize {
+    let area = centered_rect(90, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let Some(entry) = app.selected_entry() else {
+        return 0;
+    };
+    let lines: Vec<Line> = match app.output.as_ref().map(|view| &view.lines) {
+        None => vec![Line::from(Span::styled("Reading...", theme::muted()))],
+        Some(Err(error)) => vec![Line::from(Span::styled(error.as_str(), theme::error()))],
+        Some(Ok(lines)) if lines.is_empty() => {
+            vec![Line::from(Span::styled("No output yet", theme::muted()))]
+        }
+        Some(Ok(lines)) => lines.iter().map(|l| Line::from(l.as_str())).collect(),
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    let source = app
+        .output
+        .as_ref()
+        .and_then(|view| view.source.as_deref())
+        .map(|source| format!(" - {source}"))
+        .unwrap_or_default();
+    frame.render_widget(
+        theme::popup_block(&format!(
+            "Output of {} :{}{source}",
+            entry.process_name, entry.local_port
+        )),
+        area,
+    );
+
+    let max_scroll = lines.len().saturating_sub(usize::from(chunks[0].height));
+    let scroll = u16::try_from(app.output_scroll.min(max_scroll)).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "[r] Reload  [j/k] Scroll  [g/G] Top/End  [Esc] Close",
+            theme::muted(),
+        )),
+        chunks[1],
+    );
+    max_scroll
+}
+
+/// How long a connection has been seen, e.g. `42s`, `3m05s`, `1h02m`; a
+/// trailing `+` when it was already there at the first look.
+fn age_display(age: Duration, before: bool) -> String {
+    let secs = age.as_secs();
+    let age = if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    };
+    if before { format!("{age}+") } else { age }
+}
+
+/// Draw the Help popup from the keymap; returns how far it can scroll.
+fn draw_help_popup(frame: &mut Frame, app: &App) -> usize {
+    let area = centered_rect(50, 70, frame.area());
+    frame.render_widget(Clear, area);
 
-    if app.is_remote() || app.is_docker_target() {
-        lines.push(help_key("F", "Quick forward (same port)"));
+    let mut lines = Vec::new();
+    for category in KeyCategory::ALL {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            category.title(),
+            theme::highlight(),
+        )));
+        for binding in KEYMAP.iter().filter(|b| b.category == category) {
+            let keys: Vec<String> = binding.keys.iter().map(|&k| key_label(k)).collect();
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<10}", keys.join("/")),
+                    Style::default().fg(theme::BRAND),
+                ),
+                Span::raw(binding.description),
+            ]));
+        }
     }
 
     if app.is_docker_target() {
@@ -453,31 +1550,32 @@ fn draw_help_popup(frame: &mut Frame, app: &App) {
             theme::highlight(),
         )));
         lines.push(Line::from("  Container ports discovered via ss"));
-        if let Some(ref ip) = app.container_ip {
-            lines.push(Line::from(format!("  Container IP: {ip}")));
+        match (&app.container_ip, app.docker_vm) {
+            (Some(ip), Some(vm)) => {
+                lines.push(Line::from(format!(
+                    "  Container IP: {ip} (inside the {vm} VM)"
+                )));
+            }
+            (Some(ip), None) => lines.push(Line::from(format!("  Container IP: {ip}"))),
+            (None, _) => {}
         }
         lines.push(Line::from("  F tunnels through SSH to container"));
     }
 
-    lines.extend([
-        help_key("p", "Show presets"),
-        help_key("r", "Refresh"),
-        help_key("a", "Toggle auto-refresh"),
-        help_key("q/Esc", "Quit"),
-        Line::from(""),
-        Line::from(Span::styled("Connections", theme::highlight())),
-        help_key("h", "Previous connection"),
-        help_key("l", "Next connection"),
-        help_key("c", "Connection manager"),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("[Esc] ", theme::muted()),
-            Span::raw("Close"),
-        ]),
-    ]);
-
-    let paragraph = Paragraph::new(lines).block(theme::popup_block("Help"));
-    frame.render_widget(paragraph, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("Help"), area);
+
+    let max_scroll = lines.len().saturating_sub(usize::from(chunks[0].height));
+    let scroll = u16::try_from(app.help_scroll.min(max_scroll)).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled("[j/k] Scroll  [Esc] Close", theme::muted())),
+        chunks[1],
+    );
+    max_scroll
 }
 
 #[allow(clippy::too_many_lines)]
@@ -494,6 +1592,7 @@ fn draw_forward_popup(frame: &mut Frame, app: &App) {
             ForwardField::RemoteHost => input.is_remote_host_valid(),
             ForwardField::RemotePort => input.is_remote_port_valid(),
             ForwardField::SshHost => input.is_ssh_host_valid(),
+            ForwardField::BindAll | ForwardField::SshOptions => true,
         }
     };
 
@@ -594,11 +1693,45 @@ fn draw_forward_popup(frame: &mut Frame, app: &App) {
                 cursor(ForwardField::SshHost),
             ]
         }),
-        Line::from(""),
+        Line::from(vec![
+            Span::styled("Bind all:    ", field_style(ForwardField::BindAll)),
+            Span::styled(
+                if input.bind_all { "[x]" } else { "[ ]" },
+                field_style(ForwardField::BindAll),
+            ),
+            Span::styled(" other machines can connect (Space)", theme::muted()),
+        ]),
+        Line::from(if input.bind_all {
+            Span::styled(
+                format!(
+                    "\u{26a0} Anyone who can reach this machine can use :{} to reach {}:{} via {}",
+                    input.local_port, input.remote_host, input.remote_port, input.ssh_host
+                ),
+                theme::error(),
+            )
+        } else {
+            Span::raw("")
+        }),
+        Line::from(Span::styled("Advanced", theme::muted())),
+        Line::from(vec![
+            Span::styled("SSH options: ", field_style(ForwardField::SshOptions)),
+            Span::styled(
+                input.ssh_options.as_str(),
+                field_style(ForwardField::SshOptions),
+            ),
+            cursor(ForwardField::SshOptions),
+            if input.ssh_options.is_empty() && active != ForwardField::SshOptions {
+                Span::styled("e.g. -C -o ServerAliveInterval=30", theme::muted())
+            } else {
+                Span::raw("")
+            },
+        ]),
         footer,
     ];
 
-    let paragraph = Paragraph::new(lines).block(theme::popup_block("New Forward"));
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(theme::popup_block("New Forward"));
     frame.render_widget(paragraph, area);
 }
 
@@ -607,7 +1740,7 @@ fn draw_connections_popup(frame: &mut Frame, app: &App) {
     let area = centered_rect(60, 60, frame.area());
     frame.render_widget(Clear, area);
 
-    if app.connection_popup_mode == ConnectionPopupMode::AddNew {
+    if app.connection_popup_mode != ConnectionPopupMode::List {
         draw_connection_add_form(frame, app, area);
         return;
     }
@@ -629,15 +1762,21 @@ fn draw_connections_popup(frame: &mut Frame, app: &App) {
             Style::default()
         };
 
-        lines.push(Line::from(Span::styled(
-            format!("{prefix}{}{active_marker}", conn.name),
-            style,
-        )));
+        let mut spans = vec![Span::styled(prefix, style)];
+        if let Some(health) = app.health_of(conn) {
+            spans.push(health_dot(health));
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled(format!("{}{active_marker}", conn.name), style));
+        lines.push(Line::from(spans));
 
         let mut details = Vec::new();
         if let Some(ref host) = conn.remote_host {
             details.push(format!("remote: {host}"));
         }
+        if let Some(health) = app.health_of(conn) {
+            details.push(health.label());
+        }
         if let Some(ref target) = conn.docker_target {
             details.push(format!("docker: {target}"));
         }
@@ -651,7 +1790,11 @@ fn draw_connections_popup(frame: &mut Frame, app: &App) {
 
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "[j/k] Navigate  [Enter] Switch  [a] Add  [d] Delete  [Esc] Close",
+        "[j/k] Navigate  [Enter] Switch  [J/K] Move  [Esc] Close",
+        theme::muted(),
+    )));
+    lines.push(Line::from(Span::styled(
+        "[a] Add  [e] Edit  [t] Test  [d] Delete",
         theme::muted(),
     )));
 
@@ -698,8 +1841,13 @@ fn draw_connection_add_form(frame: &mut Frame, app: &App, area: Rect) {
         ))
     };
 
+    let title = if app.connection_popup_mode == ConnectionPopupMode::Edit {
+        "Edit Connection"
+    } else {
+        "New Connection"
+    };
     let lines = vec![
-        Line::from(Span::styled("New Connection", theme::title())),
+        Line::from(Span::styled(title, theme::title())),
         Line::from(""),
         Line::from(vec![
             Span::styled("Name:           ", field_style(ConnectionField::Name)),
@@ -734,7 +1882,7 @@ fn draw_connection_add_form(frame: &mut Frame, app: &App, area: Rect) {
         footer,
     ];
 
-    let paragraph = Paragraph::new(lines).block(theme::popup_block("New Connection"));
+    let paragraph = Paragraph::new(lines).block(theme::popup_block(title));
     frame.render_widget(paragraph, area);
 }
 
@@ -743,11 +1891,24 @@ fn draw_presets_popup(frame: &mut Frame, app: &App) {
     let area = centered_rect(60, 60, frame.area());
     frame.render_widget(Clear, area);
 
+    if app.preset_popup_mode == PresetPopupMode::Form {
+        draw_preset_form(frame, app, area);
+        return;
+    }
+
     if app.presets.is_empty() {
         let lines = vec![
             Line::from(Span::styled("No Presets", theme::highlight())),
             Line::from(""),
-            Line::from("Create presets in:"),
+            Line::from(vec![
+                Span::raw("Press "),
+                Span::styled("a", theme::highlight()),
+                Span::raw(" to add one, or "),
+                Span::styled("P", theme::highlight()),
+                Span::raw(" on a table row to save it as a preset."),
+            ]),
+            Line::from(""),
+            Line::from("Presets are stored in:"),
             Line::from(Span::styled(
                 "~/.config/quay/presets.toml",
                 Style::default().fg(theme::BRAND),
@@ -762,6 +1923,8 @@ fn draw_presets_popup(frame: &mut Frame, app: &App) {
             Line::from(Span::styled("ssh_host = \"myserver\"", theme::muted())),
             Line::from(""),
             Line::from(vec![
+                Span::styled("[a] ", theme::muted()),
+                Span::raw("Add  "),
                 Span::styled("[Esc] ", theme::muted()),
                 Span::raw("Close"),
             ]),
@@ -777,7 +1940,15 @@ fn draw_presets_popup(frame: &mut Frame, app: &App) {
         Line::from(""),
     ];
 
-    for (i, preset) in app.presets.iter().enumerate() {
+    let visible = app.visible_preset_indices();
+    if visible.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No presets for this connection (see `presets` in connections.toml)",
+            theme::muted(),
+        )));
+    }
+    for i in visible {
+        let preset = &app.presets[i];
         let is_selected = i == app.preset_selected;
         let prefix = if is_selected { "> " } else { "  " };
         let style = if is_selected {
@@ -791,10 +1962,17 @@ fn draw_presets_popup(frame: &mut Frame, app: &App) {
             .as_ref()
             .map(|k| format!("[{k}] "))
             .unwrap_or_default();
-        lines.push(Line::from(Span::styled(
+        let mut spans = vec![Span::styled(
             format!("{}{}{}", prefix, key_str, preset.name),
             style,
-        )));
+        )];
+        if let Some(ref group) = preset.group {
+            spans.push(Span::styled(
+                format!("  [{group}]"),
+                Style::default().fg(theme::BRAND),
+            ));
+        }
+        lines.push(Line::from(spans));
         lines.push(Line::from(Span::styled(
             format!(
                 "    {}:{} -> {}:{}",
@@ -805,11 +1983,847 @@ fn draw_presets_popup(frame: &mut Frame, app: &App) {
     }
 
     lines.push(Line::from(""));
-    lines.push(Line::from(Span::styled(
-        "j/k: Navigate  Enter: Launch  Esc: Cancel",
-        theme::muted(),
-    )));
+    match app.selected_preset() {
+        Some(preset) if app.preset_popup_mode == PresetPopupMode::ConfirmDelete => {
+            lines.push(Line::from(vec![
+                Span::raw("Delete "),
+                Span::styled(preset.name.as_str(), theme::highlight()),
+                Span::raw(" from presets.toml?  "),
+                Span::styled("[y] Delete  [n] Cancel", theme::muted()),
+            ]));
+        }
+        _ => lines.push(Line::from(Span::styled(
+            "j/k: Navigate  Enter/[key]: Launch  g: Launch group  a: Add  e: Edit  d: Delete  Esc: Cancel",
+            theme::muted(),
+        ))),
+    }
 
     let paragraph = Paragraph::new(lines).block(theme::popup_block("Presets"));
     frame.render_widget(paragraph, area);
 }
+
+fn draw_snapshots_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(Span::styled("Saved SSH Forwards", theme::title())),
+        Line::from(""),
+    ];
+    if app.snapshots.snapshots.is_empty() {
+        lines.push(Line::from(vec![
+            Span::raw("No snapshots. Press "),
+            Span::styled("s", theme::highlight()),
+            Span::raw(" to save the running forwards."),
+        ]));
+    }
+    for (i, (name, snapshot)) in app.snapshots.snapshots.iter().enumerate() {
+        let is_selected = i == app.snapshot_selected && app.snapshot_name.is_none();
+        let prefix = if is_selected { "> " } else { "  " };
+        let style = if is_selected {
+            theme::highlight()
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![Span::styled(format!("{prefix}{name}"), style)];
+        if let Some(saved_at) = &snapshot.saved_at {
+            spans.push(Span::styled(format!("  {saved_at}"), theme::muted()));
+        }
+        lines.push(Line::from(spans));
+        for forward in &snapshot.forward {
+            let running = forward.is_running(&app.entries);
+            let (mark, mark_style) = if running {
+                ("\u{25cf}", theme::success())
+            } else {
+                ("\u{25cb}", theme::muted())
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("    {mark} "), mark_style),
+                Span::styled(forward.label(), theme::muted()),
+            ]));
+        }
+    }
+
+    lines.push(Line::from(""));
+    if let Some(name) = &app.snapshot_name {
+        lines.push(Line::from(vec![
+            Span::raw("Save running forwards as: "),
+            Span::styled(format!("{name}_"), theme::highlight()),
+        ]));
+        lines.push(Line::from(Span::styled(
+            "Enter: Save  Esc: Cancel",
+            theme::muted(),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "j/k: Navigate  Enter: Restore  s: Save running forwards  d: Delete  Esc: Close",
+            theme::muted(),
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines).block(theme::popup_block("Snapshots"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_export_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let example = app.export_path(Format::Markdown, chrono::Local::now());
+    let option = |key: &'static str, label: &'static str| {
+        Line::from(vec![
+            Span::styled(format!("  [{key}] "), theme::highlight()),
+            Span::raw(label),
+        ])
+    };
+    let lines = vec![
+        Line::from(format!(
+            "Export the {} entries shown to the current directory,",
+            app.filtered_entries.len()
+        )),
+        Line::from(Span::styled(
+            format!("e.g. {}", example.display()),
+            theme::muted(),
+        )),
+        Line::from(""),
+        option("m", "Markdown table"),
+        option("c", "CSV"),
+        option("j", "JSON"),
+        Line::from(""),
+        Line::from(Span::styled("[Esc] Cancel", theme::muted())),
+    ];
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block("Export"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_scan_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let host = app.remote_host.as_deref().unwrap_or("localhost");
+    let mut lines = vec![
+        Line::from(format!(
+            "Try each port on {host} from here and list the open ones."
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("Ports: "),
+            Span::styled(format!("{}_", app.scan_range), theme::highlight()),
+        ]),
+        Line::from(Span::styled("e.g. 8000-9000 or 22,80,443", theme::muted())),
+    ];
+    if !app.scan_entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Empty clears the {} result(s) of the last scan.",
+                app.scan_entries.len()
+            ),
+            theme::muted(),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Enter: Scan  Esc: Cancel",
+        theme::muted(),
+    )));
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block("Scan"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_context_menu(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut lines = Vec::new();
+    for (i, item) in app.context_menu.iter().enumerate() {
+        let is_selected = i == app.context_selected;
+        let prefix = if is_selected { "> " } else { "  " };
+        let style = if is_selected {
+            theme::highlight()
+        } else {
+            Style::default()
+        };
+        let key = menu_key(app, item.action)
+            .map(|k| format!("  [{k}]"))
+            .unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{prefix}{}", item.label), style),
+            Span::styled(key, theme::muted()),
+        ]));
+        if let Some(command) = &item.command {
+            lines.push(Line::from(Span::styled(
+                format!("    $ {command}"),
+                theme::muted(),
+            )));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "j/k: Navigate  Enter: Run  Esc: Cancel",
+        theme::muted(),
+    )));
+
+    let title = app.selected_entry().map_or_else(
+        || "Actions".to_string(),
+        |e| format!("Actions for :{}", e.local_port),
+    );
+    let paragraph = Paragraph::new(lines).block(theme::popup_block(&title));
+    frame.render_widget(paragraph, area);
+}
+
+/// Key that runs `action` from the table, shown next to it in the context menu.
+fn menu_key(app: &App, action: Action) -> Option<String> {
+    if let Action::RunCustom(index) = action {
+        return app.custom_actions.get(index)?.key.map(|c| c.to_string());
+    }
+    KEYMAP
+        .iter()
+        .find(|binding| binding.action == action)
+        .map(|binding| key_label(binding.keys[0]))
+}
+
+fn draw_launch_results_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let failed = app
+        .launch_results
+        .iter()
+        .filter(|r| r.outcome.is_err())
+        .count();
+    let summary = if failed == 0 {
+        Span::styled(
+            format!("All {} forward(s) started", app.launch_results.len()),
+            theme::success(),
+        )
+    } else {
+        Span::styled(
+            format!("{failed} of {} forward(s) failed", app.launch_results.len()),
+            theme::error_bold(),
+        )
+    };
+
+    let mut lines = vec![Line::from(summary), Line::from("")];
+    for result in &app.launch_results {
+        let (mark, style, detail) = match &result.outcome {
+            Ok(detail) => ("\u{2713}", theme::success(), detail),
+            Err(reason) => ("\u{2717}", theme::error(), reason),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{mark} "), style),
+            Span::styled(result.name.as_str(), Style::default().fg(Color::White)),
+            Span::styled(format!("  {detail}"), theme::muted()),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("[Esc] Close", theme::muted())));
+
+    let paragraph = Paragraph::new(lines).block(theme::popup_block(&app.launch_title));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_error_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    // Keep in step with ErrorReport::line_count, which bounds the scroll offset
+    let mut lines = Vec::new();
+    for report in &app.errors {
+        lines.push(Line::from(Span::styled(
+            report.title.as_str(),
+            theme::error_bold(),
+        )));
+        lines.push(Line::from(report.summary.as_str()));
+        if let Some(command) = &report.command {
+            lines.push(Line::from(vec![
+                Span::styled("Command:   ", theme::muted()),
+                Span::raw(command.as_str()),
+            ]));
+            let exit = report
+                .exit_code
+                .map_or_else(|| "killed by signal".to_string(), |c| c.to_string());
+            lines.push(Line::from(vec![
+                Span::styled("Exit code: ", theme::muted()),
+                Span::raw(exit),
+            ]));
+        }
+        lines.push(Line::from(Span::styled("stderr:", theme::muted())));
+        if report.stderr.is_empty() {
+            lines.push(Line::from(Span::styled("  (empty)", theme::muted())));
+        } else {
+            lines.extend(
+                report
+                    .stderr
+                    .lines()
+                    .map(|l| Line::from(Span::styled(format!("  {l}"), theme::error()))),
+            );
+        }
+        lines.push(Line::from(""));
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("Errors"), area);
+
+    let scroll = u16::try_from(app.error_scroll).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "[j/k] Scroll  [r] Retry  [Esc] Close",
+            theme::muted(),
+        )),
+        chunks[1],
+    );
+}
+
+fn draw_messages_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = if app.status_history.is_empty() {
+        vec![Line::from(Span::styled("No messages yet", theme::muted()))]
+    } else {
+        // Newest first
+        app.status_history
+            .iter()
+            .rev()
+            .map(|entry| {
+                let style = if entry.is_error {
+                    theme::error()
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{}  ", entry.at.format("%H:%M:%S")), theme::muted()),
+                    Span::styled(entry.message.as_str(), style),
+                ])
+            })
+            .collect()
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("Messages"), area);
+
+    let scroll = u16::try_from(app.messages_scroll).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled("[j/k] Scroll  [Esc] Close", theme::muted())),
+        chunks[1],
+    );
+}
+
+fn draw_insights_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(80, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let insights = app.insights();
+    let lines: Vec<Line> = if insights.is_empty() {
+        vec![Line::from(Span::styled(
+            "Nothing to report",
+            theme::muted(),
+        ))]
+    } else {
+        insights
+            .iter()
+            .enumerate()
+            .map(|(i, insight)| {
+                let selected = i == app.insight_selected;
+                let (prefix, style) = if selected {
+                    ("> ", theme::highlight())
+                } else {
+                    ("  ", Style::default())
+                };
+                Line::from(vec![
+                    Span::styled(prefix, style),
+                    Span::styled(format!("{:<10}", insight.kind.to_string()), theme::error()),
+                    Span::styled(format!(":{:<6}", insight.port), theme::muted()),
+                    Span::styled(insight.message.clone(), style),
+                ])
+            })
+            .collect()
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("Insights"), area);
+
+    // Keep the selection in view
+    let height = usize::from(chunks[0].height);
+    let scroll = (app.insight_selected + 1).saturating_sub(height);
+    let scroll = u16::try_from(scroll).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "[j/k] Navigate  [Enter] Jump to entry  [Esc] Close",
+            theme::muted(),
+        )),
+        chunks[1],
+    );
+}
+
+fn draw_history_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = if app.history.is_empty() {
+        vec![Line::from(Span::styled(
+            "Nothing killed or forwarded yet",
+            theme::muted(),
+        ))]
+    } else {
+        // Newest first; earlier sessions dimmed
+        app.history
+            .iter()
+            .rev()
+            .map(|record| {
+                let style = if record.is_current_session() {
+                    Style::default()
+                } else {
+                    theme::muted()
+                };
+                let port = record.port.map(|p| format!(":{p}")).unwrap_or_default();
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{}  ", record.time.format("%m-%d %H:%M:%S")),
+                        theme::muted(),
+                    ),
+                    Span::styled(format!("{:<8}{port:<7}", record.action.label()), style),
+                    Span::styled(record.target.as_str(), style),
+                ];
+                if let Some(host) = &record.host {
+                    spans.push(Span::styled(format!(" on {host}"), style));
+                }
+                if let Some(error) = &record.error {
+                    spans.push(Span::styled(format!("  failed: {error}"), theme::error()));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(theme::popup_block("").inner(area));
+    frame.render_widget(theme::popup_block("History"), area);
+
+    let scroll = u16::try_from(app.history_scroll).unwrap_or(u16::MAX);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "[j/k] Scroll  [Esc] Close  (dimmed: earlier sessions)",
+            theme::muted(),
+        )),
+        chunks[1],
+    );
+}
+
+fn draw_auth_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let host = app.auth_host.as_deref().unwrap_or("remote host");
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(host, theme::highlight()),
+            Span::raw(" needs a password, key passphrase, or 2FA code."),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "quay can hand over the terminal so you can log in with ssh.",
+            theme::muted(),
+        )),
+        Line::from(Span::styled(
+            "The session stays open for background scans until quay exits.",
+            theme::muted(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "[Enter] Log in  [Esc] Skip  (later: A)",
+            theme::muted(),
+        )),
+    ];
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block("Authentication Required"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_rerun_popup(frame: &mut Frame, app: &App) {
+    let Some(rerun) = &app.rerun else {
+        return;
+    };
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("Run "),
+            Span::styled(rerun.process.as_str(), theme::highlight()),
+            Span::raw(format!(", killed on :{}, again?", rerun.port)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(rerun.command_line(), theme::title())),
+    ];
+    if let Some(cwd) = &rerun.cwd {
+        lines.push(Line::from(Span::styled(
+            format!("in {}", cwd.display()),
+            theme::muted(),
+        )));
+    }
+    lines.extend([
+        Line::from(""),
+        Line::from(Span::styled(
+            "It runs in the background without its old environment; its output isn't shown.",
+            theme::muted(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled("[y] Run  [n] Cancel", theme::muted())),
+    ]);
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block("Undo Kill"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_start_popup(frame: &mut Frame, app: &App) {
+    let Some(service) = &app.start_prompt else {
+        return;
+    };
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Nothing listens on "),
+            Span::styled(format!(":{}", service.port), theme::highlight()),
+            Span::raw(format!(". Start {}?", service.name)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            service.command.as_deref().unwrap_or_default(),
+            theme::title(),
+        )),
+        Line::from(Span::styled(
+            format!("in {}, from {}", service.dir.display(), service.origin),
+            theme::muted(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "It runs in the background with PORT set; quay waits for the port to open.",
+            theme::muted(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "[Enter] Start  [d] Details  [Esc] Cancel",
+            theme::muted(),
+        )),
+    ];
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block("Start Service"));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_sudo_kill_popup(frame: &mut Frame, app: &App) {
+    let Some(request) = &app.sudo_kill else {
+        return;
+    };
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let location = request.host.as_deref().unwrap_or("this machine");
+    let target = Span::styled(
+        format!("{} (PID {})", request.process, request.pid),
+        theme::highlight(),
+    );
+    // Offered up front for another user's process, or after a refused kill
+    let (summary, confirm, title) = match &request.owner {
+        Some(owner) => (
+            Line::from(vec![
+                target,
+                Span::raw(format!(" on :{} belongs to ", request.port)),
+                Span::styled(owner.as_str(), theme::foreign()),
+                Span::raw("; killing it needs sudo."),
+            ]),
+            "[y] Kill with sudo  [n] Cancel",
+            "Kill as Another User",
+        ),
+        None => (
+            Line::from(vec![
+                Span::raw("Not allowed to kill "),
+                target,
+                Span::raw(format!(" on :{}.", request.port)),
+            ]),
+            "[y] Retry with sudo  [n] Cancel",
+            "Permission Denied",
+        ),
+    };
+    let lines = vec![
+        summary,
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("quay can hand over the terminal to run sudo kill on {location}."),
+            theme::muted(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(confirm, theme::muted())),
+    ];
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(theme::popup_block(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_preset_form(frame: &mut Frame, app: &App, area: Rect) {
+    let input = &app.preset_input;
+    let active = input.active_field;
+
+    let field_style = |field: PresetField| {
+        let valid = input.is_field_valid(field);
+        if field == active {
+            if valid {
+                theme::highlight()
+            } else {
+                theme::error_bold()
+            }
+        } else if valid {
+            Style::default().fg(Color::White)
+        } else {
+            theme::error()
+        }
+    };
+
+    let field_line = |label: &'static str, value: &str, field: PresetField| {
+        let mut spans = vec![
+            Span::styled(label, field_style(field)),
+            Span::styled(value.to_string(), field_style(field)),
+        ];
+        if field == active {
+            spans.push(Span::styled(
+                "_",
+                theme::cursor(input.is_field_valid(field)),
+            ));
+        }
+        Line::from(spans)
+    };
+
+    let footer = if input.is_valid() {
+        Line::from(Span::styled(
+            "Tab/\u{2191}\u{2193}: Switch field  Enter: Save  Esc: Cancel",
+            theme::muted(),
+        ))
+    } else {
+        let fix_text = format!(
+            "Fix: {}  Tab/\u{2191}\u{2193}: Switch  Esc: Cancel",
+            input.invalid_field_names().join(", ")
+        );
+        Line::from(Span::styled(fix_text, theme::error()))
+    };
+
+    let title = if app.preset_editing.is_some() {
+        "Edit Preset"
+    } else {
+        "New Preset"
+    };
+    let lines = vec![
+        Line::from(Span::styled(title, theme::title())),
+        Line::from(""),
+        field_line("Name:        ", &input.name, PresetField::Name),
+        field_line("Key:         ", &input.key, PresetField::Key),
+        field_line("Group:       ", &input.group, PresetField::Group),
+        field_line("Local Port:  ", &input.local_port, PresetField::LocalPort),
+        field_line("Remote Host: ", &input.remote_host, PresetField::RemoteHost),
+        field_line("Remote Port: ", &input.remote_port, PresetField::RemotePort),
+        field_line("SSH Host:    ", &input.ssh_host, PresetField::SshHost),
+        Line::from(""),
+        Line::from(Span::styled(
+            "(Key: optional single-character shortcut; Group: optional)",
+            theme::muted(),
+        )),
+        Line::from(""),
+        footer,
+    ];
+
+    let paragraph = Paragraph::new(lines).block(theme::popup_block(title));
+    frame.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::row;
+
+    #[test]
+    fn test_columns_drop_by_width() {
+        use Column::{
+            Connection, Cpu, Latency, Local, Memory, Process, Remote, Service, Type, TypeShort,
+            User,
+        };
+        assert_eq!(
+            columns(120, true, false, false),
+            vec![Connection, Type, Local, Remote, User, Service, Process]
+        );
+        assert_eq!(
+            columns(87, false, false, false),
+            vec![Type, Local, Remote, User, Service, Process]
+        );
+        assert_eq!(
+            columns(86, false, false, false),
+            vec![Type, Local, Remote, User, Process]
+        );
+        assert_eq!(
+            columns(71, false, false, false),
+            vec![Type, Local, Remote, Process]
+        );
+        assert_eq!(
+            columns(70, true, false, false),
+            vec![Connection, Type, Local, Process]
+        );
+        assert_eq!(columns(50, true, false, false), vec![Type, Local, Process]);
+        assert_eq!(
+            columns(30, false, false, false),
+            vec![TypeShort, Local, Process]
+        );
+        assert_eq!(
+            columns(96, false, true, false),
+            vec![Type, Local, Latency, Remote, User, Service, Process]
+        );
+        assert_eq!(
+            columns(95, false, true, false),
+            vec![Type, Local, Remote, User, Service, Process]
+        );
+        assert_eq!(
+            columns(102, false, false, true),
+            vec![Type, Local, Remote, User, Service, Cpu, Memory, Process]
+        );
+        assert_eq!(
+            columns(101, false, false, true),
+            vec![Type, Local, Remote, User, Cpu, Memory, Process]
+        );
+        assert_eq!(
+            columns(85, false, false, true),
+            vec![Type, Local, Remote, User, Cpu, Process]
+        );
+        assert_eq!(
+            columns(79, false, false, true),
+            vec![Type, Local, Remote, User, Process]
+        );
+    }
+
+    #[test]
+    fn test_idle_display() {
+        assert_eq!(idle_display(Duration::from_secs(42 * 60 + 59)), "42m");
+        assert_eq!(
+            idle_display(Duration::from_secs(3 * 3600 + 5 * 60)),
+            "3h05m"
+        );
+    }
+
+    #[test]
+    fn test_age_display() {
+        assert_eq!(age_display(Duration::from_secs(42), false), "42s");
+        assert_eq!(age_display(Duration::from_secs(185), true), "3m05s+");
+        assert_eq!(age_display(Duration::from_secs(3720), false), "1h02m");
+    }
+
+    #[test]
+    fn test_reverse_bind_state() {
+        let binds =
+            |addrs: &[&str]| -> Vec<String> { addrs.iter().map(ToString::to_string).collect() };
+        assert!(matches!(reverse_bind_state(&[]), ReverseBind::Missing));
+        let loopback = binds(&["127.0.0.1", "[::1]"]);
+        assert!(matches!(
+            reverse_bind_state(&loopback),
+            ReverseBind::LoopbackOnly
+        ));
+        assert_eq!(bind_list(&loopback, 9090), "127.0.0.1:9090, [::1]:9090");
+        assert!(matches!(
+            reverse_bind_state(&binds(&["127.0.0.1", "*"])),
+            ReverseBind::Public
+        ));
+    }
+
+    #[test]
+    fn test_source_badges() {
+        let tunnel = row(PortSource::Ssh, 15432);
+        let sources = [
+            (PortSource::Local, SourceStatus::Ok),
+            (
+                PortSource::Docker,
+                SourceStatus::Failed("daemon down".to_string()),
+            ),
+            (PortSource::Ssh, SourceStatus::Ok),
+        ];
+        let text = |sources: &[(PortSource, SourceStatus)], vm| -> String {
+            source_badges(sources, std::slice::from_ref(&tunnel), vm)
+                .iter()
+                .map(|span| span.content.to_string())
+                .collect()
+        };
+        assert_eq!(
+            text(&sources, None),
+            "  LOCAL \u{2713}  DOCKER \u{2717} daemon down  SSH \u{2713} 1 tunnel"
+        );
+        let docker_ok = [(PortSource::Docker, SourceStatus::Ok)];
+        assert_eq!(
+            text(&docker_ok, Some(VmBackend::Colima)),
+            "  DOCKER \u{2713} Colima"
+        );
+    }
+
+    #[test]
+    fn test_stats_line() {
+        let mut stats = SessionStats {
+            per_source: vec![(PortSource::Local, 5), (PortSource::Ssh, 2)],
+            open: 6,
+            closed: 1,
+            forwards_started: 2,
+        };
+        let text = |stats: &SessionStats| stats_line(stats).to_string();
+        assert_eq!(
+            text(&stats),
+            " LOCAL 5  SSH 2 \u{b7} 6 open, 1 closed \u{b7} 2 forwards started "
+        );
+        stats.per_source.clear();
+        stats.forwards_started = 0;
+        assert_eq!(text(&stats), " 6 open, 1 closed ");
+    }
+
+    #[test]
+    fn test_refresh_badge() {
+        let text = |total, slowest| refresh_badge(total, slowest).map(|s| s.content.to_string());
+        assert_eq!(text(None, None), None);
+        assert_eq!(
+            text(
+                Some(Duration::from_millis(3200)),
+                Some(("ssh", Duration::from_millis(2900)))
+            )
+            .as_deref(),
+            Some("  last refresh: 3.2s (ssh: 2.9s)")
+        );
+        let badge = refresh_badge(
+            Some(Duration::from_millis(400)),
+            Some(("local", Duration::from_millis(300))),
+        )
+        .unwrap();
+        assert_eq!(badge.style, theme::muted());
+        let slow = refresh_badge(Some(SLOW_PHASE), Some(("docker", SLOW_PHASE))).unwrap();
+        assert_eq!(slow.style, theme::error());
+    }
+}
@@ -0,0 +1,252 @@
+//! Snapshot tests of the whole screen.
+//!
+//! Each test renders [`ui::draw`] into a headless `TestBackend` with the
+//! `--mock` entries and compares the buffer with `src/snapshots/`. After an
+//! intended UI change, review and accept the new screens with
+//! `cargo insta review` (or `INSTA_UPDATE=always cargo test`).
+
+use crate::app::{App, ErrorReport, Filter, InputMode, LaunchResult, Popup, SudoKill};
+use crate::dev::mock::generate_mock_entries;
+use crate::history::{Kind, Record};
+use crate::ui;
+use chrono::TimeZone;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+const WIDTH: u16 = 120;
+const HEIGHT: u16 = 30;
+
+/// An app showing the mock entries, as `quay --mock` starts.
+fn mock_app() -> App {
+    let mut app = App::new();
+    app.mock_mode = true;
+    app.set_entries(generate_mock_entries());
+    app
+}
+
+/// The screen `app` draws, one quoted line per row.
+fn render(app: &App) -> TestBackend {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal
+        .draw(|frame| {
+            ui::draw(frame, app);
+        })
+        .unwrap();
+    terminal.backend().clone()
+}
+
+macro_rules! assert_screen {
+    ($name:expr, $app:expr) => {
+        insta::with_settings!({
+            // The Export popup's example file name carries the current time
+            filters => vec![(r"\d{8}-\d{6}", "[timestamp]")],
+        }, {
+            insta::assert_snapshot!($name, render(&$app));
+        });
+    };
+}
+
+#[test]
+fn test_main_screen() {
+    assert_screen!("main", mock_app());
+}
+
+#[test]
+fn test_filters() {
+    for (name, filter) in [
+        ("filter_local", Filter::Local),
+        ("filter_ssh", Filter::Ssh),
+        ("filter_docker", Filter::Docker),
+    ] {
+        let mut app = mock_app();
+        app.set_filter(filter);
+        assert_screen!(name, app);
+    }
+}
+
+#[test]
+fn test_modes() {
+    let mut app = mock_app();
+    app.input_mode = InputMode::Search;
+    app.search_query = "post".to_string();
+    app.apply_filter();
+    assert_screen!("search", app);
+
+    let mut app = mock_app();
+    app.compact = true;
+    assert_screen!("compact", app);
+
+    let mut app = mock_app();
+    app.set_status("Refreshing...");
+    app.loading = true;
+    assert_screen!("loading", app);
+
+    let mut app = mock_app();
+    app.cycle_sort();
+    assert_screen!("sort_cpu", app);
+
+    let mut app = mock_app();
+    app.toggle_activity();
+    assert_screen!("activity", app);
+}
+
+#[test]
+fn test_popups() {
+    for (name, popup) in [
+        ("popup_help", Popup::Help),
+        ("popup_forward", Popup::Forward),
+        ("popup_presets", Popup::Presets),
+        ("popup_connections", Popup::Connections),
+        ("popup_messages", Popup::Messages),
+        ("popup_snapshots", Popup::Snapshots),
+        ("popup_export", Popup::Export),
+        ("popup_scan", Popup::Scan),
+    ] {
+        let mut app = mock_app();
+        app.popup = popup;
+        assert_screen!(name, app);
+    }
+
+    let mut app = mock_app();
+    app.open_details();
+    assert_screen!("popup_details", app);
+
+    let mut app = mock_app();
+    app.open_established();
+    let entry = app.selected_entry().cloned().unwrap();
+    let list = crate::app::EstablishedList {
+        port: entry.local_port,
+        connections: Ok(crate::dev::mock::mock_established(&entry)),
+    };
+    app.apply_established(list, std::time::Instant::now());
+    assert_screen!("popup_established", app);
+
+    let mut app = mock_app();
+    app.open_context_menu();
+    assert_screen!("popup_context_menu", app);
+
+    let mut app = mock_app();
+    app.errors = vec![ErrorReport::new(
+        "Refresh failed",
+        &anyhow::anyhow!("ssh: connect to host devbox port 22: Connection refused"),
+    )];
+    app.popup = Popup::Error;
+    assert_screen!("popup_error", app);
+
+    let mut app = mock_app();
+    app.auth_host = Some("devbox".to_string());
+    app.popup = Popup::Auth;
+    assert_screen!("popup_auth", app);
+
+    let mut app = mock_app();
+    app.presets = vec![crate::preset::Preset {
+        name: "Production DB".to_string(),
+        key: Some("p".to_string()),
+        local_port: 15432,
+        remote_host: "localhost".to_string(),
+        remote_port: 5432,
+        ssh_host: "prod".to_string(),
+        group: None,
+        ssh_options: Vec::new(),
+    }];
+    app.preset_popup_mode = crate::app::PresetPopupMode::ConfirmDelete;
+    app.popup = Popup::Presets;
+    assert_screen!("popup_preset_delete", app);
+
+    let mut app = mock_app();
+    app.sudo_kill = Some(SudoKill {
+        port: 80,
+        pid: 1,
+        process: "nginx".to_string(),
+        host: None,
+        owner: Some("root".to_string()),
+    });
+    app.popup = Popup::SudoKill;
+    assert_screen!("popup_sudo_kill", app);
+
+    let mut app = mock_app();
+    app.rerun = Some(crate::undo::Rerun {
+        port: 3000,
+        process: "node".to_string(),
+        command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+        cwd: Some("/home/me/storefront".into()),
+    });
+    app.popup = Popup::Rerun;
+    assert_screen!("popup_rerun", app);
+
+    let mut app = mock_app();
+    app.start_prompt = Some(crate::expected::ExpectedService {
+        name: "storefront".to_string(),
+        port: 3000,
+        command: Some("npm run dev".to_string()),
+        dir: "/home/me/storefront".into(),
+        origin: crate::expected::PROCFILE,
+    });
+    app.popup = Popup::StartService;
+    assert_screen!("popup_start_service", app);
+
+    let mut app = mock_app();
+    app.launch_results = vec![
+        LaunchResult {
+            name: "db".to_string(),
+            outcome: Ok("PID 4242".to_string()),
+        },
+        LaunchResult {
+            name: "cache".to_string(),
+            outcome: Err("port 6379 is already in use".to_string()),
+        },
+    ];
+    app.popup = Popup::LaunchResults;
+    assert_screen!("popup_launch_results", app);
+}
+
+#[test]
+fn test_log_popups() {
+    let mut app = mock_app();
+    let at = |h, m, s| {
+        chrono::Local
+            .with_ymd_and_hms(2026, 10, 16, h, m, s)
+            .unwrap()
+    };
+    let mut earlier = Record::new(Kind::Kill, Some(3000), "node (PID 4242)", None);
+    earlier.time = at(9, 12, 5);
+    earlier.session = 1;
+    let mut forward = Record::new(
+        Kind::Forward,
+        Some(5432),
+        "-L 5432:localhost:5432 via devbox",
+        Some("bind [127.0.0.1]:5432: Address already in use".to_string()),
+    );
+    forward.time = at(14, 2, 11);
+    let mut stop = Record::new(Kind::StopContainer, Some(6379), "container redis", None)
+        .on_host(Some("devbox"));
+    stop.time = at(14, 3, 40);
+    app.history = vec![earlier, forward, stop];
+    app.popup = Popup::History;
+    assert_screen!("popup_history", app);
+
+    let mut app = mock_app();
+    app.env_ports.ports = vec![crate::env_ports::EnvPort {
+        name: "PORT".to_string(),
+        port: 4000,
+        origin: ".env".to_string(),
+    }];
+    let db = app
+        .entries
+        .iter()
+        .position(|e| e.local_port == 5432)
+        .unwrap();
+    app.entries[db].health = Some("unhealthy".to_string());
+    app.popup = Popup::Insights;
+    assert_screen!("popup_insights", app);
+
+    let mut app = mock_app();
+    app.popup = Popup::Output;
+    let entry = app.selected_entry().unwrap().clone();
+    app.apply_output(crate::app::OutputView {
+        port: entry.local_port,
+        source: Some("/tmp/node.log".to_string()),
+        lines: Ok(crate::dev::mock::mock_output(&entry)),
+    });
+    assert_screen!("popup_output", app);
+}
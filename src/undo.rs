@@ -0,0 +1,255 @@
+//! Taking back the last kill or forward with `u`.
+//!
+//! Killing an SSH tunnel remembers the forward it served, and killing another
+//! process on this machine remembers its command line and working directory,
+//! read just before the signal. Starting a forward remembers its ssh PID. `u`
+//! takes back the newest action of the last [`UNDO_WINDOW`]: a forward is
+//! started again or stopped, and a command line is shown for confirmation
+//! before it runs again. The environment of a killed process isn't kept.
+//!
+//! [`restart`] uses the same capture and relaunch for `B`: kill a process,
+//! wait for its port to close, and run its command line again.
+
+use crate::history;
+use crate::port::ssh::Started;
+use crate::port::{self, PortEntry};
+use crate::snapshot::SnapshotForward;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// How long an action can be undone.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(60);
+
+/// Actions kept at most; older ones are forgotten.
+const MAX_HISTORY: usize = 20;
+
+/// How long a restart waits for the killed process to release its port.
+const RESTART_WAIT: Duration = Duration::from_secs(10);
+
+/// How often a restart checks whether the port closed.
+const RESTART_POLL: Duration = Duration::from_millis(200);
+
+/// A killed process that can be started again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rerun {
+    pub port: u16,
+    pub process: String,
+    /// Program and arguments.
+    pub command: Vec<String>,
+    pub cwd: Option<PathBuf>,
+}
+
+impl Rerun {
+    /// The command as it would be typed in a shell.
+    pub fn command_line(&self) -> String {
+        self.command
+            .iter()
+            .map(|arg| shell_escape::escape(arg.as_str().into()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// What `u` does to take an action back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoAction {
+    /// Start a killed forward again.
+    StartForward { port: u16, forward: SnapshotForward },
+    /// Stop a forward that was started.
+    StopForward { port: u16, started: Started },
+    /// Run a killed process's command line again, once confirmed.
+    Rerun(Rerun),
+}
+
+impl UndoAction {
+    pub fn port(&self) -> u16 {
+        match self {
+            UndoAction::StartForward { port, .. } | UndoAction::StopForward { port, .. } => *port,
+            UndoAction::Rerun(rerun) => rerun.port,
+        }
+    }
+}
+
+/// Recent actions that can be undone, newest last.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    actions: Vec<(UndoAction, Instant)>,
+}
+
+impl History {
+    pub fn push(&mut self, action: UndoAction, now: Instant) {
+        self.actions.push((action, now));
+        if self.actions.len() > MAX_HISTORY {
+            self.actions.remove(0);
+        }
+    }
+
+    /// The newest action still within [`UNDO_WINDOW`]; expired ones are dropped.
+    pub fn pop(&mut self, now: Instant) -> Option<UndoAction> {
+        self.actions
+            .retain(|(_, at)| now.saturating_duration_since(*at) <= UNDO_WINDOW);
+        self.actions.pop().map(|(action, _)| action)
+    }
+
+    /// Drop the newest action on `port`, when what it would undo failed.
+    pub fn forget(&mut self, port: u16) {
+        if let Some(index) = self.actions.iter().rposition(|(a, _)| a.port() == port) {
+            self.actions.remove(index);
+        }
+    }
+}
+
+/// Command line and working directory of `pid` on this machine.
+pub fn capture(pid: u32) -> Option<(Vec<String>, Option<PathBuf>)> {
+    // /proc keeps arguments with spaces apart; ps joins them
+    let command = if let Ok(cmdline) = std::fs::read(format!("/proc/{pid}/cmdline")) {
+        parse_cmdline(&cmdline)
+    } else {
+        let pid = pid.to_string();
+        let output = std::process::Command::new("ps")
+            .args(["-ww", "-o", "args=", "-p", &pid])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    };
+    if command.is_empty() {
+        return None;
+    }
+    let cwd = std::fs::read_link(format!("/proc/{pid}/cwd"))
+        .ok()
+        .or_else(|| {
+            let pid = pid.to_string();
+            let output = std::process::Command::new("lsof")
+                .args(["-a", "-p", &pid, "-d", "cwd", "-Fn"])
+                .output()
+                .ok()?;
+            parse_lsof_cwd(&String::from_utf8_lossy(&output.stdout))
+        });
+    Some((command, cwd))
+}
+
+/// Arguments in `/proc/<pid>/cmdline`, NUL-separated.
+fn parse_cmdline(cmdline: &[u8]) -> Vec<String> {
+    cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Directory in `lsof -d cwd -Fn` output.
+fn parse_lsof_cwd(output: &str) -> Option<PathBuf> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(PathBuf::from)
+}
+
+/// Start `command` in `cwd`, detached from the terminal; returns its PID.
+pub fn rerun(command: &[String], cwd: Option<&Path>) -> Result<u32> {
+    let (program, args) = command.split_first().context("Empty command line")?;
+    let mut process = std::process::Command::new(program);
+    process
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    // Its own process group, so it outlives the TUI and ignores its Ctrl-C
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+    let child = process
+        .spawn()
+        .with_context(|| format!("Failed to run {program}"))?;
+    Ok(child.id())
+}
+
+/// Kill `entry`, a process on this machine, wait until nothing listens on
+/// its port, and run `rerun` again; returns the new PID.
+pub async fn restart(entry: &PortEntry, rerun: &Rerun) -> Result<u32> {
+    let port = entry.local_port;
+    let killed = port::kill_entry(entry, port::Signal::Term, None).await;
+    history::record_kill(entry, None, &killed);
+    killed?;
+    let deadline = Instant::now() + RESTART_WAIT;
+    while port::open_ports(&[port], None, None).await?.contains(&port) {
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                ":{port} still in use after {}s; not started again",
+                RESTART_WAIT.as_secs()
+            );
+        }
+        tokio::time::sleep(RESTART_POLL).await;
+    }
+    let started = self::rerun(&rerun.command, rerun.cwd.as_deref());
+    let record = history::Record::new(
+        history::Kind::Rerun,
+        Some(port),
+        &rerun.command_line(),
+        history::failure(&started),
+    );
+    history::append(&record);
+    started
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(port: u16) -> UndoAction {
+        UndoAction::StopForward {
+            port,
+            started: Started::Process(4242),
+        }
+    }
+
+    #[test]
+    fn test_history_pops_newest_within_window() {
+        let start = Instant::now();
+        let mut history = History::default();
+        history.push(stop(3000), start);
+        history.push(stop(8080), start + Duration::from_secs(50));
+        history.push(stop(9090), start + Duration::from_secs(55));
+        history.forget(9090);
+
+        let now = start + Duration::from_secs(70);
+        assert_eq!(history.pop(now), Some(stop(8080)));
+        // :3000 expired
+        assert_eq!(history.pop(now), None);
+
+        for port in 0..30 {
+            history.push(stop(port), now);
+        }
+        assert_eq!(history.actions.len(), MAX_HISTORY);
+        assert_eq!(history.pop(now), Some(stop(29)));
+    }
+
+    #[test]
+    fn test_parse_captured_command() {
+        assert_eq!(
+            parse_cmdline(b"node\0server.js\0--title\0my app\0"),
+            ["node", "server.js", "--title", "my app"]
+        );
+        assert!(parse_cmdline(b"").is_empty());
+        assert_eq!(
+            parse_lsof_cwd("p4242\nfcwd\nn/home/me/app\n"),
+            Some(PathBuf::from("/home/me/app"))
+        );
+        assert_eq!(parse_lsof_cwd(""), None);
+
+        let rerun = Rerun {
+            port: 3000,
+            process: "node".to_string(),
+            command: parse_cmdline(b"node\0server.js\0--title\0my app\0"),
+            cwd: None,
+        };
+        assert_eq!(rerun.command_line(), "node server.js --title 'my app'");
+    }
+}
@@ -0,0 +1,957 @@
+//! Actions as state updates.
+//!
+//! [`App::update`] applies an [`Action`] to the app state and returns the
+//! side effects it needs as [`Command`]s; the main loop executes them
+//! (collections on the worker, kills, tmux, saving config files, anything
+//! that needs the runtime or the terminal). Keeping the state change free of
+//! I/O means an action can be tested, replayed, or undone without a terminal.
+//!
+//! While a popup is open, the action comes from that popup's key bindings
+//! and is applied to the popup instead.
+
+use crate::app::{
+    App, ConnectionInput, ConnectionPopupMode, Filter, ForwardInput, InputMode, Popup, PresetInput,
+    PresetPopupMode, SudoKill,
+};
+use crate::event::Action;
+use crate::forward::resolve_docker_forward;
+use crate::output::Format;
+use crate::undo::UndoAction;
+use std::time::Instant;
+
+/// A side effect an action asks the main loop to carry out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Collect the current view again.
+    Refresh,
+    /// Re-probe the selected entry only.
+    Recheck,
+    /// Kill the process (or container process) of the selected entry.
+    Kill,
+    /// Collect the raw output shown in the Details popup.
+    LoadDetails,
+    /// List the connections shown in the Established popup.
+    LoadEstablished,
+    /// Read the recent output shown in the Output popup.
+    LoadOutput,
+    /// Forward the selected remote port to the same local port.
+    QuickForward,
+    /// Switch to the previous (`-1`) or next (`1`) connection.
+    SwitchConnection(i32),
+    /// Log in to the host interactively on the released terminal.
+    Authenticate(String),
+    /// Save the pin toggled on the selected port.
+    SavePins,
+    CopyAddress,
+    OpenBrowser,
+    ContainerLogs,
+    RestartContainer,
+    /// Kill the selected local process and run its command line again.
+    RestartProcess,
+    /// Run the start command of the selected expected service.
+    StartService,
+    CloseIdleTunnels,
+    TmuxShell,
+    TmuxForward,
+    /// Run `[[actions]]` entry `index` on the selected entry.
+    RunCustom(usize),
+    /// Take back a kill or a forward (a rerun is confirmed in its popup first).
+    Undo(UndoAction),
+    /// Read history.jsonl for the History popup.
+    LoadHistory,
+    /// Load the active connection: restore its forwards, then collect.
+    Activate,
+    /// Create the forward typed in the Forward popup.
+    CreateForward,
+    /// Start the forward of preset `index`.
+    LaunchPreset(usize),
+    /// Start the forwards of every preset in a group.
+    LaunchGroup(String),
+    /// Save the preset form to presets.toml.
+    SavePreset,
+    /// Remove the selected preset from presets.toml.
+    DeletePreset,
+    /// Save the running forwards under the name typed in the Snapshots popup.
+    SaveSnapshot,
+    RestoreSnapshot,
+    DeleteSnapshot,
+    /// Open the form for the selected connection, unless a group generates it.
+    EditConnection,
+    /// Save the connection form to connections.toml, as a new connection or
+    /// over the one being edited.
+    SaveConnection,
+    /// Move the selected connection up (`true`) or down.
+    MoveConnection(bool),
+    DeleteConnection,
+    /// Probe a host and report how it went.
+    TestConnection(String),
+    /// Kill again through sudo on the released terminal.
+    SudoKill(SudoKill),
+    /// Write the table to a file.
+    Export(Format),
+    /// Probe the range typed in the Scan popup.
+    Scan,
+}
+
+impl App {
+    /// Apply `action` to the open popup, or else to the table; returns what
+    /// is left to do outside the app state.
+    #[allow(clippy::too_many_lines)]
+    pub fn update(&mut self, action: Action) -> Vec<Command> {
+        if self.popup != Popup::None {
+            return self.update_popup(action);
+        }
+        if self.input_mode == InputMode::PresetLeader {
+            // The key after the leader launches that preset; any other cancels
+            self.input_mode = InputMode::Normal;
+            return match action {
+                Action::PresetHotkey(key) => self.launch_hotkey(key),
+                _ => Vec::new(),
+            };
+        }
+        if changes_entries(action) && self.read_only() {
+            return Vec::new();
+        }
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Up => self.previous(),
+            Action::Down => self.next(),
+            Action::First => self.first(),
+            Action::Last => self.last(),
+            Action::EnterSearch => self.input_mode = InputMode::Search,
+            Action::ExitSearch => self.input_mode = InputMode::Normal,
+            Action::UpdateSearch => self.apply_filter(),
+            Action::FilterAll => self.set_filter(Filter::All),
+            Action::FilterLocal => self.set_filter(Filter::Local),
+            Action::FilterSsh => self.set_filter(Filter::Ssh),
+            Action::FilterDocker => self.set_filter(Filter::Docker),
+            Action::Refresh if !self.mock_mode || self.mock_churn => {
+                self.loading = true;
+                self.set_status("Refreshing...");
+                return vec![Command::Refresh];
+            }
+            Action::ToggleAutoRefresh if !self.mock_mode || self.mock_churn => {
+                self.auto_refresh = !self.auto_refresh;
+                if self.auto_refresh {
+                    self.set_status("Auto-refresh ON");
+                } else {
+                    self.set_status("Auto-refresh OFF");
+                }
+            }
+            Action::RecheckEntry if !self.mock_mode => return vec![Command::Recheck],
+            Action::Kill => return vec![Command::Kill],
+            Action::Select if self.is_group_selected() => {
+                self.set_group_expanded(None);
+            }
+            Action::Select if self.startable_service().is_some() && !self.read_only() => {
+                self.start_prompt = self.startable_service();
+                self.popup = Popup::StartService;
+            }
+            Action::Select | Action::ShowDetails => {
+                self.open_details();
+                return vec![Command::LoadDetails];
+            }
+            Action::Expand => {
+                self.set_group_expanded(Some(true));
+            }
+            Action::Collapse => {
+                self.set_group_expanded(Some(false));
+            }
+            Action::ShowHelp => {
+                self.help_scroll = 0;
+                self.popup = Popup::Help;
+            }
+            Action::StartForward => self.start_forward(),
+            Action::ShowPresets => {
+                self.preset_first();
+                self.preset_popup_mode = PresetPopupMode::List;
+                self.popup = Popup::Presets;
+            }
+            Action::PresetLeader => {
+                if self.presets.iter().any(|p| p.hotkey().is_some()) {
+                    self.input_mode = InputMode::PresetLeader;
+                } else {
+                    self.set_status("No preset hotkeys defined");
+                }
+            }
+            Action::SaveAsPreset => {
+                if let Some(entry) = self.selected_entry() {
+                    self.preset_input = PresetInput::from_entry(entry, self.remote_host.as_deref());
+                    self.preset_editing = None;
+                    self.preset_popup_mode = PresetPopupMode::Form;
+                    self.popup = Popup::Presets;
+                }
+            }
+            Action::ClosePopup => self.popup = Popup::None,
+            Action::QuickForward => return vec![Command::QuickForward],
+            Action::PrevConnection => return vec![Command::SwitchConnection(-1)],
+            Action::NextConnection => return vec![Command::SwitchConnection(1)],
+            Action::Authenticate => match self.remote_host.clone() {
+                Some(host) if self.mock_mode => {
+                    self.set_status(&format!("[mock] Would log in to {host}"));
+                }
+                Some(host) => return vec![Command::Authenticate(host)],
+                None => self.set_status("Authentication needs a remote connection"),
+            },
+            Action::ToggleLogs => self.show_logs = !self.show_logs,
+            Action::ToggleCompact => self.compact = !self.compact,
+            Action::CycleSort => self.cycle_sort(),
+            Action::ToggleActivity => self.toggle_activity(),
+            Action::ShowMessages => {
+                self.dismiss_error();
+                self.messages_scroll = 0;
+                self.popup = Popup::Messages;
+            }
+            Action::ShowInsights => {
+                self.dismiss_error();
+                self.insight_selected = 0;
+                self.popup = Popup::Insights;
+            }
+            Action::ShowHistory => {
+                self.dismiss_error();
+                self.history_scroll = 0;
+                self.popup = Popup::History;
+                return vec![Command::LoadHistory];
+            }
+            Action::TogglePin => return self.toggle_pin_action(),
+            Action::Hide => {
+                if let Some(message) = self.toggle_hidden() {
+                    self.set_status(&message);
+                }
+            }
+            Action::ToggleShowHidden => {
+                self.show_hidden = !self.show_hidden;
+                self.apply_filter();
+                if self.show_hidden {
+                    self.set_status("Showing hidden ports");
+                } else {
+                    self.set_status("Hiding ignored ports");
+                }
+            }
+            Action::ShowErrors => {
+                if self.errors.is_empty() {
+                    self.set_status("No errors from the last scan");
+                } else {
+                    self.error_scroll = 0;
+                    self.popup = Popup::Error;
+                }
+            }
+            Action::ShowConnections => {
+                self.connection_selected = self.active_connection;
+                self.connection_popup_mode = ConnectionPopupMode::List;
+                self.popup = Popup::Connections;
+            }
+            Action::ShowContextMenu => {
+                self.open_context_menu();
+            }
+            Action::ShowExport => self.popup = Popup::Export,
+            Action::ShowScan if self.is_docker_target() => {
+                self.set_status("Scanning isn't available in Docker target mode");
+            }
+            Action::ShowScan => self.popup = Popup::Scan,
+            Action::ShowEstablished if self.is_docker_target() => {
+                self.set_status("Connections aren't listed in Docker target mode");
+            }
+            Action::ShowEstablished => {
+                if self.selected_entry().is_some() {
+                    self.open_established();
+                    return vec![Command::LoadEstablished];
+                }
+            }
+            Action::ShowOutput => {
+                if self.selected_entry().is_some() {
+                    self.open_output();
+                    return vec![Command::LoadOutput];
+                }
+            }
+            Action::ShowSnapshots => {
+                self.snapshot_name = None;
+                self.popup = Popup::Snapshots;
+            }
+            Action::CopyAddress => return vec![Command::CopyAddress],
+            Action::OpenBrowser => return vec![Command::OpenBrowser],
+            Action::ShowLogs => return vec![Command::ContainerLogs],
+            Action::RestartContainer => return vec![Command::RestartContainer],
+            Action::RestartProcess if self.startable_service().is_some() => {
+                return vec![Command::StartService];
+            }
+            Action::RestartProcess => return vec![Command::RestartProcess],
+            Action::StartService => return vec![Command::StartService],
+            Action::CloseIdleTunnels => return vec![Command::CloseIdleTunnels],
+            Action::TmuxShell => return vec![Command::TmuxShell],
+            Action::TmuxForward => return vec![Command::TmuxForward],
+            Action::RunCustom(index) => return vec![Command::RunCustom(index)],
+            Action::Undo => return self.undo(),
+            Action::ClearSearch => {
+                // Esc dismisses a sticky error before clearing the search
+                if !self.dismiss_error() {
+                    self.search_query.clear();
+                    self.apply_filter();
+                }
+            }
+            Action::SelectRow(row) if row < self.rows.len() => self.selected = row,
+            Action::OpenRow(row) if row < self.rows.len() => {
+                self.selected = row;
+                return self.update(Action::Select);
+            }
+            Action::ContextMenuRow(row) if row < self.rows.len() => {
+                self.selected = row;
+                self.open_context_menu();
+            }
+            Action::Refresh
+            | Action::ToggleAutoRefresh
+            | Action::RecheckEntry
+            | Action::SubmitForward
+            | Action::LaunchPreset
+            | Action::AddPreset
+            | Action::EditPreset
+            | Action::DeletePreset
+            | Action::SubmitPreset
+            | Action::PresetHotkey(_)
+            | Action::LaunchGroup
+            | Action::SelectRow(_)
+            | Action::OpenRow(_)
+            | Action::ContextMenuRow(_)
+            | Action::Export(_)
+            | Action::SubmitScan
+            | Action::SaveSnapshot
+            | Action::SubmitSnapshot
+            | Action::RestoreSnapshot
+            | Action::DeleteSnapshot
+            | Action::ActivateConnection
+            | Action::AddConnection
+            | Action::DeleteConnection
+            | Action::SubmitConnection
+            | Action::EditConnection
+            | Action::MoveConnectionUp
+            | Action::MoveConnectionDown
+            | Action::TestConnection
+            | Action::SudoKill
+            | Action::Retry => {
+                // Mock mode has nothing to collect; the rest belong to the
+                // popups' key handlers, or name a row that's gone
+            }
+        }
+        Vec::new()
+    }
+
+    /// Apply `action`, from the key bindings of the open popup, to that popup.
+    #[allow(clippy::too_many_lines)]
+    fn update_popup(&mut self, action: Action) -> Vec<Command> {
+        let max = self.popup_max_scroll;
+        match (self.popup, action) {
+            (Popup::Forward, action) => return self.update_forward(action),
+            (Popup::Presets, action) => return self.update_presets(action),
+            (Popup::Snapshots, action) => return self.update_snapshots(action),
+            (Popup::Connections, action) => return self.update_connections(action),
+            (Popup::Auth, action) => {
+                self.popup = Popup::None;
+                match (action, self.auth_host.take()) {
+                    (Action::Authenticate, Some(host)) if self.mock_mode => {
+                        self.set_status(&format!("[mock] Would log in to {host}"));
+                    }
+                    (Action::Authenticate, Some(host)) => return vec![Command::Authenticate(host)],
+                    (_, Some(host)) => {
+                        self.auth_declined.insert(host);
+                    }
+                    _ => {}
+                }
+            }
+            (Popup::SudoKill, action) => {
+                self.popup = Popup::None;
+                let request = self.sudo_kill.take();
+                if let (Action::SudoKill, Some(request)) = (action, request) {
+                    return vec![Command::SudoKill(request)];
+                }
+            }
+            (Popup::Rerun, action) => {
+                self.popup = Popup::None;
+                if let (Action::Undo, Some(rerun)) = (action, self.rerun.take()) {
+                    return vec![Command::Undo(UndoAction::Rerun(rerun))];
+                }
+            }
+            (Popup::Error, Action::Down) => self.scroll_errors(1),
+            (Popup::Error, Action::Up) => self.scroll_errors(-1),
+            (Popup::Error, Action::First) => self.error_scroll = 0,
+            (Popup::Error, Action::Last) => self.scroll_errors(isize::MAX),
+            (Popup::Error, Action::Retry) => {
+                self.popup = Popup::None;
+                if self.mock_mode {
+                    self.set_status("[mock] Would retry the scan");
+                } else {
+                    self.loading = true;
+                    self.set_status("Retrying...");
+                    return vec![Command::Activate];
+                }
+            }
+            (Popup::Details, Action::Down) => self.scroll_details(1, max),
+            (Popup::Details, Action::Up) => self.scroll_details(-1, max),
+            (Popup::Details, Action::First) => self.details_scroll = 0,
+            (Popup::Details, Action::Last) => self.details_scroll = max,
+            (Popup::Established, Action::Down) => {
+                self.established_scroll = (self.established_scroll + 1).min(max);
+            }
+            (Popup::Established, Action::Up) => {
+                self.established_scroll = self.established_scroll.saturating_sub(1);
+            }
+            (Popup::Established, Action::First) => self.established_scroll = 0,
+            (Popup::Established, Action::Last) => self.established_scroll = max,
+            (Popup::Established, Action::Refresh) => return vec![Command::LoadEstablished],
+            // It opens scrolled to the end, past `max`
+            (Popup::Output, Action::Down) => {
+                self.output_scroll = (self.output_scroll.min(max) + 1).min(max);
+            }
+            (Popup::Output, Action::Up) => {
+                self.output_scroll = self.output_scroll.min(max).saturating_sub(1);
+            }
+            (Popup::Output, Action::First) => self.output_scroll = 0,
+            (Popup::Output, Action::Last) => self.output_scroll = usize::MAX,
+            (Popup::Output, Action::Refresh) => return vec![Command::LoadOutput],
+            (Popup::Help, Action::Down) => self.help_scroll = (self.help_scroll + 1).min(max),
+            (Popup::Help, Action::Up) => self.help_scroll = self.help_scroll.saturating_sub(1),
+            (Popup::Help, Action::First) => self.help_scroll = 0,
+            (Popup::Help, Action::Last) => self.help_scroll = max,
+            (Popup::Messages, Action::Down) => self.scroll_messages(1),
+            (Popup::Messages, Action::Up) => self.scroll_messages(-1),
+            (Popup::Messages, Action::First) => self.messages_scroll = 0,
+            (Popup::Messages, Action::Last) => self.scroll_messages(isize::MAX),
+            (Popup::Insights, Action::Down) => self.move_insight(1),
+            (Popup::Insights, Action::Up) => self.move_insight(-1),
+            (Popup::Insights, Action::First) => self.insight_selected = 0,
+            (Popup::Insights, Action::Last) => self.move_insight(isize::MAX),
+            (Popup::Insights, Action::Select) => self.jump_to_insight(),
+            (Popup::History, Action::Down) => self.scroll_history(1),
+            (Popup::History, Action::Up) => self.scroll_history(-1),
+            (Popup::History, Action::First) => self.history_scroll = 0,
+            (Popup::History, Action::Last) => self.scroll_history(isize::MAX),
+            (Popup::Export, Action::Export(format)) => {
+                self.popup = Popup::None;
+                return vec![Command::Export(format)];
+            }
+            (Popup::Scan, Action::SubmitScan) => return vec![Command::Scan],
+            (Popup::ContextMenu, Action::Down) => self.context_menu_next(),
+            (Popup::ContextMenu, Action::Up) => self.context_menu_previous(),
+            (Popup::ContextMenu, Action::First) => self.context_selected = 0,
+            (Popup::ContextMenu, Action::Last) => {
+                self.context_selected = self.context_menu.len().saturating_sub(1);
+            }
+            // A chosen item runs like its key
+            (Popup::ContextMenu, Action::Select) => {
+                self.popup = Popup::None;
+                if let Some(item) = self.context_menu.get(self.context_selected) {
+                    return self.update(item.action);
+                }
+            }
+            (Popup::StartService, Action::ClosePopup) => {
+                self.popup = Popup::None;
+                self.start_prompt = None;
+            }
+            // Confirming runs the choice like its key
+            (Popup::StartService, action) => {
+                self.popup = Popup::None;
+                return self.update(action);
+            }
+            (_, Action::ClosePopup) => self.popup = Popup::None,
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn update_forward(&mut self, action: Action) -> Vec<Command> {
+        match action {
+            Action::ClosePopup => {
+                self.popup = Popup::None;
+                self.reset_forward_input();
+            }
+            Action::SubmitForward => return vec![Command::CreateForward],
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn update_presets(&mut self, action: Action) -> Vec<Command> {
+        match (self.preset_popup_mode, action) {
+            (PresetPopupMode::Form | PresetPopupMode::ConfirmDelete, Action::ClosePopup) => {
+                self.preset_popup_mode = PresetPopupMode::List;
+                self.reset_preset_input();
+            }
+            (PresetPopupMode::Form, Action::SubmitPreset) => return vec![Command::SavePreset],
+            (PresetPopupMode::ConfirmDelete, Action::DeletePreset) => {
+                self.preset_popup_mode = PresetPopupMode::List;
+                return vec![Command::DeletePreset];
+            }
+            (PresetPopupMode::List, Action::ClosePopup) => self.popup = Popup::None,
+            (PresetPopupMode::List, Action::Up) => self.preset_previous(),
+            (PresetPopupMode::List, Action::Down) => self.preset_next(),
+            (PresetPopupMode::List, Action::LaunchPreset) => {
+                self.popup = Popup::None;
+                return vec![Command::LaunchPreset(self.preset_selected)];
+            }
+            (PresetPopupMode::List, Action::LaunchGroup) => {
+                match self.selected_preset().and_then(|p| p.group.clone()) {
+                    Some(group) => return vec![Command::LaunchGroup(group)],
+                    None => self.set_status("Selected preset has no group"),
+                }
+            }
+            (PresetPopupMode::List, Action::PresetHotkey(key)) => return self.launch_hotkey(key),
+            (PresetPopupMode::List, Action::AddPreset) => {
+                self.reset_preset_input();
+                self.preset_popup_mode = PresetPopupMode::Form;
+            }
+            (PresetPopupMode::List, Action::EditPreset) => {
+                if let Some(preset) = self.selected_preset() {
+                    self.preset_input = PresetInput::from_preset(preset);
+                    self.preset_editing = Some(self.preset_selected);
+                    self.preset_popup_mode = PresetPopupMode::Form;
+                }
+            }
+            (PresetPopupMode::List, Action::DeletePreset) if self.selected_preset().is_some() => {
+                self.preset_popup_mode = PresetPopupMode::ConfirmDelete;
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    /// Launch the preset bound to `key`, from the Presets popup or after the
+    /// preset leader.
+    fn launch_hotkey(&mut self, key: char) -> Vec<Command> {
+        let Some(index) = self.preset_for_key(key) else {
+            self.set_status(&format!("No preset on key '{key}'"));
+            return Vec::new();
+        };
+        self.preset_selected = index;
+        self.popup = Popup::None;
+        vec![Command::LaunchPreset(index)]
+    }
+
+    fn update_snapshots(&mut self, action: Action) -> Vec<Command> {
+        if self.snapshot_name.is_some() {
+            match action {
+                Action::ClosePopup => self.snapshot_name = None,
+                Action::SubmitSnapshot => return vec![Command::SaveSnapshot],
+                _ => {}
+            }
+            return Vec::new();
+        }
+        match action {
+            Action::ClosePopup => self.popup = Popup::None,
+            Action::Up => self.snapshot_previous(),
+            Action::Down => self.snapshot_next(),
+            Action::RestoreSnapshot => return vec![Command::RestoreSnapshot],
+            Action::SaveSnapshot => self.snapshot_name = Some(String::new()),
+            Action::DeleteSnapshot => return vec![Command::DeleteSnapshot],
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn update_connections(&mut self, action: Action) -> Vec<Command> {
+        if self.connection_popup_mode != ConnectionPopupMode::List {
+            match action {
+                Action::ClosePopup => {
+                    self.connection_popup_mode = ConnectionPopupMode::List;
+                    self.reset_connection_input();
+                }
+                Action::SubmitConnection => return vec![Command::SaveConnection],
+                _ => {}
+            }
+            return Vec::new();
+        }
+        let selected = self.connection_selected;
+        match action {
+            Action::ClosePopup => self.popup = Popup::None,
+            Action::Up => self.connection_previous(),
+            Action::Down => self.connection_next(),
+            Action::ActivateConnection => {
+                self.active_connection = selected;
+                self.activate_connection_ui();
+                self.popup = Popup::None;
+                if !self.mock_mode {
+                    return vec![Command::Activate];
+                }
+            }
+            Action::AddConnection => {
+                self.connection_popup_mode = ConnectionPopupMode::AddNew;
+                self.reset_connection_input();
+            }
+            Action::EditConnection if selected == 0 => {
+                self.set_status("Cannot edit Local connection");
+            }
+            Action::EditConnection if self.is_aggregate_connection(selected) => {
+                self.set_status("Cannot edit the All connections view");
+            }
+            Action::EditConnection => return vec![Command::EditConnection],
+            Action::MoveConnectionUp => return vec![Command::MoveConnection(true)],
+            Action::MoveConnectionDown => return vec![Command::MoveConnection(false)],
+            Action::TestConnection => {
+                let host = self
+                    .connections
+                    .get(selected)
+                    .and_then(|c| c.remote_host.clone());
+                match host {
+                    Some(host) if self.mock_mode => {
+                        self.set_status(&format!("[mock] Would test {host}"));
+                    }
+                    Some(host) => return vec![Command::TestConnection(host)],
+                    None => self.set_status("Connection has no SSH host to test"),
+                }
+            }
+            Action::DeleteConnection if selected == 0 => {
+                self.set_status("Cannot delete Local connection");
+            }
+            Action::DeleteConnection if self.is_aggregate_connection(selected) => {
+                self.set_status("Cannot delete the All connections view");
+            }
+            Action::DeleteConnection => return vec![Command::DeleteConnection],
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    /// Open the form for the selected connection; a group-generated one
+    /// can't be edited, which `generated` tells.
+    pub fn edit_connection(&mut self, generated: bool) {
+        if generated {
+            self.set_status(GENERATED_CONNECTION);
+        } else if let Some(conn) = self.connections.get(self.connection_selected) {
+            self.connection_input = ConnectionInput::from_connection(conn);
+            self.connection_popup_mode = ConnectionPopupMode::Edit;
+        }
+    }
+
+    /// Show the active connection as loading, with an empty table.
+    pub fn activate_connection_ui(&mut self) {
+        self.apply_connection();
+        self.entries.clear();
+        self.apply_filter();
+        self.selected = 0;
+        self.loading = true;
+        let name = self
+            .active_connection()
+            .map_or("Unknown", |c| c.name.as_str())
+            .to_string();
+        self.set_status(&format!("Switched to: {name}"));
+    }
+
+    /// Take back the newest recent kill or forward; a killed process is only
+    /// started again from the Rerun popup.
+    fn undo(&mut self) -> Vec<Command> {
+        match self.undo.pop(Instant::now()) {
+            None => {
+                self.set_status("Nothing to undo");
+                Vec::new()
+            }
+            Some(UndoAction::Rerun(rerun)) => {
+                self.rerun = Some(rerun);
+                self.popup = Popup::Rerun;
+                Vec::new()
+            }
+            Some(action) => vec![Command::Undo(action)],
+        }
+    }
+
+    /// The "All connections" view only shows; refuse (with a status) when it is active.
+    fn read_only(&mut self) -> bool {
+        if self.is_aggregate() {
+            self.set_status("All connections view is read-only; switch to a connection first");
+        }
+        self.is_aggregate()
+    }
+
+    /// Open the Forward popup, prefilled from the selected entry.
+    fn start_forward(&mut self) {
+        self.forward_input = match (self.selected_entry(), self.remote_host.as_deref()) {
+            (Some(entry), Some(host)) if self.is_docker_target() => {
+                let mut input = ForwardInput::for_remote_entry(entry, host);
+                if let Some((target, rport)) = resolve_docker_forward(
+                    entry.local_port,
+                    &self.docker_port_mappings,
+                    self.container_ip.as_deref(),
+                    self.docker_vm,
+                ) {
+                    input.remote_host = target;
+                    input.remote_port = rport.to_string();
+                }
+                input
+            }
+            (Some(entry), Some(host)) => ForwardInput::for_remote_entry(entry, host),
+            (Some(entry), None) => ForwardInput::from_entry(entry),
+            _ => ForwardInput::new(),
+        };
+        self.popup = Popup::Forward;
+    }
+
+    fn toggle_pin_action(&mut self) -> Vec<Command> {
+        let Some((port, pinned)) = self.toggle_pin() else {
+            return Vec::new();
+        };
+        if pinned {
+            self.set_status(&format!("Pinned :{port}"));
+        } else {
+            self.set_status(&format!("Unpinned :{port}"));
+        }
+        if self.mock_mode {
+            Vec::new()
+        } else {
+            vec![Command::SavePins]
+        }
+    }
+}
+
+/// Status for edits of a connection that a `[[connection_group]]` generates.
+pub const GENERATED_CONNECTION: &str =
+    "Generated from ~/.ssh/config by a [[connection_group]]; change it in connections.toml";
+
+/// Actions that change ports or forwards, which the read-only "All
+/// connections" view refuses.
+fn changes_entries(action: Action) -> bool {
+    matches!(
+        action,
+        Action::Kill
+            | Action::StartForward
+            | Action::QuickForward
+            | Action::SaveAsPreset
+            | Action::ShowLogs
+            | Action::RestartContainer
+            | Action::RestartProcess
+            | Action::StartService
+            | Action::CloseIdleTunnels
+            | Action::TmuxForward
+            | Action::ShowScan
+            | Action::Undo
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::MenuItem;
+    use crate::preset::Preset;
+    use crate::testing::row;
+    use quay_core::port::PortSource;
+    use quay_core::port::ssh::Started;
+
+    #[test]
+    fn test_update_changes_state_and_returns_commands() {
+        let mut app = App::new();
+        app.set_entries(vec![
+            row(PortSource::Local, 3000),
+            row(PortSource::Local, 5432),
+        ]);
+
+        assert!(app.update(Action::Down).is_empty());
+        assert_eq!(app.selected, 1);
+        assert!(app.update(Action::ShowHelp).is_empty());
+        assert_eq!(app.popup, Popup::Help);
+        // An open popup takes the actions until it closes
+        assert!(app.update(Action::Refresh).is_empty());
+        assert!(app.update(Action::ClosePopup).is_empty());
+
+        assert_eq!(app.update(Action::Refresh), vec![Command::Refresh]);
+        assert!(app.loading);
+        assert_eq!(app.update(Action::Select), vec![Command::LoadDetails]);
+        assert_eq!(app.popup, Popup::Details);
+        app.update(Action::ClosePopup);
+        assert_eq!(app.update(Action::ShowHistory), vec![Command::LoadHistory]);
+        assert_eq!(app.popup, Popup::History);
+        app.update(Action::ClosePopup);
+        assert_eq!(
+            app.update(Action::NextConnection),
+            vec![Command::SwitchConnection(1)]
+        );
+
+        // Mock mode collects nothing, unless its entries churn
+        app.mock_mode = true;
+        assert!(app.update(Action::Refresh).is_empty());
+        assert!(app.update(Action::RecheckEntry).is_empty());
+        app.mock_churn = true;
+        assert_eq!(app.update(Action::Refresh), vec![Command::Refresh]);
+        assert!(app.update(Action::RecheckEntry).is_empty());
+    }
+
+    #[test]
+    fn test_update_show_output() {
+        let mut app = App::new();
+        assert!(app.update(Action::ShowOutput).is_empty(), "no entry");
+
+        app.set_entries(vec![
+            row(PortSource::Local, 3000),
+            row(PortSource::Local, 5432),
+        ]);
+        assert_eq!(app.update(Action::ShowOutput), vec![Command::LoadOutput]);
+        assert_eq!(app.popup, Popup::Output);
+        assert!(app.output.is_none());
+
+        // Late output for another row is dropped; the current one opens at its end
+        let view = |port| crate::app::OutputView {
+            port,
+            source: Some("/tmp/app.log".to_string()),
+            lines: Ok(vec!["ready".to_string()]),
+        };
+        app.apply_output(view(5432));
+        assert!(app.output.is_none());
+        app.apply_output(view(3000));
+        assert_eq!(app.output, Some(view(3000)));
+        assert_eq!(app.output_scroll, usize::MAX);
+    }
+
+    #[test]
+    fn test_update_show_established() {
+        let mut app = App::new();
+        assert!(app.update(Action::ShowEstablished).is_empty(), "no entry");
+        assert_eq!(app.popup, Popup::None);
+
+        app.set_entries(vec![row(PortSource::Local, 5432)]);
+        assert_eq!(
+            app.update(Action::ShowEstablished),
+            vec![Command::LoadEstablished]
+        );
+        assert_eq!(app.popup, Popup::Established);
+        assert!(app.established.is_none());
+
+        app.popup = Popup::None;
+        app.docker_target = Some("api".to_string());
+        assert!(app.update(Action::ShowEstablished).is_empty());
+        assert_eq!(app.popup, Popup::None);
+    }
+
+    #[test]
+    fn test_update_undo() {
+        let mut app = App::new();
+        assert!(app.update(Action::Undo).is_empty());
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("Nothing to undo")
+        );
+
+        let stop = UndoAction::StopForward {
+            port: 8080,
+            started: Started::Process(4242),
+        };
+        let rerun = crate::undo::Rerun {
+            port: 3000,
+            process: "node".to_string(),
+            command: vec!["node".to_string(), "server.js".to_string()],
+            cwd: None,
+        };
+        app.undo.push(stop.clone(), Instant::now());
+        app.undo
+            .push(UndoAction::Rerun(rerun.clone()), Instant::now());
+        // A rerun is confirmed in its popup first
+        assert!(app.update(Action::Undo).is_empty());
+        assert_eq!(app.popup, Popup::Rerun);
+        assert_eq!(app.rerun, Some(rerun.clone()));
+        assert_eq!(
+            app.update(Action::Undo),
+            vec![Command::Undo(UndoAction::Rerun(rerun))]
+        );
+        assert_eq!(app.popup, Popup::None);
+        assert_eq!(app.update(Action::Undo), vec![Command::Undo(stop)]);
+    }
+
+    #[test]
+    fn test_enter_offers_start_command_of_closed_port() {
+        let mut app = App::new();
+        app.registry = crate::registry::PortRegistry::parse(
+            r#"3000 = { label = "storefront", start = "npm run dev" }"#,
+        )
+        .unwrap();
+        app.set_entries(Vec::new());
+        assert_eq!(app.entries.len(), 1);
+
+        assert!(app.update(Action::Select).is_empty());
+        assert_eq!(app.popup, Popup::StartService);
+        let prompt = app.start_prompt.clone().unwrap();
+        assert_eq!(prompt.name, "storefront");
+        assert_eq!(prompt.command.as_deref(), Some("npm run dev"));
+        assert_eq!(
+            app.update(Action::StartService),
+            vec![Command::StartService]
+        );
+        assert_eq!(app.update(Action::ShowDetails), vec![Command::LoadDetails]);
+
+        // While quay waits for the port, Enter shows details again
+        app.popup = Popup::None;
+        let pending = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        app.started.insert(
+            3000,
+            crate::app::StartedService {
+                name: "storefront".to_string(),
+                pid: 4242,
+                pending: pending.clone(),
+            },
+        );
+        assert!(app.startable_service().is_none());
+        assert_eq!(app.update(Action::Select), vec![Command::LoadDetails]);
+        pending.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(app.starting(3000).is_none());
+        assert!(app.startable_service().is_some());
+    }
+
+    #[test]
+    fn test_update_routes_popup_actions() {
+        let mut app = App::new();
+        app.presets = vec![Preset {
+            name: "db".to_string(),
+            key: Some("d".to_string()),
+            local_port: 5432,
+            remote_host: "localhost".to_string(),
+            remote_port: 5432,
+            ssh_host: "prod".to_string(),
+            group: None,
+            ssh_options: Vec::new(),
+        }];
+
+        // Deleting a preset asks first
+        app.update(Action::ShowPresets);
+        assert!(app.update(Action::DeletePreset).is_empty());
+        assert_eq!(app.preset_popup_mode, PresetPopupMode::ConfirmDelete);
+        assert!(app.update(Action::ClosePopup).is_empty());
+        assert_eq!(app.preset_popup_mode, PresetPopupMode::List);
+        app.update(Action::DeletePreset);
+        assert_eq!(
+            app.update(Action::DeletePreset),
+            vec![Command::DeletePreset]
+        );
+        assert_eq!(app.popup, Popup::Presets);
+        app.update(Action::ClosePopup);
+
+        // The leader launches the preset on the next key
+        app.update(Action::PresetLeader);
+        assert_eq!(
+            app.update(Action::PresetHotkey('d')),
+            vec![Command::LaunchPreset(0)]
+        );
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.popup = Popup::Error;
+        assert_eq!(app.update(Action::Retry), vec![Command::Activate]);
+        assert_eq!(app.popup, Popup::None);
+
+        app.popup = Popup::ContextMenu;
+        app.context_menu = vec![MenuItem {
+            label: "Help".to_string(),
+            action: Action::ShowHelp,
+            command: None,
+        }];
+        assert!(app.update(Action::Select).is_empty());
+        assert_eq!(app.popup, Popup::Help);
+
+        app.popup_max_scroll = 1;
+        app.update(Action::Down);
+        app.update(Action::Down);
+        assert_eq!(app.help_scroll, 1);
+    }
+
+    #[test]
+    fn test_update_refuses_changes_in_aggregate_view() {
+        let mut app = App::new();
+        app.aggregate_connection = Some(0);
+        assert!(app.update(Action::Kill).is_empty());
+        assert!(app.status_message.is_some());
+        assert_ne!(app.popup, Popup::Forward);
+        assert!(app.update(Action::StartForward).is_empty());
+        assert_ne!(app.popup, Popup::Forward);
+    }
+}
@@ -0,0 +1,152 @@
+//! `quay wait`: block until ports are open (or closed) in the selected context.
+//!
+//! Meant for scripts that start a service or tunnel and need to know when it
+//! is reachable, e.g. `quay up staging && quay wait -p 5432 && psql ...`.
+
+use crate::port;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+#[derive(clap::Args)]
+pub struct WaitArgs {
+    /// Port to wait for (repeat or comma-separate for several)
+    #[arg(
+        short,
+        long = "port",
+        value_name = "PORT",
+        value_delimiter = ',',
+        required = true
+    )]
+    pub ports: Vec<u16>,
+    /// Wait until the ports are open (the default)
+    #[arg(long, conflicts_with = "closed")]
+    pub open: bool,
+    /// Wait until the ports are closed
+    #[arg(long)]
+    pub closed: bool,
+    /// Finish as soon as any port is in the desired state, instead of all
+    #[arg(long)]
+    pub any: bool,
+    /// Give up after this many seconds (0 waits forever)
+    #[arg(short, long, value_name = "SECS", default_value_t = 60)]
+    pub timeout: u64,
+    /// Milliseconds between checks
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub interval: u64,
+}
+
+/// Ports of `ports` not yet in the desired state, given the set that is open.
+fn pending(ports: &[u16], open: &HashSet<u16>, want_open: bool) -> Vec<u16> {
+    ports
+        .iter()
+        .copied()
+        .filter(|p| open.contains(p) != want_open)
+        .collect()
+}
+
+/// Whether waiting is over: all ports (or, with `any`, at least one) are done.
+fn satisfied(ports: &[u16], pending: &[u16], any: bool) -> bool {
+    if any {
+        pending.len() < ports.len()
+    } else {
+        pending.is_empty()
+    }
+}
+
+fn port_list(ports: &[u16]) -> String {
+    let list: Vec<String> = ports.iter().map(|p| format!(":{p}")).collect();
+    list.join(", ")
+}
+
+pub async fn run(
+    args: &WaitArgs,
+    remote_host: Option<&str>,
+    docker_target: Option<&str>,
+) -> Result<()> {
+    let want_open = !args.closed;
+    let state = if want_open { "open" } else { "closed" };
+    let mut ports = args.ports.clone();
+    ports.sort_unstable();
+    ports.dedup();
+
+    let deadline = (args.timeout > 0).then(|| Instant::now() + Duration::from_secs(args.timeout));
+    let interval = Duration::from_millis(args.interval.max(50));
+    let quantifier = if args.any && ports.len() > 1 {
+        "any of "
+    } else {
+        ""
+    };
+    eprintln!(
+        "Waiting for {quantifier}{} to be {state}...",
+        port_list(&ports)
+    );
+
+    let mut still_pending = ports.clone();
+    loop {
+        // Remote and container scans can fail transiently (e.g. while a host reboots)
+        let error = match port::open_ports(&ports, remote_host, docker_target).await {
+            Ok(open) => {
+                let pending = pending(&ports, &open, want_open);
+                if satisfied(&ports, &pending, args.any) {
+                    let done: Vec<u16> = ports
+                        .iter()
+                        .copied()
+                        .filter(|p| !pending.contains(p))
+                        .collect();
+                    println!("{} {state}", port_list(&done));
+                    return Ok(());
+                }
+                tracing::debug!(?pending, "waiting for ports");
+                still_pending = pending;
+                None
+            }
+            Err(e) => {
+                tracing::debug!(error = %format!("{e:#}"), "wait check failed");
+                Some(e)
+            }
+        };
+
+        if deadline.is_some_and(|d| Instant::now() + interval > d) {
+            let message = format!(
+                "Timed out after {}s waiting for {quantifier}{} to be {state} (not {state}: {})",
+                args.timeout,
+                port_list(&ports),
+                port_list(&still_pending)
+            );
+            return Err(match error {
+                Some(e) => e.context(message),
+                None => anyhow::anyhow!(message),
+            });
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_and_satisfied() {
+        let ports = [3000, 5432];
+        let open: HashSet<u16> = [5432].into_iter().collect();
+
+        let waiting_open = pending(&ports, &open, true);
+        assert_eq!(waiting_open, vec![3000]);
+        assert!(!satisfied(&ports, &waiting_open, false));
+        assert!(satisfied(&ports, &waiting_open, true));
+
+        let waiting_closed = pending(&ports, &open, false);
+        assert_eq!(waiting_closed, vec![5432]);
+        assert!(satisfied(&ports, &waiting_closed, true));
+
+        let none_open = pending(&ports, &HashSet::new(), true);
+        assert!(!satisfied(&ports, &none_open, true));
+        assert!(satisfied(
+            &ports,
+            &pending(&ports, &HashSet::new(), false),
+            false
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! Background collection worker.
+//!
+//! One task runs every port collection of the TUI. Refreshes (manual, auto,
+//! after a kill or a new forward, after switching connections) are sent to it
+//! as [`CollectRequest`]s and come back as [`CollectionResult`] messages.
+//! While a collection runs, further requests for the same connection are
+//! coalesced into one follow-up scan (auto-refreshes are dropped, the running
+//! scan is fresh enough); a request for another connection cancels it.
+
+use crate::app::App;
+use crate::dev::mock::MockGenerator;
+use crate::port::{self, CollectTarget, Collection};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Why a collection was asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The auto-refresh timer.
+    Auto,
+    /// The user, or a change quay made (kill, forward, connection switch).
+    Manual,
+}
+
+/// What to collect: a snapshot of the active view when the request was made.
+#[derive(Debug, Clone)]
+pub struct CollectRequest {
+    pub active_connection: usize,
+    pub trigger: Trigger,
+    remote_host: Option<String>,
+    docker_target: Option<String>,
+    known_forwards: HashMap<u16, u16>,
+    /// Every connection, for the "All connections" view.
+    aggregate: Option<Vec<CollectTarget>>,
+}
+
+impl CollectRequest {
+    /// Collect the view `app` shows.
+    pub fn of(app: &App, trigger: Trigger) -> Self {
+        let aggregate = app.is_aggregate().then(|| {
+            app.aggregate_sources()
+                .map(|(index, conn)| CollectTarget {
+                    name: conn.name.clone(),
+                    remote_host: conn.remote_host.clone(),
+                    docker_target: conn.docker_target.clone(),
+                    docker_context: conn.docker_context.clone(),
+                    known_forwards: app.ssh_forwards.get(&index).cloned().unwrap_or_default(),
+                })
+                .collect()
+        });
+        Self {
+            active_connection: app.active_connection,
+            trigger,
+            remote_host: app.remote_host.clone(),
+            docker_target: app.docker_target.clone(),
+            known_forwards: app.known_forwards().clone(),
+            aggregate,
+        }
+    }
+}
+
+/// A finished collection.
+pub struct CollectionResult {
+    pub active_connection: usize,
+    pub entries: anyhow::Result<Collection>,
+    /// Connections the aggregate view could not collect from.
+    pub failed: Vec<(String, anyhow::Error)>,
+    /// How long the collection took.
+    pub elapsed: Duration,
+}
+
+/// Handle to the worker task; cheap to clone into other tasks.
+#[derive(Clone)]
+pub struct Worker {
+    requests: mpsc::UnboundedSender<CollectRequest>,
+}
+
+impl Worker {
+    /// Start the worker; results arrive on the returned receiver.
+    pub fn spawn() -> (Self, mpsc::Receiver<CollectionResult>) {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let (results, results_rx) = mpsc::channel(4);
+        tokio::spawn(run(rx, results, collect));
+        (Self { requests }, results_rx)
+    }
+
+    /// Start a worker that answers every request with the next churn of `generator`.
+    pub fn spawn_mock(generator: MockGenerator) -> (Self, mpsc::Receiver<CollectionResult>) {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let (results, results_rx) = mpsc::channel(4);
+        let generator = Arc::new(Mutex::new(generator));
+        tokio::spawn(run(rx, results, move |request: CollectRequest| {
+            let entries = match generator.lock() {
+                Ok(mut generator) => {
+                    generator.churn();
+                    Ok(generator.collection())
+                }
+                Err(_) => Err(anyhow::anyhow!("mock generator panicked")),
+            };
+            async move {
+                CollectionResult {
+                    active_connection: request.active_connection,
+                    entries,
+                    failed: Vec::new(),
+                    elapsed: Duration::ZERO,
+                }
+            }
+        }));
+        (Self { requests }, results_rx)
+    }
+
+    pub fn request(&self, request: CollectRequest) {
+        // Only fails once the UI loop is gone
+        let _ = self.requests.send(request);
+    }
+}
+
+/// Collect the active connection, or every connection in the aggregate view.
+async fn collect(request: CollectRequest) -> CollectionResult {
+    let (entries, failed) = match request.aggregate {
+        Some(targets) => {
+            let (collection, failed) = port::collect_connections(targets).await;
+            (Ok(collection), failed)
+        }
+        None => (
+            port::collect_all(
+                request.remote_host.as_deref(),
+                request.docker_target.as_deref(),
+                &request.known_forwards,
+            )
+            .await,
+            Vec::new(),
+        ),
+    };
+    CollectionResult {
+        active_connection: request.active_connection,
+        entries,
+        failed,
+        elapsed: Duration::ZERO,
+    }
+}
+
+/// Serve `requests` one collection at a time until the UI hangs up.
+async fn run<F, Fut>(
+    mut requests: mpsc::UnboundedReceiver<CollectRequest>,
+    results: mpsc::Sender<CollectionResult>,
+    collect: F,
+) where
+    F: Fn(CollectRequest) -> Fut,
+    Fut: Future<Output = CollectionResult>,
+{
+    let mut next: Option<CollectRequest> = None;
+    loop {
+        let request = match next.take() {
+            Some(request) => request,
+            None => match requests.recv().await {
+                Some(request) => request,
+                None => return,
+            },
+        };
+        let connection = request.active_connection;
+        let started = Instant::now();
+        let scan = collect(request);
+        tokio::pin!(scan);
+        let result = loop {
+            tokio::select! {
+                result = &mut scan => break Some(CollectionResult {
+                    elapsed: started.elapsed(),
+                    ..result
+                }),
+                newer = requests.recv() => match newer {
+                    None => return,
+                    Some(newer) if newer.active_connection != connection => {
+                        tracing::debug!(connection, "collection cancelled by a connection switch");
+                        next = Some(newer);
+                        break None;
+                    }
+                    Some(newer) if newer.trigger == Trigger::Auto => {}
+                    Some(newer) => next = Some(newer),
+                },
+            }
+        };
+        if let Some(result) = result {
+            if results.send(result).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn request(connection: usize, trigger: Trigger) -> CollectRequest {
+        CollectRequest {
+            active_connection: connection,
+            trigger,
+            remote_host: None,
+            docker_target: None,
+            known_forwards: HashMap::new(),
+            aggregate: None,
+        }
+    }
+
+    #[test]
+    fn test_requests_are_coalesced() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let started = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&started);
+            let (tx, rx) = mpsc::unbounded_channel();
+            let (results, mut results_rx) = mpsc::channel(8);
+            let worker = tokio::spawn(run(rx, results, move |request: CollectRequest| {
+                log.lock().unwrap().push(request.active_connection);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    CollectionResult {
+                        active_connection: request.active_connection,
+                        entries: Ok(Collection::default()),
+                        failed: Vec::new(),
+                        elapsed: Duration::ZERO,
+                    }
+                }
+            }));
+
+            tx.send(request(0, Trigger::Manual)).unwrap();
+            tokio::task::yield_now().await;
+            // Arrive mid-scan: one follow-up for the manual requests, none for auto
+            tx.send(request(0, Trigger::Manual)).unwrap();
+            tx.send(request(0, Trigger::Auto)).unwrap();
+            tx.send(request(0, Trigger::Manual)).unwrap();
+            let first = results_rx.recv().await.unwrap();
+            assert_eq!(first.active_connection, 0);
+            assert!(first.elapsed >= Duration::from_millis(20));
+            assert_eq!(results_rx.recv().await.unwrap().active_connection, 0);
+
+            // A connection switch cancels the scan in flight
+            tx.send(request(0, Trigger::Manual)).unwrap();
+            tokio::task::yield_now().await;
+            tx.send(request(1, Trigger::Manual)).unwrap();
+            assert_eq!(results_rx.recv().await.unwrap().active_connection, 1);
+
+            drop(tx);
+            worker.await.unwrap();
+            assert_eq!(*started.lock().unwrap(), vec![0, 0, 0, 1]);
+            assert!(results_rx.try_recv().is_err());
+        });
+    }
+}